@@ -0,0 +1,50 @@
+use serde::Serialize;
+
+pub mod compare;
+pub mod entropy;
+pub mod jwt;
+pub mod key;
+pub mod nonce;
+pub mod secret;
+pub mod weak;
+
+#[derive(
+    Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Info,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub severity: Severity,
+    /// stable kebab-case identifier of the check
+    pub code: &'static str,
+    pub detail: String,
+}
+
+impl Finding {
+    pub fn new(
+        severity: Severity,
+        code: &'static str,
+        detail: impl Into<String>,
+    ) -> Self {
+        Finding {
+            severity,
+            code,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Most severe findings first, the order of the checks otherwise.
+pub(crate) fn sorted(mut findings: Vec<Finding>) -> Vec<Finding> {
+    findings.sort_by_key(|finding| finding.severity);
+    findings
+}
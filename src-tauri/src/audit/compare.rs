@@ -0,0 +1,588 @@
+//! Whether two keys are the same whatever their format, compared on their
+//! components.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Context;
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use const_oid::ObjectIdentifier;
+use elliptic_curve::{
+    sec1::{FromEncodedPoint, ModulusSize, ToEncodedPoint},
+    AffinePoint, CurveArithmetic, FieldBytesSize, PublicKey, SecretKey,
+};
+use rsa::{
+    traits::{PrivateKeyParts, PublicKeyParts},
+    BigUint, RsaPrivateKey, RsaPublicKey,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::key::{
+    self, AuditedKey, SECP256K1, SECP256R1, SECP384R1, SECP521R1, SM2,
+};
+use crate::{
+    crypto::digest::digest_inner,
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+    ssh,
+};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyComparison {
+    /// what each key was read as, `pem`, `der`, `jwk` or `ssh`
+    pub formats: [&'static str; 2],
+    pub kinds: [&'static str; 2],
+    pub same_public: bool,
+    /// both are private keys of one pair, `None` unless both are private
+    pub same_pair: Option<bool>,
+    /// the components that differ, private ones prefixed with `private`
+    pub differences: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    /// `None` when the input has no such field
+    pub a: Option<String>,
+    pub b: Option<String>,
+    pub equal: bool,
+}
+
+impl FieldDiff {
+    pub(crate) fn new(
+        field: impl Into<String>,
+        a: Option<String>,
+        b: Option<String>,
+    ) -> Self {
+        FieldDiff {
+            field: field.into(),
+            equal: a == b,
+            a,
+            b,
+        }
+    }
+}
+
+type Components = BTreeMap<String, Vec<u8>>;
+
+struct NormalizedKey {
+    kind: &'static str,
+    /// the oid of the named curve of ec keys
+    curve: Option<String>,
+    public: Components,
+    /// empty for a public key
+    private: Components,
+}
+
+#[tauri::command]
+pub fn compare_keys(
+    a: String,
+    b: String,
+    encoding: TextEncoding,
+) -> Result<KeyComparison> {
+    let (a_format, a) = read(&a, encoding)?;
+    let (b_format, b) = read(&b, encoding)?;
+    let (a, b) = (normalize(a)?, normalize(b)?);
+
+    let mut differences = Vec::new();
+    if a.kind != b.kind {
+        differences.push("kind".to_string());
+    } else if a.curve != b.curve {
+        differences.push("curve".to_string());
+    } else {
+        differences.extend(differing(&a.public, &b.public));
+    }
+    let same_public = differences.is_empty();
+    let same_pair = if a.private.is_empty() || b.private.is_empty() {
+        None
+    } else {
+        if a.kind == b.kind && a.curve == b.curve {
+            differences.extend(
+                differing(&a.private, &b.private)
+                    .map(|name| format!("private {}", name)),
+            );
+        }
+        Some(differences.is_empty())
+    };
+    info!(
+        "compare keys, formats: {} {}, kinds: {} {}, same public: {}",
+        a_format, b_format, a.kind, b.kind, same_public
+    );
+    Ok(KeyComparison {
+        formats: [a_format, b_format],
+        kinds: [a.kind, b.kind],
+        same_public,
+        same_pair,
+        differences,
+    })
+}
+
+/// Private components show as the first bytes of their sha-256 only.
+#[tauri::command]
+pub fn diff_keys(
+    a: String,
+    b: String,
+    encoding: TextEncoding,
+) -> Result<Vec<FieldDiff>> {
+    let (a_format, a) = read(&a, encoding)?;
+    let (b_format, b) = read(&b, encoding)?;
+    let (a, b) = (normalize(a)?, normalize(b)?);
+    let text = |value: &str| Some(value.to_string());
+    let mut fields = vec![
+        FieldDiff::new("format", text(a_format), text(b_format)),
+        FieldDiff::new("kind", text(a.kind), text(b.kind)),
+        FieldDiff::new("curve", a.curve.clone(), b.curve.clone()),
+        FieldDiff::new(
+            "private",
+            text(&(!a.private.is_empty()).to_string()),
+            text(&(!b.private.is_empty()).to_string()),
+        ),
+    ];
+    rows(&mut fields, "", &a.public, &b.public, |value| {
+        TextEncoding::Hex.encode(value)
+    })?;
+    rows(&mut fields, "private ", &a.private, &b.private, |value| {
+        TextEncoding::Hex
+            .encode(&digest_inner(Digest::Sha256, value)[.. 8])
+            .map(|hex| format!("sha256:{}", hex))
+    })?;
+    info!(
+        "diff keys, fields: {}, differing: {}",
+        fields.len(),
+        fields.iter().filter(|field| !field.equal).count()
+    );
+    Ok(fields)
+}
+
+fn rows<F>(
+    fields: &mut Vec<FieldDiff>,
+    prefix: &str,
+    a: &Components,
+    b: &Components,
+    show: F,
+) -> Result<()>
+where
+    F: Fn(&[u8]) -> Result<String>,
+{
+    let show = |value: Option<&Vec<u8>>| value.map(|v| show(v)).transpose();
+    for name in a.keys().chain(b.keys()).collect::<BTreeSet<_>>() {
+        fields.push(FieldDiff::new(
+            format!("{}{}", prefix, name),
+            show(a.get(name))?,
+            show(b.get(name))?,
+        ));
+    }
+    Ok(())
+}
+
+fn differing<'a>(
+    a: &'a Components,
+    b: &'a Components,
+) -> impl Iterator<Item = String> + 'a {
+    a.keys()
+        .chain(b.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| a.get(*name) != b.get(*name))
+        .cloned()
+}
+
+pub(crate) fn read(
+    key: &str,
+    encoding: TextEncoding,
+) -> Result<(&'static str, AuditedKey)> {
+    let trimmed = key.trim();
+    if trimmed.starts_with('{') {
+        return Ok(("jwk", from_jwk(trimmed)?));
+    }
+    if trimmed
+        .split_whitespace()
+        .next()
+        .is_some_and(ssh::is_key_type)
+    {
+        return Ok(("ssh", from_ssh(trimmed)?));
+    }
+    let format = if trimmed.starts_with("-----BEGIN ") {
+        "pem"
+    } else {
+        "der"
+    };
+    Ok((format, key::parse(key, encoding)?))
+}
+
+fn from_jwk(input: &str) -> Result<AuditedKey> {
+    let jwk: Value = serde_json::from_str(input).context("invalid jwk")?;
+    let field = |name: &str| -> Result<Option<Vec<u8>>> {
+        Ok(jwk[name]
+            .as_str()
+            .map(|value| {
+                Base64UrlUnpadded::decode_vec(value)
+                    .with_context(|| format!("invalid jwk {}", name))
+            })
+            .transpose()?)
+    };
+    let required = |name: &str| -> Result<Vec<u8>> {
+        field(name)?
+            .ok_or_else(|| Error::Unsupported(format!("jwk without {}", name)))
+    };
+    let crv = jwk["crv"].as_str().unwrap_or_default();
+    match jwk["kty"].as_str() {
+        Some("RSA") => {
+            let n = BigUint::from_bytes_be(&required("n")?);
+            let e = BigUint::from_bytes_be(&required("e")?);
+            let Some(d) = field("d")? else {
+                return Ok(AuditedKey::RsaPublic(RsaPublicKey::new_unchecked(
+                    n, e,
+                )));
+            };
+            // the primes are recovered from d when the jwk leaves them out
+            let primes = [field("p")?, field("q")?]
+                .into_iter()
+                .flatten()
+                .map(|prime| BigUint::from_bytes_be(&prime))
+                .collect();
+            Ok(AuditedKey::RsaPrivate(
+                RsaPrivateKey::from_components(
+                    n,
+                    e,
+                    BigUint::from_bytes_be(&d),
+                    primes,
+                )
+                .context("invalid jwk")?,
+            ))
+        }
+        Some("EC") => {
+            let curve = match crv {
+                "P-256" => SECP256R1,
+                "P-384" => SECP384R1,
+                "P-521" => SECP521R1,
+                "secp256k1" => SECP256K1,
+                "SM2" => SM2,
+                crv => return Err(Error::Unsupported(format!("jwk {}", crv))),
+            };
+            Ok(AuditedKey::Ec {
+                curve: Some(curve),
+                secret: field("d")?,
+                point: Some(
+                    [&[0x04][..], &required("x")?, &required("y")?].concat(),
+                ),
+            })
+        }
+        Some("oct") => Ok(AuditedKey::Symmetric(required("k")?)),
+        Some("OKP") if matches!(crv, "Ed25519" | "X25519") => {
+            Ok(AuditedKey::Edwards {
+                x25519: crv == "X25519",
+                secret: field("d")?,
+                point: Some(required("x")?),
+            })
+        }
+        kty => Err(Error::Unsupported(format!(
+            "jwk {} {}",
+            kty.unwrap_or_default(),
+            crv
+        ))),
+    }
+}
+
+fn from_ssh(line: &str) -> Result<AuditedKey> {
+    let blob = line.split_whitespace().nth(1).ok_or_else(|| {
+        Error::Unsupported("ssh key without its blob".to_string())
+    })?;
+    let blob = Base64::decode_vec(blob).context("base64 decode failed")?;
+    let mut input = blob.as_slice();
+    match ssh::take_string(&mut input)? {
+        b"ssh-rsa" => {
+            let e = BigUint::from_bytes_be(ssh::take_string(&mut input)?);
+            let n = BigUint::from_bytes_be(ssh::take_string(&mut input)?);
+            Ok(AuditedKey::RsaPublic(RsaPublicKey::new_unchecked(n, e)))
+        }
+        b"ssh-ed25519" => Ok(AuditedKey::Edwards {
+            x25519: false,
+            secret: None,
+            point: Some(ssh::take_string(&mut input)?.to_vec()),
+        }),
+        key_type if key_type.starts_with(b"ecdsa-sha2-") => {
+            let curve = match ssh::take_string(&mut input)? {
+                b"nistp256" => SECP256R1,
+                b"nistp384" => SECP384R1,
+                b"nistp521" => SECP521R1,
+                curve => {
+                    return Err(Error::Unsupported(format!(
+                        "ssh curve {}",
+                        String::from_utf8_lossy(curve)
+                    )))
+                }
+            };
+            Ok(AuditedKey::Ec {
+                curve: Some(curve),
+                secret: None,
+                point: Some(ssh::take_string(&mut input)?.to_vec()),
+            })
+        }
+        key_type => Err(Error::Unsupported(format!(
+            "ssh key type {}",
+            String::from_utf8_lossy(key_type)
+        ))),
+    }
+}
+
+/// `None` for raw key bytes and for curves jwk has no name for.
+pub(crate) fn public_jwk(key: AuditedKey) -> Result<Option<Value>> {
+    let crv = match &key {
+        AuditedKey::Symmetric(_) => return Ok(None),
+        AuditedKey::Ec { curve, .. } => match curve {
+            Some(SECP256R1) => Some("P-256"),
+            Some(SECP384R1) => Some("P-384"),
+            Some(SECP521R1) => Some("P-521"),
+            Some(SECP256K1) => Some("secp256k1"),
+            Some(SM2) => Some("SM2"),
+            _ => return Ok(None),
+        },
+        _ => None,
+    };
+    let key = normalize(key)?;
+    let encode =
+        |name: &str| Base64UrlUnpadded::encode_string(&key.public[name]);
+    Ok(Some(match key.kind {
+        "rsa" => json!({
+            "kty": "RSA",
+            "n": encode("modulus"),
+            "e": encode("exponent"),
+        }),
+        "ec" => {
+            let point = &key.public["point"][1 ..];
+            let (x, y) = point.split_at(point.len() / 2);
+            json!({
+                "kty": "EC",
+                "crv": crv,
+                "x": Base64UrlUnpadded::encode_string(x),
+                "y": Base64UrlUnpadded::encode_string(y),
+            })
+        }
+        kind => json!({
+            "kty": "OKP",
+            "crv": if kind == "x25519" { "X25519" } else { "Ed25519" },
+            "x": encode("point"),
+        }),
+    }))
+}
+
+fn normalize(key: AuditedKey) -> Result<NormalizedKey> {
+    let integer = |value: &BigUint| value.to_bytes_be();
+    let mut curve = None;
+    let (kind, public, private) = match key {
+        AuditedKey::RsaPrivate(key) => {
+            let mut primes = key.primes().to_vec();
+            primes.sort();
+            let private = primes
+                .iter()
+                .enumerate()
+                .map(|(index, prime)| {
+                    (format!("prime {}", index + 1), integer(prime))
+                })
+                .collect();
+            ("rsa", rsa_components(key.n(), key.e()), private)
+        }
+        AuditedKey::RsaPublic(key) => {
+            ("rsa", rsa_components(key.n(), key.e()), Components::new())
+        }
+        AuditedKey::Ec {
+            curve: oid,
+            secret,
+            point,
+        } => {
+            let point = ec_point(oid, secret.as_deref(), point.as_deref())?;
+            curve =
+                Some(oid.map_or("explicit".to_string(), |oid| oid.to_string()));
+            let public = Components::from([("point".to_string(), point)]);
+            let private = secret
+                .map(|secret| {
+                    let leading =
+                        secret.iter().take_while(|byte| **byte == 0).count();
+                    ("scalar".to_string(), secret[leading ..].to_vec())
+                })
+                .into_iter()
+                .collect();
+            ("ec", public, private)
+        }
+        AuditedKey::Edwards {
+            x25519,
+            secret,
+            point,
+        } => {
+            let point = match (point, &secret) {
+                (Some(point), _) => point,
+                (None, Some(secret)) => edwards_point(x25519, secret)?,
+                (None, None) => {
+                    return Err(Error::Unsupported(
+                        "edwards key without a point".to_string(),
+                    ))
+                }
+            };
+            let public = Components::from([("point".to_string(), point)]);
+            let private = secret
+                .map(|secret| ("seed".to_string(), secret))
+                .into_iter()
+                .collect();
+            (if x25519 { "x25519" } else { "ed25519" }, public, private)
+        }
+        AuditedKey::Symmetric(_) => {
+            return Err(Error::Unsupported("raw key bytes".to_string()))
+        }
+    };
+    Ok(NormalizedKey {
+        kind,
+        curve,
+        public,
+        private,
+    })
+}
+
+fn rsa_components(n: &BigUint, e: &BigUint) -> Components {
+    Components::from([
+        ("modulus".to_string(), n.to_bytes_be()),
+        ("exponent".to_string(), e.to_bytes_be()),
+    ])
+}
+
+pub(crate) fn ec_point(
+    curve: Option<ObjectIdentifier>,
+    secret: Option<&[u8]>,
+    point: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    match curve {
+        Some(SECP256R1) => uncompressed::<p256::NistP256>(secret, point),
+        Some(SECP384R1) => uncompressed::<p384::NistP384>(secret, point),
+        Some(SECP521R1) => uncompressed::<p521::NistP521>(secret, point),
+        Some(SECP256K1) => uncompressed::<k256::Secp256k1>(secret, point),
+        Some(SM2) => uncompressed::<sm2::Sm2>(secret, point),
+        // compared as written
+        _ => point.map(<[u8]>::to_vec).ok_or_else(|| {
+            Error::Unsupported(
+                "ec private key without a point on an unknown curve"
+                    .to_string(),
+            )
+        }),
+    }
+}
+
+fn uncompressed<C>(
+    secret: Option<&[u8]>,
+    point: Option<&[u8]>,
+) -> Result<Vec<u8>>
+where
+    C: CurveArithmetic,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let public = match (point, secret) {
+        (Some(point), _) => PublicKey::<C>::from_sec1_bytes(point)
+            .context("informal ecc public key")?,
+        (None, Some(secret)) => SecretKey::<C>::from_slice(secret)
+            .context("informal ecc private key")?
+            .public_key(),
+        (None, None) => {
+            return Err(Error::Unsupported(
+                "ec key without a point".to_string(),
+            ))
+        }
+    };
+    Ok(public.to_encoded_point(false).as_bytes().to_vec())
+}
+
+pub(crate) fn edwards_point(x25519: bool, secret: &[u8]) -> Result<Vec<u8>> {
+    let secret: [u8; 32] = secret.try_into().map_err(|_| {
+        Error::Unsupported(format!("{} bytes edwards secret", secret.len()))
+    })?;
+    Ok(if x25519 {
+        x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::from(secret))
+            .as_bytes()
+            .to_vec()
+    } else {
+        ed25519_dalek::SigningKey::from_bytes(&secret)
+            .verifying_key()
+            .to_bytes()
+            .to_vec()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use elliptic_curve::sec1::ToEncodedPoint;
+    use rsa::{pkcs8::EncodePublicKey, BigUint, RsaPublicKey};
+
+    use super::compare_keys;
+    use crate::enums::TextEncoding;
+
+    fn compare(a: &str, b: &str) -> super::KeyComparison {
+        compare_keys(a.to_string(), b.to_string(), TextEncoding::Base64)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compare_ec() {
+        let secret = p256::SecretKey::from_slice(&[7; 32]).unwrap();
+        let sec1 = secret.to_sec1_pem(Default::default()).unwrap();
+        let point = secret.public_key().to_encoded_point(false);
+        let jwk = format!(
+            r#"{{"kty":"EC","crv":"P-256","x":"{}","y":"{}"}}"#,
+            Base64UrlUnpadded::encode_string(point.x().unwrap()),
+            Base64UrlUnpadded::encode_string(point.y().unwrap()),
+        );
+        let comparison = compare(&sec1, &jwk);
+        assert_eq!(comparison.formats, ["pem", "jwk"]);
+        assert!(comparison.same_public);
+        assert_eq!(comparison.same_pair, None);
+
+        let other = p256::SecretKey::from_slice(&[8; 32]).unwrap();
+        let comparison =
+            compare(&sec1, &other.to_sec1_pem(Default::default()).unwrap());
+        assert!(!comparison.same_public);
+        assert_eq!(comparison.same_pair, Some(false));
+        assert_eq!(comparison.differences, ["point", "private scalar"]);
+    }
+
+    #[test]
+    fn test_compare_ed25519_ssh() {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[9; 32]);
+        let pkcs8 = key.to_pkcs8_pem(Default::default()).unwrap();
+        let point = key.verifying_key().to_bytes();
+        let blob = [
+            &11u32.to_be_bytes()[..],
+            b"ssh-ed25519",
+            &32u32.to_be_bytes(),
+            &point,
+        ]
+        .concat();
+        let line =
+            format!("ssh-ed25519 {} me@host", Base64::encode_string(&blob));
+        let comparison = compare(&pkcs8, &line);
+        assert_eq!(comparison.kinds, ["ed25519", "ed25519"]);
+        assert!(comparison.same_public);
+    }
+
+    #[test]
+    fn test_compare_rsa_kinds() {
+        let key = RsaPublicKey::new_unchecked(
+            BigUint::from(1000036000099u64),
+            BigUint::from(3u8),
+        );
+        let der =
+            Base64::encode_string(key.to_public_key_der().unwrap().as_bytes());
+        let jwk = r#"{"kty":"RSA","n":"6NbKYWM","e":"Aw"}"#;
+        let comparison = compare(&der, jwk);
+        assert_eq!(comparison.formats, ["der", "jwk"]);
+        assert!(comparison.same_public);
+
+        let jwk = r#"{"kty":"RSA","n":"6NbKYWM","e":"AQAB"}"#;
+        assert_eq!(compare(&der, jwk).differences, ["exponent"]);
+
+        let ed25519 = r#"{"kty":"OKP","crv":"Ed25519","x":"AAAA"}"#;
+        assert_eq!(compare(&der, ed25519).differences, ["kind"]);
+    }
+}
@@ -0,0 +1,233 @@
+//! Statistics over a byte string, the monobit and runs tests follow nist
+//! sp 800-22 and share its 0.01 significance level.
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+};
+
+const SIGNIFICANCE: f64 = 0.01;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EntropyAnalysis {
+    pub length: usize,
+    /// shannon entropy in bits per byte, 8 at most
+    pub shannon: f64,
+    /// min-entropy in bits per byte from the most frequent value
+    pub min_entropy: f64,
+    pub distinct: usize,
+    /// arithmetic mean of the bytes, 127.5 when uniform
+    pub mean: f64,
+    pub serial_correlation: f64,
+    pub tests: Vec<RandomnessTest>,
+    pub histogram: Vec<u64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RandomnessTest {
+    pub name: &'static str,
+    pub statistic: f64,
+    /// absent when the input is too short for the test to mean anything
+    pub p_value: Option<f64>,
+    pub passed: bool,
+}
+
+impl RandomnessTest {
+    fn new(name: &'static str, statistic: f64, p_value: Option<f64>) -> Self {
+        RandomnessTest {
+            name,
+            statistic,
+            p_value,
+            passed: p_value.map_or(false, |p| p >= SIGNIFICANCE),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn analyze_entropy(
+    input: String,
+    encoding: TextEncoding,
+) -> Result<EntropyAnalysis> {
+    let input = encoding.decode(&input)?;
+    if input.is_empty() {
+        return Err(Error::Unsupported("empty input".to_string()));
+    }
+    info!("analyze entropy, {} bytes", input.len());
+    let mut histogram = vec![0u64; 256];
+    input.iter().for_each(|byte| histogram[*byte as usize] += 1);
+    let length = input.len() as f64;
+    let min_entropy = match histogram.iter().max() {
+        Some(max) if *max > 0 => -(*max as f64 / length).log2(),
+        _ => 0.0,
+    };
+    let bits = input
+        .iter()
+        .flat_map(|byte| (0 .. 8).rev().map(move |bit| byte >> bit & 1 == 1))
+        .collect::<Vec<_>>();
+    Ok(EntropyAnalysis {
+        length: input.len(),
+        shannon: shannon(&histogram, input.len()),
+        min_entropy,
+        distinct: histogram.iter().filter(|count| **count > 0).count(),
+        mean: input.iter().map(|byte| *byte as f64).sum::<f64>() / length,
+        serial_correlation: serial_correlation(&input),
+        tests: vec![
+            chi_square(&histogram, input.len()),
+            monobit(&bits),
+            runs(&bits),
+        ],
+        histogram,
+    })
+}
+
+pub(crate) fn shannon(histogram: &[u64], length: usize) -> f64 {
+    histogram
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / length as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// 255 degrees of freedom, wilson-hilferty for the p-value.
+fn chi_square(histogram: &[u64], length: usize) -> RandomnessTest {
+    let expected = length as f64 / 256.0;
+    let statistic = histogram
+        .iter()
+        .map(|count| (*count as f64 - expected).powi(2) / expected)
+        .sum::<f64>();
+    // every expected count should be at least 5
+    if length < 256 * 5 {
+        return RandomnessTest::new("chi-square", statistic, None);
+    }
+    let k = 255.0;
+    let z = ((statistic / k).cbrt() - (1.0 - 2.0 / (9.0 * k)))
+        / (2.0 / (9.0 * k)).sqrt();
+    RandomnessTest::new(
+        "chi-square",
+        statistic,
+        Some(0.5 * erfc(z / std::f64::consts::SQRT_2)),
+    )
+}
+
+fn monobit(bits: &[bool]) -> RandomnessTest {
+    let sum = bits
+        .iter()
+        .map(|bit| if *bit { 1i64 } else { -1 })
+        .sum::<i64>();
+    let statistic = sum.unsigned_abs() as f64 / (bits.len() as f64).sqrt();
+    let p_value =
+        (bits.len() >= 100).then(|| erfc(statistic / std::f64::consts::SQRT_2));
+    RandomnessTest::new("monobit", statistic, p_value)
+}
+
+fn runs(bits: &[bool]) -> RandomnessTest {
+    let n = bits.len() as f64;
+    let ones = bits.iter().filter(|bit| **bit).count() as f64 / n;
+    let statistic =
+        1.0 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count() as f64;
+    // the test is only defined once the monobit frequency is close enough
+    let p_value = (bits.len() >= 100 && (ones - 0.5).abs() < 2.0 / n.sqrt())
+        .then(|| {
+            let spread = 2.0 * n * ones * (1.0 - ones);
+            erfc(
+                (statistic - spread).abs()
+                    / (2.0 * (2.0 * n).sqrt() * ones * (1.0 - ones)),
+            )
+        });
+    RandomnessTest::new("runs", statistic, p_value)
+}
+
+/// Wrapping around, close to 0 for random data.
+fn serial_correlation(input: &[u8]) -> f64 {
+    if input.len() < 2 {
+        return 0.0;
+    }
+    let n = input.len() as f64;
+    let (mut sum, mut squares, mut products) = (0.0, 0.0, 0.0);
+    for (index, byte) in input.iter().enumerate() {
+        let value = *byte as f64;
+        let next = input[(index + 1) % input.len()] as f64;
+        sum += value;
+        squares += value * value;
+        products += value * next;
+    }
+    let denominator = n * squares - sum * sum;
+    if denominator == 0.0 {
+        return 1.0;
+    }
+    (n * products - sum * sum) / denominator
+}
+
+/// The chebyshev fit from numerical recipes, relative error under 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, coefficient| acc * t + coefficient);
+    let value = t * (-z * z + polynomial).exp();
+    if x >= 0.0 {
+        value
+    } else {
+        2.0 - value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{analyze_entropy, monobit, runs};
+    use crate::enums::TextEncoding;
+
+    fn bits(epsilon: &str) -> Vec<bool> {
+        epsilon.chars().map(|bit| bit == '1').collect()
+    }
+
+    // the 100 bit examples of nist sp 800-22 2.1.8 and 2.3.8
+    #[test]
+    fn test_nist_examples() {
+        let epsilon = bits(
+            "11001001000011111101101010100010001000010110100011\
+             00001000110100110001001100011001100010100010111000",
+        );
+        let monobit = monobit(&epsilon);
+        assert!((monobit.p_value.unwrap() - 0.109599).abs() < 1e-5);
+        let runs = runs(&epsilon);
+        assert_eq!(runs.statistic, 52.0);
+        assert!((runs.p_value.unwrap() - 0.500798).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_analyze_entropy() {
+        let constant =
+            analyze_entropy("00".repeat(2048), TextEncoding::Hex).unwrap();
+        assert_eq!(constant.shannon, 0.0);
+        assert_eq!(constant.distinct, 1);
+        assert!(constant.tests.iter().all(|test| !test.passed));
+
+        let counter = (0 .. 4096).map(|index| index as u8).collect::<Vec<_>>();
+        let counter = TextEncoding::Base64.encode(&counter).unwrap();
+        let counter = analyze_entropy(counter, TextEncoding::Base64).unwrap();
+        assert_eq!(counter.shannon, 8.0);
+        assert_eq!(counter.histogram, vec![16; 256]);
+    }
+}
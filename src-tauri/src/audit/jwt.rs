@@ -0,0 +1,425 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::{sorted, Finding, Severity};
+use crate::{
+    errors::{Error, Result},
+    jwt::{jws, JwkeyAlgorithm},
+    vault,
+};
+
+/// Secrets from tutorials and defaults that keep showing up in production.
+const COMMON_SECRETS: &[&str] = &[
+    "",
+    "secret",
+    "secretkey",
+    "secret-key",
+    "your-256-bit-secret",
+    "your-secret-key",
+    "jwt",
+    "jwt-secret",
+    "key",
+    "password",
+    "changeme",
+    "123456",
+    "test",
+    "default",
+];
+
+/// Guesses per second of the dictionary test.
+const DICTIONARY_RATE: usize = 20_000;
+/// Candidates of a wordlist tried at most.
+const DICTIONARY_LIMIT: usize = 1_000_000;
+
+/// Tokens living longer than this are reported as long lived.
+const LONG_LIVED: u64 = 30 * 24 * 3600;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JwtAudit {
+    pub header: Value,
+    /// absent for encrypted tokens
+    pub payload: Option<Value>,
+    pub findings: Vec<Finding>,
+}
+
+/// Needs no key, `secret` only adds the length check of a known hmac secret.
+#[tauri::command]
+pub fn audit_jwt(token: String, secret: Option<String>) -> Result<JwtAudit> {
+    let segments = token.trim().split('.').collect::<Vec<_>>();
+    info!("audit jwt, segments: {}", segments.len());
+    let mut findings = Vec::new();
+    let header = decode_segment(segments[0])?;
+    let payload = match segments.len() {
+        3 => Some(decode_segment(segments[1])?),
+        5 => {
+            findings.push(Finding::new(
+                Severity::Info,
+                "encrypted",
+                "jwe, only the protected header is audited",
+            ));
+            None
+        }
+        count => {
+            return Err(Error::Unsupported(format!(
+                "token with {} segments",
+                count
+            )))
+        }
+    };
+
+    audit_header(&header, &mut findings);
+    if let Some(payload) = &payload {
+        audit_claims(payload, vault::now(), &mut findings);
+        audit_signature(&header, &segments, secret, &mut findings);
+    }
+    Ok(JwtAudit {
+        header,
+        payload,
+        findings: sorted(findings),
+    })
+}
+
+fn decode_segment(segment: &str) -> Result<Value> {
+    let bytes = Base64UrlUnpadded::decode_vec(segment)
+        .map_err(|_| Error::Unsupported("non base64url segment".to_string()))?;
+    Ok(serde_json::from_slice(&bytes).context("segment is not json")?)
+}
+
+fn audit_header(header: &Value, findings: &mut Vec<Finding>) {
+    match header["alg"].as_str() {
+        None => findings.push(Finding::new(
+            Severity::High,
+            "alg-missing",
+            "header has no alg, verifiers may fall back to anything",
+        )),
+        Some(alg) if alg.eq_ignore_ascii_case("none") => {
+            findings.push(Finding::new(
+                Severity::Critical,
+                "alg-none",
+                format!("alg is `{}`, the token is not signed", alg),
+            ))
+        }
+        Some(_) => {}
+    }
+
+    if let Some(kid) = header["kid"].as_str() {
+        let patterns = [
+            ("../", "path traversal"),
+            ("..\\", "path traversal"),
+            ("/dev/", "device file"),
+            ("\0", "nul byte"),
+            ("'", "sql quote"),
+            ("--", "sql comment"),
+            (" or ", "sql condition"),
+            ("|", "shell pipe"),
+            (";", "command separator"),
+            ("$(", "command substitution"),
+            ("`", "command substitution"),
+            ("://", "url"),
+        ];
+        let lowercase = kid.to_ascii_lowercase();
+        for (pattern, name) in patterns {
+            if lowercase.contains(pattern) {
+                findings.push(Finding::new(
+                    Severity::High,
+                    "kid-injection",
+                    format!("kid contains a {} pattern `{}`", name, pattern),
+                ));
+            }
+        }
+    }
+
+    if header.get("jwk").is_some() {
+        findings.push(Finding::new(
+            Severity::High,
+            "embedded-jwk",
+            "header embeds its own jwk, verifiers trusting it accept any \
+             attacker key",
+        ));
+    }
+    for member in ["jku", "x5u"] {
+        if let Some(url) = header[member].as_str() {
+            findings.push(Finding::new(
+                Severity::Medium,
+                "key-url",
+                format!(
+                    "{} points at `{}`, it must be checked against an \
+                     allowlist",
+                    member, url
+                ),
+            ));
+        }
+    }
+    if header.get("x5c").is_some() {
+        findings.push(Finding::new(
+            Severity::Low,
+            "embedded-certificate",
+            "x5c chain must be validated up to a trusted root",
+        ));
+    }
+}
+
+fn audit_claims(payload: &Value, now: u64, findings: &mut Vec<Finding>) {
+    let exp = payload["exp"].as_u64();
+    let iat = payload["iat"].as_u64();
+    match exp {
+        None => findings.push(Finding::new(
+            Severity::Medium,
+            "no-expiry",
+            "token has no exp and never expires",
+        )),
+        Some(exp) if exp <= now => findings.push(Finding::new(
+            Severity::Info,
+            "expired",
+            format!("expired {} seconds ago", now - exp),
+        )),
+        Some(exp) if exp - iat.unwrap_or(now).min(exp) > LONG_LIVED => findings
+            .push(Finding::new(
+                Severity::Low,
+                "long-lived",
+                format!(
+                    "valid for {} days",
+                    (exp - iat.unwrap_or(now).min(exp)) / 86400
+                ),
+            )),
+        Some(_) => {}
+    }
+    if let Some(iat) = iat.filter(|iat| *iat > now) {
+        findings.push(Finding::new(
+            Severity::Low,
+            "issued-in-future",
+            format!("iat is {} seconds ahead", iat - now),
+        ));
+    }
+    if let Some(nbf) = payload["nbf"].as_u64().filter(|nbf| *nbf > now) {
+        findings.push(Finding::new(
+            Severity::Info,
+            "not-yet-valid",
+            format!("nbf is {} seconds ahead", nbf - now),
+        ));
+    }
+}
+
+fn audit_signature(
+    header: &Value,
+    segments: &[&str],
+    secret: Option<String>,
+    findings: &mut Vec<Finding>,
+) {
+    if segments[2].is_empty() {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "unsigned",
+            "signature segment is empty",
+        ));
+        return;
+    }
+    let Some((algorithm, signed_with)) = hmac_oracle(header, segments) else {
+        return;
+    };
+    if let Some(common) = COMMON_SECRETS
+        .iter()
+        .find(|common| signed_with(common.as_bytes()))
+    {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "guessable-secret",
+            format!("signed with the well known secret `{}`", common),
+        ));
+    }
+    if let Some(secret) = secret {
+        let minimum = match algorithm {
+            JwkeyAlgorithm::HS256 => 32,
+            JwkeyAlgorithm::HS384 => 48,
+            _ => 64,
+        };
+        if secret.len() < minimum {
+            findings.push(Finding::new(
+                Severity::High,
+                "weak-secret",
+                format!(
+                    "{} byte secret is brute-forceable offline, {:?} wants at \
+                     least {}",
+                    secret.len(),
+                    algorithm,
+                    minimum
+                ),
+            ));
+        }
+        if !signed_with(secret.as_bytes()) {
+            findings.push(Finding::new(
+                Severity::Info,
+                "secret-mismatch",
+                "the given secret does not verify the signature",
+            ));
+        }
+    }
+}
+
+/// `None` for tokens not signed with hmac.
+fn hmac_oracle(
+    header: &Value,
+    segments: &[&str],
+) -> Option<(JwkeyAlgorithm, impl Fn(&[u8]) -> bool)> {
+    let algorithm =
+        match serde_json::from_value::<JwkeyAlgorithm>(header["alg"].clone()) {
+            Ok(
+                algorithm @ (JwkeyAlgorithm::HS256
+                | JwkeyAlgorithm::HS384
+                | JwkeyAlgorithm::HS512),
+            ) => algorithm,
+            _ => return None,
+        };
+    let signature = Base64UrlUnpadded::decode_vec(segments[2]).ok()?;
+    let signing_input = format!("{}.{}", segments[0], segments[1]);
+    Some((algorithm, move |secret: &[u8]| {
+        let jwk = json!({
+            "kty": "oct",
+            "k": Base64UrlUnpadded::encode_string(secret),
+        });
+        jws::verify_jws(algorithm, &jwk, signing_input.as_bytes(), &signature)
+            .unwrap_or(false)
+    }))
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JwtDictionaryDto {
+    pub token: String,
+    /// one per line
+    pub wordlist: Option<String>,
+    /// a local wordlist file, read when `wordlist` is absent
+    pub wordlist_path: Option<String>,
+    /// guesses per second, `DICTIONARY_RATE` at most
+    pub rate: Option<usize>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JwtDictionaryResult {
+    pub algorithm: JwkeyAlgorithm,
+    pub secret: Option<String>,
+    pub line: Option<usize>,
+    pub tried: usize,
+    /// the wordlist had more than `DICTIONARY_LIMIT` candidates
+    pub truncated: bool,
+}
+
+/// Nothing leaves the machine and the guesses are throttled.
+#[tauri::command]
+pub async fn jwt_dictionary_test(
+    data: JwtDictionaryDto,
+) -> Result<JwtDictionaryResult> {
+    let segments = data.token.trim().split('.').collect::<Vec<_>>();
+    if segments.len() != 3 {
+        return Err(Error::Unsupported("not a compact jws".to_string()));
+    }
+    let header = decode_segment(segments[0])?;
+    let (algorithm, signed_with) =
+        hmac_oracle(&header, &segments).ok_or_else(|| {
+            Error::Unsupported("token is not signed with hmac".to_string())
+        })?;
+    let wordlist = match (data.wordlist, &data.wordlist_path) {
+        (Some(wordlist), _) => wordlist,
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .with_context(|| format!("read {} failed", path))?,
+        (None, None) => {
+            return Err(Error::Unsupported("no wordlist".to_string()))
+        }
+    };
+    let rate = data
+        .rate
+        .unwrap_or(DICTIONARY_RATE)
+        .clamp(1, DICTIONARY_RATE);
+    info!("jwt dictionary test, {:?}, rate: {}", algorithm, rate);
+    let candidates = wordlist
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect::<Vec<_>>();
+    let truncated = candidates.len() > DICTIONARY_LIMIT;
+    let mut tried = 0;
+    for batch in
+        candidates[.. candidates.len().min(DICTIONARY_LIMIT)].chunks(rate)
+    {
+        let started = Instant::now();
+        for candidate in batch {
+            tried += 1;
+            if signed_with(candidate.as_bytes()) {
+                return Ok(JwtDictionaryResult {
+                    algorithm,
+                    secret: Some(candidate.to_string()),
+                    line: Some(tried),
+                    tried,
+                    truncated,
+                });
+            }
+        }
+        if let Some(rest) =
+            Duration::from_secs(1).checked_sub(started.elapsed())
+        {
+            tokio::time::sleep(rest).await;
+        }
+    }
+    Ok(JwtDictionaryResult {
+        algorithm,
+        secret: None,
+        line: None,
+        tried,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{audit_jwt, jwt_dictionary_test, JwtDictionaryDto};
+
+    // the jwt.io example, HS256 over `your-256-bit-secret`
+    const JWT_IO: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                          eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+                          SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c";
+
+    fn codes(token: &str, secret: Option<&str>) -> Vec<&'static str> {
+        audit_jwt(token.to_string(), secret.map(String::from))
+            .unwrap()
+            .findings
+            .iter()
+            .map(|finding| finding.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_audit_jwt() {
+        // {"alg":"none"}.{"sub":"1"}.
+        let codes_none = codes("eyJhbGciOiJub25lIn0.eyJzdWIiOiIxIn0.", None);
+        assert_eq!(codes_none, ["alg-none", "unsigned", "no-expiry"]);
+
+        let found = codes(JWT_IO, Some("your-256-bit-secret"));
+        assert_eq!(found, ["guessable-secret", "weak-secret", "no-expiry"]);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_dictionary() {
+        let dictionary = |wordlist: &str| JwtDictionaryDto {
+            token: JWT_IO.to_string(),
+            wordlist: Some(wordlist.to_string()),
+            wordlist_path: None,
+            rate: None,
+        };
+        let found =
+            jwt_dictionary_test(dictionary("admin\r\nyour-256-bit-secret\r\n"))
+                .await
+                .unwrap();
+        assert_eq!(found.secret.as_deref(), Some("your-256-bit-secret"));
+        assert_eq!(found.line, Some(2));
+        let missed = jwt_dictionary_test(dictionary("admin\nhunter2"))
+            .await
+            .unwrap();
+        assert_eq!((missed.secret, missed.tried), (None, 2));
+    }
+}
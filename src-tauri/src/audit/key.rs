@@ -0,0 +1,518 @@
+use anyhow::Context;
+use const_oid::ObjectIdentifier;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use der::Decode;
+use num_bigint::BigUint;
+use pem_rfc7468::PemLabel;
+use pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use pkcs8::DecodePrivateKey;
+use rsa::{
+    traits::{PrivateKeyParts, PublicKeyParts},
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::Serialize;
+use spki::DecodePublicKey;
+use tracing::info;
+
+use super::{sorted, weak, Finding, Severity};
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+};
+
+const RSA_ENCRYPTION: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_PUBLIC_KEY: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+const X25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.110");
+pub(crate) const SECP256R1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+pub(crate) const SECP384R1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.132.0.34");
+pub(crate) const SECP521R1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.132.0.35");
+pub(crate) const SECP256K1: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.132.0.10");
+pub(crate) const SM2: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.156.10197.1.301");
+
+/// Fermat steps tried on public moduli, enough to factor keys whose primes
+/// share their top half.
+const FERMAT_ROUNDS: usize = 1000;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyAudit {
+    pub kind: String,
+    pub bits: usize,
+    pub private: bool,
+    pub findings: Vec<Finding>,
+}
+
+pub(crate) enum AuditedKey {
+    RsaPrivate(RsaPrivateKey),
+    RsaPublic(RsaPublicKey),
+    Ec {
+        curve: Option<ObjectIdentifier>,
+        secret: Option<Vec<u8>>,
+        point: Option<Vec<u8>>,
+    },
+    Edwards {
+        x25519: bool,
+        secret: Option<Vec<u8>>,
+        point: Option<Vec<u8>>,
+    },
+    Symmetric(Vec<u8>),
+}
+
+#[tauri::command]
+pub fn audit_key(key: String, encoding: TextEncoding) -> Result<KeyAudit> {
+    let audited = parse(&key, encoding)?;
+    let mut findings = Vec::new();
+    let (kind, bits, private) = match &audited {
+        AuditedKey::RsaPrivate(key) => {
+            audit_rsa(key.n(), key.e(), &mut findings)?;
+            audit_rsa_primes(key, &mut findings);
+            ("rsa", key.n().bits(), true)
+        }
+        AuditedKey::RsaPublic(key) => {
+            audit_rsa(key.n(), key.e(), &mut findings)?;
+            ("rsa", key.n().bits(), false)
+        }
+        AuditedKey::Ec {
+            curve,
+            secret,
+            point,
+        } => {
+            let bits = audit_ec(*curve, secret, point, &mut findings);
+            ("ec", bits, secret.is_some())
+        }
+        AuditedKey::Edwards {
+            x25519,
+            secret,
+            point,
+        } => {
+            match point {
+                Some(point) if !x25519 => {
+                    audit_ed25519_point(point, &mut findings)
+                }
+                _ => {}
+            }
+            (
+                if *x25519 { "x25519" } else { "ed25519" },
+                255,
+                secret.is_some(),
+            )
+        }
+        AuditedKey::Symmetric(key) => {
+            audit_symmetric(key, &mut findings);
+            ("symmetric", key.len() * 8, true)
+        }
+    };
+    info!("audit key, kind: {}, findings: {}", kind, findings.len());
+    Ok(KeyAudit {
+        kind: kind.to_string(),
+        bits,
+        private,
+        findings: sorted(findings),
+    })
+}
+
+pub(crate) fn parse(key: &str, encoding: TextEncoding) -> Result<AuditedKey> {
+    if key.trim_start().starts_with("-----BEGIN ") {
+        let (label, der) = pem_rfc7468::decode_vec(key.trim().as_bytes())
+            .context("invalid pem key to audit")?;
+        return match label {
+            pkcs1::RsaPrivateKey::PEM_LABEL => Ok(AuditedKey::RsaPrivate(
+                RsaPrivateKey::from_pkcs1_der(&der)
+                    .context("invalid pkcs1 private key")?,
+            )),
+            pkcs1::RsaPublicKey::PEM_LABEL => Ok(AuditedKey::RsaPublic(
+                RsaPublicKey::from_pkcs1_der(&der)
+                    .context("invalid pkcs1 public key")?,
+            )),
+            sec1::EcPrivateKey::PEM_LABEL => parse_sec1(&der, None),
+            pkcs8::PrivateKeyInfo::PEM_LABEL => parse_pkcs8(&der),
+            spki::SubjectPublicKeyInfoOwned::PEM_LABEL => parse_spki(&der),
+            _ => Err(Error::Unsupported(format!("{} pem", label))),
+        };
+    }
+    Ok(parse_der(encoding.decode(key)?))
+}
+
+/// Raw key bytes when no key structure fits.
+pub(crate) fn parse_der(der: Vec<u8>) -> AuditedKey {
+    parse_pkcs8(&der)
+        .or_else(|_| parse_spki(&der))
+        .or_else(|_| parse_sec1(&der, None))
+        .or_else(|_| {
+            RsaPrivateKey::from_pkcs1_der(&der).map(AuditedKey::RsaPrivate)
+        })
+        .or_else(|_| {
+            RsaPublicKey::from_pkcs1_der(&der).map(AuditedKey::RsaPublic)
+        })
+        .unwrap_or(AuditedKey::Symmetric(der))
+}
+
+fn parse_pkcs8(der: &[u8]) -> Result<AuditedKey> {
+    let info = pkcs8::PrivateKeyInfo::try_from(der)
+        .context("invalid pkcs8 private key")?;
+    match info.algorithm.oid {
+        RSA_ENCRYPTION => Ok(AuditedKey::RsaPrivate(
+            RsaPrivateKey::from_pkcs8_der(der)
+                .context("invalid pkcs8 rsa private key")?,
+        )),
+        EC_PUBLIC_KEY => {
+            parse_sec1(info.private_key, info.algorithm.parameters_oid().ok())
+        }
+        // the private key is the seed in an octet string of its own
+        oid @ (ED25519 | X25519) => Ok(AuditedKey::Edwards {
+            x25519: oid == X25519,
+            secret: Some(
+                der::asn1::OctetStringRef::from_der(info.private_key)
+                    .context("invalid pkcs8 private key")?
+                    .as_bytes()
+                    .to_vec(),
+            ),
+            point: info.public_key.map(|point| point.to_vec()),
+        }),
+        oid => Err(Error::Unsupported(format!("key algorithm {}", oid))),
+    }
+}
+
+fn parse_spki(der: &[u8]) -> Result<AuditedKey> {
+    let info = spki::SubjectPublicKeyInfoRef::try_from(der)
+        .context("invalid spki public key")?;
+    let point = info.subject_public_key.raw_bytes().to_vec();
+    match info.algorithm.oid {
+        RSA_ENCRYPTION => Ok(AuditedKey::RsaPublic(
+            RsaPublicKey::from_public_key_der(der)
+                .context("invalid rsa public key")?,
+        )),
+        EC_PUBLIC_KEY => Ok(AuditedKey::Ec {
+            curve: info.algorithm.parameters_oid().ok(),
+            secret: None,
+            point: Some(point),
+        }),
+        oid @ (ED25519 | X25519) => Ok(AuditedKey::Edwards {
+            x25519: oid == X25519,
+            secret: None,
+            point: Some(point),
+        }),
+        oid => Err(Error::Unsupported(format!("key algorithm {}", oid))),
+    }
+}
+
+fn parse_sec1(
+    der: &[u8],
+    curve: Option<ObjectIdentifier>,
+) -> Result<AuditedKey> {
+    let key = sec1::EcPrivateKey::from_der(der)
+        .context("invalid sec1 private key")?;
+    Ok(AuditedKey::Ec {
+        curve: curve.or(key
+            .parameters
+            .and_then(|parameters| parameters.named_curve())),
+        secret: Some(key.private_key.to_vec()),
+        point: key.public_key.map(|point| point.to_vec()),
+    })
+}
+
+fn audit_rsa(
+    n: &rsa::BigUint,
+    e: &rsa::BigUint,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let bits = n.bits();
+    if bits < 1024 {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-size",
+            format!("{} bits modulus is factorable today", bits),
+        ));
+    } else if bits < 2048 {
+        findings.push(Finding::new(
+            Severity::High,
+            "rsa-size",
+            format!("{} bits modulus is below the 2048 bits minimum", bits),
+        ));
+    }
+
+    let e = BigUint::from_bytes_be(&e.to_bytes_be());
+    if e <= BigUint::from(1u8) || !e.bit(0) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-exponent",
+            format!("public exponent {} is not a valid rsa exponent", e),
+        ));
+    } else if e < BigUint::from(65537u32) {
+        findings.push(Finding::new(
+            Severity::Medium,
+            "rsa-exponent",
+            format!(
+                "public exponent {} makes padding and broadcast attacks easier",
+                e
+            ),
+        ));
+    }
+
+    let n = BigUint::from_bytes_be(&n.to_bytes_be());
+    if let Some(factor) = fermat(&n) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-close-primes",
+            format!(
+                "primes are too close, fermat factors the modulus with {}",
+                factor
+            ),
+        ));
+    }
+    if weak::roca(&n) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-roca",
+            "modulus has the roca fingerprint of infineon rsalib, the private \
+             key can be recovered",
+        ));
+    }
+    match weak::debian_weak(&n)? {
+        Some(true) => findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-debian-weak",
+            "modulus is on the debian openssl blocklist",
+        )),
+        Some(false) => {}
+        None => findings.push(Finding::new(
+            Severity::Info,
+            "debian-blocklist-missing",
+            format!("no debian blocklist installed for {} bits keys", bits),
+        )),
+    }
+    Ok(())
+}
+
+fn audit_rsa_primes(key: &RsaPrivateKey, findings: &mut Vec<Finding>) {
+    let primes = key.primes();
+    for (index, prime) in primes.iter().enumerate() {
+        if primes[.. index].contains(prime) {
+            findings.push(Finding::new(
+                Severity::Critical,
+                "rsa-repeated-prime",
+                "the modulus is built from a repeated prime",
+            ));
+        }
+    }
+    if primes.len() > 2 {
+        findings.push(Finding::new(
+            Severity::Info,
+            "rsa-multi-prime",
+            format!("{} primes, not every library accepts it", primes.len()),
+        ));
+    }
+}
+
+fn fermat(n: &BigUint) -> Option<BigUint> {
+    let mut a = n.sqrt();
+    if &a * &a < *n {
+        a += 1u8;
+    }
+    for _ in 0 .. FERMAT_ROUNDS {
+        let b2 = &a * &a - n;
+        let b = b2.sqrt();
+        if &b * &b == b2 {
+            return Some(&a - &b);
+        }
+        a += 1u8;
+    }
+    None
+}
+
+fn audit_ec(
+    curve: Option<ObjectIdentifier>,
+    secret: &Option<Vec<u8>>,
+    point: &Option<Vec<u8>>,
+    findings: &mut Vec<Finding>,
+) -> usize {
+    let on_curve = |point: &[u8]| match curve {
+        Some(SECP256R1) => {
+            Some(p256::PublicKey::from_sec1_bytes(point).is_ok())
+        }
+        Some(SECP384R1) => {
+            Some(p384::PublicKey::from_sec1_bytes(point).is_ok())
+        }
+        Some(SECP521R1) => {
+            Some(p521::PublicKey::from_sec1_bytes(point).is_ok())
+        }
+        Some(SECP256K1) => {
+            Some(k256::PublicKey::from_sec1_bytes(point).is_ok())
+        }
+        _ => None,
+    };
+    let bits = match curve {
+        Some(SECP256R1) | Some(SECP256K1) | Some(SM2) => 256,
+        Some(SECP384R1) => 384,
+        Some(SECP521R1) => 521,
+        Some(oid) => {
+            findings.push(Finding::new(
+                Severity::Medium,
+                "ec-curve",
+                format!("{} is not a widely reviewed named curve", oid),
+            ));
+            0
+        }
+        None => {
+            findings.push(Finding::new(
+                Severity::High,
+                "ec-explicit-curve",
+                "explicit curve parameters, they may describe a weak curve",
+            ));
+            0
+        }
+    };
+    if point.as_deref().and_then(on_curve) == Some(false) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "ec-invalid-point",
+            "public point is not on the curve",
+        ));
+    }
+    if let Some(secret) = secret {
+        let leading = secret.iter().take_while(|byte| **byte == 0).count();
+        if leading == secret.len() {
+            findings.push(Finding::new(
+                Severity::Critical,
+                "ec-zero-secret",
+                "private scalar is zero",
+            ));
+        } else if leading * 2 >= secret.len() {
+            findings.push(Finding::new(
+                Severity::High,
+                "ec-small-secret",
+                format!(
+                    "private scalar has {} leading zero bytes, it was not \
+                     drawn uniformly",
+                    leading
+                ),
+            ));
+        }
+    }
+    bits
+}
+
+fn audit_ed25519_point(point: &[u8], findings: &mut Vec<Finding>) {
+    match CompressedEdwardsY::from_slice(point)
+        .ok()
+        .and_then(|point| point.decompress())
+    {
+        None => findings.push(Finding::new(
+            Severity::Critical,
+            "ed25519-invalid-point",
+            "public key does not decode to a curve point",
+        )),
+        Some(point) if point.is_small_order() => findings.push(Finding::new(
+            Severity::Critical,
+            "ed25519-small-order",
+            "public key is a small order point, signatures verify for many \
+             messages",
+        )),
+        Some(point) if !point.is_torsion_free() => findings.push(Finding::new(
+            Severity::Medium,
+            "ed25519-mixed-order",
+            "public key has a torsion component, verifiers may disagree",
+        )),
+        Some(_) => {}
+    }
+}
+
+pub(super) fn audit_symmetric(key: &[u8], findings: &mut Vec<Finding>) {
+    if ![16, 24, 32].contains(&key.len()) {
+        findings.push(Finding::new(
+            Severity::Medium,
+            "symmetric-size",
+            format!("{} bytes is not an aes key size", key.len()),
+        ));
+    }
+    if key.len() < 16 {
+        findings.push(Finding::new(
+            Severity::High,
+            "symmetric-short",
+            format!("{} bits key is brute-forceable", key.len() * 8),
+        ));
+    }
+    if key.is_empty() {
+        return;
+    }
+    if key.iter().all(|byte| *byte == key[0]) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "symmetric-constant",
+            format!("every byte is {:02x}", key[0]),
+        ));
+        return;
+    }
+    if key.iter().all(|byte| (0x20 .. 0x7f).contains(byte)) {
+        findings.push(Finding::new(
+            Severity::High,
+            "symmetric-ascii",
+            "key is printable ascii, a password or encoded text rather than \
+             random bytes",
+        ));
+    }
+    let mut seen = [false; 256];
+    key.iter().for_each(|byte| seen[*byte as usize] = true);
+    let distinct = seen.iter().filter(|seen| **seen).count();
+    if distinct * 4 < key.len() * 3 {
+        findings.push(Finding::new(
+            Severity::Medium,
+            "symmetric-low-entropy",
+            format!("only {} distinct values in {} bytes", distinct, key.len()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rsa::{pkcs8::EncodePublicKey, BigUint, RsaPublicKey};
+
+    use super::audit_key;
+    use crate::enums::TextEncoding;
+
+    fn codes(key: &str, encoding: TextEncoding) -> Vec<&'static str> {
+        audit_key(key.to_string(), encoding)
+            .unwrap()
+            .findings
+            .iter()
+            .map(|finding| finding.code)
+            // depends on the blocklists installed on the host
+            .filter(|code| *code != "debian-blocklist-missing")
+            .collect()
+    }
+
+    #[test]
+    fn test_audit_symmetric() {
+        assert_eq!(codes("00".repeat(16).as_str(), TextEncoding::Hex), [
+            "symmetric-constant"
+        ]);
+        assert_eq!(
+            codes("correct horse battery staple", TextEncoding::Utf8),
+            ["symmetric-ascii", "symmetric-size", "symmetric-low-entropy"]
+        );
+    }
+
+    #[test]
+    fn test_audit_rsa_close_primes() {
+        // 1000003 * 1000033 with e = 3
+        let key = RsaPublicKey::new_unchecked(
+            BigUint::from(1000036000099u64),
+            BigUint::from(3u8),
+        );
+        let der = key.to_public_key_der().unwrap();
+        let key = TextEncoding::Base64.encode(der.as_bytes()).unwrap();
+        assert_eq!(codes(&key, TextEncoding::Base64), [
+            "rsa-size",
+            "rsa-close-primes",
+            "rsa-exponent"
+        ]);
+    }
+}
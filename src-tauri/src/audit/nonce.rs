@@ -0,0 +1,276 @@
+//! Nonce reuse across collected AES-GCM ciphertexts, whose plaintexts then
+//! share a keystream.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{sorted, Finding, Severity};
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+};
+
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GcmSample {
+    /// samples with the same id, or all without one, share a key
+    pub key_id: Option<String>,
+    /// taken from the front of `ciphertext` when not given
+    pub nonce: Option<String>,
+    /// with the tag at its end
+    pub ciphertext: String,
+    /// when known, recovers the keystream
+    pub plaintext: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceReuseDto {
+    pub samples: Vec<GcmSample>,
+    pub encoding: TextEncoding,
+    pub plaintext_encoding: TextEncoding,
+    /// of the nonces in front of the ciphertexts, 12 unless given
+    pub nonce_size: Option<usize>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceReuseReport {
+    pub reuses: Vec<NonceReuse>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceReuse {
+    pub key_id: Option<String>,
+    pub nonce: String,
+    pub samples: Vec<usize>,
+    /// xor of the first two ciphertexts, that of their plaintexts
+    pub plaintext_xor: String,
+    pub recovered: Vec<RecoveredPlaintext>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredPlaintext {
+    pub sample: usize,
+    pub from: usize,
+    pub hex: String,
+    pub text: Option<String>,
+    /// the known plaintext covered the whole ciphertext
+    pub complete: bool,
+}
+
+struct Parsed {
+    nonce: Vec<u8>,
+    /// without the tag
+    body: Vec<u8>,
+    plaintext: Option<Vec<u8>>,
+}
+
+#[tauri::command]
+pub fn detect_nonce_reuse(data: NonceReuseDto) -> Result<NonceReuseReport> {
+    info!("detect gcm nonce reuse, samples: {}", data.samples.len());
+    let nonce_size = data.nonce_size.unwrap_or(NONCE_SIZE);
+    let parsed = data
+        .samples
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| parse(index, sample, &data, nonce_size))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut groups: BTreeMap<(Option<&str>, &[u8]), Vec<usize>> =
+        BTreeMap::new();
+    for (index, sample) in parsed.iter().enumerate() {
+        groups
+            .entry((data.samples[index].key_id.as_deref(), &sample.nonce))
+            .or_default()
+            .push(index);
+    }
+
+    let mut reuses = vec![];
+    let mut findings = vec![];
+    for ((key_id, nonce), samples) in groups {
+        if samples.len() < 2 {
+            continue;
+        }
+        let nonce = TextEncoding::Hex.encode(nonce)?;
+        findings.push(Finding::new(
+            Severity::Critical,
+            "gcm-nonce-reuse",
+            format!(
+                "nonce {} is used by samples {:?}{}, their plaintexts xor \
+                 together and the tags can be forged",
+                nonce,
+                samples,
+                key_id
+                    .map(|id| format!(" under key {}", id))
+                    .unwrap_or_default()
+            ),
+        ));
+        let (first, second) = (&parsed[samples[0]], &parsed[samples[1]]);
+        reuses.push(NonceReuse {
+            key_id: key_id.map(str::to_string),
+            nonce,
+            plaintext_xor: TextEncoding::Hex
+                .encode(&xor(&first.body, &second.body))?,
+            recovered: recover(&parsed, &samples)?,
+            samples,
+        });
+    }
+    if reuses.iter().any(|reuse| !reuse.recovered.is_empty()) {
+        findings.push(Finding::new(
+            Severity::Critical,
+            "gcm-keystream-recovered",
+            "a known plaintext gave away the keystream of its nonce",
+        ));
+    }
+    Ok(NonceReuseReport {
+        reuses,
+        findings: sorted(findings),
+    })
+}
+
+fn parse(
+    index: usize,
+    sample: &GcmSample,
+    data: &NonceReuseDto,
+    nonce_size: usize,
+) -> Result<Parsed> {
+    let mut ciphertext = data.encoding.decode_lenient(&sample.ciphertext)?;
+    let nonce = match &sample.nonce {
+        Some(nonce) => data.encoding.decode_lenient(nonce)?,
+        None if ciphertext.len() >= nonce_size => {
+            ciphertext.drain(.. nonce_size).collect()
+        }
+        None => {
+            return Err(Error::Unsupported(format!(
+                "sample {} is shorter than its nonce",
+                index
+            )))
+        }
+    };
+    if ciphertext.len() < TAG_SIZE {
+        return Err(Error::Unsupported(format!(
+            "sample {} is shorter than a gcm tag",
+            index
+        )));
+    }
+    ciphertext.truncate(ciphertext.len() - TAG_SIZE);
+    Ok(Parsed {
+        nonce,
+        body: ciphertext,
+        plaintext: sample
+            .plaintext
+            .as_ref()
+            .map(|plaintext| data.plaintext_encoding.decode(plaintext))
+            .transpose()?,
+    })
+}
+
+/// Uses the longest known plaintext among `samples`.
+fn recover(
+    parsed: &[Parsed],
+    samples: &[usize],
+) -> Result<Vec<RecoveredPlaintext>> {
+    let known = samples
+        .iter()
+        .filter_map(|index| {
+            let plaintext = parsed[*index].plaintext.as_ref()?;
+            Some((*index, plaintext))
+        })
+        .max_by_key(|(_, plaintext)| plaintext.len());
+    let Some((from, plaintext)) = known else {
+        return Ok(vec![]);
+    };
+    let keystream = xor(&parsed[from].body, plaintext);
+    samples
+        .iter()
+        .filter(|index| parsed[**index].plaintext.is_none())
+        .map(|index| {
+            let body = &parsed[*index].body;
+            let recovered = xor(body, &keystream);
+            Ok(RecoveredPlaintext {
+                sample: *index,
+                from,
+                hex: TextEncoding::Hex.encode(&recovered)?,
+                text: String::from_utf8(recovered).ok(),
+                complete: keystream.len() >= body.len(),
+            })
+        })
+        .collect()
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{detect_nonce_reuse, GcmSample, NonceReuseDto};
+    use crate::{
+        crypto::aes::encrypt_or_decrypt_aes,
+        enums::{AesEncryptionPadding, EncryptionMode, TextEncoding},
+    };
+
+    #[test]
+    fn test_detect_nonce_reuse() {
+        let key = [0x42; 16];
+        let seal = |nonce: [u8; 12], plaintext: &str| {
+            let ciphertext = encrypt_or_decrypt_aes(
+                EncryptionMode::Gcm,
+                plaintext.as_bytes(),
+                &key,
+                Some(nonce.to_vec()),
+                None,
+                AesEncryptionPadding::NoPadding,
+                true,
+            )
+            .unwrap();
+            let sealed = [nonce.as_slice(), &ciphertext].concat();
+            TextEncoding::Hex.encode(&sealed).unwrap()
+        };
+        let sample = |ciphertext, plaintext: Option<&str>| GcmSample {
+            key_id: None,
+            nonce: None,
+            ciphertext,
+            plaintext: plaintext.map(str::to_string),
+        };
+        let report = detect_nonce_reuse(NonceReuseDto {
+            samples: vec![
+                sample(seal([1; 12], "known plaintext, long enough"), None),
+                sample(seal([2; 12], "another nonce"), None),
+                sample(seal([1; 12], "secret message"), None),
+                sample(
+                    seal([1; 12], "known plaintext, long enough"),
+                    Some("known plaintext, long enough"),
+                ),
+            ],
+            encoding: TextEncoding::Hex,
+            plaintext_encoding: TextEncoding::Utf8,
+            nonce_size: None,
+        })
+        .unwrap();
+        assert_eq!(report.reuses.len(), 1);
+        let reuse = &report.reuses[0];
+        assert_eq!(reuse.samples, [0, 2, 3]);
+        assert_eq!(reuse.nonce, "010101010101010101010101");
+        let recovered = reuse
+            .recovered
+            .iter()
+            .map(|recovered| (recovered.sample, recovered.text.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(recovered, [
+            (0, Some("known plaintext, long enough")),
+            (2, Some("secret message"))
+        ]);
+        assert!(reuse.recovered.iter().all(|recovered| recovered.complete));
+        assert_eq!(report.findings.len(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+//! What a raw symmetric key is good for: its size, how random it looks and
+//! the encodings other tools expect it in.
+
+use serde::Serialize;
+use tracing::info;
+
+use super::{entropy, key::audit_symmetric, sorted, Finding};
+use crate::{
+    codec::{base64_encode, hex_encode},
+    enums::TextEncoding,
+    errors::{Error, Result},
+};
+
+/// Name, output and block size in bytes, RFC 2104 wants keys at least as
+/// long as the output and hashes the ones longer than a block.
+const HMACS: &[(&str, usize, usize)] = &[
+    ("HMAC-SHA1", 20, 64),
+    ("HMAC-SHA256", 32, 64),
+    ("HMAC-SHA384", 48, 128),
+    ("HMAC-SHA512", 64, 128),
+];
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInspection {
+    pub length: usize,
+    pub bits: usize,
+    /// shannon entropy in bits per byte
+    pub shannon: f64,
+    /// an upper bound, a counter also scores high
+    pub estimated_bits: usize,
+    pub aes128: bool,
+    pub aes192: bool,
+    pub aes256: bool,
+    pub hmac: Vec<HmacSuitability>,
+    pub hex: String,
+    pub base64: String,
+    /// unpadded, as in jwk `k`
+    pub base64url: String,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HmacSuitability {
+    pub algorithm: &'static str,
+    /// at least as long as the digest output
+    pub suitable: bool,
+    /// longer than a block, the key is replaced by its digest
+    pub hashed: bool,
+}
+
+#[tauri::command]
+pub fn inspect_secret(
+    input: String,
+    encoding: TextEncoding,
+) -> Result<SecretInspection> {
+    let secret = encoding.decode_lenient(&input)?;
+    if secret.is_empty() {
+        return Err(Error::Unsupported("empty secret".to_string()));
+    }
+    info!("inspect secret, {} bytes", secret.len());
+    let mut histogram = vec![0u64; 256];
+    secret
+        .iter()
+        .for_each(|byte| histogram[*byte as usize] += 1);
+    let shannon = entropy::shannon(&histogram, secret.len());
+    let ceiling = (secret.len().min(256) as f64).log2();
+    let estimated_bits = if ceiling > 0.0 {
+        (secret.len() as f64 * 8.0 * shannon / ceiling).round() as usize
+    } else {
+        0
+    };
+    let mut findings = vec![];
+    audit_symmetric(&secret, &mut findings);
+    Ok(SecretInspection {
+        length: secret.len(),
+        bits: secret.len() * 8,
+        shannon,
+        estimated_bits,
+        aes128: secret.len() == 16,
+        aes192: secret.len() == 24,
+        aes256: secret.len() == 32,
+        hmac: HMACS
+            .iter()
+            .map(|(algorithm, output, block)| HmacSuitability {
+                algorithm: *algorithm,
+                suitable: secret.len() >= *output,
+                hashed: secret.len() > *block,
+            })
+            .collect(),
+        hex: hex_encode(&secret, false)?,
+        base64: base64_encode(&secret, false, false)?,
+        base64url: base64_encode(&secret, true, true)?,
+        findings: sorted(findings),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::inspect_secret;
+    use crate::enums::TextEncoding;
+
+    #[test]
+    fn test_inspect_secret() {
+        let key = (0 .. 32).collect::<Vec<u8>>();
+        let key = TextEncoding::Hex.encode(&key).unwrap();
+        let inspection = inspect_secret(key, TextEncoding::Hex).unwrap();
+        assert_eq!(inspection.bits, 256);
+        assert_eq!(inspection.estimated_bits, 256);
+        assert!(inspection.aes256 && !inspection.aes128);
+        let suitable = inspection
+            .hmac
+            .iter()
+            .map(|hmac| hmac.suitable)
+            .collect::<Vec<_>>();
+        assert_eq!(suitable, [true, true, false, false]);
+        assert_eq!(
+            inspection.base64url,
+            "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8"
+        );
+        assert!(inspection.findings.is_empty());
+
+        let password =
+            inspect_secret("password".to_string(), TextEncoding::Utf8).unwrap();
+        assert!(password.estimated_bits < password.bits);
+        let codes = password
+            .findings
+            .iter()
+            .map(|finding| finding.code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes, [
+            "symmetric-short",
+            "symmetric-ascii",
+            "symmetric-size"
+        ]);
+    }
+}
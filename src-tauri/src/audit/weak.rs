@@ -0,0 +1,99 @@
+//! Checks against keys known to be generated badly: the ROCA fingerprint of
+//! Infineon RSALib moduli and the Debian OpenSSL PRNG blocklists.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use num_bigint::BigUint;
+use sha1::{Digest, Sha1};
+
+use crate::errors::Result;
+
+/// A ROCA modulus reduced by each lands in the subgroup generated by 65537.
+const ROCA_PRIMES: &[u32] = &[
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+    73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151,
+    157, 163, 167,
+];
+
+/// Where the `openssl-blacklist` package installs `blacklist.RSA-<bits>`.
+const DEBIAN_BLOCKLIST_DIR: &str = "/usr/share/openssl-blacklist";
+
+pub(crate) fn roca(n: &BigUint) -> bool {
+    ROCA_PRIMES.iter().all(|prime| {
+        let residue = (n % prime)
+            .to_u32_digits()
+            .first()
+            .copied()
+            .unwrap_or_default();
+        let mut element = 1u32;
+        loop {
+            if element == residue {
+                return true;
+            }
+            element = (element as u64 * 65537 % *prime as u64) as u32;
+            if element == 1 {
+                return false;
+            }
+        }
+    })
+}
+
+/// `None` when no list is installed, `KITS_WEAK_KEY_DIR` overrides the
+/// directory.
+pub(crate) fn debian_weak(n: &BigUint) -> Result<Option<bool>> {
+    let dir = std::env::var("KITS_WEAK_KEY_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEBIAN_BLOCKLIST_DIR));
+    let path = dir.join(format!("blacklist.RSA-{}", n.bits()));
+    let blocklist = match std::fs::read_to_string(&path) {
+        Ok(blocklist) => blocklist,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(anyhow::Error::from(err)
+                .context(format!("read {}", path.display()))
+                .into())
+        }
+    };
+    let fingerprint = debian_fingerprint(n);
+    Ok(Some(
+        blocklist
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .any(|line| line.eq_ignore_ascii_case(&fingerprint)),
+    ))
+}
+
+/// What the blocklists store, the last 80 bits of the sha-1 of
+/// `openssl rsa -modulus` output.
+fn debian_fingerprint(n: &BigUint) -> String {
+    let modulus = format!("Modulus={}\n", n.to_str_radix(16).to_uppercase());
+    let digest = Sha1::digest(modulus.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    digest[20 ..].to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+
+    use super::{roca, ROCA_PRIMES};
+
+    #[test]
+    fn test_roca() {
+        let primorial = ROCA_PRIMES
+            .iter()
+            .fold(BigUint::from(1u8), |acc, prime| acc * *prime);
+        let generator = BigUint::from(65537u32);
+        // both factors have the rsalib shape k * M + (65537^a mod M)
+        let p = generator.modpow(&BigUint::from(1234u32), &primorial)
+            + &primorial * 7u8;
+        let q = generator.modpow(&BigUint::from(4321u32), &primorial)
+            + &primorial * 11u8;
+        assert!(roca(&(p * q)));
+        // 2 is not a power of 65537 modulo 11
+        assert!(!roca(&(primorial + 2u8)));
+    }
+}
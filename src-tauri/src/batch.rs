@@ -0,0 +1,188 @@
+//! `kits batch`: NDJSON requests `{"id": .., "op": .., "params": {..}}` on
+//! stdin, one response line each on stdout, in order.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, Context};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    codec,
+    crypto::{aes, digest, ecc, kdf, rsa},
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+    guard,
+    jwt::jwk,
+};
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    op: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize)]
+struct Response {
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Error>,
+}
+
+impl Response {
+    fn new(id: Value, result: Result<Value>) -> Self {
+        match result {
+            Ok(output) => Response {
+                id,
+                output: Some(output),
+                code: None,
+                error: None,
+            },
+            Err(err) => Response {
+                id,
+                output: None,
+                code: Some(err.code()),
+                error: Some(err),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertParams {
+    input: String,
+    from: TextEncoding,
+    to: TextEncoding,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DigestParams {
+    digest: Digest,
+    input: String,
+    input_encoding: TextEncoding,
+    output_encoding: TextEncoding,
+}
+
+/// Only io errors stop the batch.
+pub async fn run<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+) -> Result<usize> {
+    let mut answered = 0;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let result = match guard::check(&request.op, &request.params) {
+                    Ok(()) => execute(&request.op, request.params).await,
+                    Err(err) => Err(err),
+                };
+                Response::new(request.id, result)
+            }
+            Err(err) => Response::new(
+                Value::Null,
+                Err(anyhow!(err).context("invalid json input").into()),
+            ),
+        };
+        serde_json::to_writer(&mut output, &response)
+            .context("serialize batch response failed")?;
+        writeln!(output)?;
+        output.flush()?;
+        answered += 1;
+    }
+    info!("batch done, {} operations", answered);
+    Ok(answered)
+}
+
+async fn execute(op: &str, params: Value) -> Result<Value> {
+    fn parse<T: DeserializeOwned>(params: Value) -> Result<T> {
+        Ok(serde_json::from_value(params).context("invalid json input")?)
+    }
+    fn value<T: Serialize>(output: T) -> Result<Value> {
+        Ok(serde_json::to_value(output)
+            .context("serialize batch output failed")?)
+    }
+    match op {
+        "convert" => {
+            let params: ConvertParams = parse(params)?;
+            value(codec::convert_encoding(
+                params.input,
+                params.from,
+                params.to,
+            )?)
+        }
+        "digest" => {
+            let params: DigestParams = parse(params)?;
+            value(
+                digest::digest(
+                    params.digest,
+                    params.input,
+                    params.input_encoding,
+                    params.output_encoding,
+                )
+                .await?,
+            )
+        }
+        "kdf" => value(kdf::kdf(parse(params)?)?),
+        "aes" => value(aes::crypto_aes(parse(params)?).await?),
+        "rsa" => value(rsa::crypto_rsa(parse(params)?).await?),
+        "ecies" => value(ecc::ecies(parse(params)?).await?),
+        "jwk" => value(jwk::generate_jwk(parse(params)?).await?),
+        op => Err(Error::Unsupported(format!("batch operation {}", op))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::run;
+
+    #[tokio::test]
+    async fn test_batch() {
+        let input = [
+            concat!(
+                r#"{"id":1,"op":"digest","params":{"digest":"sha256","#,
+                r#""input":"abc","inputEncoding":"utf8","#,
+                r#""outputEncoding":"hex"}}"#
+            ),
+            "",
+            concat!(
+                r#"{"id":"two","op":"convert","params":{"input":"kits","#,
+                r#""from":"utf8","to":"base64"}}"#
+            ),
+            r#"{"id":3,"op":"md4"}"#,
+            "not json",
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        assert_eq!(run(input.as_bytes(), &mut output).await.unwrap(), 4);
+        let lines = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines[0]["output"]["output"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(lines[1]["id"], "two");
+        assert_eq!(lines[1]["output"], "a2l0cw==");
+        assert_eq!(lines[2]["code"], "unsupported");
+        assert_eq!(lines[3]["id"], Value::Null);
+        assert_eq!(lines[3]["code"], "codec.json");
+    }
+}
@@ -2,12 +2,17 @@ use anyhow::Context;
 use base64ct::{
     Base64, Base64Unpadded, Base64Url, Base64UrlUnpadded, Encoding,
 };
+use ciborium::value::Value as CborValue;
+use serde::Serialize;
+use serde_json::{json, Number};
 
 use crate::{
     enums::{KeyFormat, Pkcs, TextEncoding},
-    errors::Result,
+    errors::{Error, Result},
 };
 
+pub mod pem;
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy)]
 pub struct PkcsDto {
     pub pkcs: Pkcs,
@@ -15,6 +20,15 @@ pub struct PkcsDto {
     pub encoding: TextEncoding,
 }
 
+pub(crate) fn reject_spki_private(from: Pkcs, to: Pkcs) -> Result<()> {
+    if from == Pkcs::Spki || to == Pkcs::Spki {
+        return Err(Error::Unsupported(
+            "spki private key, spki carries public keys only".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub fn convert_encoding(
     input: String,
@@ -26,6 +40,286 @@ pub fn convert_encoding(
     to.encode(&decoded)
 }
 
+#[derive(Serialize, Debug)]
+pub struct CborDecoded {
+    pub diagnostic: String,
+    pub json: String,
+}
+
+#[tauri::command]
+pub fn cbor_decode(input: String, from: TextEncoding) -> Result<CborDecoded> {
+    let input = from.decode(&input)?;
+    let mut reader = input.as_slice();
+    let value: CborValue =
+        ciborium::from_reader(&mut reader).context("invalid cbor input")?;
+    if !reader.is_empty() {
+        return Err(Error::Unsupported(format!(
+            "{} trailing bytes after cbor item",
+            reader.len()
+        )));
+    }
+    Ok(CborDecoded {
+        diagnostic: cbor_diagnostic(&value)?,
+        json: serde_json::to_string_pretty(&cbor_to_json(&value)?)
+            .context("value to string failed")?,
+    })
+}
+
+#[tauri::command]
+pub fn cbor_encode(input: String, to: TextEncoding) -> Result<String> {
+    let value: serde_json::Value =
+        serde_json::from_str(&input).context("invalid json input")?;
+    let mut output = Vec::new();
+    ciborium::into_writer(&json_to_cbor(&value), &mut output)
+        .context("cbor encode failed")?;
+    to.encode(&output)
+}
+
+#[derive(serde::Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BytesOp {
+    Xor,
+    And,
+    Or,
+    Not,
+    /// shifts and rotations treat the input as one big-endian bit string
+    /// and keep its length
+    ShiftLeft {
+        bits: usize,
+    },
+    ShiftRight {
+        bits: usize,
+    },
+    RotateLeft {
+        bits: usize,
+    },
+    RotateRight {
+        bits: usize,
+    },
+    Slice {
+        start: usize,
+        end: Option<usize>,
+    },
+    Concat,
+    /// fills up to `length` bytes with `byte`, on the left when `left`
+    Pad {
+        length: usize,
+        byte: u8,
+        left: bool,
+    },
+}
+
+/// `b` is either as long as `a` or a single byte applied to every byte.
+#[tauri::command]
+pub fn bytes_op(
+    a: String,
+    a_encoding: TextEncoding,
+    b: Option<String>,
+    b_encoding: Option<TextEncoding>,
+    op: BytesOp,
+    output_encoding: TextEncoding,
+) -> Result<String> {
+    let a = a_encoding.decode(&a)?;
+    let b = match b {
+        Some(b) => Some(b_encoding.unwrap_or(a_encoding).decode(&b)?),
+        None => None,
+    };
+    let binary = |f: fn(u8, u8) -> u8| -> Result<Vec<u8>> {
+        let b = b.as_deref().ok_or_else(|| {
+            Error::Unsupported(format!("{:?} without second operand", op))
+        })?;
+        if b.len() != a.len() && b.len() != 1 {
+            return Err(Error::Unsupported(format!(
+                "operands of {} and {} bytes",
+                a.len(),
+                b.len()
+            )));
+        }
+        Ok(a.iter()
+            .zip(b.iter().cycle())
+            .map(|(a, b)| f(*a, *b))
+            .collect())
+    };
+    let output = match op {
+        BytesOp::Xor => binary(|a, b| a ^ b)?,
+        BytesOp::And => binary(|a, b| a & b)?,
+        BytesOp::Or => binary(|a, b| a | b)?,
+        BytesOp::Not => a.iter().map(|byte| !byte).collect(),
+        BytesOp::ShiftLeft { bits } => shift_bits(&a, bits as isize, false),
+        BytesOp::ShiftRight { bits } => shift_bits(&a, -(bits as isize), false),
+        BytesOp::RotateLeft { bits } => shift_bits(&a, bits as isize, true),
+        BytesOp::RotateRight { bits } => shift_bits(&a, -(bits as isize), true),
+        BytesOp::Slice { start, end } => {
+            let end = end.unwrap_or(a.len());
+            a.get(start .. end)
+                .ok_or_else(|| {
+                    Error::Unsupported(format!(
+                        "slice {}..{} of {} bytes",
+                        start,
+                        end,
+                        a.len()
+                    ))
+                })?
+                .to_vec()
+        }
+        BytesOp::Concat => {
+            [a.as_slice(), b.as_deref().unwrap_or_default()].concat()
+        }
+        BytesOp::Pad { length, byte, left } => {
+            if a.len() > length {
+                return Err(Error::Unsupported(format!(
+                    "{} bytes is longer than the padded length {}",
+                    a.len(),
+                    length
+                )));
+            }
+            let padding = vec![byte; length - a.len()];
+            if left {
+                [padding, a].concat()
+            } else {
+                [a, padding].concat()
+            }
+        }
+    };
+    output_encoding.encode(&output)
+}
+
+/// Negative offsets shift right, vacated bits are zero unless rotating.
+fn shift_bits(input: &[u8], offset: isize, rotate: bool) -> Vec<u8> {
+    let length = input.len() as isize * 8;
+    let bit = |index: isize| {
+        let index = match rotate {
+            true if length > 0 => index.rem_euclid(length),
+            _ if index < 0 || index >= length => return 0,
+            _ => index,
+        };
+        input[index as usize / 8] >> (7 - index % 8) & 1
+    };
+    (0 .. input.len() as isize)
+        .map(|byte| {
+            (0 .. 8).fold(0u8, |acc, position| {
+                acc << 1 | bit(byte * 8 + position + offset)
+            })
+        })
+        .collect()
+}
+
+/// RFC 8949 section 8.
+pub fn cbor_diagnostic(value: &CborValue) -> Result<String> {
+    Ok(match value {
+        CborValue::Null => "null".to_string(),
+        CborValue::Bool(value) => value.to_string(),
+        CborValue::Integer(value) => i128::from(*value).to_string(),
+        CborValue::Float(value) if value.is_nan() => "NaN".to_string(),
+        CborValue::Float(value) if value.is_infinite() => if *value > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_string(),
+        CborValue::Float(value) => format!("{:?}", value),
+        CborValue::Text(value) => {
+            serde_json::to_string(value).context("serialize text failed")?
+        }
+        CborValue::Bytes(value) => format!("h'{}'", hex_encode(value, false)?),
+        CborValue::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(cbor_diagnostic)
+                .collect::<Result<Vec<_>>>()?
+                .join(", ")
+        ),
+        CborValue::Map(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(key, value)| Ok(format!(
+                    "{}: {}",
+                    cbor_diagnostic(key)?,
+                    cbor_diagnostic(value)?
+                )))
+                .collect::<Result<Vec<_>>>()?
+                .join(", ")
+        ),
+        CborValue::Tag(tag, value) => {
+            format!("{}({})", tag, cbor_diagnostic(value)?)
+        }
+        value => {
+            return Err(Error::Unsupported(format!("cbor value {:?}", value)))
+        }
+    })
+}
+
+pub(crate) fn json_to_cbor(value: &serde_json::Value) -> CborValue {
+    match value {
+        serde_json::Value::Null => CborValue::Null,
+        serde_json::Value::Bool(value) => CborValue::Bool(*value),
+        serde_json::Value::Number(number) => {
+            if let Some(value) = number.as_i64() {
+                CborValue::from(value)
+            } else if let Some(value) = number.as_u64() {
+                CborValue::from(value)
+            } else {
+                CborValue::Float(number.as_f64().unwrap_or_default())
+            }
+        }
+        serde_json::Value::String(value) => CborValue::Text(value.clone()),
+        serde_json::Value::Array(values) => {
+            CborValue::Array(values.iter().map(json_to_cbor).collect())
+        }
+        serde_json::Value::Object(map) => CborValue::Map(
+            map.iter()
+                .map(|(key, value)| {
+                    (CborValue::Text(key.clone()), json_to_cbor(value))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Byte strings become hex strings and tags are dropped.
+pub(crate) fn cbor_to_json(value: &CborValue) -> Result<serde_json::Value> {
+    Ok(match value {
+        CborValue::Null => serde_json::Value::Null,
+        CborValue::Bool(value) => json!(value),
+        CborValue::Integer(value) => {
+            let value = i128::from(*value);
+            if let Ok(value) = i64::try_from(value) {
+                json!(value)
+            } else if let Ok(value) = u64::try_from(value) {
+                json!(value)
+            } else {
+                json!(value.to_string())
+            }
+        }
+        CborValue::Float(value) => Number::from_f64(*value)
+            .map(serde_json::Value::Number)
+            .unwrap_or(json!(value.to_string())),
+        CborValue::Text(value) => json!(value),
+        CborValue::Bytes(value) => json!(TextEncoding::Hex.encode(value)?),
+        CborValue::Array(values) => serde_json::Value::Array(
+            values.iter().map(cbor_to_json).collect::<Result<_>>()?,
+        ),
+        CborValue::Map(entries) => serde_json::Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        CborValue::Text(key) => key.clone(),
+                        key => cbor_to_json(key)?.to_string(),
+                    };
+                    Ok((key, cbor_to_json(value)?))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        CborValue::Tag(_, value) => cbor_to_json(value)?,
+        value => {
+            return Err(Error::Unsupported(format!("cbor value {:?}", value)))
+        }
+    })
+}
+
 pub fn base64_encode(
     input: &[u8],
     unpadded: bool,
@@ -87,9 +381,9 @@ pub fn hex_decode(input: &str, uppercase: bool) -> Result<Vec<u8>> {
         Ok("".as_bytes().to_vec())
     } else {
         Ok(if uppercase {
-            base16ct::upper::decode_vec(input).context("hex encode failed")?
+            base16ct::upper::decode_vec(input).context("hex decode failed")?
         } else {
-            base16ct::lower::decode_vec(input).context("hex encode failed")?
+            base16ct::lower::decode_vec(input).context("hex decode failed")?
         })
     }
 }
@@ -112,7 +406,7 @@ where
     Ok(match encoding {
         KeyFormat::Pem => {
             let key_string = String::from_utf8(input.to_vec())
-                .context("invalid utf-8 key")?;
+                .context("invalid pkcs8 pem private key")?;
             E::from_pkcs8_pem(&key_string)
                 .context("invalid pkcs8 pem private key")?
         }
@@ -132,7 +426,7 @@ where
     Ok(match format {
         KeyFormat::Pem => {
             let key_string = String::from_utf8(input.to_vec())
-                .context("invalid utf-8 key")?;
+                .context("invalid pkcs8 pem public key")?;
             E::from_public_key_pem(&key_string)
                 .context("invalid pkcs8 pem public key")?
         }
@@ -151,12 +445,12 @@ where
     Ok(match format {
         KeyFormat::Pem => input
             .to_pkcs8_pem(base64ct::LineEnding::LF)
-            .context("invalid pkcs8 private key to pem")?
+            .context("export pkcs8 pem private key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => input
             .to_pkcs8_der()
-            .context("invalid pkcs8 private key to der")?
+            .context("export pkcs8 der private key failed")?
             .as_bytes()
             .to_vec(),
     })
@@ -171,11 +465,101 @@ where
     Ok(match encoding {
         KeyFormat::Pem => input
             .to_public_key_pem(base64ct::LineEnding::LF)
-            .context("invalid pkcs8 public key to pem")?
+            .context("export pkcs8 pem public key failed")?
             .into_bytes(),
         KeyFormat::Der => input
             .to_public_key_der()
-            .context("invalid pkcs8 public key to der")?
+            .context("export pkcs8 der public key failed")?
             .to_vec(),
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::{bytes_op, cbor_decode, cbor_encode, BytesOp};
+    use crate::enums::TextEncoding;
+
+    #[test]
+    fn test_cbor_diagnostic() {
+        let decoded = cbor_decode("0101".to_string(), TextEncoding::Hex);
+        assert!(decoded.is_err());
+        let decoded = cbor_decode(
+            "a3616101624142c2406162836161fb3ff8000000000000f97e00".to_string(),
+            TextEncoding::Hex,
+        )
+        .unwrap();
+        assert_eq!(
+            decoded.diagnostic,
+            r#"{"a": 1, "AB": 2(h''), "b": ["a", 1.5, NaN]}"#
+        );
+        assert_eq!(
+            cbor_encode(
+                r#"{"a": [1, -1, "x"]}"#.to_string(),
+                TextEncoding::Hex
+            )
+            .unwrap(),
+            "a161618301206178"
+        );
+    }
+
+    #[test]
+    fn test_decode_lenient() {
+        let wrapped = "AAEC\nAwQF\r\n  Bgc=\n";
+        assert!(TextEncoding::Base64.decode(wrapped).is_err());
+        assert_eq!(TextEncoding::Base64.decode_lenient(wrapped).unwrap(), [
+            0, 1, 2, 3, 4, 5, 6, 7
+        ]);
+        assert_eq!(TextEncoding::Hex.decode_lenient("00 01\n02").unwrap(), [
+            0, 1, 2
+        ]);
+        assert_eq!(TextEncoding::Utf8.decode_lenient(" a\n").unwrap(), b" a\n");
+    }
+
+    fn op(a: &str, b: Option<&str>, op: BytesOp) -> String {
+        bytes_op(
+            a.to_string(),
+            TextEncoding::Hex,
+            b.map(String::from),
+            None,
+            op,
+            TextEncoding::Hex,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bytes_op() {
+        assert_eq!(op("0f0f", Some("ff00"), BytesOp::Xor), "f00f");
+        assert_eq!(op("0f0f", Some("3c"), BytesOp::And), "0c0c");
+        assert_eq!(op("0f0f", None, BytesOp::Not), "f0f0");
+        assert_eq!(op("8001", None, BytesOp::ShiftLeft { bits: 4 }), "0010");
+        assert_eq!(op("8001", None, BytesOp::RotateLeft { bits: 1 }), "0003");
+        assert_eq!(op("8001", None, BytesOp::RotateRight { bits: 9 }), "00c0");
+        assert_eq!(op("8001", None, BytesOp::ShiftRight { bits: 20 }), "0000");
+        assert_eq!(
+            op("00112233", None, BytesOp::Slice {
+                start: 1,
+                end: Some(3)
+            }),
+            "1122"
+        );
+        assert_eq!(
+            op("01", None, BytesOp::Pad {
+                length: 3,
+                byte: 0,
+                left: true
+            }),
+            "000001"
+        );
+        assert_eq!(op("01", Some("02"), BytesOp::Concat), "0102");
+        assert!(bytes_op(
+            "0102".to_string(),
+            TextEncoding::Hex,
+            Some("010203".to_string()),
+            None,
+            BytesOp::Or,
+            TextEncoding::Hex,
+        )
+        .is_err());
+    }
+}
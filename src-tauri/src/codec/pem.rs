@@ -0,0 +1,321 @@
+//! Turns what users paste as pem into what strict RFC 7468 parsers accept.
+
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    errors::{Error, Result},
+    x509::{read_all, read_tlv, Tlv},
+};
+
+const LINE_WIDTH: usize = 64;
+const PKCS7: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07];
+/// RFC 7468 section 5.3 and openssl spellings of the same labels.
+const LEGACY_LABELS: &[(&str, &str)] = &[
+    ("X509 CERTIFICATE", "CERTIFICATE"),
+    ("X.509 CERTIFICATE", "CERTIFICATE"),
+    ("NEW CERTIFICATE REQUEST", "CERTIFICATE REQUEST"),
+    ("CRL", "X509 CRL"),
+    ("PKCS #7 SIGNED DATA", "PKCS7"),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PemLineEnding {
+    Lf,
+    Crlf,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PemBlock {
+    pub label: String,
+    /// the label as pasted, when it was replaced
+    pub original_label: Option<String>,
+    pub pem: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizedPem {
+    /// every block, one after the other
+    pub pem: String,
+    pub blocks: Vec<PemBlock>,
+    pub notes: Vec<String>,
+}
+
+/// `headers` are the RFC 1421 ones of legacy encrypted keys.
+struct RawBlock<'a> {
+    label: &'a str,
+    headers: Vec<&'a str>,
+    body: String,
+}
+
+#[tauri::command]
+pub fn normalize_pem(
+    input: String,
+    line_ending: Option<PemLineEnding>,
+) -> Result<NormalizedPem> {
+    info!("normalize pem, size: {}", input.len());
+    let newline = match line_ending.unwrap_or(PemLineEnding::Lf) {
+        PemLineEnding::Lf => "\n",
+        PemLineEnding::Crlf => "\r\n",
+    };
+    let input = input.replace("\\r\\n", "\n").replace("\\n", "\n");
+    let mut notes = vec![];
+    let (raw_blocks, dropped) = blocks(&input);
+    if dropped > 0 && !raw_blocks.is_empty() {
+        notes.push(format!("dropped {} lines around the blocks", dropped));
+    }
+    let raw_blocks = if raw_blocks.is_empty() {
+        // a bare body, the label comes from its content
+        let body = input.split_whitespace().collect::<String>();
+        if body.is_empty() {
+            return Err(Error::Unsupported("no pem block".to_string()));
+        }
+        notes.push("no pem armor, the input is taken as a body".to_string());
+        vec![RawBlock {
+            label: "",
+            headers: vec![],
+            body,
+        }]
+    } else {
+        raw_blocks
+    };
+
+    let mut blocks = vec![];
+    for (index, block) in raw_blocks.into_iter().enumerate() {
+        let Some(der) = decode_body(&block.body) else {
+            notes.push(format!("block {} is not base64, skipped", index + 1));
+            continue;
+        };
+        let pasted = block.label.trim().to_ascii_uppercase();
+        let legacy = LEGACY_LABELS
+            .iter()
+            .find(|(legacy, _)| *legacy == pasted)
+            .map(|(_, label)| label.to_string());
+        // encrypted legacy keys hide their content, trust their label
+        let sniffed = if block.headers.is_empty() {
+            sniff_label(&der)
+        } else {
+            None
+        };
+        let label = match (sniffed, legacy) {
+            (Some(sniffed), _) => sniffed.to_string(),
+            (None, Some(legacy)) => legacy,
+            (None, None) if pasted.is_empty() => {
+                notes.push(format!(
+                    "block {} has no label and unrecognized content, skipped",
+                    index + 1
+                ));
+                continue;
+            }
+            (None, None) => pasted.clone(),
+        };
+        let original_label = (label != pasted).then_some(pasted);
+        if let Some(original) = original_label.as_deref() {
+            if !original.is_empty() {
+                notes.push(format!(
+                    "block {} relabeled from {} to {}",
+                    index + 1,
+                    original,
+                    label
+                ));
+            }
+        }
+        blocks.push(PemBlock {
+            pem: encode(&label, &block.headers, &der, newline),
+            label,
+            original_label,
+        });
+    }
+    if blocks.is_empty() {
+        return Err(Error::Unsupported("no usable pem block".to_string()));
+    }
+    Ok(NormalizedPem {
+        pem: blocks.iter().map(|block| block.pem.as_str()).collect(),
+        blocks,
+        notes,
+    })
+}
+
+/// From the shape of the der alone.
+pub(crate) fn sniff_label(der: &[u8]) -> Option<&'static str> {
+    let (outer, rest) = read_tlv(der).ok()?;
+    if outer.tag != 0x30 || !rest.is_empty() {
+        return None;
+    }
+    let elements = read_all(outer.content).ok()?;
+    let tags = elements.iter().map(|e| e.tag).collect::<Vec<_>>();
+    match &tags[..] {
+        // signed structures: tbs, algorithm, signature
+        [0x30, 0x30, 0x03] => signed_label(&elements[0]),
+        [0x02, 0x30, 0x04, ..] => Some("PRIVATE KEY"),
+        [0x30, 0x04] => Some("ENCRYPTED PRIVATE KEY"),
+        [0x30, 0x03] => Some("PUBLIC KEY"),
+        [0x02, 0x02] => Some("RSA PUBLIC KEY"),
+        [0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, ..] => {
+            Some("RSA PRIVATE KEY")
+        }
+        [0x02, 0x04, ..] => Some("EC PRIVATE KEY"),
+        [0x06, 0xa0, ..] if elements[0].content.starts_with(PKCS7) => {
+            Some("PKCS7")
+        }
+        _ => None,
+    }
+}
+
+fn signed_label(tbs: &Tlv) -> Option<&'static str> {
+    let elements = read_all(tbs.content).ok()?;
+    let is_time = |tlv: &Tlv| matches!(tlv.tag, 0x17 | 0x18);
+    if elements.iter().any(|element| {
+        element.tag == 0x30
+            && read_all(element.content).is_ok_and(|times| {
+                times.len() == 2 && times.iter().all(is_time)
+            })
+    }) {
+        return Some("CERTIFICATE");
+    }
+    if elements.iter().any(is_time) {
+        return Some("X509 CRL");
+    }
+    match (elements.first(), elements.last()) {
+        (Some(version), Some(attributes))
+            if version.tag == 0x02 && attributes.tag == 0xa0 =>
+        {
+            Some("CERTIFICATE REQUEST")
+        }
+        _ => None,
+    }
+}
+
+/// Also counts the non blank lines outside the blocks.
+fn blocks(input: &str) -> (Vec<RawBlock>, usize) {
+    let mut blocks = vec![];
+    let mut dropped = 0;
+    let mut current: Option<RawBlock> = None;
+    let mut in_headers = false;
+    for line in input.lines().map(str::trim) {
+        if let Some(label) = boundary(line, "BEGIN") {
+            blocks.extend(current.take());
+            current = Some(RawBlock {
+                label,
+                headers: vec![],
+                body: String::new(),
+            });
+            in_headers = true;
+            continue;
+        }
+        let Some(block) = current.as_mut() else {
+            dropped += usize::from(!line.is_empty());
+            continue;
+        };
+        if boundary(line, "END").is_some() {
+            blocks.extend(current.take());
+            continue;
+        }
+        if in_headers {
+            // RFC 1421 headers, continuation lines start with whitespace
+            // which the trim above removed, so anything with a colon
+            if line.contains(':') {
+                block.headers.push(line);
+                continue;
+            }
+            in_headers = false;
+            if line.is_empty() {
+                continue;
+            }
+        }
+        block.body.extend(line.split_whitespace());
+    }
+    // a block missing its end line is still worth a try
+    blocks.extend(current);
+    (blocks, dropped)
+}
+
+fn boundary<'a>(line: &'a str, kind: &str) -> Option<&'a str> {
+    let line = line.trim_matches('-');
+    let label = line.strip_prefix(kind)?;
+    (label.is_empty() || label.starts_with(' ')).then(|| label.trim())
+}
+
+/// Standard base64, missing padding and url safe characters tolerated.
+fn decode_body(body: &str) -> Option<Vec<u8>> {
+    let mut body = body
+        .trim_end_matches('=')
+        .replace('-', "+")
+        .replace('_', "/");
+    while body.len() % 4 != 0 {
+        body.push('=');
+    }
+    Base64::decode_vec(&body).ok().filter(|der| !der.is_empty())
+}
+
+fn encode(label: &str, headers: &[&str], der: &[u8], newline: &str) -> String {
+    let mut pem = format!("-----BEGIN {}-----{}", label, newline);
+    if !headers.is_empty() {
+        for header in headers {
+            pem.push_str(header);
+            pem.push_str(newline);
+        }
+        pem.push_str(newline);
+    }
+    let body = Base64::encode_string(der);
+    for chunk in body.as_bytes().chunks(LINE_WIDTH) {
+        pem.push_str(&String::from_utf8_lossy(chunk));
+        pem.push_str(newline);
+    }
+    pem.push_str(&format!("-----END {}-----{}", label, newline));
+    pem
+}
+
+#[cfg(test)]
+mod test {
+    use super::{normalize_pem, PemLineEnding};
+
+    const CHAIN: &str = include_str!("../../tests/vectors/chain.pem");
+
+    #[test]
+    fn test_normalize_pem() {
+        let leaf = CHAIN
+            .split_inclusive("-----END CERTIFICATE-----\n")
+            .next()
+            .unwrap();
+        let body = leaf
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+
+        // a json escaped certificate in a private key block, with a comment
+        let pasted = format!(
+            "here is the key you asked for:\n  -----BEGIN PRIVATE \
+             KEY-----\\n{}\\n-----END PRIVATE KEY-----\nthanks",
+            body
+        );
+        let normalized = normalize_pem(pasted, None).unwrap();
+        assert_eq!(normalized.pem, leaf);
+        assert_eq!(normalized.blocks[0].label, "CERTIFICATE");
+        assert_eq!(
+            normalized.blocks[0].original_label.as_deref(),
+            Some("PRIVATE KEY")
+        );
+        assert_eq!(normalized.notes, [
+            "dropped 2 lines around the blocks",
+            "block 1 relabeled from PRIVATE KEY to CERTIFICATE"
+        ]);
+
+        // the bare body in one line, written back with crlf
+        let normalized =
+            normalize_pem(body.clone(), Some(PemLineEnding::Crlf)).unwrap();
+        assert_eq!(normalized.pem, leaf.replace('\n', "\r\n"));
+
+        // the whole chain passes through untouched
+        let normalized = normalize_pem(CHAIN.to_string(), None).unwrap();
+        assert_eq!(normalized.pem, CHAIN);
+        assert_eq!(normalized.blocks.len(), 3);
+        assert!(normalized.notes.is_empty());
+
+        assert!(normalize_pem("not pem at all!".to_string(), None).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::anyhow;
+use coset::{
+    cbor::value::Value, iana, CborSerializable, CoseKey, KeyType, Label,
+};
+
+use crate::errors::{Error, Result};
+
+pub mod cwt;
+pub mod encrypt0;
+pub mod key;
+pub mod sign1;
+
+pub(crate) fn parse_cose_key(key: &[u8]) -> Result<CoseKey> {
+    Ok(CoseKey::from_slice(key)
+        .map_err(|err| anyhow!("invalid cose key: {:?}", err))?)
+}
+
+pub(crate) fn key_param(key: &CoseKey, label: i64) -> Option<&Value> {
+    key.params
+        .iter()
+        .find(|(l, _)| *l == Label::Int(label))
+        .map(|(_, value)| value)
+}
+
+pub(crate) fn key_bytes(key: &CoseKey, label: i64) -> Result<Vec<u8>> {
+    match key_param(key, label) {
+        Some(Value::Bytes(bytes)) => Ok(bytes.clone()),
+        _ => Err(Error::Unsupported(format!(
+            "cose key without bytes parameter {}",
+            label
+        ))),
+    }
+}
+
+pub(crate) fn key_curve(key: &CoseKey) -> Result<iana::EllipticCurve> {
+    let crv = match key_param(key, iana::Ec2KeyParameter::Crv as i64) {
+        Some(Value::Integer(crv)) => i128::from(*crv),
+        _ => {
+            return Err(Error::Unsupported(
+                "cose key without curve".to_string(),
+            ))
+        }
+    };
+    [
+        iana::EllipticCurve::P_256,
+        iana::EllipticCurve::P_384,
+        iana::EllipticCurve::P_521,
+        iana::EllipticCurve::X25519,
+        iana::EllipticCurve::Ed25519,
+        iana::EllipticCurve::Secp256k1,
+    ]
+    .into_iter()
+    .find(|curve| *curve as i128 == crv)
+    .ok_or(Error::Unsupported(format!("cose curve {}", crv)))
+}
+
+pub(crate) fn is_key_type(key: &CoseKey, kty: iana::KeyType) -> bool {
+    key.kty == KeyType::Assigned(kty)
+}
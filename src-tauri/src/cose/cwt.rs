@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Context};
+use coset::{
+    cbor::value::Value,
+    cwt::{ClaimName, ClaimsSet, Timestamp},
+    CborSerializable, RegisteredLabelWithPrivate,
+};
+use serde_json::{json, Map};
+use tracing::info;
+
+use crate::{
+    codec::{cbor_to_json, json_to_cbor},
+    enums::TextEncoding,
+    errors::{Error, Result},
+};
+
+/// Registered claims use their JWT names, `cti` is hex.
+#[tauri::command]
+pub fn cwt_encode(claims: String, encoding: TextEncoding) -> Result<String> {
+    let claims: serde_json::Value =
+        serde_json::from_str(&claims).context("invalid json claims")?;
+    let serde_json::Value::Object(claims) = claims else {
+        return Err(Error::Unsupported("non object cwt claims".to_string()));
+    };
+    info!("cwt encode, {} claims", claims.len());
+    let mut claims_set = ClaimsSet::default();
+    for (name, value) in claims {
+        let text = || {
+            value
+                .as_str()
+                .map(String::from)
+                .ok_or(anyhow!("claim `{}` must be a string", name))
+        };
+        let timestamp = || -> Result<Timestamp> {
+            match value.as_i64() {
+                Some(seconds) => Ok(Timestamp::WholeSeconds(seconds)),
+                None => value
+                    .as_f64()
+                    .map(Timestamp::FractionalSeconds)
+                    .ok_or(anyhow!("claim `{}` must be a number", name).into()),
+            }
+        };
+        match name.as_str() {
+            "iss" => claims_set.issuer = Some(text()?),
+            "sub" => claims_set.subject = Some(text()?),
+            "aud" => claims_set.audience = Some(text()?),
+            "exp" => claims_set.expiration_time = Some(timestamp()?),
+            "nbf" => claims_set.not_before = Some(timestamp()?),
+            "iat" => claims_set.issued_at = Some(timestamp()?),
+            "cti" => {
+                claims_set.cwt_id = Some(TextEncoding::Hex.decode(&text()?)?)
+            }
+            _ => {
+                let claim = match name.parse::<i64>() {
+                    Ok(label) => ClaimName::PrivateUse(label),
+                    Err(_) => ClaimName::Text(name.clone()),
+                };
+                claims_set.rest.push((claim, json_to_cbor(&value)));
+            }
+        }
+    }
+    encoding.encode(
+        &claims_set
+            .to_vec()
+            .map_err(|err| anyhow!("serialize cwt claims failed: {:?}", err))?,
+    )
+}
+
+#[tauri::command]
+pub fn cwt_decode(input: String, encoding: TextEncoding) -> Result<String> {
+    let claims_set = ClaimsSet::from_slice(&encoding.decode(&input)?)
+        .map_err(|err| anyhow!("invalid cwt claims: {:?}", err))?;
+    let timestamp = |timestamp: &Timestamp| match timestamp {
+        Timestamp::WholeSeconds(seconds) => json!(seconds),
+        Timestamp::FractionalSeconds(seconds) => json!(seconds),
+    };
+    let mut claims = Map::new();
+    if let Some(iss) = claims_set.issuer {
+        claims.insert("iss".to_string(), json!(iss));
+    }
+    if let Some(sub) = claims_set.subject {
+        claims.insert("sub".to_string(), json!(sub));
+    }
+    if let Some(aud) = claims_set.audience {
+        claims.insert("aud".to_string(), json!(aud));
+    }
+    if let Some(exp) = &claims_set.expiration_time {
+        claims.insert("exp".to_string(), timestamp(exp));
+    }
+    if let Some(nbf) = &claims_set.not_before {
+        claims.insert("nbf".to_string(), timestamp(nbf));
+    }
+    if let Some(iat) = &claims_set.issued_at {
+        claims.insert("iat".to_string(), timestamp(iat));
+    }
+    if let Some(cti) = &claims_set.cwt_id {
+        claims.insert("cti".to_string(), json!(TextEncoding::Hex.encode(cti)?));
+    }
+    for (name, value) in &claims_set.rest {
+        let name = match name {
+            RegisteredLabelWithPrivate::PrivateUse(label) => label.to_string(),
+            RegisteredLabelWithPrivate::Assigned(label) => {
+                format!("{:?}", label)
+            }
+            RegisteredLabelWithPrivate::Text(name) => name.clone(),
+        };
+        claims.insert(name, cbor_to_json(value)?);
+    }
+    Ok(serde_json::to_string_pretty(&claims)
+        .context("value to string failed")?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cwt_decode, cwt_encode};
+    use crate::enums::TextEncoding;
+
+    #[test]
+    fn test_cwt_claims() {
+        // RFC 8392 appendix A.1
+        let claims = cwt_decode(
+            "a70175636f61703a2f2f61732e6578616d706c652e636f6d02656572696b770\
+             37818636f61703a2f2f6c696768742e6578616d706c652e636f6d041a5612aeb\
+             0051a5610d9f0061a5610d9f007420b71"
+                .to_string(),
+            TextEncoding::Hex,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&claims).unwrap();
+        assert_eq!(value["iss"], "coap://as.example.com");
+        assert_eq!(value["exp"], 1444064944);
+        assert_eq!(value["cti"], "0b71");
+        let encoded = cwt_encode(claims, TextEncoding::Hex).unwrap();
+        assert_eq!(
+            cwt_decode(encoded, TextEncoding::Hex).unwrap(),
+            serde_json::to_string_pretty(&value).unwrap()
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use aes_gcm::{
+    aead::{Aead, Payload},
+    aes::Aes192,
+    Aes128Gcm, Aes256Gcm, AesGcm, KeyInit, Nonce,
+};
+use anyhow::anyhow;
+use coset::{
+    iana, Algorithm, CborSerializable, CoseEncrypt0, CoseEncrypt0Builder,
+    HeaderBuilder, TaggedCborSerializable,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{key_bytes, parse_cose_key, sign1::decode_optional};
+use crate::{
+    add_encryption_trait_impl,
+    crypto::EncryptionDto,
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng,
+};
+
+type Aes192Gcm = AesGcm<Aes192, aes_gcm::aead::consts::U12>;
+
+add_encryption_trait_impl!(CoseEncrypt0Dto {
+    aad: Option<String>,
+    aad_encoding: Option<TextEncoding>,
+    tagged: Option<bool>,
+    for_encryption: bool
+});
+
+/// The iv travels in the unprotected header.
+#[tauri::command]
+pub fn cose_encrypt0(data: CoseEncrypt0Dto) -> Result<String> {
+    let key = parse_cose_key(&data.get_key()?)?;
+    let secret = key_bytes(&key, iana::SymmetricKeyParameter::K as i64)?;
+    let input = data.get_input()?;
+    let aad = decode_optional(&data.aad, data.aad_encoding)?;
+    let output = if data.for_encryption {
+        let algorithm = match (key.alg.as_ref(), secret.len()) {
+            (Some(Algorithm::Assigned(algorithm)), _) => *algorithm,
+            (None, 16) => iana::Algorithm::A128GCM,
+            (None, 24) => iana::Algorithm::A192GCM,
+            (None, 32) => iana::Algorithm::A256GCM,
+            (_, size) => {
+                return Err(Error::Unsupported(format!(
+                    "cose encrypt0 key size {}",
+                    size
+                )))
+            }
+        };
+        info!("cose encrypt0, algorithm: {:?}", algorithm);
+        let mut iv = vec![0u8; 12];
+        rng::rng().fill_bytes(&mut iv);
+        let encrypt0 = CoseEncrypt0Builder::new()
+            .protected(
+                HeaderBuilder::new()
+                    .algorithm(algorithm)
+                    .key_id(key.key_id.clone())
+                    .build(),
+            )
+            .unprotected(HeaderBuilder::new().iv(iv.clone()).build())
+            .try_create_ciphertext(&input, &aad, |plaintext, aad| {
+                gcm(algorithm, &secret, &iv, plaintext, aad, true)
+            })?
+            .build();
+        if data.tagged.unwrap_or(false) {
+            encrypt0.to_tagged_vec()
+        } else {
+            encrypt0.to_vec()
+        }
+        .map_err(|err| anyhow!("serialize cose encrypt0 failed: {:?}", err))?
+    } else {
+        let encrypt0 = CoseEncrypt0::from_tagged_slice(&input)
+            .or_else(|_| CoseEncrypt0::from_slice(&input))
+            .map_err(|err| anyhow!("invalid cose encrypt0: {:?}", err))?;
+        let algorithm = match &encrypt0.protected.header.alg {
+            Some(Algorithm::Assigned(algorithm)) => *algorithm,
+            _ => {
+                return Err(Error::Unsupported(
+                    "cose encrypt0 without algorithm".to_string(),
+                ))
+            }
+        };
+        info!("cose decrypt0, algorithm: {:?}", algorithm);
+        let iv = encrypt0.unprotected.iv.clone();
+        encrypt0.decrypt(&aad, |ciphertext, aad| {
+            gcm(algorithm, &secret, &iv, ciphertext, aad, false)
+        })?
+    };
+    data.get_output_encoding().encode(&output)
+}
+
+fn gcm(
+    algorithm: iana::Algorithm,
+    key: &[u8],
+    iv: &[u8],
+    msg: &[u8],
+    aad: &[u8],
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    if iv.len() != 12 {
+        return Err(Error::Unsupported(format!("cose iv size {}", iv.len())));
+    }
+    let nonce = Nonce::from_slice(iv);
+    let payload = Payload { msg, aad };
+    let invalid_key = |_| anyhow!("invalid aes key for {:?}", algorithm);
+    let output = match (algorithm, for_encryption) {
+        (iana::Algorithm::A128GCM, true) => Aes128Gcm::new_from_slice(key)
+            .map_err(invalid_key)?
+            .encrypt(nonce, payload),
+        (iana::Algorithm::A128GCM, false) => Aes128Gcm::new_from_slice(key)
+            .map_err(invalid_key)?
+            .decrypt(nonce, payload),
+        (iana::Algorithm::A192GCM, true) => Aes192Gcm::new_from_slice(key)
+            .map_err(invalid_key)?
+            .encrypt(nonce, payload),
+        (iana::Algorithm::A192GCM, false) => Aes192Gcm::new_from_slice(key)
+            .map_err(invalid_key)?
+            .decrypt(nonce, payload),
+        (iana::Algorithm::A256GCM, true) => Aes256Gcm::new_from_slice(key)
+            .map_err(invalid_key)?
+            .encrypt(nonce, payload),
+        (iana::Algorithm::A256GCM, false) => Aes256Gcm::new_from_slice(key)
+            .map_err(invalid_key)?
+            .decrypt(nonce, payload),
+        (algorithm, _) => {
+            return Err(Error::Unsupported(format!(
+                "cose encryption algorithm {:?}",
+                algorithm
+            )))
+        }
+    };
+    Ok(output.map_err(|_| anyhow!("cose aead operation failed"))?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cose_encrypt0, CoseEncrypt0Dto};
+    use crate::{cose::key::jwk_to_cose_key, enums::TextEncoding};
+
+    #[test]
+    fn test_cose_encrypt0() {
+        let key = jwk_to_cose_key(
+            r#"{"kty":"oct","k":"AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8"}"#
+                .to_string(),
+            TextEncoding::Hex,
+        )
+        .unwrap();
+        let dto = |input: String, for_encryption| CoseEncrypt0Dto {
+            input,
+            input_encoding: if for_encryption {
+                TextEncoding::Utf8
+            } else {
+                TextEncoding::Hex
+            },
+            key: key.clone(),
+            key_encoding: TextEncoding::Hex,
+            input_path: None,
+            key_path: None,
+            output_encoding: if for_encryption {
+                TextEncoding::Hex
+            } else {
+                TextEncoding::Utf8
+            },
+            aad: Some("aad".to_string()),
+            aad_encoding: Some(TextEncoding::Utf8),
+            tagged: Some(true),
+            for_encryption,
+        };
+        let ciphertext = cose_encrypt0(dto("plaintext".to_string(), true)).unwrap();
+        assert_eq!(
+            "plaintext",
+            cose_encrypt0(dto(ciphertext, false)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Context};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use coset::{
+    cbor::value::Value, iana, Algorithm, CborSerializable, CoseKey, KeyType,
+    Label,
+};
+use serde_json::json;
+use tracing::info;
+
+use super::{is_key_type, key_bytes, key_curve, parse_cose_key};
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    jwt::{jwk::generate_jwk_inner, JwkeyAlgorithm},
+};
+
+const ALGORITHMS: [(&str, iana::Algorithm); 12] = [
+    ("ES256", iana::Algorithm::ES256),
+    ("ES384", iana::Algorithm::ES384),
+    ("ES512", iana::Algorithm::ES512),
+    ("ES521", iana::Algorithm::ES512),
+    ("ES256K", iana::Algorithm::ES256K),
+    ("EdDSA", iana::Algorithm::EdDSA),
+    ("A128GCM", iana::Algorithm::A128GCM),
+    ("A192GCM", iana::Algorithm::A192GCM),
+    ("A256GCM", iana::Algorithm::A256GCM),
+    ("HS256", iana::Algorithm::HMAC_256_256),
+    ("HS384", iana::Algorithm::HMAC_384_384),
+    ("HS512", iana::Algorithm::HMAC_512_512),
+];
+
+const CURVES: [(&str, iana::EllipticCurve); 6] = [
+    ("P-256", iana::EllipticCurve::P_256),
+    ("P-384", iana::EllipticCurve::P_384),
+    ("P-521", iana::EllipticCurve::P_521),
+    ("secp256k1", iana::EllipticCurve::Secp256k1),
+    ("Ed25519", iana::EllipticCurve::Ed25519),
+    ("X25519", iana::EllipticCurve::X25519),
+];
+
+#[tauri::command]
+pub async fn generate_cose_key(
+    algorithm: JwkeyAlgorithm,
+    key_id: Option<String>,
+    encoding: TextEncoding,
+) -> Result<String> {
+    info!("generate cose key, algorithm: {:?}", algorithm);
+    let mut jwk = generate_jwk_inner(algorithm).await?;
+    jwk["alg"] = json!(algorithm);
+    if let Some(key_id) = key_id {
+        jwk["kid"] = json!(key_id);
+    }
+    encoding.encode(&jwk_to_cose_key_inner(&jwk)?.to_vec().map_err(
+        |err| anyhow!("serialize cose key failed: {:?}", err),
+    )?)
+}
+
+#[tauri::command]
+pub fn jwk_to_cose_key(jwk: String, encoding: TextEncoding) -> Result<String> {
+    let jwk: serde_json::Value =
+        serde_json::from_str(&jwk).context("invalid jwk")?;
+    encoding.encode(&jwk_to_cose_key_inner(&jwk)?.to_vec().map_err(
+        |err| anyhow!("serialize cose key failed: {:?}", err),
+    )?)
+}
+
+#[tauri::command]
+pub fn cose_key_to_jwk(key: String, encoding: TextEncoding) -> Result<String> {
+    let key = parse_cose_key(&encoding.decode(&key)?)?;
+    let b64 = |label: i64| {
+        key_bytes(&key, label)
+            .map(|bytes| Base64UrlUnpadded::encode_string(&bytes))
+    };
+    let curve_name = || {
+        key_curve(&key).and_then(|curve| {
+            CURVES
+                .iter()
+                .find(|(_, c)| *c == curve)
+                .map(|(name, _)| *name)
+                .ok_or(Error::Unsupported(format!("jwk curve {:?}", curve)))
+        })
+    };
+    let mut jwk = if is_key_type(&key, iana::KeyType::EC2) {
+        json!({
+            "kty": "EC",
+            "crv": curve_name()?,
+            "x": b64(iana::Ec2KeyParameter::X as i64)?,
+            "y": b64(iana::Ec2KeyParameter::Y as i64)?,
+        })
+    } else if is_key_type(&key, iana::KeyType::OKP) {
+        json!({
+            "kty": "OKP",
+            "crv": curve_name()?,
+            "x": b64(iana::OkpKeyParameter::X as i64)?,
+        })
+    } else if is_key_type(&key, iana::KeyType::Symmetric) {
+        json!({
+            "kty": "oct",
+            "k": b64(iana::SymmetricKeyParameter::K as i64)?,
+        })
+    } else {
+        return Err(Error::Unsupported(format!("cose key type {:?}", key.kty)));
+    };
+    // Ec2KeyParameter::D and OkpKeyParameter::D share the same label
+    if let Ok(d) = b64(iana::Ec2KeyParameter::D as i64)
+        && !is_key_type(&key, iana::KeyType::Symmetric)
+    {
+        jwk["d"] = json!(d);
+    }
+    if !key.key_id.is_empty() {
+        jwk["kid"] = json!(String::from_utf8_lossy(&key.key_id));
+    }
+    if let Some(Algorithm::Assigned(alg)) = key.alg
+        && let Some((name, _)) = ALGORITHMS.iter().find(|(_, a)| *a == alg)
+    {
+        jwk["alg"] = json!(name);
+    }
+    Ok(serde_json::to_string_pretty(&jwk).context("value to string failed")?)
+}
+
+pub(crate) fn jwk_to_cose_key_inner(jwk: &serde_json::Value) -> Result<CoseKey> {
+    let field = |name: &str| -> Result<Vec<u8>> {
+        let value = jwk[name]
+            .as_str()
+            .ok_or(anyhow!("jwk field `{}` is missing", name))?;
+        Ok(Base64UrlUnpadded::decode_vec(value)
+            .map_err(|_| anyhow!("jwk field `{}` is not base64url", name))?)
+    };
+    let curve = || -> Result<iana::EllipticCurve> {
+        let crv = jwk["crv"].as_str().unwrap_or_default();
+        CURVES
+            .iter()
+            .find(|(name, _)| *name == crv)
+            .map(|(_, curve)| *curve)
+            .ok_or(Error::Unsupported(format!("jwk curve {}", crv)))
+    };
+    let bytes = |label: i64, value: Vec<u8>| (Label::Int(label), Value::Bytes(value));
+    let (kty, mut params) = match jwk["kty"].as_str().unwrap_or_default() {
+        "EC" => (
+            iana::KeyType::EC2,
+            vec![
+                (
+                    Label::Int(iana::Ec2KeyParameter::Crv as i64),
+                    Value::from(curve()? as u64),
+                ),
+                bytes(iana::Ec2KeyParameter::X as i64, field("x")?),
+                bytes(iana::Ec2KeyParameter::Y as i64, field("y")?),
+            ],
+        ),
+        "OKP" => (
+            iana::KeyType::OKP,
+            vec![
+                (
+                    Label::Int(iana::OkpKeyParameter::Crv as i64),
+                    Value::from(curve()? as u64),
+                ),
+                bytes(iana::OkpKeyParameter::X as i64, field("x")?),
+            ],
+        ),
+        "oct" => (
+            iana::KeyType::Symmetric,
+            vec![bytes(iana::SymmetricKeyParameter::K as i64, field("k")?)],
+        ),
+        kty => return Err(Error::Unsupported(format!("jwk key type {}", kty))),
+    };
+    if kty != iana::KeyType::Symmetric
+        && let Ok(d) = field("d")
+    {
+        params.push(bytes(iana::Ec2KeyParameter::D as i64, d));
+    }
+    let alg = jwk["alg"].as_str().and_then(|alg| {
+        ALGORITHMS
+            .iter()
+            .find(|(name, _)| *name == alg)
+            .map(|(_, alg)| Algorithm::Assigned(*alg))
+    });
+    Ok(CoseKey {
+        kty: KeyType::Assigned(kty),
+        key_id: jwk["kid"]
+            .as_str()
+            .map(|kid| kid.as_bytes().to_vec())
+            .unwrap_or_default(),
+        alg,
+        params,
+        ..Default::default()
+    })
+}
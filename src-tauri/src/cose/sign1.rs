@@ -0,0 +1,321 @@
+use anyhow::anyhow;
+use coset::{
+    iana, Algorithm, CborSerializable, CoseKey, CoseSign1, CoseSign1Builder,
+    HeaderBuilder, TaggedCborSerializable,
+};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{is_key_type, key_bytes, key_curve, parse_cose_key};
+use crate::{
+    add_encryption_trait_impl,
+    crypto::EncryptionDto,
+    enums::TextEncoding,
+    errors::{Error, Result},
+    jwt::JwkeyUsage,
+    vault,
+};
+
+add_encryption_trait_impl!(CoseSign1Dto {
+    aad: Option<String>,
+    aad_encoding: Option<TextEncoding>,
+    detached: Option<bool>,
+    tagged: Option<bool>
+});
+
+add_encryption_trait_impl!(CoseVerify1Dto {
+    aad: Option<String>,
+    aad_encoding: Option<TextEncoding>,
+    payload: Option<String>,
+    payload_encoding: Option<TextEncoding>
+});
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CoseVerification {
+    pub valid: bool,
+    pub algorithm: Option<String>,
+    pub key_id: Option<String>,
+    pub payload: Option<String>,
+}
+
+/// `key` is a serialized COSE_Key holding the private part.
+#[tauri::command]
+pub fn cose_sign1(data: CoseSign1Dto) -> Result<String> {
+    let key_bytes = data.get_key()?;
+    let key = parse_cose_key(&key_bytes)?;
+    let payload = data.get_input()?;
+    let aad = decode_optional(&data.aad, data.aad_encoding)?;
+    let algorithm = signature_algorithm(&key)?;
+    info!("cose sign1, algorithm: {:?}", algorithm);
+    vault::enforce_policy(
+        &key_bytes,
+        JwkeyUsage::Signature,
+        &format!("{:?}", algorithm),
+    )?;
+    let protected = HeaderBuilder::new()
+        .algorithm(algorithm)
+        .key_id(key.key_id.clone())
+        .build();
+    let builder = CoseSign1Builder::new().protected(protected);
+    let signer = |data: &[u8]| sign(&key, algorithm, data);
+    let sign1 = if data.detached.unwrap_or(false) {
+        builder.try_create_detached_signature(&payload, &aad, signer)?
+    } else {
+        builder
+            .payload(payload)
+            .try_create_signature(&aad, signer)?
+    }
+    .build();
+    let output = if data.tagged.unwrap_or(false) {
+        sign1.to_tagged_vec()
+    } else {
+        sign1.to_vec()
+    }
+    .map_err(|err| anyhow!("serialize cose sign1 failed: {:?}", err))?;
+    data.get_output_encoding().encode(&output)
+}
+
+/// `key` is a serialized COSE_Key, the embedded payload comes back.
+#[tauri::command]
+pub fn cose_verify1(data: CoseVerify1Dto) -> Result<CoseVerification> {
+    let key = parse_cose_key(&data.get_key()?)?;
+    let message = data.get_input()?;
+    let aad = decode_optional(&data.aad, data.aad_encoding)?;
+    let sign1 = CoseSign1::from_tagged_slice(&message)
+        .or_else(|_| CoseSign1::from_slice(&message))
+        .map_err(|err| anyhow!("invalid cose sign1: {:?}", err))?;
+    let algorithm = match &sign1.protected.header.alg {
+        Some(Algorithm::Assigned(algorithm)) => *algorithm,
+        _ => signature_algorithm(&key)?,
+    };
+    let verifier = |signature: &[u8], data: &[u8]| {
+        verify(&key, algorithm, signature, data)
+    };
+    let valid = match (&sign1.payload, &data.payload) {
+        (Some(_), _) => sign1.verify_signature(&aad, verifier),
+        (None, Some(payload)) => {
+            let payload =
+                decode_optional(&Some(payload.clone()), data.payload_encoding)?;
+            sign1.verify_detached_signature(&payload, &aad, verifier)
+        }
+        (None, None) => {
+            return Err(Error::Unsupported(
+                "detached cose sign1 without payload".to_string(),
+            ))
+        }
+    }
+    .is_ok();
+    let key_id = &sign1.protected.header.key_id;
+    Ok(CoseVerification {
+        valid,
+        algorithm: Some(format!("{:?}", algorithm)),
+        key_id: (!key_id.is_empty())
+            .then(|| String::from_utf8_lossy(key_id).to_string()),
+        payload: sign1
+            .payload
+            .as_ref()
+            .map(|payload| data.get_output_encoding().encode(payload))
+            .transpose()?,
+    })
+}
+
+pub(crate) fn decode_optional(
+    value: &Option<String>,
+    encoding: Option<TextEncoding>,
+) -> Result<Vec<u8>> {
+    match (value, encoding) {
+        (Some(value), Some(encoding)) => encoding.decode(value),
+        (Some(_), None) => {
+            Err(Error::Unsupported("missing encoding".to_string()))
+        }
+        _ => Ok(vec![]),
+    }
+}
+
+fn signature_algorithm(key: &CoseKey) -> Result<iana::Algorithm> {
+    if let Some(Algorithm::Assigned(algorithm)) = key.alg {
+        return Ok(algorithm);
+    }
+    Ok(match key_curve(key)? {
+        iana::EllipticCurve::P_256 => iana::Algorithm::ES256,
+        iana::EllipticCurve::P_384 => iana::Algorithm::ES384,
+        iana::EllipticCurve::Secp256k1 => iana::Algorithm::ES256K,
+        iana::EllipticCurve::Ed25519 => iana::Algorithm::EdDSA,
+        curve => {
+            return Err(Error::Unsupported(format!(
+                "cose signature curve {:?}",
+                curve
+            )))
+        }
+    })
+}
+
+fn sign(
+    key: &CoseKey,
+    algorithm: iana::Algorithm,
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let d = key_bytes(key, iana::Ec2KeyParameter::D as i64)?;
+    Ok(match algorithm {
+        iana::Algorithm::ES256 => {
+            let signature: p256::ecdsa::Signature =
+                p256::ecdsa::SigningKey::from_slice(&d)
+                    .map_err(|_| anyhow!("invalid p-256 private key"))?
+                    .sign(data);
+            signature.to_vec()
+        }
+        iana::Algorithm::ES384 => {
+            let signature: p384::ecdsa::Signature =
+                p384::ecdsa::SigningKey::from_slice(&d)
+                    .map_err(|_| anyhow!("invalid p-384 private key"))?
+                    .sign(data);
+            signature.to_vec()
+        }
+        iana::Algorithm::ES256K => {
+            let signature: k256::ecdsa::Signature =
+                k256::ecdsa::SigningKey::from_slice(&d)
+                    .map_err(|_| anyhow!("invalid secp256k1 private key"))?
+                    .sign(data);
+            signature.to_vec()
+        }
+        iana::Algorithm::EdDSA if is_key_type(key, iana::KeyType::OKP) => {
+            let secret: [u8; 32] = d
+                .try_into()
+                .map_err(|_| anyhow!("invalid ed25519 private key"))?;
+            ed25519_dalek::SigningKey::from_bytes(&secret)
+                .sign(data)
+                .to_vec()
+        }
+        algorithm => {
+            return Err(Error::Unsupported(format!(
+                "cose signature algorithm {:?}",
+                algorithm
+            )))
+        }
+    })
+}
+
+fn verify(
+    key: &CoseKey,
+    algorithm: iana::Algorithm,
+    signature: &[u8],
+    data: &[u8],
+) -> Result<()> {
+    let x = key_bytes(key, iana::Ec2KeyParameter::X as i64)?;
+    let sec1 = || -> Result<Vec<u8>> {
+        let y = key_bytes(key, iana::Ec2KeyParameter::Y as i64)?;
+        Ok([vec![0x04], x.clone(), y].concat())
+    };
+    let invalid = |_| anyhow!("cose signature mismatch");
+    match algorithm {
+        iana::Algorithm::ES256 => {
+            p256::ecdsa::VerifyingKey::from_sec1_bytes(&sec1()?)
+                .map_err(|_| anyhow!("invalid p-256 public key"))?
+                .verify(
+                    data,
+                    &p256::ecdsa::Signature::from_slice(signature)
+                        .map_err(invalid)?,
+                )
+                .map_err(invalid)?
+        }
+        iana::Algorithm::ES384 => {
+            p384::ecdsa::VerifyingKey::from_sec1_bytes(&sec1()?)
+                .map_err(|_| anyhow!("invalid p-384 public key"))?
+                .verify(
+                    data,
+                    &p384::ecdsa::Signature::from_slice(signature)
+                        .map_err(invalid)?,
+                )
+                .map_err(invalid)?
+        }
+        iana::Algorithm::ES256K => {
+            k256::ecdsa::VerifyingKey::from_sec1_bytes(&sec1()?)
+                .map_err(|_| anyhow!("invalid secp256k1 public key"))?
+                .verify(
+                    data,
+                    &k256::ecdsa::Signature::from_slice(signature)
+                        .map_err(invalid)?,
+                )
+                .map_err(invalid)?
+        }
+        iana::Algorithm::EdDSA => {
+            let public: [u8; 32] = x
+                .try_into()
+                .map_err(|_| anyhow!("invalid ed25519 public key"))?;
+            ed25519_dalek::VerifyingKey::from_bytes(&public)
+                .map_err(|_| anyhow!("invalid ed25519 public key"))?
+                .verify(
+                    data,
+                    &ed25519_dalek::Signature::from_slice(signature)
+                        .map_err(invalid)?,
+                )
+                .map_err(invalid)?
+        }
+        algorithm => {
+            return Err(Error::Unsupported(format!(
+                "cose signature algorithm {:?}",
+                algorithm
+            )))
+        }
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cose_sign1, cose_verify1, CoseSign1Dto, CoseVerify1Dto};
+    use crate::{
+        cose::key::generate_cose_key, enums::TextEncoding, jwt::JwkeyAlgorithm,
+    };
+
+    #[tokio::test]
+    async fn test_cose_sign1() {
+        for algorithm in [
+            JwkeyAlgorithm::ES256,
+            JwkeyAlgorithm::ES384,
+            JwkeyAlgorithm::ES256K,
+            JwkeyAlgorithm::EdDSA,
+        ] {
+            let key = generate_cose_key(
+                algorithm,
+                Some("kid".to_string()),
+                TextEncoding::Hex,
+            )
+            .await
+            .unwrap();
+            let message = cose_sign1(CoseSign1Dto {
+                input: "payload".to_string(),
+                input_encoding: TextEncoding::Utf8,
+                key: key.clone(),
+                key_encoding: TextEncoding::Hex,
+                input_path: None,
+                key_path: None,
+                output_encoding: TextEncoding::Hex,
+                aad: None,
+                aad_encoding: None,
+                detached: None,
+                tagged: Some(true),
+            })
+            .unwrap();
+            let verification = cose_verify1(CoseVerify1Dto {
+                input: message,
+                input_encoding: TextEncoding::Hex,
+                key,
+                key_encoding: TextEncoding::Hex,
+                input_path: None,
+                key_path: None,
+                output_encoding: TextEncoding::Utf8,
+                aad: None,
+                aad_encoding: None,
+                payload: None,
+                payload_encoding: None,
+            })
+            .unwrap();
+            assert!(verification.valid);
+            assert_eq!(Some("payload".to_string()), verification.payload);
+            assert_eq!(Some("kid".to_string()), verification.key_id);
+        }
+    }
+}
@@ -1,10 +1,32 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
 use crate::{enums::TextEncoding, errors::Result};
 
 pub mod aes;
+pub mod blind;
+pub mod calibrate;
+pub mod chacha;
+pub mod classical;
+pub mod commitment;
+pub mod digest;
 pub mod ecc;
+pub mod ecies;
 pub mod edwards;
+pub mod envelope;
+pub mod hpke;
 pub mod kdf;
+pub mod mac;
+pub mod merkle;
+pub mod oracle;
+pub mod pake;
+pub mod prf;
+pub mod ratchet;
 pub mod rsa;
+pub mod shamir;
+pub mod siv;
 
 pub trait EncryptionDto {
     fn get_input(&self) -> Result<Vec<u8>>;
@@ -12,6 +34,113 @@ pub trait EncryptionDto {
     fn get_output_encoding(&self) -> TextEncoding;
 }
 
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOutput {
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Outputs longer than this are not repeated in the other encodings.
+const ALT_ENCODING_LIMIT: usize = 4096;
+
+/// A command output with the context the ui shows next to it. Commands that
+/// turn one input into one encoded output return it, key generation,
+/// verification verdicts and structured outputs keep their own types.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationResult<T> {
+    pub output: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt_encodings: Option<BTreeMap<TextEncoding, String>>,
+    pub metadata: BTreeMap<&'static str, String>,
+    pub warnings: Vec<String>,
+    /// milliseconds
+    pub timing: f64,
+}
+
+impl<T> OperationResult<T> {
+    pub fn new(output: T, started: Instant) -> Self {
+        OperationResult {
+            output,
+            alt_encodings: None,
+            metadata: BTreeMap::new(),
+            warnings: vec![],
+            timing: started.elapsed().as_secs_f64() * 1000.0,
+        }
+    }
+
+    pub fn metadata(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.metadata.insert(key, value.to_string());
+        self
+    }
+
+    pub fn warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+}
+
+impl OperationResult<String> {
+    /// Other encodings are only added for short outputs.
+    pub fn encoded(
+        output: &[u8],
+        encoding: TextEncoding,
+        started: Instant,
+    ) -> Result<Self> {
+        let alt_encodings = (output.len() <= ALT_ENCODING_LIMIT).then(|| {
+            [TextEncoding::Base64, TextEncoding::Hex, TextEncoding::Utf8]
+                .into_iter()
+                .filter(|alt| *alt != encoding)
+                .filter_map(|alt| Some((alt, alt.encode(output).ok()?)))
+                .collect()
+        });
+        Ok(OperationResult {
+            alt_encodings,
+            ..OperationResult::new(encoding.encode(output)?, started)
+        })
+    }
+}
+
+impl From<Result<String>> for BatchOutput {
+    fn from(value: Result<String>) -> Self {
+        match value {
+            Ok(output) => BatchOutput {
+                output: Some(output),
+                error: None,
+            },
+            Err(err) => {
+                tracing::warn!("batch item failed: {:?}", err);
+                BatchOutput {
+                    output: None,
+                    error: Some(err.to_string()),
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn batch<F>(
+    inputs: &[String],
+    input_encoding: TextEncoding,
+    output_encoding: TextEncoding,
+    f: F,
+) -> Vec<BatchOutput>
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>> + Sync,
+{
+    inputs
+        .par_iter()
+        .map(|input| {
+            input_encoding
+                .decode(input)
+                .and_then(|input| f(&input))
+                .and_then(|output| output_encoding.encode(&output))
+                .into()
+        })
+        .collect()
+}
+
 #[macro_export]
 macro_rules! add_encryption_trait_impl {
   ($struct_name:ident { $($field_name:ident : $field_type:ty),* }) => {
@@ -22,6 +151,12 @@ macro_rules! add_encryption_trait_impl {
           pub input_encoding: TextEncoding,
           pub key: String,
           pub key_encoding: TextEncoding,
+          /// read raw from a file in scope instead of `input`
+          #[serde(default)]
+          pub input_path: Option<String>,
+          /// read raw from a file in scope instead of `key`
+          #[serde(default)]
+          pub key_path: Option<String>,
           pub output_encoding: TextEncoding,
           $($field_name : $field_type,)*
 
@@ -29,10 +164,18 @@ macro_rules! add_encryption_trait_impl {
 
       impl EncryptionDto for $struct_name {
           fn get_input(&self) -> Result<Vec<u8>> {
-            self.input_encoding.decode(&self.input)
+            if let Some(path) = &self.input_path {
+                return $crate::files::read_scoped(path);
+            }
+            self.input_encoding
+                .decode_lenient(&$crate::pipeline::resolve(&self.input)?)
           }
           fn get_key(&self) -> Result<Vec<u8>> {
-            self.key_encoding.decode(&self.key)
+            if let Some(path) = &self.key_path {
+                return $crate::files::read_scoped(path);
+            }
+            self.key_encoding
+                .decode_lenient(&$crate::pipeline::resolve(&self.key)?)
           }
           fn get_output_encoding(&self) -> TextEncoding {
             self.output_encoding
@@ -1,25 +1,36 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Instant};
 
 use aes::{
     cipher::{
-        block_padding::Pkcs7, typenum, BlockCipher, BlockDecrypt,
-        BlockDecryptMut, BlockEncrypt, BlockEncryptMut, BlockSizeUser, KeyInit,
-        KeyIvInit,
+        block_padding::Pkcs7, generic_array::GenericArray, typenum,
+        BlockCipher, BlockDecrypt, BlockDecryptMut, BlockEncrypt,
+        BlockEncryptMut, BlockSizeUser, KeyInit, KeyIvInit,
     },
     Aes128, Aes256,
 };
 use aes_gcm::{aead::AeadMutInPlace, AesGcm, Nonce};
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use block_padding::NoPadding;
+use ccm::{Ccm, NonceSize, TagSize};
+use hkdf::hmac::{Hmac, Mac};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
 use tracing::{debug, info};
 
 use crate::{
     add_encryption_trait_impl,
-    crypto::EncryptionDto,
+    crypto::{
+        batch, commitment, siv, BatchOutput, EncryptionDto, OperationResult,
+    },
     enums::{AesEncryptionPadding, EncryptionMode, TextEncoding},
     errors::{Error, Result},
+    jwt::JwkeyUsage,
+    pool,
+    profile::{self, Algorithm},
+    rng,
     utils::random_bytes,
+    vault,
 };
 
 add_encryption_trait_impl!(
@@ -30,10 +41,28 @@ add_encryption_trait_impl!(
         iv_encoding: Option<TextEncoding>,
         aad: Option<String>,
         aad_encoding: Option<TextEncoding>,
+        key_commitment: Option<bool>,
+        /// ccm only, [`CCM_TAG_SIZE`] when not given
+        tag_size: Option<usize>,
+        /// xts only, the tweak as a sector number in place of the iv
+        sector: Option<u64>,
         for_encryption: bool
     }
 );
 
+/// Tag size of ccm unless another is asked for, as long as the gcm tag.
+pub(crate) const CCM_TAG_SIZE: usize = 16;
+
+const XTS_BLOCK_SIZE: usize = 16;
+
+struct CcmInput<'a> {
+    input: &'a [u8],
+    key: &'a [u8],
+    nonce: &'a [u8],
+    aad: &'a [u8],
+    for_encryption: bool,
+}
+
 impl Debug for AesEncryptoinDto {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("AesEncryptoinDto")
@@ -46,6 +75,9 @@ impl Debug for AesEncryptoinDto {
             .field("iv_encoding", &self.iv_encoding)
             .field("aad", &self.aad)
             .field("aad_encoding", &self.aad_encoding)
+            .field("key_commitment", &self.key_commitment)
+            .field("tag_size", &self.tag_size)
+            .field("sector", &self.sector)
             .field("for_encryption", &self.for_encryption)
             .finish()
     }
@@ -70,34 +102,208 @@ pub async fn generate_aes(
 }
 
 #[tauri::command]
-pub async fn crypto_aes(data: AesEncryptoinDto) -> Result<String> {
+pub async fn crypto_aes(
+    data: AesEncryptoinDto,
+) -> Result<OperationResult<String>> {
+    let started = Instant::now();
     info!(
         "aes crypto-> for_encryption: {} mode: {:?} padding: {:?}",
         data.for_encryption, data.mode, data.padding
     );
-    let iv: Option<Vec<u8>> = data.iv.as_ref().and_then(|nonce| {
-        data.iv_encoding
-            .map(|enc| enc.decode(nonce).unwrap_or_default())
-    });
+    let plaintext = data.get_input()?;
+    let output_encoding = data.get_output_encoding();
+    let output = crypto_aes_inner(&data, &plaintext)?;
+    Ok(OperationResult::encoded(&output, output_encoding, started)?
+        .metadata("mode", format!("{:?}", data.mode))
+        .metadata("padding", format!("{:?}", data.padding))
+        .metadata("keySize", data.get_key()?.len() * 8)
+        .warnings(warnings(&data)))
+}
+
+fn warnings(data: &AesEncryptoinDto) -> Vec<String> {
+    let mut warnings = vec![];
+    if data.mode == EncryptionMode::Ecb {
+        warnings.push(
+            "ecb encrypts equal blocks alike and leaks plaintext patterns"
+                .to_string(),
+        );
+    }
+    if data.mode == EncryptionMode::Siv
+        && data.iv.as_ref().map_or(true, |iv| iv.is_empty())
+    {
+        warnings.push(siv::DETERMINISTIC_WARNING.to_string());
+    }
+    if data.mode == EncryptionMode::Cbc {
+        warnings.push(
+            "cbc alone does not detect tampering, CBC-HMAC does".to_string(),
+        );
+    }
+    if data.mode == EncryptionMode::Xts {
+        warnings.push(
+            "xts does not detect tampering and encrypts a sector alike every \
+             time it is written"
+                .to_string(),
+        );
+    }
+    let iv = match (&data.iv, data.iv_encoding) {
+        (Some(iv), Some(encoding)) => encoding.decode(iv).ok(),
+        _ => None,
+    };
+    if data.mode == EncryptionMode::Ccm
+        && data.tag_size.is_some_and(|size| size < 8)
+    {
+        warnings.push(
+            "ccm tags under 8 bytes can be forged by trying them all"
+                .to_string(),
+        );
+    }
+    if iv.is_some_and(|iv| !iv.is_empty() && iv.iter().all(|byte| *byte == 0)) {
+        warnings.push("the iv is all zeros, a static iv".to_string());
+    }
+    warnings
+}
+
+#[tauri::command]
+pub async fn crypto_aes_batch(
+    data: AesEncryptoinDto,
+    inputs: Vec<String>,
+) -> Result<Vec<BatchOutput>> {
+    info!(
+        "aes batch crypto-> for_encryption: {} mode: {:?} padding: {:?}, \
+         size: {}",
+        data.for_encryption,
+        data.mode,
+        data.padding,
+        inputs.len()
+    );
+    pool::spawn(move || {
+        batch(
+            &inputs,
+            data.input_encoding,
+            data.get_output_encoding(),
+            |input| match batch_iv_size(data.mode) {
+                Some(iv_size) => crypto_aes_item(&data, input, iv_size),
+                None => crypto_aes_inner(&data, input),
+            },
+        )
+    })
+    .await
+}
+
+/// One iv across a batch is nonce reuse, each item gets a fresh one in front
+/// of its ciphertext.
+fn crypto_aes_item(
+    data: &AesEncryptoinDto,
+    input: &[u8],
+    iv_size: usize,
+) -> Result<Vec<u8>> {
+    if data.for_encryption {
+        let mut iv = vec![0u8; iv_size];
+        rng::rng().fill_bytes(&mut iv);
+        let ciphertext = crypto_aes_with_iv(data, input, Some(iv.clone()))?;
+        iv.extend(ciphertext);
+        return Ok(iv);
+    }
+    if input.len() < iv_size {
+        return Err(Error::Unsupported(format!(
+            "batch item of {} bytes without its iv",
+            input.len()
+        )));
+    }
+    let (iv, ciphertext) = input.split_at(iv_size);
+    crypto_aes_with_iv(data, ciphertext, Some(iv.to_vec()))
+}
 
+fn batch_iv_size(mode: EncryptionMode) -> Option<usize> {
+    match mode {
+        EncryptionMode::Cbc | EncryptionMode::CbcHmac => Some(16),
+        EncryptionMode::Gcm | EncryptionMode::Ccm => Some(12),
+        _ => None,
+    }
+}
+
+pub(crate) fn crypto_aes_inner(
+    data: &AesEncryptoinDto,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let iv: Option<Vec<u8>> = match data.sector {
+        Some(sector) if data.mode == EncryptionMode::Xts => {
+            Some(xts_tweak(sector).to_vec())
+        }
+        _ => data.iv.as_ref().and_then(|nonce| {
+            data.iv_encoding
+                .map(|enc| enc.decode(nonce).unwrap_or_default())
+        }),
+    };
+    crypto_aes_with_iv(data, input, iv)
+}
+
+fn crypto_aes_with_iv(
+    data: &AesEncryptoinDto,
+    input: &[u8],
+    iv: Option<Vec<u8>>,
+) -> Result<Vec<u8>> {
     let aad: Option<Vec<u8>> = data.aad.as_ref().and_then(|association| {
         data.aad_encoding
             .map(|enc| enc.decode(association).unwrap_or_default())
     });
     debug!("iv: {:?}, aad: {:?}", iv, aad);
+    profile::check(Algorithm::AesMode(data.mode))?;
     let key_bytes = data.get_key()?;
-    let plaintext = data.get_input()?;
-    let output_encoding = data.get_output_encoding();
-    let output = encrypt_or_decrypt_aes(
-        data.mode,
-        &plaintext,
+    vault::enforce_policy(
         &key_bytes,
-        iv,
-        aad,
-        data.padding,
-        data.for_encryption,
+        JwkeyUsage::Encryption,
+        &format!("AES-{:?}", data.mode).to_uppercase(),
     )?;
-    output_encoding.encode(&output)
+    if !data.key_commitment.unwrap_or(false) {
+        if data.mode == EncryptionMode::Ccm {
+            return aes_ccm(
+                input,
+                &key_bytes,
+                iv,
+                aad,
+                data.tag_size.unwrap_or(CCM_TAG_SIZE),
+                data.for_encryption,
+            );
+        }
+        return encrypt_or_decrypt_aes(
+            data.mode,
+            input,
+            &key_bytes,
+            iv,
+            aad,
+            data.padding,
+            data.for_encryption,
+        );
+    }
+    if data.mode != EncryptionMode::Gcm {
+        return Err(Error::Unsupported(format!(
+            "key commitment with aes {:?}",
+            data.mode
+        )));
+    }
+    if data.for_encryption {
+        let ciphertext = encrypt_or_decrypt_aes(
+            data.mode,
+            input,
+            &key_bytes,
+            iv,
+            aad,
+            data.padding,
+            true,
+        )?;
+        commitment::commit(&key_bytes, ciphertext)
+    } else {
+        encrypt_or_decrypt_aes(
+            data.mode,
+            commitment::open(&key_bytes, input)?,
+            &key_bytes,
+            iv,
+            aad,
+            data.padding,
+            false,
+        )
+    }
 }
 
 pub(crate) fn encrypt_or_decrypt_aes(
@@ -109,6 +315,25 @@ pub(crate) fn encrypt_or_decrypt_aes(
     padding: AesEncryptionPadding,
     for_encryption: bool,
 ) -> Result<Vec<u8>> {
+    match mode {
+        EncryptionMode::CbcHmac => {
+            return cbc_hmac(plaintext, key, iv, aad, padding, for_encryption)
+        }
+        // the iv is the nonce, the last component, an empty field of the
+        // form is no component at all
+        EncryptionMode::Siv => {
+            let components = aad
+                .into_iter()
+                .chain(iv)
+                .filter(|component| !component.is_empty())
+                .collect::<Vec<_>>();
+            return siv::siv(key, &components, plaintext, for_encryption);
+        }
+        EncryptionMode::Xts => {
+            return aes_xts(plaintext, key, iv, for_encryption)
+        }
+        _ => {}
+    }
     match key.len() {
         16 => encrypt_or_decrypt_aes_inner::<Aes128>(
             mode,
@@ -177,14 +402,22 @@ where
                         key,
                         iv.unwrap().as_ref(),
                     )
-                    .context("construct aes_ecb_decryptor failed")?,
+                    .context("construct aes_cbc_decryptor failed")?,
                     padding,
                     plaintext,
                 )
             }
         }
         EncryptionMode::Gcm => {
-            let nonce = iv.unwrap();
+            let nonce = match iv {
+                Some(nonce) if nonce.len() == 12 => nonce,
+                nonce => {
+                    return Err(Error::Unsupported(format!(
+                        "gcm nonce of {} bytes, 12 expected",
+                        nonce.map_or(0, |nonce| nonce.len())
+                    )))
+                }
+            };
             let nonce = Nonce::from_slice(&nonce);
             let mut payload = Vec::from(plaintext);
             let association = &if let Some(association) = aad {
@@ -204,6 +437,313 @@ where
             };
             Ok(payload)
         }
+        EncryptionMode::Ccm => {
+            let nonce =
+                iv.ok_or(Error::Unsupported("iv is required".to_string()))?;
+            aes_ccm_nonce::<C>(
+                CcmInput {
+                    input: plaintext,
+                    key,
+                    nonce: &nonce,
+                    aad: &aad.unwrap_or_default(),
+                    for_encryption,
+                },
+                CCM_TAG_SIZE,
+            )
+        }
+        EncryptionMode::CbcHmac | EncryptionMode::Siv | EncryptionMode::Xts => {
+            Err(Error::Unsupported(format!(
+                "aes {:?} with a bare aes key",
+                mode
+            )))
+        }
+    }
+}
+
+/// RFC 3610, the tag follows the ciphertext.
+pub(crate) fn aes_ccm(
+    input: &[u8],
+    key: &[u8],
+    iv: Option<Vec<u8>>,
+    aad: Option<Vec<u8>>,
+    tag_size: usize,
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    let nonce = iv.ok_or(Error::Unsupported("iv is required".to_string()))?;
+    let aad = aad.unwrap_or_default();
+    let ccm = CcmInput {
+        input,
+        key,
+        nonce: &nonce,
+        aad: &aad,
+        for_encryption,
+    };
+    match key.len() {
+        16 => aes_ccm_nonce::<Aes128>(ccm, tag_size),
+        32 => aes_ccm_nonce::<Aes256>(ccm, tag_size),
+        _ => Err(Error::Unsupported(format!("keysize {}", key.len()))),
+    }
+}
+
+fn aes_ccm_nonce<C>(ccm: CcmInput, tag_size: usize) -> Result<Vec<u8>>
+where
+    C: BlockCipher
+        + BlockEncrypt
+        + KeyInit
+        + BlockSizeUser<BlockSize = typenum::U16>,
+{
+    match ccm.nonce.len() {
+        7 => aes_ccm_tag::<C, typenum::U7>(ccm, tag_size),
+        8 => aes_ccm_tag::<C, typenum::U8>(ccm, tag_size),
+        9 => aes_ccm_tag::<C, typenum::U9>(ccm, tag_size),
+        10 => aes_ccm_tag::<C, typenum::U10>(ccm, tag_size),
+        11 => aes_ccm_tag::<C, typenum::U11>(ccm, tag_size),
+        12 => aes_ccm_tag::<C, typenum::U12>(ccm, tag_size),
+        13 => aes_ccm_tag::<C, typenum::U13>(ccm, tag_size),
+        size => Err(Error::Unsupported(format!("ccm nonce size {}", size))),
+    }
+}
+
+fn aes_ccm_tag<C, N>(ccm: CcmInput, tag_size: usize) -> Result<Vec<u8>>
+where
+    C: BlockCipher
+        + BlockEncrypt
+        + KeyInit
+        + BlockSizeUser<BlockSize = typenum::U16>,
+    N: NonceSize,
+{
+    match tag_size {
+        4 => aes_ccm_inner::<C, typenum::U4, N>(ccm),
+        6 => aes_ccm_inner::<C, typenum::U6, N>(ccm),
+        8 => aes_ccm_inner::<C, typenum::U8, N>(ccm),
+        10 => aes_ccm_inner::<C, typenum::U10, N>(ccm),
+        12 => aes_ccm_inner::<C, typenum::U12, N>(ccm),
+        14 => aes_ccm_inner::<C, typenum::U14, N>(ccm),
+        16 => aes_ccm_inner::<C, typenum::U16, N>(ccm),
+        size => Err(Error::Unsupported(format!("ccm tag size {}", size))),
+    }
+}
+
+fn aes_ccm_inner<C, M, N>(ccm: CcmInput) -> Result<Vec<u8>>
+where
+    C: BlockCipher
+        + BlockEncrypt
+        + KeyInit
+        + BlockSizeUser<BlockSize = typenum::U16>,
+    M: TagSize,
+    N: NonceSize,
+{
+    let mut c = Ccm::<C, M, N>::new_from_slice(ccm.key)
+        .context("construct aes_ccm_cipher failed")?;
+    let nonce = Nonce::<N>::from_slice(ccm.nonce);
+    let mut payload = ccm.input.to_vec();
+    if ccm.for_encryption {
+        c.encrypt_in_place(nonce, ccm.aad, &mut payload)
+            .context("aes ccm encrypt failed")?
+    } else {
+        c.decrypt_in_place(nonce, ccm.aad, &mut payload)
+            .context("aes ccm decrypt failed")?
+    };
+    Ok(payload)
+}
+
+/// The tweak of a sector, its number little endian as IEEE 1619 has it.
+pub(crate) fn xts_tweak(sector: u64) -> [u8; XTS_BLOCK_SIZE] {
+    let mut tweak = [0u8; XTS_BLOCK_SIZE];
+    tweak[.. 8].copy_from_slice(&sector.to_le_bytes());
+    tweak
+}
+
+/// IEEE 1619 with ciphertext stealing, the second half of `key` encrypts
+/// the tweak.
+fn aes_xts(
+    input: &[u8],
+    key: &[u8],
+    iv: Option<Vec<u8>>,
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    let tweak =
+        iv.ok_or(Error::Unsupported("tweak is required".to_string()))?;
+    let tweak: [u8; XTS_BLOCK_SIZE] =
+        tweak.as_slice().try_into().map_err(|_| {
+            Error::Unsupported(format!("xts tweak size {}", tweak.len()))
+        })?;
+    if input.len() < XTS_BLOCK_SIZE {
+        return Err(Error::Unsupported(format!(
+            "xts input of {} bytes",
+            input.len()
+        )));
+    }
+    let (data_key, tweak_key) = key.split_at(key.len() / 2);
+    if data_key == tweak_key {
+        return Err(Error::Unsupported(
+            "xts key with equal halves".to_string(),
+        ));
+    }
+    match key.len() {
+        32 => aes_xts_inner::<Aes128>(input, key, tweak, for_encryption),
+        64 => aes_xts_inner::<Aes256>(input, key, tweak, for_encryption),
+        _ => Err(Error::Unsupported(format!("xts keysize {}", key.len()))),
+    }
+}
+
+fn aes_xts_inner<C>(
+    input: &[u8],
+    key: &[u8],
+    mut tweak: [u8; XTS_BLOCK_SIZE],
+    for_encryption: bool,
+) -> Result<Vec<u8>>
+where
+    C: BlockEncrypt
+        + BlockDecrypt
+        + KeyInit
+        + BlockSizeUser<BlockSize = typenum::U16>,
+{
+    let (data_key, tweak_key) = key.split_at(key.len() / 2);
+    let cipher = C::new_from_slice(data_key)
+        .context("construct aes_xts_cipher failed")?;
+    C::new_from_slice(tweak_key)
+        .context("construct aes_xts_cipher failed")?
+        .encrypt_block(GenericArray::from_mut_slice(&mut tweak));
+    let crypt = |block: &mut [u8], tweak: &[u8; XTS_BLOCK_SIZE]| {
+        block.iter_mut().zip(tweak).for_each(|(b, t)| *b ^= t);
+        let array = GenericArray::from_mut_slice(block);
+        if for_encryption {
+            cipher.encrypt_block(array);
+        } else {
+            cipher.decrypt_block(array);
+        }
+        block.iter_mut().zip(tweak).for_each(|(b, t)| *b ^= t);
+    };
+
+    let mut output = input.to_vec();
+    let tail = input.len() % XTS_BLOCK_SIZE;
+    // the last whole block takes part in the stealing when a tail follows
+    let whole = input.len() / XTS_BLOCK_SIZE - usize::from(tail > 0);
+    let (blocks, rest) = output.split_at_mut(whole * XTS_BLOCK_SIZE);
+    for block in blocks.chunks_mut(XTS_BLOCK_SIZE) {
+        crypt(block, &tweak);
+        tweak = xts_next_tweak(tweak);
+    }
+    if tail > 0 {
+        let (last, partial) = rest.split_at_mut(XTS_BLOCK_SIZE);
+        // decryption undoes the last two blocks in the reverse order
+        let (first, second) = if for_encryption {
+            (tweak, xts_next_tweak(tweak))
+        } else {
+            (xts_next_tweak(tweak), tweak)
+        };
+        crypt(last, &first);
+        let mut stolen = [0u8; XTS_BLOCK_SIZE];
+        stolen[.. tail].copy_from_slice(partial);
+        stolen[tail ..].copy_from_slice(&last[tail ..]);
+        partial.copy_from_slice(&last[.. tail]);
+        crypt(&mut stolen, &second);
+        last.copy_from_slice(&stolen);
+    }
+    Ok(output)
+}
+
+/// The tweak times the primitive element of GF(2^128), little endian.
+fn xts_next_tweak(tweak: [u8; XTS_BLOCK_SIZE]) -> [u8; XTS_BLOCK_SIZE] {
+    let mut next = [0u8; XTS_BLOCK_SIZE];
+    let mut carry = 0;
+    for (next, byte) in next.iter_mut().zip(tweak) {
+        *next = (byte << 1) | carry;
+        carry = byte >> 7;
+    }
+    next[0] ^= 0x87 * carry;
+    next
+}
+
+/// RFC 7518 5.2, the first half of `key` is the hmac key.
+fn cbc_hmac(
+    input: &[u8],
+    key: &[u8],
+    iv: Option<Vec<u8>>,
+    aad: Option<Vec<u8>>,
+    padding: AesEncryptionPadding,
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    let iv = iv.ok_or(Error::Unsupported("iv is required".to_string()))?;
+    let aad = aad.unwrap_or_default();
+    match key.len() {
+        32 => cbc_hmac_inner::<Sha256>(
+            input,
+            key,
+            &iv,
+            &aad,
+            padding,
+            for_encryption,
+        ),
+        64 => cbc_hmac_inner::<Sha512>(
+            input,
+            key,
+            &iv,
+            &aad,
+            padding,
+            for_encryption,
+        ),
+        _ => Err(Error::Unsupported(format!(
+            "cbc hmac keysize {}",
+            key.len()
+        ))),
+    }
+}
+
+fn cbc_hmac_inner<D>(
+    input: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    padding: AesEncryptionPadding,
+    for_encryption: bool,
+) -> Result<Vec<u8>>
+where
+    Hmac<D>: Mac + KeyInit,
+{
+    let (mac_key, enc_key) = key.split_at(key.len() / 2);
+    let mac = |ciphertext: &[u8]| -> Result<Hmac<D>> {
+        let mut mac = <Hmac<D> as KeyInit>::new_from_slice(mac_key)
+            .context("invalid hmac key")?;
+        mac.update(aad);
+        mac.update(iv);
+        mac.update(ciphertext);
+        mac.update(&(aad.len() as u64 * 8).to_be_bytes());
+        Ok(mac)
+    };
+    if for_encryption {
+        let mut ciphertext = encrypt_or_decrypt_aes(
+            EncryptionMode::Cbc,
+            input,
+            enc_key,
+            Some(iv.to_vec()),
+            None,
+            padding,
+            true,
+        )?;
+        let tag = mac(&ciphertext)?.finalize().into_bytes();
+        ciphertext.extend_from_slice(&tag[.. mac_key.len()]);
+        Ok(ciphertext)
+    } else {
+        let (ciphertext, tag) = input.split_at(
+            input
+                .len()
+                .checked_sub(mac_key.len())
+                .ok_or(anyhow!("aes cbc hmac tag mismatch"))?,
+        );
+        mac(ciphertext)?
+            .verify_truncated_left(tag)
+            .map_err(|_| anyhow!("aes cbc hmac tag mismatch"))?;
+        encrypt_or_decrypt_aes(
+            EncryptionMode::Cbc,
+            ciphertext,
+            enc_key,
+            Some(iv.to_vec()),
+            None,
+            padding,
+            false,
+        )
     }
 }
 
@@ -255,9 +795,11 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::generate_aes;
+    use super::{aes_ccm, encrypt_or_decrypt_aes, generate_aes, xts_tweak};
     use crate::{
-        crypto::aes::{crypto_aes, generate_iv, AesEncryptoinDto},
+        crypto::aes::{
+            crypto_aes, crypto_aes_batch, generate_iv, AesEncryptoinDto,
+        },
         enums::{AesEncryptionPadding, EncryptionMode, TextEncoding},
         utils::random_bytes,
     };
@@ -276,6 +818,8 @@ mod test {
                 input_encoding: TextEncoding::Utf8,
                 key: key.to_string(),
                 key_encoding: encoding,
+                input_path: None,
+                key_path: None,
                 output_encoding: encoding,
                 mode: EncryptionMode::Gcm,
                 padding: AesEncryptionPadding::NoPadding,
@@ -283,10 +827,14 @@ mod test {
                 iv_encoding: Some(encoding),
                 aad: Some(aad.to_string()),
                 aad_encoding: Some(encoding),
+                key_commitment: None,
+                tag_size: None,
+                sector: None,
                 for_encryption: true,
             })
             .await
-            .unwrap();
+            .unwrap()
+            .output;
             assert_eq!(
                 plaintext,
                 crypto_aes(AesEncryptoinDto {
@@ -294,6 +842,8 @@ mod test {
                     input_encoding: encoding,
                     key,
                     key_encoding: encoding,
+                    input_path: None,
+                    key_path: None,
                     output_encoding: TextEncoding::Utf8,
                     mode: EncryptionMode::Gcm,
                     padding: AesEncryptionPadding::NoPadding,
@@ -301,11 +851,245 @@ mod test {
                     iv_encoding: Some(encoding),
                     aad: Some(aad),
                     aad_encoding: Some(encoding),
+                    key_commitment: None,
+                    tag_size: None,
+                    sector: None,
                     for_encryption: false
                 })
                 .await
                 .unwrap()
+                .output
             )
         }
     }
+
+    #[test]
+    fn test_aes_gcm_nonce_size() {
+        let gcm = |iv: Option<Vec<u8>>| {
+            encrypt_or_decrypt_aes(
+                EncryptionMode::Gcm,
+                b"plaintext",
+                &[0x2b; 16],
+                iv,
+                None,
+                AesEncryptionPadding::NoPadding,
+                true,
+            )
+        };
+        assert!(gcm(Some(vec![0; 12])).is_ok());
+        assert!(gcm(Some(vec![0; 16])).is_err());
+        assert!(gcm(Some(vec![0; 8])).is_err());
+        assert!(gcm(None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_aes_batch_encryption() {
+        let encoding = TextEncoding::Base64;
+        let key = generate_aes(256, encoding).await.unwrap();
+        let plaintexts = vec![
+            "first".to_string(),
+            "second".to_string(),
+            "first".to_string(),
+        ];
+        let dto = |mode: EncryptionMode,
+                   input_encoding,
+                   output_encoding,
+                   for_encryption| {
+            AesEncryptoinDto {
+                input: "".to_string(),
+                input_encoding,
+                key: key.to_string(),
+                key_encoding: encoding,
+                input_path: None,
+                key_path: None,
+                output_encoding,
+                mode,
+                padding: if mode == EncryptionMode::Gcm {
+                    AesEncryptionPadding::NoPadding
+                } else {
+                    AesEncryptionPadding::Pkcs7Padding
+                },
+                iv: None,
+                iv_encoding: None,
+                aad: None,
+                aad_encoding: None,
+                key_commitment: None,
+                tag_size: None,
+                sector: None,
+                for_encryption,
+            }
+        };
+        for mode in [EncryptionMode::Cbc, EncryptionMode::Gcm] {
+            let ciphertexts = crypto_aes_batch(
+                dto(mode, TextEncoding::Utf8, encoding, true),
+                plaintexts.clone(),
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|item| item.output.unwrap())
+            .chain(["not base64".to_string()])
+            .collect::<Vec<String>>();
+            // equal items are sealed under their own ivs
+            assert_ne!(ciphertexts[0], ciphertexts[2]);
+            let outputs = crypto_aes_batch(
+                dto(mode, encoding, TextEncoding::Utf8, false),
+                ciphertexts,
+            )
+            .await
+            .unwrap();
+            for (plaintext, output) in plaintexts.iter().zip(outputs.iter()) {
+                assert_eq!(Some(plaintext), output.output.as_ref());
+            }
+            assert!(outputs[3].output.is_none() && outputs[3].error.is_some());
+        }
+    }
+
+    // RFC 7518 appendix B.1, A128CBC-HS256
+    #[tokio::test]
+    async fn test_aes_cbc_hmac() {
+        let plaintext = "A cipher system must not be required to be secret, \
+                         and it must be able to fall into the hands of the \
+                         enemy without inconvenience";
+        let dto = |input: &str, aad: &str, for_encryption| {
+            AesEncryptoinDto {
+            input: input.to_string(),
+            input_encoding: if for_encryption {
+                TextEncoding::Utf8
+            } else {
+                TextEncoding::Hex
+            },
+            key: "000102030405060708090a0b0c0d0e0f\
+                  101112131415161718191a1b1c1d1e1f"
+                .to_string(),
+            key_encoding: TextEncoding::Hex,
+            input_path: None,
+            key_path: None,
+            output_encoding: if for_encryption {
+                TextEncoding::Hex
+            } else {
+                TextEncoding::Utf8
+            },
+            mode: EncryptionMode::CbcHmac,
+            padding: AesEncryptionPadding::Pkcs7Padding,
+            iv: Some("1af38c2dc2b96ffdd86694092341bc04".to_string()),
+            iv_encoding: Some(TextEncoding::Hex),
+            aad: Some(aad.to_string()),
+            aad_encoding: Some(TextEncoding::Utf8),
+            key_commitment: None,
+            tag_size: None,
+            sector: None,
+            for_encryption,
+        }
+        };
+        let aad = "The second principle of Auguste Kerckhoffs";
+        let ciphertext =
+            crypto_aes(dto(plaintext, aad, true)).await.unwrap().output;
+        assert!(ciphertext.starts_with("c80edfa32ddf39d5ef00c0b468834279"));
+        assert!(ciphertext.ends_with("652c3fa36b0a7c5b3219fab3a30bc1c4"));
+        assert_eq!(
+            crypto_aes(dto(&ciphertext, aad, false))
+                .await
+                .unwrap()
+                .output,
+            plaintext
+        );
+        assert!(crypto_aes(dto(&ciphertext, "tampered", false))
+            .await
+            .is_err());
+    }
+
+    // RFC 5297 appendix A.1 through the aes modes, the aad is the only
+    // component
+    #[test]
+    fn test_aes_siv_mode() {
+        let hex = |input: &str| TextEncoding::Hex.decode(input).unwrap();
+        let ciphertext = encrypt_or_decrypt_aes(
+            EncryptionMode::Siv,
+            &hex("112233445566778899aabbccddee"),
+            &hex("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0\
+                  f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"),
+            Some(vec![]),
+            Some(hex("101112131415161718191a1b1c1d1e1f2021222324252627")),
+            AesEncryptionPadding::NoPadding,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            TextEncoding::Hex.encode(&ciphertext).unwrap(),
+            "85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c"
+        );
+    }
+
+    // RFC 3610 packet vector #1, a 13 bytes nonce and an 8 bytes tag
+    #[test]
+    fn test_aes_ccm() {
+        let key = (0xc0 ..= 0xcf).collect::<Vec<u8>>();
+        let nonce = vec![
+            0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3,
+            0xa4, 0xa5,
+        ];
+        let aad = (0x00 .. 0x08).collect::<Vec<u8>>();
+        let plaintext = (0x08 .. 0x1f).collect::<Vec<u8>>();
+        let ccm = |input: &[u8], tag_size, for_encryption| {
+            aes_ccm(
+                input,
+                &key,
+                Some(nonce.clone()),
+                Some(aad.clone()),
+                tag_size,
+                for_encryption,
+            )
+        };
+        let ciphertext = ccm(&plaintext, 8, true).unwrap();
+        assert_eq!(
+            TextEncoding::Hex.encode(&ciphertext).unwrap(),
+            "588c979a61c663d2f066d0c2c0f989806d5f6b61dac38417e8d12cfdf926e0"
+        );
+        assert_eq!(ccm(&ciphertext, 8, false).unwrap(), plaintext);
+        assert!(ccm(&ciphertext, 16, false).is_err());
+        assert!(ccm(&plaintext, 5, true).is_err());
+    }
+
+    // IEEE 1619 vector 2, then a ragged sector through ciphertext stealing
+    #[test]
+    fn test_aes_xts() {
+        let xts = |input: &[u8], key: &[u8], sector, for_encryption| {
+            encrypt_or_decrypt_aes(
+                EncryptionMode::Xts,
+                input,
+                key,
+                Some(xts_tweak(sector).to_vec()),
+                None,
+                AesEncryptionPadding::NoPadding,
+                for_encryption,
+            )
+            .unwrap()
+        };
+        let key = [[0x11; 16], [0x22; 16]].concat();
+        let ciphertext = xts(&[0x44; 32], &key, 0x3333333333, true);
+        assert_eq!(
+            TextEncoding::Hex.encode(&ciphertext).unwrap(),
+            "c454185e6a16936e39334038acef838bfb186fff7480adc4289382ecd6d394f0"
+        );
+        let key = (0 .. 64).collect::<Vec<u8>>();
+        let plaintext = b"disk sector with a ragged tail!!!!!";
+        let ciphertext = xts(plaintext, &key, 7, true);
+        assert_eq!(
+            TextEncoding::Hex.encode(&ciphertext).unwrap(),
+            "be89835cfb48f280784ee7349f5abe188cbd5f0d53ce74326d4fe3da9d037880\
+             4ff594"
+        );
+        assert_eq!(xts(&ciphertext, &key, 7, false), plaintext);
+        assert!(encrypt_or_decrypt_aes(
+            EncryptionMode::Xts,
+            plaintext,
+            &[0x11; 32],
+            Some(xts_tweak(7).to_vec()),
+            None,
+            AesEncryptionPadding::NoPadding,
+            true,
+        )
+        .is_err());
+    }
 }
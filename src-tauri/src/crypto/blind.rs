@@ -0,0 +1,370 @@
+//! Experimental RSA blind signatures (RSABSSA, RFC 9474).
+
+use anyhow::{anyhow, Context};
+use num_bigint::BigUint;
+use rand::RngCore;
+use rsa::{traits::PublicKeyParts, Pss, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha384};
+use tracing::info;
+
+use crate::{
+    add_encryption_trait_impl,
+    crypto::{
+        rsa::{
+            diagnosis::{mgf1, xor},
+            key,
+        },
+        EncryptionDto,
+    },
+    enums::{Digest, KeyFormat, Pkcs, TextEncoding},
+    errors::{Error, Result},
+    rng,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlindVariant {
+    #[serde(rename = "RSABSSA-SHA384-PSS-Randomized")]
+    PssRandomized,
+    #[serde(rename = "RSABSSA-SHA384-PSSZERO-Randomized")]
+    PssZeroRandomized,
+    #[serde(rename = "RSABSSA-SHA384-PSS-Deterministic")]
+    PssDeterministic,
+    #[serde(rename = "RSABSSA-SHA384-PSSZERO-Deterministic")]
+    PssZeroDeterministic,
+}
+
+impl BlindVariant {
+    fn salt_len(self) -> usize {
+        match self {
+            BlindVariant::PssRandomized | BlindVariant::PssDeterministic => 48,
+            _ => 0,
+        }
+    }
+
+    fn randomized(self) -> bool {
+        matches!(
+            self,
+            BlindVariant::PssRandomized | BlindVariant::PssZeroRandomized
+        )
+    }
+}
+
+add_encryption_trait_impl!(RsaBlindDto {
+    pkcs: Pkcs,
+    format: KeyFormat,
+    variant: BlindVariant
+});
+
+add_encryption_trait_impl!(RsaFinalizeDto {
+    pkcs: Pkcs,
+    format: KeyFormat,
+    variant: BlindVariant,
+    blind_signature: String,
+    inverse: String
+});
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlindOutput {
+    /// the input with its random prefix for randomized variants
+    pub prepared_message: String,
+    pub blinded_message: String,
+    /// kept by the client until finalization
+    pub inverse: String,
+}
+
+/// Client side.
+#[tauri::command]
+pub fn rsa_blind(data: RsaBlindDto) -> Result<BlindOutput> {
+    let message = data.get_input()?;
+    info!(
+        "rsa blind, variant: {:?}, size: {}",
+        data.variant,
+        message.len()
+    );
+    let public_key =
+        key::bytes_to_public_key(&data.get_key()?, data.pkcs, data.format)?;
+    let prepared = prepare(&message, data.variant);
+    let (blinded, inverse) = blind(&public_key, &prepared, data.variant)?;
+    let encoding = data.get_output_encoding();
+    Ok(BlindOutput {
+        prepared_message: encoding.encode(&prepared)?,
+        blinded_message: encoding.encode(&blinded)?,
+        inverse: encoding.encode(&inverse)?,
+    })
+}
+
+/// Signer side.
+#[tauri::command]
+pub fn rsa_blind_sign(data: RsaBlindDto) -> Result<String> {
+    let blinded = data.get_input()?;
+    info!("rsa blind sign, size: {}", blinded.len());
+    let private_key =
+        key::bytes_to_private_key(&data.get_key()?, data.pkcs, data.format)?;
+    let k = private_key.size();
+    if blinded.len() != k {
+        return Err(Error::Unsupported(format!(
+            "blinded message must be {} bytes, got {}",
+            k,
+            blinded.len()
+        )));
+    }
+    let m = rsa::BigUint::from_bytes_be(&blinded);
+    if &m >= private_key.n() {
+        return Err(Error::Unsupported(
+            "blinded message is not smaller than the modulus".to_string(),
+        ));
+    }
+    let s = rsa::hazmat::rsa_decrypt_and_check(
+        &private_key,
+        Some(&mut rng::rng()),
+        &m,
+    )
+    .context("rsa blind sign failed")?;
+    data.get_output_encoding()
+        .encode(&i2osp(&s.to_bytes_be(), k))
+}
+
+/// Client side, checks the unblinded signature.
+#[tauri::command]
+pub fn rsa_blind_finalize(data: RsaFinalizeDto) -> Result<String> {
+    let prepared = data.get_input()?;
+    info!("rsa blind finalize, variant: {:?}", data.variant);
+    let public_key =
+        key::bytes_to_public_key(&data.get_key()?, data.pkcs, data.format)?;
+    let blind_signature = data.input_encoding.decode(&data.blind_signature)?;
+    let inverse = data.input_encoding.decode(&data.inverse)?;
+    let signature = finalize(
+        &public_key,
+        &prepared,
+        &blind_signature,
+        &inverse,
+        data.variant,
+    )?;
+    data.get_output_encoding().encode(&signature)
+}
+
+#[tauri::command]
+pub fn rsa_blind_verify(
+    data: RsaBlindDto,
+    signature: String,
+    signature_encoding: TextEncoding,
+) -> Result<bool> {
+    let prepared = data.get_input()?;
+    let public_key =
+        key::bytes_to_public_key(&data.get_key()?, data.pkcs, data.format)?;
+    let signature = signature_encoding.decode(&signature)?;
+    Ok(verify(&public_key, &prepared, &signature, data.variant))
+}
+
+fn prepare(message: &[u8], variant: BlindVariant) -> Vec<u8> {
+    if !variant.randomized() {
+        return message.to_vec();
+    }
+    let mut prepared = vec![0u8; 32];
+    rng::rng().fill_bytes(&mut prepared);
+    prepared.extend_from_slice(message);
+    prepared
+}
+
+fn blind(
+    public_key: &RsaPublicKey,
+    prepared: &[u8],
+    variant: BlindVariant,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let k = public_key.size();
+    let n = BigUint::from_bytes_be(&public_key.n().to_bytes_be());
+    let e = BigUint::from_bytes_be(&public_key.e().to_bytes_be());
+
+    let mut salt = vec![0u8; variant.salt_len()];
+    rng::rng().fill_bytes(&mut salt);
+    let em = emsa_pss_encode(
+        &Sha384::digest(prepared),
+        public_key.n().bits() - 1,
+        &salt,
+    )?;
+    let m = BigUint::from_bytes_be(&em);
+    if m.modinv(&n).is_none() {
+        return Err(Error::Unsupported(
+            "encoded message is not coprime with the modulus".to_string(),
+        ));
+    }
+
+    let mut rng = rng::rng();
+    let mut bytes = vec![0u8; k];
+    let (r, inverse) = loop {
+        rng.fill_bytes(&mut bytes);
+        let r = BigUint::from_bytes_be(&bytes) % &n;
+        if let Some(inverse) = r.modinv(&n) {
+            break (r, inverse);
+        }
+    };
+    let x = m * r.modpow(&e, &n) % &n;
+    Ok((i2osp(&x.to_bytes_be(), k), i2osp(&inverse.to_bytes_be(), k)))
+}
+
+fn finalize(
+    public_key: &RsaPublicKey,
+    prepared: &[u8],
+    blind_signature: &[u8],
+    inverse: &[u8],
+    variant: BlindVariant,
+) -> Result<Vec<u8>> {
+    let k = public_key.size();
+    if blind_signature.len() != k {
+        return Err(Error::Unsupported(format!(
+            "blind signature must be {} bytes, got {}",
+            k,
+            blind_signature.len()
+        )));
+    }
+    let n = BigUint::from_bytes_be(&public_key.n().to_bytes_be());
+    let z = BigUint::from_bytes_be(blind_signature);
+    let s = z * BigUint::from_bytes_be(inverse) % &n;
+    let signature = i2osp(&s.to_bytes_be(), k);
+    if !verify(public_key, prepared, &signature, variant) {
+        return Err(anyhow!("unblinded signature does not verify").into());
+    }
+    Ok(signature)
+}
+
+fn verify(
+    public_key: &RsaPublicKey,
+    prepared: &[u8],
+    signature: &[u8],
+    variant: BlindVariant,
+) -> bool {
+    public_key
+        .verify(
+            Pss::new_with_salt::<Sha384>(variant.salt_len()),
+            &Sha384::digest(prepared),
+            signature,
+        )
+        .is_ok()
+}
+
+fn emsa_pss_encode(
+    m_hash: &[u8],
+    em_bits: usize,
+    salt: &[u8],
+) -> Result<Vec<u8>> {
+    let h_len = m_hash.len();
+    let em_len = (em_bits + 7) / 8;
+    if em_len < h_len + salt.len() + 2 {
+        return Err(Error::Unsupported(
+            "key is too small for the pss encoding".to_string(),
+        ));
+    }
+    let mut hasher = Digest::Sha384.as_digest();
+    hasher.update(&[0u8; 8]);
+    hasher.update(m_hash);
+    hasher.update(salt);
+    let h = hasher.finalize();
+
+    let mut db = vec![0u8; em_len - salt.len() - h_len - 2];
+    db.push(0x01);
+    db.extend_from_slice(salt);
+    let mut masked_db = xor(&db, &mgf1(Digest::Sha384, &h, db.len()));
+    masked_db[0] &= 0xff >> (8 * em_len - em_bits);
+
+    let mut em = masked_db;
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    Ok(em)
+}
+
+fn i2osp(bytes: &[u8], length: usize) -> Vec<u8> {
+    let mut output = vec![0u8; length];
+    output[length - bytes.len() ..].copy_from_slice(bytes);
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        rsa_blind, rsa_blind_finalize, rsa_blind_sign, BlindVariant,
+        RsaBlindDto, RsaFinalizeDto,
+    };
+    use crate::enums::{KeyFormat, Pkcs, TextEncoding};
+
+    // pkcs#1 pems, whatever the fixture names say
+    const PRIVATE_KEY: &str =
+        include_str!("../../tests/rsa/pkcs8_private_key.pem");
+    const PUBLIC_KEY: &str =
+        include_str!("../../tests/rsa/pkcs8_public_key.pem");
+
+    fn dto(input: &str, key: &str, variant: BlindVariant) -> RsaBlindDto {
+        RsaBlindDto {
+            input: input.to_string(),
+            input_encoding: TextEncoding::Hex,
+            key: key.to_string(),
+            key_encoding: TextEncoding::Utf8,
+            input_path: None,
+            key_path: None,
+            output_encoding: TextEncoding::Hex,
+            pkcs: Pkcs::Pkcs1,
+            format: KeyFormat::Pem,
+            variant,
+        }
+    }
+
+    fn blind_sign(message: &[u8], variant: BlindVariant) -> String {
+        let hex = |bytes: &[u8]| TextEncoding::Hex.encode(bytes).unwrap();
+        let blinded =
+            rsa_blind(dto(&hex(message), PUBLIC_KEY, variant)).unwrap();
+        let blind_signature =
+            rsa_blind_sign(dto(&blinded.blinded_message, PRIVATE_KEY, variant))
+                .unwrap();
+        rsa_blind_finalize(RsaFinalizeDto {
+            input: blinded.prepared_message,
+            input_encoding: TextEncoding::Hex,
+            key: PUBLIC_KEY.to_string(),
+            key_encoding: TextEncoding::Utf8,
+            input_path: None,
+            key_path: None,
+            output_encoding: TextEncoding::Hex,
+            pkcs: Pkcs::Pkcs1,
+            format: KeyFormat::Pem,
+            variant,
+            blind_signature,
+            inverse: blinded.inverse,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rsa_blind_signature() {
+        for variant in [
+            BlindVariant::PssRandomized,
+            BlindVariant::PssZeroRandomized,
+            BlindVariant::PssDeterministic,
+            BlindVariant::PssZeroDeterministic,
+        ] {
+            blind_sign(b"privacy pass token", variant);
+        }
+
+        // the signer refuses what is not a blinded message under its key
+        let variant = BlindVariant::PssDeterministic;
+        assert!(rsa_blind_sign(dto("00", PRIVATE_KEY, variant)).is_err());
+        assert!(rsa_blind_sign(dto(&"ff".repeat(256), PRIVATE_KEY, variant))
+            .is_err());
+    }
+
+    // without salt or prefix the unblinded signature is plain RSASSA-PSS,
+    // checked against pyca/cryptography: PSS, SHA-384, MGF1-SHA-384, salt 0
+    #[test]
+    fn test_rsa_blind_signature_deterministic() {
+        assert_eq!(
+            blind_sign(b"kits blind token", BlindVariant::PssZeroDeterministic),
+            "af29d07957439f7ea72dbc8f44db31272b29e3a423c53aa9fee110ac090d5748\
+             9286560c10d9117d7103084b7f802a832e0823a39e947b5f2e91a7a1d488d4e3\
+             e71f10985ecb7aa072342a5798421fe3a3ca6c3b53463f29f315b80be0d4fbed\
+             7980e513f745541aed0a1bda493e0e835d234d6c7ddde8c332275e38c67d8b1d\
+             9b8b3dad877eee48103152619b03059e217a02f6abe71f05f2f0f838fea9fbbf\
+             8f6daac1fd12dc461a145cc470f6aa7f069d90d6194297bc8dbf2994ac443828\
+             4c27174d563b4b35e4d1290dfb2b41f5c630adc479476e0de1211da801021155\
+             8796f0c3026b96487ceb97b9b249007d5981e844d5bcc2931808ae88e49917d5"
+        );
+    }
+}
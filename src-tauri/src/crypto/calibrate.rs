@@ -0,0 +1,205 @@
+//! Password hashing costs grown on this machine until a derivation takes
+//! about the target latency, never below the OWASP floors.
+
+use std::{collections::BTreeMap, time::Instant};
+
+use anyhow::anyhow;
+use argon2::{Algorithm, Argon2, Version};
+use serde::Serialize;
+use tracing::info;
+
+use crate::errors::{Error, Result};
+
+const PASSWORD: &[u8] = b"calibration password";
+const SALT: &[u8] = b"calibration salt";
+const OUTPUT_SIZE: usize = 32;
+
+const PBKDF2_PROBE: u32 = 10_000;
+const PBKDF2_FLOOR: u32 = 600_000;
+/// log2 of N, 32 MiB to 1 GiB of memory at r = 8
+const SCRYPT_LOG_N: (u8, u8) = (15, 20);
+/// KiB, 19 MiB to 1 GiB
+const ARGON2_MEMORY: (u32, u32) = (19 * 1024, 1024 * 1024);
+const ARGON2_MAX_TIME: u32 = 10;
+const ARGON2_MAX_LANES: usize = 4;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfRecommendation {
+    pub algorithm: &'static str,
+    pub parameters: BTreeMap<&'static str, u32>,
+    /// milliseconds one derivation took with these parameters
+    pub measured: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfCalibration {
+    pub target_ms: u64,
+    pub recommendations: Vec<KdfRecommendation>,
+    /// parameters held at their floor or cap instead of the target
+    pub notes: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn calibrate_kdf(target_ms: u64) -> Result<KdfCalibration> {
+    if !(10 ..= 10_000).contains(&target_ms) {
+        return Err(Error::Unsupported(format!(
+            "{} ms target, between 10 and 10000",
+            target_ms
+        )));
+    }
+    let target = target_ms as f64;
+    let mut notes = Vec::new();
+    let recommendations = vec![
+        pbkdf2(target, &mut notes),
+        scrypt(target, &mut notes)?,
+        argon2(target, &mut notes)?,
+    ];
+    info!(
+        "calibrate kdf, target: {} ms, {:?}",
+        target_ms, recommendations
+    );
+    Ok(KdfCalibration {
+        target_ms,
+        recommendations,
+        notes,
+    })
+}
+
+fn measure<F: FnMut() -> Result<()>>(mut derive: F) -> Result<f64> {
+    let started = Instant::now();
+    derive()?;
+    Ok(started.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// Linear in the iterations, timed once to scale and once to confirm.
+fn pbkdf2(target: f64, notes: &mut Vec<String>) -> KdfRecommendation {
+    let run = |iterations| {
+        measure(|| {
+            let mut output = [0u8; OUTPUT_SIZE];
+            pbkdf2::pbkdf2_hmac::<sha2::Sha256>(
+                PASSWORD,
+                SALT,
+                iterations,
+                &mut output,
+            );
+            Ok(())
+        })
+        .unwrap_or_default()
+    };
+    let probe = run(PBKDF2_PROBE).max(f64::EPSILON);
+    let scaled = (PBKDF2_PROBE as f64 * target / probe) as u32;
+    if scaled < PBKDF2_FLOOR {
+        notes.push(format!(
+            "pbkdf2 held at {} iterations, the target allows {}",
+            PBKDF2_FLOOR, scaled
+        ));
+    }
+    let iterations = scaled.max(PBKDF2_FLOOR);
+    KdfRecommendation {
+        algorithm: "pbkdf2-sha256",
+        parameters: BTreeMap::from([("iterations", iterations)]),
+        measured: run(iterations),
+    }
+}
+
+/// `N` doubles while the derivation stays within the target, r = 8, p = 1.
+fn scrypt(target: f64, notes: &mut Vec<String>) -> Result<KdfRecommendation> {
+    let run = |log_n: u8| {
+        let params = scrypt::Params::new(log_n, 8, 1, OUTPUT_SIZE)
+            .map_err(|err| anyhow!("invalid scrypt parameters: {}", err))?;
+        measure(|| {
+            let mut output = [0u8; OUTPUT_SIZE];
+            scrypt::scrypt(PASSWORD, SALT, &params, &mut output)
+                .map_err(|err| anyhow!("scrypt derive failed: {}", err))?;
+            Ok(())
+        })
+    };
+    let (floor, cap) = SCRYPT_LOG_N;
+    let mut log_n = floor;
+    let mut measured = run(log_n)?;
+    if measured > target {
+        notes.push(format!("scrypt held at log n {}", floor));
+    }
+    while log_n < cap && measured * 2.0 <= target {
+        log_n += 1;
+        measured = run(log_n)?;
+    }
+    if log_n == cap && measured * 2.0 <= target {
+        notes.push(format!("scrypt capped at log n {}, 1 GiB", cap));
+    }
+    Ok(KdfRecommendation {
+        algorithm: "scrypt",
+        parameters: BTreeMap::from([
+            ("logN", log_n as u32),
+            ("r", 8),
+            ("p", 1),
+        ]),
+        measured,
+    })
+}
+
+/// Memory doubles first, passes are added once it reaches its cap.
+fn argon2(target: f64, notes: &mut Vec<String>) -> Result<KdfRecommendation> {
+    let lanes = std::thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(ARGON2_MAX_LANES) as u32;
+    let run = |memory: u32, time: u32| {
+        let params = argon2::Params::new(memory, time, lanes, None)
+            .map_err(|err| anyhow!("invalid argon2 parameters: {}", err))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        measure(|| {
+            let mut output = [0u8; OUTPUT_SIZE];
+            argon2
+                .hash_password_into(PASSWORD, SALT, &mut output)
+                .map_err(|err| anyhow!("argon2 derive failed: {}", err))?;
+            Ok(())
+        })
+    };
+    let (floor, cap) = ARGON2_MEMORY;
+    let (mut memory, mut time) = (floor, 2);
+    let mut measured = run(memory, time)?;
+    if measured > target {
+        notes.push(format!("argon2id held at {} KiB and 2 passes", floor));
+    }
+    while memory < cap && measured * 2.0 <= target {
+        memory = (memory * 2).min(cap);
+        measured = run(memory, time)?;
+    }
+    while memory == cap
+        && time < ARGON2_MAX_TIME
+        && measured * (time + 1) as f64 / time as f64 <= target
+    {
+        time += 1;
+        measured = run(memory, time)?;
+    }
+    Ok(KdfRecommendation {
+        algorithm: "argon2id",
+        parameters: BTreeMap::from([("m", memory), ("t", time), ("p", lanes)]),
+        measured,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::calibrate_kdf;
+
+    #[tokio::test]
+    async fn test_calibrate_kdf() {
+        assert!(calibrate_kdf(1).await.is_err());
+
+        let calibration = calibrate_kdf(10).await.unwrap();
+        let algorithms = calibration
+            .recommendations
+            .iter()
+            .map(|recommendation| recommendation.algorithm)
+            .collect::<Vec<_>>();
+        assert_eq!(algorithms, ["pbkdf2-sha256", "scrypt", "argon2id"]);
+        // the floors win over a target this low
+        let parameters = &calibration.recommendations[0].parameters;
+        assert_eq!(parameters["iterations"], 600_000);
+        let parameters = &calibration.recommendations[2].parameters;
+        assert!(parameters["m"] >= 19 * 1024 && parameters["t"] >= 2);
+    }
+}
@@ -0,0 +1,206 @@
+use std::{fmt::Debug, time::Instant};
+
+use anyhow::Context;
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    ChaCha20Poly1305, KeyInit, XChaCha20Poly1305,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    add_encryption_trait_impl,
+    crypto::{commitment, EncryptionDto, OperationResult},
+    enums::TextEncoding,
+    errors::{Error, Result},
+    jwt::JwkeyUsage,
+    vault,
+};
+
+add_encryption_trait_impl!(ChachaEncryptionDto {
+    nonce: String,
+    nonce_encoding: TextEncoding,
+    aad: Option<String>,
+    aad_encoding: Option<TextEncoding>,
+    key_commitment: Option<bool>,
+    for_encryption: bool
+});
+
+impl Debug for ChachaEncryptionDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChachaEncryptionDto")
+            .field("input_encoding", &self.input_encoding)
+            .field("key_encoding", &self.key_encoding)
+            .field("output_encoding", &self.output_encoding)
+            .field("nonce", &self.nonce)
+            .field("nonce_encoding", &self.nonce_encoding)
+            .field("aad", &self.aad)
+            .field("aad_encoding", &self.aad_encoding)
+            .field("key_commitment", &self.key_commitment)
+            .field("for_encryption", &self.for_encryption)
+            .finish()
+    }
+}
+
+/// XChaCha20-Poly1305 when the nonce is 24 bytes, the tag follows the
+/// ciphertext.
+#[tauri::command]
+pub fn crypto_chacha20_poly1305(
+    data: ChachaEncryptionDto,
+) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    info!("chacha20-poly1305: {:?}", data);
+    let input = data.get_input()?;
+    let key = data.get_key()?;
+    vault::enforce_policy(&key, JwkeyUsage::Encryption, "C20P")?;
+    let nonce = data.nonce_encoding.decode(&data.nonce)?;
+    let aad = match (&data.aad, data.aad_encoding) {
+        (Some(aad), Some(encoding)) => encoding.decode(aad)?,
+        _ => vec![],
+    };
+    let committed = data.key_commitment.unwrap_or(false);
+    let output = if data.for_encryption {
+        let ciphertext = chacha(&key, &nonce, &aad, &input, true)?;
+        if committed {
+            commitment::commit(&key, ciphertext)?
+        } else {
+            ciphertext
+        }
+    } else {
+        let ciphertext = if committed {
+            commitment::open(&key, &input)?
+        } else {
+            input.as_slice()
+        };
+        chacha(&key, &nonce, &aad, ciphertext, false)?
+    };
+    Ok(
+        OperationResult::encoded(&output, data.get_output_encoding(), started)?
+            .metadata("cipher", match nonce.len() {
+                24 => "XChaCha20-Poly1305",
+                _ => "ChaCha20-Poly1305",
+            })
+            .metadata("keyCommitment", committed),
+    )
+}
+
+fn chacha(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    input: &[u8],
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    if key.len() != 32 {
+        return Err(Error::Unsupported(format!(
+            "chacha20-poly1305 keysize {}",
+            key.len()
+        )));
+    }
+    let payload = Payload { msg: input, aad };
+    let output =
+        match (nonce.len(), for_encryption) {
+            (12, true) => {
+                ChaCha20Poly1305::new(key.into()).encrypt(nonce.into(), payload)
+            }
+            (12, false) => {
+                ChaCha20Poly1305::new(key.into()).decrypt(nonce.into(), payload)
+            }
+            (24, true) => XChaCha20Poly1305::new(key.into())
+                .encrypt(nonce.into(), payload),
+            (24, false) => XChaCha20Poly1305::new(key.into())
+                .decrypt(nonce.into(), payload),
+            (size, _) => {
+                return Err(Error::Unsupported(format!(
+                    "chacha20-poly1305 nonce size {}",
+                    size
+                )))
+            }
+        };
+    Ok(output.ok().context(if for_encryption {
+        "chacha20-poly1305 encrypt failed"
+    } else {
+        "chacha20-poly1305 decrypt failed"
+    })?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crypto_chacha20_poly1305, ChachaEncryptionDto};
+    use crate::enums::TextEncoding;
+
+    const PLAINTEXT: &str = "Ladies and Gentlemen of the class of '99: If I \
+                             could offer you only one tip for the future, \
+                             sunscreen would be it.";
+
+    fn dto(
+        input: String,
+        input_encoding: TextEncoding,
+        output_encoding: TextEncoding,
+        key_commitment: bool,
+        for_encryption: bool,
+    ) -> ChachaEncryptionDto {
+        ChachaEncryptionDto {
+            input,
+            input_encoding,
+            key: "808182838485868788898a8b8c8d8e8f\
+                  909192939495969798999a9b9c9d9e9f"
+                .to_string(),
+            key_encoding: TextEncoding::Hex,
+            input_path: None,
+            key_path: None,
+            output_encoding,
+            nonce: "070000004041424344454647".to_string(),
+            nonce_encoding: TextEncoding::Hex,
+            aad: Some("50515253c0c1c2c3c4c5c6c7".to_string()),
+            aad_encoding: Some(TextEncoding::Hex),
+            key_commitment: Some(key_commitment),
+            for_encryption,
+        }
+    }
+
+    // RFC 8439 section 2.8.2
+    #[test]
+    fn test_chacha20_poly1305() {
+        let ciphertext = crypto_chacha20_poly1305(dto(
+            PLAINTEXT.to_string(),
+            TextEncoding::Utf8,
+            TextEncoding::Hex,
+            false,
+            true,
+        ))
+        .unwrap()
+        .output;
+        assert!(ciphertext.starts_with("d31a8d34648e60db7b86afbc53ef7ec2"));
+        assert!(ciphertext.ends_with("1ae10b594f09e26a7e902ecbd0600691"));
+
+        let committed = crypto_chacha20_poly1305(dto(
+            PLAINTEXT.to_string(),
+            TextEncoding::Utf8,
+            TextEncoding::Hex,
+            true,
+            true,
+        ))
+        .unwrap()
+        .output;
+        assert!(committed.ends_with(&ciphertext));
+        let plaintext = crypto_chacha20_poly1305(dto(
+            committed,
+            TextEncoding::Hex,
+            TextEncoding::Utf8,
+            true,
+            false,
+        ))
+        .unwrap()
+        .output;
+        assert_eq!(plaintext, PLAINTEXT);
+        assert!(crypto_chacha20_poly1305(dto(
+            ciphertext,
+            TextEncoding::Hex,
+            TextEncoding::Utf8,
+            true,
+            false,
+        ))
+        .is_err());
+    }
+}
@@ -0,0 +1,424 @@
+//! Caesar, Vigenère and xor ciphers and the statistics to break them.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+};
+
+/// Letter frequencies of english text, a to z.
+const ENGLISH: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015, 0.06094,
+    0.06966, 0.00153, 0.00772, 0.04025, 0.02406, 0.06749, 0.07507, 0.01929,
+    0.00095, 0.05987, 0.06327, 0.09056, 0.02758, 0.00978, 0.02360, 0.00150,
+    0.01974, 0.00074,
+];
+
+/// Index of coincidence of english text, random letters give 1 / 26.
+const ENGLISH_IC: f64 = 0.0667;
+
+const MAX_KEY_LENGTH: usize = 40;
+
+const MIN_COLUMN: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassicalCipher {
+    Caesar,
+    Vigenere,
+    Xor,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassicalDto {
+    pub cipher: ClassicalCipher,
+    pub input: String,
+    pub input_encoding: TextEncoding,
+    /// the shift for caesar, letters for vigenère and bytes for xor
+    pub key: String,
+    pub key_encoding: Option<TextEncoding>,
+    pub for_encryption: bool,
+    pub output_encoding: TextEncoding,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Frequency {
+    pub length: usize,
+    pub letters: usize,
+    /// occurrences of a to z, case folded
+    pub counts: Vec<u64>,
+    pub index_of_coincidence: f64,
+    /// chi-square distance of the letters to english, lower is closer
+    pub english_chi_square: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassicalCandidate {
+    /// the shift for caesar, letters for vigenère and hex for xor
+    pub key: String,
+    pub key_length: usize,
+    /// higher reads more like english
+    pub score: f64,
+    pub output: String,
+}
+
+#[tauri::command]
+pub fn classical_cipher(data: ClassicalDto) -> Result<String> {
+    info!(
+        "classical {:?}, encryption: {}",
+        data.cipher, data.for_encryption
+    );
+    let input = data.input_encoding.decode(&data.input)?;
+    let output = match data.cipher {
+        ClassicalCipher::Caesar => {
+            let shift = data.key.trim().parse::<i64>().map_err(|_| {
+                Error::Unsupported(format!("caesar shift `{}`", data.key))
+            })?;
+            let shift = shift.rem_euclid(26) as u8;
+            let shift = if data.for_encryption {
+                shift
+            } else {
+                (26 - shift) % 26
+            };
+            vigenere(&input, &[shift])
+        }
+        ClassicalCipher::Vigenere => {
+            let mut shifts = data
+                .key
+                .bytes()
+                .filter(u8::is_ascii_alphabetic)
+                .map(|letter| letter.to_ascii_lowercase() - b'a')
+                .collect::<Vec<_>>();
+            if shifts.is_empty() {
+                return Err(Error::Unsupported(
+                    "vigenère key without letters".to_string(),
+                ));
+            }
+            if !data.for_encryption {
+                shifts
+                    .iter_mut()
+                    .for_each(|shift| *shift = (26 - *shift) % 26);
+            }
+            vigenere(&input, &shifts)
+        }
+        ClassicalCipher::Xor => {
+            let key = data
+                .key_encoding
+                .unwrap_or(TextEncoding::Utf8)
+                .decode(&data.key)?;
+            if key.is_empty() {
+                return Err(Error::Unsupported("empty xor key".to_string()));
+            }
+            xor(&input, &key)
+        }
+    };
+    data.output_encoding.encode(&output)
+}
+
+#[tauri::command]
+pub fn frequency_analysis(
+    input: String,
+    encoding: TextEncoding,
+) -> Result<Frequency> {
+    let input = encoding.decode(&input)?;
+    let counts = letter_counts(&input);
+    Ok(Frequency {
+        length: input.len(),
+        letters: counts.iter().sum::<u64>() as usize,
+        index_of_coincidence: index_of_coincidence(&counts),
+        english_chi_square: english_chi_square(&counts),
+        counts: counts.to_vec(),
+    })
+}
+
+/// Caesar candidates rank by how english they read, vigenère and xor ones
+/// by key length.
+#[tauri::command]
+pub fn crack_classical(
+    cipher: ClassicalCipher,
+    input: String,
+    input_encoding: TextEncoding,
+    max_key_length: Option<usize>,
+    output_encoding: TextEncoding,
+) -> Result<Vec<ClassicalCandidate>> {
+    let input = input_encoding.decode(&input)?;
+    let max_key_length = max_key_length.unwrap_or(MAX_KEY_LENGTH).max(1);
+    info!("crack {:?}, size: {}", cipher, input.len());
+    if input.is_empty() {
+        return Err(Error::Unsupported("empty ciphertext".to_string()));
+    }
+    let letters = input
+        .iter()
+        .filter(|byte| byte.is_ascii_alphabetic())
+        .map(u8::to_ascii_lowercase)
+        .collect::<Vec<_>>();
+    let candidates = match cipher {
+        ClassicalCipher::Caesar => {
+            let mut candidates = (0 .. 26)
+                .map(|shift| {
+                    let plaintext = vigenere(&input, &[(26 - shift) % 26]);
+                    (shift.to_string(), 1, plaintext)
+                })
+                .collect::<Vec<_>>();
+            candidates.sort_by(|a, b| {
+                english_score(&b.2).total_cmp(&english_score(&a.2))
+            });
+            candidates
+        }
+        ClassicalCipher::Vigenere => key_lengths(&letters, max_key_length)
+            .into_iter()
+            .map(|length| {
+                let shifts = (0 .. length)
+                    .map(|column| {
+                        crack_caesar(&columns(&letters, length)[column])
+                    })
+                    .collect::<Vec<_>>();
+                let key = shifts
+                    .iter()
+                    .map(|shift| (b'a' + shift) as char)
+                    .collect::<String>();
+                let inverse = shifts
+                    .iter()
+                    .map(|shift| (26 - shift) % 26)
+                    .collect::<Vec<_>>();
+                (key, length, vigenere(&input, &inverse))
+            })
+            .collect(),
+        ClassicalCipher::Xor => key_lengths(&input, max_key_length)
+            .into_iter()
+            .map(|length| {
+                let key = columns(&input, length)
+                    .iter()
+                    .map(|column| crack_single_xor(column))
+                    .collect::<Vec<_>>();
+                let plaintext = xor(&input, &key);
+                Ok((TextEncoding::Hex.encode(&key)?, length, plaintext))
+            })
+            .collect::<Result<_>>()?,
+    };
+    candidates
+        .into_iter()
+        .map(|(key, key_length, plaintext)| {
+            Ok(ClassicalCandidate {
+                key,
+                key_length,
+                score: english_score(&plaintext),
+                output: output_encoding.encode(&plaintext)?,
+            })
+        })
+        .collect()
+}
+
+/// Other bytes pass through without consuming the key.
+fn vigenere(input: &[u8], shifts: &[u8]) -> Vec<u8> {
+    let mut shifts = shifts.iter().cycle();
+    input
+        .iter()
+        .map(|byte| {
+            let base = match byte {
+                b'a' ..= b'z' => b'a',
+                b'A' ..= b'Z' => b'A',
+                _ => return *byte,
+            };
+            let shift = shifts.next().copied().unwrap_or_default();
+            base + (byte - base + shift) % 26
+        })
+        .collect()
+}
+
+fn xor(input: &[u8], key: &[u8]) -> Vec<u8> {
+    input
+        .iter()
+        .zip(key.iter().cycle())
+        .map(|(byte, key)| byte ^ key)
+        .collect()
+}
+
+fn letter_counts(input: &[u8]) -> [u64; 26] {
+    let mut counts = [0u64; 26];
+    input
+        .iter()
+        .filter(|byte| byte.is_ascii_alphabetic())
+        .for_each(|byte| {
+            counts[(byte.to_ascii_lowercase() - b'a') as usize] += 1
+        });
+    counts
+}
+
+fn index_of_coincidence(counts: &[u64]) -> f64 {
+    let total = counts.iter().sum::<u64>() as f64;
+    if total < 2.0 {
+        return 0.0;
+    }
+    counts
+        .iter()
+        .map(|count| *count as f64 * (*count as f64 - 1.0))
+        .sum::<f64>()
+        / (total * (total - 1.0))
+}
+
+fn english_chi_square(counts: &[u64; 26]) -> f64 {
+    let total = counts.iter().sum::<u64>() as f64;
+    counts
+        .iter()
+        .zip(ENGLISH)
+        .map(|(count, frequency)| {
+            let expected = total * frequency;
+            (*count as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+fn english_score(input: &[u8]) -> f64 {
+    if input.is_empty() {
+        return 0.0;
+    }
+    input
+        .iter()
+        .map(|byte| match byte {
+            b' ' => 0.13,
+            b'a' ..= b'z' => ENGLISH[(byte - b'a') as usize],
+            b'A' ..= b'Z' => ENGLISH[(byte - b'A') as usize] * 0.5,
+            b'\n' | b'\r' | b'\t' => 0.0,
+            0x21 ..= 0x7e => 0.005,
+            _ => -0.1,
+        })
+        .sum::<f64>()
+        / input.len() as f64
+}
+
+fn columns(input: &[u8], length: usize) -> Vec<Vec<u8>> {
+    (0 .. length)
+        .map(|column| {
+            input.iter().skip(column).step_by(length).copied().collect()
+        })
+        .collect()
+}
+
+/// Up to three key lengths within 80% of the best index of coincidence,
+/// shortest first so the key wins over its own repetitions.
+fn key_lengths(input: &[u8], max_key_length: usize) -> Vec<usize> {
+    let coincidences = (1 ..= max_key_length
+        .min(input.len() / MIN_COLUMN)
+        .max(1))
+        .map(|length| {
+            let coincidence = columns(input, length)
+                .iter()
+                .map(|column| {
+                    let mut counts = [0u64; 256];
+                    column.iter().for_each(|byte| counts[*byte as usize] += 1);
+                    index_of_coincidence(&counts)
+                })
+                .sum::<f64>()
+                / length as f64;
+            (length, coincidence)
+        })
+        .collect::<Vec<_>>();
+    let best = coincidences
+        .iter()
+        .map(|(_, coincidence)| *coincidence)
+        .fold(0.0, f64::max);
+    coincidences
+        .into_iter()
+        .filter(|(_, coincidence)| *coincidence >= best * 0.8)
+        .map(|(length, _)| length)
+        .take(3)
+        .collect()
+}
+
+fn crack_caesar(column: &[u8]) -> u8 {
+    let counts = letter_counts(column);
+    (0 .. 26u8)
+        .min_by(|a, b| {
+            let unshifted = |shift: u8| {
+                let mut unshifted = [0u64; 26];
+                for (index, count) in counts.iter().enumerate() {
+                    unshifted[(index + 26 - shift as usize) % 26] = *count;
+                }
+                english_chi_square(&unshifted)
+            };
+            unshifted(*a).total_cmp(&unshifted(*b))
+        })
+        .unwrap_or_default()
+}
+
+fn crack_single_xor(column: &[u8]) -> u8 {
+    (0 ..= 255u8)
+        .max_by(|a, b| {
+            english_score(&xor(column, &[*a]))
+                .total_cmp(&english_score(&xor(column, &[*b])))
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        classical_cipher, crack_classical, ClassicalCipher, ClassicalDto,
+    };
+    use crate::enums::TextEncoding;
+
+    const PLAINTEXT: &str =
+        "Alice was beginning to get very tired of sitting by her sister on \
+         the bank, and of having nothing to do: once or twice she had peeped \
+         into the book her sister was reading, but it had no pictures or \
+         conversations in it, and what is the use of a book, thought Alice \
+         without pictures or conversations? So she was considering in her own \
+         mind (as well as she could, for the hot day made her feel very \
+         sleepy and stupid), whether the pleasure of making a daisy-chain \
+         would be worth the trouble of getting up and picking the daisies, \
+         when suddenly a White Rabbit with pink eyes ran close by her.";
+
+    fn encrypt(cipher: ClassicalCipher, key: &str) -> String {
+        classical_cipher(ClassicalDto {
+            cipher,
+            input: PLAINTEXT.to_string(),
+            input_encoding: TextEncoding::Utf8,
+            key: key.to_string(),
+            key_encoding: None,
+            for_encryption: true,
+            output_encoding: TextEncoding::Hex,
+        })
+        .unwrap()
+    }
+
+    fn crack(cipher: ClassicalCipher, ciphertext: String) -> (String, String) {
+        let best = crack_classical(
+            cipher,
+            ciphertext,
+            TextEncoding::Hex,
+            None,
+            TextEncoding::Utf8,
+        )
+        .unwrap()
+        .remove(0);
+        (best.key, best.output)
+    }
+
+    #[test]
+    fn test_caesar() {
+        let ciphertext = encrypt(ClassicalCipher::Caesar, "3");
+        assert!(ciphertext.starts_with("446f6c6668"));
+        let (key, plaintext) = crack(ClassicalCipher::Caesar, ciphertext);
+        assert_eq!((key.as_str(), plaintext.as_str()), ("3", PLAINTEXT));
+    }
+
+    #[test]
+    fn test_vigenere() {
+        let ciphertext = encrypt(ClassicalCipher::Vigenere, "Dickens");
+        let (key, plaintext) = crack(ClassicalCipher::Vigenere, ciphertext);
+        assert_eq!((key.as_str(), plaintext.as_str()), ("dickens", PLAINTEXT));
+    }
+
+    #[test]
+    fn test_xor() {
+        let ciphertext = encrypt(ClassicalCipher::Xor, "ICE");
+        let (key, plaintext) = crack(ClassicalCipher::Xor, ciphertext);
+        assert_eq!((key.as_str(), plaintext.as_str()), ("494345", PLAINTEXT));
+    }
+}
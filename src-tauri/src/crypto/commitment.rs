@@ -0,0 +1,58 @@
+//! Key commitment for aead outputs: HMAC-SHA256(key, label) in front of the
+//! ciphertext, so gcm, chacha20-poly1305 and siv ciphertexts cannot be made
+//! to open under two keys.
+
+use hkdf::hmac::{digest::KeyInit, Hmac, Mac};
+use sha2::Sha256;
+
+use crate::errors::{Error, Result};
+
+pub(crate) const COMMITMENT_SIZE: usize = 32;
+
+const LABEL: &[u8] = b"kits key commitment v1";
+
+fn commitment(key: &[u8]) -> Result<Hmac<Sha256>> {
+    let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(key)
+        .map_err(|_| Error::Unsupported("commitment key size".to_string()))?;
+    mac.update(LABEL);
+    Ok(mac)
+}
+
+pub(crate) fn commit(key: &[u8], ciphertext: Vec<u8>) -> Result<Vec<u8>> {
+    let mut output = commitment(key)?.finalize().into_bytes().to_vec();
+    output.extend(ciphertext);
+    Ok(output)
+}
+
+/// Constant time.
+pub(crate) fn open<'a>(key: &[u8], input: &'a [u8]) -> Result<&'a [u8]> {
+    if input.len() < COMMITMENT_SIZE {
+        return Err(Error::Unsupported(format!(
+            "{} bytes is shorter than the key commitment",
+            input.len()
+        )));
+    }
+    let (tag, ciphertext) = input.split_at(COMMITMENT_SIZE);
+    commitment(key)?.verify_slice(tag).map_err(|_| {
+        Error::Unsupported(
+            "key commitment mismatch, the ciphertext was not made under this \
+             key"
+            .to_string(),
+        )
+    })?;
+    Ok(ciphertext)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{commit, open, COMMITMENT_SIZE};
+
+    #[test]
+    fn test_commitment() {
+        let committed = commit(&[1; 16], b"ciphertext".to_vec()).unwrap();
+        assert_eq!(committed.len(), COMMITMENT_SIZE + 10);
+        assert_eq!(open(&[1; 16], &committed).unwrap(), b"ciphertext");
+        assert!(open(&[2; 16], &committed).is_err());
+        assert!(open(&[1; 16], &committed[.. 8]).is_err());
+    }
+}
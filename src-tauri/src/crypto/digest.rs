@@ -0,0 +1,514 @@
+use std::{fmt::Debug, path::Path, time::Instant};
+
+use anyhow::Context;
+use base64ct::{Base64, Encoding};
+use digest::{ExtendableOutput, Update};
+use serde::{Deserialize, Serialize};
+use sha3::{CShake128Core, CShake256Core};
+use tracing::info;
+
+use super::OperationResult;
+use crate::{
+    enums::{Digest, TextEncoding, Xof},
+    errors::{Error, Result},
+    profile::{self, Algorithm},
+};
+
+#[tauri::command]
+pub async fn digest(
+    digest: Digest,
+    input: String,
+    input_encoding: TextEncoding,
+    output_encoding: TextEncoding,
+) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    info!("digest: {:?}, size: {}", digest, input.len());
+    profile::check(Algorithm::Digest(digest))?;
+    let input = input_encoding.decode(&input)?;
+    Ok(OperationResult::encoded(
+        &digest_inner(digest, &input),
+        output_encoding,
+        started,
+    )?
+    .metadata("digest", format!("{:?}", digest))
+    .metadata("inputLength", input.len()))
+}
+
+pub(crate) fn digest_inner(digest: Digest, input: &[u8]) -> Vec<u8> {
+    let mut hasher = digest.as_digest();
+    hasher.update(input);
+    hasher.finalize().to_vec()
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XofDto {
+    pub xof: Xof,
+    pub input: String,
+    pub input_encoding: TextEncoding,
+    pub key: Option<String>,
+    pub key_encoding: Option<TextEncoding>,
+    pub customization: Option<String>,
+    pub customization_encoding: Option<TextEncoding>,
+    pub function_name: Option<String>,
+    pub output_length: usize,
+    pub output_encoding: TextEncoding,
+}
+
+impl Debug for XofDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XofDto")
+            .field("xof", &self.xof)
+            .field("input", &self.input.len())
+            .field("input_encoding", &self.input_encoding)
+            .field("key_encoding", &self.key_encoding)
+            .field("customization_encoding", &self.customization_encoding)
+            .field("function_name", &self.function_name)
+            .field("output_length", &self.output_length)
+            .field("output_encoding", &self.output_encoding)
+            .finish()
+    }
+}
+
+/// SHAKE, cSHAKE (FIPS 202, SP 800-185) and KMAC.
+#[tauri::command]
+pub async fn xof(data: XofDto) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    info!("xof: {:?}", data);
+    let input = data.input_encoding.decode(&data.input)?;
+    let customization = match (&data.customization, data.customization_encoding)
+    {
+        (Some(customization), Some(encoding)) => {
+            encoding.decode(customization)?
+        }
+        (Some(customization), None) => customization.as_bytes().to_vec(),
+        _ => vec![],
+    };
+    let function_name = data.function_name.clone().unwrap_or_default();
+    let key = || -> Result<Vec<u8>> {
+        match (&data.key, data.key_encoding) {
+            (Some(key), Some(encoding)) => encoding.decode(key),
+            _ => Err(Error::Unsupported("kmac without key".to_string())),
+        }
+    };
+    let length = data.output_length;
+    let output = match data.xof {
+        Xof::Shake128 => xof_inner(sha3::Shake128::default(), &input, length),
+        Xof::Shake256 => xof_inner(sha3::Shake256::default(), &input, length),
+        Xof::CShake128 => xof_inner(
+            sha3::CShake128::from_core(CShake128Core::new_with_function_name(
+                function_name.as_bytes(),
+                &customization,
+            )),
+            &input,
+            length,
+        ),
+        Xof::CShake256 => xof_inner(
+            sha3::CShake256::from_core(CShake256Core::new_with_function_name(
+                function_name.as_bytes(),
+                &customization,
+            )),
+            &input,
+            length,
+        ),
+        Xof::Kmac128 | Xof::KmacXof128 => xof_inner(
+            sha3::CShake128::from_core(CShake128Core::new_with_function_name(
+                b"KMAC",
+                &customization,
+            )),
+            &kmac_input(&key()?, &input, 168, length, data.xof == Xof::Kmac128),
+            length,
+        ),
+        Xof::Kmac256 | Xof::KmacXof256 => xof_inner(
+            sha3::CShake256::from_core(CShake256Core::new_with_function_name(
+                b"KMAC",
+                &customization,
+            )),
+            &kmac_input(&key()?, &input, 136, length, data.xof == Xof::Kmac256),
+            length,
+        ),
+    };
+    Ok(
+        OperationResult::encoded(&output, data.output_encoding, started)?
+            .metadata("xof", format!("{:?}", data.xof)),
+    )
+}
+
+fn xof_inner<X: Update + ExtendableOutput>(
+    mut hasher: X,
+    input: &[u8],
+    length: usize,
+) -> Vec<u8> {
+    let mut output = vec![0u8; length];
+    hasher.update(input);
+    hasher.finalize_xof_into(&mut output);
+    output
+}
+
+/// bytepad(encode_string(K), rate) || X || right_encode(L), where L is 0
+/// for the XOF variants.
+fn kmac_input(
+    key: &[u8],
+    input: &[u8],
+    rate: usize,
+    length: usize,
+    fixed_length: bool,
+) -> Vec<u8> {
+    let mut padded = left_encode(rate as u64);
+    padded.extend(left_encode(key.len() as u64 * 8));
+    padded.extend_from_slice(key);
+    padded.resize(padded.len().div_ceil(rate) * rate, 0);
+    padded.extend_from_slice(input);
+    padded.extend(right_encode(if fixed_length {
+        length as u64 * 8
+    } else {
+        0
+    }));
+    padded
+}
+
+fn encode_integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    bytes[skip ..].to_vec()
+}
+
+fn left_encode(value: u64) -> Vec<u8> {
+    let bytes = encode_integer(value);
+    [vec![bytes.len() as u8], bytes].concat()
+}
+
+fn right_encode(value: u64) -> Vec<u8> {
+    let bytes = encode_integer(value);
+    let size = bytes.len() as u8;
+    [bytes, vec![size]].concat()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestCheckDto {
+    /// the file unnamed digests are checked against, or the directory names
+    /// are relative to
+    pub path: Option<String>,
+    /// checked instead of a file when there is no path
+    pub input: Option<String>,
+    pub input_encoding: Option<TextEncoding>,
+    /// `sha256sum` or bsd style lines, sri tokens or bare hex digests
+    pub expected: String,
+    /// the digest of hex entries, guessed from their length otherwise
+    pub digest: Option<Digest>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestCheck {
+    pub line: usize,
+    /// the file the entry names, absent for sri tokens and bare digests
+    pub name: Option<String>,
+    pub digest: Option<Digest>,
+    pub expected: String,
+    /// hex digest of the data, absent when it could not be read
+    pub actual: Option<String>,
+    pub matched: bool,
+    pub error: Option<String>,
+}
+
+struct ChecksumEntry<'a> {
+    line: usize,
+    name: Option<&'a str>,
+    digest: Option<Digest>,
+    expected: &'a str,
+    sri: bool,
+}
+
+/// One missing file does not hide the other entries.
+#[tauri::command]
+pub async fn verify_digest(data: DigestCheckDto) -> Result<Vec<DigestCheck>> {
+    info!(
+        "verify digest, path: {:?}, size: {}",
+        data.path,
+        data.expected.len()
+    );
+    let path = data.path.as_deref().map(Path::new);
+    let directory = path.filter(|path| path.is_dir());
+    // the data unnamed entries are checked against
+    let subject = match (path, &data.input, data.input_encoding) {
+        (Some(path), ..) if directory.is_none() => Some(
+            std::fs::read(path)
+                .with_context(|| format!("read {} failed", path.display()))?,
+        ),
+        (None, Some(input), encoding) => {
+            Some(encoding.unwrap_or(TextEncoding::Utf8).decode(input)?)
+        }
+        _ => None,
+    };
+    let entries = checksum_entries(&data.expected);
+    if entries.is_empty() {
+        return Err(Error::Unsupported("no checksum entry".to_string()));
+    }
+    let mut checks = vec![];
+    for entry in entries {
+        let digest = entry.digest.or(data.digest).or_else(|| {
+            (!entry.sri).then(|| guess_digest(entry.expected)).flatten()
+        });
+        let check = |actual: Option<String>, matched, error| DigestCheck {
+            line: entry.line,
+            name: entry.name.map(String::from),
+            digest,
+            expected: entry.expected.to_string(),
+            actual,
+            matched,
+            error,
+        };
+        let Some(digest) = digest else {
+            checks.push(check(None, false, Some("unknown digest".into())));
+            continue;
+        };
+        let expected = if entry.sri {
+            Base64::decode_vec(entry.expected).ok()
+        } else {
+            TextEncoding::Hex
+                .decode(&entry.expected.to_ascii_lowercase())
+                .ok()
+        };
+        let Some(expected) = expected else {
+            checks.push(check(None, false, Some("malformed digest".into())));
+            continue;
+        };
+        let file;
+        let input = match (entry.name, directory) {
+            // a list of files, or a name standing for the one file given
+            (Some(name), Some(directory)) => {
+                match std::fs::read(directory.join(name)) {
+                    Ok(read) => {
+                        file = read;
+                        Ok(file.as_slice())
+                    }
+                    Err(e) => Err(format!("read {} failed: {}", name, e)),
+                }
+            }
+            _ => subject
+                .as_deref()
+                .ok_or_else(|| "no file or input to check".to_string()),
+        };
+        checks.push(match input {
+            Ok(input) => {
+                let actual = digest_inner(digest, input);
+                check(
+                    Some(TextEncoding::Hex.encode(&actual)?),
+                    actual == expected,
+                    None,
+                )
+            }
+            Err(error) => check(None, false, Some(error)),
+        });
+    }
+    Ok(checks)
+}
+
+/// Coreutils and bsd lines, sri tokens and bare hex digests.
+fn checksum_entries(list: &str) -> Vec<ChecksumEntry> {
+    let mut entries = vec![];
+    for (index, line) in list.lines().enumerate() {
+        // coreutils escapes names with backslashes or newlines this way
+        let line = line.trim().trim_start_matches('\\');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_number = index + 1;
+        if let Some((algorithm, rest)) = line.split_once(" (") {
+            if let Some((name, expected)) = rest.rsplit_once(") = ") {
+                entries.push(ChecksumEntry {
+                    line: line_number,
+                    name: Some(name),
+                    digest: bsd_digest(algorithm),
+                    expected: expected.trim(),
+                    sri: false,
+                });
+                continue;
+            }
+        }
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if let Some(sri) = tokens
+            .iter()
+            .map(|token| token.split_once('-'))
+            .collect::<Option<Vec<_>>>()
+            .filter(|tokens| {
+                tokens.iter().all(|(algorithm, _)| {
+                    matches!(*algorithm, "sha256" | "sha384" | "sha512")
+                })
+            })
+        {
+            entries.extend(sri.into_iter().map(|(algorithm, value)| {
+                ChecksumEntry {
+                    line: line_number,
+                    name: None,
+                    digest: bsd_digest(algorithm),
+                    expected: value.split('?').next().unwrap_or_default(),
+                    sri: true,
+                }
+            }));
+            continue;
+        }
+        // a space, then a space for text or `*` for binary mode
+        let (expected, name) = match line.split_once(' ') {
+            Some((expected, name)) => (
+                expected,
+                Some(name.strip_prefix([' ', '*']).unwrap_or(name).trim_end()),
+            ),
+            None => (line, None),
+        };
+        entries.push(ChecksumEntry {
+            line: line_number,
+            name,
+            digest: None,
+            expected,
+            sri: false,
+        });
+    }
+    entries
+}
+
+fn bsd_digest(algorithm: &str) -> Option<Digest> {
+    Some(match algorithm.to_ascii_uppercase().as_str() {
+        "SHA1" => Digest::Sha1,
+        "SHA256" => Digest::Sha256,
+        "SHA384" => Digest::Sha384,
+        "SHA512" => Digest::Sha512,
+        "SHA3-256" => Digest::Sha3_256,
+        "SHA3-384" => Digest::Sha3_384,
+        "SHA3-512" => Digest::Sha3_512,
+        _ => return None,
+    })
+}
+
+/// The sha-2 (or sha-1) digest of a hex digest's length.
+fn guess_digest(hex: &str) -> Option<Digest> {
+    Some(match hex.len() {
+        40 => Digest::Sha1,
+        64 => Digest::Sha256,
+        96 => Digest::Sha384,
+        128 => Digest::Sha512,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{digest, verify_digest, xof, DigestCheckDto, XofDto};
+    use crate::enums::{Digest, TextEncoding, Xof};
+
+    #[tokio::test]
+    async fn test_keccak256() {
+        let result = digest(
+            Digest::Keccak256,
+            "".to_string(),
+            TextEncoding::Utf8,
+            TextEncoding::Hex,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            result.output,
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        let alt_encodings = result.alt_encodings.unwrap();
+        assert_eq!(
+            alt_encodings[&TextEncoding::Base64],
+            "xdJGAYb3IzySfn2y3McDwOUAtlPKgic7e/rYBF2FpHA="
+        );
+        // not valid utf-8, left out
+        assert!(!alt_encodings.contains_key(&TextEncoding::Utf8));
+        assert_eq!(result.metadata["digest"], "Keccak256");
+    }
+
+    #[tokio::test]
+    async fn test_xof() {
+        let dto =
+            |xof, input: &str, key: Option<&str>, customization: &str| XofDto {
+                xof,
+                input: input.to_string(),
+                input_encoding: TextEncoding::Hex,
+                key: key.map(String::from),
+                key_encoding: Some(TextEncoding::Hex),
+                customization: Some(customization.to_string()),
+                customization_encoding: Some(TextEncoding::Utf8),
+                function_name: None,
+                output_length: 32,
+                output_encoding: TextEncoding::Hex,
+            };
+        assert_eq!(
+            xof(dto(Xof::Shake128, "", None, "")).await.unwrap().output,
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+        // SP 800-185 KMAC samples #1 and #2
+        let key =
+            "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f";
+        assert_eq!(
+            xof(dto(Xof::Kmac128, "00010203", Some(key), ""))
+                .await
+                .unwrap()
+                .output,
+            "e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"
+        );
+        assert_eq!(
+            xof(dto(
+                Xof::Kmac128,
+                "00010203",
+                Some(key),
+                "My Tagged Application"
+            ))
+            .await
+            .unwrap()
+            .output,
+            "3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_digest() {
+        let directory = std::env::temp_dir().join("kits-verify-digest");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a.txt"), "abc").unwrap();
+        let expected = "# release checksums\n\
+             ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad \
+             *a.txt\n\
+             SHA1 (a.txt) = a9993e364706816aba3e25717850c26c9cd0d89d\n\
+             ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  \
+             missing.txt\n";
+        let checks = verify_digest(DigestCheckDto {
+            path: Some(directory.to_string_lossy().to_string()),
+            input: None,
+            input_encoding: None,
+            expected: expected.to_string(),
+            digest: None,
+        })
+        .await
+        .unwrap();
+        let results = checks
+            .iter()
+            .map(|check| (check.line, check.name.as_deref(), check.matched))
+            .collect::<Vec<_>>();
+        assert_eq!(results, [
+            (2, Some("a.txt"), true),
+            (3, Some("a.txt"), true),
+            (4, Some("missing.txt"), false)
+        ]);
+        assert!(checks[2].error.is_some());
+
+        let checks = verify_digest(DigestCheckDto {
+            path: None,
+            input: Some("abc".to_string()),
+            input_encoding: None,
+            expected: "sha384-ywB1P0WjXou1oD1pmsZQBycsMqsO3tFjGotgWkP/\
+                       W+2AhgcroefMI1i67KE0yCWn sha256-AAAA"
+                .to_string(),
+            digest: None,
+        })
+        .await
+        .unwrap();
+        let matched =
+            checks.iter().map(|check| check.matched).collect::<Vec<_>>();
+        assert_eq!(matched, [true, false]);
+    }
+}
@@ -0,0 +1,254 @@
+use anyhow::anyhow;
+use elliptic_curve::{
+    bigint::U256,
+    ops::Reduce,
+    sec1::{FromEncodedPoint, ToEncodedPoint},
+    Field, PrimeField,
+};
+use serde::{Deserialize, Serialize};
+use sm2::{AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar};
+use sm3::{Digest, Sm3};
+use tracing::info;
+
+use crate::{
+    enums::{EccCurveName, TextEncoding},
+    errors::{Error, Result},
+    rng,
+    utils::KeyTuple,
+};
+
+const DEFAULT_ID: &str = "1234567812345678";
+const CURVE_A: &str =
+    "fffffffeffffffffffffffffffffffffffffffff00000000fffffffffffffffc";
+const CURVE_B: &str =
+    "28e9fa9e9d9f5e344d5a9e4bcf6509a7f39789f515ab8f92ddbcbd414d940e93";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sm2ExchangeRole {
+    Initiator,
+    Responder,
+}
+
+/// Private keys are 32 byte scalars, public keys sec1 points. Ids default
+/// to "1234567812345678".
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2ExchangeDto {
+    pub role: Sm2ExchangeRole,
+    pub id: Option<String>,
+    pub peer_id: Option<String>,
+    pub private_key: String,
+    pub peer_public_key: String,
+    pub ephemeral_private_key: String,
+    pub peer_ephemeral_public_key: String,
+    pub key_length: usize,
+    pub encoding: TextEncoding,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Sm2ExchangeOutput {
+    pub za: String,
+    pub zb: String,
+    pub shared_point: String,
+    pub shared_key: String,
+    /// S_B for the responder, S_A for the initiator
+    pub confirmation: String,
+    /// S_B (S_1) for the initiator, S_A (S_2) for the responder
+    pub peer_confirmation: String,
+}
+
+/// Serves as both the static and the ephemeral key.
+#[tauri::command]
+pub fn sm2_exchange_key(encoding: TextEncoding) -> Result<KeyTuple> {
+    let d = Scalar::random(&mut rng::rng());
+    let point = (ProjectivePoint::GENERATOR * d).to_affine();
+    let public_key = point.to_encoded_point(false);
+    let mut key = KeyTuple::new(
+        encoding.encode(&d.to_repr())?,
+        encoding.encode(public_key.as_bytes())?,
+    );
+    key.algorithm("SM2")
+        .parameter("curveName", EccCurveName::SM2)
+        .fingerprint(public_key.as_bytes());
+    Ok(key)
+}
+
+/// GB/T 32918.3, from the point of view of `role`.
+#[tauri::command]
+pub fn sm2_key_exchange(data: Sm2ExchangeDto) -> Result<Sm2ExchangeOutput> {
+    info!("sm2 key exchange, role: {:?}", data.role);
+    let encoding = data.encoding;
+    let d = scalar(&encoding.decode(&data.private_key)?)?;
+    let r = scalar(&encoding.decode(&data.ephemeral_private_key)?)?;
+    let peer = point(&encoding.decode(&data.peer_public_key)?)?;
+    let peer_r = point(&encoding.decode(&data.peer_ephemeral_public_key)?)?;
+    let own = (ProjectivePoint::GENERATOR * d).to_affine();
+    let own_r = (ProjectivePoint::GENERATOR * r).to_affine();
+
+    let id = data.id.as_deref().unwrap_or(DEFAULT_ID);
+    let peer_id = data.peer_id.as_deref().unwrap_or(DEFAULT_ID);
+    let z = za(id.as_bytes(), &own)?;
+    let peer_z = za(peer_id.as_bytes(), &peer)?;
+
+    // t = (d + x̄·r) mod n, shared point = [h·t](P' + [x̄']R') with h = 1
+    let t = d + x_bar(&own_r) * r;
+    let shared = ((ProjectivePoint::from(peer)
+        + ProjectivePoint::from(peer_r) * x_bar(&peer_r))
+        * t)
+        .to_affine()
+        .to_encoded_point(false);
+    let (Some(x), Some(y)) = (shared.x(), shared.y()) else {
+        return Err(anyhow!("sm2 key exchange produced the identity").into());
+    };
+
+    // from here on everything is ordered initiator (A) first
+    let (z_a, z_b, r_a, r_b) = match data.role {
+        Sm2ExchangeRole::Initiator => (&z, &peer_z, own_r, peer_r),
+        Sm2ExchangeRole::Responder => (&peer_z, &z, peer_r, own_r),
+    };
+    let shared_key = kdf(&[&x[..], &y[..], z_a, z_b].concat(), data.key_length);
+    let inner = Sm3::new()
+        .chain_update(x)
+        .chain_update(z_a)
+        .chain_update(z_b)
+        .chain_update(coordinates(&r_a))
+        .chain_update(coordinates(&r_b))
+        .finalize();
+    let confirm = |tag: u8| {
+        Sm3::new()
+            .chain_update([tag])
+            .chain_update(y)
+            .chain_update(inner)
+            .finalize()
+            .to_vec()
+    };
+    let (confirmation, peer_confirmation) = match data.role {
+        Sm2ExchangeRole::Initiator => (confirm(0x03), confirm(0x02)),
+        Sm2ExchangeRole::Responder => (confirm(0x02), confirm(0x03)),
+    };
+    Ok(Sm2ExchangeOutput {
+        za: encoding.encode(z_a)?,
+        zb: encoding.encode(z_b)?,
+        shared_point: encoding.encode(shared.as_bytes())?,
+        shared_key: encoding.encode(&shared_key)?,
+        confirmation: encoding.encode(&confirmation)?,
+        peer_confirmation: encoding.encode(&peer_confirmation)?,
+    })
+}
+
+/// Z = SM3(ENTL || ID || a || b || xG || yG || x || y)
+pub(crate) fn za(id: &[u8], public_key: &AffinePoint) -> Result<Vec<u8>> {
+    let entl = u16::try_from(id.len() * 8)
+        .map_err(|_| Error::Unsupported("sm2 id too long".to_string()))?;
+    Ok(Sm3::new()
+        .chain_update(entl.to_be_bytes())
+        .chain_update(id)
+        .chain_update(TextEncoding::Hex.decode(CURVE_A)?)
+        .chain_update(TextEncoding::Hex.decode(CURVE_B)?)
+        .chain_update(coordinates(&ProjectivePoint::GENERATOR.to_affine()))
+        .chain_update(coordinates(public_key))
+        .finalize()
+        .to_vec())
+}
+
+/// x̄ = 2^w + (x & (2^w - 1)) with w = 127 for the 256 bit order.
+fn x_bar(point: &AffinePoint) -> Scalar {
+    let encoded = point.to_encoded_point(false);
+    let mut bytes = FieldBytes::default();
+    if let Some(x) = encoded.x() {
+        bytes[16 ..].copy_from_slice(&x[16 ..]);
+    }
+    bytes[16] |= 0x80;
+    <Scalar as Reduce<U256>>::reduce_bytes(&bytes)
+}
+
+fn coordinates(point: &AffinePoint) -> Vec<u8> {
+    point.to_encoded_point(false).as_bytes()[1 ..].to_vec()
+}
+
+fn kdf(z: &[u8], length: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(length);
+    let mut counter = 1u32;
+    while output.len() < length {
+        output.extend(
+            Sm3::new()
+                .chain_update(z)
+                .chain_update(counter.to_be_bytes())
+                .finalize(),
+        );
+        counter += 1;
+    }
+    output.truncate(length);
+    output
+}
+
+fn scalar(bytes: &[u8]) -> Result<Scalar> {
+    let bytes = FieldBytes::from_exact_iter(bytes.iter().copied())
+        .ok_or(Error::Unsupported("sm2 private key size".to_string()))?;
+    Option::<Scalar>::from(Scalar::from_repr(bytes))
+        .filter(|scalar| !bool::from(scalar.is_zero()))
+        .ok_or(Error::Unsupported(
+            "sm2 private key out of range".to_string(),
+        ))
+}
+
+fn point(bytes: &[u8]) -> Result<AffinePoint> {
+    let encoded = EncodedPoint::from_bytes(bytes)
+        .map_err(|_| anyhow!("invalid sm2 public key encoding"))?;
+    Ok(
+        Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded))
+            .ok_or(anyhow!("sm2 public key is not on the curve"))?,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        sm2_exchange_key, sm2_key_exchange, Sm2ExchangeDto, Sm2ExchangeRole,
+    };
+    use crate::enums::TextEncoding;
+
+    #[test]
+    fn test_sm2_key_exchange() {
+        let encoding = TextEncoding::Hex;
+        let keys = || {
+            let key = sm2_exchange_key(encoding).unwrap();
+            (key.private_key.unwrap(), key.public_key.unwrap())
+        };
+        let (a, a_pub) = keys();
+        let (ra, ra_pub) = keys();
+        let (b, b_pub) = keys();
+        let (rb, rb_pub) = keys();
+        let initiator = sm2_key_exchange(Sm2ExchangeDto {
+            role: Sm2ExchangeRole::Initiator,
+            id: Some("ALICE123@YAHOO.COM".to_string()),
+            peer_id: Some("BILL456@YAHOO.COM".to_string()),
+            private_key: a,
+            peer_public_key: b_pub,
+            ephemeral_private_key: ra,
+            peer_ephemeral_public_key: rb_pub,
+            key_length: 16,
+            encoding,
+        })
+        .unwrap();
+        let responder = sm2_key_exchange(Sm2ExchangeDto {
+            role: Sm2ExchangeRole::Responder,
+            id: Some("BILL456@YAHOO.COM".to_string()),
+            peer_id: Some("ALICE123@YAHOO.COM".to_string()),
+            private_key: b,
+            peer_public_key: a_pub,
+            ephemeral_private_key: rb,
+            peer_ephemeral_public_key: ra_pub,
+            key_length: 16,
+            encoding,
+        })
+        .unwrap();
+        assert_eq!(initiator.za, responder.za);
+        assert_eq!(initiator.zb, responder.zb);
+        assert_eq!(initiator.shared_key, responder.shared_key);
+        assert_eq!(initiator.confirmation, responder.peer_confirmation);
+        assert_eq!(initiator.peer_confirmation, responder.confirmation);
+    }
+}
@@ -12,6 +12,7 @@ use pem_rfc7468::PemLabel;
 use pkcs8::{
     DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey,
 };
+use rand_core::CryptoRngCore;
 use sec1::point::ModulusSize;
 use serde::{Deserialize, Serialize};
 use sm2::Sm2;
@@ -20,11 +21,14 @@ use tracing::info;
 use crate::{
     codec::{
         private_bytes_to_pkcs8, private_pkcs8_to_bytes, public_bytes_to_pkcs8,
-        public_pkcs8_to_bytes, PkcsDto,
+        public_pkcs8_to_bytes, reject_spki_private, PkcsDto,
     },
+    crypto::shamir::{GeneratedKey, Split},
     enums::{EccCurveName, KeyFormat, Pkcs, TextEncoding},
     errors::{Error, Result},
-    utils::KeyTuple,
+    profile::{self, Algorithm},
+    rng,
+    utils::{KeyTuple, Provenance},
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,32 +45,41 @@ pub async fn generate_ecc(
     pkcs: Pkcs,
     format: KeyFormat,
     encoding: TextEncoding,
-) -> Result<KeyTuple> {
+    split: Option<Split>,
+) -> Result<GeneratedKey> {
     info!(
         "generate ecc key, curve_name: {:?}, pkcs: {:?}, format: {:?}, \
-         encoding: {:?}",
-        curve_name, pkcs, format, encoding
+         encoding: {:?}, split: {:?}",
+        curve_name, pkcs, format, encoding, split
     );
+    profile::check(Algorithm::EllipticCurve(curve_name))?;
+    let rng = &mut rng::rng();
     let (private_key_bytes, public_key_bytes) = (match curve_name {
         EccCurveName::NistP256 => {
-            generate_ecc_key::<p256::NistP256>(pkcs, format).await
+            generate_ecc_key::<p256::NistP256, _>(rng, pkcs, format)
         }
         EccCurveName::NistP384 => {
-            generate_ecc_key::<p384::NistP384>(pkcs, format).await
+            generate_ecc_key::<p384::NistP384, _>(rng, pkcs, format)
         }
         EccCurveName::NistP521 => {
-            generate_ecc_key::<p521::NistP521>(pkcs, format).await
+            generate_ecc_key::<p521::NistP521, _>(rng, pkcs, format)
         }
         EccCurveName::Secp256k1 => {
-            generate_ecc_key::<k256::Secp256k1>(pkcs, format).await
+            generate_ecc_key::<k256::Secp256k1, _>(rng, pkcs, format)
         }
-        EccCurveName::SM2 => generate_ecc_key::<sm2::Sm2>(pkcs, format).await,
+        EccCurveName::SM2 => generate_ecc_key::<sm2::Sm2, _>(rng, pkcs, format),
     })?;
 
-    Ok(KeyTuple::new(
+    let mut key = KeyTuple::new(
         encoding.encode(&private_key_bytes)?,
         encoding.encode(&public_key_bytes)?,
-    ))
+    );
+    key.algorithm("EC")
+        .parameter("curveName", curve_name)
+        .parameter("pkcs", pkcs)
+        .parameter("format", format)
+        .fingerprint(&public_key_bytes);
+    GeneratedKey::new(key, split, encoding)
 }
 
 #[tauri::command]
@@ -76,8 +89,8 @@ pub async fn derive_ecc(
     pkcs: Pkcs,
     format: KeyFormat,
     encoding: TextEncoding,
-) -> Result<String> {
-    let key_bytes = encoding.decode(&input)?;
+) -> Result<KeyTuple> {
+    let key_bytes = encoding.decode_lenient(&input)?;
     let public_key_bytes = (match curve_name {
         EccCurveName::NistP256 => {
             derive_ecc_inner::<NistP256>(&key_bytes, pkcs, format)
@@ -95,7 +108,14 @@ pub async fn derive_ecc(
             derive_ecc_inner::<sm2::Sm2>(&key_bytes, pkcs, format)
         }
     })?;
-    encoding.encode(&public_key_bytes)
+    let mut tuple = KeyTuple::empty();
+    tuple
+        .public(Some(encoding.encode(&public_key_bytes)?))
+        .algorithm("EC")
+        .parameter("curveName", curve_name)
+        .parameter("format", format)
+        .fingerprint(&public_key_bytes);
+    Ok(tuple)
 }
 
 #[tauri::command]
@@ -105,6 +125,7 @@ pub async fn transfer_ecc_key(
     public_key: Option<String>,
     from: PkcsDto,
     to: PkcsDto,
+    provenance: Option<Provenance>,
 ) -> Result<KeyTuple> {
     info!(
         "ecc key format transfer, curve_name: {:?}, {:?} to {:?}. \
@@ -115,60 +136,65 @@ pub async fn transfer_ecc_key(
         private_key.is_some(),
         public_key.is_some()
     );
+    let decode = |key: Option<String>| {
+        key.filter(|key| !key.trim().is_empty())
+            .map(|key| from.encoding.decode_lenient(&key))
+            .transpose()
+    };
+    let private_key = decode(private_key)?;
+    let public_key = decode(public_key)?;
 
     let mut tuple = KeyTuple::empty();
 
     tuple
-        .private(if let Some(key) = private_key {
-            if !key.trim().is_empty() {
-                let key_bytes = from.encoding.decode(&key)?;
-                let private_bytes = pkcs8_sec1_converter(
-                    curve_name,
-                    key_bytes.as_slice(),
-                    from,
-                    to,
-                    false,
-                )?;
-                Some(to.encoding.encode(&private_bytes)?)
-            } else {
-                None
-            }
+        .private(if let Some(key_bytes) = &private_key {
+            let private_bytes = pkcs8_sec1_converter(
+                curve_name,
+                key_bytes.as_slice(),
+                from,
+                to,
+                false,
+            )?;
+            Some(to.encoding.encode(&private_bytes)?)
         } else {
             None
         })
-        .public(if let Some(key) = public_key {
-            if !key.trim().is_empty() {
-                let key_bytes = from.encoding.decode(&key)?;
-                let public_bytes = pkcs8_sec1_converter(
-                    curve_name,
-                    key_bytes.as_slice(),
-                    from,
-                    to,
-                    true,
-                )?;
-                Some(to.encoding.encode(&public_bytes)?)
-            } else {
-                None
-            }
-        } else {
-            None
-        });
+        .algorithm("EC")
+        .parameter("curveName", curve_name)
+        .parameter("pkcs", to.pkcs)
+        .parameter("format", to.format);
+    if let Some(key_bytes) = &public_key {
+        let public_bytes = pkcs8_sec1_converter(
+            curve_name,
+            key_bytes.as_slice(),
+            from,
+            to,
+            true,
+        )?;
+        tuple
+            .public(Some(to.encoding.encode(&public_bytes)?))
+            .fingerprint(&public_bytes);
+    }
+    if let Some(source) = public_key.or(private_key) {
+        tuple.provenance(provenance, &source, "transfer_ecc_key", from, to);
+    }
     Ok(tuple)
 }
 
-pub(crate) async fn generate_ecc_key<C>(
+pub(crate) fn generate_ecc_key<C, R>(
+    rng: &mut R,
     pkcs: Pkcs,
     format: KeyFormat,
 ) -> Result<(Vec<u8>, Vec<u8>)>
 where
+    R: CryptoRngCore,
     C: elliptic_curve::Curve,
     C: elliptic_curve::CurveArithmetic + pkcs8::AssociatedOid,
     AffinePoint<C>: elliptic_curve::sec1::FromEncodedPoint<C>
         + elliptic_curve::sec1::ToEncodedPoint<C>,
     elliptic_curve::FieldBytesSize<C>: elliptic_curve::sec1::ModulusSize,
 {
-    let mut rng = rand::thread_rng();
-    let secret_key = elliptic_curve::SecretKey::<C>::random(&mut rng);
+    let secret_key = elliptic_curve::SecretKey::<C>::random(rng);
     let private_key = export_ecc_private_key(&secret_key, pkcs, format)?;
     let public_secret_key = secret_key.public_key();
     let public_key = export_ecc_public_key(public_secret_key, format)?;
@@ -195,8 +221,8 @@ where
 pub fn parse_ecc(input: String) -> Result<EccKeyInfo> {
     info!("parse ecc: {}", input.len());
     let pem_decodor = |(input, format): (&str, KeyFormat)| {
-        let (label, _) =
-            pem_rfc7468::decode_vec(input.as_bytes()).context("invalid pem")?;
+        let (label, _) = pem_rfc7468::decode_vec(input.as_bytes())
+            .context("invalid ecc pem key")?;
 
         let pkcs = match label {
             sec1::EcPrivateKey::PEM_LABEL => Pkcs::Sec1,
@@ -323,10 +349,10 @@ where
 {
     Ok(match (pkcs, encoding) {
         (Pkcs::Pkcs8, KeyFormat::Pem) => {
-            let public_key_str = String::from_utf8(input.to_vec())
-                .context("informal ecc pkcs8 private key")?;
+            let private_key_str = String::from_utf8(input.to_vec())
+                .context("informal ecc pkcs8 pem private key")?;
 
-            elliptic_curve::SecretKey::<C>::from_pkcs8_pem(&public_key_str)
+            elliptic_curve::SecretKey::<C>::from_pkcs8_pem(&private_key_str)
                 .context("informal ecc pkcs8 pem private key")?
         }
         (Pkcs::Pkcs8, KeyFormat::Der) => {
@@ -334,10 +360,10 @@ where
                 .context("informal ecc pkcs8 der private key")?
         }
         (Pkcs::Sec1, KeyFormat::Pem) => {
-            let public_key_str = String::from_utf8(input.to_vec())
-                .context("informal ecc pkcs8 private key")?;
+            let private_key_str = String::from_utf8(input.to_vec())
+                .context("informal ecc sec1 pem private key")?;
 
-            elliptic_curve::SecretKey::<C>::from_sec1_pem(&public_key_str)
+            elliptic_curve::SecretKey::<C>::from_sec1_pem(&private_key_str)
                 .context("informal ecc sec1 pem private key")?
         }
         (Pkcs::Sec1, KeyFormat::Der) => {
@@ -346,7 +372,7 @@ where
         }
         _ => {
             return Err(Error::Unsupported(
-                "unsupported rsa pkcs1 key".to_string(),
+                "pkcs1 ecc private key".to_string(),
             ));
         }
     })
@@ -366,12 +392,12 @@ where
     Ok(match format {
         KeyFormat::Pem => {
             let public_key_str = String::from_utf8(input.to_vec())
-                .context("informal ecc public key")?;
+                .context("informal ecc pem public key")?;
             elliptic_curve::PublicKey::from_public_key_pem(&public_key_str)
-                .context("informal pem public key")?
+                .context("informal ecc pem public key")?
         }
         KeyFormat::Der => elliptic_curve::PublicKey::from_public_key_der(input)
-            .context("informal der public key")?,
+            .context("informal ecc der public key")?,
     })
 }
 
@@ -404,17 +430,17 @@ where
         Pkcs::Sec1 => match codec {
             KeyFormat::Pem => secret_key
                 .to_sec1_pem(base64ct::LineEnding::LF)
-                .context("export ecc pkcs8 sec1 private key failed")?
+                .context("export ecc sec1 pem private key failed")?
                 .as_bytes()
                 .to_vec(),
             KeyFormat::Der => secret_key
                 .to_sec1_der()
-                .context("export ecc pkcs8 sec1 private key failed")?
+                .context("export ecc sec1 der private key failed")?
                 .to_vec(),
         },
         _ => {
             return Err(Error::Unsupported(
-                "unsupported pkcs1 rsa encoding".to_string(),
+                "pkcs1 ecc private key".to_string(),
             ));
         }
     })
@@ -435,12 +461,12 @@ where
     Ok(match encoding {
         KeyFormat::Pem => public_key
             .to_public_key_pem(base64ct::LineEnding::LF)
-            .context("init pem private key failed")?
+            .context("export ecc pem public key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => public_key
             .to_public_key_der()
-            .context("init der private key failed")?
+            .context("export ecc der public key failed")?
             .to_vec(),
     })
 }
@@ -487,15 +513,20 @@ where
         + elliptic_curve::sec1::ToEncodedPoint<C>,
     elliptic_curve::FieldBytesSize<C>: elliptic_curve::sec1::ModulusSize,
 {
+    if !is_public {
+        reject_spki_private(from.pkcs, to.pkcs)?;
+    }
     match from.pkcs {
-        Pkcs::Pkcs8 => {
+        Pkcs::Pkcs8 | Pkcs::Spki => {
             if is_public {
                 let key = public_bytes_to_pkcs8::<elliptic_curve::PublicKey<C>>(
                     input,
                     from.format,
                 )?;
                 match to.pkcs {
-                    Pkcs::Pkcs8 => public_pkcs8_to_bytes(key, to.format),
+                    Pkcs::Pkcs8 | Pkcs::Spki => {
+                        public_pkcs8_to_bytes(key, to.format)
+                    }
                     Pkcs::Sec1 => public_sec1_to_bytes::<C>(key, to.format),
                     _ => Err(Error::Unsupported(
                         "only supported ecc key".to_string(),
@@ -519,7 +550,9 @@ where
             if is_public {
                 let key = public_bytes_to_sec1::<C>(input, from.format)?;
                 match to.pkcs {
-                    Pkcs::Pkcs8 => public_pkcs8_to_bytes(key, to.format),
+                    Pkcs::Pkcs8 | Pkcs::Spki => {
+                        public_pkcs8_to_bytes(key, to.format)
+                    }
                     Pkcs::Sec1 => public_sec1_to_bytes::<C>(key, to.format),
                     _ => Err(Error::Unsupported(
                         "only supported ecc key".to_string(),
@@ -553,7 +586,7 @@ where
     Ok(match encoding {
         KeyFormat::Pem => {
             let key_string = String::from_utf8(input.to_vec())
-                .context("invalid utf-8 key")?;
+                .context("invalid sec1 pem private key")?;
             E::from_sec1_pem(&key_string)
                 .context("invalid sec1 pem private key")?
         }
@@ -575,15 +608,16 @@ where
 {
     Ok(match format {
         KeyFormat::Pem => {
-            let key =
-                String::from_utf8(input.to_vec()).context("".to_string())?;
+            let key = String::from_utf8(input.to_vec())
+                .context("invalid sec1 pem public key")?;
             elliptic_curve::PublicKey::<C>::from_public_key_pem(&key)
+                .context("invalid sec1 pem public key")?
         }
         KeyFormat::Der => {
             elliptic_curve::PublicKey::<C>::from_public_key_der(input)
+                .context("invalid sec1 der public key")?
         }
-    }
-    .context("invalid sec1 pem public key")?)
+    })
 }
 
 pub(crate) fn private_sec1_to_bytes<E>(
@@ -596,12 +630,12 @@ where
     Ok(match encoding {
         KeyFormat::Pem => input
             .to_sec1_pem(base64ct::LineEnding::LF)
-            .context("to sec1 pem private key failed")?
+            .context("export sec1 pem private key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => input
             .to_sec1_der()
-            .context("to sec1 der private key failed")?
+            .context("export sec1 der private key failed")?
             .as_bytes()
             .to_vec(),
     })
@@ -622,12 +656,12 @@ where
             &input,
             base64ct::LineEnding::LF,
         )
-        .context("sec1 pem public key to bytes failed")?
+        .context("export sec1 pem public key failed")?
         .as_bytes()
         .to_vec(),
         KeyFormat::Der => {
             elliptic_curve::PublicKey::<C>::to_public_key_der(&input)
-                .context("sec1 der public key to bytes failed")?
+                .context("export sec1 der public key failed")?
                 .to_vec()
         }
     })
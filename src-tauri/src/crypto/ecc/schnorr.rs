@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use anyhow::anyhow;
+use k256::schnorr::{Signature, SigningKey, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    add_encryption_trait_impl,
+    crypto::{EncryptionDto, OperationResult},
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng,
+    utils::KeyTuple,
+};
+
+add_encryption_trait_impl!(SchnorrSignDto {
+    aux_rand: Option<String>,
+    aux_rand_encoding: Option<TextEncoding>
+});
+
+#[tauri::command]
+pub fn generate_schnorr(encoding: TextEncoding) -> Result<KeyTuple> {
+    let key = SigningKey::random(&mut rng::rng());
+    let public_key = key.verifying_key().to_bytes();
+    let mut tuple = KeyTuple::new(
+        encoding.encode(&key.to_bytes())?,
+        encoding.encode(&public_key)?,
+    );
+    tuple
+        .algorithm("Schnorr")
+        .parameter("curveName", "secp256k1")
+        .fingerprint(&public_key);
+    Ok(tuple)
+}
+
+/// BIP-340 takes the message without prehashing.
+#[tauri::command]
+pub fn schnorr_sign(data: SchnorrSignDto) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    let message = data.get_input()?;
+    info!("schnorr sign, size: {}", message.len());
+    let key = SigningKey::from_bytes(&data.get_key()?)
+        .map_err(|_| anyhow!("invalid schnorr secret key"))?;
+    let mut aux_rand = [0u8; 32];
+    match (&data.aux_rand, data.aux_rand_encoding) {
+        (Some(aux), Some(encoding)) => {
+            aux_rand = encoding.decode(aux)?.try_into().map_err(|_| {
+                Error::Unsupported("aux_rand must be 32 bytes".to_string())
+            })?;
+        }
+        _ => rng::rng().fill_bytes(&mut aux_rand),
+    }
+    let signature = key
+        .sign_raw(&message, &aux_rand)
+        .map_err(|_| anyhow!("schnorr sign failed"))?;
+    Ok(OperationResult::encoded(
+        &signature.to_bytes(),
+        data.get_output_encoding(),
+        started,
+    )?
+    .metadata("curve", "secp256k1")
+    .metadata("scheme", "BIP-340"))
+}
+
+#[tauri::command]
+pub fn schnorr_verify(
+    message: String,
+    message_encoding: TextEncoding,
+    public_key: String,
+    signature: String,
+    encoding: TextEncoding,
+) -> Result<bool> {
+    let message = message_encoding.decode(&message)?;
+    let key = VerifyingKey::from_bytes(&encoding.decode(&public_key)?)
+        .map_err(|_| anyhow!("invalid x-only public key"))?;
+    let signature =
+        Signature::try_from(encoding.decode(&signature)?.as_slice())
+            .map_err(|_| anyhow!("invalid schnorr signature"))?;
+    Ok(key.verify_raw(&message, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{schnorr_sign, schnorr_verify, SchnorrSignDto};
+    use crate::enums::TextEncoding;
+
+    // BIP-340 test vector 0
+    #[test]
+    fn test_bip340() {
+        let zero = "00".repeat(32);
+        let signature = schnorr_sign(SchnorrSignDto {
+            input: zero.clone(),
+            input_encoding: TextEncoding::Hex,
+            key: format!("{}03", "00".repeat(31)),
+            key_encoding: TextEncoding::Hex,
+            input_path: None,
+            key_path: None,
+            output_encoding: TextEncoding::Hex,
+            aux_rand: Some(zero.clone()),
+            aux_rand_encoding: Some(TextEncoding::Hex),
+        })
+        .unwrap()
+        .output;
+        assert_eq!(
+            signature,
+            "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca8215\
+             25f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0"
+        );
+        assert!(schnorr_verify(
+            zero,
+            TextEncoding::Hex,
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9"
+                .to_string(),
+            signature,
+            TextEncoding::Hex,
+        )
+        .unwrap());
+    }
+}
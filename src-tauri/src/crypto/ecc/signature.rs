@@ -0,0 +1,378 @@
+use std::ops::Add;
+
+use anyhow::anyhow;
+use ecdsa::{
+    der::{MaxOverhead, MaxSize},
+    RecoveryId, Signature, SignatureSize,
+};
+use elliptic_curve::{
+    generic_array::{typenum::Unsigned, ArrayLength},
+    sec1::ToEncodedPoint,
+    CurveArithmetic, FieldBytesSize, PrimeCurve,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    crypto::digest::digest_inner,
+    enums::{Digest, EccCurveName, TextEncoding},
+    errors::{Error, Result},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureFormat {
+    /// ASN.1 `SEQUENCE { r INTEGER, s INTEGER }` as produced by OpenSSL
+    Der,
+    /// fixed width r || s as used by JOSE and COSE, optionally followed by
+    /// a recovery byte as used by Ethereum
+    Raw,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInspection {
+    pub r: String,
+    pub s: String,
+    pub low_s: bool,
+    pub recovery_id: Option<u8>,
+    pub normalized_raw: String,
+    pub normalized_der: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredPublicKey {
+    pub recovery_id: u8,
+    pub public_key: String,
+    pub compressed_public_key: String,
+    /// Ethereum address, secp256k1 only
+    pub address: Option<String>,
+}
+
+#[tauri::command]
+pub fn convert_signature(
+    curve: EccCurveName,
+    signature: String,
+    from: SignatureFormat,
+    to: SignatureFormat,
+    encoding: TextEncoding,
+) -> Result<String> {
+    info!("convert {:?} signature from {:?} to {:?}", curve, from, to);
+    let signature = encoding.decode(&signature)?;
+    let output = match curve {
+        EccCurveName::NistP256 => {
+            convert::<p256::NistP256>(&signature, from, to)
+        }
+        EccCurveName::NistP384 => {
+            convert::<p384::NistP384>(&signature, from, to)
+        }
+        EccCurveName::NistP521 => {
+            convert::<p521::NistP521>(&signature, from, to)
+        }
+        EccCurveName::Secp256k1 => {
+            convert::<k256::Secp256k1>(&signature, from, to)
+        }
+        EccCurveName::SM2 => convert::<sm2::Sm2>(&signature, from, to),
+    }?;
+    encoding.encode(&output)
+}
+
+#[tauri::command]
+pub fn inspect_signature(
+    curve: EccCurveName,
+    signature: String,
+    format: SignatureFormat,
+    encoding: TextEncoding,
+) -> Result<SignatureInspection> {
+    let signature = encoding.decode(&signature)?;
+    match curve {
+        EccCurveName::NistP256 => {
+            inspect::<p256::NistP256>(&signature, format, encoding)
+        }
+        EccCurveName::NistP384 => {
+            inspect::<p384::NistP384>(&signature, format, encoding)
+        }
+        EccCurveName::NistP521 => {
+            inspect::<p521::NistP521>(&signature, format, encoding)
+        }
+        EccCurveName::Secp256k1 => {
+            inspect::<k256::Secp256k1>(&signature, format, encoding)
+        }
+        EccCurveName::SM2 => inspect::<sm2::Sm2>(&signature, format, encoding),
+    }
+}
+
+/// The recovery id is `recovery_id` or else the trailing byte of a raw
+/// signature.
+#[tauri::command]
+pub fn recover_ecdsa_pubkey(
+    curve: EccCurveName,
+    message_digest: String,
+    signature: String,
+    format: SignatureFormat,
+    recovery_id: Option<u8>,
+    encoding: TextEncoding,
+) -> Result<RecoveredPublicKey> {
+    info!("recover {:?} public key", curve);
+    let digest = encoding.decode(&message_digest)?;
+    let signature = encoding.decode(&signature)?;
+    let id = |embedded: Option<u8>| {
+        recovery_id
+            .or(embedded)
+            .and_then(RecoveryId::from_byte)
+            .ok_or(Error::Unsupported("missing recovery id".to_string()))
+    };
+    let invalid = |_| anyhow!("public key recovery failed");
+    let (recovery_id, public_key) = match curve {
+        EccCurveName::Secp256k1 => {
+            let (signature, embedded) =
+                parse_signature::<k256::Secp256k1>(&signature, format)?;
+            let recovery_id = id(embedded)?;
+            let key = k256::ecdsa::VerifyingKey::recover_from_prehash(
+                &digest,
+                &signature,
+                recovery_id,
+            )
+            .map_err(invalid)?;
+            (
+                recovery_id,
+                (
+                    key.to_encoded_point(false).as_bytes().to_vec(),
+                    key.to_encoded_point(true).as_bytes().to_vec(),
+                ),
+            )
+        }
+        EccCurveName::NistP256 => {
+            let (signature, embedded) =
+                parse_signature::<p256::NistP256>(&signature, format)?;
+            let recovery_id = id(embedded)?;
+            let key = p256::ecdsa::VerifyingKey::recover_from_prehash(
+                &digest,
+                &signature,
+                recovery_id,
+            )
+            .map_err(invalid)?;
+            (
+                recovery_id,
+                (
+                    key.to_encoded_point(false).as_bytes().to_vec(),
+                    key.to_encoded_point(true).as_bytes().to_vec(),
+                ),
+            )
+        }
+        curve => {
+            return Err(Error::Unsupported(format!(
+                "public key recovery on {:?}",
+                curve
+            )))
+        }
+    };
+    let (uncompressed, compressed) = public_key;
+    let address = (curve == EccCurveName::Secp256k1)
+        .then(|| {
+            let hash = digest_inner(Digest::Keccak256, &uncompressed[1 ..]);
+            TextEncoding::Hex
+                .encode(&hash[12 ..])
+                .map(|address| format!("0x{}", address))
+        })
+        .transpose()?;
+    Ok(RecoveredPublicKey {
+        recovery_id: recovery_id.to_byte(),
+        public_key: encoding.encode(&uncompressed)?,
+        compressed_public_key: encoding.encode(&compressed)?,
+        address,
+    })
+}
+
+pub(crate) fn parse_signature<C>(
+    bytes: &[u8],
+    format: SignatureFormat,
+) -> Result<(Signature<C>, Option<u8>)>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<MaxOverhead> + ArrayLength<u8>,
+{
+    let invalid = |_| anyhow!("invalid {:?} signature", format);
+    Ok(match format {
+        SignatureFormat::Der => {
+            (Signature::<C>::from_der(bytes).map_err(invalid)?, None)
+        }
+        SignatureFormat::Raw => {
+            let size = SignatureSize::<C>::USIZE;
+            let recovery_id = match bytes.len() {
+                len if len == size => None,
+                len if len == size + 1 => Some(match bytes[size] {
+                    v @ (0 | 1) => v,
+                    v @ (27 | 28) => v - 27,
+                    v if v >= 35 => (v - 35) % 2,
+                    v => {
+                        return Err(Error::Unsupported(format!(
+                            "recovery byte {}",
+                            v
+                        )))
+                    }
+                }),
+                len => {
+                    return Err(Error::Unsupported(format!(
+                        "raw signature of {} bytes, expected {}",
+                        len, size
+                    )))
+                }
+            };
+            (
+                Signature::<C>::from_slice(&bytes[.. size]).map_err(invalid)?,
+                recovery_id,
+            )
+        }
+    })
+}
+
+fn encode_signature<C>(
+    signature: &Signature<C>,
+    format: SignatureFormat,
+) -> Vec<u8>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<MaxOverhead> + ArrayLength<u8>,
+{
+    match format {
+        SignatureFormat::Der => signature.to_der().as_bytes().to_vec(),
+        SignatureFormat::Raw => signature.to_bytes().to_vec(),
+    }
+}
+
+pub(crate) fn convert<C>(
+    bytes: &[u8],
+    from: SignatureFormat,
+    to: SignatureFormat,
+) -> Result<Vec<u8>>
+where
+    C: PrimeCurve,
+    SignatureSize<C>: ArrayLength<u8>,
+    MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<MaxOverhead> + ArrayLength<u8>,
+{
+    let (signature, recovery_id) = parse_signature::<C>(bytes, from)?;
+    let mut output = encode_signature(&signature, to);
+    if to == SignatureFormat::Raw
+        && let Some(recovery_id) = recovery_id
+    {
+        output.push(recovery_id);
+    }
+    Ok(output)
+}
+
+fn inspect<C>(
+    bytes: &[u8],
+    format: SignatureFormat,
+    encoding: TextEncoding,
+) -> Result<SignatureInspection>
+where
+    C: PrimeCurve + CurveArithmetic,
+    SignatureSize<C>: ArrayLength<u8>,
+    MaxSize<C>: ArrayLength<u8>,
+    <FieldBytesSize<C> as Add>::Output: Add<MaxOverhead> + ArrayLength<u8>,
+{
+    let (signature, recovery_id) = parse_signature::<C>(bytes, format)?;
+    let (r, s) = signature.split_bytes();
+    let normalized = signature.normalize_s();
+    let low_s = normalized.is_none();
+    // replacing s with n - s flips the parity of the recovered point
+    let recovery_id = recovery_id.map(|id| if low_s { id } else { id ^ 1 });
+    let normalized = normalized.unwrap_or(signature);
+    Ok(SignatureInspection {
+        r: TextEncoding::Hex.encode(&r)?,
+        s: TextEncoding::Hex.encode(&s)?,
+        low_s,
+        recovery_id,
+        normalized_raw: encoding
+            .encode(&encode_signature(&normalized, SignatureFormat::Raw))?,
+        normalized_der: encoding
+            .encode(&encode_signature(&normalized, SignatureFormat::Der))?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use k256::ecdsa::SigningKey;
+
+    use super::{
+        convert_signature, inspect_signature, recover_ecdsa_pubkey,
+        SignatureFormat,
+    };
+    use crate::enums::{EccCurveName, TextEncoding};
+
+    #[test]
+    fn test_recover_ecdsa_pubkey() {
+        // the well known private key 1, its address is
+        // 0x7e5f4552091a69125d5dfcb7b8c2659029395bdf
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let key = SigningKey::from_slice(&secret).unwrap();
+        let digest = [7u8; 32];
+        let (signature, recovery_id) =
+            key.sign_prehash_recoverable(&digest).unwrap();
+        let mut raw = signature.to_bytes().to_vec();
+        raw.push(recovery_id.to_byte() + 27);
+        let recovered = recover_ecdsa_pubkey(
+            EccCurveName::Secp256k1,
+            hex(&digest),
+            hex(&raw),
+            SignatureFormat::Raw,
+            None,
+            TextEncoding::Hex,
+        )
+        .unwrap();
+        assert_eq!(
+            recovered.address.as_deref(),
+            Some("0x7e5f4552091a69125d5dfcb7b8c2659029395bdf")
+        );
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        TextEncoding::Hex.encode(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_convert_signature() {
+        let raw = "a0b1c2d3e4f5061728394a5b6c7d8e9fa0b1c2d3e4f5061728394a5b6c7d8e9f\
+                   fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140";
+        let der = convert_signature(
+            EccCurveName::Secp256k1,
+            raw.to_string(),
+            SignatureFormat::Raw,
+            SignatureFormat::Der,
+            TextEncoding::Hex,
+        )
+        .unwrap();
+        assert!(der.starts_with("3046022100a0b1"));
+        assert_eq!(
+            convert_signature(
+                EccCurveName::Secp256k1,
+                der,
+                SignatureFormat::Der,
+                SignatureFormat::Raw,
+                TextEncoding::Hex,
+            )
+            .unwrap(),
+            raw
+        );
+        let inspection = inspect_signature(
+            EccCurveName::Secp256k1,
+            format!("{}1b", raw),
+            SignatureFormat::Raw,
+            TextEncoding::Hex,
+        )
+        .unwrap();
+        assert!(!inspection.low_s);
+        assert_eq!(inspection.recovery_id, Some(1));
+        assert!(inspection.normalized_raw.ends_with(
+            "0000000000000000000000000000000000000000000000000000000000000001"
+        ));
+    }
+}
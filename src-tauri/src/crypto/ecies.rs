@@ -0,0 +1,240 @@
+//! Versioned header in front of every ecies ciphertext, magic, version and
+//! suite id. Version 2 suites name the curve, kdf, digest and aead, version
+//! 1 only the curve and aead; headerless ciphertexts still decrypt.
+
+use std::fmt::Debug;
+
+use strum::IntoEnumIterator;
+
+use crate::{
+    enums::{
+        Digest, EccCurveName, EciesEncryptionAlgorithm, EdwardsCurveName, Kdf,
+    },
+    errors::{Error, Result},
+};
+
+pub(crate) const MAGIC: &[u8; 3] = b"KES";
+const VERSION: u8 = 2;
+const SUITE_LENGTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Suite {
+    curve: u8,
+    pub(crate) kdf: Kdf,
+    pub(crate) digest: Digest,
+    pub(crate) aead: EciesEncryptionAlgorithm,
+}
+
+impl Suite {
+    pub(crate) fn ecc(
+        curve_name: EccCurveName,
+        kdf: Kdf,
+        digest: Digest,
+        aead: EciesEncryptionAlgorithm,
+    ) -> Self {
+        Suite {
+            curve: match curve_name {
+                EccCurveName::NistP256 => 0x01,
+                EccCurveName::NistP384 => 0x02,
+                EccCurveName::NistP521 => 0x03,
+                EccCurveName::Secp256k1 => 0x04,
+                EccCurveName::SM2 => 0x05,
+            },
+            kdf,
+            digest,
+            aead,
+        }
+    }
+
+    /// Edwards ecies always derives with pbkdf2 and sha-512.
+    pub(crate) fn edwards(
+        curve_name: EdwardsCurveName,
+        aead: EciesEncryptionAlgorithm,
+    ) -> Self {
+        Suite {
+            curve: match curve_name {
+                EdwardsCurveName::Curve25519 => 0x19,
+            },
+            kdf: Kdf::PbKdf2,
+            digest: Digest::Sha512,
+            aead,
+        }
+    }
+
+    fn id(&self) -> [u8; SUITE_LENGTH] {
+        [
+            self.curve,
+            kdf_id(self.kdf),
+            digest_id(self.digest),
+            aead_id(self.aead),
+        ]
+    }
+
+    fn parse(id: &[u8]) -> Result<Self> {
+        let unknown = || Error::Unsupported(format!("ecies suite {:02x?}", id));
+        Ok(Suite {
+            curve: id[0],
+            kdf: Kdf::iter()
+                .find(|kdf| kdf_id(*kdf) == id[1])
+                .ok_or_else(unknown)?,
+            digest: Digest::iter()
+                .find(|digest| digest_id(*digest) == id[2])
+                .ok_or_else(unknown)?,
+            aead: EciesEncryptionAlgorithm::iter()
+                .find(|aead| aead_id(*aead) == id[3])
+                .ok_or_else(unknown)?,
+        })
+    }
+}
+
+fn kdf_id(kdf: Kdf) -> u8 {
+    match kdf {
+        Kdf::HKdf => 0x01,
+        Kdf::Concatenation => 0x02,
+        Kdf::PbKdf2 => 0x03,
+        Kdf::Scrypt => 0x04,
+    }
+}
+
+fn digest_id(digest: Digest) -> u8 {
+    match digest {
+        Digest::Sha1 => 0x01,
+        Digest::Sha256 => 0x02,
+        Digest::Sha384 => 0x03,
+        Digest::Sha512 => 0x04,
+        Digest::Sha3_256 => 0x05,
+        Digest::Sha3_384 => 0x06,
+        Digest::Sha3_512 => 0x07,
+        Digest::Keccak256 => 0x08,
+    }
+}
+
+fn aead_id(aead: EciesEncryptionAlgorithm) -> u8 {
+    match aead {
+        EciesEncryptionAlgorithm::AesGcm => 0x01,
+        EciesEncryptionAlgorithm::AesCcm => 0x02,
+    }
+}
+
+pub(crate) fn seal(suite: Suite, ciphertext: &[u8]) -> Vec<u8> {
+    let mut sealed =
+        Vec::with_capacity(MAGIC.len() + 1 + SUITE_LENGTH + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(VERSION);
+    sealed.extend_from_slice(&suite.id());
+    sealed.extend_from_slice(ciphertext);
+    sealed
+}
+
+/// The header suite configures the decryption unless `strict` holds it to
+/// `expected`, legacy input gets `None`. A legacy ciphertext that happens to
+/// start with the magic is retried whole.
+pub(crate) fn open<F>(
+    expected: Suite,
+    strict: bool,
+    input: &[u8],
+    decrypt: F,
+) -> Result<Vec<u8>>
+where
+    F: Fn(Option<Suite>, &[u8]) -> Result<Vec<u8>>,
+{
+    if input.len() <= MAGIC.len() || !input.starts_with(MAGIC) {
+        return decrypt(None, input);
+    }
+    let body = &input[MAGIC.len() + 1 ..];
+    let mismatch = |found: &dyn Debug| -> Result<Vec<u8>> {
+        Err(Error::Unsupported(format!(
+            "ecies suite {:?}, the ciphertext was sealed with {:?}",
+            expected, found
+        )))
+    };
+    let opened = match input[MAGIC.len()] {
+        1 if body.len() >= 2 => {
+            let id = [expected.curve, aead_id(expected.aead)];
+            if body[.. 2] != id {
+                mismatch(&&body[.. 2])
+            } else {
+                decrypt(None, &body[2 ..])
+            }
+        }
+        VERSION if body.len() >= SUITE_LENGTH => {
+            match Suite::parse(&body[.. SUITE_LENGTH]) {
+                Ok(suite)
+                    if suite.curve != expected.curve
+                        || strict && suite != expected =>
+                {
+                    mismatch(&suite)
+                }
+                Ok(suite) => decrypt(Some(suite), &body[SUITE_LENGTH ..]),
+                Err(err) => Err(err),
+            }
+        }
+        version => Err(Error::Unsupported(format!(
+            "ecies envelope version {}",
+            version
+        ))),
+    };
+    opened.or_else(|err| decrypt(None, input).map_err(|_| err))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{open, seal, Suite};
+    use crate::{
+        enums::{Digest, EccCurveName, EciesEncryptionAlgorithm, Kdf},
+        errors::{Error, Result},
+    };
+
+    #[test]
+    fn test_envelope() {
+        let suite = |kdf| {
+            Suite::ecc(
+                EccCurveName::NistP256,
+                kdf,
+                Digest::Sha384,
+                EciesEncryptionAlgorithm::AesGcm,
+            )
+        };
+        let (hkdf, pbkdf2) = (suite(Kdf::HKdf), suite(Kdf::PbKdf2));
+        // stands in for the aead, accepts only its own ciphertext and tells
+        // the kdf it was configured with
+        let decrypt =
+            |sealed: Option<Suite>, input: &[u8]| -> Result<Vec<u8>> {
+                let plaintext = input
+                    .strip_prefix(b"ct:")
+                    .ok_or(Error::Unsupported("tag mismatch".to_string()))?;
+                let kdf = sealed.map(|suite| suite.kdf);
+                Ok([format!("{:?} ", kdf).as_bytes(), plaintext].concat())
+            };
+        let sealed = seal(hkdf, b"ct:plaintext");
+        assert_eq!(&sealed[.. 8], b"KES\x02\x01\x01\x03\x01");
+        // the header wins over what was selected
+        assert_eq!(
+            open(pbkdf2, false, &sealed, decrypt).unwrap(),
+            b"Some(HKdf) plaintext"
+        );
+        assert!(open(pbkdf2, true, &sealed, decrypt).is_err());
+        assert!(open(hkdf, true, &sealed, decrypt).is_ok());
+
+        // version 1 and headerless ciphertexts
+        let v1 = [&b"KES\x01\x01\x01"[..], b"ct:plaintext"].concat();
+        assert_eq!(open(hkdf, false, &v1, decrypt).unwrap(), b"None plaintext");
+        assert_eq!(
+            open(hkdf, false, b"ct:legacy", decrypt).unwrap(),
+            b"None legacy"
+        );
+
+        let other = Suite::ecc(
+            EccCurveName::NistP384,
+            Kdf::HKdf,
+            Digest::Sha384,
+            EciesEncryptionAlgorithm::AesGcm,
+        );
+        assert!(open(other, false, &sealed, decrypt).is_err());
+        assert!(open(other, false, &v1, decrypt).is_err());
+        let mut future = sealed.clone();
+        future[3] = 3;
+        let err = open(hkdf, false, &future, decrypt).unwrap_err();
+        assert!(err.to_string().contains("version 3"));
+    }
+}
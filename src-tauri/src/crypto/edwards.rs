@@ -1,17 +1,27 @@
+use std::time::Instant;
+
 use base64ct::Encoding;
+use rand_core::CryptoRngCore;
 use serde::{Deserialize, Serialize};
 use tracing::debug;
 
 use crate::{
     add_encryption_trait_impl,
-    crypto::{self, kdf::SALT, EncryptionDto},
+    crypto::{
+        self,
+        ecies::{self, Suite},
+        kdf::SALT,
+        EncryptionDto, OperationResult,
+    },
     enums::{
         AesEncryptionPadding, EciesEncryptionAlgorithm, EdwardsCurveName,
-        EncryptionMode, KeyFormat, TextEncoding,
+        KeyFormat, TextEncoding,
     },
     errors::Result,
+    rng,
 };
 
+pub mod frost;
 pub mod key;
 
 add_encryption_trait_impl!(EciesEdwardsDto {
@@ -22,21 +32,32 @@ add_encryption_trait_impl!(EciesEdwardsDto {
 });
 
 #[tauri::command]
-pub async fn ecies_edwards(data: EciesEdwardsDto) -> Result<String> {
+pub async fn ecies_edwards(
+    data: EciesEdwardsDto,
+) -> Result<OperationResult<String>> {
+    let started = Instant::now();
     let input = data.get_input()?;
     let key = data.get_key()?;
     let output_encoding = data.get_output_encoding();
 
-    let output = match data.curve_name {
+    let suite = Suite::edwards(data.curve_name, data.encryption_alg);
+    let crypt = |input: &[u8]| match data.curve_name {
         EdwardsCurveName::Curve25519 => curve_25519_ecies(
-            &input,
+            input,
             &key,
             data.format,
             data.encryption_alg,
             data.for_encryption,
         ),
-    }?;
-    output_encoding.encode(&output)
+    };
+    let output = if data.for_encryption {
+        ecies::seal(suite, &crypt(&input)?)
+    } else {
+        ecies::open(suite, false, &input, |_, input| crypt(input))?
+    };
+    Ok(OperationResult::encoded(&output, output_encoding, started)?
+        .metadata("curve", format!("{:?}", data.curve_name))
+        .metadata("encryption", format!("{:?}", data.encryption_alg)))
 }
 
 pub(crate) fn curve_25519_ecies(
@@ -47,19 +68,19 @@ pub(crate) fn curve_25519_ecies(
     for_encryption: bool,
 ) -> Result<Vec<u8>> {
     if for_encryption {
-        curve_25519_ecies_encrypt(input, key, format, ea)
+        curve_25519_ecies_encrypt(&mut rng::rng(), input, key, format, ea)
     } else {
         curve_25519_ecies_decrypt(input, key, format, ea)
     }
 }
 
-fn curve_25519_ecies_encrypt(
+fn curve_25519_ecies_encrypt<R: CryptoRngCore>(
+    rng: &mut R,
     input: &[u8],
     key: &[u8],
     format: KeyFormat,
-    _ea: EciesEncryptionAlgorithm,
+    ea: EciesEncryptionAlgorithm,
 ) -> Result<Vec<u8>> {
-    let rng = rand::thread_rng();
     let mut result = Vec::new();
     let receiver_secret_key =
         x25519_dalek::EphemeralSecret::random_from_rng(rng);
@@ -83,7 +104,7 @@ fn curve_25519_ecies_encrypt(
         base64ct::Base64::encode_string(secret)
     );
     let encrypted = crypto::aes::encrypt_or_decrypt_aes(
-        EncryptionMode::Gcm,
+        ea.as_encryption_mode(),
         input,
         secret,
         Some(iv.to_vec()),
@@ -99,7 +120,7 @@ fn curve_25519_ecies_decrypt(
     input: &[u8],
     key: &[u8],
     format: KeyFormat,
-    _ea: EciesEncryptionAlgorithm,
+    ea: EciesEncryptionAlgorithm,
 ) -> Result<Vec<u8>> {
     let signing_key = key::import_curve_25519_private_key(key, format)?;
 
@@ -126,7 +147,7 @@ fn curve_25519_ecies_decrypt(
         base64ct::Base64::encode_string(secret)
     );
     crypto::aes::encrypt_or_decrypt_aes(
-        EncryptionMode::Gcm,
+        ea.as_encryption_mode(),
         input,
         secret,
         Some(iv.to_vec()),
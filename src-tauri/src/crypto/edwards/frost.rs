@@ -0,0 +1,367 @@
+//! FROST(Ed25519, SHA-512) threshold signatures from RFC 9591, every
+//! participant simulated locally. The dkg leaves out the proofs of knowledge.
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY,
+    EdwardsPoint, Scalar,
+};
+use ed25519_dalek::Verifier;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use tracing::info;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng,
+};
+
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FrostShare {
+    pub identifier: u16,
+    pub signing_share: String,
+    pub verifying_share: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FrostKeyGeneration {
+    pub threshold: u16,
+    pub group_public_key: String,
+    /// the first of each is its contribution to the group key
+    pub commitments: Vec<Vec<String>>,
+    pub shares: Vec<FrostShare>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FrostSignerRecord {
+    pub identifier: u16,
+    pub hiding_commitment: String,
+    pub binding_commitment: String,
+    pub binding_factor: String,
+    pub signature_share: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FrostSignature {
+    pub signature: String,
+    pub group_commitment: String,
+    pub signers: Vec<FrostSignerRecord>,
+}
+
+#[tauri::command]
+pub fn frost_keygen(
+    threshold: u16,
+    participants: u16,
+    encoding: TextEncoding,
+) -> Result<FrostKeyGeneration> {
+    info!("frost keygen, {} of {}", threshold, participants);
+    if threshold < 2 || threshold > participants {
+        return Err(Error::Unsupported(format!(
+            "{} of {} threshold",
+            threshold, participants
+        )));
+    }
+    let mut rng = rng::rng();
+    let polynomials = (0 .. participants)
+        .map(|_| {
+            (0 .. threshold)
+                .map(|_| Scalar::random(&mut rng))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let commitments = polynomials
+        .iter()
+        .map(|coefficients| {
+            coefficients
+                .iter()
+                .map(|coefficient| coefficient * ED25519_BASEPOINT_POINT)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut shares = Vec::with_capacity(participants as usize);
+    for identifier in 1 ..= participants {
+        let x = Scalar::from(identifier);
+        let mut signing_share = Scalar::ZERO;
+        for (coefficients, commitment) in polynomials.iter().zip(&commitments) {
+            let share = evaluate(coefficients, x);
+            // what the receiving participant checks before accepting
+            if share * ED25519_BASEPOINT_POINT
+                != evaluate_commitment(commitment, x)
+            {
+                return Err(anyhow!("frost share verification failed").into());
+            }
+            signing_share += share;
+        }
+        shares.push(FrostShare {
+            identifier,
+            signing_share: encoding.encode(signing_share.as_bytes())?,
+            verifying_share: encoding.encode(
+                (signing_share * ED25519_BASEPOINT_POINT)
+                    .compress()
+                    .as_bytes(),
+            )?,
+        });
+    }
+
+    let group_public_key: EdwardsPoint =
+        commitments.iter().map(|commitment| commitment[0]).sum();
+    Ok(FrostKeyGeneration {
+        threshold,
+        group_public_key: encoding
+            .encode(group_public_key.compress().as_bytes())?,
+        commitments: commitments
+            .iter()
+            .map(|commitment| {
+                commitment
+                    .iter()
+                    .map(|point| encoding.encode(point.compress().as_bytes()))
+                    .collect()
+            })
+            .collect::<Result<_>>()?,
+        shares,
+    })
+}
+
+#[tauri::command]
+pub fn frost_sign(
+    message: String,
+    message_encoding: TextEncoding,
+    group_public_key: String,
+    shares: Vec<FrostShare>,
+    encoding: TextEncoding,
+) -> Result<FrostSignature> {
+    let message = message_encoding.decode(&message)?;
+    info!(
+        "frost sign, signers: {}, size: {}",
+        shares.len(),
+        message.len()
+    );
+    let group_public_key_bytes = encoding.decode(&group_public_key)?;
+    let group_public_key = decode_point(&group_public_key_bytes)?;
+
+    let mut signers = BTreeMap::new();
+    for share in &shares {
+        if share.identifier == 0 {
+            return Err(Error::Unsupported("identifier 0".to_string()));
+        }
+        let signing_share =
+            decode_scalar(&encoding.decode(&share.signing_share)?)?;
+        let verifying_share =
+            decode_point(&encoding.decode(&share.verifying_share)?)?;
+        if signers
+            .insert(share.identifier, (signing_share, verifying_share))
+            .is_some()
+        {
+            return Err(Error::Unsupported(format!(
+                "duplicate identifier {}",
+                share.identifier
+            )));
+        }
+    }
+
+    // round one, each signer commits to a hiding and a binding nonce
+    let nonces = signers
+        .iter()
+        .map(|(identifier, (signing_share, _))| {
+            let hiding = nonce_generate(signing_share);
+            let binding = nonce_generate(signing_share);
+            (*identifier, (hiding, binding))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut encoded_commitments = Vec::new();
+    for (identifier, (hiding, binding)) in &nonces {
+        encoded_commitments
+            .extend_from_slice(Scalar::from(*identifier).as_bytes());
+        encoded_commitments.extend_from_slice(
+            (hiding * ED25519_BASEPOINT_POINT).compress().as_bytes(),
+        );
+        encoded_commitments.extend_from_slice(
+            (binding * ED25519_BASEPOINT_POINT).compress().as_bytes(),
+        );
+    }
+
+    let message_hash = hash(&[CONTEXT, b"msg", &message]);
+    let commitments_hash = hash(&[CONTEXT, b"com", &encoded_commitments]);
+    let binding_factors = nonces
+        .keys()
+        .map(|identifier| {
+            let factor = hash_to_scalar(&[
+                CONTEXT,
+                b"rho",
+                &group_public_key_bytes,
+                &message_hash,
+                &commitments_hash,
+                Scalar::from(*identifier).as_bytes(),
+            ]);
+            (*identifier, factor)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let group_commitment: EdwardsPoint = nonces
+        .iter()
+        .map(|(identifier, (hiding, binding))| {
+            (hiding + binding * binding_factors[identifier])
+                * ED25519_BASEPOINT_POINT
+        })
+        .sum();
+    let group_commitment_bytes = group_commitment.compress().to_bytes();
+    let challenge = hash_to_scalar(&[
+        &group_commitment_bytes,
+        &group_public_key_bytes,
+        &message,
+    ]);
+
+    // round two, each signer answers with its share of the response
+    let identifiers = signers.keys().copied().collect::<Vec<_>>();
+    let mut response = Scalar::ZERO;
+    let mut records = Vec::with_capacity(signers.len());
+    for (identifier, (signing_share, verifying_share)) in &signers {
+        let (hiding, binding) = nonces[identifier];
+        let rho = binding_factors[identifier];
+        let lambda = lagrange_coefficient(*identifier, &identifiers);
+        let signature_share =
+            hiding + binding * rho + lambda * signing_share * challenge;
+        if signature_share * ED25519_BASEPOINT_POINT
+            != (hiding + binding * rho) * ED25519_BASEPOINT_POINT
+                + challenge * lambda * verifying_share
+        {
+            return Err(Error::Unsupported(format!(
+                "signature share of participant {} does not match its \
+                 verifying share",
+                identifier
+            )));
+        }
+        response += signature_share;
+        records.push(FrostSignerRecord {
+            identifier: *identifier,
+            hiding_commitment: encoding.encode(
+                (hiding * ED25519_BASEPOINT_POINT).compress().as_bytes(),
+            )?,
+            binding_commitment: encoding.encode(
+                (binding * ED25519_BASEPOINT_POINT).compress().as_bytes(),
+            )?,
+            binding_factor: encoding.encode(rho.as_bytes())?,
+            signature_share: encoding.encode(signature_share.as_bytes())?,
+        });
+    }
+
+    let mut signature = group_commitment_bytes.to_vec();
+    signature.extend_from_slice(response.as_bytes());
+    let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(
+        &group_public_key.compress().to_bytes(),
+    )
+    .map_err(|_| anyhow!("invalid group public key"))?;
+    let ed25519_signature = ed25519_dalek::Signature::from_slice(&signature)
+        .map_err(|_| anyhow!("invalid frost signature"))?;
+    if verifying_key.verify(&message, &ed25519_signature).is_err() {
+        return Err(Error::Unsupported(format!(
+            "aggregated signature from {} signers, below the threshold",
+            signers.len()
+        )));
+    }
+    Ok(FrostSignature {
+        signature: encoding.encode(&signature)?,
+        group_commitment: encoding.encode(&group_commitment_bytes)?,
+        signers: records,
+    })
+}
+
+fn nonce_generate(secret: &Scalar) -> Scalar {
+    let mut random_bytes = [0u8; 32];
+    rng::rng().fill_bytes(&mut random_bytes);
+    hash_to_scalar(&[CONTEXT, b"nonce", &random_bytes, secret.as_bytes()])
+}
+
+fn lagrange_coefficient(identifier: u16, identifiers: &[u16]) -> Scalar {
+    let x = Scalar::from(identifier);
+    let (numerator, denominator) = identifiers
+        .iter()
+        .filter(|other| **other != identifier)
+        .map(|other| Scalar::from(*other))
+        .fold((Scalar::ONE, Scalar::ONE), |(num, den), other| {
+            (num * other, den * (other - x))
+        });
+    numerator * denominator.invert()
+}
+
+fn evaluate(coefficients: &[Scalar], x: Scalar) -> Scalar {
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+fn evaluate_commitment(commitment: &[EdwardsPoint], x: Scalar) -> EdwardsPoint {
+    commitment
+        .iter()
+        .rev()
+        .fold(EdwardsPoint::default(), |acc, point| acc * x + point)
+}
+
+fn hasher(parts: &[&[u8]]) -> Sha512 {
+    parts
+        .iter()
+        .fold(Sha512::new(), |hasher, part| hasher.chain_update(part))
+}
+
+fn hash(parts: &[&[u8]]) -> Vec<u8> {
+    hasher(parts).finalize().to_vec()
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    Scalar::from_hash(hasher(parts))
+}
+
+fn decode_scalar(bytes: &[u8]) -> Result<Scalar> {
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| {
+        Error::Unsupported("scalar must be 32 bytes".to_string())
+    })?;
+    Option::from(Scalar::from_canonical_bytes(bytes))
+        .ok_or_else(|| anyhow!("non canonical scalar").into())
+}
+
+fn decode_point(bytes: &[u8]) -> Result<EdwardsPoint> {
+    CompressedEdwardsY::from_slice(bytes)
+        .ok()
+        .and_then(|point| point.decompress())
+        .ok_or_else(|| anyhow!("invalid edwards point").into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{frost_keygen, frost_sign};
+    use crate::enums::TextEncoding;
+
+    #[test]
+    fn test_frost_threshold() {
+        let keygen = frost_keygen(2, 3, TextEncoding::Hex).unwrap();
+        assert_eq!(3, keygen.shares.len());
+        let sign = |identifiers: &[u16]| {
+            frost_sign(
+                "frost".to_string(),
+                TextEncoding::Utf8,
+                keygen.group_public_key.clone(),
+                keygen
+                    .shares
+                    .iter()
+                    .filter(|share| identifiers.contains(&share.identifier))
+                    .cloned()
+                    .collect(),
+                TextEncoding::Hex,
+            )
+        };
+        assert!(sign(&[1, 3]).is_ok());
+        assert!(sign(&[1, 2, 3]).is_ok());
+        assert!(sign(&[2]).is_err());
+    }
+}
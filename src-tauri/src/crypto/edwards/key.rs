@@ -1,31 +1,41 @@
 use anyhow::Context;
 use pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use spki::DecodePublicKey;
 use tracing::info;
 
 use crate::{
-    codec::{
-        private_bytes_to_pkcs8, private_pkcs8_to_bytes, public_bytes_to_pkcs8,
-        public_pkcs8_to_bytes, PkcsDto,
-    },
+    codec::{base64_decode, base64_encode},
+    crypto::shamir::{GeneratedKey, Split},
     enums::{EdwardsCurveName, KeyFormat, TextEncoding},
-    errors::Result,
-    utils::KeyTuple,
+    errors::{Error, Result},
+    rng,
+    utils::{KeyTuple, Provenance},
 };
 #[tauri::command]
 pub async fn generate_edwards(
     curve_name: EdwardsCurveName,
     format: KeyFormat,
     encoding: TextEncoding,
-) -> Result<KeyTuple> {
+    split: Option<Split>,
+) -> Result<GeneratedKey> {
     let (private_key, public_key) = match curve_name {
-        EdwardsCurveName::Curve25519 => generate_curve_25519_key(format),
+        EdwardsCurveName::Curve25519 => {
+            generate_curve_25519_key(&mut rng::rng(), format)
+        }
     }?;
 
-    Ok(KeyTuple::new(
+    let mut key = KeyTuple::new(
         encoding.encode(&private_key)?,
         encoding.encode(&public_key)?,
-    ))
+    );
+    key.algorithm("EdDSA")
+        .parameter("curveName", curve_name)
+        .parameter("format", format)
+        .fingerprint(&public_key);
+    GeneratedKey::new(key, split, encoding)
 }
 
 #[tauri::command]
@@ -34,23 +44,47 @@ pub fn derive_edwards(
     input: String,
     format: KeyFormat,
     encoding: TextEncoding,
-) -> Result<String> {
-    let input = encoding.decode(&input)?;
+) -> Result<KeyTuple> {
+    let input = encoding.decode_lenient(&input)?;
 
     let public_key = match curve_name {
         EdwardsCurveName::Curve25519 => derive_curve_25519(&input, format),
     }?;
 
-    encoding.encode(&public_key)
+    let mut tuple = KeyTuple::empty();
+    tuple
+        .public(Some(encoding.encode(&public_key)?))
+        .algorithm("EdDSA")
+        .parameter("curveName", curve_name)
+        .parameter("format", format)
+        .fingerprint(&public_key);
+    Ok(tuple)
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EdwardsKeyFormat {
+    Pem,
+    Der,
+    Raw,
+    Jwk,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct EdwardsKeyDto {
+    pub format: EdwardsKeyFormat,
+    pub encoding: TextEncoding,
+}
+
+/// Without a public key, the one of the private key comes out.
 #[tauri::command]
 pub fn transfer_edwards_key(
     curve_name: EdwardsCurveName,
     private_key: Option<String>,
     public_key: Option<String>,
-    from: PkcsDto,
-    to: PkcsDto,
+    from: EdwardsKeyDto,
+    to: EdwardsKeyDto,
+    provenance: Option<Provenance>,
 ) -> Result<KeyTuple> {
     info!(
         "edwards key format transfer, curve_name: {:?}, {:?} to {:?}. \
@@ -61,54 +95,178 @@ pub fn transfer_edwards_key(
         private_key.is_some(),
         public_key.is_some()
     );
+    let decode = |key: Option<String>| {
+        key.filter(|key| !key.trim().is_empty())
+            .map(|key| from.encoding.decode_lenient(&key))
+            .transpose()
+    };
+    let private_bytes = decode(private_key)?;
+    let public_bytes = decode(public_key)?;
 
-    let mut tuple = KeyTuple::empty();
+    let private_key = private_bytes
+        .as_deref()
+        .map(|key| import_signing_key(key, from.format))
+        .transpose()?;
+    let public_key = match public_bytes.as_deref() {
+        Some(key) => Some(import_verifying_key(key, from.format)?),
+        None => private_key.as_ref().map(|key| key.verifying_key()),
+    };
 
+    let mut tuple = KeyTuple::empty();
     tuple
-        .private(if let Some(key) = private_key {
-            if !key.trim().is_empty() {
-                let key_bytes = from.encoding.decode(&key)?;
-                let private_bytes = private_bytes_to_pkcs8::<
-                    ed25519_dalek::SigningKey,
-                >(&key_bytes, from.format)
-                .and_then(|key| {
-                    private_pkcs8_to_bytes::<ed25519_dalek::SigningKey>(
-                        key, to.format,
-                    )
-                })?;
-                Some(to.encoding.encode(&private_bytes)?)
-            } else {
-                None
-            }
-        } else {
-            None
-        })
-        .public(if let Some(key) = public_key {
-            if !key.trim().is_empty() {
-                let key_bytes = from.encoding.decode(&key)?;
-                let public_bytes = public_bytes_to_pkcs8::<
-                    ed25519_dalek::VerifyingKey,
-                >(&key_bytes, from.format)
-                .and_then(|key| {
-                    public_pkcs8_to_bytes::<ed25519_dalek::VerifyingKey>(
-                        key, to.format,
-                    )
-                })?;
-                Some(to.encoding.encode(&public_bytes)?)
-            } else {
-                None
-            }
-        } else {
-            None
-        });
+        .private(
+            private_key
+                .map(|key| {
+                    to.encoding.encode(&export_signing_key(&key, to.format)?)
+                })
+                .transpose()?,
+        )
+        .public(
+            public_key
+                .map(|key| {
+                    to.encoding.encode(&export_verifying_key(key, to.format)?)
+                })
+                .transpose()?,
+        )
+        .algorithm("EdDSA")
+        .parameter("curveName", curve_name)
+        .parameter("format", to.format);
+    if let Some(key) = public_key {
+        tuple.fingerprint(&export_verifying_key(key, EdwardsKeyFormat::Der)?);
+    }
+    if let Some(source) = public_bytes.or(private_bytes) {
+        tuple.provenance(provenance, &source, "transfer_edwards_key", from, to);
+    }
     Ok(tuple)
 }
 
-pub(crate) fn generate_curve_25519_key(
+fn import_signing_key(
+    input: &[u8],
+    format: EdwardsKeyFormat,
+) -> Result<ed25519_dalek::SigningKey> {
+    match format {
+        EdwardsKeyFormat::Pem => {
+            import_curve_25519_private_key(input, KeyFormat::Pem)
+        }
+        EdwardsKeyFormat::Der => {
+            import_curve_25519_private_key(input, KeyFormat::Der)
+        }
+        EdwardsKeyFormat::Raw => {
+            Ok(ed25519_dalek::SigningKey::from_bytes(&raw_key(input)?))
+        }
+        EdwardsKeyFormat::Jwk => {
+            let jwk = okp_jwk(input)?;
+            let key = ed25519_dalek::SigningKey::from_bytes(&raw_key(
+                &jwk_field(&jwk, "d")?,
+            )?);
+            if jwk["x"].is_string()
+                && jwk_field(&jwk, "x")? != key.verifying_key().to_bytes()
+            {
+                return Err(Error::Unsupported(
+                    "jwk x does not match its d".to_string(),
+                ));
+            }
+            Ok(key)
+        }
+    }
+}
+
+fn import_verifying_key(
+    input: &[u8],
+    format: EdwardsKeyFormat,
+) -> Result<ed25519_dalek::VerifyingKey> {
+    let point = match format {
+        EdwardsKeyFormat::Pem => {
+            return import_curve_25519_public_key(input, KeyFormat::Pem)
+        }
+        EdwardsKeyFormat::Der => {
+            return import_curve_25519_public_key(input, KeyFormat::Der)
+        }
+        EdwardsKeyFormat::Raw => raw_key(input)?,
+        EdwardsKeyFormat::Jwk => raw_key(&jwk_field(&okp_jwk(input)?, "x")?)?,
+    };
+    Ok(ed25519_dalek::VerifyingKey::from_bytes(&point)
+        .context("invalid ed25519 point")?)
+}
+
+fn export_signing_key(
+    key: &ed25519_dalek::SigningKey,
+    format: EdwardsKeyFormat,
+) -> Result<Vec<u8>> {
+    match format {
+        EdwardsKeyFormat::Pem => {
+            export_curve_25519_private_key(key, KeyFormat::Pem)
+        }
+        EdwardsKeyFormat::Der => {
+            export_curve_25519_private_key(key, KeyFormat::Der)
+        }
+        EdwardsKeyFormat::Raw => Ok(key.to_bytes().to_vec()),
+        EdwardsKeyFormat::Jwk => {
+            let mut jwk = public_jwk(key.verifying_key())?;
+            jwk["d"] = json!(base64_encode(key.as_bytes(), true, true)?);
+            serialize_jwk(&jwk)
+        }
+    }
+}
+
+fn export_verifying_key(
+    key: ed25519_dalek::VerifyingKey,
+    format: EdwardsKeyFormat,
+) -> Result<Vec<u8>> {
+    match format {
+        EdwardsKeyFormat::Pem => {
+            export_curve_25519_public_key(key, KeyFormat::Pem)
+        }
+        EdwardsKeyFormat::Der => {
+            export_curve_25519_public_key(key, KeyFormat::Der)
+        }
+        EdwardsKeyFormat::Raw => Ok(key.to_bytes().to_vec()),
+        EdwardsKeyFormat::Jwk => serialize_jwk(&public_jwk(key)?),
+    }
+}
+
+fn raw_key(input: &[u8]) -> Result<[u8; 32]> {
+    input.try_into().map_err(|_| {
+        Error::Unsupported(format!("{} byte ed25519 key", input.len()))
+    })
+}
+
+fn okp_jwk(input: &[u8]) -> Result<Value> {
+    let jwk: Value = serde_json::from_slice(input).context("invalid jwk")?;
+    match (jwk["kty"].as_str(), jwk["crv"].as_str()) {
+        (Some("OKP"), Some("Ed25519")) => Ok(jwk),
+        (kty, crv) => Err(Error::Unsupported(format!(
+            "jwk {} {}",
+            kty.unwrap_or_default(),
+            crv.unwrap_or_default()
+        ))),
+    }
+}
+
+fn jwk_field(jwk: &Value, name: &str) -> Result<Vec<u8>> {
+    let value = jwk[name]
+        .as_str()
+        .ok_or_else(|| Error::Unsupported(format!("jwk without {}", name)))?;
+    base64_decode(value, true, true)
+}
+
+fn public_jwk(key: ed25519_dalek::VerifyingKey) -> Result<Value> {
+    Ok(json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "x": base64_encode(key.as_bytes(), true, true)?,
+    }))
+}
+
+fn serialize_jwk(jwk: &Value) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec_pretty(jwk).context("serialize jwk failed")?)
+}
+
+pub(crate) fn generate_curve_25519_key<R: CryptoRngCore>(
+    rng: &mut R,
     format: KeyFormat,
 ) -> Result<(Vec<u8>, Vec<u8>)> {
-    let mut rng = rand::thread_rng();
-    let secret_key = ed25519_dalek::SigningKey::generate(&mut rng);
+    let secret_key = ed25519_dalek::SigningKey::generate(rng);
 
     let private_key = export_curve_25519_private_key(&secret_key, format)?;
     let public_secret_key = secret_key.verifying_key();
@@ -131,12 +289,12 @@ pub(crate) fn import_curve_25519_private_key(
     Ok(match format {
         KeyFormat::Pem => {
             let private_key_str = String::from_utf8(input.to_vec())
-                .context("informal curve 25519 private key")?;
+                .context("informal curve 25519 pkcs8 pem private key")?;
             ed25519_dalek::SigningKey::from_pkcs8_pem(&private_key_str)
                 .context("informal curve 25519 pkcs8 pem private key")?
         }
         KeyFormat::Der => ed25519_dalek::SigningKey::from_pkcs8_der(input)
-            .context("informal ecc pkcs8 der private key")?,
+            .context("informal curve 25519 pkcs8 der private key")?,
     })
 }
 
@@ -147,13 +305,13 @@ pub(crate) fn import_curve_25519_public_key(
     Ok(match format {
         KeyFormat::Pem => {
             let public_key_str = String::from_utf8(input.to_vec())
-                .context("informal ecc public key")?;
+                .context("informal curve 25519 pem public key")?;
             ed25519_dalek::VerifyingKey::from_public_key_pem(&public_key_str)
-                .context("informal pem public key")?
+                .context("informal curve 25519 pem public key")?
         }
         KeyFormat::Der => {
             ed25519_dalek::VerifyingKey::from_public_key_der(input)
-                .context("informal der public key")?
+                .context("informal curve 25519 der public key")?
         }
     })
 }
@@ -165,12 +323,12 @@ pub(crate) fn export_curve_25519_private_key(
     Ok(match format {
         KeyFormat::Pem => secret_key
             .to_pkcs8_pem(base64ct::LineEnding::LF)
-            .context("export ecc pkcs8 pem private key failed")?
+            .context("export curve 25519 pkcs8 pem private key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => secret_key
             .to_pkcs8_der()
-            .context("export ecc pkcs8 der private key failed")?
+            .context("export curve 25519 pkcs8 der private key failed")?
             .as_bytes()
             .to_vec(),
     })
@@ -183,12 +341,59 @@ pub(crate) fn export_curve_25519_public_key(
     Ok(match format {
         KeyFormat::Pem => public_key
             .to_public_key_pem(base64ct::LineEnding::LF)
-            .context("init pem private key failed")?
+            .context("export curve 25519 pem public key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => public_key
             .to_public_key_der()
-            .context("init der private key failed")?
+            .context("export curve 25519 der public key failed")?
             .to_vec(),
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::{transfer_edwards_key, EdwardsKeyDto, EdwardsKeyFormat};
+    use crate::enums::{EdwardsCurveName, TextEncoding};
+
+    // RFC 8032 7.1 test 1
+    const SEED: &str =
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBLIC: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    #[test]
+    fn test_transfer_raw_and_jwk() {
+        let dto = |format, encoding| EdwardsKeyDto { format, encoding };
+        let raw = dto(EdwardsKeyFormat::Raw, TextEncoding::Hex);
+        let jwk = dto(EdwardsKeyFormat::Jwk, TextEncoding::Utf8);
+        let transfer = |private_key: Option<&str>, from, to| {
+            transfer_edwards_key(
+                EdwardsCurveName::Curve25519,
+                private_key.map(str::to_string),
+                None,
+                from,
+                to,
+                None,
+            )
+            .unwrap()
+        };
+        let jwks = transfer(Some(SEED), raw, jwk);
+        let public: serde_json::Value =
+            serde_json::from_str(jwks.public_key.as_ref().unwrap()).unwrap();
+        assert_eq!(public["x"], "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo");
+
+        let pem = transfer(
+            jwks.private_key.as_deref(),
+            jwk,
+            dto(EdwardsKeyFormat::Pem, TextEncoding::Utf8),
+        );
+        let back = transfer(
+            pem.private_key.as_deref(),
+            dto(EdwardsKeyFormat::Pem, TextEncoding::Utf8),
+            raw,
+        );
+        assert_eq!(back.private_key.unwrap(), SEED);
+        assert_eq!(back.public_key.unwrap(), PUBLIC);
+    }
+}
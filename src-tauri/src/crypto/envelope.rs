@@ -0,0 +1,227 @@
+//! The kms envelope pattern: data is encrypted under a fresh data key and
+//! only that key, wrapped by the master key, is stored next to the data.
+
+use std::fmt::Debug;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use zeroize::Zeroizing;
+
+use crate::{
+    crypto::aes::encrypt_or_decrypt_aes,
+    enums::{AesEncryptionPadding, EncryptionMode, TextEncoding},
+    errors::{Error, Result},
+    jwt::JwkeyUsage,
+    pipeline, rng, vault,
+};
+
+const NONCE_SIZE: usize = 12;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataKeyDto {
+    pub master_key: String,
+    pub master_key_encoding: TextEncoding,
+    /// data key size in bits, 128 or 256
+    pub key_size: usize,
+    /// bound to the wrapped key as aad, like a kms encryption context
+    pub context: Option<String>,
+    pub output_encoding: TextEncoding,
+}
+
+impl Debug for DataKeyDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataKeyDto")
+            .field("master_key_encoding", &self.master_key_encoding)
+            .field("key_size", &self.key_size)
+            .field("context", &self.context)
+            .field("output_encoding", &self.output_encoding)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DataKey {
+    pub plaintext: String,
+    /// nonce || AES-GCM(master key, data key) with the context as aad
+    pub wrapped: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvelopeDecryptDto {
+    pub master_key: String,
+    pub master_key_encoding: TextEncoding,
+    pub wrapped_key: String,
+    pub wrapped_key_encoding: TextEncoding,
+    pub context: Option<String>,
+    /// AES-GCM ciphertext and tag made with the data key
+    pub input: String,
+    pub input_encoding: TextEncoding,
+    pub iv: String,
+    pub iv_encoding: TextEncoding,
+    pub aad: Option<String>,
+    pub aad_encoding: Option<TextEncoding>,
+    pub output_encoding: TextEncoding,
+}
+
+impl Debug for EnvelopeDecryptDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvelopeDecryptDto")
+            .field("master_key_encoding", &self.master_key_encoding)
+            .field("wrapped_key", &self.wrapped_key)
+            .field("wrapped_key_encoding", &self.wrapped_key_encoding)
+            .field("context", &self.context)
+            .field("input_encoding", &self.input_encoding)
+            .field("iv", &self.iv)
+            .field("iv_encoding", &self.iv_encoding)
+            .field("aad", &self.aad)
+            .field("aad_encoding", &self.aad_encoding)
+            .field("output_encoding", &self.output_encoding)
+            .finish()
+    }
+}
+
+/// The data key comes back in the clear, to use and forget, and wrapped
+/// under the master key, to store with the data.
+#[tauri::command]
+pub fn generate_data_key(data: DataKeyDto) -> Result<DataKey> {
+    info!("generate data key: {:?}", data);
+    if ![128, 256].contains(&data.key_size) {
+        return Err(Error::Unsupported(format!(
+            "data key size {}",
+            data.key_size
+        )));
+    }
+    let master_key = master_key(&data.master_key, data.master_key_encoding)?;
+    let mut data_key = Zeroizing::new(vec![0u8; data.key_size / 8]);
+    let mut nonce = vec![0u8; NONCE_SIZE];
+    let mut rng = rng::rng();
+    rng.fill_bytes(&mut data_key);
+    rng.fill_bytes(&mut nonce);
+    let mut wrapped = nonce.clone();
+    wrapped.extend(encrypt_or_decrypt_aes(
+        EncryptionMode::Gcm,
+        &data_key,
+        &master_key,
+        Some(nonce),
+        data.context.map(String::into_bytes),
+        AesEncryptionPadding::NoPadding,
+        true,
+    )?);
+    Ok(DataKey {
+        plaintext: data.output_encoding.encode(&data_key)?,
+        wrapped: data.output_encoding.encode(&wrapped)?,
+    })
+}
+
+#[tauri::command]
+pub fn decrypt_with_wrapped_key(data: EnvelopeDecryptDto) -> Result<String> {
+    info!("decrypt with wrapped key: {:?}", data);
+    let master_key = master_key(&data.master_key, data.master_key_encoding)?;
+    let wrapped = data
+        .wrapped_key_encoding
+        .decode_lenient(&data.wrapped_key)?;
+    if wrapped.len() <= NONCE_SIZE {
+        return Err(Error::Unsupported(format!(
+            "wrapped key of {} bytes",
+            wrapped.len()
+        )));
+    }
+    let (nonce, wrapped) = wrapped.split_at(NONCE_SIZE);
+    let data_key = encrypt_or_decrypt_aes(
+        EncryptionMode::Gcm,
+        wrapped,
+        &master_key,
+        Some(nonce.to_vec()),
+        data.context.map(String::into_bytes),
+        AesEncryptionPadding::NoPadding,
+        false,
+    )
+    .map_err(|_| {
+        Error::Unsupported(
+            "wrapped key does not open under this master key and context"
+                .to_string(),
+        )
+    })?;
+    let aad = match (&data.aad, data.aad_encoding) {
+        (Some(aad), Some(encoding)) => Some(encoding.decode(aad)?),
+        _ => None,
+    };
+    let iv = data.iv_encoding.decode(&data.iv)?;
+    if iv.len() != NONCE_SIZE {
+        return Err(Error::Unsupported(format!("gcm iv size {}", iv.len())));
+    }
+    let plaintext = encrypt_or_decrypt_aes(
+        EncryptionMode::Gcm,
+        &data.input_encoding.decode(&data.input)?,
+        &data_key,
+        Some(iv),
+        aad,
+        AesEncryptionPadding::NoPadding,
+        false,
+    )?;
+    data.output_encoding.encode(&plaintext)
+}
+
+fn master_key(key: &str, encoding: TextEncoding) -> Result<Vec<u8>> {
+    let key = encoding.decode_lenient(&pipeline::resolve(key)?)?;
+    vault::enforce_policy(&key, JwkeyUsage::Encryption, "AES-GCM")?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        decrypt_with_wrapped_key, generate_data_key, DataKeyDto,
+        EnvelopeDecryptDto,
+    };
+    use crate::{
+        crypto::aes::encrypt_or_decrypt_aes,
+        enums::{AesEncryptionPadding, EncryptionMode, TextEncoding},
+    };
+
+    #[test]
+    fn test_envelope() {
+        let master_key = "00".repeat(32);
+        let data_key = generate_data_key(DataKeyDto {
+            master_key: master_key.clone(),
+            master_key_encoding: TextEncoding::Hex,
+            key_size: 256,
+            context: Some("tenant=kits".to_string()),
+            output_encoding: TextEncoding::Hex,
+        })
+        .unwrap();
+        let ciphertext = encrypt_or_decrypt_aes(
+            EncryptionMode::Gcm,
+            b"envelope",
+            &TextEncoding::Hex.decode(&data_key.plaintext).unwrap(),
+            Some(vec![7; 12]),
+            None,
+            AesEncryptionPadding::NoPadding,
+            true,
+        )
+        .unwrap();
+        let dto = |context: &str| EnvelopeDecryptDto {
+            master_key: master_key.clone(),
+            master_key_encoding: TextEncoding::Hex,
+            wrapped_key: data_key.wrapped.clone(),
+            wrapped_key_encoding: TextEncoding::Hex,
+            context: Some(context.to_string()),
+            input: TextEncoding::Hex.encode(&ciphertext).unwrap(),
+            input_encoding: TextEncoding::Hex,
+            iv: "07".repeat(12),
+            iv_encoding: TextEncoding::Hex,
+            aad: None,
+            aad_encoding: None,
+            output_encoding: TextEncoding::Utf8,
+        };
+        assert_eq!(
+            decrypt_with_wrapped_key(dto("tenant=kits")).unwrap(),
+            "envelope"
+        );
+        assert!(decrypt_with_wrapped_key(dto("tenant=other")).is_err());
+    }
+}
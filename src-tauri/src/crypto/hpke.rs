@@ -0,0 +1,448 @@
+use std::{fmt::Debug, time::Instant};
+
+use anyhow::Context;
+use hpke::{
+    aead::{Aead, AesGcm128, AesGcm256, ChaCha20Poly1305},
+    kdf::{HkdfSha256, HkdfSha384, HkdfSha512, Kdf},
+    kem::{DhP256HkdfSha256, Kem, X25519HkdfSha256},
+    Deserializable, OpModeR, OpModeS, PskBundle, Serializable,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    add_encryption_trait_impl,
+    crypto::{EncryptionDto, OperationResult},
+    enums::{HpkeAead, HpkeKdf, HpkeKem, HpkeMode, TextEncoding},
+    errors::{Error, Result},
+    rng,
+    utils::KeyTuple,
+};
+
+add_encryption_trait_impl!(HpkeDto {
+    kem: HpkeKem,
+    kdf: HpkeKdf,
+    aead: HpkeAead,
+    mode: HpkeMode,
+    info: Option<String>,
+    info_encoding: Option<TextEncoding>,
+    aad: Option<String>,
+    aad_encoding: Option<TextEncoding>,
+    psk: Option<String>,
+    psk_id: Option<String>,
+    psk_encoding: Option<TextEncoding>,
+    sender_key: Option<String>,
+    sender_key_encoding: Option<TextEncoding>,
+    for_encryption: bool
+});
+
+impl Debug for HpkeDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HpkeDto")
+            .field("input_encoding", &self.input_encoding)
+            .field("key_encoding", &self.key_encoding)
+            .field("output_encoding", &self.output_encoding)
+            .field("kem", &self.kem)
+            .field("kdf", &self.kdf)
+            .field("aead", &self.aead)
+            .field("mode", &self.mode)
+            .field("for_encryption", &self.for_encryption)
+            .finish()
+    }
+}
+
+struct HpkeParams {
+    info: Vec<u8>,
+    aad: Vec<u8>,
+    psk: Option<(Vec<u8>, Vec<u8>)>,
+    sender_key: Option<Vec<u8>>,
+}
+
+impl HpkeDto {
+    fn params(&self) -> Result<HpkeParams> {
+        let decode = |value: &Option<String>,
+                      encoding: Option<TextEncoding>,
+                      name: &str|
+         -> Result<Option<Vec<u8>>> {
+            match (value, encoding) {
+                (Some(value), Some(encoding)) => {
+                    encoding.decode(value).map(Some)
+                }
+                (Some(_), None) => Err(Error::Unsupported(format!(
+                    "{} encoding is required",
+                    name
+                ))),
+                _ => Ok(None),
+            }
+        };
+        let psk = match (self.mode, &self.psk, &self.psk_id) {
+            (HpkeMode::Psk | HpkeMode::AuthPsk, Some(_), Some(_)) => Some((
+                decode(&self.psk, self.psk_encoding, "psk")?
+                    .unwrap_or_default(),
+                decode(&self.psk_id, self.psk_encoding, "psk")?
+                    .unwrap_or_default(),
+            )),
+            (HpkeMode::Psk | HpkeMode::AuthPsk, ..) => {
+                return Err(Error::Unsupported(
+                    "psk mode without psk and psk_id".to_string(),
+                ));
+            }
+            _ => None,
+        };
+        let sender_key = match self.mode {
+            HpkeMode::Auth | HpkeMode::AuthPsk => Some(
+                decode(
+                    &self.sender_key,
+                    self.sender_key_encoding,
+                    "sender key",
+                )?
+                .ok_or(Error::Unsupported(
+                    "auth mode without sender key".to_string(),
+                ))?,
+            ),
+            _ => None,
+        };
+        Ok(HpkeParams {
+            info: decode(&self.info, self.info_encoding, "info")?
+                .unwrap_or_default(),
+            aad: decode(&self.aad, self.aad_encoding, "aad")?
+                .unwrap_or_default(),
+            psk,
+            sender_key,
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn generate_hpke(
+    kem: HpkeKem,
+    encoding: TextEncoding,
+) -> Result<KeyTuple> {
+    info!("generate hpke key, kem: {:?}", kem);
+    let (private_key, public_key) = match kem {
+        HpkeKem::X25519HkdfSha256 => generate_hpke_inner::<X25519HkdfSha256>(),
+        HpkeKem::P256HkdfSha256 => generate_hpke_inner::<DhP256HkdfSha256>(),
+    };
+    let mut key = KeyTuple::new(
+        encoding.encode(&private_key)?,
+        encoding.encode(&public_key)?,
+    );
+    key.algorithm("HPKE")
+        .parameter("kem", kem)
+        .fingerprint(&public_key);
+    Ok(key)
+}
+
+#[tauri::command]
+pub async fn hpke(data: HpkeDto) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    info!("hpke: {:?}", data);
+    let key = data.get_key()?;
+    let input = data.get_input()?;
+    let params = data.params()?;
+    let output = match data.kem {
+        HpkeKem::X25519HkdfSha256 => {
+            hpke_kdf::<X25519HkdfSha256>(&data, &key, &input, &params)
+        }
+        HpkeKem::P256HkdfSha256 => {
+            hpke_kdf::<DhP256HkdfSha256>(&data, &key, &input, &params)
+        }
+    }?;
+    Ok(
+        OperationResult::encoded(&output, data.get_output_encoding(), started)?
+            .metadata("kem", format!("{:?}", data.kem))
+            .metadata("kdf", format!("{:?}", data.kdf))
+            .metadata("aead", format!("{:?}", data.aead))
+            .metadata("mode", format!("{:?}", data.mode)),
+    )
+}
+
+fn generate_hpke_inner<K: Kem>() -> (Vec<u8>, Vec<u8>) {
+    let (private_key, public_key) = K::gen_keypair(&mut rng::rng());
+    (
+        private_key.to_bytes().to_vec(),
+        public_key.to_bytes().to_vec(),
+    )
+}
+
+fn hpke_kdf<K: Kem>(
+    data: &HpkeDto,
+    key: &[u8],
+    input: &[u8],
+    params: &HpkeParams,
+) -> Result<Vec<u8>> {
+    match data.kdf {
+        HpkeKdf::HkdfSha256 => {
+            hpke_aead::<K, HkdfSha256>(data, key, input, params)
+        }
+        HpkeKdf::HkdfSha384 => {
+            hpke_aead::<K, HkdfSha384>(data, key, input, params)
+        }
+        HpkeKdf::HkdfSha512 => {
+            hpke_aead::<K, HkdfSha512>(data, key, input, params)
+        }
+    }
+}
+
+fn hpke_aead<K: Kem, F: Kdf>(
+    data: &HpkeDto,
+    key: &[u8],
+    input: &[u8],
+    params: &HpkeParams,
+) -> Result<Vec<u8>> {
+    match (data.aead, data.for_encryption) {
+        (HpkeAead::Aes128Gcm, true) => {
+            seal::<K, F, AesGcm128>(key, input, params)
+        }
+        (HpkeAead::Aes256Gcm, true) => {
+            seal::<K, F, AesGcm256>(key, input, params)
+        }
+        (HpkeAead::ChaCha20Poly1305, true) => {
+            seal::<K, F, ChaCha20Poly1305>(key, input, params)
+        }
+        (HpkeAead::Aes128Gcm, false) => {
+            open::<K, F, AesGcm128>(key, input, params)
+        }
+        (HpkeAead::Aes256Gcm, false) => {
+            open::<K, F, AesGcm256>(key, input, params)
+        }
+        (HpkeAead::ChaCha20Poly1305, false) => {
+            open::<K, F, ChaCha20Poly1305>(key, input, params)
+        }
+    }
+}
+
+/// The encapsulated key, of a fixed size per kem, goes first.
+fn seal<K: Kem, F: Kdf, A: Aead>(
+    key: &[u8],
+    input: &[u8],
+    params: &HpkeParams,
+) -> Result<Vec<u8>> {
+    let public_key = K::PublicKey::from_bytes(key)
+        .map_err(|err| anyhow::anyhow!("invalid hpke public key: {}", err))?;
+    let sender = params
+        .sender_key
+        .as_ref()
+        .map(|sender_key| {
+            let private_key =
+                K::PrivateKey::from_bytes(sender_key).map_err(|err| {
+                    anyhow::anyhow!("invalid hpke sender key: {}", err)
+                })?;
+            let public_key = K::sk_to_pk(&private_key);
+            Ok::<_, Error>((private_key, public_key))
+        })
+        .transpose()?;
+    let psk = params
+        .psk
+        .as_ref()
+        .map(|(psk, psk_id)| PskBundle { psk, psk_id });
+    let mode = match (sender, psk) {
+        (None, None) => OpModeS::Base,
+        (None, Some(psk)) => OpModeS::Psk(psk),
+        (Some(sender), None) => OpModeS::Auth(sender),
+        (Some(sender), Some(psk)) => OpModeS::AuthPsk(sender, psk),
+    };
+    let (encapped_key, ciphertext) = hpke::single_shot_seal::<A, F, K, _>(
+        &mode,
+        &public_key,
+        &params.info,
+        input,
+        &params.aad,
+        &mut rng::rng(),
+    )
+    .map_err(|err| anyhow::anyhow!("hpke seal failed: {}", err))?;
+    let mut output = encapped_key.to_bytes().to_vec();
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+fn open<K: Kem, F: Kdf, A: Aead>(
+    key: &[u8],
+    input: &[u8],
+    params: &HpkeParams,
+) -> Result<Vec<u8>> {
+    let private_key = K::PrivateKey::from_bytes(key)
+        .map_err(|err| anyhow::anyhow!("invalid hpke private key: {}", err))?;
+    let encapped_key_size = K::EncappedKey::size();
+    if input.len() < encapped_key_size {
+        return Err(Error::Unsupported(
+            "hpke ciphertext shorter than the encapsulated key".to_string(),
+        ));
+    }
+    let (encapped_key, ciphertext) = input.split_at(encapped_key_size);
+    let encapped_key = K::EncappedKey::from_bytes(encapped_key)
+        .map_err(|err| anyhow::anyhow!("invalid encapsulated key: {}", err))?;
+    let sender = params
+        .sender_key
+        .as_ref()
+        .map(|sender_key| {
+            K::PublicKey::from_bytes(sender_key).map_err(|err| {
+                anyhow::anyhow!("invalid hpke sender key: {}", err)
+            })
+        })
+        .transpose()?;
+    let psk = params
+        .psk
+        .as_ref()
+        .map(|(psk, psk_id)| PskBundle { psk, psk_id });
+    let mode = match (sender, psk) {
+        (None, None) => OpModeR::Base,
+        (None, Some(psk)) => OpModeR::Psk(psk),
+        (Some(sender), None) => OpModeR::Auth(sender),
+        (Some(sender), Some(psk)) => OpModeR::AuthPsk(sender, psk),
+    };
+    Ok(hpke::single_shot_open::<A, F, K>(
+        &mode,
+        &private_key,
+        &encapped_key,
+        &params.info,
+        ciphertext,
+        &params.aad,
+    )
+    .context("hpke open failed")?)
+}
+
+#[cfg(test)]
+mod test {
+    use strum::IntoEnumIterator;
+
+    use super::{generate_hpke, hpke, HpkeDto};
+    use crate::enums::{HpkeAead, HpkeKdf, HpkeKem, HpkeMode, TextEncoding};
+
+    #[tokio::test]
+    async fn test_hpke_modes() {
+        let encoding = TextEncoding::Base64;
+        for kem in HpkeKem::iter() {
+            let recipient = generate_hpke(kem, encoding).await.unwrap();
+            let sender = generate_hpke(kem, encoding).await.unwrap();
+            for mode in HpkeMode::iter() {
+                for kdf in HpkeKdf::iter() {
+                    for aead in HpkeAead::iter() {
+                        let dto =
+                            |key: &Option<String>,
+                             sender_key: &Option<String>,
+                             input: String,
+                             for_encryption| {
+                                HpkeDto {
+                                    input,
+                                    input_encoding: if for_encryption {
+                                        TextEncoding::Utf8
+                                    } else {
+                                        encoding
+                                    },
+                                    key: key.clone().unwrap(),
+                                    key_encoding: encoding,
+                                    input_path: None,
+                                    key_path: None,
+                                    output_encoding: if for_encryption {
+                                        encoding
+                                    } else {
+                                        TextEncoding::Utf8
+                                    },
+                                    kem,
+                                    kdf,
+                                    aead,
+                                    mode,
+                                    info: Some("info".to_string()),
+                                    info_encoding: Some(TextEncoding::Utf8),
+                                    aad: Some("aad".to_string()),
+                                    aad_encoding: Some(TextEncoding::Utf8),
+                                    psk: Some("a".repeat(32)),
+                                    psk_id: Some("psk id".to_string()),
+                                    psk_encoding: Some(TextEncoding::Utf8),
+                                    sender_key: sender_key.clone(),
+                                    sender_key_encoding: Some(encoding),
+                                    for_encryption,
+                                }
+                            };
+                        let ciphertext = hpke(dto(
+                            &recipient.public_key,
+                            &sender.private_key,
+                            "plaintext".to_string(),
+                            true,
+                        ))
+                        .await
+                        .unwrap()
+                        .output;
+                        let plaintext = hpke(dto(
+                            &recipient.private_key,
+                            &sender.public_key,
+                            ciphertext,
+                            false,
+                        ))
+                        .await
+                        .unwrap()
+                        .output;
+                        assert_eq!("plaintext", plaintext);
+                    }
+                }
+            }
+        }
+    }
+
+    /// RFC 9180 A.1.1 and A.1.4, DHKEM(X25519, HKDF-SHA256), HKDF-SHA256,
+    /// AES-128-GCM, the first message of each.
+    #[tokio::test]
+    async fn test_hpke_rfc9180() {
+        let open = |mode,
+                    key: &str,
+                    sender_key: Option<&str>,
+                    input: String| {
+            hpke(HpkeDto {
+                input,
+                input_encoding: TextEncoding::Hex,
+                key: key.to_string(),
+                key_encoding: TextEncoding::Hex,
+                input_path: None,
+                key_path: None,
+                output_encoding: TextEncoding::Utf8,
+                kem: HpkeKem::X25519HkdfSha256,
+                kdf: HpkeKdf::HkdfSha256,
+                aead: HpkeAead::Aes128Gcm,
+                mode,
+                info: Some("Ode on a Grecian Urn".to_string()),
+                info_encoding: Some(TextEncoding::Utf8),
+                aad: Some("Count-0".to_string()),
+                aad_encoding: Some(TextEncoding::Utf8),
+                psk: Some(
+                    "0247fd33b913760fa1fa51e1892d9f307fbe65eb171e8132c2af18555a738b82"
+                        .to_string(),
+                ),
+                psk_id: Some("456e6e796e20447572696e206172616e204d6f726961".to_string()),
+                psk_encoding: Some(TextEncoding::Hex),
+                sender_key: sender_key.map(str::to_string),
+                sender_key_encoding: Some(TextEncoding::Hex),
+                for_encryption: false,
+            })
+        };
+        let base = open(
+            HpkeMode::Base,
+            "4612c550263fc8ad58375df3f557aac531d26850903e55a9f23f21d8534e8ac8",
+            None,
+            [
+                "37fda3567bdbd628e88668c3c8d7e97d1d1253b6d4ea6d44c150f741f1bf4431",
+                "f938558b5d72f1a23810b4be2ab4f84331acc02fc97babc53a52ae8218a355a9",
+                "6d8770ac83d07bea87e13c512a",
+            ]
+            .concat(),
+        );
+        assert_eq!(base.await.unwrap().output, "Beauty is truth, truth beauty");
+        let auth_psk = open(
+            HpkeMode::AuthPsk,
+            "cb29a95649dc5656c2d054c1aa0d3df0493155e9d5da6d7e344ed8b6a64a9423",
+            Some(
+                "2bfb2eb18fcad1af0e4f99142a1c474ae74e21b9425fc5c589382c69b50cc57e",
+            ),
+            [
+                "820818d3c23993492cc5623ab437a48a0a7ca3e9639c140fe1e33811eb844b7c",
+                "a84c64df1e11d8fd11450039d4fe64ff0c8a99fca0bd72c2d4c3e0400bc14a40",
+                "f27e45e141a24001697737533e",
+            ]
+            .concat(),
+        );
+        assert_eq!(
+            auth_psk.await.unwrap().output,
+            "Beauty is truth, truth beauty"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt::Debug, vec};
+use std::{fmt::Debug, time::Instant, vec};
 
 use anyhow::Context;
 use crypto_common::BlockSizeUser;
@@ -11,10 +11,11 @@ use digest::{
 use hkdf::hmac::Hmac;
 use serde::{Deserialize, Serialize};
 
-use super::EncryptionDto;
+use super::{EncryptionDto, OperationResult};
 use crate::{
     enums::{Digest, Kdf, TextEncoding},
     errors::{Error, Result},
+    profile::{self, Algorithm},
 };
 
 pub(crate) const SALT: &str = "VSPDJrx1Pj1zqVGN";
@@ -50,7 +51,8 @@ impl Debug for KdfDto {
 
 impl EncryptionDto for KdfDto {
     fn get_input(&self) -> Result<Vec<u8>> {
-        self.input_encoding.decode(&self.input)
+        self.input_encoding
+            .decode(&crate::pipeline::resolve(&self.input)?)
     }
 
     fn get_key(&self) -> Result<Vec<u8>> {
@@ -63,7 +65,10 @@ impl EncryptionDto for KdfDto {
 }
 
 #[tauri::command]
-pub fn kdf(data: KdfDto) -> Result<String> {
+pub fn kdf(data: KdfDto) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    profile::check(Algorithm::Kdf(data.kdf))?;
+    profile::check(Algorithm::Digest(data.digest))?;
     let input = data.get_input()?;
     let salt_encoding = data.salt_encoding;
     let info_encoding = data.info_encoding;
@@ -74,6 +79,7 @@ pub fn kdf(data: KdfDto) -> Result<String> {
         info_encoding.and_then(|encoding| encoding.decode(&s).ok())
     });
 
+    let warnings = warnings(data.kdf, data.digest, salt.as_deref());
     let output = kdf_inner_digest(
         data.kdf,
         data.digest,
@@ -83,7 +89,41 @@ pub fn kdf(data: KdfDto) -> Result<String> {
         data.key_length,
     )?;
 
-    data.output_encoding.encode(&output)
+    Ok(
+        OperationResult::encoded(&output, data.output_encoding, started)?
+            .metadata("kdf", format!("{:?}", data.kdf))
+            .metadata("digest", format!("{:?}", data.digest))
+            .metadata("keyLength", data.key_length)
+            .warnings(warnings),
+    )
+}
+
+fn warnings(kdf: Kdf, digest: Digest, salt: Option<&[u8]>) -> Vec<String> {
+    let mut warnings = vec![];
+    if digest == Digest::Sha1 {
+        warnings.push("sha-1 is kept for legacy derivations only".to_string());
+    }
+    match salt {
+        Some(salt) if salt == SALT.as_bytes() => warnings.push(
+            "static salt used, every derivation of the same input collides"
+                .to_string(),
+        ),
+        Some(salt)
+            if matches!(kdf, Kdf::PbKdf2 | Kdf::Scrypt) && salt.len() < 16 =>
+        {
+            warnings.push(format!(
+                "{} byte salt, password hashing wants 16 at least",
+                salt.len()
+            ))
+        }
+        None if kdf == Kdf::HKdf => warnings.push(
+            "hkdf without salt extracts with zeros, fine only for uniformly \
+             random input"
+                .to_string(),
+        ),
+        _ => {}
+    }
+    warnings
 }
 
 pub(crate) fn kdf_inner_digest(
@@ -116,6 +156,9 @@ pub(crate) fn kdf_inner_digest(
         Digest::Sha3_512 => {
             kdf_inner::<sha3::Sha3_512>(kdf, input, salt, info, key_size)
         }
+        Digest::Keccak256 => {
+            kdf_inner::<sha3::Keccak256>(kdf, input, salt, info, key_size)
+        }
     }
 }
 
@@ -0,0 +1,396 @@
+//! Merkle trees of certificate transparency logs (RFC 9162 section 2.1).
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    crypto::digest::digest_inner,
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleTreeDto {
+    pub items: Vec<String>,
+    pub encoding: TextEncoding,
+    pub digest: Digest,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleTree {
+    pub size: usize,
+    pub root: String,
+    pub leaves: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleProofDto {
+    pub items: Vec<String>,
+    pub encoding: TextEncoding,
+    pub digest: Digest,
+    /// the leaf index for an audit proof, the old tree size for a
+    /// consistency proof
+    pub index: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleAuditProof {
+    pub digest: Digest,
+    pub index: usize,
+    pub size: usize,
+    pub leaf: String,
+    pub path: Vec<String>,
+    pub root: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleConsistencyProof {
+    pub digest: Digest,
+    pub old_size: usize,
+    pub old_root: String,
+    pub size: usize,
+    pub root: String,
+    pub path: Vec<String>,
+}
+
+#[tauri::command]
+pub fn merkle_tree(data: MerkleTreeDto) -> Result<MerkleTree> {
+    info!("merkle tree: {:?}, size: {}", data.digest, data.items.len());
+    let leaves = leaves(&data.items, data.encoding, data.digest)?;
+    Ok(MerkleTree {
+        size: leaves.len(),
+        root: TextEncoding::Hex.encode(&root(data.digest, &leaves))?,
+        leaves: leaves
+            .iter()
+            .map(|leaf| TextEncoding::Hex.encode(leaf))
+            .collect::<Result<_>>()?,
+    })
+}
+
+#[tauri::command]
+pub fn merkle_audit_proof(data: MerkleProofDto) -> Result<MerkleAuditProof> {
+    info!(
+        "merkle audit proof: {:?}, index: {}, size: {}",
+        data.digest,
+        data.index,
+        data.items.len()
+    );
+    let leaves = leaves(&data.items, data.encoding, data.digest)?;
+    if data.index >= leaves.len() {
+        return Err(Error::Unsupported(format!(
+            "leaf {} of a tree of {}",
+            data.index,
+            leaves.len()
+        )));
+    }
+    Ok(MerkleAuditProof {
+        digest: data.digest,
+        index: data.index,
+        size: leaves.len(),
+        leaf: TextEncoding::Hex.encode(&leaves[data.index])?,
+        path: hex(audit_path(data.digest, data.index, &leaves))?,
+        root: TextEncoding::Hex.encode(&root(data.digest, &leaves))?,
+    })
+}
+
+#[tauri::command]
+pub fn merkle_consistency_proof(
+    data: MerkleProofDto,
+) -> Result<MerkleConsistencyProof> {
+    info!(
+        "merkle consistency proof: {:?}, old size: {}, size: {}",
+        data.digest,
+        data.index,
+        data.items.len()
+    );
+    let leaves = leaves(&data.items, data.encoding, data.digest)?;
+    if data.index == 0 || data.index > leaves.len() {
+        return Err(Error::Unsupported(format!(
+            "consistency of {} with a tree of {}",
+            data.index,
+            leaves.len()
+        )));
+    }
+    Ok(MerkleConsistencyProof {
+        digest: data.digest,
+        old_size: data.index,
+        old_root: TextEncoding::Hex
+            .encode(&root(data.digest, &leaves[.. data.index]))?,
+        size: leaves.len(),
+        root: TextEncoding::Hex.encode(&root(data.digest, &leaves))?,
+        path: hex(subproof(data.digest, data.index, &leaves, true))?,
+    })
+}
+
+/// RFC 9162 2.1.3.2, the leaf is its hash as given in the proof.
+#[tauri::command]
+pub fn verify_merkle_audit_proof(data: MerkleAuditProof) -> Result<bool> {
+    info!("verify merkle audit proof: {:?}", data);
+    if data.index >= data.size {
+        return Ok(false);
+    }
+    let (mut index, mut last) = (data.index, data.size - 1);
+    let mut hash = TextEncoding::Hex.decode(&data.leaf)?;
+    for sibling in decode(&data.path)? {
+        if last == 0 {
+            return Ok(false);
+        }
+        if index & 1 == 1 || index == last {
+            hash = node(data.digest, &sibling, &hash);
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node(data.digest, &hash, &sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+    Ok(last == 0 && hash == TextEncoding::Hex.decode(&data.root)?)
+}
+
+/// RFC 9162 2.1.4.2.
+#[tauri::command]
+pub fn verify_merkle_consistency_proof(
+    data: MerkleConsistencyProof,
+) -> Result<bool> {
+    info!("verify merkle consistency proof: {:?}", data);
+    let old_root = TextEncoding::Hex.decode(&data.old_root)?;
+    let root = TextEncoding::Hex.decode(&data.root)?;
+    let mut path = decode(&data.path)?;
+    if data.old_size == 0 || data.old_size > data.size {
+        return Ok(false);
+    }
+    if data.old_size == data.size {
+        return Ok(path.is_empty() && old_root == root);
+    }
+    if data.old_size.is_power_of_two() {
+        path.insert(0, old_root.clone());
+    }
+    let Some((first, path)) = path.split_first() else {
+        return Ok(false);
+    };
+    let (mut index, mut last) = (data.old_size - 1, data.size - 1);
+    while index & 1 == 1 {
+        index >>= 1;
+        last >>= 1;
+    }
+    let (mut old_hash, mut hash) = (first.clone(), first.clone());
+    for sibling in path {
+        if last == 0 {
+            return Ok(false);
+        }
+        if index & 1 == 1 || index == last {
+            old_hash = node(data.digest, sibling, &old_hash);
+            hash = node(data.digest, sibling, &hash);
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node(data.digest, &hash, sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+    Ok(last == 0 && old_hash == old_root && hash == root)
+}
+
+fn leaves(
+    items: &[String],
+    encoding: TextEncoding,
+    digest: Digest,
+) -> Result<Vec<Vec<u8>>> {
+    items
+        .iter()
+        .map(|item| {
+            let mut input = vec![0x00];
+            input.extend(encoding.decode(item)?);
+            Ok(digest_inner(digest, &input))
+        })
+        .collect()
+}
+
+fn node(digest: Digest, left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut input = Vec::with_capacity(1 + left.len() + right.len());
+    input.push(0x01);
+    input.extend_from_slice(left);
+    input.extend_from_slice(right);
+    digest_inner(digest, &input)
+}
+
+fn split(size: usize) -> usize {
+    1 << (usize::BITS - 1 - (size - 1).leading_zeros())
+}
+
+fn root(digest: Digest, leaves: &[Vec<u8>]) -> Vec<u8> {
+    match leaves {
+        [] => digest_inner(digest, &[]),
+        [leaf] => leaf.clone(),
+        _ => {
+            let k = split(leaves.len());
+            node(
+                digest,
+                &root(digest, &leaves[.. k]),
+                &root(digest, &leaves[k ..]),
+            )
+        }
+    }
+}
+
+fn audit_path(
+    digest: Digest,
+    index: usize,
+    leaves: &[Vec<u8>],
+) -> Vec<Vec<u8>> {
+    if leaves.len() <= 1 {
+        return vec![];
+    }
+    let k = split(leaves.len());
+    let (mut path, sibling) = if index < k {
+        (
+            audit_path(digest, index, &leaves[.. k]),
+            root(digest, &leaves[k ..]),
+        )
+    } else {
+        (
+            audit_path(digest, index - k, &leaves[k ..]),
+            root(digest, &leaves[.. k]),
+        )
+    };
+    path.push(sibling);
+    path
+}
+
+/// SUBPROOF of RFC 9162 2.1.4.1, `complete` when the old tree is the
+/// subtree itself.
+fn subproof(
+    digest: Digest,
+    size: usize,
+    leaves: &[Vec<u8>],
+    complete: bool,
+) -> Vec<Vec<u8>> {
+    if size == leaves.len() {
+        return if complete {
+            vec![]
+        } else {
+            vec![root(digest, leaves)]
+        };
+    }
+    let k = split(leaves.len());
+    let (mut path, sibling) = if size <= k {
+        (
+            subproof(digest, size, &leaves[.. k], complete),
+            root(digest, &leaves[k ..]),
+        )
+    } else {
+        (
+            subproof(digest, size - k, &leaves[k ..], false),
+            root(digest, &leaves[.. k]),
+        )
+    };
+    path.push(sibling);
+    path
+}
+
+fn hex(hashes: Vec<Vec<u8>>) -> Result<Vec<String>> {
+    hashes
+        .iter()
+        .map(|hash| TextEncoding::Hex.encode(hash))
+        .collect()
+}
+
+fn decode(hashes: &[String]) -> Result<Vec<Vec<u8>>> {
+    hashes
+        .iter()
+        .map(|hash| TextEncoding::Hex.decode(hash))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        merkle_audit_proof, merkle_consistency_proof, merkle_tree,
+        verify_merkle_audit_proof, verify_merkle_consistency_proof,
+        MerkleProofDto, MerkleTreeDto,
+    };
+    use crate::enums::{Digest, TextEncoding};
+
+    /// The leaves of the certificate transparency reference tests.
+    fn items() -> Vec<String> {
+        [
+            "",
+            "00",
+            "10",
+            "2021",
+            "3031",
+            "40414243",
+            "5051525354555657",
+            "606162636465666768696a6b6c6d6e6f",
+        ]
+        .iter()
+        .map(|item| item.to_string())
+        .collect()
+    }
+
+    fn dto(size: usize, index: usize) -> MerkleProofDto {
+        MerkleProofDto {
+            items: items()[.. size].to_vec(),
+            encoding: TextEncoding::Hex,
+            digest: Digest::Sha256,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_merkle_tree() {
+        let tree = merkle_tree(MerkleTreeDto {
+            items: items(),
+            encoding: TextEncoding::Hex,
+            digest: Digest::Sha256,
+        })
+        .unwrap();
+        assert_eq!(tree.size, 8);
+        assert_eq!(
+            tree.root,
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"
+        );
+        let tree = merkle_tree(MerkleTreeDto {
+            items: items()[.. 3].to_vec(),
+            encoding: TextEncoding::Hex,
+            digest: Digest::Sha256,
+        })
+        .unwrap();
+        assert_eq!(
+            tree.root,
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77"
+        );
+    }
+
+    #[test]
+    fn test_merkle_proofs() {
+        for size in 1 ..= 8 {
+            for index in 0 .. size {
+                let proof = merkle_audit_proof(dto(size, index)).unwrap();
+                assert!(verify_merkle_audit_proof(proof).unwrap());
+                let proof = merkle_consistency_proof(dto(size, index + 1));
+                assert!(
+                    verify_merkle_consistency_proof(proof.unwrap()).unwrap()
+                );
+            }
+        }
+        let mut proof = merkle_audit_proof(dto(7, 5)).unwrap();
+        proof.index = 4;
+        assert!(!verify_merkle_audit_proof(proof).unwrap());
+        let mut proof = merkle_consistency_proof(dto(7, 3)).unwrap();
+        proof.path.pop();
+        assert!(!verify_merkle_consistency_proof(proof).unwrap());
+        assert!(merkle_audit_proof(dto(3, 3)).is_err());
+    }
+}
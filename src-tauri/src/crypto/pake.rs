@@ -0,0 +1,394 @@
+use std::fmt::Debug;
+
+use anyhow::anyhow;
+use opaque_ke::{
+    ciphersuite::CipherSuite, ClientLogin, ClientLoginFinishParameters,
+    ClientRegistration, ClientRegistrationFinishParameters,
+    CredentialFinalization, CredentialRequest, CredentialResponse,
+    RegistrationRequest, RegistrationResponse, RegistrationUpload,
+    Ristretto255, ServerLogin, ServerLoginStartParameters, ServerRegistration,
+    ServerSetup,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use srp::{
+    client::SrpClient,
+    groups::{G_1024, G_1536, G_2048, G_3072, G_4096},
+    server::SrpServer,
+    types::SrpGroup as Group,
+};
+use tracing::info;
+
+use crate::{
+    enums::{Digest, SrpGroup, TextEncoding},
+    errors::{Error, Result},
+    rng,
+};
+
+struct OpaqueSuite;
+
+impl CipherSuite for OpaqueSuite {
+    type KeGroup = Ristretto255;
+    type KeyExchange = opaque_ke::key_exchange::tripledh::TripleDh;
+    type Ksf = opaque_ke::ksf::Identity;
+    type OprfCs = Ristretto255;
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SrpDto {
+    pub group: SrpGroup,
+    pub digest: Digest,
+    pub username: String,
+    pub password: String,
+    pub salt: Option<String>,
+    pub verifier: Option<String>,
+    pub encoding: TextEncoding,
+}
+
+impl Debug for SrpDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SrpDto")
+            .field("group", &self.group)
+            .field("digest", &self.digest)
+            .field("username", &self.username)
+            .field("encoding", &self.encoding)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SrpVerifier {
+    pub salt: String,
+    pub verifier: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SrpExchange {
+    pub salt: String,
+    pub verifier: String,
+    pub client_secret: String,
+    pub client_public: String,
+    pub server_secret: String,
+    pub server_public: String,
+    pub client_proof: String,
+    pub server_proof: String,
+    pub client_key: String,
+    pub server_key: String,
+    pub matched: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaqueDto {
+    pub username: String,
+    pub password: String,
+    pub server_setup: Option<String>,
+    pub registration_record: Option<String>,
+    pub encoding: TextEncoding,
+}
+
+impl Debug for OpaqueDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpaqueDto")
+            .field("username", &self.username)
+            .field("encoding", &self.encoding)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaqueRegistration {
+    pub server_setup: String,
+    pub registration_request: String,
+    pub registration_response: String,
+    pub registration_upload: String,
+    pub registration_record: String,
+    pub export_key: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaqueLogin {
+    pub credential_request: String,
+    pub credential_response: String,
+    pub credential_finalization: String,
+    pub client_session_key: String,
+    pub server_session_key: String,
+    pub export_key: String,
+    pub matched: bool,
+}
+
+impl SrpGroup {
+    fn as_group(&self) -> &'static Group {
+        match self {
+            SrpGroup::G1024 => &G_1024,
+            SrpGroup::G1536 => &G_1536,
+            SrpGroup::G2048 => &G_2048,
+            SrpGroup::G3072 => &G_3072,
+            SrpGroup::G4096 => &G_4096,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn srp_verifier(data: SrpDto) -> Result<SrpVerifier> {
+    info!("srp verifier: {:?}", data);
+    let salt = srp_salt(&data)?;
+    let verifier = match data.digest {
+        Digest::Sha1 => compute_verifier::<sha1::Sha1>(&data, &salt),
+        Digest::Sha256 => compute_verifier::<sha2::Sha256>(&data, &salt),
+        Digest::Sha384 => compute_verifier::<sha2::Sha384>(&data, &salt),
+        Digest::Sha512 => compute_verifier::<sha2::Sha512>(&data, &salt),
+        digest => {
+            return Err(Error::Unsupported(format!("srp digest {:?}", digest)))
+        }
+    };
+    Ok(SrpVerifier {
+        salt: data.encoding.encode(&salt)?,
+        verifier: data.encoding.encode(&verifier)?,
+    })
+}
+
+#[tauri::command]
+pub fn srp_exchange(data: SrpDto) -> Result<SrpExchange> {
+    info!("srp exchange: {:?}", data);
+    match data.digest {
+        Digest::Sha1 => srp_exchange_inner::<sha1::Sha1>(&data),
+        Digest::Sha256 => srp_exchange_inner::<sha2::Sha256>(&data),
+        Digest::Sha384 => srp_exchange_inner::<sha2::Sha384>(&data),
+        Digest::Sha512 => srp_exchange_inner::<sha2::Sha512>(&data),
+        digest => Err(Error::Unsupported(format!("srp digest {:?}", digest))),
+    }
+}
+
+#[tauri::command]
+pub fn opaque_registration(data: OpaqueDto) -> Result<OpaqueRegistration> {
+    info!("opaque registration: {:?}", data);
+    let mut rng = rng::rng();
+    let password = data.password.as_bytes();
+    let server_setup = match &data.server_setup {
+        Some(server_setup) => ServerSetup::<OpaqueSuite>::deserialize(
+            &data.encoding.decode(server_setup)?,
+        )
+        .map_err(|err| anyhow!("invalid opaque server setup: {:?}", err))?,
+        None => ServerSetup::<OpaqueSuite>::new(&mut rng),
+    };
+    let client_start = ClientRegistration::<OpaqueSuite>::start(
+        &mut rng, password,
+    )
+    .map_err(|err| anyhow!("opaque registration start failed: {:?}", err))?;
+    let server_start = ServerRegistration::<OpaqueSuite>::start(
+        &server_setup,
+        client_start.message.clone(),
+        data.username.as_bytes(),
+    )
+    .map_err(|err| anyhow!("opaque registration response failed: {:?}", err))?;
+    let client_finish = client_start
+        .state
+        .finish(
+            &mut rng,
+            password,
+            server_start.message.clone(),
+            ClientRegistrationFinishParameters::default(),
+        )
+        .map_err(|err| {
+            anyhow!("opaque registration finish failed: {:?}", err)
+        })?;
+    let record = ServerRegistration::<OpaqueSuite>::finish(
+        client_finish.message.clone(),
+    );
+    let encoding = data.encoding;
+    Ok(OpaqueRegistration {
+        server_setup: encoding.encode(&server_setup.serialize())?,
+        registration_request: encoding
+            .encode(&RegistrationRequest::serialize(&client_start.message))?,
+        registration_response: encoding
+            .encode(&RegistrationResponse::serialize(&server_start.message))?,
+        registration_upload: encoding
+            .encode(&RegistrationUpload::serialize(&client_finish.message))?,
+        registration_record: encoding.encode(&record.serialize())?,
+        export_key: encoding.encode(&client_finish.export_key)?,
+    })
+}
+
+#[tauri::command]
+pub fn opaque_login(data: OpaqueDto) -> Result<OpaqueLogin> {
+    info!("opaque login: {:?}", data);
+    let (Some(server_setup), Some(record)) =
+        (&data.server_setup, &data.registration_record)
+    else {
+        return Err(Error::Unsupported(
+            "opaque login without server setup or registration record"
+                .to_string(),
+        ));
+    };
+    let mut rng = rng::rng();
+    let password = data.password.as_bytes();
+    let server_setup = ServerSetup::<OpaqueSuite>::deserialize(
+        &data.encoding.decode(server_setup)?,
+    )
+    .map_err(|err| anyhow!("invalid opaque server setup: {:?}", err))?;
+    let record = ServerRegistration::<OpaqueSuite>::deserialize(
+        &data.encoding.decode(record)?,
+    )
+    .map_err(|err| anyhow!("invalid opaque registration record: {:?}", err))?;
+    let client_start = ClientLogin::<OpaqueSuite>::start(&mut rng, password)
+        .map_err(|err| anyhow!("opaque login start failed: {:?}", err))?;
+    let server_start = ServerLogin::start(
+        &mut rng,
+        &server_setup,
+        Some(record),
+        client_start.message.clone(),
+        data.username.as_bytes(),
+        ServerLoginStartParameters::default(),
+    )
+    .map_err(|err| anyhow!("opaque credential response failed: {:?}", err))?;
+    let credential_response = server_start.message.clone();
+    let client_finish = client_start
+        .state
+        .finish(
+            password,
+            server_start.message,
+            ClientLoginFinishParameters::default(),
+        )
+        .map_err(|err| anyhow!("opaque login finish failed: {:?}", err))?;
+    let server_finish = server_start
+        .state
+        .finish(client_finish.message.clone())
+        .map_err(|err| anyhow!("opaque server finish failed: {:?}", err))?;
+    let encoding = data.encoding;
+    Ok(OpaqueLogin {
+        credential_request: encoding
+            .encode(&CredentialRequest::serialize(&client_start.message))?,
+        credential_response: encoding
+            .encode(&CredentialResponse::serialize(&credential_response))?,
+        credential_finalization: encoding.encode(
+            &CredentialFinalization::serialize(&client_finish.message),
+        )?,
+        client_session_key: encoding.encode(&client_finish.session_key)?,
+        server_session_key: encoding.encode(&server_finish.session_key)?,
+        export_key: encoding.encode(&client_finish.export_key)?,
+        matched: client_finish.session_key == server_finish.session_key,
+    })
+}
+
+fn srp_salt(data: &SrpDto) -> Result<Vec<u8>> {
+    Ok(match &data.salt {
+        Some(salt) => data.encoding.decode(salt)?,
+        None => {
+            let mut salt = vec![0u8; 16];
+            rng::rng().fill_bytes(&mut salt);
+            salt
+        }
+    })
+}
+
+fn compute_verifier<D: digest::Digest>(data: &SrpDto, salt: &[u8]) -> Vec<u8> {
+    SrpClient::<D>::new(data.group.as_group()).compute_verifier(
+        data.username.as_bytes(),
+        data.password.as_bytes(),
+        salt,
+    )
+}
+
+fn srp_exchange_inner<D: digest::Digest>(data: &SrpDto) -> Result<SrpExchange> {
+    let group = data.group.as_group();
+    let salt = srp_salt(data)?;
+    let verifier = match &data.verifier {
+        Some(verifier) => data.encoding.decode(verifier)?,
+        None => compute_verifier::<D>(data, &salt),
+    };
+    let username = data.username.as_bytes();
+    let password = data.password.as_bytes();
+    let mut rng = rng::rng();
+    let mut a = vec![0u8; 64];
+    rng.fill_bytes(&mut a);
+    let mut b = vec![0u8; 64];
+    rng.fill_bytes(&mut b);
+
+    let client = SrpClient::<D>::new(group);
+    let server = SrpServer::<D>::new(group);
+    let a_pub = client.compute_public_ephemeral(&a);
+    let b_pub = server.compute_public_ephemeral(&b, &verifier);
+    let client_verifier = client
+        .process_reply(&a, username, password, &salt, &b_pub)
+        .map_err(|err| anyhow!("srp client reply failed: {}", err))?;
+    let server_verifier = server
+        .process_reply(&b, &verifier, &a_pub)
+        .map_err(|err| anyhow!("srp server reply failed: {}", err))?;
+    let matched = server_verifier
+        .verify_client(client_verifier.proof())
+        .is_ok()
+        && client_verifier
+            .verify_server(server_verifier.proof())
+            .is_ok();
+
+    let encoding = data.encoding;
+    Ok(SrpExchange {
+        salt: encoding.encode(&salt)?,
+        verifier: encoding.encode(&verifier)?,
+        client_secret: encoding.encode(&a)?,
+        client_public: encoding.encode(&a_pub)?,
+        server_secret: encoding.encode(&b)?,
+        server_public: encoding.encode(&b_pub)?,
+        client_proof: encoding.encode(client_verifier.proof())?,
+        server_proof: encoding.encode(server_verifier.proof())?,
+        client_key: encoding.encode(client_verifier.key())?,
+        server_key: encoding.encode(server_verifier.key())?,
+        matched,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        opaque_login, opaque_registration, srp_exchange, OpaqueDto, SrpDto,
+    };
+    use crate::enums::{Digest, SrpGroup, TextEncoding};
+
+    #[test]
+    fn test_srp_exchange() {
+        let exchange = srp_exchange(SrpDto {
+            group: SrpGroup::G2048,
+            digest: Digest::Sha256,
+            username: "alice".to_string(),
+            password: "password123".to_string(),
+            salt: None,
+            verifier: None,
+            encoding: TextEncoding::Hex,
+        })
+        .unwrap();
+        assert!(exchange.matched);
+        assert_eq!(exchange.client_key, exchange.server_key);
+    }
+
+    #[test]
+    fn test_opaque_exchange() {
+        let dto =
+            |server_setup, registration_record, password: &str| OpaqueDto {
+                username: "alice".to_string(),
+                password: password.to_string(),
+                server_setup,
+                registration_record,
+                encoding: TextEncoding::Base64,
+            };
+        let registration =
+            opaque_registration(dto(None, None, "password123")).unwrap();
+        let login = opaque_login(dto(
+            Some(registration.server_setup.clone()),
+            Some(registration.registration_record.clone()),
+            "password123",
+        ))
+        .unwrap();
+        assert!(login.matched);
+        assert_eq!(registration.export_key, login.export_key);
+        assert!(opaque_login(dto(
+            Some(registration.server_setup),
+            Some(registration.registration_record),
+            "wrong",
+        ))
+        .is_err());
+    }
+}
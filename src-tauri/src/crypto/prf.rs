@@ -0,0 +1,209 @@
+use std::{fmt::Debug, time::Instant};
+
+use aes::{Aes128, Aes192, Aes256};
+use cmac::Cmac;
+use hkdf::hmac::{digest::KeyInit, Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    crypto::OperationResult,
+    enums::{Prf, TextEncoding},
+    errors::{Error, Result},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PrfSegment {
+    Bytes {
+        value: String,
+        encoding: TextEncoding,
+    },
+    /// big-endian block counter of `size` bytes
+    Counter { size: usize },
+    /// big-endian output length in bits, as used by SP 800-108
+    Length { size: usize },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrfDto {
+    pub prf: Prf,
+    pub key: String,
+    pub key_encoding: TextEncoding,
+    pub segments: Vec<PrfSegment>,
+    pub counter_start: Option<u64>,
+    pub output_length: usize,
+    pub output_encoding: TextEncoding,
+}
+
+impl Debug for PrfDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrfDto")
+            .field("prf", &self.prf)
+            .field("key_encoding", &self.key_encoding)
+            .field("segments", &self.segments.len())
+            .field("counter_start", &self.counter_start)
+            .field("output_length", &self.output_length)
+            .field("output_encoding", &self.output_encoding)
+            .finish()
+    }
+}
+
+#[tauri::command]
+pub fn prf(data: PrfDto) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    info!("prf: {:?}", data);
+    let key = data.key_encoding.decode(&data.key)?;
+    let has_counter = data
+        .segments
+        .iter()
+        .any(|segment| matches!(segment, PrfSegment::Counter { .. }));
+    let mut counter = data.counter_start.unwrap_or(1);
+    let mut output = Vec::with_capacity(data.output_length);
+    while output.len() < data.output_length {
+        let mut message = Vec::new();
+        for segment in &data.segments {
+            match segment {
+                PrfSegment::Bytes { value, encoding } => {
+                    message.extend(encoding.decode(value)?)
+                }
+                PrfSegment::Counter { size } => {
+                    message.extend(be_bytes(counter, *size, "counter")?)
+                }
+                PrfSegment::Length { size } => message.extend(be_bytes(
+                    data.output_length as u64 * 8,
+                    *size,
+                    "length",
+                )?),
+            }
+        }
+        output.extend(prf_inner(data.prf, &key, &message)?);
+        if !has_counter && output.len() < data.output_length {
+            return Err(Error::Unsupported(
+                "output longer than one prf block requires a counter segment"
+                    .to_string(),
+            ));
+        }
+        counter += 1;
+    }
+    output.truncate(data.output_length);
+    Ok(
+        OperationResult::encoded(&output, data.output_encoding, started)?
+            .metadata("prf", format!("{:?}", data.prf))
+            .metadata("blocks", counter - data.counter_start.unwrap_or(1)),
+    )
+}
+
+pub(crate) fn prf_inner(
+    prf: Prf,
+    key: &[u8],
+    message: &[u8],
+) -> Result<Vec<u8>> {
+    Ok(match prf {
+        Prf::HmacSha1 => mac::<Hmac<sha1::Sha1>>(key, message)?,
+        Prf::HmacSha256 => mac::<Hmac<sha2::Sha256>>(key, message)?,
+        Prf::HmacSha384 => mac::<Hmac<sha2::Sha384>>(key, message)?,
+        Prf::HmacSha512 => mac::<Hmac<sha2::Sha512>>(key, message)?,
+        Prf::CmacAes => match key.len() {
+            16 => mac::<Cmac<Aes128>>(key, message)?,
+            24 => mac::<Cmac<Aes192>>(key, message)?,
+            32 => mac::<Cmac<Aes256>>(key, message)?,
+            size => {
+                return Err(Error::Unsupported(format!(
+                    "cmac aes key size {}",
+                    size
+                )))
+            }
+        },
+    })
+}
+
+fn mac<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = <M as KeyInit>::new_from_slice(key)
+        .map_err(|_| Error::Unsupported("prf key size".to_string()))?;
+    mac.update(message);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn be_bytes(value: u64, size: usize, name: &str) -> Result<Vec<u8>> {
+    let bytes = value.to_be_bytes();
+    if size == 0 || size > bytes.len() || size < 8 && value >> (size * 8) != 0 {
+        return Err(Error::Unsupported(format!(
+            "{} {} does not fit in {} bytes",
+            name, value, size
+        )));
+    }
+    Ok(bytes[bytes.len() - size ..].to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{prf, PrfDto, PrfSegment};
+    use crate::enums::{Prf, TextEncoding};
+
+    fn dto(
+        prf: Prf,
+        key: &str,
+        segments: Vec<PrfSegment>,
+        length: usize,
+    ) -> PrfDto {
+        PrfDto {
+            prf,
+            key: key.to_string(),
+            key_encoding: TextEncoding::Hex,
+            segments,
+            counter_start: None,
+            output_length: length,
+            output_encoding: TextEncoding::Hex,
+        }
+    }
+
+    #[test]
+    fn test_prf() {
+        // RFC 4493 example 2
+        assert_eq!(
+            prf(dto(
+                Prf::CmacAes,
+                "2b7e151628aed2a6abf7158809cf4f3c",
+                vec![PrfSegment::Bytes {
+                    value: "6bc1bee22e409f96e93d7e117393172a".to_string(),
+                    encoding: TextEncoding::Hex,
+                }],
+                16,
+            ))
+            .unwrap()
+            .output,
+            "070a16b46b4d4144f79bdd9dd04a287c"
+        );
+        // RFC 4231 test case 2
+        assert_eq!(
+            prf(dto(
+                Prf::HmacSha256,
+                "4a656665",
+                vec![PrfSegment::Bytes {
+                    value: "what do ya want for nothing?".to_string(),
+                    encoding: TextEncoding::Utf8,
+                }],
+                32,
+            ))
+            .unwrap()
+            .output,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        let segments = vec![
+            PrfSegment::Counter { size: 4 },
+            PrfSegment::Bytes {
+                value: "label".to_string(),
+                encoding: TextEncoding::Utf8,
+            },
+            PrfSegment::Length { size: 4 },
+        ];
+        let long =
+            prf(dto(Prf::HmacSha256, "00", segments.clone(), 48)).unwrap();
+        assert_eq!(long.output.len(), 96);
+        assert_eq!(long.metadata["blocks"], "2");
+        assert!(prf(dto(Prf::HmacSha256, "00", segments[1 ..].to_vec(), 48))
+            .is_err());
+    }
+}
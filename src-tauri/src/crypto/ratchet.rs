@@ -0,0 +1,518 @@
+//! An educational X3DH and Double Ratchet after the Signal specifications.
+//! The whole session, private keys included, travels with each call.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{
+    crypto::{
+        aes::encrypt_or_decrypt_aes, kdf::kdf_inner_digest, prf::prf_inner,
+    },
+    enums::{
+        AesEncryptionPadding, Digest, EncryptionMode, Kdf, Prf, TextEncoding,
+    },
+    errors::{Error, Result},
+    rng,
+};
+
+/// Skipped message keys kept per chain before a message is refused.
+const MAX_SKIP: u32 = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Party {
+    Alice,
+    Bob,
+}
+
+/// Keys are hex, x25519 private keys included.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct X3dhTranscript {
+    pub alice_identity: String,
+    pub alice_ephemeral: String,
+    pub bob_identity: String,
+    pub bob_signed_prekey: String,
+    /// consumed by the handshake, its private half is gone
+    pub bob_one_time_prekey: String,
+    /// DH1 .. DH4 as seen by alice
+    pub dh: Vec<String>,
+    pub shared_secret: String,
+    /// alice's identity key followed by bob's
+    pub associated_data: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedKey {
+    pub ratchet_public: String,
+    pub n: u32,
+    pub message_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetState {
+    pub identity_key: String,
+    pub ratchet_key: String,
+    pub ratchet_public: String,
+    pub remote_ratchet_public: Option<String>,
+    pub root_key: String,
+    pub sending_chain_key: Option<String>,
+    pub receiving_chain_key: Option<String>,
+    pub sent: u32,
+    pub received: u32,
+    pub previous_chain_length: u32,
+    pub skipped: Vec<SkippedKey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetSession {
+    pub x3dh: X3dhTranscript,
+    pub alice: RatchetState,
+    pub bob: RatchetState,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetMessage {
+    pub ratchet_public: String,
+    pub previous_chain_length: u32,
+    pub n: u32,
+    /// AES-256-GCM ciphertext and tag in hex
+    pub ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetEncryptDto {
+    pub session: RatchetSession,
+    pub from: Party,
+    pub plaintext: String,
+    pub encoding: TextEncoding,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetDecryptDto {
+    pub session: RatchetSession,
+    pub to: Party,
+    pub message: RatchetMessage,
+    pub encoding: TextEncoding,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RatchetStep {
+    /// the session after the step, pass it to the next call
+    pub session: RatchetSession,
+    pub message: RatchetMessage,
+    pub plaintext: String,
+    /// the chain key the message key was derived from, absent when it
+    /// came from the skipped keys
+    pub chain_key: Option<String>,
+    pub message_key: String,
+    /// whether the step turned the diffie-hellman ratchet
+    pub dh_ratchet: bool,
+}
+
+/// Bob's signed prekey is his first ratchet key, alice ratchets against it
+/// right away.
+#[tauri::command]
+pub fn ratchet_init() -> Result<RatchetSession> {
+    info!("ratchet init");
+    let alice_identity = generate();
+    let alice_ephemeral = generate();
+    let bob_identity = generate();
+    let bob_signed_prekey = generate();
+    let bob_one_time_prekey = generate();
+
+    let dh = vec![
+        alice_identity.diffie_hellman(&PublicKey::from(&bob_signed_prekey)),
+        alice_ephemeral.diffie_hellman(&PublicKey::from(&bob_identity)),
+        alice_ephemeral.diffie_hellman(&PublicKey::from(&bob_signed_prekey)),
+        alice_ephemeral.diffie_hellman(&PublicKey::from(&bob_one_time_prekey)),
+    ];
+    let mut material = vec![0xff; 32];
+    for secret in &dh {
+        material.extend_from_slice(secret.as_bytes());
+    }
+    let shared_secret = kdf_inner_digest(
+        Kdf::HKdf,
+        Digest::Sha256,
+        &material,
+        Some(vec![0; 32]),
+        Some(b"kits x3dh".to_vec()),
+        32,
+    )?;
+    let mut associated_data =
+        PublicKey::from(&alice_identity).as_bytes().to_vec();
+    associated_data
+        .extend_from_slice(PublicKey::from(&bob_identity).as_bytes());
+
+    let alice_ratchet = generate();
+    let (root_key, sending_chain_key) = kdf_root(
+        &shared_secret,
+        alice_ratchet
+            .diffie_hellman(&PublicKey::from(&bob_signed_prekey))
+            .as_bytes(),
+    )?;
+    let alice = RatchetState {
+        identity_key: hex(alice_identity.as_bytes())?,
+        ratchet_key: hex(alice_ratchet.as_bytes())?,
+        ratchet_public: hex(PublicKey::from(&alice_ratchet).as_bytes())?,
+        remote_ratchet_public: Some(hex(
+            PublicKey::from(&bob_signed_prekey).as_bytes()
+        )?),
+        root_key: hex(&root_key)?,
+        sending_chain_key: Some(hex(&sending_chain_key)?),
+        receiving_chain_key: None,
+        sent: 0,
+        received: 0,
+        previous_chain_length: 0,
+        skipped: vec![],
+    };
+    let bob = RatchetState {
+        identity_key: hex(bob_identity.as_bytes())?,
+        ratchet_key: hex(bob_signed_prekey.as_bytes())?,
+        ratchet_public: hex(PublicKey::from(&bob_signed_prekey).as_bytes())?,
+        remote_ratchet_public: None,
+        root_key: hex(&shared_secret)?,
+        sending_chain_key: None,
+        receiving_chain_key: None,
+        sent: 0,
+        received: 0,
+        previous_chain_length: 0,
+        skipped: vec![],
+    };
+    Ok(RatchetSession {
+        x3dh: X3dhTranscript {
+            alice_identity: hex(PublicKey::from(&alice_identity).as_bytes())?,
+            alice_ephemeral: hex(PublicKey::from(&alice_ephemeral).as_bytes())?,
+            bob_identity: hex(PublicKey::from(&bob_identity).as_bytes())?,
+            bob_signed_prekey: hex(
+                PublicKey::from(&bob_signed_prekey).as_bytes()
+            )?,
+            bob_one_time_prekey: hex(
+                PublicKey::from(&bob_one_time_prekey).as_bytes()
+            )?,
+            dh: dh
+                .iter()
+                .map(|secret| hex(secret.as_bytes()))
+                .collect::<Result<_>>()?,
+            shared_secret: hex(&shared_secret)?,
+            associated_data: hex(&associated_data)?,
+        },
+        alice,
+        bob,
+    })
+}
+
+#[tauri::command]
+pub fn ratchet_encrypt(data: RatchetEncryptDto) -> Result<RatchetStep> {
+    info!("ratchet encrypt, from: {:?}", data.from);
+    let mut session = data.session;
+    let associated_data = decode(&session.x3dh.associated_data)?;
+    let state = match data.from {
+        Party::Alice => &mut session.alice,
+        Party::Bob => &mut session.bob,
+    };
+    let chain_key = state.sending_chain_key.clone().ok_or_else(|| {
+        Error::Unsupported(
+            "no sending chain yet, bob has to receive first".to_string(),
+        )
+    })?;
+    let (next_chain_key, message_key) = kdf_chain(&decode(&chain_key)?)?;
+    state.sending_chain_key = Some(hex(&next_chain_key)?);
+    let mut message = RatchetMessage {
+        ratchet_public: state.ratchet_public.clone(),
+        previous_chain_length: state.previous_chain_length,
+        n: state.sent,
+        ciphertext: String::new(),
+    };
+    state.sent += 1;
+    message.ciphertext = hex(&seal(
+        &message_key,
+        &data.encoding.decode(&data.plaintext)?,
+        &aad(&associated_data, &message)?,
+        true,
+    )?)?;
+    Ok(RatchetStep {
+        session,
+        message,
+        plaintext: data.plaintext,
+        chain_key: Some(chain_key),
+        message_key: hex(&message_key)?,
+        dh_ratchet: false,
+    })
+}
+
+#[tauri::command]
+pub fn ratchet_decrypt(data: RatchetDecryptDto) -> Result<RatchetStep> {
+    info!("ratchet decrypt, to: {:?}", data.to);
+    let mut session = data.session;
+    let associated_data = decode(&session.x3dh.associated_data)?;
+    let aad = aad(&associated_data, &data.message)?;
+    let ciphertext = decode(&data.message.ciphertext)?;
+    let state = match data.to {
+        Party::Alice => &mut session.alice,
+        Party::Bob => &mut session.bob,
+    };
+
+    if let Some(index) = state.skipped.iter().position(|skipped| {
+        skipped.ratchet_public == data.message.ratchet_public
+            && skipped.n == data.message.n
+    }) {
+        let skipped = state.skipped.remove(index);
+        let plaintext =
+            seal(&decode(&skipped.message_key)?, &ciphertext, &aad, false)?;
+        return Ok(RatchetStep {
+            session,
+            message: data.message,
+            plaintext: data.encoding.encode(&plaintext)?,
+            chain_key: None,
+            message_key: skipped.message_key,
+            dh_ratchet: false,
+        });
+    }
+
+    let dh_ratchet = state.remote_ratchet_public.as_ref()
+        != Some(&data.message.ratchet_public);
+    if dh_ratchet {
+        skip(state, data.message.previous_chain_length)?;
+        state.previous_chain_length = state.sent;
+        state.sent = 0;
+        state.received = 0;
+        state.remote_ratchet_public = Some(data.message.ratchet_public.clone());
+        let remote = public(&data.message.ratchet_public)?;
+        let (root_key, receiving_chain_key) = kdf_root(
+            &decode(&state.root_key)?,
+            secret(&state.ratchet_key)?
+                .diffie_hellman(&remote)
+                .as_bytes(),
+        )?;
+        let ratchet_key = generate();
+        let (root_key, sending_chain_key) = kdf_root(
+            &root_key,
+            ratchet_key.diffie_hellman(&remote).as_bytes(),
+        )?;
+        state.ratchet_key = hex(ratchet_key.as_bytes())?;
+        state.ratchet_public = hex(PublicKey::from(&ratchet_key).as_bytes())?;
+        state.root_key = hex(&root_key)?;
+        state.receiving_chain_key = Some(hex(&receiving_chain_key)?);
+        state.sending_chain_key = Some(hex(&sending_chain_key)?);
+    }
+    skip(state, data.message.n)?;
+    let chain_key = state
+        .receiving_chain_key
+        .clone()
+        .ok_or_else(|| Error::Unsupported("no receiving chain".to_string()))?;
+    let (next_chain_key, message_key) = kdf_chain(&decode(&chain_key)?)?;
+    state.receiving_chain_key = Some(hex(&next_chain_key)?);
+    state.received += 1;
+    let plaintext = seal(&message_key, &ciphertext, &aad, false)?;
+    Ok(RatchetStep {
+        session,
+        message: data.message,
+        plaintext: data.encoding.encode(&plaintext)?,
+        chain_key: Some(chain_key),
+        message_key: hex(&message_key)?,
+        dh_ratchet,
+    })
+}
+
+fn skip(state: &mut RatchetState, until: u32) -> Result<()> {
+    if state.received + MAX_SKIP < until {
+        return Err(Error::Unsupported(format!(
+            "more than {} skipped messages",
+            MAX_SKIP
+        )));
+    }
+    let (Some(chain_key), Some(remote)) =
+        (&state.receiving_chain_key, &state.remote_ratchet_public)
+    else {
+        return Ok(());
+    };
+    let mut chain_key = decode(chain_key)?;
+    let remote = remote.clone();
+    while state.received < until {
+        let (next_chain_key, message_key) = kdf_chain(&chain_key)?;
+        state.skipped.push(SkippedKey {
+            ratchet_public: remote.clone(),
+            n: state.received,
+            message_key: hex(&message_key)?,
+        });
+        chain_key = next_chain_key;
+        state.received += 1;
+    }
+    state.receiving_chain_key = Some(hex(&chain_key)?);
+    Ok(())
+}
+
+/// KDF_RK, HKDF-SHA256 salted with the root key.
+fn kdf_root(root_key: &[u8], dh: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut output = kdf_inner_digest(
+        Kdf::HKdf,
+        Digest::Sha256,
+        dh,
+        Some(root_key.to_vec()),
+        Some(b"kits ratchet root".to_vec()),
+        64,
+    )?;
+    let chain_key = output.split_off(32);
+    Ok((output, chain_key))
+}
+
+/// KDF_CK, HMAC-SHA256 over 0x02 for the chain key and 0x01 for the message
+/// key.
+fn kdf_chain(chain_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    Ok((
+        prf_inner(Prf::HmacSha256, chain_key, &[0x02])?,
+        prf_inner(Prf::HmacSha256, chain_key, &[0x01])?,
+    ))
+}
+
+/// AES-256-GCM with key and nonce expanded from the message key.
+fn seal(
+    message_key: &[u8],
+    input: &[u8],
+    aad: &[u8],
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    let mut key = kdf_inner_digest(
+        Kdf::HKdf,
+        Digest::Sha256,
+        message_key,
+        Some(vec![0; 32]),
+        Some(b"kits ratchet message".to_vec()),
+        44,
+    )?;
+    let nonce = key.split_off(32);
+    encrypt_or_decrypt_aes(
+        EncryptionMode::Gcm,
+        input,
+        &key,
+        Some(nonce),
+        Some(aad.to_vec()),
+        AesEncryptionPadding::NoPadding,
+        for_encryption,
+    )
+}
+
+/// The X3DH associated data followed by the encoded header.
+fn aad(associated_data: &[u8], message: &RatchetMessage) -> Result<Vec<u8>> {
+    let mut aad = associated_data.to_vec();
+    aad.extend(decode(&message.ratchet_public)?);
+    aad.extend_from_slice(&message.previous_chain_length.to_be_bytes());
+    aad.extend_from_slice(&message.n.to_be_bytes());
+    Ok(aad)
+}
+
+fn generate() -> StaticSecret {
+    StaticSecret::random_from_rng(rng::rng())
+}
+
+fn hex(input: &[u8]) -> Result<String> {
+    TextEncoding::Hex.encode(input)
+}
+
+fn decode(input: &str) -> Result<Vec<u8>> {
+    TextEncoding::Hex.decode(input)
+}
+
+fn key(input: &str) -> Result<[u8; 32]> {
+    decode(input)?
+        .try_into()
+        .map_err(|_| Error::Unsupported(format!("x25519 key `{}`", input)))
+}
+
+fn secret(input: &str) -> Result<StaticSecret> {
+    Ok(StaticSecret::from(key(input)?))
+}
+
+fn public(input: &str) -> Result<PublicKey> {
+    Ok(PublicKey::from(key(input)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ratchet_decrypt, ratchet_encrypt, ratchet_init, Party,
+        RatchetDecryptDto, RatchetEncryptDto, RatchetSession, RatchetStep,
+    };
+    use crate::enums::TextEncoding;
+
+    fn send(session: RatchetSession, from: Party, text: &str) -> RatchetStep {
+        ratchet_encrypt(RatchetEncryptDto {
+            session,
+            from,
+            plaintext: text.to_string(),
+            encoding: TextEncoding::Utf8,
+        })
+        .unwrap()
+    }
+
+    fn receive(
+        session: RatchetSession,
+        to: Party,
+        sent: &RatchetStep,
+    ) -> RatchetStep {
+        ratchet_decrypt(RatchetDecryptDto {
+            session,
+            to,
+            message: sent.message.clone(),
+            encoding: TextEncoding::Utf8,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_double_ratchet() {
+        let session = ratchet_init().unwrap();
+        assert!(ratchet_encrypt(RatchetEncryptDto {
+            session: session.clone(),
+            from: Party::Bob,
+            plaintext: "too early".to_string(),
+            encoding: TextEncoding::Utf8,
+        })
+        .is_err());
+
+        let first = send(session, Party::Alice, "first");
+        let second = send(first.session.clone(), Party::Alice, "second");
+        assert_ne!(first.message_key, second.message_key);
+
+        // bob gets the second message first, the first one from the
+        // skipped keys
+        let received = receive(second.session.clone(), Party::Bob, &second);
+        assert!(received.dh_ratchet);
+        assert_eq!(received.plaintext, "second");
+        assert_eq!(received.message_key, second.message_key);
+        assert_eq!(received.session.bob.skipped.len(), 1);
+        let received = receive(received.session, Party::Bob, &first);
+        assert_eq!(received.plaintext, "first");
+        assert_eq!(received.chain_key, None);
+        assert!(received.session.bob.skipped.is_empty());
+
+        let reply = send(received.session, Party::Bob, "reply");
+        assert_ne!(
+            reply.message.ratchet_public,
+            reply.session.x3dh.bob_signed_prekey
+        );
+        let received = receive(reply.session.clone(), Party::Alice, &reply);
+        assert!(received.dh_ratchet);
+        assert_eq!(received.plaintext, "reply");
+        assert_eq!(received.session.alice.previous_chain_length, 2);
+
+        let mut tampered = reply.message.clone();
+        tampered.n = 1;
+        assert!(ratchet_decrypt(RatchetDecryptDto {
+            session: reply.session,
+            to: Party::Alice,
+            message: tampered,
+            encoding: TextEncoding::Utf8,
+        })
+        .is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Instant};
 
 use anyhow::Context;
 use rsa::{RsaPrivateKey, RsaPublicKey};
@@ -7,11 +7,17 @@ use tracing::info;
 
 use crate::{
     add_encryption_trait_impl,
-    crypto::EncryptionDto,
+    crypto::{batch, BatchOutput, EncryptionDto, OperationResult},
     enums::{Digest, KeyFormat, Pkcs, RsaEncryptionPadding, TextEncoding},
     errors::Result,
+    jwt::JwkeyUsage,
+    pool,
+    profile::{self, Algorithm},
+    rng, vault,
 };
 
+pub mod diagnosis;
+pub mod fips;
 pub mod key;
 
 add_encryption_trait_impl!(RsaEncryptionDto {
@@ -20,7 +26,8 @@ add_encryption_trait_impl!(RsaEncryptionDto {
     padding: RsaEncryptionPadding,
     digest: Option<Digest>,
     mgf_digest: Option<Digest>,
-    for_encryption: bool
+    for_encryption: bool,
+    debug: Option<bool>
 });
 
 impl Debug for RsaEncryptionDto {
@@ -95,44 +102,151 @@ fn to_padding(
 }
 
 #[tauri::command]
-pub async fn crypto_rsa(data: RsaEncryptionDto) -> Result<String> {
+pub async fn crypto_rsa(
+    data: RsaEncryptionDto,
+) -> Result<OperationResult<String>> {
+    let started = Instant::now();
     info!("rsa crypto: {:?}", data);
-    let key = data.get_key()?;
     let input = data.get_input()?;
     let output_encoding = data.get_output_encoding();
-    let output = if data.for_encryption {
+    let output = crypto_rsa_inner(&data, &input)?;
+    let key_size =
+        key::parse_key_size(&data.get_key()?, data.pkcs, data.format)?;
+    let mut result =
+        OperationResult::encoded(&output, output_encoding, started)?
+            .metadata("padding", format!("{:?}", data.padding))
+            .metadata("keySize", key_size);
+    if data.padding == RsaEncryptionPadding::Oaep {
+        result = result.metadata(
+            "digest",
+            format!("{:?}", data.digest.unwrap_or(Digest::Sha256)),
+        );
+    }
+    Ok(result.warnings(warnings(&data)))
+}
+
+fn warnings(data: &RsaEncryptionDto) -> Vec<String> {
+    match data.padding {
+        RsaEncryptionPadding::Pkcs1v15 => vec!["pkcs#1 v1.5 encryption is \
+                                                deprecated and open to \
+                                                padding oracles, prefer oaep"
+            .to_string()],
+        RsaEncryptionPadding::Oaep => [data.digest, data.mgf_digest]
+            .into_iter()
+            .flatten()
+            .filter(|digest| *digest == Digest::Sha1)
+            .take(1)
+            .map(|_| "oaep with sha-1, prefer sha-256".to_string())
+            .collect(),
+    }
+}
+
+fn enforce_policy(key: &[u8], data: &RsaEncryptionDto) -> Result<()> {
+    profile::check(Algorithm::RsaPadding(data.padding))?;
+    if data.padding == RsaEncryptionPadding::Oaep {
+        for digest in [data.digest, data.mgf_digest].into_iter().flatten() {
+            profile::check(Algorithm::Digest(digest))?;
+        }
+    }
+    profile::check(Algorithm::RsaKeySize(key::parse_key_size(
+        key,
+        data.pkcs,
+        data.format,
+    )?))?;
+    vault::enforce_policy(key, JwkeyUsage::Encryption, match data.padding {
+        RsaEncryptionPadding::Pkcs1v15 => "RSA1_5",
+        RsaEncryptionPadding::Oaep => "RSA-OAEP",
+    })
+}
+
+pub(crate) fn crypto_rsa_inner(
+    data: &RsaEncryptionDto,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let key = data.get_key()?;
+    enforce_policy(&key, &data)?;
+    diagnosis::check(data)?;
+    if data.for_encryption {
         let public_key =
             key::bytes_to_public_key(&key, data.pkcs, data.format)?;
         encrypt_rsa_inner(
-            public_key,
-            &input,
+            &public_key,
+            input,
             data.padding,
             data.digest,
             data.mgf_digest,
-        )?
+        )
     } else {
-        let input = data.input_encoding.decode(&data.input)?;
         let private_key =
             key::bytes_to_private_key(&key, data.pkcs, data.format)?;
         decrypt_rsa_inner(
-            private_key,
-            &input,
+            &private_key,
+            input,
             data.padding,
             data.digest,
             data.mgf_digest,
-        )?
-    };
-    output_encoding.encode(&output)
+        )
+        .map_err(|e| diagnosis::explain(e, data, &private_key, input))
+    }
+}
+
+#[tauri::command]
+pub async fn crypto_rsa_batch(
+    data: RsaEncryptionDto,
+    inputs: Vec<String>,
+) -> Result<Vec<BatchOutput>> {
+    info!("rsa batch crypto: {:?}, size: {}", data, inputs.len());
+    let key = data.get_key()?;
+    enforce_policy(&key, &data)?;
+    let output_encoding = data.get_output_encoding();
+    let RsaEncryptionDto {
+        padding,
+        digest,
+        mgf_digest,
+        input_encoding,
+        ..
+    } = data;
+    Ok(if data.for_encryption {
+        let public_key =
+            key::bytes_to_public_key(&key, data.pkcs, data.format)?;
+        pool::spawn(move || {
+            batch(&inputs, input_encoding, output_encoding, |input| {
+                encrypt_rsa_inner(
+                    &public_key,
+                    input,
+                    padding,
+                    digest,
+                    mgf_digest,
+                )
+            })
+        })
+        .await?
+    } else {
+        let private_key =
+            key::bytes_to_private_key(&key, data.pkcs, data.format)?;
+        pool::spawn(move || {
+            batch(&inputs, input_encoding, output_encoding, |input| {
+                decrypt_rsa_inner(
+                    &private_key,
+                    input,
+                    padding,
+                    digest,
+                    mgf_digest,
+                )
+            })
+        })
+        .await?
+    })
 }
 
 pub fn encrypt_rsa_inner(
-    key: RsaPublicKey,
+    key: &RsaPublicKey,
     input: &[u8],
     padding: RsaEncryptionPadding,
     digest: Option<Digest>,
     mgf_digest: Option<Digest>,
 ) -> Result<Vec<u8>> {
-    let mut rng = rand::thread_rng();
+    let mut rng = rng::rng();
     let pad = to_padding(padding, digest, mgf_digest);
     Ok(key
         .encrypt(&mut rng, pad, input)
@@ -140,12 +254,16 @@ pub fn encrypt_rsa_inner(
 }
 
 pub fn decrypt_rsa_inner(
-    key: RsaPrivateKey,
+    key: &RsaPrivateKey,
     input: &[u8],
     padding: RsaEncryptionPadding,
     digest: Option<Digest>,
     mgf_digest: Option<Digest>,
 ) -> Result<Vec<u8>> {
     let pad = to_padding(padding, digest, mgf_digest);
-    Ok(key.decrypt(pad, input).context("rsa decrypt failed")?)
+    // keep a single failure message for every decryption error, debug builds
+    // add details through `diagnosis::explain`
+    Ok(key
+        .decrypt(pad, input)
+        .map_err(|_| anyhow::anyhow!("rsa decrypt failed"))?)
 }
@@ -0,0 +1,310 @@
+use anyhow::Context;
+use rsa::{traits::PublicKeyParts, BigUint, RsaPrivateKey};
+use tracing::warn;
+
+use super::RsaEncryptionDto;
+use crate::{
+    enums::{Digest, RsaEncryptionPadding},
+    errors::{Error, Result},
+    rng,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsaDecryptionVerdict {
+    Decryptable,
+    WrongKeySize,
+    MalformedCiphertext,
+    PaddingCheckFailed,
+    DigestOrLabelMismatch,
+}
+
+#[derive(Debug)]
+pub struct RsaDecryptionDiagnosis {
+    key_size: usize,
+    ciphertext_size: usize,
+    padding: RsaEncryptionPadding,
+    verdict: RsaDecryptionVerdict,
+    detail: String,
+}
+
+/// `debug` is refused outside debug builds, like `rng::seed_rng`.
+pub(crate) fn check(data: &RsaEncryptionDto) -> Result<()> {
+    if data.debug == Some(true) && !cfg!(debug_assertions) {
+        return Err(Error::Unsupported(
+            "rsa decryption diagnosis outside debug builds".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Deliberately a padding oracle: a failed decryption carries why it failed
+/// when a debug build asks for it through `debug`.
+pub(crate) fn explain(
+    error: Error,
+    data: &RsaEncryptionDto,
+    private_key: &RsaPrivateKey,
+    ciphertext: &[u8],
+) -> Error {
+    if !cfg!(debug_assertions) || data.debug != Some(true) {
+        return error;
+    }
+    warn!("rsa decryption diagnosis requested: {:?}", data);
+    match diagnose(
+        private_key,
+        ciphertext,
+        data.padding,
+        data.digest.unwrap_or(Digest::Sha256),
+        data.mgf_digest.unwrap_or(Digest::Sha256),
+    ) {
+        Ok(diagnosis) => anyhow::anyhow!(
+            "rsa decrypt failed, {:?}: {}",
+            diagnosis.verdict,
+            diagnosis.detail
+        )
+        .into(),
+        Err(_) => error,
+    }
+}
+
+fn diagnose(
+    private_key: &RsaPrivateKey,
+    ciphertext: &[u8],
+    padding: RsaEncryptionPadding,
+    digest: Digest,
+    mgf_digest: Digest,
+) -> Result<RsaDecryptionDiagnosis> {
+    let k = private_key.size();
+    let diagnosis = |verdict, detail: String| {
+        Ok(RsaDecryptionDiagnosis {
+            key_size: k * 8,
+            ciphertext_size: ciphertext.len(),
+            padding,
+            verdict,
+            detail,
+        })
+    };
+
+    if ciphertext.len() != k {
+        return if [256, 384, 512].contains(&ciphertext.len()) {
+            diagnosis(
+                RsaDecryptionVerdict::WrongKeySize,
+                format!(
+                    "ciphertext fits a {} bits key, the key is {} bits",
+                    ciphertext.len() * 8,
+                    k * 8
+                ),
+            )
+        } else {
+            diagnosis(
+                RsaDecryptionVerdict::MalformedCiphertext,
+                format!(
+                    "ciphertext must be {} bytes, got {}",
+                    k,
+                    ciphertext.len()
+                ),
+            )
+        };
+    }
+
+    let c = BigUint::from_bytes_be(ciphertext);
+    if &c >= private_key.n() {
+        return diagnosis(
+            RsaDecryptionVerdict::MalformedCiphertext,
+            "ciphertext is not smaller than the modulus".to_string(),
+        );
+    }
+
+    let m = rsa::hazmat::rsa_decrypt_and_check(
+        private_key,
+        Some(&mut rng::rng()),
+        &c,
+    )
+    .context("rsa raw decryption failed")?;
+    let mut em = vec![0u8; k];
+    let m = m.to_bytes_be();
+    em[k - m.len() ..].copy_from_slice(&m);
+
+    match padding {
+        RsaEncryptionPadding::Pkcs1v15 => {
+            if em[0] != 0 || em[1] != 2 {
+                diagnosis(
+                    RsaDecryptionVerdict::PaddingCheckFailed,
+                    format!(
+                        "encoded message starts with {:02x}{:02x}, expected \
+                         0002",
+                        em[0], em[1]
+                    ),
+                )
+            } else if let Some(separator) =
+                em[2 ..].iter().position(|byte| *byte == 0)
+                && separator >= 8
+            {
+                diagnosis(
+                    RsaDecryptionVerdict::Decryptable,
+                    format!("message is {} bytes", k - separator - 3),
+                )
+            } else {
+                diagnosis(
+                    RsaDecryptionVerdict::PaddingCheckFailed,
+                    "padding string is shorter than 8 bytes or has no \
+                     separator"
+                        .to_string(),
+                )
+            }
+        }
+        RsaEncryptionPadding::Oaep => {
+            let h_len = digest.as_digest().output_size();
+            if k < 2 * h_len + 2 {
+                return diagnosis(
+                    RsaDecryptionVerdict::WrongKeySize,
+                    format!("key is too small for {:?} oaep", digest),
+                );
+            }
+            let (masked_seed, masked_db) = em[1 ..].split_at(h_len);
+            let seed_mask = mgf1(mgf_digest, masked_db, h_len);
+            let seed = xor(masked_seed, &seed_mask);
+            let db_mask = mgf1(mgf_digest, &seed, masked_db.len());
+            let db = xor(masked_db, &db_mask);
+
+            let mut hasher = digest.as_digest();
+            hasher.update(b"");
+            let label_hash = hasher.finalize();
+
+            if db[.. h_len] != *label_hash {
+                diagnosis(
+                    RsaDecryptionVerdict::DigestOrLabelMismatch,
+                    "label hash mismatch, check digest and mgf digest"
+                        .to_string(),
+                )
+            } else if em[0] != 0 {
+                diagnosis(
+                    RsaDecryptionVerdict::PaddingCheckFailed,
+                    format!("leading byte is {:02x}, expected 00", em[0]),
+                )
+            } else {
+                match db[h_len ..].iter().position(|byte| *byte != 0) {
+                    Some(index) if db[h_len + index] == 1 => diagnosis(
+                        RsaDecryptionVerdict::Decryptable,
+                        format!(
+                            "message is {} bytes",
+                            db.len() - h_len - index - 1
+                        ),
+                    ),
+                    _ => diagnosis(
+                        RsaDecryptionVerdict::PaddingCheckFailed,
+                        "padding string is not terminated by 01".to_string(),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn mgf1(digest: Digest, seed: &[u8], length: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(length);
+    let mut counter = 0u32;
+    while mask.len() < length {
+        let mut hasher = digest.as_digest();
+        hasher.update(seed);
+        hasher.update(&counter.to_be_bytes());
+        mask.extend_from_slice(&hasher.finalize());
+        counter += 1;
+    }
+    mask.truncate(length);
+    mask
+}
+
+pub(crate) fn xor(left: &[u8], right: &[u8]) -> Vec<u8> {
+    left.iter().zip(right).map(|(l, r)| l ^ r).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rsa::RsaPrivateKey;
+
+    use super::{diagnose, explain, RsaDecryptionVerdict};
+    use crate::{
+        crypto::rsa::{encrypt_rsa_inner, RsaEncryptionDto},
+        enums::{Digest, KeyFormat, Pkcs, RsaEncryptionPadding, TextEncoding},
+        errors::Error,
+    };
+
+    #[test]
+    fn test_diagnose_oaep() {
+        let private_key =
+            RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let ciphertext = encrypt_rsa_inner(
+            &private_key.to_public_key(),
+            b"plaintext",
+            RsaEncryptionPadding::Oaep,
+            Some(Digest::Sha256),
+            Some(Digest::Sha256),
+        )
+        .unwrap();
+        let verdict = |input: &[u8], padding, digest| {
+            diagnose(&private_key, input, padding, digest, Digest::Sha256)
+                .unwrap()
+                .verdict
+        };
+        assert_eq!(
+            RsaDecryptionVerdict::Decryptable,
+            verdict(&ciphertext, RsaEncryptionPadding::Oaep, Digest::Sha256)
+        );
+        assert_eq!(
+            RsaDecryptionVerdict::DigestOrLabelMismatch,
+            verdict(&ciphertext, RsaEncryptionPadding::Oaep, Digest::Sha1)
+        );
+        assert_eq!(
+            RsaDecryptionVerdict::PaddingCheckFailed,
+            verdict(
+                &ciphertext,
+                RsaEncryptionPadding::Pkcs1v15,
+                Digest::Sha256
+            )
+        );
+        assert_eq!(
+            RsaDecryptionVerdict::MalformedCiphertext,
+            verdict(
+                &ciphertext[1 ..],
+                RsaEncryptionPadding::Oaep,
+                Digest::Sha256
+            )
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let private_key =
+            RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let ciphertext = encrypt_rsa_inner(
+            &private_key.to_public_key(),
+            b"plaintext",
+            RsaEncryptionPadding::Oaep,
+            None,
+            None,
+        )
+        .unwrap();
+        let explained = |debug| {
+            let data = RsaEncryptionDto {
+                input: String::new(),
+                input_encoding: TextEncoding::Base64,
+                key: String::new(),
+                key_encoding: TextEncoding::Base64,
+                input_path: None,
+                key_path: None,
+                output_encoding: TextEncoding::Base64,
+                pkcs: Pkcs::Pkcs8,
+                format: KeyFormat::Der,
+                padding: RsaEncryptionPadding::Pkcs1v15,
+                digest: None,
+                mgf_digest: None,
+                for_encryption: false,
+                debug,
+            };
+            let error = Error::Internal(anyhow::anyhow!("rsa decrypt failed"));
+            explain(error, &data, &private_key, &ciphertext).to_string()
+        };
+        assert!(!explained(None).contains("PaddingCheckFailed"));
+        assert!(explained(Some(true)).contains("PaddingCheckFailed"));
+    }
+}
@@ -0,0 +1,225 @@
+//! RSA key generation after FIPS 186-5 appendix A.1.3, the candidates
+//! drawn from a recorded seed so an auditor can replay them.
+
+use anyhow::Context;
+use num_bigint::BigUint;
+use rand_core::RngCore;
+use rsa::RsaPrivateKey;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    enums::{RsaKeySize, TextEncoding},
+    errors::{Error, Result},
+    rng::{self, KitsRng},
+};
+
+const PUBLIC_EXPONENT: u32 = 65537;
+const DERIVATION: &str = "sha-256(seed || counter || block), 32-bit big \
+                          endian counter and block, lowest bit set";
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrimeEvidence {
+    /// hex seed the candidates of this prime are drawn from
+    pub seed: String,
+    /// the candidate that became the prime
+    pub counter: u32,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RsaGenerationEvidence {
+    pub standard: &'static str,
+    pub public_exponent: u32,
+    /// rounds of miller-rabin every candidate passed
+    pub miller_rabin_rounds: usize,
+    /// how a seed and counter give a candidate
+    pub derivation: &'static str,
+    pub p: PrimeEvidence,
+    pub q: PrimeEvidence,
+}
+
+/// In the `evidence` parameter of the key `generate_rsa` gives with `fips`.
+pub(crate) fn generate(
+    key_size: RsaKeySize,
+) -> Result<(RsaPrivateKey, RsaGenerationEvidence)> {
+    let nlen = key_size as usize;
+    let rounds = miller_rabin_rounds(key_size);
+    let mut rng = rng::rng();
+    let e = BigUint::from(PUBLIC_EXPONENT);
+    // A.1.3 step 5.5, |p - q| > 2^(nlen / 2 - 100)
+    let distance = BigUint::from(1u8) << (nlen / 2 - 100);
+    loop {
+        let (p, p_evidence) = prime(nlen, rounds, &mut rng, |_| true)?;
+        let (q, q_evidence) = prime(nlen, rounds, &mut rng, |q| {
+            let difference = if *q > p { q - &p } else { &p - q };
+            difference > distance
+        })?;
+        let one = BigUint::from(1u8);
+        let (p1, q1) = (&p - &one, &q - &one);
+        let lambda = &p1 * &q1 / gcd(&p1, &q1);
+        let d = e.modinv(&lambda).context("e is not invertible")?;
+        // B.3.1, d > 2^(nlen / 2), redrawn in the unlikely other case
+        if d.bits() as usize <= nlen / 2 {
+            continue;
+        }
+        let convert =
+            |n: &BigUint| rsa::BigUint::from_bytes_be(&n.to_bytes_be());
+        let mut private_key = RsaPrivateKey::from_components(
+            convert(&(&p * &q)),
+            convert(&e),
+            convert(&d),
+            vec![convert(&p), convert(&q)],
+        )
+        .context("invalid rsa components")?;
+        private_key.precompute().context("rsa precompute failed")?;
+        return Ok((private_key, RsaGenerationEvidence {
+            standard: "FIPS 186-5 A.1.3",
+            public_exponent: PUBLIC_EXPONENT,
+            miller_rabin_rounds: rounds,
+            derivation: DERIVATION,
+            p: p_evidence,
+            q: q_evidence,
+        }));
+    }
+}
+
+/// FIPS 186-5 table B.1, probable primes tested with miller-rabin alone.
+fn miller_rabin_rounds(key_size: RsaKeySize) -> usize {
+    match key_size {
+        RsaKeySize::Rsa2048 => 5,
+        RsaKeySize::Rsa3072 | RsaKeySize::Rsa4096 => 4,
+    }
+}
+
+/// A.1.3 steps 4 and 5.
+fn prime(
+    nlen: usize,
+    rounds: usize,
+    rng: &mut KitsRng,
+    accept: impl Fn(&BigUint) -> bool,
+) -> Result<(BigUint, PrimeEvidence)> {
+    let mut seed = vec![0u8; 32];
+    rng.fill_bytes(&mut seed);
+    // p >= sqrt(2) 2^(nlen / 2 - 1), compared squared
+    let lower = BigUint::from(1u8) << (nlen - 1);
+    let e = BigUint::from(PUBLIC_EXPONENT);
+    for counter in 0 .. (5 * nlen / 2) as u32 {
+        let candidate = candidate(&seed, counter, nlen / 2);
+        if &candidate * &candidate < lower
+            || !accept(&candidate)
+            || ((&candidate - 1u8) % &e).bits() == 0
+            || !miller_rabin(&candidate, rounds, rng)
+        {
+            continue;
+        }
+        return Ok((candidate, PrimeEvidence {
+            seed: TextEncoding::Hex.encode(&seed)?,
+            counter,
+        }));
+    }
+    Err(Error::Unsupported(
+        "no prime within the candidate limit".to_string(),
+    ))
+}
+
+pub(crate) fn candidate(seed: &[u8], counter: u32, bits: usize) -> BigUint {
+    let mut bytes = vec![];
+    for block in 0u32 .. {
+        if bytes.len() * 8 >= bits {
+            break;
+        }
+        bytes.extend(
+            Sha256::new()
+                .chain_update(seed)
+                .chain_update(counter.to_be_bytes())
+                .chain_update(block.to_be_bytes())
+                .finalize(),
+        );
+    }
+    bytes.truncate(bits.div_ceil(8));
+    let mut candidate = BigUint::from_bytes_be(&bytes);
+    candidate >>= bytes.len() * 8 - bits;
+    candidate.set_bit(0, true);
+    candidate
+}
+
+/// Appendix B.3.1 with bases from the rng.
+fn miller_rabin(n: &BigUint, rounds: usize, rng: &mut KitsRng) -> bool {
+    let one = BigUint::from(1u8);
+    let n1 = n - &one;
+    let Some(s) = n1.trailing_zeros() else {
+        return false;
+    };
+    let m = &n1 >> s;
+    let mut bytes = vec![0u8; (n.bits() as usize).div_ceil(8) + 8];
+    for _ in 0 .. rounds {
+        rng.fill_bytes(&mut bytes);
+        // 1 < b < n - 1
+        let b = BigUint::from_bytes_be(&bytes) % (n - 3u8) + 2u8;
+        let mut z = b.modpow(&m, n);
+        if z == one || z == n1 {
+            continue;
+        }
+        let mut composite = true;
+        for _ in 1 .. s {
+            z = z.modpow(&BigUint::from(2u8), n);
+            if z == n1 {
+                composite = false;
+                break;
+            }
+            if z == one {
+                break;
+            }
+        }
+        if composite {
+            return false;
+        }
+    }
+    true
+}
+
+fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while b.bits() != 0 {
+        let r = &a % &b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigUint;
+    use rsa::traits::PrivateKeyParts;
+
+    use super::{candidate, generate, miller_rabin};
+    use crate::{
+        enums::{RsaKeySize, TextEncoding},
+        rng,
+    };
+
+    #[test]
+    fn test_miller_rabin() {
+        let mut rng = rng::rng();
+        // 2^127 - 1 and the carmichael number 561
+        let mersenne = (BigUint::from(1u8) << 127) - 1u8;
+        assert!(miller_rabin(&mersenne, 5, &mut rng));
+        assert!(!miller_rabin(&BigUint::from(561u32), 5, &mut rng));
+    }
+
+    #[test]
+    fn test_generate_fips() {
+        let (key, evidence) = generate(RsaKeySize::Rsa2048).unwrap();
+        assert!(key.validate().is_ok());
+        for (prime, evidence) in
+            key.primes().iter().zip([&evidence.p, &evidence.q])
+        {
+            let seed = TextEncoding::Hex.decode(&evidence.seed).unwrap();
+            let replayed = candidate(&seed, evidence.counter, 1024);
+            assert_eq!(replayed.to_bytes_be(), prime.to_bytes_be());
+        }
+    }
+}
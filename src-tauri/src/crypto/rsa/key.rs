@@ -4,14 +4,17 @@ use rsa::{traits::PublicKeyParts, RsaPrivateKey, RsaPublicKey};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
+use super::fips;
 use crate::{
     codec::{
         private_bytes_to_pkcs8, private_pkcs8_to_bytes, public_bytes_to_pkcs8,
-        public_pkcs8_to_bytes, PkcsDto,
+        public_pkcs8_to_bytes, reject_spki_private, PkcsDto,
     },
+    crypto::shamir::{GeneratedKey, Split},
     enums::{KeyFormat, Pkcs, RsaKeySize, TextEncoding},
     errors::{Error, Result},
-    utils::KeyTuple,
+    rng,
+    utils::{KeyTuple, Provenance},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,21 +31,39 @@ pub async fn generate_rsa(
     pkcs: Pkcs,
     format: KeyFormat,
     encoding: TextEncoding,
-) -> Result<KeyTuple> {
+    fips: Option<bool>,
+    split: Option<Split>,
+) -> Result<GeneratedKey> {
     info!(
-        "generate rsa key, key_size: {:?}, pkcs_encoding: {:?}, encoding: {:?}",
-        key_size, pkcs, format
+        "generate rsa key, key_size: {:?}, pkcs_encoding: {:?}, encoding: \
+         {:?}, fips: {:?}, split: {:?}",
+        key_size, pkcs, format, fips, split
     );
-    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-    let private_key = RsaPrivateKey::new(&mut rng, key_size as usize)
-        .context("generate rsa key failed")?;
+    let (private_key, evidence) = if fips.unwrap_or_default() {
+        let (private_key, evidence) = fips::generate(key_size)?;
+        (private_key, Some(evidence))
+    } else {
+        let private_key =
+            RsaPrivateKey::new(&mut rng::rng(), key_size as usize)
+                .context("generate rsa key failed")?;
+        (private_key, None)
+    };
     let public_key = private_key.to_public_key();
     let private_key_bytes = private_key_to_bytes(private_key, pkcs, format)?;
     let public_key_bytes = public_key_to_bytes(public_key, pkcs, format)?;
-    Ok(KeyTuple::new(
+    let mut key = KeyTuple::new(
         encoding.encode(&private_key_bytes)?,
         encoding.encode(&public_key_bytes)?,
-    ))
+    );
+    key.algorithm("RSA")
+        .parameter("keySize", key_size as usize)
+        .parameter("pkcs", pkcs)
+        .parameter("format", format)
+        .fingerprint(&public_key_bytes);
+    if let Some(evidence) = evidence {
+        key.parameter("evidence", evidence);
+    }
+    GeneratedKey::new(key, split, encoding)
 }
 
 #[tauri::command]
@@ -51,15 +72,25 @@ pub async fn derive_rsa(
     pkcs: Pkcs,
     format: KeyFormat,
     encoding: TextEncoding,
-) -> Result<String> {
+) -> Result<KeyTuple> {
     info!(
         "generate rsa public key, pkcs_encoding: {:?}, key_encoding: {:?}",
         pkcs, format
     );
-    let key_bytes = encoding.decode(&key)?;
+    let key_bytes = encoding.decode_lenient(&key)?;
     let private_key = bytes_to_private_key(&key_bytes, pkcs, format)?;
     let public_key = RsaPublicKey::from(private_key);
-    encoding.encode(&public_key_to_bytes(public_key, pkcs, format)?)
+    let key_size = public_key.size() * 8;
+    let public_key_bytes = public_key_to_bytes(public_key, pkcs, format)?;
+    let mut tuple = KeyTuple::empty();
+    tuple
+        .public(Some(encoding.encode(&public_key_bytes)?))
+        .algorithm("RSA")
+        .parameter("keySize", key_size)
+        .parameter("pkcs", pkcs)
+        .parameter("format", format)
+        .fingerprint(&public_key_bytes);
+    Ok(tuple)
 }
 
 #[tauri::command]
@@ -68,6 +99,7 @@ pub async fn transfer_rsa_key(
     public_key: Option<String>,
     from: PkcsDto,
     to: PkcsDto,
+    provenance: Option<Provenance>,
 ) -> Result<KeyTuple> {
     info!(
         "rsa key format transfer,  {:?} to {:?}. private->{}, public->{}",
@@ -76,10 +108,15 @@ pub async fn transfer_rsa_key(
         private_key.is_some(),
         public_key.is_some()
     );
+    let private_key = private_key
+        .map(|key| from.encoding.decode_lenient(&key))
+        .transpose()?;
+    let public_key = public_key
+        .map(|key| from.encoding.decode_lenient(&key))
+        .transpose()?;
 
-    Ok(KeyTuple::new(
-        if let Some(key) = private_key {
-            let key_bytes = from.encoding.decode(&key)?;
+    let mut tuple = KeyTuple::new(
+        if let Some(key_bytes) = &private_key {
             let private_bytes = pkcs8_pkcs1_converter_inner(
                 key_bytes.as_slice(),
                 from,
@@ -90,27 +127,31 @@ pub async fn transfer_rsa_key(
         } else {
             "".to_string()
         },
-        if let Some(key) = public_key {
-            let key_bytes = from.encoding.decode(&key)?;
-            let public_bytes = pkcs8_pkcs1_converter_inner(
-                key_bytes.as_slice(),
-                from,
-                to,
-                true,
-            )?;
-            to.encoding.encode(&public_bytes)?
-        } else {
-            "".to_string()
-        },
-    ))
+        "".to_string(),
+    );
+    if let Some(key_bytes) = &public_key {
+        let public_bytes =
+            pkcs8_pkcs1_converter_inner(key_bytes.as_slice(), from, to, true)?;
+        tuple
+            .public(Some(to.encoding.encode(&public_bytes)?))
+            .fingerprint(&public_bytes);
+    }
+    tuple
+        .algorithm("RSA")
+        .parameter("pkcs", to.pkcs)
+        .parameter("format", to.format);
+    if let Some(source) = public_key.or(private_key) {
+        tuple.provenance(provenance, &source, "transfer_rsa_key", from, to);
+    }
+    Ok(tuple)
 }
 
 #[tauri::command]
 pub fn parse_rsa(input: String) -> Result<RsaKeyInfo> {
     info!("parse rsa key: {}", input.len());
     let pem_decodor = |(input, format): (&str, KeyFormat)| {
-        let (label, _) =
-            pem_rfc7468::decode_vec(input.as_bytes()).context("invalid pem")?;
+        let (label, _) = pem_rfc7468::decode_vec(input.as_bytes())
+            .context("invalid rsa pem key")?;
 
         let pkcs = match label {
             pkcs1::RsaPrivateKey::PEM_LABEL => Pkcs::Pkcs1,
@@ -225,7 +266,7 @@ pub(crate) fn bytes_to_public_key(
     format: KeyFormat,
 ) -> Result<RsaPublicKey> {
     match pkcs {
-        Pkcs::Pkcs8 => {
+        Pkcs::Pkcs8 | Pkcs::Spki => {
             public_bytes_to_pkcs8::<rsa::RsaPublicKey>(input, format)
         }
         Pkcs::Pkcs1 => {
@@ -241,7 +282,7 @@ pub(crate) fn public_key_to_bytes(
     format: KeyFormat,
 ) -> Result<Vec<u8>> {
     match pkcs {
-        Pkcs::Pkcs8 => {
+        Pkcs::Pkcs8 | Pkcs::Spki => {
             public_pkcs8_to_bytes::<rsa::RsaPublicKey>(input, format)
         }
         Pkcs::Pkcs1 => {
@@ -257,15 +298,20 @@ pub(crate) fn pkcs8_pkcs1_converter_inner(
     to: PkcsDto,
     is_public: bool,
 ) -> Result<Vec<u8>> {
+    if !is_public {
+        reject_spki_private(from.pkcs, to.pkcs)?;
+    }
     match from.pkcs {
-        Pkcs::Pkcs8 => {
+        Pkcs::Pkcs8 | Pkcs::Spki => {
             if is_public {
                 let key = public_bytes_to_pkcs8::<rsa::RsaPublicKey>(
                     input,
                     from.format,
                 )?;
                 match to.pkcs {
-                    Pkcs::Pkcs8 => public_pkcs8_to_bytes(key, to.format),
+                    Pkcs::Pkcs8 | Pkcs::Spki => {
+                        public_pkcs8_to_bytes(key, to.format)
+                    }
                     Pkcs::Pkcs1 => public_pkcs1_to_bytes(key, to.format),
                     _ => Err(Error::Unsupported(
                         "only supported rsa key".to_string(),
@@ -292,7 +338,9 @@ pub(crate) fn pkcs8_pkcs1_converter_inner(
                     from.format,
                 )?;
                 match to.pkcs {
-                    Pkcs::Pkcs8 => public_pkcs8_to_bytes(key, to.format),
+                    Pkcs::Pkcs8 | Pkcs::Spki => {
+                        public_pkcs8_to_bytes(key, to.format)
+                    }
                     Pkcs::Pkcs1 => public_pkcs1_to_bytes(key, to.format),
                     _ => Err(Error::Unsupported(
                         "only supported rsa key".to_string(),
@@ -326,7 +374,7 @@ where
     Ok(match encoding {
         KeyFormat::Pem => {
             let key_string = String::from_utf8(input.to_vec())
-                .context("invalid utf-8 key")?;
+                .context("invalid pkcs1 pem public key")?;
             E::from_pkcs1_pem(&key_string)
                 .context("invalid pkcs1 pem public key")?
         }
@@ -346,7 +394,7 @@ where
     Ok(match format {
         KeyFormat::Pem => {
             let key_string = String::from_utf8(input.to_vec())
-                .context("invalid utf-8 key")?;
+                .context("invalid pkcs1 pem private key")?;
             <E as pkcs1::DecodeRsaPrivateKey>::from_pkcs1_pem(&key_string)
                 .context("invalid pkcs1 pem private key")?
         }
@@ -367,12 +415,12 @@ where
     Ok(match format {
         KeyFormat::Pem => input
             .to_pkcs1_pem(base64ct::LineEnding::LF)
-            .context("invalid pkcs1 pem key")?
+            .context("export rsa pkcs1 pem private key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => input
             .to_pkcs1_der()
-            .context("invalid pkcs1 der key")?
+            .context("export rsa pkcs1 der private key failed")?
             .as_bytes()
             .to_vec(),
     })
@@ -388,13 +436,107 @@ where
     Ok(match encoding {
         KeyFormat::Pem => input
             .to_pkcs1_pem(base64ct::LineEnding::LF)
-            .context("invalid pkcs1 pem public key")?
+            .context("export rsa pkcs1 pem public key failed")?
             .as_bytes()
             .to_vec(),
         KeyFormat::Der => input
             .to_pkcs1_der()
-            .context("invalid pkcs1 der key")?
+            .context("export rsa pkcs1 der public key failed")?
             .as_bytes()
             .to_vec(),
     })
 }
+
+#[cfg(test)]
+mod test {
+    use rsa::{pkcs8::EncodePublicKey, RsaPrivateKey};
+
+    use super::{generate_rsa, parse_rsa, transfer_rsa_key};
+    use crate::{
+        codec::PkcsDto,
+        crypto::shamir::GeneratedKey,
+        enums::{KeyFormat, Pkcs, RsaKeySize, TextEncoding},
+        rng,
+    };
+
+    #[tokio::test]
+    async fn test_generate_fips_evidence() {
+        let generate = |fips| {
+            generate_rsa(
+                RsaKeySize::Rsa2048,
+                Pkcs::Pkcs8,
+                KeyFormat::Pem,
+                TextEncoding::Utf8,
+                fips,
+                None,
+            )
+        };
+        let GeneratedKey::Whole(key) = generate(Some(true)).await.unwrap()
+        else {
+            panic!("split key without split");
+        };
+        assert_eq!(key.parameters["evidence"]["standard"], "FIPS 186-5 A.1.3");
+        let GeneratedKey::Whole(key) = generate(None).await.unwrap() else {
+            panic!("split key without split");
+        };
+        assert!(!key.parameters.contains_key("evidence"));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_spki() {
+        let private_key = RsaPrivateKey::new(&mut rng::rng(), 2048).unwrap();
+        let public_key = private_key
+            .to_public_key()
+            .to_public_key_pem(base64ct::LineEnding::LF)
+            .unwrap();
+        let info = parse_rsa(public_key.clone()).unwrap();
+        assert_eq!(info.pkcs, Pkcs::Spki);
+
+        let dto = |pkcs| PkcsDto {
+            pkcs,
+            format: KeyFormat::Pem,
+            encoding: TextEncoding::Utf8,
+        };
+        let pkcs1 = transfer_rsa_key(
+            None,
+            Some(public_key.clone()),
+            dto(info.pkcs),
+            dto(Pkcs::Pkcs1),
+            None,
+        )
+        .await
+        .unwrap();
+        let provenance = pkcs1.provenance.unwrap();
+        let pkcs1 = pkcs1.public_key.unwrap();
+        assert!(pkcs1.starts_with("-----BEGIN RSA PUBLIC KEY-----"));
+        let spki = transfer_rsa_key(
+            None,
+            Some(pkcs1),
+            dto(Pkcs::Pkcs1),
+            dto(Pkcs::Spki),
+            Some(provenance),
+        )
+        .await
+        .unwrap();
+        assert_eq!(spki.algorithm.as_deref(), Some("RSA"));
+        assert!(spki.fingerprint.is_some());
+        assert_eq!(spki.public_key.unwrap(), public_key);
+
+        // back where it started, from the spki it was converted from
+        let provenance = spki.provenance.unwrap();
+        assert_eq!(Some(provenance.source_fingerprint), spki.fingerprint);
+        assert_eq!(provenance.chain.len(), 2);
+        assert_eq!(provenance.chain[0].to["pkcs"], "pkcs1");
+        assert_eq!(provenance.chain[1].operation, "transfer_rsa_key");
+
+        assert!(transfer_rsa_key(
+            Some(public_key),
+            None,
+            dto(Pkcs::Spki),
+            dto(Pkcs::Pkcs1),
+            None,
+        )
+        .await
+        .is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::{fmt::Debug, time::Instant};
+
+use aes_siv::{
+    siv::{Aes128Siv, Aes256Siv},
+    KeyInit,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    add_encryption_trait_impl,
+    crypto::{commitment, EncryptionDto, OperationResult},
+    enums::TextEncoding,
+    errors::{Error, Result},
+    jwt::JwkeyUsage,
+    vault,
+};
+
+pub(crate) const DETERMINISTIC_WARNING: &str =
+    "deterministic encryption: the same plaintext, key and aad always give \
+     the same ciphertext, which reveals equal plaintexts to anyone seeing the \
+     ciphertexts";
+
+/// Length of the synthetic iv in front of the ciphertext.
+const SIV_SIZE: usize = 16;
+
+add_encryption_trait_impl!(SivEncryptionDto {
+    aad: Option<String>,
+    /// further associated data components, a nonce goes last
+    aads: Option<Vec<String>>,
+    aad_encoding: Option<TextEncoding>,
+    /// on decryption, the synthetic iv when `input` is the ciphertext alone
+    synthetic_iv: Option<String>,
+    key_commitment: Option<bool>,
+    for_encryption: bool
+});
+
+impl Debug for SivEncryptionDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SivEncryptionDto")
+            .field("input_encoding", &self.input_encoding)
+            .field("key_encoding", &self.key_encoding)
+            .field("output_encoding", &self.output_encoding)
+            .field("aad", &self.aad)
+            .field("aads", &self.aads)
+            .field("aad_encoding", &self.aad_encoding)
+            .field("synthetic_iv", &self.synthetic_iv)
+            .field("key_commitment", &self.key_commitment)
+            .field("for_encryption", &self.for_encryption)
+            .finish()
+    }
+}
+
+/// AES-SIV (RFC 5297) without a nonce, for convergent encryption where equal
+/// plaintexts map to equal ciphertexts.
+#[tauri::command]
+pub fn crypto_aes_siv(
+    data: SivEncryptionDto,
+) -> Result<OperationResult<String>> {
+    let started = Instant::now();
+    info!("aes siv: {:?}", data);
+    let input = data.get_input()?;
+    let key = data.get_key()?;
+    vault::enforce_policy(&key, JwkeyUsage::Encryption, "AES-SIV")?;
+    let aad = match data.aad_encoding {
+        Some(encoding) => data
+            .aad
+            .iter()
+            .chain(data.aads.iter().flatten())
+            .map(|aad| encoding.decode(aad))
+            .collect::<Result<Vec<_>>>()?,
+        None => vec![],
+    };
+    let committed = data.key_commitment.unwrap_or(false);
+    let output_encoding = data.get_output_encoding();
+    if data.for_encryption {
+        warn!("{}", DETERMINISTIC_WARNING);
+        let ciphertext = siv(&key, &aad, &input, true)?;
+        let synthetic_iv = output_encoding.encode(&ciphertext[.. SIV_SIZE])?;
+        let output = if committed {
+            commitment::commit(&key, ciphertext)?
+        } else {
+            ciphertext
+        };
+        return Ok(OperationResult::encoded(
+            &output,
+            output_encoding,
+            started,
+        )?
+        .metadata("syntheticIv", synthetic_iv)
+        .metadata("keyCommitment", committed)
+        .warnings(vec![DETERMINISTIC_WARNING.to_string()]));
+    }
+    let ciphertext = if committed {
+        commitment::open(&key, &input)?
+    } else {
+        input.as_slice()
+    };
+    let output = match &data.synthetic_iv {
+        Some(iv) => {
+            let mut joined = data.input_encoding.decode_lenient(iv)?;
+            if joined.len() != SIV_SIZE {
+                return Err(Error::Unsupported(format!(
+                    "synthetic iv size {}",
+                    joined.len()
+                )));
+            }
+            joined.extend_from_slice(ciphertext);
+            siv(&key, &aad, &joined, false)?
+        }
+        None => siv(&key, &aad, ciphertext, false)?,
+    };
+    Ok(OperationResult::encoded(&output, output_encoding, started)?
+        .metadata("keyCommitment", committed))
+}
+
+pub(crate) fn siv(
+    key: &[u8],
+    aad: &[Vec<u8>],
+    input: &[u8],
+    for_encryption: bool,
+) -> Result<Vec<u8>> {
+    let output = match (key.len(), for_encryption) {
+        (32, true) => Aes128Siv::new(key.into()).encrypt(aad, input),
+        (32, false) => Aes128Siv::new(key.into()).decrypt(aad, input),
+        (64, true) => Aes256Siv::new(key.into()).encrypt(aad, input),
+        (64, false) => Aes256Siv::new(key.into()).decrypt(aad, input),
+        (size, _) => {
+            return Err(Error::Unsupported(format!("aes siv keysize {}", size)))
+        }
+    };
+    Ok(output.map_err(|_| {
+        anyhow::anyhow!(if for_encryption {
+            "aes siv encrypt failed"
+        } else {
+            "aes siv decrypt failed"
+        })
+    })?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crypto_aes_siv, SivEncryptionDto};
+    use crate::enums::TextEncoding;
+
+    fn dto(
+        input: &str,
+        commitment: bool,
+        encryption: bool,
+    ) -> SivEncryptionDto {
+        SivEncryptionDto {
+            input: input.to_string(),
+            input_encoding: TextEncoding::Hex,
+            key: "fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0\
+                  f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"
+                .to_string(),
+            key_encoding: TextEncoding::Hex,
+            input_path: None,
+            key_path: None,
+            output_encoding: TextEncoding::Hex,
+            aad: Some(
+                "101112131415161718191a1b1c1d1e1f2021222324252627"
+                    .to_string(),
+            ),
+            aads: None,
+            aad_encoding: Some(TextEncoding::Hex),
+            synthetic_iv: None,
+            key_commitment: Some(commitment),
+            for_encryption: encryption,
+        }
+    }
+
+    // RFC 5297 appendix A.1
+    #[test]
+    fn test_aes_siv() {
+        let plaintext = "112233445566778899aabbccddee";
+        let encrypted = crypto_aes_siv(dto(plaintext, false, true)).unwrap();
+        assert_eq!(
+            encrypted.output,
+            "85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c"
+        );
+        assert_eq!(encrypted.warnings.len(), 1);
+
+        let committed = crypto_aes_siv(dto(plaintext, true, true)).unwrap();
+        assert!(committed.output.ends_with(&encrypted.output));
+        let decrypted =
+            crypto_aes_siv(dto(&committed.output, true, false)).unwrap();
+        assert_eq!(decrypted.output, plaintext);
+        assert!(decrypted.warnings.is_empty());
+        assert!(crypto_aes_siv(dto(&encrypted.output, true, false)).is_err());
+    }
+
+    // RFC 5297 appendix A.2, two associated data and a nonce
+    #[test]
+    fn test_aes_siv_components() {
+        let plaintext = "7468697320697320736f6d6520706c61696e7465787420746f20\
+                         656e6372797074207573696e67205349562d414553";
+        let mut data = dto(plaintext, false, true);
+        data.key =
+            "7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f"
+                .to_string();
+        data.aad = Some(
+            "00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766\
+             554433221100"
+                .to_string(),
+        );
+        data.aads = Some(vec![
+            "102030405060708090a0".to_string(),
+            "09f911029d74e35bd84156c5635688c0".to_string(),
+        ]);
+        let encrypted = crypto_aes_siv(data.clone()).unwrap();
+        assert_eq!(
+            encrypted.metadata.get("syntheticIv").map(String::as_str),
+            Some("7bdb6e3b432667eb06f4d14bff2fbd0f")
+        );
+        assert_eq!(
+            encrypted.output,
+            "7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17\
+             dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d"
+        );
+
+        data.input = encrypted.output[32 ..].to_string();
+        data.synthetic_iv = encrypted.metadata.get("syntheticIv").cloned();
+        data.for_encryption = false;
+        assert_eq!(crypto_aes_siv(data.clone()).unwrap().output, plaintext);
+        data.aads.as_mut().unwrap().pop();
+        assert!(crypto_aes_siv(data).is_err());
+    }
+}
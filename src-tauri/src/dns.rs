@@ -0,0 +1,389 @@
+//! DNSSEC delegation signers (RFC 4034) and TLSA (RFC 6698) records, DKIM
+//! in `dkim`.
+
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    crypto::digest::digest_inner,
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+    x509::{certificates_from_pem, subject_public_key_info},
+};
+
+pub mod dkim;
+
+/// DNSKEY flags, RFC 4034 section 2.1.1
+const ZONE_KEY: u16 = 0x0100;
+const SECURE_ENTRY_POINT: u16 = 0x0001;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DsDto {
+    /// a DNSKEY record in presentation format, parentheses and comments
+    /// allowed
+    pub dnskey: String,
+    /// the owner name when the record does not start with it
+    pub owner: Option<String>,
+    /// sha-256 when absent
+    pub digests: Option<Vec<Digest>>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DsRecord {
+    pub key_tag: u16,
+    pub algorithm: u8,
+    pub digest_type: u8,
+    /// upper case hex
+    pub digest: String,
+    pub record: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DnskeyDs {
+    pub owner: String,
+    pub flags: u16,
+    /// a key signing key
+    pub secure_entry_point: bool,
+    pub key_tag: u16,
+    pub records: Vec<DsRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsaDto {
+    pub certificate: String,
+    /// 0 PKIX-TA, 1 PKIX-EE, 2 DANE-TA, 3 DANE-EE
+    pub usage: u8,
+    /// 0 the certificate, 1 its public key, both when absent
+    pub selector: Option<u8>,
+    /// 0 exact, 1 sha-256, 2 sha-512, both hashes when absent
+    pub matching: Option<u8>,
+    /// names the record `_port._protocol.host.` when given
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub protocol: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsaRecord {
+    pub usage: u8,
+    pub selector: u8,
+    pub matching: u8,
+    /// lower case hex
+    pub data: String,
+    pub record: String,
+}
+
+#[tauri::command]
+pub fn dnssec_ds(data: DsDto) -> Result<DnskeyDs> {
+    info!("dnssec ds: {:?}, digests: {:?}", data.owner, data.digests);
+    let (owner, rdata) = parse_dnskey(&data.dnskey, data.owner.as_deref())?;
+    let flags = u16::from_be_bytes([rdata[0], rdata[1]]);
+    let (protocol, algorithm) = (rdata[2], rdata[3]);
+    if protocol != 3 {
+        return Err(Error::Unsupported(format!(
+            "dnskey protocol {}",
+            protocol
+        )));
+    }
+    if flags & ZONE_KEY == 0 {
+        return Err(Error::Unsupported(
+            "dnskey without the zone key flag".to_string(),
+        ));
+    }
+    // RSA/MD5 computes its tag differently, and is long gone
+    if algorithm == 1 {
+        return Err(Error::Unsupported("dnskey algorithm 1".to_string()));
+    }
+    let key_tag = key_tag(&rdata);
+    let mut input = wire_name(&owner)?;
+    input.extend_from_slice(&rdata);
+    let records = data
+        .digests
+        .unwrap_or(vec![Digest::Sha256])
+        .into_iter()
+        .map(|digest| {
+            let digest_type = match digest {
+                Digest::Sha1 => 1,
+                Digest::Sha256 => 2,
+                Digest::Sha384 => 4,
+                _ => {
+                    return Err(Error::Unsupported(format!(
+                        "ds digest {:?}",
+                        digest
+                    )))
+                }
+            };
+            let digest = TextEncoding::Hex
+                .encode(&digest_inner(digest, &input))?
+                .to_uppercase();
+            Ok(DsRecord {
+                key_tag,
+                algorithm,
+                digest_type,
+                record: format!(
+                    "{} IN DS {} {} {} {}",
+                    owner, key_tag, algorithm, digest_type, digest
+                ),
+                digest,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(DnskeyDs {
+        owner,
+        flags,
+        secure_entry_point: flags & SECURE_ENTRY_POINT != 0,
+        key_tag,
+        records,
+    })
+}
+
+#[tauri::command]
+pub fn tlsa_record(data: TlsaDto) -> Result<Vec<TlsaRecord>> {
+    info!(
+        "tlsa record: {:?}, usage: {}, selector: {:?}, matching: {:?}",
+        data.host, data.usage, data.selector, data.matching
+    );
+    if data.usage > 3 {
+        return Err(Error::Unsupported(format!("tlsa usage {}", data.usage)));
+    }
+    let certificate = certificates_from_pem(&data.certificate)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::Unsupported("no certificate".to_string()))?;
+    let owner = data.host.as_deref().map(|host| {
+        format!(
+            "_{}._{}.{}.",
+            data.port.unwrap_or(443),
+            data.protocol.as_deref().unwrap_or("tcp"),
+            host.trim_end_matches('.')
+        )
+    });
+    let selectors = data.selector.map_or(vec![0, 1], |selector| vec![selector]);
+    let matchings = data.matching.map_or(vec![1, 2], |matching| vec![matching]);
+    let mut records = vec![];
+    for &selector in &selectors {
+        let selected = match selector {
+            0 => &certificate[..],
+            1 => subject_public_key_info(&certificate)?,
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "tlsa selector {}",
+                    selector
+                )))
+            }
+        };
+        for &matching in &matchings {
+            let data_bytes = match matching {
+                0 => selected.to_vec(),
+                1 => digest_inner(Digest::Sha256, selected),
+                2 => digest_inner(Digest::Sha512, selected),
+                _ => {
+                    return Err(Error::Unsupported(format!(
+                        "tlsa matching type {}",
+                        matching
+                    )))
+                }
+            };
+            let hex = TextEncoding::Hex.encode(&data_bytes)?;
+            let rdata =
+                format!("{} {} {} {}", data.usage, selector, matching, hex);
+            records.push(TlsaRecord {
+                usage: data.usage,
+                selector,
+                matching,
+                record: match &owner {
+                    Some(owner) => format!("{} IN TLSA {}", owner, rdata),
+                    None => rdata,
+                },
+                data: hex,
+            });
+        }
+    }
+    Ok(records)
+}
+
+fn parse_dnskey(
+    record: &str,
+    owner: Option<&str>,
+) -> Result<(String, Vec<u8>)> {
+    let text = record
+        .lines()
+        .map(|line| line.split(';').next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(['(', ')'], " ");
+    let tokens = text.split_whitespace().collect::<Vec<_>>();
+    let (owner, fields) = match tokens
+        .iter()
+        .position(|token| token.eq_ignore_ascii_case("DNSKEY"))
+    {
+        Some(index) => {
+            let owner = match owner {
+                Some(owner) => owner,
+                None if index > 0 => tokens[0],
+                None => {
+                    return Err(Error::Unsupported(
+                        "dnskey without owner name".to_string(),
+                    ))
+                }
+            };
+            (owner, &tokens[index + 1 ..])
+        }
+        // the rdata alone
+        None => (
+            owner.ok_or_else(|| {
+                Error::Unsupported("dnskey without owner name".to_string())
+            })?,
+            &tokens[..],
+        ),
+    };
+    let invalid = || Error::Unsupported("malformed dnskey".to_string());
+    let [flags, protocol, algorithm, key @ ..] = fields else {
+        return Err(invalid());
+    };
+    let mut rdata = flags
+        .parse::<u16>()
+        .map_err(|_| invalid())?
+        .to_be_bytes()
+        .to_vec();
+    rdata.push(protocol.parse().map_err(|_| invalid())?);
+    rdata.push(algorithm.parse().map_err(|_| invalid())?);
+    let key = Base64::decode_vec(&key.concat()).map_err(|_| invalid())?;
+    if key.is_empty() {
+        return Err(invalid());
+    }
+    rdata.extend_from_slice(&key);
+    let owner = format!("{}.", owner.trim_end_matches('.').to_lowercase());
+    Ok((owner, rdata))
+}
+
+/// RFC 4034 appendix B.
+fn key_tag(rdata: &[u8]) -> u16 {
+    let mut sum = rdata
+        .iter()
+        .enumerate()
+        .map(|(index, byte)| {
+            if index & 1 == 0 {
+                (*byte as u32) << 8
+            } else {
+                *byte as u32
+            }
+        })
+        .sum::<u32>();
+    sum += (sum >> 16) & 0xffff;
+    (sum & 0xffff) as u16
+}
+
+/// Lower case labels.
+fn wire_name(name: &str) -> Result<Vec<u8>> {
+    let mut wire = vec![];
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() {
+            continue;
+        }
+        if label.len() > 63 {
+            return Err(Error::Unsupported(format!("dns label {}", label)));
+        }
+        wire.push(label.len() as u8);
+        wire.extend(label.bytes().map(|byte| byte.to_ascii_lowercase()));
+    }
+    wire.push(0);
+    Ok(wire)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dnssec_ds, tlsa_record, DsDto, TlsaDto};
+    use crate::enums::Digest;
+
+    #[test]
+    fn test_dnssec_ds() {
+        // RFC 4034 section 5.4
+        let ds = dnssec_ds(DsDto {
+            dnskey: "dskey.example.com. 86400 IN DNSKEY 256 3 5 (
+                AQOeiiR0GOMYkDshWoSKz9Xz fwJr1AYtsmx3TGkJaNXVbfi/
+                2pHm822aJ5iI9BMzNXxeYCmZ DRD99WYwYqUSdjMmmAphXdvx
+                egXd/M5+X7OrzKBaMbCVdFLU Uh6DhweJBjEVv5f2wwjM9Xzc
+                nOf+EPbtG9DMBmADjFDc2w/r ljwvFw==
+                ) ; key id = 60485"
+                .to_string(),
+            owner: None,
+            digests: Some(vec![Digest::Sha1]),
+        })
+        .unwrap();
+        assert_eq!(ds.key_tag, 60485);
+        assert!(!ds.secure_entry_point);
+        assert_eq!(
+            ds.records[0].record,
+            "dskey.example.com. IN DS 60485 5 1 \
+             2BB183AF5F22588179A53B0A98631FAD1A292118"
+        );
+
+        // RFC 6605 section 6.1, the rdata alone
+        let ds = dnssec_ds(DsDto {
+            dnskey: "257 3 13 GojIhhXUN/u4v54ZQqGSnyhWJwaubCvTmeexv7bR6edb\
+                     krSqQpF64cYbcB7wNcP+e+MAnLr+Wi9xMWyQLc8NAA=="
+                .to_string(),
+            owner: Some("Example.NET".to_string()),
+            digests: None,
+        })
+        .unwrap();
+        assert_eq!(ds.owner, "example.net.");
+        assert!(ds.secure_entry_point);
+        assert_eq!(
+            ds.records[0].record,
+            "example.net. IN DS 55648 13 2 \
+             B4C8C1FE2E7477127B27115656AD6256F424625BF5C1E2770CE6D6E37DF61D17"
+        );
+
+        assert!(dnssec_ds(DsDto {
+            dnskey: "example.net. IN DNSKEY 0 3 13 AAAA".to_string(),
+            owner: None,
+            digests: None,
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_tlsa_record() {
+        let certificate = include_str!("../tests/vectors/chain.pem")
+            .split_inclusive("-----END CERTIFICATE-----\n")
+            .next()
+            .unwrap()
+            .to_string();
+        let records = tlsa_record(TlsaDto {
+            certificate,
+            usage: 3,
+            selector: None,
+            matching: None,
+            host: Some("mail.example.".to_string()),
+            port: Some(25),
+            protocol: None,
+        })
+        .unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1].data.len(), 128);
+        assert_eq!(
+            records[2].record,
+            "_25._tcp.mail.example. IN TLSA 3 1 1 \
+             2483d753a6503d8d260652720b6c19212f351af9b7588a10b096a60f7b5c77ec"
+        );
+
+        let records = tlsa_record(TlsaDto {
+            certificate: records[0].data.clone(),
+            usage: 3,
+            selector: Some(1),
+            matching: Some(1),
+            host: None,
+            port: None,
+            protocol: None,
+        });
+        assert!(records.is_err());
+    }
+}
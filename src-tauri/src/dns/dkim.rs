@@ -0,0 +1,695 @@
+//! DKIM (RFC 6376) keys, records and signatures, rsa-sha256 and
+//! ed25519-sha256 (RFC 8463).
+
+use anyhow::Context;
+use base64ct::{Base64, Encoding};
+use ed25519_dalek::{Signer, Verifier};
+use pkcs8::{
+    DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey,
+    LineEnding,
+};
+use rsa::{
+    pkcs1::DecodeRsaPublicKey, traits::PublicKeyParts, Pkcs1v15Sign,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    audit::{sorted, Finding, Severity},
+    crypto::digest::digest_inner,
+    enums::{Digest, RsaKeySize},
+    errors::{Error, Result},
+    rng, vault,
+};
+
+const SIGNATURE_HEADER: &str = "DKIM-Signature";
+const SIGNED_HEADERS: &[&str] = &[
+    "from",
+    "to",
+    "cc",
+    "subject",
+    "date",
+    "message-id",
+    "reply-to",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DkimAlgorithm {
+    RsaSha256,
+    Ed25519Sha256,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DkimKeyDto {
+    pub selector: String,
+    pub domain: String,
+    pub algorithm: DkimAlgorithm,
+    /// 2048 when absent, rsa only
+    pub key_size: Option<RsaKeySize>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DkimKey {
+    /// `selector._domainkey.domain.`
+    pub name: String,
+    pub record: String,
+    /// pkcs#8 pem
+    pub private_key: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DkimRecord {
+    pub key_type: String,
+    /// rsa modulus size
+    pub bits: Option<usize>,
+    /// the `h=` hash algorithms, all when empty
+    pub hashes: Vec<String>,
+    /// `t=y`, verifiers treat failures as passes
+    pub testing: bool,
+    /// an empty `p=`
+    pub revoked: bool,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DkimSignDto {
+    pub message: String,
+    /// pkcs#8 pem rsa or ed25519 key
+    pub private_key: String,
+    pub selector: String,
+    pub domain: String,
+    /// the usual ones present in the message when absent
+    pub headers: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DkimVerifyDto {
+    /// the message with its `DKIM-Signature` header
+    pub message: String,
+    /// the TXT record of the signing domain
+    pub record: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DkimVerification {
+    pub domain: String,
+    pub selector: String,
+    pub algorithm: String,
+    pub canonicalization: String,
+    pub signed_headers: Vec<String>,
+    pub body_hash_valid: bool,
+    pub signature_valid: bool,
+}
+
+enum DkimSigningKey {
+    Rsa(RsaPrivateKey),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+enum DkimPublicKey {
+    Rsa(RsaPublicKey),
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+/// A header as written, line folding included.
+struct Header<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+#[tauri::command]
+pub async fn generate_dkim_key(data: DkimKeyDto) -> Result<DkimKey> {
+    info!(
+        "generate dkim key: {}._domainkey.{}, {:?}",
+        data.selector, data.domain, data.algorithm
+    );
+    let mut rng = rng::rng();
+    let (key_type, public_key, private_key) = match data.algorithm {
+        DkimAlgorithm::RsaSha256 => {
+            let key_size = data.key_size.unwrap_or(RsaKeySize::Rsa2048);
+            let private_key = RsaPrivateKey::new(&mut rng, key_size as usize)
+                .context("generate rsa key failed")?;
+            let public_key = private_key
+                .to_public_key()
+                .to_public_key_der()
+                .context("encode rsa public key failed")?;
+            let private_key = private_key
+                .to_pkcs8_pem(LineEnding::LF)
+                .context("encode rsa private key failed")?;
+            ("rsa", public_key.into_vec(), private_key.to_string())
+        }
+        DkimAlgorithm::Ed25519Sha256 => {
+            let private_key = ed25519_dalek::SigningKey::generate(&mut rng);
+            let public_key = private_key.verifying_key().to_bytes().to_vec();
+            let private_key = private_key
+                .to_pkcs8_pem(LineEnding::LF)
+                .context("encode ed25519 private key failed")?;
+            ("ed25519", public_key, private_key.to_string())
+        }
+    };
+    Ok(DkimKey {
+        name: format!(
+            "{}._domainkey.{}.",
+            data.selector,
+            data.domain.trim_end_matches('.')
+        ),
+        record: format!(
+            "v=DKIM1; k={}; p={}",
+            key_type,
+            Base64::encode_string(&public_key)
+        ),
+        private_key,
+    })
+}
+
+#[tauri::command]
+pub fn validate_dkim_record(record: String) -> Result<DkimRecord> {
+    info!("validate dkim record, size: {}", record.len());
+    let tags = tags(&record)?;
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| *tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+    if let Some(version) = tag("v") {
+        if version != "DKIM1" || tags[0].0 != "v" {
+            return Err(Error::Unsupported(format!(
+                "dkim record version {}",
+                version
+            )));
+        }
+    }
+    let key_type = tag("k").unwrap_or("rsa").to_string();
+    let hashes = tag("h")
+        .map(|hashes| hashes.split(':').map(String::from).collect())
+        .unwrap_or_else(Vec::new);
+    let testing =
+        tag("t").is_some_and(|flags| flags.split(':').any(|f| f == "y"));
+    let mut findings = vec![];
+    let revoked = tag("p")
+        .ok_or_else(|| Error::Unsupported("dkim record without p=".into()))?
+        .is_empty();
+    let bits = if revoked {
+        findings.push(Finding::new(
+            Severity::High,
+            "revoked",
+            "the key is revoked, signatures under it fail",
+        ));
+        None
+    } else {
+        match public_key(&record)? {
+            DkimPublicKey::Rsa(key) => Some(key.n().bits()),
+            DkimPublicKey::Ed25519(_) => None,
+        }
+    };
+    match bits {
+        Some(bits) if bits < 1024 => findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-size",
+            format!("{} bit key, verifiers ignore keys under 1024", bits),
+        )),
+        Some(bits) if bits < 2048 => findings.push(Finding::new(
+            Severity::Medium,
+            "rsa-size",
+            format!("{} bit key, 2048 bits are recommended", bits),
+        )),
+        _ => {}
+    }
+    if hashes.iter().any(|hash| hash == "sha1") {
+        findings.push(Finding::new(
+            Severity::Medium,
+            "sha1",
+            "rsa-sha1 is allowed, RFC 8301 forbids it",
+        ));
+    }
+    if testing {
+        findings.push(Finding::new(
+            Severity::Low,
+            "testing",
+            "t=y asks verifiers to ignore failures",
+        ));
+    }
+    if key_type == "ed25519" {
+        findings.push(Finding::new(
+            Severity::Info,
+            "ed25519-only",
+            "many verifiers ignore ed25519, publish an rsa key as well",
+        ));
+    }
+    Ok(DkimRecord {
+        key_type,
+        bits,
+        hashes,
+        testing,
+        revoked,
+        findings: sorted(findings),
+    })
+}
+
+#[tauri::command]
+pub fn dkim_sign(data: DkimSignDto) -> Result<String> {
+    info!(
+        "dkim sign: {}._domainkey.{}, headers: {:?}",
+        data.selector, data.domain, data.headers
+    );
+    let message = crlf(&data.message);
+    let (headers, body) = split_message(&message)?;
+    let names = match data.headers {
+        Some(names) => names,
+        None => SIGNED_HEADERS
+            .iter()
+            .filter(|name| {
+                headers
+                    .iter()
+                    .any(|header| header.name.eq_ignore_ascii_case(name))
+            })
+            .map(|name| name.to_string())
+            .collect(),
+    };
+    if !names.iter().any(|name| name.eq_ignore_ascii_case("from")) {
+        return Err(Error::Unsupported(
+            "dkim signatures must cover the from header".to_string(),
+        ));
+    }
+    let key = if let Ok(key) = RsaPrivateKey::from_pkcs8_pem(&data.private_key)
+    {
+        DkimSigningKey::Rsa(key)
+    } else if let Ok(key) =
+        ed25519_dalek::SigningKey::from_pkcs8_pem(&data.private_key)
+    {
+        DkimSigningKey::Ed25519(key)
+    } else {
+        return Err(Error::Unsupported(
+            "private key is neither rsa nor ed25519 pkcs#8".to_string(),
+        ));
+    };
+    let algorithm = match key {
+        DkimSigningKey::Rsa(_) => "rsa-sha256",
+        DkimSigningKey::Ed25519(_) => "ed25519-sha256",
+    };
+    let body_hash = digest_inner(Digest::Sha256, &relaxed_body(body));
+    let value = format!(
+        " v=1; a={}; c=relaxed/relaxed; d={}; s={}; t={}; h={}; bh={}; b=",
+        algorithm,
+        data.domain.trim_end_matches('.'),
+        data.selector,
+        vault::now(),
+        names.join(":"),
+        Base64::encode_string(&body_hash)
+    );
+    let input = signed_data(
+        &headers,
+        &names,
+        &Header {
+            name: SIGNATURE_HEADER,
+            value: &value,
+        },
+        true,
+    );
+    let hash = digest_inner(Digest::Sha256, &input);
+    let signature = match key {
+        DkimSigningKey::Rsa(key) => key
+            .sign(Pkcs1v15Sign::new::<sha2::Sha256>(), &hash)
+            .context("rsa sign failed")?,
+        // RFC 8463 signs the hash, not the data
+        DkimSigningKey::Ed25519(key) => key.sign(&hash).to_bytes().to_vec(),
+    };
+    Ok(format!(
+        "{}:{}{}\r\n{}",
+        SIGNATURE_HEADER,
+        value,
+        Base64::encode_string(&signature),
+        message
+    ))
+}
+
+/// `record` is given, not looked up.
+#[tauri::command]
+pub fn dkim_verify(data: DkimVerifyDto) -> Result<DkimVerification> {
+    info!("dkim verify, size: {}", data.message.len());
+    let message = crlf(&data.message);
+    let (headers, body) = split_message(&message)?;
+    let signature_header = headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case(SIGNATURE_HEADER))
+        .ok_or_else(|| {
+            Error::Unsupported("message without dkim signature".to_string())
+        })?;
+    let tags = tags(signature_header.value)?;
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| *tag == name)
+            .map(|(_, value)| value.as_str())
+            .ok_or_else(|| {
+                Error::Unsupported(format!("dkim signature without {}=", name))
+            })
+    };
+    if tag("v")? != "1" {
+        return Err(Error::Unsupported(format!(
+            "dkim signature version {}",
+            tag("v")?
+        )));
+    }
+    let canonicalization = tags
+        .iter()
+        .find(|(tag, _)| tag == "c")
+        .map_or("simple/simple", |(_, value)| value.as_str());
+    let (header, body_canonicalization) = canonicalization
+        .split_once('/')
+        .unwrap_or((canonicalization, "simple"));
+    let mut canonical_body = if body_canonicalization == "relaxed" {
+        relaxed_body(body)
+    } else {
+        simple_body(body)
+    };
+    if let Some(length) = tags.iter().find(|(tag, _)| tag == "l") {
+        let length = length
+            .1
+            .parse()
+            .map_err(|_| Error::Unsupported("invalid l= tag".to_string()))?;
+        canonical_body.truncate(length);
+    }
+    let body_hash_valid = Base64::decode_vec(tag("bh")?).is_ok_and(|hash| {
+        hash == digest_inner(Digest::Sha256, &canonical_body)
+    });
+    let names = tag("h")?
+        .split(':')
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+    // the signature covers its own header with b= emptied
+    let value = signature_header
+        .value
+        .split(';')
+        .map(|part| match part.split_once('=') {
+            Some((name, _)) if name.trim() == "b" => format!("{}=", name),
+            _ => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(";");
+    let input = signed_data(
+        &headers,
+        &names,
+        &Header {
+            name: signature_header.name,
+            value: &value,
+        },
+        header == "relaxed",
+    );
+    let signature = Base64::decode_vec(tag("b")?)
+        .map_err(|_| Error::Unsupported("invalid b= tag".to_string()))?;
+    let algorithm = tag("a")?;
+    let signature_valid = match (algorithm, public_key(&data.record)?) {
+        ("rsa-sha256", DkimPublicKey::Rsa(key)) => key
+            .verify(
+                Pkcs1v15Sign::new::<sha2::Sha256>(),
+                &digest_inner(Digest::Sha256, &input),
+                &signature,
+            )
+            .is_ok(),
+        ("ed25519-sha256", DkimPublicKey::Ed25519(key)) => {
+            ed25519_dalek::Signature::from_slice(&signature).is_ok_and(
+                |signature| {
+                    key.verify(
+                        &digest_inner(Digest::Sha256, &input),
+                        &signature,
+                    )
+                    .is_ok()
+                },
+            )
+        }
+        (algorithm, _) => {
+            return Err(Error::Unsupported(format!(
+                "dkim algorithm {} with this record",
+                algorithm
+            )))
+        }
+    };
+    Ok(DkimVerification {
+        domain: tag("d")?.to_string(),
+        selector: tag("s")?.to_string(),
+        algorithm: algorithm.to_string(),
+        canonicalization: canonicalization.to_string(),
+        signed_headers: names,
+        body_hash_valid,
+        signature_valid,
+    })
+}
+
+/// `name=value` pairs of a tag list, folding whitespace removed from the
+/// values.
+fn tags(list: &str) -> Result<Vec<(String, String)>> {
+    list.split(';')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| {
+            let (name, value) = tag.split_once('=').ok_or_else(|| {
+                Error::Unsupported(format!("dkim tag `{}`", tag))
+            })?;
+            Ok((
+                name.trim().to_string(),
+                value.split_whitespace().collect::<String>(),
+            ))
+        })
+        .collect()
+}
+
+/// `p=` as spki, or the pkcs#1 key some publish, for rsa and the raw key
+/// for ed25519.
+fn public_key(record: &str) -> Result<DkimPublicKey> {
+    let tags = tags(record)?;
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let key = Base64::decode_vec(tag("p").unwrap_or_default())
+        .map_err(|_| Error::Unsupported("invalid p= tag".to_string()))?;
+    if key.is_empty() {
+        return Err(Error::Unsupported("the dkim key is revoked".to_string()));
+    }
+    match tag("k").unwrap_or("rsa") {
+        "rsa" => Ok(DkimPublicKey::Rsa(
+            RsaPublicKey::from_public_key_der(&key)
+                .or_else(|_| RsaPublicKey::from_pkcs1_der(&key))
+                .context("invalid dkim rsa key")?,
+        )),
+        "ed25519" => {
+            let key: [u8; 32] = key.try_into().map_err(|_| {
+                Error::Unsupported("invalid dkim ed25519 key".to_string())
+            })?;
+            Ok(DkimPublicKey::Ed25519(
+                ed25519_dalek::VerifyingKey::from_bytes(&key)
+                    .context("invalid dkim ed25519 key")?,
+            ))
+        }
+        key_type => {
+            Err(Error::Unsupported(format!("dkim key type {}", key_type)))
+        }
+    }
+}
+
+/// Pasted messages often lost their carriage returns.
+fn crlf(message: &str) -> String {
+    message.replace("\r\n", "\n").replace('\n', "\r\n")
+}
+
+fn split_message(message: &str) -> Result<(Vec<Header>, &str)> {
+    let (head, body) = match message.split_once("\r\n\r\n") {
+        Some((head, body)) => (head, body),
+        None => (message.trim_end_matches("\r\n"), ""),
+    };
+    let mut headers: Vec<Header> = vec![];
+    let mut start = 0;
+    for (index, _) in head.match_indices("\r\n") {
+        // a line starting with whitespace continues the header
+        if !head[index + 2 ..].starts_with([' ', '\t']) {
+            headers.push(header(&head[start .. index])?);
+            start = index + 2;
+        }
+    }
+    if start < head.len() {
+        headers.push(header(&head[start ..])?);
+    }
+    Ok((headers, body))
+}
+
+fn header(line: &str) -> Result<Header> {
+    let (name, value) = line
+        .split_once(':')
+        .ok_or_else(|| Error::Unsupported(format!("header `{}`", line)))?;
+    Ok(Header { name, value })
+}
+
+/// RFC 6376 section 3.7, each name takes the last instance not yet used.
+fn signed_data(
+    headers: &[Header],
+    names: &[String],
+    signature: &Header,
+    relaxed: bool,
+) -> Vec<u8> {
+    let canonical = |header: &Header| {
+        if relaxed {
+            format!(
+                "{}:{}\r\n",
+                header.name.trim().to_ascii_lowercase(),
+                header
+                    .value
+                    .replace("\r\n", "")
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )
+        } else {
+            format!("{}:{}\r\n", header.name, header.value)
+        }
+    };
+    let mut used = vec![false; headers.len()];
+    let mut data = String::new();
+    for name in names {
+        let found = (0 .. headers.len()).rev().find(|index| {
+            !used[*index]
+                && headers[*index].name.trim().eq_ignore_ascii_case(name)
+        });
+        if let Some(index) = found {
+            used[index] = true;
+            data.push_str(&canonical(&headers[index]));
+        }
+    }
+    let signature = canonical(signature);
+    data.push_str(signature.trim_end_matches("\r\n"));
+    data.into_bytes()
+}
+
+/// RFC 6376 section 3.4.3.
+fn simple_body(body: &str) -> Vec<u8> {
+    format!("{}\r\n", body.trim_end_matches("\r\n")).into_bytes()
+}
+
+/// RFC 6376 section 3.4.4.
+fn relaxed_body(body: &str) -> Vec<u8> {
+    let mut canonical = String::new();
+    for line in body.split("\r\n") {
+        let mut space = false;
+        for c in line.trim_end_matches([' ', '\t']).chars() {
+            if c == ' ' || c == '\t' {
+                if !space {
+                    canonical.push(' ');
+                }
+                space = true;
+            } else {
+                canonical.push(c);
+                space = false;
+            }
+        }
+        canonical.push_str("\r\n");
+    }
+    let canonical = canonical.trim_end_matches("\r\n");
+    if canonical.is_empty() {
+        return vec![];
+    }
+    format!("{}\r\n", canonical).into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        dkim_sign, dkim_verify, generate_dkim_key, relaxed_body, signed_data,
+        simple_body, split_message, validate_dkim_record, DkimAlgorithm,
+        DkimKeyDto, DkimSignDto, DkimVerifyDto,
+    };
+    use crate::audit::Severity;
+
+    const MESSAGE: &str = "From: alice@kits.example\nTo: \
+                           bob@kits.example\nSubject: hello\n\nhi bob  \n\n";
+
+    #[test]
+    fn test_canonicalization() {
+        // RFC 6376 section 3.4.5
+        let (headers, body) = split_message(
+            "A: X\r\nB : Y\t\r\n\tZ  \r\n\r\n C \r\nD \t E\r\n\r\n\r\n",
+        )
+        .unwrap();
+        let names = ["a".to_string(), "b".to_string()];
+        let data = signed_data(&headers[.. 1], &names, &headers[1], true);
+        assert_eq!(data, b"a:X\r\nb:Y Z");
+        assert_eq!(relaxed_body(body), b" C\r\nD E\r\n");
+        assert_eq!(simple_body(body), b" C \r\nD \t E\r\n");
+        assert!(relaxed_body("\r\n\r\n").is_empty());
+        assert_eq!(simple_body(""), b"\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_dkim_sign_verify() {
+        let key = generate_dkim_key(DkimKeyDto {
+            selector: "mail".to_string(),
+            domain: "kits.example".to_string(),
+            algorithm: DkimAlgorithm::Ed25519Sha256,
+            key_size: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(key.name, "mail._domainkey.kits.example.");
+        let record = validate_dkim_record(key.record.clone()).unwrap();
+        assert_eq!(record.key_type, "ed25519");
+        assert_eq!(record.findings[0].code, "ed25519-only");
+
+        let signed = dkim_sign(DkimSignDto {
+            message: MESSAGE.to_string(),
+            private_key: key.private_key,
+            selector: "mail".to_string(),
+            domain: "kits.example".to_string(),
+            headers: None,
+        })
+        .unwrap();
+        let verify = |message: String| {
+            dkim_verify(DkimVerifyDto {
+                message,
+                record: key.record.clone(),
+            })
+            .unwrap()
+        };
+        let verification = verify(signed.clone());
+        assert!(verification.body_hash_valid && verification.signature_valid);
+        assert_eq!(verification.signed_headers, ["from", "to", "subject"]);
+
+        // relaxed canonicalization tolerates whitespace changes
+        let verification = verify(signed.replace("hi bob  ", "hi   bob"));
+        assert!(verification.body_hash_valid && verification.signature_valid);
+
+        let verification = verify(signed.replace("hi bob", "hi eve"));
+        assert!(!verification.body_hash_valid);
+        let verification = verify(signed.replace("hello", "urgent"));
+        assert!(verification.body_hash_valid);
+        assert!(!verification.signature_valid);
+    }
+
+    #[test]
+    fn test_validate_dkim_record() {
+        let record =
+            validate_dkim_record("v=DKIM1; h=sha1:sha256; t=y; p=".to_string())
+                .unwrap();
+        assert!(record.revoked && record.testing);
+        assert_eq!(
+            record
+                .findings
+                .iter()
+                .map(|finding| (finding.severity, finding.code))
+                .collect::<Vec<_>>(),
+            [
+                (Severity::High, "revoked"),
+                (Severity::Medium, "sha1"),
+                (Severity::Low, "testing")
+            ]
+        );
+        let misplaced = "k=rsa; v=DKIM1; p=".to_string();
+        assert!(validate_dkim_record(misplaced).is_err());
+    }
+}
@@ -89,7 +89,16 @@ pub enum EdwardsCurveName {
 pub enum EncryptionMode {
     Ecb,
     Cbc,
+    /// cbc then hmac over it with a combined key, as the JWE A128CBC-HS256
+    #[serde(rename = "CBC-HMAC")]
+    CbcHmac,
     Gcm,
+    /// counter with cbc-mac, the tag size is chosen per call
+    Ccm,
+    /// RFC 5297 with a double length key, deterministic without an iv
+    Siv,
+    /// IEEE 1619 with a double length key, the iv is the 16 bytes tweak
+    Xts,
 }
 
 #[derive(
@@ -118,6 +127,14 @@ impl TextEncoding {
             TextEncoding::Hex => hex_decode(input, false),
         }
     }
+
+    /// Whitespace in base64 and hex input is ignored.
+    pub fn decode_lenient(&self, input: &str) -> Result<Vec<u8>> {
+        match self {
+            TextEncoding::Utf8 => self.decode(input),
+            _ => self.decode(&input.split_whitespace().collect::<String>()),
+        }
+    }
 }
 
 #[derive(
@@ -159,12 +176,15 @@ pub enum KeyFormat {
 pub enum EciesEncryptionAlgorithm {
     #[serde(rename = "AES-GCM")]
     AesGcm,
+    #[serde(rename = "AES-CCM")]
+    AesCcm,
 }
 
 impl EciesEncryptionAlgorithm {
     pub fn as_encryption_mode(&self) -> EncryptionMode {
         match self {
             EciesEncryptionAlgorithm::AesGcm => EncryptionMode::Gcm,
+            EciesEncryptionAlgorithm::AesCcm => EncryptionMode::Ccm,
         }
     }
 }
@@ -217,6 +237,7 @@ pub enum Digest {
     Sha3_256,
     Sha3_384,
     Sha3_512,
+    Keccak256,
 }
 
 impl Digest {
@@ -229,6 +250,7 @@ impl Digest {
             Digest::Sha3_256 => Box::new(sha3::Sha3_256::new()),
             Digest::Sha3_384 => Box::new(sha3::Sha3_384::new()),
             Digest::Sha3_512 => Box::new(sha3::Sha3_512::new()),
+            Digest::Keccak256 => Box::new(sha3::Keccak256::new()),
         }
     }
 }
@@ -252,3 +274,154 @@ pub enum Kdf {
     PbKdf2,
     Scrypt,
 }
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HpkeMode {
+    Base,
+    Psk,
+    Auth,
+    AuthPsk,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum HpkeKem {
+    #[serde(rename = "DHKEM(X25519, HKDF-SHA256)")]
+    X25519HkdfSha256,
+    #[serde(rename = "DHKEM(P-256, HKDF-SHA256)")]
+    P256HkdfSha256,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum HpkeKdf {
+    #[serde(rename = "HKDF-SHA256")]
+    HkdfSha256,
+    #[serde(rename = "HKDF-SHA384")]
+    HkdfSha384,
+    #[serde(rename = "HKDF-SHA512")]
+    HkdfSha512,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum HpkeAead {
+    #[serde(rename = "AES-128-GCM")]
+    Aes128Gcm,
+    #[serde(rename = "AES-256-GCM")]
+    Aes256Gcm,
+    #[serde(rename = "ChaCha20Poly1305")]
+    ChaCha20Poly1305,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum SrpGroup {
+    #[serde(rename = "1024")]
+    G1024,
+    #[serde(rename = "1536")]
+    G1536,
+    #[serde(rename = "2048")]
+    G2048,
+    #[serde(rename = "3072")]
+    G3072,
+    #[serde(rename = "4096")]
+    G4096,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Xof {
+    Shake128,
+    Shake256,
+    CShake128,
+    CShake256,
+    Kmac128,
+    Kmac256,
+    KmacXof128,
+    KmacXof256,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Prf {
+    HmacSha1,
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+    CmacAes,
+}
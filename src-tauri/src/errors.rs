@@ -1,5 +1,7 @@
 use core::result;
 
+use crate::i18n::{self, Locale};
+
 pub type Result<T> = result::Result<T, Error>;
 
 #[derive(Debug, thiserror::Error)]
@@ -14,6 +16,29 @@ pub enum Error {
     Internal(#[from] anyhow::Error),
 }
 
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_) => i18n::IO,
+            Error::Unsupported(_) => i18n::UNSUPPORTED,
+            Error::Internal(err) => {
+                i18n::code_of(&err.to_string()).unwrap_or(i18n::INTERNAL)
+            }
+        }
+    }
+
+    /// As it is when the catalog has no entry.
+    pub fn localized(&self, locale: Locale) -> String {
+        let detail = match self {
+            Error::Io(err) => err.to_string(),
+            Error::Unsupported(detail) => detail.clone(),
+            Error::Internal(_) => String::new(),
+        };
+        i18n::render(self.code(), locale, &detail)
+            .unwrap_or_else(|| self.to_string())
+    }
+}
+
 impl serde::Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
     where
@@ -29,6 +54,6 @@ impl serde::Serialize for Error {
             }
         }
 
-        serializer.serialize_str(self.to_string().as_ref())
+        serializer.serialize_str(self.localized(i18n::current()).as_ref())
     }
 }
@@ -0,0 +1,61 @@
+use std::{sync::OnceLock, time::Duration};
+
+use anyhow::Context;
+use reqwest::{redirect::Policy, Client, Url};
+use tracing::info;
+
+use crate::errors::{Error, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Responses are documents and certificates, anything larger is refused.
+const MAX_SIZE: usize = 1 << 20;
+
+fn client() -> Result<&'static Client> {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .redirect(Policy::limited(5))
+        .user_agent(concat!("kits/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("build http client failed")?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// `https_only` suits key sets, signed objects like certificates may come
+/// over plain http.
+pub(crate) async fn get(url: &str, https_only: bool) -> Result<Vec<u8>> {
+    info!("fetch: {}", url);
+    let url = Url::parse(url).context("invalid url")?;
+    match url.scheme() {
+        "https" => {}
+        "http" if !https_only => {}
+        scheme => {
+            return Err(Error::Unsupported(format!("{} url {}", scheme, url)))
+        }
+    }
+    let mut response = client()?
+        .get(url.clone())
+        .send()
+        .await
+        .with_context(|| format!("fetch {} failed", url))?
+        .error_for_status()
+        .with_context(|| format!("fetch {} failed", url))?;
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("read {} failed", url))?
+    {
+        body.extend_from_slice(&chunk);
+        if body.len() > MAX_SIZE {
+            return Err(Error::Unsupported(format!(
+                "{} is larger than {} bytes",
+                url, MAX_SIZE
+            )));
+        }
+    }
+    Ok(body)
+}
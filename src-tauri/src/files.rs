@@ -0,0 +1,131 @@
+//! Files the commands read by path instead of through the webview: regular
+//! files under the home or temporary directory or `KITS_FILE_SCOPE`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use tracing::info;
+
+use crate::errors::{Error, Result};
+
+const SCOPE_ENV: &str = "KITS_FILE_SCOPE";
+const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+fn scope() -> Vec<PathBuf> {
+    let mut roots = vec![std::env::temp_dir()];
+    roots.extend(
+        ["HOME", "USERPROFILE"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .map(PathBuf::from),
+    );
+    if let Some(paths) = std::env::var_os(SCOPE_ENV) {
+        roots.extend(std::env::split_paths(&paths));
+    }
+    roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .collect()
+}
+
+/// The canonical form of `path` when it is a regular file in scope.
+pub(crate) fn scoped(path: &str) -> Result<PathBuf> {
+    let canonical = Path::new(path)
+        .canonicalize()
+        .with_context(|| format!("open {} failed", path))?;
+    if !scope().iter().any(|root| canonical.starts_with(root)) {
+        return Err(Error::Unsupported(format!(
+            "{} outside of the file scope, add its directory to {}",
+            canonical.display(),
+            SCOPE_ENV
+        )));
+    }
+    let metadata = canonical
+        .metadata()
+        .with_context(|| format!("open {} failed", path))?;
+    if !metadata.is_file() {
+        return Err(Error::Unsupported(format!("{} is not a file", path)));
+    }
+    if metadata.len() > MAX_FILE_SIZE {
+        return Err(Error::Unsupported(format!(
+            "{} bytes file, at most {}",
+            metadata.len(),
+            MAX_FILE_SIZE
+        )));
+    }
+    Ok(canonical)
+}
+
+/// The canonical form of `path` when its directory is in scope, the file
+/// itself may not exist yet.
+pub(crate) fn scoped_new(path: &Path) -> Result<PathBuf> {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(Error::Unsupported(format!(
+            "{} is not a file path",
+            path.display()
+        )));
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+    let canonical = parent
+        .canonicalize()
+        .with_context(|| format!("open {} failed", parent.display()))?
+        .join(name);
+    if canonical
+        .symlink_metadata()
+        .is_ok_and(|m| m.file_type().is_symlink())
+    {
+        return Err(Error::Unsupported(format!(
+            "{} is a symlink",
+            canonical.display()
+        )));
+    }
+    if !scope().iter().any(|root| canonical.starts_with(root)) {
+        return Err(Error::Unsupported(format!(
+            "{} outside of the file scope, add its directory to {}",
+            canonical.display(),
+            SCOPE_ENV
+        )));
+    }
+    Ok(canonical)
+}
+
+pub(crate) fn read_scoped(path: &str) -> Result<Vec<u8>> {
+    let canonical = scoped(path)?;
+    let content = std::fs::read(&canonical)
+        .with_context(|| format!("read {} failed", canonical.display()))?;
+    info!("read {}, size: {}", canonical.display(), content.len());
+    Ok(content)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_scoped, scoped_new};
+
+    #[test]
+    fn test_read_scoped() {
+        let path = std::env::temp_dir().join("kits-files-scoped.der");
+        std::fs::write(&path, [0x30, 0x00]).unwrap();
+        assert_eq!(read_scoped(path.to_str().unwrap()).unwrap(), [0x30, 0x00]);
+
+        let escaped = std::env::temp_dir().join("..").join("..");
+        assert!(read_scoped(escaped.to_str().unwrap()).is_err());
+        assert!(read_scoped("/nonexistent/kits").is_err());
+    }
+
+    #[test]
+    fn test_scoped_new() {
+        let path = std::env::temp_dir().join("kits-files-scoped-new.der");
+        assert!(scoped_new(&path)
+            .unwrap()
+            .ends_with("kits-files-scoped-new.der"));
+
+        let escaped = std::env::temp_dir().join("..").join("..").join("kits");
+        assert!(scoped_new(&escaped).is_err());
+        assert!(scoped_new(&std::env::temp_dir().join("..")).is_err());
+        assert!(scoped_new(std::path::Path::new("/nonexistent/kits")).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Entry points of the cargo-fuzz targets. Errors are expected, panics,
+//! hangs and runaway allocations are the findings.
+
+use coset::{CborSerializable, CoseEncrypt0, CoseSign1};
+
+use crate::{
+    audit::jwt::audit_jwt,
+    codec::{
+        cbor_decode,
+        pem::{normalize_pem, sniff_label},
+    },
+    cose::{cwt::cwt_decode, key::cose_key_to_jwk, parse_cose_key},
+    crypto::{
+        ecc::key::{import_ecc_private_key, import_ecc_public_key, parse_ecc},
+        ecies::{open, Suite},
+        rsa::key::{bytes_to_private_key, bytes_to_public_key, parse_rsa},
+        shamir::combine_shares,
+    },
+    dns::{
+        dkim::{dkim_verify, validate_dkim_record, DkimVerifyDto},
+        dnssec_ds, DsDto,
+    },
+    enums::{
+        Digest, EccCurveName, EciesEncryptionAlgorithm, Kdf, KeyFormat, Pkcs,
+        TextEncoding,
+    },
+    identify,
+    keybridge::{self, KeyBridgeDto, KeyBridgeFormat},
+    share::{open_share, OpenShareDto},
+    ssh::{analyze_authorized_keys, analyze_known_hosts},
+    vault::backup::import_vault,
+    x509::{certificates_from_pem, read_all, sct::parse_sct},
+};
+
+fn text(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+fn hex(data: &[u8]) -> String {
+    TextEncoding::Hex.encode(data).unwrap_or_default()
+}
+
+pub fn parse_rsa_key(data: &[u8]) {
+    let _ = parse_rsa(text(data));
+    for pkcs in [Pkcs::Pkcs1, Pkcs::Pkcs8] {
+        for format in [KeyFormat::Pem, KeyFormat::Der] {
+            let _ = bytes_to_private_key(data, pkcs, format);
+            let _ = bytes_to_public_key(data, pkcs, format);
+        }
+    }
+}
+
+pub fn parse_ecc_key(data: &[u8]) {
+    let _ = parse_ecc(text(data));
+    for pkcs in [Pkcs::Sec1, Pkcs::Pkcs8] {
+        for format in [KeyFormat::Pem, KeyFormat::Der] {
+            let _ =
+                import_ecc_private_key::<p256::NistP256>(data, pkcs, format);
+            let _ =
+                import_ecc_private_key::<k256::Secp256k1>(data, pkcs, format);
+        }
+    }
+    for format in [KeyFormat::Pem, KeyFormat::Der] {
+        let _ = import_ecc_public_key::<p256::NistP256>(data, format);
+    }
+}
+
+/// The pem normalizer and the der readers behind the converters.
+pub fn pem_der(data: &[u8]) {
+    let _ = normalize_pem(text(data), None);
+    let _ = certificates_from_pem(&text(data));
+    let _ = sniff_label(data);
+    let _ = read_all(data);
+}
+
+pub fn jwt(data: &[u8]) {
+    let _ = audit_jwt(text(data), None);
+}
+
+/// Every parser at once, as the blob analyzer runs them.
+pub fn identify(data: &[u8]) {
+    let _ = identify::identify(text(data));
+}
+
+/// The ecies header, the decryption itself is replaced by the identity.
+pub fn ecies_envelope(data: &[u8]) {
+    let suite = Suite::ecc(
+        EccCurveName::NistP256,
+        Kdf::HKdf,
+        Digest::Sha256,
+        EciesEncryptionAlgorithm::AesGcm,
+    );
+    for strict in [false, true] {
+        let _ = open(suite, strict, data, |_, body| Ok(body.to_vec()));
+    }
+}
+
+/// One share per line.
+pub fn shamir(data: &[u8]) {
+    let shares = data.split(|&byte| byte == b'\n').map(hex).collect();
+    let _ = combine_shares(shares, TextEncoding::Hex);
+}
+
+/// Both kinds of links, the argon2 costs are capped by the decoder.
+pub fn share_link(data: &[u8]) {
+    let _ = open_share(OpenShareDto {
+        link: text(data),
+        passphrase: Some("passphrase".to_string()),
+        private_key: Some(hex(&[7; 32])),
+        private_key_encoding: Some(TextEncoding::Hex),
+    });
+}
+
+/// The vault is only written once the archive authenticates.
+pub fn vault_archive(data: &[u8]) {
+    let _ = import_vault(
+        hex(data),
+        TextEncoding::Hex,
+        "passphrase".to_string(),
+        None,
+    );
+}
+
+pub fn sct(data: &[u8]) {
+    let _ =
+        parse_sct(hex(data), None, KeyFormat::Der, vec![], TextEncoding::Hex);
+}
+
+pub fn dnskey(data: &[u8]) {
+    let _ = dnssec_ds(DsDto {
+        dnskey: text(data),
+        owner: None,
+        digests: None,
+    });
+}
+
+/// The input is both the message and the key record.
+pub fn dkim(data: &[u8]) {
+    let _ = validate_dkim_record(text(data));
+    let _ = dkim_verify(DkimVerifyDto {
+        message: text(data),
+        record: text(data),
+    });
+}
+
+pub fn known_hosts(data: &[u8]) {
+    let _ = analyze_known_hosts(text(data), Some("example.com".to_string()));
+    let _ = analyze_authorized_keys(text(data));
+}
+
+/// Every source format, the protobuf of tink keysets among them.
+pub fn keybridge(data: &[u8]) {
+    for from in [
+        KeyBridgeFormat::Tink,
+        KeyBridgeFormat::Libsodium,
+        KeyBridgeFormat::Jwk,
+        KeyBridgeFormat::Pkcs8,
+    ] {
+        let _ = keybridge::keybridge(KeyBridgeDto {
+            input: text(data),
+            from,
+            to: KeyBridgeFormat::Jwk,
+            key_type: None,
+            encoding: None,
+        });
+    }
+}
+
+pub fn cbor_cose(data: &[u8]) {
+    let _ = cbor_decode(hex(data), TextEncoding::Hex);
+    let _ = parse_cose_key(data);
+    let _ = cose_key_to_jwk(hex(data), TextEncoding::Hex);
+    let _ = cwt_decode(hex(data), TextEncoding::Hex);
+    let _ = CoseSign1::from_slice(data);
+    let _ = CoseEncrypt0::from_slice(data);
+}
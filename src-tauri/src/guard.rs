@@ -0,0 +1,214 @@
+//! Limits every command call is checked against before it runs, so a typo
+//! like a key length of 10^9 is refused instead of allocating gigabytes.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{Invoke, Manager, Runtime};
+use tracing::{info, warn};
+
+use crate::{
+    errors::{Error, Result},
+    vault::session::Session,
+};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Compared in lowercase without underscores.
+const PARAMETERS: &[(&str, u64)] = &[
+    // bytes of derived key material
+    ("keylength", 1024),
+    // bits, rsa stops at 16384
+    ("keysize", 16 * 1024),
+    ("outputlength", 1024 * 1024),
+    ("length", 1024 * 1024),
+    ("size", 1024 * 1024),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InputLimits {
+    /// bytes of strings in the arguments of a call
+    pub max_input: usize,
+    /// overrides of `max_input` by command name
+    #[serde(default)]
+    pub commands: BTreeMap<String, usize>,
+    /// calls of one command within a second
+    pub max_calls_per_second: u32,
+}
+
+impl InputLimits {
+    const fn new() -> Self {
+        InputLimits {
+            max_input: 64 * 1024 * 1024,
+            commands: BTreeMap::new(),
+            max_calls_per_second: 200,
+        }
+    }
+}
+
+static LIMITS: RwLock<InputLimits> = RwLock::new(InputLimits::new());
+/// start of the current window and the calls in it, by command
+static CALLS: Mutex<BTreeMap<String, (Instant, u32)>> =
+    Mutex::new(BTreeMap::new());
+
+#[tauri::command]
+pub fn input_limits() -> Result<InputLimits> {
+    Ok(LIMITS
+        .read()
+        .map_err(|_| anyhow!("input limits lock poisoned"))?
+        .clone())
+}
+
+#[tauri::command]
+pub fn set_input_limits(limits: InputLimits) -> Result<InputLimits> {
+    if limits.max_input == 0 || limits.max_calls_per_second == 0 {
+        return Err(Error::Unsupported("zero input limits".to_string()));
+    }
+    info!("set input limits: {:?}", limits);
+    *LIMITS
+        .write()
+        .map_err(|_| anyhow!("input limits lock poisoned"))? = limits.clone();
+    Ok(limits)
+}
+
+pub(crate) fn check(command: &str, arguments: &Value) -> Result<()> {
+    check_with_input(command, arguments, 0)
+}
+
+/// `check` with `input` raw bytes besides the arguments.
+pub(crate) fn check_with_input(
+    command: &str,
+    arguments: &Value,
+    input: usize,
+) -> Result<()> {
+    let max_input = max_input(command)?;
+    let size = input_size(arguments) + input;
+    if size > max_input {
+        return Err(Error::Unsupported(format!(
+            "{} bytes input to {}, at most {}",
+            size, command, max_input
+        )));
+    }
+    check_parameters(command, arguments)
+}
+
+/// Bytes of input `command` takes at most.
+pub(crate) fn max_input(command: &str) -> Result<usize> {
+    let limits = LIMITS
+        .read()
+        .map_err(|_| anyhow!("input limits lock poisoned"))?;
+    Ok(limits
+        .commands
+        .get(command)
+        .copied()
+        .unwrap_or(limits.max_input))
+}
+
+pub(crate) fn throttle(command: &str) -> Result<()> {
+    let max_calls_per_second = LIMITS
+        .read()
+        .map_err(|_| anyhow!("input limits lock poisoned"))?
+        .max_calls_per_second;
+    let mut calls = CALLS
+        .lock()
+        .map_err(|_| anyhow!("call counter lock poisoned"))?;
+    let now = Instant::now();
+    let (started, count) = calls.entry(command.to_string()).or_insert((now, 0));
+    if now.duration_since(*started) >= WINDOW {
+        (*started, *count) = (now, 0);
+    }
+    *count += 1;
+    if *count > max_calls_per_second {
+        warn!("{} called {} times within a second", command, count);
+        return Err(Error::Unsupported(format!(
+            "more than {} calls of {} per second",
+            max_calls_per_second, command
+        )));
+    }
+    Ok(())
+}
+
+fn input_size(value: &Value) -> usize {
+    match value {
+        Value::String(text) => text.len(),
+        Value::Array(values) => values.iter().map(input_size).sum(),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(name, value)| name.len() + input_size(value))
+            .sum(),
+        _ => 8,
+    }
+}
+
+fn check_parameters(command: &str, value: &Value) -> Result<()> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .try_for_each(|value| check_parameters(command, value)),
+        Value::Object(fields) => fields.iter().try_for_each(|(name, value)| {
+            let normalized = name.replace('_', "").to_lowercase();
+            let bound = PARAMETERS
+                .iter()
+                .find(|(parameter, _)| *parameter == normalized);
+            match (bound, value.as_u64()) {
+                (Some((_, max)), Some(given)) if given > *max => {
+                    Err(Error::Unsupported(format!(
+                        "{} of {} to {}, at most {}",
+                        name, given, command, max
+                    )))
+                }
+                _ => check_parameters(command, value),
+            }
+        }),
+        _ => Ok(()),
+    }
+}
+
+pub fn guarded<R: Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    move |invoke: Invoke<R>| {
+        let command = invoke.message.command();
+        let checked = check(command, invoke.message.payload())
+            .and_then(|_| throttle(command))
+            .and_then(|_| {
+                invoke.message.window().state::<Session>().enter(command)
+            });
+        match checked {
+            Ok(()) => handler(invoke),
+            Err(err) => invoke.resolver.reject(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{check, throttle};
+
+    #[test]
+    fn test_check() {
+        let kdf = |key_length: u64| {
+            json!({"data": {"kdf": "hkdf", "input": "abc",
+                "key_length": key_length}})
+        };
+        assert!(check("kdf", &kdf(32)).is_ok());
+        assert!(check("kdf", &kdf(1_000_000_000)).is_err());
+        assert!(check("generate_rsa", &json!({"keySize": 65536})).is_err());
+        assert!(check("generate_iv", &json!({"size": 16})).is_ok());
+
+        let flood = (0 .. 1000)
+            .map(|_| throttle("test_flood"))
+            .filter(Result::is_err)
+            .count();
+        assert!(flood > 0);
+    }
+}
@@ -0,0 +1,203 @@
+//! A small pkcs#11 console. Every call loads `module`, the vendor library,
+//! and finalizes it when done.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{enums::TextEncoding, errors::Result};
+
+#[cfg(feature = "hsm")]
+mod token;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HsmSlot {
+    pub id: u64,
+    pub description: String,
+    pub token_label: Option<String>,
+    pub token_model: Option<String>,
+    pub token_serial: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HsmObject {
+    pub class: String,
+    pub key_type: Option<String>,
+    pub label: String,
+    /// CKA_ID in hex
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HsmKeyType {
+    #[serde(rename = "RSA-2048")]
+    Rsa2048,
+    #[serde(rename = "RSA-3072")]
+    Rsa3072,
+    #[serde(rename = "RSA-4096")]
+    Rsa4096,
+    #[serde(rename = "P-256")]
+    P256,
+    #[serde(rename = "P-384")]
+    P384,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HsmMechanism {
+    /// CKM_SHA256_RSA_PKCS
+    #[serde(rename = "RS256")]
+    Rs256,
+    /// CKM_SHA256_RSA_PKCS_PSS with a 32 byte salt
+    #[serde(rename = "PS256")]
+    Ps256,
+    /// CKM_ECDSA_SHA256, raw r || s output
+    #[serde(rename = "ES256")]
+    Es256,
+    /// CKM_ECDSA_SHA384, raw r || s output
+    #[serde(rename = "ES384")]
+    Es384,
+    /// CKM_RSA_PKCS
+    #[serde(rename = "RSA1_5")]
+    Rsa1_5,
+    /// CKM_RSA_PKCS_OAEP with sha-256 and mgf1-sha256
+    #[serde(rename = "RSA-OAEP-256")]
+    RsaOaep256,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HsmSessionDto {
+    pub module: String,
+    pub slot: u64,
+    pub pin: Option<String>,
+}
+
+impl std::fmt::Debug for HsmSessionDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HsmSessionDto")
+            .field("module", &self.module)
+            .field("slot", &self.slot)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HsmGenerateDto {
+    pub session: HsmSessionDto,
+    pub key_type: HsmKeyType,
+    pub label: String,
+    /// CKA_ID in hex, random when absent
+    pub id: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HsmGeneratedKey {
+    pub label: String,
+    pub id: String,
+    /// spki pem of the public half
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HsmOperationDto {
+    pub session: HsmSessionDto,
+    /// label of the private key object
+    pub label: String,
+    pub mechanism: HsmMechanism,
+    pub input: String,
+    pub input_encoding: TextEncoding,
+    pub output_encoding: TextEncoding,
+}
+
+#[tauri::command]
+pub fn hsm_slots(module: String) -> Result<Vec<HsmSlot>> {
+    info!("hsm slots: {}", module);
+    token::slots(&module)
+}
+
+#[tauri::command]
+pub fn hsm_objects(session: HsmSessionDto) -> Result<Vec<HsmObject>> {
+    info!("hsm objects: {:?}", session);
+    token::objects(&session)
+}
+
+#[tauri::command]
+pub fn hsm_generate_key(data: HsmGenerateDto) -> Result<HsmGeneratedKey> {
+    info!("hsm generate key: {:?}", data);
+    token::generate_key(&data)
+}
+
+#[tauri::command]
+pub fn hsm_sign(data: HsmOperationDto) -> Result<String> {
+    info!("hsm sign: {:?}", data);
+    let input = data.input_encoding.decode(&data.input)?;
+    data.output_encoding.encode(&token::sign(
+        &data.session,
+        &data.label,
+        data.mechanism,
+        &input,
+    )?)
+}
+
+#[tauri::command]
+pub fn hsm_decrypt(data: HsmOperationDto) -> Result<String> {
+    info!("hsm decrypt: {:?}", data);
+    let input = data.input_encoding.decode(&data.input)?;
+    data.output_encoding.encode(&token::decrypt(
+        &data.session,
+        &data.label,
+        data.mechanism,
+        &input,
+    )?)
+}
+
+#[cfg(not(feature = "hsm"))]
+mod token {
+    use super::{
+        HsmGenerateDto, HsmGeneratedKey, HsmMechanism, HsmObject,
+        HsmSessionDto, HsmSlot,
+    };
+    use crate::errors::{Error, Result};
+
+    fn disabled<T>() -> Result<T> {
+        Err(Error::Unsupported(
+            "pkcs#11, kits was built without the `hsm` feature".to_string(),
+        ))
+    }
+
+    pub(super) fn slots(_module: &str) -> Result<Vec<HsmSlot>> {
+        disabled()
+    }
+
+    pub(super) fn objects(_session: &HsmSessionDto) -> Result<Vec<HsmObject>> {
+        disabled()
+    }
+
+    pub(super) fn generate_key(
+        _data: &HsmGenerateDto,
+    ) -> Result<HsmGeneratedKey> {
+        disabled()
+    }
+
+    pub(super) fn sign(
+        _session: &HsmSessionDto,
+        _label: &str,
+        _mechanism: HsmMechanism,
+        _input: &[u8],
+    ) -> Result<Vec<u8>> {
+        disabled()
+    }
+
+    pub(super) fn decrypt(
+        _session: &HsmSessionDto,
+        _label: &str,
+        _mechanism: HsmMechanism,
+        _input: &[u8],
+    ) -> Result<Vec<u8>> {
+        disabled()
+    }
+}
@@ -0,0 +1,318 @@
+use anyhow::Context;
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    mechanism::{
+        rsa::{PkcsMgfType, PkcsOaepParams, PkcsOaepSource, PkcsPssParams},
+        Mechanism, MechanismType,
+    },
+    object::{Attribute, AttributeType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::AuthPin,
+};
+use der::{asn1::OctetString, Decode};
+use pkcs8::{EncodePublicKey, LineEnding};
+
+use super::{
+    HsmGenerateDto, HsmGeneratedKey, HsmKeyType, HsmMechanism, HsmObject,
+    HsmSessionDto, HsmSlot,
+};
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    utils::random_bytes,
+};
+
+/// DER encoded named curve oids for CKA_EC_PARAMS.
+const P256_PARAMS: &[u8] =
+    &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const P384_PARAMS: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x22];
+
+fn load(module: &str) -> Result<Pkcs11> {
+    let pkcs11 = Pkcs11::new(module)
+        .context(format!("load pkcs#11 module {} failed", module))?;
+    pkcs11
+        .initialize(CInitializeArgs::OsThreads)
+        .context("initialize pkcs#11 module failed")?;
+    Ok(pkcs11)
+}
+
+fn open(pkcs11: &Pkcs11, session: &HsmSessionDto) -> Result<Session> {
+    let slot = pkcs11
+        .get_all_slots()
+        .context("list pkcs#11 slots failed")?
+        .into_iter()
+        .find(|slot| slot.id() == session.slot)
+        .ok_or_else(|| {
+            Error::Unsupported(format!("pkcs#11 slot {}", session.slot))
+        })?;
+    let opened = pkcs11
+        .open_rw_session(slot)
+        .context(format!("open session on slot {} failed", session.slot))?;
+    if let Some(pin) = &session.pin {
+        opened
+            .login(UserType::User, Some(&AuthPin::new(pin.clone())))
+            .context("pkcs#11 login failed")?;
+    }
+    Ok(opened)
+}
+
+pub(super) fn slots(module: &str) -> Result<Vec<HsmSlot>> {
+    let pkcs11 = load(module)?;
+    pkcs11
+        .get_all_slots()
+        .context("list pkcs#11 slots failed")?
+        .into_iter()
+        .map(|slot| {
+            let info = pkcs11
+                .get_slot_info(slot)
+                .context("read pkcs#11 slot info failed")?;
+            let token = if info.token_present() {
+                Some(
+                    pkcs11
+                        .get_token_info(slot)
+                        .context("read pkcs#11 token info failed")?,
+                )
+            } else {
+                None
+            };
+            Ok(HsmSlot {
+                id: slot.id(),
+                description: info.slot_description().trim().to_string(),
+                token_label: token
+                    .as_ref()
+                    .map(|token| token.label().trim().to_string()),
+                token_model: token
+                    .as_ref()
+                    .map(|token| token.model().trim().to_string()),
+                token_serial: token
+                    .as_ref()
+                    .map(|token| token.serial_number().trim().to_string()),
+            })
+        })
+        .collect()
+}
+
+pub(super) fn objects(session: &HsmSessionDto) -> Result<Vec<HsmObject>> {
+    let pkcs11 = load(&session.module)?;
+    let opened = open(&pkcs11, session)?;
+    opened
+        .find_objects(&[Attribute::Token(true)])
+        .context("find pkcs#11 objects failed")?
+        .into_iter()
+        .map(|handle| {
+            let mut object = HsmObject {
+                class: String::new(),
+                key_type: None,
+                label: String::new(),
+                id: String::new(),
+            };
+            let attributes = opened
+                .get_attributes(handle, &[
+                    AttributeType::Class,
+                    AttributeType::KeyType,
+                    AttributeType::Label,
+                    AttributeType::Id,
+                ])
+                .context("read pkcs#11 object attributes failed")?;
+            for attribute in attributes {
+                match attribute {
+                    Attribute::Class(class) => object.class = class.to_string(),
+                    Attribute::KeyType(key_type) => {
+                        object.key_type = Some(key_type.to_string())
+                    }
+                    Attribute::Label(label) => {
+                        object.label = String::from_utf8_lossy(&label).into()
+                    }
+                    Attribute::Id(id) => {
+                        object.id = TextEncoding::Hex.encode(&id)?
+                    }
+                    _ => {}
+                }
+            }
+            Ok(object)
+        })
+        .collect()
+}
+
+pub(super) fn generate_key(data: &HsmGenerateDto) -> Result<HsmGeneratedKey> {
+    let pkcs11 = load(&data.session.module)?;
+    let opened = open(&pkcs11, &data.session)?;
+    let id = match &data.id {
+        Some(id) => TextEncoding::Hex.decode(id)?,
+        None => random_bytes(8)?,
+    };
+    let label = Attribute::Label(data.label.as_bytes().to_vec());
+    let mut public = vec![
+        Attribute::Token(true),
+        Attribute::Verify(true),
+        label.clone(),
+        Attribute::Id(id.clone()),
+    ];
+    let mut private = vec![
+        Attribute::Token(true),
+        Attribute::Private(true),
+        Attribute::Sensitive(true),
+        Attribute::Extractable(false),
+        Attribute::Sign(true),
+        label,
+        Attribute::Id(id.clone()),
+    ];
+    let mechanism = match data.key_type {
+        HsmKeyType::Rsa2048 | HsmKeyType::Rsa3072 | HsmKeyType::Rsa4096 => {
+            let bits: u64 = match data.key_type {
+                HsmKeyType::Rsa2048 => 2048,
+                HsmKeyType::Rsa3072 => 3072,
+                _ => 4096,
+            };
+            public.extend([
+                Attribute::Encrypt(true),
+                Attribute::ModulusBits(bits.into()),
+                Attribute::PublicExponent(vec![0x01, 0x00, 0x01]),
+            ]);
+            private.push(Attribute::Decrypt(true));
+            Mechanism::RsaPkcsKeyPairGen
+        }
+        HsmKeyType::P256 => {
+            public.push(Attribute::EcParams(P256_PARAMS.to_vec()));
+            Mechanism::EccKeyPairGen
+        }
+        HsmKeyType::P384 => {
+            public.push(Attribute::EcParams(P384_PARAMS.to_vec()));
+            Mechanism::EccKeyPairGen
+        }
+    };
+    let (public, _) = opened
+        .generate_key_pair(&mechanism, &public, &private)
+        .context("generate pkcs#11 key pair failed")?;
+    Ok(HsmGeneratedKey {
+        label: data.label.clone(),
+        id: TextEncoding::Hex.encode(&id)?,
+        public_key: public_key_pem(&opened, public, data.key_type)?,
+    })
+}
+
+fn public_key_pem(
+    session: &Session,
+    handle: ObjectHandle,
+    key_type: HsmKeyType,
+) -> Result<String> {
+    let attributes = session
+        .get_attributes(handle, &[
+            AttributeType::Modulus,
+            AttributeType::PublicExponent,
+            AttributeType::EcPoint,
+        ])
+        .context("read pkcs#11 public key failed")?;
+    let mut modulus = None;
+    let mut exponent = None;
+    let mut point = None;
+    for attribute in attributes {
+        match attribute {
+            Attribute::Modulus(value) => modulus = Some(value),
+            Attribute::PublicExponent(value) => exponent = Some(value),
+            Attribute::EcPoint(value) => point = Some(value),
+            _ => {}
+        }
+    }
+    let missing = || anyhow::anyhow!("token did not return the public key");
+    let pem = match key_type {
+        HsmKeyType::P256 | HsmKeyType::P384 => {
+            let point = point.ok_or_else(missing)?;
+            // CKA_EC_POINT is meant to be a der octet string, some tokens
+            // return the bare point
+            let point = OctetString::from_der(&point)
+                .map(|octets| octets.as_bytes().to_vec())
+                .unwrap_or(point);
+            if key_type == HsmKeyType::P256 {
+                p256::PublicKey::from_sec1_bytes(&point)
+                    .context("invalid p-256 point from token")?
+                    .to_public_key_pem(LineEnding::LF)
+            } else {
+                p384::PublicKey::from_sec1_bytes(&point)
+                    .context("invalid p-384 point from token")?
+                    .to_public_key_pem(LineEnding::LF)
+            }
+        }
+        _ => rsa::RsaPublicKey::new(
+            rsa::BigUint::from_bytes_be(&modulus.ok_or_else(missing)?),
+            rsa::BigUint::from_bytes_be(&exponent.ok_or_else(missing)?),
+        )
+        .context("invalid rsa public key from token")?
+        .to_public_key_pem(LineEnding::LF),
+    }
+    .context("encode public key failed")?;
+    Ok(pem)
+}
+
+fn private_key(session: &Session, label: &str) -> Result<ObjectHandle> {
+    session
+        .find_objects(&[
+            Attribute::Class(ObjectClass::PRIVATE_KEY),
+            Attribute::Label(label.as_bytes().to_vec()),
+        ])
+        .context("find pkcs#11 private key failed")?
+        .into_iter()
+        .next()
+        .ok_or_else(|| Error::Unsupported(format!("private key `{}`", label)))
+}
+
+pub(super) fn sign(
+    session: &HsmSessionDto,
+    label: &str,
+    mechanism: HsmMechanism,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let pss = PkcsPssParams {
+        hash_alg: MechanismType::SHA256,
+        mgf: PkcsMgfType::MGF1_SHA256,
+        s_len: 32u64.into(),
+    };
+    let mechanism = match mechanism {
+        HsmMechanism::Rs256 => Mechanism::Sha256RsaPkcs,
+        HsmMechanism::Ps256 => Mechanism::Sha256RsaPkcsPss(pss),
+        HsmMechanism::Es256 => Mechanism::EcdsaSha256,
+        HsmMechanism::Es384 => Mechanism::EcdsaSha384,
+        mechanism => {
+            return Err(Error::Unsupported(format!(
+                "{:?} is not a signature mechanism",
+                mechanism
+            )))
+        }
+    };
+    let pkcs11 = load(&session.module)?;
+    let opened = open(&pkcs11, session)?;
+    let key = private_key(&opened, label)?;
+    Ok(opened
+        .sign(&mechanism, key, input)
+        .context("pkcs#11 sign failed")?)
+}
+
+pub(super) fn decrypt(
+    session: &HsmSessionDto,
+    label: &str,
+    mechanism: HsmMechanism,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    let mechanism = match mechanism {
+        HsmMechanism::Rsa1_5 => Mechanism::RsaPkcs,
+        HsmMechanism::RsaOaep256 => {
+            Mechanism::RsaPkcsOaep(PkcsOaepParams::new(
+                MechanismType::SHA256,
+                PkcsMgfType::MGF1_SHA256,
+                PkcsOaepSource::empty(),
+            ))
+        }
+        mechanism => {
+            return Err(Error::Unsupported(format!(
+                "{:?} is not a decryption mechanism",
+                mechanism
+            )))
+        }
+    };
+    let pkcs11 = load(&session.module)?;
+    let opened = open(&pkcs11, session)?;
+    let key = private_key(&opened, label)?;
+    Ok(opened
+        .decrypt(&mechanism, key, input)
+        .context("pkcs#11 decrypt failed")?)
+}
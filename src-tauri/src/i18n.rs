@@ -0,0 +1,250 @@
+//! Message catalog, errors get a stable code and come out in the app locale.
+//! Internal errors are keyed by their outermost context, details after a
+//! `, ` left out.
+
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::errors::Result;
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::En);
+
+pub(crate) const IO: &str = "io";
+pub(crate) const UNSUPPORTED: &str = "unsupported";
+pub(crate) const INTERNAL: &str = "internal";
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+struct Message {
+    code: &'static str,
+    en: &'static str,
+    zh: &'static str,
+}
+
+macro_rules! catalog {
+    ($($code:literal => $en:literal, $zh:literal;)*) => {
+        &[$(Message { code: $code, en: $en, zh: $zh }),*]
+    };
+}
+
+/// `{0}` stands for the detail of io and unsupported errors.
+const CATALOG: &[Message] = catalog! {
+    "io" => "{0}", "读写失败: {0}";
+    "unsupported" => "`{0}` is unsupported", "不支持 `{0}`";
+    "codec.base64" => "base64 decode failed", "base64 解码失败";
+    "codec.hex.encode" => "hex encode failed", "hex 编码失败";
+    "codec.hex.decode" => "hex decode failed", "hex 解码失败";
+    "codec.utf8" => "utf-8 encode failed", "不是有效的 utf-8 文本";
+    "codec.json" => "invalid json input", "无效的 json 输入";
+    "key.hmac" => "invalid hmac key", "无效的 hmac 密钥";
+    "key.save.pem" => "invalid pem key to save", "要保存的 pem 密钥无效";
+    "key.audit.pem" => "invalid pem key to audit", "要审计的 pem 密钥无效";
+    "pkcs8.pem.private" => "invalid pkcs8 pem private key",
+        "无效的 pkcs8 pem 私钥";
+    "pkcs8.der.private" => "invalid pkcs8 der private key",
+        "无效的 pkcs8 der 私钥";
+    "pkcs8.pem.public" => "invalid pkcs8 pem public key",
+        "无效的 pkcs8 pem 公钥";
+    "pkcs8.der.public" => "invalid pkcs8 der public key",
+        "无效的 pkcs8 der 公钥";
+    "pkcs8.private" => "invalid pkcs8 private key", "无效的 pkcs8 私钥";
+    "pkcs8.pem.private.export" => "export pkcs8 pem private key failed",
+        "导出 pkcs8 pem 私钥失败";
+    "pkcs8.der.private.export" => "export pkcs8 der private key failed",
+        "导出 pkcs8 der 私钥失败";
+    "pkcs8.pem.public.export" => "export pkcs8 pem public key failed",
+        "导出 pkcs8 pem 公钥失败";
+    "pkcs8.der.public.export" => "export pkcs8 der public key failed",
+        "导出 pkcs8 der 公钥失败";
+    "ecc.pem" => "invalid ecc pem key", "无效的 ecc pem 密钥";
+    "ecc.pkcs8.pem.private" => "informal ecc pkcs8 pem private key",
+        "无效的 ecc pkcs8 pem 私钥";
+    "ecc.pkcs8.der.private" => "informal ecc pkcs8 der private key",
+        "无效的 ecc pkcs8 der 私钥";
+    "ecc.sec1.pem.private" => "informal ecc sec1 pem private key",
+        "无效的 ecc sec1 pem 私钥";
+    "ecc.sec1.der.private" => "informal ecc sec1 der private key",
+        "无效的 ecc sec1 der 私钥";
+    "ecc.pem.public" => "informal ecc pem public key", "无效的 ecc pem 公钥";
+    "ecc.der.public" => "informal ecc der public key", "无效的 ecc der 公钥";
+    "ecc.public" => "informal ecc public key", "无效的 ecc 公钥";
+    "sec1.pem.private" => "invalid sec1 pem private key",
+        "无效的 sec1 pem 私钥";
+    "sec1.der.private" => "invalid sec1 der private key",
+        "无效的 sec1 der 私钥";
+    "sec1.pem.public" => "invalid sec1 pem public key", "无效的 sec1 pem 公钥";
+    "sec1.der.public" => "invalid sec1 der public key", "无效的 sec1 der 公钥";
+    "curve25519.pkcs8.pem.private" =>
+        "informal curve 25519 pkcs8 pem private key",
+        "无效的 curve 25519 pkcs8 pem 私钥";
+    "curve25519.pkcs8.der.private" =>
+        "informal curve 25519 pkcs8 der private key",
+        "无效的 curve 25519 pkcs8 der 私钥";
+    "curve25519.pem.public" => "informal curve 25519 pem public key",
+        "无效的 curve 25519 pem 公钥";
+    "curve25519.der.public" => "informal curve 25519 der public key",
+        "无效的 curve 25519 der 公钥";
+    "rsa.pem" => "invalid rsa pem key", "无效的 rsa pem 密钥";
+    "rsa.pkcs1.pem.private" => "invalid pkcs1 pem private key",
+        "无效的 pkcs1 pem rsa 私钥";
+    "rsa.pkcs1.der.private" => "invalid pkcs1 der private key",
+        "无效的 pkcs1 der rsa 私钥";
+    "rsa.pkcs1.pem.public" => "invalid pkcs1 pem public key",
+        "无效的 pkcs1 pem rsa 公钥";
+    "rsa.pkcs1.der.public" => "invalid pkcs1 der public key",
+        "无效的 pkcs1 der rsa 公钥";
+    "rsa.pkcs1.private" => "invalid pkcs1 private key",
+        "无效的 pkcs1 rsa 私钥";
+    "rsa.public" => "invalid rsa public key", "无效的 rsa 公钥";
+    "rsa.encrypt" => "rsa encrypt failed", "rsa 加密失败";
+    "aes.cbc.encryptor" => "construct aes_cbc_encryptor failed",
+        "aes cbc 密钥或 iv 长度有误";
+    "aes.cbc.decryptor" => "construct aes_cbc_decryptor failed",
+        "aes cbc 密钥或 iv 长度有误";
+    "aes.encrypt" => "aes encrypt failed", "aes 加密失败";
+    "aes.decrypt" => "aes decrypt failed", "aes 解密失败";
+    "aes.gcm.encrypt" => "aes gcm encrypt failed", "aes gcm 加密失败";
+    "aes.gcm.decrypt" => "aes gcm decrypt failed",
+        "aes gcm 解密失败, 密钥, nonce 或密文有误";
+    "aes.ccm.cipher" => "construct aes_ccm_cipher failed",
+        "aes ccm 密钥长度有误";
+    "aes.ccm.encrypt" => "aes ccm encrypt failed", "aes ccm 加密失败";
+    "aes.ccm.decrypt" => "aes ccm decrypt failed",
+        "aes ccm 解密失败, 密钥, nonce 或密文有误";
+    "aes.xts.cipher" => "construct aes_xts_cipher failed",
+        "aes xts 密钥长度有误";
+    "aes.cbc.hmac" => "aes cbc hmac tag mismatch",
+        "aes cbc hmac 校验失败, 密钥, iv, aad 或密文有误";
+    "sm2.private" => "invalid sm2 private key", "无效的 sm2 私钥";
+    "sm2.public" => "invalid sm2 public key", "无效的 sm2 公钥";
+    "sm2.pkcs8.der.private" => "invalid sm2 pkcs8 der private key",
+        "无效的 pkcs8 der sm2 私钥";
+    "sm2.spki.der.public" => "invalid sm2 spki der public key",
+        "无效的 spki der sm2 公钥";
+    "sm2.pkcs8.der.private.export" =>
+        "export sm2 pkcs8 der private key failed",
+        "导出 pkcs8 der sm2 私钥失败";
+    "sm2.spki.der.public.export" => "export sm2 spki der public key failed",
+        "导出 spki der sm2 公钥失败";
+    "sm4.gcm.cipher" => "construct sm4_gcm_cipher failed",
+        "sm4 gcm 密钥长度有误";
+    "sm4.gcm.encrypt" => "sm4 gcm encrypt failed", "sm4 gcm 加密失败";
+    "sm4.gcm.decrypt" => "sm4 gcm decrypt failed",
+        "sm4 gcm 解密失败, 密钥, nonce 或密文有误";
+    "task.cancelled" => "task cancelled", "任务已取消";
+    "jwk" => "invalid jwk", "无效的 jwk";
+    "jwe.header" => "invalid jwe header", "无效的 jwe 头部";
+    "jwe.key.wrap" => "wrap jwe content encryption key failed",
+        "jwe 内容加密密钥封装失败";
+    "jwe.key.unwrap" => "unwrap jwe content encryption key failed",
+        "jwe 内容加密密钥解封失败, 密钥或密文有误";
+    "jwe.epk" => "invalid jwe epk", "无效的 jwe 临时公钥";
+    "ecdh_es.jwk" => "invalid ECDH-ES jwk", "无效的 ECDH-ES jwk";
+    "ecdh_es.public" => "invalid ECDH-ES public key", "无效的 ECDH-ES 公钥";
+    "ecdh_es.private" => "invalid ECDH-ES private key", "无效的 ECDH-ES 私钥";
+    "ecdh_es.derive" => "derive ECDH-ES key failed", "ECDH-ES 密钥派生失败";
+    "aes.kw.cipher" => "construct aes_kw_cipher failed", "aes kw 密钥长度有误";
+    "jwks" => "invalid jwks", "无效的 jwks";
+};
+
+#[tauri::command]
+pub fn locale() -> Locale {
+    current()
+}
+
+#[tauri::command]
+pub fn set_locale(locale: Locale) -> Result<()> {
+    info!("locale changed to {:?}", locale);
+    *LOCALE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = locale;
+    Ok(())
+}
+
+pub(crate) fn current() -> Locale {
+    *LOCALE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub(crate) fn code_of(message: &str) -> Option<&'static str> {
+    let head = message.split_once(", ").map_or(message, |(head, _)| head);
+    CATALOG
+        .iter()
+        .find(|entry| entry.en == head)
+        .map(|entry| entry.code)
+}
+
+/// The message of `code` in `locale`, with `{0}` filled in by `detail`.
+pub(crate) fn render(
+    code: &str,
+    locale: Locale,
+    detail: &str,
+) -> Option<String> {
+    CATALOG
+        .iter()
+        .find(|entry| entry.code == code)
+        .map(|entry| {
+            match locale {
+                Locale::En => entry.en,
+                Locale::Zh => entry.zh,
+            }
+            .replace("{0}", detail)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use anyhow::anyhow;
+
+    use super::{code_of, render, Locale, CATALOG};
+    use crate::errors::Error;
+
+    #[test]
+    fn test_catalog() {
+        let codes = CATALOG.iter().map(|entry| entry.code);
+        assert_eq!(codes.collect::<HashSet<_>>().len(), CATALOG.len());
+        let texts = CATALOG.iter().map(|entry| entry.en);
+        assert_eq!(texts.collect::<HashSet<_>>().len(), CATALOG.len());
+
+        assert_eq!(
+            code_of("base64 decode failed, unppaded: false, urlsafety: true"),
+            Some("codec.base64")
+        );
+        assert_eq!(
+            code_of("invalid sec1 pem private key"),
+            Some("sec1.pem.private")
+        );
+        assert_eq!(
+            code_of("invalid pkcs8 pem private key"),
+            Some("pkcs8.pem.private")
+        );
+        assert_eq!(
+            render("unsupported", Locale::Zh, "md4").unwrap(),
+            "不支持 `md4`"
+        );
+
+        let unsupported = Error::Unsupported("md4".to_string());
+        assert_eq!(unsupported.localized(Locale::En), unsupported.to_string());
+        let internal = Error::Internal(
+            anyhow!("bad tag").context("informal ecc sec1 pem private key"),
+        );
+        assert_eq!(internal.code(), "ecc.sec1.pem.private");
+        assert_eq!(internal.localized(Locale::Zh), "无效的 ecc sec1 pem 私钥");
+        let unknown = Error::Internal(anyhow!("something else"));
+        assert_eq!(unknown.code(), "internal");
+        assert_eq!(unknown.localized(Locale::Zh), "something else");
+    }
+}
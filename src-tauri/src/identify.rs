@@ -0,0 +1,644 @@
+//! Names what a pasted blob most likely is, with the commands to carry on
+//! with.
+
+use std::collections::HashSet;
+
+use base64ct::{Base64UrlUnpadded, Encoding};
+use coset::{
+    CborSerializable, CoseEncrypt0, CoseKey, CoseSign1, TaggedCborSerializable,
+};
+use rsa::traits::PublicKeyParts;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    audit::{
+        compare, entropy,
+        key::{
+            self, AuditedKey, SECP256K1, SECP256R1, SECP384R1, SECP521R1, SM2,
+        },
+    },
+    codec::{base64_decode, pem::sniff_label},
+    crypto::ecies,
+    enums::TextEncoding,
+    share, ssh, vault,
+    x509::{self, read_all, read_tlv},
+};
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Hypothesis {
+    /// stable kebab-case name of what the input would be
+    pub kind: &'static str,
+    /// 0 to 100
+    pub confidence: u8,
+    pub detail: String,
+    pub next_steps: Vec<&'static str>,
+}
+
+impl Hypothesis {
+    fn new(
+        kind: &'static str,
+        confidence: u8,
+        detail: impl Into<String>,
+        next_steps: &[&'static str],
+    ) -> Self {
+        Hypothesis {
+            kind,
+            confidence,
+            detail: detail.into(),
+            next_steps: next_steps.to_vec(),
+        }
+    }
+}
+
+/// Never fails, input nothing recognises gets an `unknown` guess.
+#[tauri::command]
+pub fn identify(input: String) -> Vec<Hypothesis> {
+    let input = input.trim();
+    let mut hypotheses = vec![];
+    from_text(input, &mut hypotheses);
+    for (encoding, bytes) in decodings(input) {
+        let confidence = match encoding {
+            "hex" => 50,
+            _ => 40,
+        };
+        hypotheses.push(Hypothesis::new(
+            encoding,
+            confidence,
+            format!("{} bytes of {}", bytes.len(), encoding),
+            &["convert_encoding"],
+        ));
+        from_bytes(&bytes, encoding, &mut hypotheses);
+    }
+    if hypotheses.is_empty() {
+        hypotheses.push(Hypothesis::new(
+            "unknown",
+            0,
+            "no parser recognises the input",
+            &["analyze_entropy"],
+        ));
+    }
+    let hypotheses = ranked(hypotheses);
+    info!(
+        "identify {} characters, best guess: {}",
+        input.len(),
+        hypotheses[0].kind
+    );
+    hypotheses
+}
+
+fn ranked(mut hypotheses: Vec<Hypothesis>) -> Vec<Hypothesis> {
+    hypotheses.sort_by(|a, b| b.confidence.cmp(&a.confidence));
+    let mut seen = HashSet::new();
+    hypotheses.retain(|hypothesis| seen.insert(hypothesis.kind));
+    hypotheses
+}
+
+fn from_text(input: &str, hypotheses: &mut Vec<Hypothesis>) {
+    if input.is_empty() {
+        return;
+    }
+    if let Some(link) = input.strip_prefix(share::PREFIX) {
+        hypotheses.push(Hypothesis::new(
+            "share-link",
+            99,
+            format!("kits share link of {} characters", link.len()),
+            &["open_share"],
+        ));
+        return;
+    }
+    if input.starts_with("-----BEGIN ") {
+        match pem_rfc7468::decode_vec(input.as_bytes()) {
+            Ok((label, der)) => from_der(label, &der, "pem", hypotheses),
+            Err(_) => hypotheses.push(Hypothesis::new(
+                "pem",
+                60,
+                "malformed pem, wrapped or labelled unlike RFC 7468",
+                &["normalize_pem"],
+            )),
+        }
+        return;
+    }
+    if input
+        .split_whitespace()
+        .next()
+        .is_some_and(ssh::is_key_type)
+    {
+        if let Ok((_, key)) = compare::read(input, TextEncoding::Base64) {
+            hypotheses.push(Hypothesis::new(
+                "ssh-public-key",
+                95,
+                format!("openssh {}", describe_key(&key)),
+                &["compare_keys", "analyze_authorized_keys"],
+            ));
+            return;
+        }
+    }
+    if input.starts_with('{') {
+        if let Ok(json) = serde_json::from_str::<Value>(input) {
+            hypotheses.push(from_json(&json, input));
+        }
+        return;
+    }
+    from_compact(input, hypotheses);
+}
+
+fn from_json(json: &Value, input: &str) -> Hypothesis {
+    let has = |name: &str| !json[name].is_null();
+    if json["keys"].is_array() {
+        let count = json["keys"].as_array().map_or(0, Vec::len);
+        return Hypothesis::new(
+            "jwks",
+            95,
+            format!("json web key set of {} keys", count),
+            &["verify_jwt", "rotate_jwks"],
+        );
+    }
+    if has("kty") {
+        let detail = match compare::read(input, TextEncoding::Base64) {
+            Ok((_, key)) => describe_key(&key),
+            Err(_) => format!("{} key", json["kty"].as_str().unwrap_or("?")),
+        };
+        return Hypothesis::new(
+            "jwk",
+            95,
+            format!("json web key, {}", detail),
+            &["audit_key", "compare_keys", "jwk_to_cose_key", "keybridge"],
+        );
+    }
+    if has("primaryKeyId") && json["key"].is_array() {
+        return Hypothesis::new(
+            "tink-keyset",
+            95,
+            "tink cleartext json keyset",
+            &["keybridge"],
+        );
+    }
+    if has("protected") && has("ciphertext") {
+        return Hypothesis::new("jwe", 85, "jwe in json serialization", &[
+            "decrypt_jwe",
+        ]);
+    }
+    if has("payload") && (has("signature") || has("signatures")) {
+        return Hypothesis::new("jws", 85, "jws in json serialization", &[
+            "verify_jwt",
+        ]);
+    }
+    Hypothesis::new("json", 50, "json document", &["canonicalize_json"])
+}
+
+fn from_compact(input: &str, hypotheses: &mut Vec<Hypothesis>) {
+    let segments = input.split('.').collect::<Vec<_>>();
+    if !matches!(segments.len(), 3 | 5) {
+        return;
+    }
+    let Some(header) = Base64UrlUnpadded::decode_vec(segments[0])
+        .ok()
+        .and_then(|header| serde_json::from_slice::<Value>(&header).ok())
+        .filter(|header| header["alg"].is_string())
+    else {
+        return;
+    };
+    let alg = header["alg"].as_str().unwrap_or_default();
+    if segments.len() == 5 {
+        hypotheses.push(Hypothesis::new(
+            "jwe",
+            98,
+            format!(
+                "compact jwe, alg {}, enc {}",
+                alg,
+                header["enc"].as_str().unwrap_or("?")
+            ),
+            &["decrypt_jwe"],
+        ));
+        return;
+    }
+    let payload = Base64UrlUnpadded::decode_vec(segments[1]).ok();
+    match payload {
+        Some(payload) if payload.is_empty() => {
+            hypotheses.push(Hypothesis::new(
+                "jws",
+                90,
+                format!("compact jws with a detached payload, alg {}", alg),
+                &["verify_jwt"],
+            ))
+        }
+        Some(payload)
+            if serde_json::from_slice::<Value>(&payload)
+                .is_ok_and(|payload| payload.is_object()) =>
+        {
+            hypotheses.push(Hypothesis::new(
+                "jwt",
+                98,
+                format!("json web token, alg {}", alg),
+                &["verify_jwt", "audit_jwt"],
+            ))
+        }
+        _ => hypotheses.push(Hypothesis::new(
+            "jws",
+            90,
+            format!("compact jws, alg {}", alg),
+            &["verify_jwt"],
+        )),
+    }
+}
+
+/// Hex digits are base64 too, such input is tried as both.
+fn decodings(input: &str) -> Vec<(&'static str, Vec<u8>)> {
+    let compact = input.split_whitespace().collect::<String>();
+    let mut decodings = vec![];
+    if compact.len() % 2 == 0
+        && compact.bytes().all(|byte| byte.is_ascii_hexdigit())
+        && let Ok(bytes) = base16ct::mixed::decode_vec(&compact)
+    {
+        decodings.push(("hex", bytes));
+    }
+    let unpadded = !compact.ends_with('=');
+    let urlsafe = compact.contains(['-', '_']);
+    if let Ok(bytes) = base64_decode(&compact, unpadded, urlsafe) {
+        decodings.push((if urlsafe { "base64url" } else { "base64" }, bytes));
+    }
+    decodings.retain(|(_, bytes)| !bytes.is_empty());
+    decodings
+}
+
+fn from_bytes(bytes: &[u8], encoding: &str, hypotheses: &mut Vec<Hypothesis>) {
+    let found = hypotheses.len();
+    if bytes.starts_with(ecies::MAGIC) && bytes.len() > ecies::MAGIC.len() {
+        hypotheses.push(Hypothesis::new(
+            "ecies-ciphertext",
+            90,
+            format!(
+                "kits ecies ciphertext, header version {}, as {}",
+                bytes[ecies::MAGIC.len()],
+                encoding
+            ),
+            &["ecies", "ecies_edwards"],
+        ));
+    }
+    if bytes.starts_with(share::MAGIC) {
+        hypotheses.push(Hypothesis::new(
+            "share-link",
+            95,
+            format!("kits share link without its prefix, as {}", encoding),
+            &["open_share"],
+        ));
+    }
+    if bytes.starts_with(vault::backup::MAGIC) {
+        hypotheses.push(Hypothesis::new(
+            "vault-backup",
+            95,
+            format!("kits vault backup, as {}", encoding),
+            &["import_vault"],
+        ));
+    }
+    if let Some(label) = sniff_label(bytes) {
+        from_der(label, bytes, encoding, hypotheses);
+    }
+    from_cbor(bytes, encoding, hypotheses);
+    if hypotheses.len() == found {
+        from_size(bytes, encoding, hypotheses);
+    }
+}
+
+fn from_der(
+    label: &str,
+    der: &[u8],
+    encoding: &str,
+    hypotheses: &mut Vec<Hypothesis>,
+) {
+    if label == "RSA PUBLIC KEY"
+        && let Some(curves) = ecdsa_signature(der)
+    {
+        hypotheses.push(Hypothesis::new(
+            "ecdsa-signature",
+            85,
+            format!("der ecdsa signature on {}, as {}", curves, encoding),
+            &["inspect_signature", "convert_signature", "verify_message"],
+        ));
+        return;
+    }
+    let hypothesis = match label {
+        "CERTIFICATE" => {
+            let name = x509::subject_common_name(der).ok().flatten();
+            Hypothesis::new(
+                "certificate",
+                95,
+                format!(
+                    "x.509 certificate of {}, as {}",
+                    name.as_deref().unwrap_or("an unnamed subject"),
+                    encoding
+                ),
+                &["verify_chain", "diff_certs", "parse_sct"],
+            )
+        }
+        "CERTIFICATE REQUEST" | "NEW CERTIFICATE REQUEST" => Hypothesis::new(
+            "csr",
+            95,
+            format!("pkcs#10 certificate request, as {}", encoding),
+            &["normalize_pem"],
+        ),
+        "X509 CRL" => Hypothesis::new(
+            "crl",
+            90,
+            format!("certificate revocation list, as {}", encoding),
+            &["normalize_pem"],
+        ),
+        "ENCRYPTED PRIVATE KEY" => Hypothesis::new(
+            "encrypted-private-key",
+            95,
+            format!("pkcs#8 encrypted private key, as {}", encoding),
+            &["normalize_pem"],
+        ),
+        "PKCS7" => Hypothesis::new(
+            "pkcs7",
+            90,
+            format!("pkcs#7 content info, as {}", encoding),
+            &["normalize_pem"],
+        ),
+        "PRIVATE KEY" | "PUBLIC KEY" | "RSA PRIVATE KEY" | "RSA PUBLIC KEY"
+        | "EC PRIVATE KEY" => match key::parse_der(der.to_vec()) {
+            AuditedKey::Symmetric(_) => Hypothesis::new(
+                "key",
+                40,
+                format!("{} shaped der no key parser accepts", label),
+                &["audit_key"],
+            ),
+            key => {
+                let private = matches!(
+                    &key,
+                    AuditedKey::RsaPrivate(_)
+                        | AuditedKey::Ec {
+                            secret: Some(_),
+                            ..
+                        }
+                        | AuditedKey::Edwards {
+                            secret: Some(_),
+                            ..
+                        }
+                );
+                Hypothesis::new(
+                    if private { "private-key" } else { "public-key" },
+                    95,
+                    format!("{}, as {}", describe_key(&key), encoding),
+                    if private {
+                        &["audit_key", "compare_keys", "sign_message"]
+                    } else {
+                        &["audit_key", "compare_keys", "verify_message"]
+                    },
+                )
+            }
+        },
+        label => Hypothesis::new(
+            "pem",
+            60,
+            format!("{} block, as {}", label, encoding),
+            &["normalize_pem"],
+        ),
+    };
+    hypotheses.push(hypothesis);
+}
+
+/// Rsa public keys have the same shape but a small exponent.
+fn ecdsa_signature(der: &[u8]) -> Option<&'static str> {
+    let (sequence, _) = read_tlv(der).ok()?;
+    let [r, s] = &read_all(sequence.content).ok()?[..] else {
+        return None;
+    };
+    match r.content.len().max(s.content.len()) {
+        9 ..= 33 => Some("P-256, secp256k1 or SM2"),
+        34 ..= 49 => Some("P-384"),
+        50 ..= 67 => Some("P-521"),
+        _ => None,
+    }
+    .filter(|_| s.content.len() > 8)
+}
+
+fn from_cbor(bytes: &[u8], encoding: &str, hypotheses: &mut Vec<Hypothesis>) {
+    if CoseSign1::from_tagged_slice(bytes).is_ok() {
+        hypotheses.push(Hypothesis::new(
+            "cose-sign1",
+            95,
+            format!("tagged COSE_Sign1, as {}", encoding),
+            &["cose_verify1"],
+        ));
+    } else if CoseSign1::from_slice(bytes).is_ok() {
+        hypotheses.push(Hypothesis::new(
+            "cose-sign1",
+            70,
+            format!("untagged COSE_Sign1, as {}", encoding),
+            &["cose_verify1"],
+        ));
+    }
+    if CoseEncrypt0::from_tagged_slice(bytes).is_ok() {
+        hypotheses.push(Hypothesis::new(
+            "cose-encrypt0",
+            95,
+            format!("tagged COSE_Encrypt0, as {}", encoding),
+            &["cose_encrypt0"],
+        ));
+    }
+    // the cwt tag around a cose message
+    if bytes.starts_with(&[0xd8, 0x3d]) {
+        hypotheses.push(Hypothesis::new(
+            "cwt",
+            85,
+            format!("cbor web token, as {}", encoding),
+            &["cose_verify1", "cwt_decode"],
+        ));
+    }
+    if CoseKey::from_slice(bytes).is_ok() {
+        hypotheses.push(Hypothesis::new(
+            "cose-key",
+            70,
+            format!("COSE_Key, as {}", encoding),
+            &["cose_key_to_jwk"],
+        ));
+    }
+}
+
+fn from_size(bytes: &[u8], encoding: &str, hypotheses: &mut Vec<Hypothesis>) {
+    let mut guess = |kind, confidence, detail: &str, next: &[&'static str]| {
+        hypotheses.push(Hypothesis::new(
+            kind,
+            confidence,
+            format!("{}, {} bytes of {}", detail, bytes.len(), encoding),
+            next,
+        ))
+    };
+    match (bytes.len(), bytes[0]) {
+        (33, 0x02 | 0x03) => {
+            guess("ec-point", 60, "compressed ec point", &["compare_keys"])
+        }
+        (65, 0x04) => guess("ec-point", 70, "uncompressed P-256 point", &[
+            "compare_keys",
+        ]),
+        (97, 0x04) => guess("ec-point", 70, "uncompressed P-384 point", &[
+            "compare_keys",
+        ]),
+        _ => {}
+    }
+    match bytes.len() {
+        16 => guess("symmetric-key", 30, "AES-128 key, iv or MD5 digest", &[
+            "audit_key",
+        ]),
+        20 => guess("digest", 35, "SHA-1 digest", &["verify_digest"]),
+        32 => {
+            guess("digest", 30, "SHA-256 digest", &["verify_digest"]);
+            guess("symmetric-key", 30, "256 bits key or curve 25519 key", &[
+                "audit_key",
+                "keybridge",
+            ]);
+        }
+        44 | 60 => guess(
+            "wrapped-data-key",
+            25,
+            "nonce, AES-GCM wrapped data key and tag",
+            &["decrypt_with_wrapped_key"],
+        ),
+        48 => guess("digest", 30, "SHA-384 digest", &["verify_digest"]),
+        64 => {
+            guess("signature", 35, "Ed25519 signature or raw P-256 r || s", &[
+                "verify_message",
+                "inspect_signature",
+            ]);
+            guess("digest", 30, "SHA-512 digest", &["verify_digest"]);
+        }
+        96 | 132 => guess("signature", 35, "raw P-384 or P-521 r || s", &[
+            "verify_message",
+            "inspect_signature",
+        ]),
+        256 | 384 | 512 => guess(
+            "rsa-output",
+            35,
+            "rsa signature or ciphertext of a modulus this size",
+            &["verify_message", "crypto_rsa"],
+        ),
+        _ => {}
+    }
+    // k || x || y, the hex shamir shares are written in
+    if encoding == "hex" && bytes.len() > 6 && bytes[0] >= 2 && bytes[1] >= 1 {
+        guess("shamir-share", 20, "shamir share", &["combine_shares"]);
+    }
+    let mut histogram = vec![0u64; 256];
+    bytes.iter().for_each(|byte| histogram[*byte as usize] += 1);
+    let shannon = entropy::shannon(&histogram, bytes.len());
+    let random = shannon >= 0.9 * (bytes.len().min(256) as f64).log2();
+    if random && bytes.len() >= 32 {
+        if bytes.len() % 16 == 0 {
+            guess(
+                "ciphertext",
+                20,
+                "random looking, a block cipher ciphertext",
+                &["crypto_aes", "analyze_entropy"],
+            );
+        } else {
+            guess(
+                "ciphertext",
+                15,
+                "random looking, a stream or aead ciphertext",
+                &["crypto_aes", "crypto_chacha20_poly1305"],
+            );
+        }
+    } else if std::str::from_utf8(bytes).is_ok_and(|text| {
+        text.chars().all(|c| !c.is_control() || c.is_whitespace())
+    }) {
+        guess("text", 30, "printable text", &["convert_encoding"]);
+    }
+}
+
+fn describe_key(key: &AuditedKey) -> String {
+    let curve = |curve: &Option<const_oid::ObjectIdentifier>| match curve {
+        Some(SECP256R1) => "P-256".to_string(),
+        Some(SECP384R1) => "P-384".to_string(),
+        Some(SECP521R1) => "P-521".to_string(),
+        Some(SECP256K1) => "secp256k1".to_string(),
+        Some(SM2) => "SM2".to_string(),
+        Some(oid) => oid.to_string(),
+        None => "explicit curve".to_string(),
+    };
+    let half = |private: bool| if private { "private" } else { "public" };
+    match key {
+        AuditedKey::RsaPrivate(key) => {
+            format!("{} bits rsa private key", key.n().bits())
+        }
+        AuditedKey::RsaPublic(key) => {
+            format!("{} bits rsa public key", key.n().bits())
+        }
+        AuditedKey::Ec {
+            curve: oid, secret, ..
+        } => {
+            format!("ec {} {} key", curve(oid), half(secret.is_some()))
+        }
+        AuditedKey::Edwards { x25519, secret, .. } => format!(
+            "{} {} key",
+            if *x25519 { "x25519" } else { "ed25519" },
+            half(secret.is_some())
+        ),
+        AuditedKey::Symmetric(key) => {
+            format!("{} bits symmetric key", key.len() * 8)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::identify;
+
+    fn best(input: &str) -> &'static str {
+        identify(input.to_string())[0].kind
+    }
+
+    #[test]
+    fn test_identify_text() {
+        assert_eq!(
+            best(concat!(
+                "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.",
+                "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+            )),
+            "jwt"
+        );
+        assert_eq!(best(r#"{"kty":"oct","k":"c2VjcmV0"}"#), "jwk");
+        assert_eq!(best(r#"{"keys":[]}"#), "jwks");
+        assert_eq!(best("kits+share:AAAA"), "share-link");
+        assert_eq!(
+            best(
+                "-----BEGIN PRIVATE \
+                 KEY-----\nMC4CAQAwBQYDK2VwBCIEIJ1hsZ3v/\
+                 VpguoRK9JLsLMREScVpezJpGXA7rAMcrn9g\n-----END PRIVATE \
+                 KEY-----\n"
+            ),
+            "private-key"
+        );
+        assert_eq!(best("not a blob!"), "unknown");
+    }
+
+    #[test]
+    fn test_identify_bytes() {
+        // the ed25519 pkcs#8 key above in hex
+        let hypotheses = identify(
+            "302e020100300506032b657004220420\
+             9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+                .to_string(),
+        );
+        assert_eq!(hypotheses[0].kind, "private-key");
+        assert!(hypotheses[0].detail.contains("ed25519"));
+        assert!(hypotheses.iter().any(|hypothesis| hypothesis.kind == "hex"));
+
+        let signature = identify(
+            "3044022001020304050607080910111213141516171819202122232425262728\
+             2930313202200102030405060708091011121314151617181920212223242526\
+             272829303132"
+                .to_string(),
+        );
+        assert_eq!(signature[0].kind, "ecdsa-signature");
+
+        let digest = identify(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                .to_string(),
+        );
+        assert!(digest.iter().any(|hypothesis| hypothesis.kind == "digest"));
+    }
+}
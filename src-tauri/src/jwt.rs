@@ -1,10 +1,17 @@
 use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use strum_macros::EnumIter;
 
+pub mod discovery;
+pub mod fixture;
+#[cfg(feature = "gm")]
+pub mod gm;
+pub mod jcs;
 pub mod jwe;
 pub mod jwk;
+pub mod jwks;
 pub mod jws;
 
 #[derive(
@@ -105,8 +112,19 @@ pub enum JwkeyAlgorithm {
     EcdhEsA192kw,
     #[serde(rename = "ECDH-ES+A256KW")]
     EcdhEsA256kw,
+
+    /// SM2 over SM3, signed by the `SM2` signature provider
+    SM2,
+    /// SM4 in gcm mode, encrypted by the `SM4-GCM` cipher provider
+    #[serde(rename = "SM4-GCM")]
+    Sm4Gcm,
 }
 
+/// Provider names of the gm profile, registered with the `gm` feature or by
+/// a plugin.
+pub(crate) const SM2_PROVIDER: &str = "SM2";
+pub(crate) const SM4_GCM_PROVIDER: &str = "SM4-GCM";
+
 #[derive(
     Serialize,
     Deserialize,
@@ -160,3 +178,61 @@ pub enum JwkeyOperation {
     DeriveKey,
     DeriveBits,
 }
+
+/// How `generate_jwk` names a key given no literal `key_id`.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum KidStrategy {
+    /// RFC 7638 sha-256 thumbprint, the same for the same key
+    #[default]
+    Thumbprint,
+    /// random version 4 uuid
+    Uuid,
+    /// unix time in milliseconds
+    Timestamp,
+    /// `kid_prefix` followed by a random id
+    Prefix,
+}
+
+/// Curves ECDH-ES agrees on.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum JwkeyCurve {
+    #[serde(rename = "P-256")]
+    P256,
+    #[serde(rename = "P-384")]
+    P384,
+    #[serde(rename = "P-521")]
+    P521,
+    X25519,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedJose {
+    pub token: String,
+    /// the private jwk
+    pub jwk: Option<Value>,
+}
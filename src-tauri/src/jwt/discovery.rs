@@ -0,0 +1,153 @@
+use std::{
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
+};
+
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    errors::{Error, Result},
+    fetch, vault,
+};
+
+const WELL_KNOWN: &str = "/.well-known/openid-configuration";
+/// Seconds a fetched key set is used without asking the server again.
+const CACHE_TTL: u64 = 600;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcDiscovery {
+    pub issuer: String,
+    pub jwks_uri: String,
+    pub configuration: Value,
+    pub keys: Vec<Value>,
+}
+
+struct CachedJwks {
+    fetched_at: u64,
+    keys: Vec<Value>,
+}
+
+fn cache() -> &'static RwLock<BTreeMap<String, CachedJwks>> {
+    static CACHE: OnceLock<RwLock<BTreeMap<String, CachedJwks>>> =
+        OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+/// `issuer` may also be the configuration url itself.
+#[tauri::command]
+pub async fn oidc_discovery(issuer: String) -> Result<OidcDiscovery> {
+    info!("oidc discovery: {}", issuer);
+    let (issuer, url) = discovery_url(&issuer);
+    let configuration: Value =
+        serde_json::from_slice(&fetch::get(&url, true).await?)
+            .context("invalid openid configuration")?;
+    // OpenID Connect Discovery 1.0 section 4.3
+    if configuration["issuer"].as_str() != Some(issuer.as_str()) {
+        return Err(Error::Unsupported(format!(
+            "configuration of {} is issued by {}",
+            issuer, configuration["issuer"]
+        )));
+    }
+    let jwks_uri = configuration["jwks_uri"]
+        .as_str()
+        .ok_or_else(|| {
+            Error::Unsupported("configuration without jwks_uri".to_string())
+        })?
+        .to_string();
+    let keys = refresh(&jwks_uri).await?;
+    Ok(OidcDiscovery {
+        issuer,
+        jwks_uri,
+        configuration,
+        keys,
+    })
+}
+
+#[tauri::command]
+pub async fn fetch_jwks(
+    jwks_uri: String,
+    refresh: Option<bool>,
+) -> Result<Vec<Value>> {
+    info!("fetch jwks: {}, refresh: {:?}", jwks_uri, refresh);
+    if refresh.unwrap_or(false) {
+        return self::refresh(&jwks_uri).await;
+    }
+    jwks_keys(&jwks_uri, None).await
+}
+
+/// Fetched again when stale or when `kid` is missing, the issuer may just
+/// have rotated.
+pub(crate) async fn jwks_keys(
+    jwks_uri: &str,
+    kid: Option<&str>,
+) -> Result<Vec<Value>> {
+    let cached = cache()
+        .read()
+        .map_err(|_| anyhow!("jwks cache lock poisoned"))?
+        .get(jwks_uri)
+        .filter(|cached| cached.fetched_at + CACHE_TTL > vault::now())
+        .map(|cached| cached.keys.clone());
+    match cached {
+        Some(keys)
+            if kid.map_or(true, |kid| {
+                keys.iter().any(|key| key["kid"].as_str() == Some(kid))
+            }) =>
+        {
+            Ok(keys)
+        }
+        _ => refresh(jwks_uri).await,
+    }
+}
+
+async fn refresh(jwks_uri: &str) -> Result<Vec<Value>> {
+    let jwks: Value =
+        serde_json::from_slice(&fetch::get(jwks_uri, true).await?)
+            .context("invalid jwks")?;
+    let keys = jwks["keys"]
+        .as_array()
+        .cloned()
+        .ok_or(Error::Unsupported("jwks without keys".to_string()))?;
+    cache()
+        .write()
+        .map_err(|_| anyhow!("jwks cache lock poisoned"))?
+        .insert(jwks_uri.to_string(), CachedJwks {
+            fetched_at: vault::now(),
+            keys: keys.clone(),
+        });
+    Ok(keys)
+}
+
+fn discovery_url(issuer: &str) -> (String, String) {
+    let issuer = issuer.trim();
+    let issuer = issuer.strip_suffix(WELL_KNOWN).unwrap_or(issuer);
+    let url = format!("{}{}", issuer.trim_end_matches('/'), WELL_KNOWN);
+    (issuer.to_string(), url)
+}
+
+#[cfg(test)]
+mod test {
+    use super::discovery_url;
+
+    #[test]
+    fn test_discovery_url() {
+        let url =
+            "https://accounts.example.com/.well-known/openid-configuration";
+        for issuer in [
+            "https://accounts.example.com",
+            "https://accounts.example.com/",
+        ] {
+            assert_eq!(
+                discovery_url(issuer),
+                (issuer.to_string(), url.to_string())
+            );
+        }
+        assert_eq!(
+            discovery_url(url),
+            ("https://accounts.example.com".to_string(), url.to_string())
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Well-known keys published with the specifications, for reproducing their
+//! examples. They protect nothing and are marked as such.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use strum_macros::EnumIter;
+
+use crate::errors::Result;
+
+const WARNING: &str = "well-known test key, never protect anything with it";
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    EnumIter,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum FixtureKind {
+    /// RFC 7515 appendix A.1, the HS256 key
+    Rfc7515Hs256,
+    /// RFC 7515 appendix A.2, the RS256 key
+    Rfc7515Rs256,
+    /// RFC 7515 appendix A.4, the ES512 key
+    Rfc7515Es512,
+    /// RFC 7516 appendix A.3, the A128KW key
+    Rfc7516A128kw,
+    /// RFC 6979 appendix A.2.5, the P-256 sample key
+    Rfc6979P256,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestFixture {
+    pub kind: FixtureKind,
+    /// where the key is published
+    pub source: &'static str,
+    pub warning: &'static str,
+    pub jwk: Value,
+}
+
+#[tauri::command]
+pub fn generate_test_fixture(kind: FixtureKind) -> Result<TestFixture> {
+    let (source, kid, mut jwk) = match kind {
+        FixtureKind::Rfc7515Hs256 => (
+            "RFC 7515 appendix A.1",
+            "rfc7515-a.1",
+            json!({
+                "kty": "oct",
+                "k": concat!(
+                    "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKt",
+                    "MN3Yj0iPS4hcgUuTwjAzZr1Z9CAow",
+                ),
+            }),
+        ),
+        FixtureKind::Rfc7515Rs256 => (
+            "RFC 7515 appendix A.2",
+            "rfc7515-a.2",
+            json!({
+                "kty": "RSA",
+                "n": concat!(
+                    "ofgWCuLjybRlzo0tZWJjNiuSfb4p4fAkd_wWJcyQoTbji9k0l8W26mPdd",
+                    "xHmfHQp-Vaw-4qPCJrcS2mJPMEzP1Pt0Bm4d4QlL-yRT-SFd2lZS-pCgN",
+                    "MsD1W_YpRPEwOWvG6b32690r2jZ47soMZo9wGzjb_7OMg0LOL-bSf63kp",
+                    "aSHSXndS5z5rexMdbBYUsLA9e-KXBdQOS-UTo7WTBEMa2R2CapHg665xs",
+                    "mtdVMTBQY4uDZlxvb3qCo5ZwKh9kG4LT6_I5IhlJH7aGhyxXFvUK-DWNm",
+                    "oudF8NAco9_h9iaGNj8q2ethFkMLs91kzk2PAcDTW9gb54h4FRWyuXpoQ",
+                ),
+                "e": "AQAB",
+                "d": concat!(
+                    "Eq5xpGnNCivDflJsRQBXHx1hdR1k6Ulwe2JZD50LpXyWPEAeP88vLNO97",
+                    "IjlA7_GQ5sLKMgvfTeXZx9SE-7YwVol2NXOoAJe46sui395IW_GO-pWJ1",
+                    "O0BkTGoVEn2bKVRUCgu-GjBVaYLU6f3l9kJfFNS3E0QbVdxzubSu3Mkqz",
+                    "jkn439X0M_V51gfpRLI9JYanrC4D4qAdGcopV_0ZHHzQlBjudU2QvXt4e",
+                    "hNYTCBr6XCLQUShb1juUO1ZdiYoFaFQT5Tw8bGUl_x_jTj3ccPDVZFD9p",
+                    "IuhLhBOneufuBiB4cS98l2SR_RQyGWSeWjnczT0QU91p1DhOVRuOopznQ",
+                ),
+                "p": concat!(
+                    "4BzEEOtIpmVdVEZNCqS7baC4crd0pqnRH_5IB3jw3bcxGn6QLvnEtfdUd",
+                    "iYrqBdss1l58BQ3KhooKeQTa9AB0Hw_Py5PJdTJNPY8cQn7ouZ2KKDcmn",
+                    "PGBY5t7yLc1QlQ5xHdwW1VhvKn-nXqhJTBgIPgtldC-KDV5z-y2XDwGUc",
+                ),
+                "q": concat!(
+                    "uQPEfgmVtjL0Uyyx88GZFF1fOunH3-7cepKmtH4pxhtCoHqpWmT8YAmZx",
+                    "aewHgHAjLYsp1ZSe7zFYHj7C6ul7TjeLQeZD_YwD66t62wDmpe_HlB-Tn",
+                    "BA-njbglfIsRLtXlnDzQkv5dTltRJ11BKBBypeeF6689rjcJIDEz9RWdc",
+                ),
+                "dp": concat!(
+                    "BwKfV3Akq5_MFZDFZCnW-wzl-CCo83WoZvnLQwCTeDv8uzluRSnm71I3Q",
+                    "CLdhrqE2e9YkxvuxdBfpT_PI7Yz-FOKnu1R6HsJeDCjn12Sk3vmAktV2z",
+                    "b34MCdy7cpdTh_YVr7tss2u6vneTwrA86rZtu5Mbr1C1XsmvkxHQAdYo0",
+                ),
+                "dq": concat!(
+                    "h_96-mK1R_7glhsum81dZxjTnYynPbZpHziZjeeHcXYsXaaMwkOlODsWa",
+                    "7I9xXDoRwbKgB719rrmI2oKr6N3Do9U0ajaHF-NKJnwgjMd2w9cjz3_-k",
+                    "yNlxAr2v4IKhGNpmM5iIgOS1VZnOZ68m6_pbLBSp3nssTdlqvd0tIiTHU",
+                ),
+                "qi": concat!(
+                    "IYd7DHOhrWvxkwPQsRM2tOgrjbcrfvtQJipd-DlcxyVuuM9sQLdgjVk2o",
+                    "y26F0EmpScGLq2MowX7fhd_QJQ3ydy5cY7YIBi87w93IKLEdfnbJtoOPL",
+                    "UW0ITrJReOgo1cq9SbsxYawBgfp_gh6A5603k2-ZQwVK0JKSHuLFkuQ3U",
+                ),
+            }),
+        ),
+        FixtureKind::Rfc7515Es512 => (
+            "RFC 7515 appendix A.4",
+            "rfc7515-a.4",
+            json!({
+                "kty": "EC",
+                "crv": "P-521",
+                "x": concat!(
+                    "AekpBQ8ST8a8VcfVOTNl353vSrDCLLJXmPk06wTjxrrjcBpXp5EOnYG_N",
+                    "jFZ6OvLFV1jSfS9tsz4qUxcWceqwQGk",
+                ),
+                "y": concat!(
+                    "ADSmRA43Z1DSNx_RvcLI87cdL07l6jQyyBXMoxVg_l2Th-x3S1WDhjDly",
+                    "79ajL4Kkd0AZMaZmh9ubmf63e3kyMj2",
+                ),
+                "d": concat!(
+                    "AY5pb7A0UFiB3RELSD64fTLOSV_jazdF7fLYyuTw8lOfRhWg6Y6rUrPAx",
+                    "erEzgdRhajnu0ferB0d53vM9mE15j2C",
+                ),
+            }),
+        ),
+        FixtureKind::Rfc7516A128kw => (
+            "RFC 7516 appendix A.3",
+            "rfc7516-a.3",
+            json!({
+                "kty": "oct",
+                "k": "GawgguFyGrWKav7AX4VKUg",
+            }),
+        ),
+        FixtureKind::Rfc6979P256 => (
+            "RFC 6979 appendix A.2.5",
+            "rfc6979-a.2.5",
+            json!({
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "YP7UuiVanTHJYet0xjVtaMBJuJI7Yfps5mliLmDyn7Y",
+                "y": "eQP-EAi4vJmkGunpVii8ZPLxsgwtfp9Rd6PClNRGIpk",
+                "d": "ya-p2EW6dRZrXCFXZ7HWk05Qw9s26JsSe4piKxIPZyE",
+            }),
+        ),
+    };
+    jwk["kid"] = Value::String(format!("insecure-test-{}", kid));
+    Ok(TestFixture {
+        kind,
+        source,
+        warning: WARNING,
+        jwk,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use elliptic_curve::{
+        sec1::{FromEncodedPoint, ModulusSize, ToEncodedPoint},
+        AffinePoint, CurveArithmetic, FieldBytesSize, JwkEcKey, JwkParameters,
+    };
+    use strum::IntoEnumIterator;
+
+    use super::{generate_test_fixture, FixtureKind};
+    use crate::jwt::{
+        jws::{rsa_key, sign_jws},
+        JwkeyAlgorithm,
+    };
+
+    #[test]
+    fn test_rfc7515_hs256() {
+        let fixture = generate_test_fixture(FixtureKind::Rfc7515Hs256).unwrap();
+        let signing_input = concat!(
+            "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.",
+            "eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFt",
+            "cGxlLmNvbS9pc19yb290Ijp0cnVlfQ",
+        );
+        let signature = sign_jws(
+            JwkeyAlgorithm::HS256,
+            &fixture.jwk,
+            signing_input.as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            Base64UrlUnpadded::encode_string(&signature),
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+        );
+    }
+
+    #[test]
+    fn test_fixtures_are_consistent() {
+        for kind in FixtureKind::iter() {
+            let fixture = generate_test_fixture(kind).unwrap();
+            assert!(fixture.jwk["kid"]
+                .as_str()
+                .unwrap()
+                .starts_with("insecure-test-"));
+            match fixture.jwk["crv"].as_str() {
+                Some("P-256") => check_ec::<p256::NistP256>(&fixture.jwk),
+                Some("P-521") => check_ec::<p521::NistP521>(&fixture.jwk),
+                _ if fixture.jwk["kty"] == "RSA" => {
+                    rsa_key(&fixture.jwk).unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn check_ec<C>(jwk: &serde_json::Value)
+    where
+        C: CurveArithmetic + JwkParameters,
+        AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+        FieldBytesSize<C>: ModulusSize,
+    {
+        let key: JwkEcKey = serde_json::from_value(jwk.clone()).unwrap();
+        let derived = key.to_secret_key::<C>().unwrap().public_key();
+        assert_eq!(derived, key.to_public_key::<C>().unwrap());
+    }
+}
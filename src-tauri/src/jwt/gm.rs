@@ -0,0 +1,214 @@
+//! Providers of the Chinese jose profile. A plugin may register its own in
+//! their place.
+
+use std::sync::Arc;
+
+use aes_gcm::{
+    aead::{consts::U12, AeadInPlace, KeyInit},
+    AesGcm, Nonce,
+};
+use anyhow::Context;
+use pkcs8::{DecodePrivateKey, DecodePublicKey};
+use sm2::dsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+
+use super::{SM2_PROVIDER, SM4_GCM_PROVIDER};
+use crate::{
+    errors::{Error, Result},
+    provider::{CipherParams, CipherProvider, Registry, SignatureProvider},
+};
+
+/// Default distinguishing identifier of GB/T 32918.
+const DIST_ID: &str = "1234567812345678";
+const IV_SIZE: usize = 12;
+
+type Sm4Gcm = AesGcm<sm4::Sm4, U12>;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_signature(Arc::new(Sm2Provider));
+    registry.register_cipher(Arc::new(Sm4GcmProvider));
+}
+
+/// Pkcs8 der private keys, spki der public keys, r || s signatures.
+struct Sm2Provider;
+
+impl SignatureProvider for Sm2Provider {
+    fn name(&self) -> String {
+        SM2_PROVIDER.to_string()
+    }
+
+    fn sign(&self, key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+        let secret_key = sm2::SecretKey::from_pkcs8_der(key)
+            .context("invalid sm2 pkcs8 der private key")?;
+        let signing_key = SigningKey::new(DIST_ID, &secret_key)
+            .context("invalid sm2 private key")?;
+        let signature: Signature = signing_key.sign(message);
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    fn verify(
+        &self,
+        key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool> {
+        let public_key = sm2::PublicKey::from_public_key_der(key)
+            .context("invalid sm2 spki der public key")?;
+        let verifying_key = VerifyingKey::new(DIST_ID, public_key)
+            .context("invalid sm2 public key")?;
+        Ok(Signature::try_from(signature).is_ok_and(|signature| {
+            verifying_key.verify(message, &signature).is_ok()
+        }))
+    }
+}
+
+/// 12 bytes iv, the tag appended.
+struct Sm4GcmProvider;
+
+impl Sm4GcmProvider {
+    fn cipher(key: &[u8], params: &CipherParams) -> Result<Sm4Gcm> {
+        match &params.iv {
+            Some(iv) if iv.len() == IV_SIZE => {}
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "sm4 gcm requires a {} bytes iv",
+                    IV_SIZE
+                )))
+            }
+        }
+        Ok(Sm4Gcm::new_from_slice(key)
+            .context("construct sm4_gcm_cipher failed")?)
+    }
+}
+
+impl CipherProvider for Sm4GcmProvider {
+    fn name(&self) -> String {
+        SM4_GCM_PROVIDER.to_string()
+    }
+
+    fn encrypt(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+    ) -> Result<Vec<u8>> {
+        let cipher = Self::cipher(key, params)?;
+        let mut buffer = input.to_vec();
+        cipher
+            .encrypt_in_place(
+                Nonce::from_slice(params.iv.as_deref().unwrap_or_default()),
+                params.aad.as_deref().unwrap_or_default(),
+                &mut buffer,
+            )
+            .context("sm4 gcm encrypt failed")?;
+        Ok(buffer)
+    }
+
+    fn decrypt(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+    ) -> Result<Vec<u8>> {
+        let cipher = Self::cipher(key, params)?;
+        let mut buffer = input.to_vec();
+        cipher
+            .decrypt_in_place(
+                Nonce::from_slice(params.iv.as_deref().unwrap_or_default()),
+                params.aad.as_deref().unwrap_or_default(),
+                &mut buffer,
+            )
+            .context("sm4 gcm decrypt failed")?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+
+    use crate::{
+        jwt::{
+            jwe::{generate_jwe, GenerateJweDto},
+            jws::{generate_jws, sign_jws, verify_jws, GenerateJwsDto},
+            JwkeyAlgorithm,
+        },
+        provider::{self, CipherParams},
+    };
+
+    #[tokio::test]
+    async fn test_sm2_jws() {
+        let generated = generate_jws(GenerateJwsDto {
+            payload: "payload".to_string(),
+            jwk: None,
+            algorithm: Some(JwkeyAlgorithm::SM2),
+            key_id: None,
+            task_id: None,
+        })
+        .await
+        .unwrap();
+        let jwk = generated.jwk.unwrap();
+        assert_eq!(jwk["crv"], "SM2");
+        let (signing_input, signature) =
+            generated.token.rsplit_once('.').unwrap();
+        let signature = Base64UrlUnpadded::decode_vec(signature).unwrap();
+        assert_eq!(signature.len(), 64);
+        assert!(verify_jws(
+            JwkeyAlgorithm::SM2,
+            &jwk,
+            signing_input.as_bytes(),
+            &signature
+        )
+        .unwrap());
+        let tampered =
+            sign_jws(JwkeyAlgorithm::SM2, &jwk, b"tampered").unwrap();
+        assert!(!verify_jws(
+            JwkeyAlgorithm::SM2,
+            &jwk,
+            signing_input.as_bytes(),
+            &tampered
+        )
+        .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_sm4_gcm_jwe() {
+        let generated = generate_jwe(GenerateJweDto {
+            plaintext: "plaintext".to_string(),
+            jwk: None,
+            algorithm: None,
+            encryption: JwkeyAlgorithm::Sm4Gcm,
+            curve: None,
+            party_u_info: None,
+            party_v_info: None,
+            key_id: None,
+            task_id: None,
+        })
+        .await
+        .unwrap();
+        let segments = generated.token.split('.').collect::<Vec<_>>();
+        let decoded = segments
+            .iter()
+            .map(|segment| Base64UrlUnpadded::decode_vec(segment).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(decoded[0], br#"{"alg":"RSA-OAEP-256","enc":"SM4-GCM"}"#);
+        let cek = crate::jwt::jws::rsa_key(&generated.jwk.unwrap())
+            .unwrap()
+            .decrypt(rsa::Oaep::new::<sha2::Sha256>(), &decoded[1])
+            .unwrap();
+        let plaintext = provider::cipher("SM4-GCM")
+            .unwrap()
+            .decrypt(
+                &cek,
+                &[decoded[3].clone(), decoded[4].clone()].concat(),
+                &CipherParams {
+                    iv: Some(decoded[2].clone()),
+                    aad: Some(segments[0].as_bytes().to_vec()),
+                },
+            )
+            .unwrap();
+        assert_eq!(plaintext, b"plaintext");
+    }
+}
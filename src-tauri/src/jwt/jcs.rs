@@ -0,0 +1,154 @@
+use anyhow::Context;
+use serde_json::{Number, Value};
+
+use crate::errors::Result;
+
+#[tauri::command]
+pub fn canonicalize_json(input: String) -> Result<String> {
+    let value: Value =
+        serde_json::from_str(&input).context("invalid json input")?;
+    canonicalize(&value)
+}
+
+/// RFC 8785.
+pub(crate) fn canonicalize(value: &Value) -> Result<String> {
+    let mut output = String::new();
+    write_value(&mut output, value)?;
+    Ok(output)
+}
+
+fn write_value(output: &mut String, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => output.push_str("null"),
+        Value::Bool(value) => {
+            output.push_str(if *value { "true" } else { "false" })
+        }
+        Value::Number(number) => output.push_str(&format_number(number)?),
+        Value::String(value) => output.push_str(
+            &serde_json::to_string(value).context("serialize string failed")?,
+        ),
+        Value::Array(values) => {
+            output.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_value(output, value)?;
+            }
+            output.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by_cached_key(|(key, _)| {
+                key.encode_utf16().collect::<Vec<u16>>()
+            });
+            output.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                output.push_str(
+                    &serde_json::to_string(key)
+                        .context("serialize key failed")?,
+                );
+                output.push(':');
+                write_value(output, value)?;
+            }
+            output.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn format_number(number: &Number) -> Result<String> {
+    let value = number
+        .as_f64()
+        .context(format!("number {} is not an ieee 754 double", number))?;
+    if !value.is_finite() {
+        return Err(anyhow::anyhow!("number {} is not finite", number).into());
+    }
+    if value == 0.0 {
+        return Ok("0".to_string());
+    }
+    // `{:e}` yields the shortest round-trip digits, which is what
+    // Number.prototype.toString starts from as well
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .context("unexpected float format")?;
+    let digits = mantissa.replace('.', "");
+    let exponent = exponent
+        .parse::<i32>()
+        .context("unexpected float exponent")?;
+    let k = digits.len() as i32;
+    let n = exponent + 1;
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[.. n as usize], &digits[n as usize ..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let (head, tail) = digits.split_at(1);
+        if tail.is_empty() {
+            format!("{}e{}{}", head, sign, (n - 1).abs())
+        } else {
+            format!("{}.{}e{}{}", head, tail, sign, (n - 1).abs())
+        }
+    };
+    Ok(if value < 0.0 {
+        format!("-{}", body)
+    } else {
+        body
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::canonicalize;
+
+    // RFC 8785 section 3.2.2 and 3.2.3 samples
+    #[test]
+    fn test_canonicalize() {
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "€$\u000F\u000aA'B\"\\\\\"\/",
+                "literals": [null, true, false],
+                "€": "Euro Sign",
+                "\r": "Carriage Return",
+                "דּ": "Hebrew Letter Dalet With Dagesh",
+                "1": "One",
+                "😀": "Emoji: Grinning Face",
+                "\u0080": "Control",
+                "ö": "Latin Small Letter O With Diaeresis"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            canonicalize(&value).unwrap(),
+            concat!(
+                r#"{"\r":"Carriage Return","1":"One","#,
+                r#""literals":[null,true,false],"#,
+                r#""numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"#,
+                r#""string":"€$\u000f\nA'B\"\\\\\"/","#,
+                "\"\u{0080}\":\"Control\",",
+                "\"\u{00f6}\":\"Latin Small Letter O With Diaeresis\",",
+                "\"\u{20ac}\":\"Euro Sign\",",
+                "\"\u{1f600}\":\"Emoji: Grinning Face\",",
+                "\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+            )
+        );
+        let value: Value = serde_json::from_str(
+            "[-0.0, 1e21, 1e-7, 123e-9, 9007199254740993]",
+        )
+        .unwrap();
+        assert_eq!(
+            canonicalize(&value).unwrap(),
+            "[0,1e+21,1e-7,1.23e-7,9007199254740992]"
+        );
+    }
+}
@@ -1,6 +1,796 @@
-use crate::errors::Result;
+use aes::{
+    cipher::{
+        consts::U16, generic_array::GenericArray, BlockDecrypt, BlockEncrypt,
+        BlockSizeUser,
+    },
+    Aes128, Aes192, Aes256,
+};
+use aes_gcm::{
+    aead::{AeadInPlace, KeyInit},
+    Aes128Gcm, Aes256Gcm, AesGcm, Nonce,
+};
+use anyhow::{anyhow, Context};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use elliptic_curve::{
+    ecdh::{diffie_hellman, EphemeralSecret},
+    sec1::{FromEncodedPoint, ModulusSize, ToEncodedPoint},
+    AffinePoint, CurveArithmetic, FieldBytesSize, JwkEcKey, JwkParameters,
+};
+use rand::RngCore;
+use rsa::{Oaep, Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+use zeroize::Zeroizing;
 
+use super::{
+    jcs,
+    jwk::{generate_ecdh_jwk, generate_jwk_key, is_ecdh_es},
+    jws::{jwk_field, rsa_key, rsa_public_key},
+    GeneratedJose, JwkeyAlgorithm, JwkeyCurve, JwkeyUsage, SM4_GCM_PROVIDER,
+};
+use crate::{
+    errors::{Error, Result},
+    pool,
+    provider::{self, CipherParams},
+    rng, vault,
+};
+
+const IV_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+/// Initial value of RFC 3394 key wrap.
+const KW_IV: [u8; 8] = [0xA6; 8];
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateJweDto {
+    pub plaintext: String,
+    /// a key pair is generated when not given
+    pub jwk: Option<String>,
+    /// the one of the jwk unless given
+    pub algorithm: Option<JwkeyAlgorithm>,
+    pub encryption: JwkeyAlgorithm,
+    /// X25519 unless given
+    pub curve: Option<JwkeyCurve>,
+    /// sent as `apu` and `apv`
+    pub party_u_info: Option<String>,
+    pub party_v_info: Option<String>,
+    pub key_id: Option<String>,
+    /// names the call for `cancel_task`
+    pub task_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecryptJweDto {
+    pub token: String,
+    pub jwk: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecryptedJwe {
+    pub header: Value,
+    pub plaintext: String,
+}
+
+/// Key generation and wrapping run on the crypto pool.
 #[tauri::command]
-pub(crate) fn generate_jwe() -> Result<String> {
-    Ok("".to_string())
+pub async fn generate_jwe(data: GenerateJweDto) -> Result<GeneratedJose> {
+    let jwk = data
+        .jwk
+        .as_deref()
+        .map(serde_json::from_str::<Value>)
+        .transpose()
+        .context("invalid jwk")?;
+    let algorithm = match (data.algorithm, &jwk) {
+        (Some(algorithm), _) => algorithm,
+        (None, Some(jwk)) => serde_json::from_value(jwk["alg"].clone())
+            .context("algorithm is required when jwk has no alg")?,
+        (None, None) => JwkeyAlgorithm::RsaOaep256,
+    };
+    let encryption = data.encryption;
+    cek_size(encryption)?;
+    info!(
+        "generate jwe, algorithm: {:?}, encryption: {:?}, generated key: {}",
+        algorithm,
+        encryption,
+        jwk.is_none()
+    );
+    if let Some(given) = &data.jwk {
+        vault::enforce_policy(
+            given.as_bytes(),
+            JwkeyUsage::Encryption,
+            &algorithm_name(algorithm),
+        )?;
+    }
+    let mut header = json!({ "alg": algorithm, "enc": encryption });
+    if let Some(apu) = &data.party_u_info {
+        header["apu"] = json!(Base64UrlUnpadded::encode_string(apu.as_bytes()));
+    }
+    if let Some(apv) = &data.party_v_info {
+        header["apv"] = json!(Base64UrlUnpadded::encode_string(apv.as_bytes()));
+    }
+    let (plaintext, key_id, curve) = (data.plaintext, data.key_id, data.curve);
+    pool::spawn_cancellable(data.task_id, move |cancel| {
+        let (jwk, generated) = match jwk {
+            Some(jwk) => (jwk, false),
+            None if is_ecdh_es(algorithm) => (
+                generate_ecdh_jwk(curve.unwrap_or(JwkeyCurve::X25519))?,
+                true,
+            ),
+            None => (generate_jwk_key(algorithm)?, true),
+        };
+        cancel.check()?;
+        if let Some(kid) = key_id.or(jwk["kid"].as_str().map(String::from)) {
+            header["kid"] = Value::String(kid);
+        }
+        let (cek, encrypted_key) =
+            encrypt_key(algorithm, encryption, &jwk, &mut header)?;
+        cancel.check()?;
+        let header = Base64UrlUnpadded::encode_string(
+            jcs::canonicalize(&header)?.as_bytes(),
+        );
+
+        let mut iv = [0u8; IV_SIZE];
+        rng::rng().fill_bytes(&mut iv);
+        let mut ciphertext = plaintext.into_bytes();
+        encrypt_content(
+            encryption,
+            &cek,
+            &iv,
+            header.as_bytes(),
+            &mut ciphertext,
+        )?;
+        let tag = ciphertext.split_off(ciphertext.len() - TAG_SIZE);
+        Ok(GeneratedJose {
+            token: [
+                header,
+                Base64UrlUnpadded::encode_string(&encrypted_key),
+                Base64UrlUnpadded::encode_string(&iv),
+                Base64UrlUnpadded::encode_string(&ciphertext),
+                Base64UrlUnpadded::encode_string(&tag),
+            ]
+            .join("."),
+            jwk: generated.then_some(jwk),
+        })
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn decrypt_jwe(data: DecryptJweDto) -> Result<DecryptedJwe> {
+    let jwk: Value = serde_json::from_str(&data.jwk).context("invalid jwk")?;
+    let segments = data.token.split('.').collect::<Vec<_>>();
+    let [protected, encrypted_key, iv, ciphertext, tag] = segments[..] else {
+        return Err(Error::Unsupported(
+            "jwe of other than 5 segments".to_string(),
+        ));
+    };
+    let decode = |segment: &str| {
+        Base64UrlUnpadded::decode_vec(segment)
+            .map_err(|_| anyhow!("jwe segment is not base64url"))
+    };
+    let header: Value = serde_json::from_slice(&decode(protected)?)
+        .context("invalid jwe header")?;
+    let algorithm: JwkeyAlgorithm =
+        serde_json::from_value(header["alg"].clone())
+            .context("invalid jwe header")?;
+    let encryption: JwkeyAlgorithm =
+        serde_json::from_value(header["enc"].clone())
+            .context("invalid jwe header")?;
+    info!(
+        "decrypt jwe, algorithm: {:?}, encryption: {:?}",
+        algorithm, encryption
+    );
+    vault::enforce_policy(
+        data.jwk.as_bytes(),
+        JwkeyUsage::Encryption,
+        &algorithm_name(algorithm),
+    )?;
+    let encrypted_key = decode(encrypted_key)?;
+    let iv = decode(iv)?;
+    let mut payload = decode(ciphertext)?;
+    payload.extend_from_slice(&decode(tag)?);
+    let aad = protected.as_bytes().to_vec();
+    pool::spawn(move || -> Result<DecryptedJwe> {
+        let cek =
+            decrypt_key(algorithm, encryption, &jwk, &header, &encrypted_key)?;
+        let plaintext =
+            decrypt_content(encryption, &cek, &iv, &aad, &mut payload)?;
+        Ok(DecryptedJwe {
+            header,
+            plaintext: String::from_utf8(plaintext)
+                .context("jwe plaintext is not utf-8")?,
+        })
+    })
+    .await?
+}
+
+fn cek_size(encryption: JwkeyAlgorithm) -> Result<usize> {
+    match encryption {
+        JwkeyAlgorithm::A128GCM => Ok(16),
+        JwkeyAlgorithm::A192GCM => Ok(24),
+        JwkeyAlgorithm::A256GCM => Ok(32),
+        JwkeyAlgorithm::Sm4Gcm => Ok(16),
+        encryption => Err(Error::Unsupported(format!(
+            "jwe content encryption {:?}",
+            encryption
+        ))),
+    }
+}
+
+fn algorithm_name(algorithm: JwkeyAlgorithm) -> String {
+    serde_json::to_value(algorithm)
+        .ok()
+        .and_then(|alg| alg.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+/// The encrypted key segment is empty for direct ECDH-ES, which puts its
+/// ephemeral key into `header`.
+fn encrypt_key(
+    algorithm: JwkeyAlgorithm,
+    encryption: JwkeyAlgorithm,
+    jwk: &Value,
+    header: &mut Value,
+) -> Result<(Zeroizing<Vec<u8>>, Vec<u8>)> {
+    let cek_size = cek_size(encryption)?;
+    if !is_ecdh_es(algorithm) {
+        let cek = random_key(cek_size);
+        let encrypted_key = wrap_key(algorithm, &rsa_public_key(jwk)?, &cek)?;
+        return Ok((cek, encrypted_key));
+    }
+    let (epk, shared) = match jwk["crv"].as_str() {
+        Some("P-256") => ephemeral_agreement::<p256::NistP256>(jwk)?,
+        Some("P-384") => ephemeral_agreement::<p384::NistP384>(jwk)?,
+        Some("P-521") => ephemeral_agreement::<p521::NistP521>(jwk)?,
+        Some("X25519") => ephemeral_x25519(jwk)?,
+        curve => {
+            return Err(Error::Unsupported(format!(
+                "ECDH-ES curve {:?}",
+                curve
+            )))
+        }
+    };
+    header["epk"] = epk;
+    match kek_size(algorithm) {
+        None => Ok((
+            agreed_key(&shared, encryption, header, cek_size)?,
+            Vec::new(),
+        )),
+        Some(kek_size) => {
+            let kek = agreed_key(&shared, algorithm, header, kek_size)?;
+            let cek = random_key(cek_size);
+            let encrypted_key = aes_key_wrap(&kek, &cek)?;
+            Ok((cek, encrypted_key))
+        }
+    }
+}
+
+fn decrypt_key(
+    algorithm: JwkeyAlgorithm,
+    encryption: JwkeyAlgorithm,
+    jwk: &Value,
+    header: &Value,
+    encrypted_key: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    let cek_size = cek_size(encryption)?;
+    let cek = if is_ecdh_es(algorithm) {
+        let epk = &header["epk"];
+        let shared = match jwk["crv"].as_str() {
+            Some("P-256") => static_agreement::<p256::NistP256>(jwk, epk)?,
+            Some("P-384") => static_agreement::<p384::NistP384>(jwk, epk)?,
+            Some("P-521") => static_agreement::<p521::NistP521>(jwk, epk)?,
+            Some("X25519") => static_x25519(jwk, epk)?,
+            curve => {
+                return Err(Error::Unsupported(format!(
+                    "ECDH-ES curve {:?}",
+                    curve
+                )))
+            }
+        };
+        match kek_size(algorithm) {
+            None if encrypted_key.is_empty() => {
+                agreed_key(&shared, encryption, header, cek_size)?
+            }
+            None => {
+                return Err(
+                    anyhow!("direct ECDH-ES jwe has an encrypted key").into()
+                )
+            }
+            Some(kek_size) => aes_key_unwrap(
+                &agreed_key(&shared, algorithm, header, kek_size)?,
+                encrypted_key,
+            )?,
+        }
+    } else {
+        unwrap_key(algorithm, &rsa_key(jwk)?, encrypted_key)?
+    };
+    if cek.len() != cek_size {
+        return Err(anyhow!("invalid jwe content encryption key").into());
+    }
+    Ok(cek)
+}
+
+fn random_key(size: usize) -> Zeroizing<Vec<u8>> {
+    let mut key = Zeroizing::new(vec![0u8; size]);
+    rng::rng().fill_bytes(&mut key);
+    key
+}
+
+fn kek_size(algorithm: JwkeyAlgorithm) -> Option<usize> {
+    match algorithm {
+        JwkeyAlgorithm::EcdhEsA128kw => Some(16),
+        JwkeyAlgorithm::EcdhEsA192kw => Some(24),
+        JwkeyAlgorithm::EcdhEsA256kw => Some(32),
+        _ => None,
+    }
+}
+
+/// Concat KDF of RFC 7518 section 4.6.2, `algorithm` is `enc` for direct
+/// agreement and `alg` for key wrapping.
+fn agreed_key(
+    shared: &[u8],
+    algorithm: JwkeyAlgorithm,
+    header: &Value,
+    key_size: usize,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let party_info = |name: &str| match header[name].as_str() {
+        Some(info) => Base64UrlUnpadded::decode_vec(info)
+            .map_err(|_| anyhow!("jwe header `{}` is not base64url", name)),
+        None => Ok(Vec::new()),
+    };
+    let mut other_info = Vec::new();
+    for field in [
+        algorithm_name(algorithm).into_bytes(),
+        party_info("apu")?,
+        party_info("apv")?,
+    ] {
+        other_info.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        other_info.extend_from_slice(&field);
+    }
+    other_info.extend_from_slice(&(key_size as u32 * 8).to_be_bytes());
+    let mut key = Zeroizing::new(vec![0u8; key_size]);
+    concat_kdf::derive_key_into::<sha2::Sha256>(shared, &other_info, &mut key)
+        .context("derive ECDH-ES key failed")?;
+    Ok(key)
+}
+
+fn ephemeral_agreement<C>(jwk: &Value) -> Result<(Value, Zeroizing<Vec<u8>>)>
+where
+    C: CurveArithmetic + JwkParameters,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let public_key = serde_json::from_value::<JwkEcKey>(jwk.clone())
+        .context("invalid ECDH-ES jwk")?
+        .to_public_key::<C>()
+        .context("invalid ECDH-ES public key")?;
+    let secret = EphemeralSecret::<C>::random(&mut rng::rng());
+    let shared = secret.diffie_hellman(&public_key);
+    let epk = serde_json::to_value(secret.public_key().to_jwk())
+        .context("serilize jwe epk failed")?;
+    Ok((epk, Zeroizing::new(shared.raw_secret_bytes().to_vec())))
+}
+
+fn static_agreement<C>(jwk: &Value, epk: &Value) -> Result<Zeroizing<Vec<u8>>>
+where
+    C: CurveArithmetic + JwkParameters,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+    FieldBytesSize<C>: ModulusSize,
+{
+    let secret_key = serde_json::from_value::<JwkEcKey>(jwk.clone())
+        .context("invalid ECDH-ES jwk")?
+        .to_secret_key::<C>()
+        .context("invalid ECDH-ES private key")?;
+    let public_key = serde_json::from_value::<JwkEcKey>(epk.clone())
+        .context("invalid jwe epk")?
+        .to_public_key::<C>()
+        .context("invalid jwe epk")?;
+    let shared =
+        diffie_hellman(secret_key.to_nonzero_scalar(), public_key.as_affine());
+    Ok(Zeroizing::new(shared.raw_secret_bytes().to_vec()))
+}
+
+fn x25519_public_key(jwk: &Value) -> Result<x25519_dalek::PublicKey> {
+    let public: [u8; 32] = jwk_field(jwk, "x")?
+        .try_into()
+        .map_err(|_| anyhow!("invalid x25519 public key"))?;
+    Ok(x25519_dalek::PublicKey::from(public))
+}
+
+fn x25519_shared(
+    shared: x25519_dalek::SharedSecret,
+) -> Result<Zeroizing<Vec<u8>>> {
+    if !shared.was_contributory() {
+        return Err(anyhow!("x25519 agreement is not contributory").into());
+    }
+    Ok(Zeroizing::new(shared.as_bytes().to_vec()))
+}
+
+fn ephemeral_x25519(jwk: &Value) -> Result<(Value, Zeroizing<Vec<u8>>)> {
+    let public_key = x25519_public_key(jwk)?;
+    let secret =
+        x25519_dalek::EphemeralSecret::random_from_rng(&mut rng::rng());
+    let epk = json!({
+        "kty": "OKP",
+        "crv": "X25519",
+        "x": Base64UrlUnpadded::encode_string(
+            x25519_dalek::PublicKey::from(&secret).as_bytes()
+        ),
+    });
+    Ok((epk, x25519_shared(secret.diffie_hellman(&public_key))?))
+}
+
+fn static_x25519(jwk: &Value, epk: &Value) -> Result<Zeroizing<Vec<u8>>> {
+    let secret: [u8; 32] = jwk_field(jwk, "d")?
+        .try_into()
+        .map_err(|_| anyhow!("invalid x25519 private key"))?;
+    x25519_shared(
+        x25519_dalek::StaticSecret::from(secret)
+            .diffie_hellman(&x25519_public_key(epk)?),
+    )
+}
+
+fn wrap_key(
+    algorithm: JwkeyAlgorithm,
+    public_key: &RsaPublicKey,
+    cek: &[u8],
+) -> Result<Vec<u8>> {
+    let mut rng = rng::rng();
+    Ok(match algorithm {
+        JwkeyAlgorithm::Rsa1_5 => {
+            public_key.encrypt(&mut rng, Pkcs1v15Encrypt, cek)
+        }
+        JwkeyAlgorithm::RsaOaep => {
+            public_key.encrypt(&mut rng, Oaep::new::<sha1::Sha1>(), cek)
+        }
+        JwkeyAlgorithm::RsaOaep256 => {
+            public_key.encrypt(&mut rng, Oaep::new::<sha2::Sha256>(), cek)
+        }
+        JwkeyAlgorithm::RsaOaep384 => {
+            public_key.encrypt(&mut rng, Oaep::new::<sha2::Sha384>(), cek)
+        }
+        JwkeyAlgorithm::RsaOaep521 => {
+            public_key.encrypt(&mut rng, Oaep::new::<sha2::Sha512>(), cek)
+        }
+        algorithm => {
+            return Err(Error::Unsupported(format!(
+                "jwe key management {:?}",
+                algorithm
+            )))
+        }
+    }
+    .context("wrap jwe content encryption key failed")?)
+}
+
+fn unwrap_key(
+    algorithm: JwkeyAlgorithm,
+    private_key: &RsaPrivateKey,
+    encrypted_key: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    Ok(Zeroizing::new(
+        match algorithm {
+            JwkeyAlgorithm::Rsa1_5 => {
+                private_key.decrypt(Pkcs1v15Encrypt, encrypted_key)
+            }
+            JwkeyAlgorithm::RsaOaep => {
+                private_key.decrypt(Oaep::new::<sha1::Sha1>(), encrypted_key)
+            }
+            JwkeyAlgorithm::RsaOaep256 => {
+                private_key.decrypt(Oaep::new::<sha2::Sha256>(), encrypted_key)
+            }
+            JwkeyAlgorithm::RsaOaep384 => {
+                private_key.decrypt(Oaep::new::<sha2::Sha384>(), encrypted_key)
+            }
+            JwkeyAlgorithm::RsaOaep521 => {
+                private_key.decrypt(Oaep::new::<sha2::Sha512>(), encrypted_key)
+            }
+            algorithm => {
+                return Err(Error::Unsupported(format!(
+                    "jwe key management {:?}",
+                    algorithm
+                )))
+            }
+        }
+        .context("unwrap jwe content encryption key failed")?,
+    ))
+}
+
+fn aes_key_wrap(kek: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    match kek.len() {
+        16 => key_wrap::<Aes128>(kek, key),
+        24 => key_wrap::<Aes192>(kek, key),
+        _ => key_wrap::<Aes256>(kek, key),
+    }
+}
+
+fn aes_key_unwrap(kek: &[u8], wrapped: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    match kek.len() {
+        16 => key_unwrap::<Aes128>(kek, wrapped),
+        24 => key_unwrap::<Aes192>(kek, wrapped),
+        _ => key_unwrap::<Aes256>(kek, wrapped),
+    }
+}
+
+/// RFC 3394 key wrap, `key` is a multiple of 8 bytes.
+fn key_wrap<C>(kek: &[u8], key: &[u8]) -> Result<Vec<u8>>
+where
+    C: BlockEncrypt + BlockSizeUser<BlockSize = U16> + KeyInit,
+{
+    let cipher =
+        C::new_from_slice(kek).context("construct aes_kw_cipher failed")?;
+    let mut a = KW_IV;
+    let mut r = key.chunks(8).map(|c| c.to_vec()).collect::<Vec<_>>();
+    let n = r.len();
+    for j in 0 .. 6 {
+        for (i, r) in r.iter_mut().enumerate() {
+            let mut block = GenericArray::<u8, U16>::default();
+            block[.. 8].copy_from_slice(&a);
+            block[8 ..].copy_from_slice(r);
+            cipher.encrypt_block(&mut block);
+            let t = ((n * j + i + 1) as u64).to_be_bytes();
+            a.iter_mut()
+                .zip(&block[.. 8])
+                .zip(t)
+                .for_each(|((a, b), t)| *a = b ^ t);
+            r.copy_from_slice(&block[8 ..]);
+        }
+    }
+    Ok([vec![a.to_vec()], r].concat().concat())
+}
+
+fn key_unwrap<C>(kek: &[u8], wrapped: &[u8]) -> Result<Zeroizing<Vec<u8>>>
+where
+    C: BlockDecrypt + BlockSizeUser<BlockSize = U16> + KeyInit,
+{
+    if wrapped.len() < 24 || wrapped.len() % 8 != 0 {
+        return Err(anyhow!("invalid aes wrapped key").into());
+    }
+    let cipher =
+        C::new_from_slice(kek).context("construct aes_kw_cipher failed")?;
+    let mut a: [u8; 8] = wrapped[.. 8].try_into().unwrap_or_default();
+    let mut r = wrapped[8 ..]
+        .chunks(8)
+        .map(|c| c.to_vec())
+        .collect::<Vec<_>>();
+    let n = r.len();
+    for j in (0 .. 6).rev() {
+        for (i, r) in r.iter_mut().enumerate().rev() {
+            let t = ((n * j + i + 1) as u64).to_be_bytes();
+            let mut block = GenericArray::<u8, U16>::default();
+            block[.. 8]
+                .iter_mut()
+                .zip(a.iter().zip(t))
+                .for_each(|(b, (a, t))| *b = a ^ t);
+            block[8 ..].copy_from_slice(r);
+            cipher.decrypt_block(&mut block);
+            a.copy_from_slice(&block[.. 8]);
+            r.copy_from_slice(&block[8 ..]);
+        }
+    }
+    if a != KW_IV {
+        return Err(anyhow!("aes key unwrap failed").into());
+    }
+    Ok(Zeroizing::new(r.concat()))
+}
+
+fn encrypt_content(
+    encryption: JwkeyAlgorithm,
+    cek: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    payload: &mut Vec<u8>,
+) -> Result<()> {
+    if encryption == JwkeyAlgorithm::Sm4Gcm {
+        *payload = provider::cipher(SM4_GCM_PROVIDER)?.encrypt(
+            cek,
+            payload,
+            &CipherParams {
+                iv: Some(iv.to_vec()),
+                aad: Some(aad.to_vec()),
+            },
+        )?;
+        return Ok(());
+    }
+    let nonce = Nonce::from_slice(iv);
+    match encryption {
+        JwkeyAlgorithm::A128GCM => Aes128Gcm::new_from_slice(cek)
+            .context("construct aes_gcm_cipher failed")?
+            .encrypt_in_place(nonce, aad, payload),
+        JwkeyAlgorithm::A192GCM => {
+            AesGcm::<Aes192, aes_gcm::aead::consts::U12>::new_from_slice(cek)
+                .context("construct aes_gcm_cipher failed")?
+                .encrypt_in_place(nonce, aad, payload)
+        }
+        _ => Aes256Gcm::new_from_slice(cek)
+            .context("construct aes_gcm_cipher failed")?
+            .encrypt_in_place(nonce, aad, payload),
+    }
+    .context("aes gcm encrypt failed")?;
+    Ok(())
+}
+
+fn decrypt_content(
+    encryption: JwkeyAlgorithm,
+    cek: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    payload: &mut Vec<u8>,
+) -> Result<Vec<u8>> {
+    if encryption == JwkeyAlgorithm::Sm4Gcm {
+        return provider::cipher(SM4_GCM_PROVIDER)?.decrypt(
+            cek,
+            payload,
+            &CipherParams {
+                iv: Some(iv.to_vec()),
+                aad: Some(aad.to_vec()),
+            },
+        );
+    }
+    if iv.len() != IV_SIZE {
+        return Err(anyhow!("invalid jwe iv").into());
+    }
+    let nonce = Nonce::from_slice(iv);
+    match encryption {
+        JwkeyAlgorithm::A128GCM => Aes128Gcm::new_from_slice(cek)
+            .context("construct aes_gcm_cipher failed")?
+            .decrypt_in_place(nonce, aad, payload),
+        JwkeyAlgorithm::A192GCM => {
+            AesGcm::<Aes192, aes_gcm::aead::consts::U12>::new_from_slice(cek)
+                .context("construct aes_gcm_cipher failed")?
+                .decrypt_in_place(nonce, aad, payload)
+        }
+        _ => Aes256Gcm::new_from_slice(cek)
+            .context("construct aes_gcm_cipher failed")?
+            .decrypt_in_place(nonce, aad, payload),
+    }
+    .context("aes gcm decrypt failed")?;
+    Ok(std::mem::take(payload))
+}
+
+#[cfg(test)]
+mod test {
+    use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use rsa::Oaep;
+    use serde_json::json;
+
+    use super::{
+        agreed_key, decrypt_jwe, generate_jwe, key_unwrap, key_wrap, rsa_key,
+        static_agreement, DecryptJweDto, GenerateJweDto,
+    };
+    use crate::jwt::{JwkeyAlgorithm, JwkeyCurve};
+
+    fn dto(
+        algorithm: JwkeyAlgorithm,
+        curve: Option<JwkeyCurve>,
+    ) -> GenerateJweDto {
+        GenerateJweDto {
+            plaintext: "plaintext".to_string(),
+            jwk: None,
+            algorithm: Some(algorithm),
+            encryption: JwkeyAlgorithm::A256GCM,
+            curve,
+            party_u_info: Some("Alice".to_string()),
+            party_v_info: Some("Bob".to_string()),
+            key_id: None,
+            task_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_jwe() {
+        let generated = generate_jwe(GenerateJweDto {
+            plaintext: "plaintext".to_string(),
+            jwk: None,
+            algorithm: None,
+            encryption: JwkeyAlgorithm::A256GCM,
+            curve: None,
+            party_u_info: None,
+            party_v_info: None,
+            key_id: Some("1".to_string()),
+            task_id: None,
+        })
+        .await
+        .unwrap();
+        let segments = generated
+            .token
+            .split('.')
+            .map(|segment| Base64UrlUnpadded::decode_vec(segment).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            segments[0],
+            br#"{"alg":"RSA-OAEP-256","enc":"A256GCM","kid":"1"}"#
+        );
+        let cek = rsa_key(&generated.jwk.unwrap())
+            .unwrap()
+            .decrypt(Oaep::new::<sha2::Sha256>(), &segments[1])
+            .unwrap();
+        let header = generated.token.split('.').next().unwrap();
+        let plaintext = Aes256Gcm::new_from_slice(&cek)
+            .unwrap()
+            .decrypt(Nonce::from_slice(&segments[2]), aes_gcm::aead::Payload {
+                msg: &[segments[3].clone(), segments[4].clone()].concat(),
+                aad: header.as_bytes(),
+            })
+            .unwrap();
+        assert_eq!(plaintext, b"plaintext");
+    }
+
+    #[tokio::test]
+    async fn test_ecdh_es_jwe() {
+        for (algorithm, curve) in [
+            (JwkeyAlgorithm::EcdhEs, JwkeyCurve::P256),
+            (JwkeyAlgorithm::EcdhEsA128kw, JwkeyCurve::P384),
+            (JwkeyAlgorithm::EcdhEsA192kw, JwkeyCurve::P521),
+            (JwkeyAlgorithm::EcdhEsA256kw, JwkeyCurve::X25519),
+            (JwkeyAlgorithm::RsaOaep, JwkeyCurve::X25519),
+        ] {
+            let generated =
+                generate_jwe(dto(algorithm, Some(curve))).await.unwrap();
+            let decrypted = decrypt_jwe(DecryptJweDto {
+                token: generated.token,
+                jwk: generated.jwk.unwrap().to_string(),
+            })
+            .await
+            .unwrap();
+            assert_eq!(decrypted.plaintext, "plaintext");
+            assert_eq!(decrypted.header["apu"], "QWxpY2U");
+        }
+    }
+
+    #[test]
+    fn test_aes_key_wrap() {
+        // RFC 3394 section 4.1
+        let kek =
+            base16ct::mixed::decode_vec("000102030405060708090A0B0C0D0E0F")
+                .unwrap();
+        let key =
+            base16ct::mixed::decode_vec("00112233445566778899AABBCCDDEEFF")
+                .unwrap();
+        let wrapped = key_wrap::<aes::Aes128>(&kek, &key).unwrap();
+        assert_eq!(
+            base16ct::upper::encode_string(&wrapped),
+            "1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5"
+        );
+        assert_eq!(*key_unwrap::<aes::Aes128>(&kek, &wrapped).unwrap(), key);
+        let mut tampered = wrapped.clone();
+        tampered[0] ^= 1;
+        assert!(key_unwrap::<aes::Aes128>(&kek, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_ecdh_es_agreement() {
+        // RFC 7518 appendix C
+        let bob = json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "weNJy2HscCSM6AEDTDg04biOvhFhyyWvOHQfeF_PxMQ",
+            "y": "e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck",
+            "d": "VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw"
+        });
+        let header = json!({
+            "alg": "ECDH-ES",
+            "enc": "A128GCM",
+            "apu": "QWxpY2U",
+            "apv": "Qm9i",
+            "epk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0",
+                "y": "SLW_xSffzlPWrHEVI30DHM_4egVwt3NQqeUD7nMFpps"
+            }
+        });
+        let shared =
+            static_agreement::<p256::NistP256>(&bob, &header["epk"]).unwrap();
+        assert_eq!(shared[.. 4], [158, 86, 217, 29]);
+        let key =
+            agreed_key(&shared, JwkeyAlgorithm::A128GCM, &header, 16).unwrap();
+        assert_eq!(
+            Base64UrlUnpadded::encode_string(&key),
+            "VqqN6vgjbSBcIijNcacQGg"
+        );
+    }
 }
@@ -1,11 +1,28 @@
-use anyhow::Context;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use elliptic_curve::sec1::ToEncodedPoint;
 use jose_jwk::OkpCurves;
+use rand::RngCore;
 use rsa::RsaPrivateKey;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
+use sha2::Digest;
+use tracing::warn;
+use zeroize::Zeroizing;
 
-use super::{JwkeyAlgorithm, JwkeyOperation, JwkeyType, JwkeyUsage};
-use crate::{enums::RsaKeySize, errors::Result, utils::random_bytes};
+use super::{
+    jcs, JwkeyAlgorithm, JwkeyCurve, JwkeyOperation, JwkeyType, JwkeyUsage,
+    KidStrategy,
+};
+use crate::{
+    codec::base64_decode,
+    enums::{RsaKeySize, TextEncoding},
+    errors::{Error, Result},
+    pool, rng,
+    utils::{jwkey_algorithm, random_id},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,62 +33,184 @@ pub struct JwkGenerate {
     pub usage: Option<JwkeyUsage>,
     pub operations: Option<Vec<JwkeyOperation>>,
     pub bits: Option<RsaKeySize>,
+    /// curve of ECDH-ES keys, P-256 for EcDSA and X25519 otherwise
+    pub curve: Option<JwkeyCurve>,
+    /// used when no `key_id` is given, a thumbprint unless given
+    pub kid_strategy: Option<KidStrategy>,
+    /// what the `Prefix` strategy puts before the random id
+    pub kid_prefix: Option<String>,
+    /// length of `oct` keys, the algorithm's unless given
+    pub key_bytes: Option<usize>,
 }
 #[tauri::command]
-pub(crate) async fn generate_jwk(data: JwkGenerate) -> Result<String> {
-    let mut value = generate_jwk_inner(
-        data.algorithm.unwrap_or(data.key_type.default_algorithm()),
-    )
-    .await?;
-    if let Some(key_id) = data.key_id {
-        value["kid"] = serde_json::Value::String(key_id);
+pub async fn generate_jwk(data: JwkGenerate) -> Result<String> {
+    let algorithm =
+        data.algorithm.unwrap_or(data.key_type.default_algorithm());
+    let curve = match (data.curve, data.key_type) {
+        (Some(curve), _) => curve,
+        (None, JwkeyType::EcDSA) => JwkeyCurve::P256,
+        (None, _) => JwkeyCurve::X25519,
+    };
+    let mut value = if is_ecdh_es(algorithm) {
+        pool::spawn(move || generate_ecdh_jwk(curve)).await??
+    } else if data.key_bytes.is_some() {
+        generate_oct_jwk(oct_key_bytes(algorithm, data.key_bytes)?)?
+    } else {
+        generate_jwk_inner(algorithm).await?
+    };
+    name_jwk(
+        &mut value,
+        data.key_id,
+        data.kid_strategy,
+        data.kid_prefix.as_deref(),
+    )?;
+    describe_jwk(&mut value, data.algorithm, data.usage, data.operations);
+
+    Ok(serde_json::to_string_pretty(&value)
+        .context("value to string failed")?)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OctJwkImport {
+    /// the existing secret, base64 in either alphabet, hex or utf8 text
+    pub secret: String,
+    pub encoding: TextEncoding,
+    pub key_id: Option<String>,
+    pub kid_strategy: Option<KidStrategy>,
+    pub kid_prefix: Option<String>,
+    pub algorithm: Option<JwkeyAlgorithm>,
+    pub usage: Option<JwkeyUsage>,
+    pub operations: Option<Vec<JwkeyOperation>>,
+}
+
+/// Wraps an existing secret, most often an hmac one, into an `oct` jwk.
+#[tauri::command]
+pub fn import_oct_jwk(data: OctJwkImport) -> Result<String> {
+    if let Some(algorithm) = data.algorithm
+        && !jwkey_algorithm(JwkeyType::Symmetric).contains(&algorithm)
+    {
+        return Err(Error::Unsupported(format!(
+            "oct jwk for {:?}",
+            algorithm
+        )));
+    }
+    let secret = Zeroizing::new(match data.encoding {
+        TextEncoding::Base64 => {
+            let secret = data.secret.trim();
+            base64_decode(
+                secret,
+                !secret.ends_with('='),
+                secret.contains(['-', '_']),
+            )?
+        }
+        encoding => encoding.decode(data.secret.trim())?,
+    });
+    if secret.is_empty() {
+        return Err(Error::Unsupported("empty oct secret".to_string()));
+    }
+    match data.algorithm {
+        // a secret already in use is kept as is, short or not
+        Some(
+            algorithm @ (JwkeyAlgorithm::HS256
+            | JwkeyAlgorithm::HS384
+            | JwkeyAlgorithm::HS512),
+        ) => {
+            let size = oct_key_bytes(algorithm, None)?;
+            if secret.len() < size {
+                warn!(
+                    "{} bytes secret for {:?}, shorter than its {} bytes hash",
+                    secret.len(),
+                    algorithm,
+                    size
+                );
+            }
+        }
+        Some(algorithm) => {
+            oct_key_bytes(algorithm, Some(secret.len()))?;
+        }
+        None => {}
     }
-    if let Some(alg) = data.algorithm {
+    let mut value = json!({
+        "kty": "oct",
+        "k": Base64UrlUnpadded::encode_string(&secret),
+    });
+    name_jwk(
+        &mut value,
+        data.key_id,
+        data.kid_strategy,
+        data.kid_prefix.as_deref(),
+    )?;
+    describe_jwk(&mut value, data.algorithm, data.usage, data.operations);
+
+    Ok(serde_json::to_string_pretty(&value)
+        .context("value to string failed")?)
+}
+
+fn name_jwk(
+    value: &mut Value,
+    key_id: Option<String>,
+    strategy: Option<KidStrategy>,
+    prefix: Option<&str>,
+) -> Result<()> {
+    let key_id = match key_id.filter(|key_id| !key_id.is_empty()) {
+        Some(key_id) => key_id,
+        None => generate_kid(value, strategy.unwrap_or_default(), prefix)?,
+    };
+    value["kid"] = Value::String(key_id);
+    Ok(())
+}
+
+fn describe_jwk(
+    value: &mut Value,
+    algorithm: Option<JwkeyAlgorithm>,
+    usage: Option<JwkeyUsage>,
+    operations: Option<Vec<JwkeyOperation>>,
+) {
+    if let Some(alg) = algorithm {
         value["alg"] = json!(alg);
     }
-    if let Some(ops) = data.operations
+    if let Some(ops) = operations
         && !ops.is_empty()
     {
         value["key_ops"] = json!(&ops);
     }
-    if let Some(usage) = data.usage {
-        value["use"] = serde_json::Value::String(usage.to_string())
+    if let Some(usage) = usage {
+        value["use"] = Value::String(usage.to_string())
     }
-
-    Ok(serde_json::to_string_pretty(&value)
-        .context("value to string failed")?)
 }
 
+/// Generates on the crypto pool, rsa keys take a while.
 pub(crate) async fn generate_jwk_inner(
     algorithm: crate::jwt::JwkeyAlgorithm,
 ) -> Result<serde_json::Value> {
-    let mut rng = rand::thread_rng();
+    pool::spawn(move || generate_jwk_key(algorithm)).await?
+}
+
+pub(crate) fn generate_jwk_key(
+    algorithm: crate::jwt::JwkeyAlgorithm,
+) -> Result<serde_json::Value> {
+    let mut rng = rng::rng();
 
     let key = match algorithm {
         JwkeyAlgorithm::Dir
         | JwkeyAlgorithm::HS256
+        | JwkeyAlgorithm::HS384
+        | JwkeyAlgorithm::HS512
         | JwkeyAlgorithm::A128GCM
         | JwkeyAlgorithm::A128GCMKW
         | JwkeyAlgorithm::A128KW
-        | JwkeyAlgorithm::A128cbcHs256 => {
-            let key = random_bytes(32)?;
-            jose_jwk::Key::Oct(jose_jwk::Oct { k: key.into() })
-        }
-        JwkeyAlgorithm::HS384
+        | JwkeyAlgorithm::A128cbcHs256
         | JwkeyAlgorithm::A192GCM
         | JwkeyAlgorithm::A192GCMKW
         | JwkeyAlgorithm::A192KW
-        | JwkeyAlgorithm::A192cbcHs384 => {
-            let key = random_bytes(48)?;
-            jose_jwk::Key::Oct(jose_jwk::Oct { k: key.into() })
-        }
-        JwkeyAlgorithm::HS512
+        | JwkeyAlgorithm::A192cbcHs384
         | JwkeyAlgorithm::A256GCM
         | JwkeyAlgorithm::A256GCMKW
         | JwkeyAlgorithm::A256KW
-        | JwkeyAlgorithm::A256cbcHs512 => {
-            let key = random_bytes(64)?;
-            jose_jwk::Key::Oct(jose_jwk::Oct { k: key.into() })
+        | JwkeyAlgorithm::A256cbcHs512
+        | JwkeyAlgorithm::Sm4Gcm => {
+            return generate_oct_jwk(oct_key_bytes(algorithm, None)?)
         }
         JwkeyAlgorithm::ES256 => {
             let secret_key =
@@ -93,6 +232,19 @@ pub(crate) async fn generate_jwk_inner(
                 elliptic_curve::SecretKey::<k256::Secp256k1>::random(&mut rng);
             jose_jwk::Key::Ec(jose_jwk::Ec::from(secret_key))
         }
+        JwkeyAlgorithm::SM2 => {
+            // jose_jwk has no curve for sm2
+            let secret_key = sm2::SecretKey::random(&mut rng);
+            let point = secret_key.public_key().to_encoded_point(false);
+            let encode = |bytes: &[u8]| Base64UrlUnpadded::encode_string(bytes);
+            return Ok(json!({
+                "kty": "EC",
+                "crv": "SM2",
+                "x": encode(point.x().context("sm2 point at infinity")?),
+                "y": encode(point.y().context("sm2 point at infinity")?),
+                "d": encode(&secret_key.to_bytes()),
+            }));
+        }
         JwkeyAlgorithm::RS256
         | JwkeyAlgorithm::PS256
         | JwkeyAlgorithm::RS384
@@ -123,6 +275,164 @@ pub(crate) async fn generate_jwk_inner(
         | JwkeyAlgorithm::EcdhEsA128kw
         | JwkeyAlgorithm::EcdhEsA192kw
         | JwkeyAlgorithm::EcdhEsA256kw => {
+            return generate_ecdh_jwk(JwkeyCurve::X25519)
+        }
+    };
+    Ok(serde_json::to_value(&key).context("serilize jwk failed")?)
+}
+
+/// RFC 7518 sizes aes keys exactly, hmac ones are the hash size at least.
+pub(crate) fn oct_key_size(algorithm: JwkeyAlgorithm) -> Option<usize> {
+    match algorithm {
+        JwkeyAlgorithm::A128KW
+        | JwkeyAlgorithm::A128GCM
+        | JwkeyAlgorithm::A128GCMKW
+        | JwkeyAlgorithm::Sm4Gcm => Some(16),
+        JwkeyAlgorithm::A192KW
+        | JwkeyAlgorithm::A192GCM
+        | JwkeyAlgorithm::A192GCMKW => Some(24),
+        JwkeyAlgorithm::Dir
+        | JwkeyAlgorithm::HS256
+        | JwkeyAlgorithm::A256KW
+        | JwkeyAlgorithm::A256GCM
+        | JwkeyAlgorithm::A256GCMKW
+        | JwkeyAlgorithm::A128cbcHs256 => Some(32),
+        JwkeyAlgorithm::HS384 | JwkeyAlgorithm::A192cbcHs384 => Some(48),
+        JwkeyAlgorithm::HS512 | JwkeyAlgorithm::A256cbcHs512 => Some(64),
+        _ => None,
+    }
+}
+
+/// Hmac keys may be longer and `dir` ones any size the `enc` takes.
+fn oct_key_bytes(
+    algorithm: JwkeyAlgorithm,
+    key_bytes: Option<usize>,
+) -> Result<usize> {
+    let size = oct_key_size(algorithm).ok_or(Error::Unsupported(format!(
+        "oct jwk for {:?}",
+        algorithm
+    )))?;
+    match key_bytes {
+        None => Ok(size),
+        Some(bytes) if bytes == size => Ok(bytes),
+        Some(bytes)
+            if bytes > size
+                && matches!(
+                    algorithm,
+                    JwkeyAlgorithm::HS256
+                        | JwkeyAlgorithm::HS384
+                        | JwkeyAlgorithm::HS512
+                ) =>
+        {
+            Ok(bytes)
+        }
+        Some(bytes) if bytes > 0 && algorithm == JwkeyAlgorithm::Dir => {
+            Ok(bytes)
+        }
+        Some(bytes) => Err(Error::Unsupported(format!(
+            "{} bytes oct key for {:?}",
+            bytes, algorithm
+        ))),
+    }
+}
+
+fn generate_oct_jwk(key_bytes: usize) -> Result<serde_json::Value> {
+    let mut key = Zeroizing::new(vec![0u8; key_bytes]);
+    rng::rng().fill_bytes(&mut key);
+    let key = jose_jwk::Key::Oct(jose_jwk::Oct {
+        k: key.to_vec().into(),
+    });
+    Ok(serde_json::to_value(&key).context("serilize jwk failed")?)
+}
+
+/// RFC 7638.
+pub(crate) fn thumbprint(jwk: &Value) -> Result<String> {
+    let members: &[&str] = match jwk["kty"].as_str() {
+        Some("EC") => &["crv", "kty", "x", "y"],
+        Some("RSA") => &["e", "kty", "n"],
+        Some("oct") => &["k", "kty"],
+        Some("OKP") => &["crv", "kty", "x"],
+        kty => {
+            return Err(Error::Unsupported(format!(
+                "thumbprint of kty {:?}",
+                kty
+            )))
+        }
+    };
+    let mut required = serde_json::Map::new();
+    for member in members {
+        let value = jwk
+            .get(*member)
+            .ok_or(anyhow!("jwk member `{}` is missing", member))?;
+        required.insert(member.to_string(), value.clone());
+    }
+    let canonical = jcs::canonicalize(&Value::Object(required))?;
+    Ok(Base64UrlUnpadded::encode_string(&sha2::Sha256::digest(
+        canonical.as_bytes(),
+    )))
+}
+
+fn generate_kid(
+    jwk: &Value,
+    strategy: KidStrategy,
+    prefix: Option<&str>,
+) -> Result<String> {
+    Ok(match strategy {
+        KidStrategy::Thumbprint => thumbprint(jwk)?,
+        KidStrategy::Uuid => {
+            let mut bytes = [0u8; 16];
+            rng::rng().fill_bytes(&mut bytes);
+            bytes[6] = (bytes[6] & 0x0f) | 0x40;
+            bytes[8] = (bytes[8] & 0x3f) | 0x80;
+            let hex = base16ct::lower::encode_string(&bytes);
+            format!(
+                "{}-{}-{}-{}-{}",
+                &hex[.. 8],
+                &hex[8 .. 12],
+                &hex[12 .. 16],
+                &hex[16 .. 20],
+                &hex[20 ..]
+            )
+        }
+        KidStrategy::Timestamp => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default()
+            .to_string(),
+        KidStrategy::Prefix => {
+            let prefix = prefix.ok_or(Error::Unsupported(
+                "kid prefix is required".to_string(),
+            ))?;
+            format!("{}{}", prefix, random_id()?)
+        }
+    })
+}
+
+pub(crate) fn is_ecdh_es(algorithm: JwkeyAlgorithm) -> bool {
+    matches!(
+        algorithm,
+        JwkeyAlgorithm::EcdhEs
+            | JwkeyAlgorithm::EcdhEsA128kw
+            | JwkeyAlgorithm::EcdhEsA192kw
+            | JwkeyAlgorithm::EcdhEsA256kw
+    )
+}
+
+pub(crate) fn generate_ecdh_jwk(
+    curve: JwkeyCurve,
+) -> Result<serde_json::Value> {
+    let mut rng = rng::rng();
+    let key = match curve {
+        JwkeyCurve::P256 => jose_jwk::Key::Ec(jose_jwk::Ec::from(
+            elliptic_curve::SecretKey::<p256::NistP256>::random(&mut rng),
+        )),
+        JwkeyCurve::P384 => jose_jwk::Key::Ec(jose_jwk::Ec::from(
+            elliptic_curve::SecretKey::<p384::NistP384>::random(&mut rng),
+        )),
+        JwkeyCurve::P521 => jose_jwk::Key::Ec(jose_jwk::Ec::from(
+            elliptic_curve::SecretKey::<p521::NistP521>::random(&mut rng),
+        )),
+        JwkeyCurve::X25519 => {
             let x25519_key =
                 x25519_dalek::StaticSecret::random_from_rng(&mut rng);
             let x25519_pub_key = x25519_dalek::PublicKey::from(&x25519_key);
@@ -138,21 +448,74 @@ pub(crate) async fn generate_jwk_inner(
 
 #[cfg(test)]
 mod test {
+    use base64ct::{Base64UrlUnpadded, Encoding};
     use num_bigint::BigInt;
+    use serde_json::json;
     use strum::IntoEnumIterator;
     use tracing::info;
     use tracing_test::traced_test;
 
-    use super::JwkeyAlgorithm;
+    use super::{generate_kid, oct_key_bytes, thumbprint, JwkeyAlgorithm};
     use crate::{
-        enums::RsaKeySize,
+        enums::{RsaKeySize, TextEncoding},
         jwt::{
-            jwk::{generate_jwk, JwkGenerate},
-            JwkeyOperation, JwkeyType,
+            jwk::{generate_jwk, import_oct_jwk, JwkGenerate, OctJwkImport},
+            JwkeyOperation, JwkeyType, JwkeyUsage, KidStrategy,
         },
         utils::random_bytes,
     };
 
+    fn oct_import(secret: &str, encoding: TextEncoding) -> OctJwkImport {
+        OctJwkImport {
+            secret: secret.to_string(),
+            encoding,
+            key_id: None,
+            kid_strategy: None,
+            kid_prefix: None,
+            algorithm: Some(JwkeyAlgorithm::A128GCM),
+            usage: Some(JwkeyUsage::Encryption),
+            operations: None,
+        }
+    }
+
+    #[test]
+    fn test_import_oct_jwk() {
+        let expected = "GawgguFyGrWKav7AX4VKUg";
+        for (secret, encoding) in [
+            ("GawgguFyGrWKav7AX4VKUg", TextEncoding::Base64),
+            ("GawgguFyGrWKav7AX4VKUg==", TextEncoding::Base64),
+            ("19ac2082e1721ab58a6afec05f854a52", TextEncoding::Hex),
+        ] {
+            let jwk: serde_json::Value = serde_json::from_str(
+                &import_oct_jwk(oct_import(secret, encoding)).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(jwk["k"], expected);
+            assert_eq!(jwk["alg"], "A128GCM");
+            assert_eq!(jwk["use"], "enc");
+            assert_eq!(
+                jwk["kid"],
+                "k1JnWRfC-5zzmL72vXIuBgTLfVROXBakS4OmGcrMCoc"
+            );
+        }
+        let mut rsa = oct_import("c2VjcmV0", TextEncoding::Base64);
+        rsa.algorithm = Some(JwkeyAlgorithm::RS256);
+        assert!(import_oct_jwk(rsa).is_err());
+        assert!(import_oct_jwk(oct_import("", TextEncoding::Utf8)).is_err());
+        // A128GCM takes exactly 16 bytes
+        assert!(import_oct_jwk(oct_import("c2VjcmV0", TextEncoding::Base64))
+            .is_err());
+        // a short hmac secret is only warned about
+        let mut hmac = oct_import("c2VjcmV0", TextEncoding::Base64);
+        hmac.algorithm = Some(JwkeyAlgorithm::HS256);
+        hmac.usage = Some(JwkeyUsage::Signature);
+        let jwk: serde_json::Value =
+            serde_json::from_str(&import_oct_jwk(hmac).unwrap()).unwrap();
+        assert_eq!(jwk["k"], "c2VjcmV0");
+        assert_eq!(jwk["alg"], "HS256");
+        assert_eq!(jwk["use"], "sig");
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_generate_jwk() {
@@ -176,6 +539,10 @@ mod test {
                         usage: None,
                         operations: Some(ops.clone()),
                         bits,
+                        curve: None,
+                        kid_strategy: None,
+                        kid_prefix: None,
+                        key_bytes: None,
                     })
                     .await
                     .unwrap()
@@ -183,6 +550,71 @@ mod test {
             }
         }
     }
+    #[tokio::test]
+    async fn test_oct_key_bytes() {
+        for (algorithm, size) in [
+            (JwkeyAlgorithm::A128KW, 16),
+            (JwkeyAlgorithm::A192GCM, 24),
+            (JwkeyAlgorithm::A256GCMKW, 32),
+            (JwkeyAlgorithm::HS384, 48),
+            (JwkeyAlgorithm::A256cbcHs512, 64),
+        ] {
+            let jwk: serde_json::Value = serde_json::from_str(
+                &generate_jwk(JwkGenerate {
+                    key_id: None,
+                    key_type: JwkeyType::Symmetric,
+                    algorithm: Some(algorithm),
+                    usage: None,
+                    operations: None,
+                    bits: None,
+                    curve: None,
+                    kid_strategy: None,
+                    kid_prefix: None,
+                    key_bytes: None,
+                })
+                .await
+                .unwrap(),
+            )
+            .unwrap();
+            let k =
+                Base64UrlUnpadded::decode_vec(jwk["k"].as_str().unwrap())
+                    .unwrap();
+            assert_eq!(k.len(), size);
+        }
+        assert_eq!(oct_key_bytes(JwkeyAlgorithm::HS256, Some(64)).unwrap(), 64);
+        assert_eq!(oct_key_bytes(JwkeyAlgorithm::Dir, Some(16)).unwrap(), 16);
+        assert!(oct_key_bytes(JwkeyAlgorithm::HS256, Some(16)).is_err());
+        assert!(oct_key_bytes(JwkeyAlgorithm::A128KW, Some(32)).is_err());
+        assert!(oct_key_bytes(JwkeyAlgorithm::RS256, None).is_err());
+    }
+
+    #[test]
+    fn test_thumbprint() {
+        let jwk = json!({ "kty": "oct", "k": "GawgguFyGrWKav7AX4VKUg" });
+        let expected = "k1JnWRfC-5zzmL72vXIuBgTLfVROXBakS4OmGcrMCoc";
+        assert_eq!(thumbprint(&jwk).unwrap(), expected);
+        let mut named = jwk.clone();
+        named["kid"] = json!("1");
+        named["alg"] = json!("A128GCM");
+        assert_eq!(thumbprint(&named).unwrap(), expected);
+        assert!(thumbprint(&json!({ "kty": "oct" })).is_err());
+    }
+
+    #[test]
+    fn test_kid_strategy() {
+        let jwk = json!({ "kty": "oct", "k": "GawgguFyGrWKav7AX4VKUg" });
+        let uuid = generate_kid(&jwk, KidStrategy::Uuid, None).unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14 .. 15], "4");
+        let timestamp =
+            generate_kid(&jwk, KidStrategy::Timestamp, None).unwrap();
+        assert!(timestamp.parse::<u128>().is_ok());
+        let prefixed =
+            generate_kid(&jwk, KidStrategy::Prefix, Some("kits-")).unwrap();
+        assert!(prefixed.starts_with("kits-"));
+        assert!(generate_kid(&jwk, KidStrategy::Prefix, None).is_err());
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_generate_kid() {
@@ -0,0 +1,146 @@
+use anyhow::Context;
+use num_bigint::{BigInt, Sign};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::{jwk::generate_jwk_inner, JwkeyAlgorithm, JwkeyUsage};
+use crate::{
+    errors::{Error, Result},
+    utils::random_bytes,
+    vault,
+};
+
+const STATUS: &str = "status";
+const ROTATED_AT: &str = "rotated_at";
+const PRIVATE_MEMBERS: [&str; 8] =
+    ["d", "p", "q", "dp", "dq", "qi", "oth", "k"];
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationPolicy {
+    pub algorithm: JwkeyAlgorithm,
+    pub usage: Option<JwkeyUsage>,
+    /// seconds a demoted key stays published before it is retired
+    pub retire_after: u64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatedJwks {
+    pub private_jwks: String,
+    pub public_jwks: String,
+    pub primary: String,
+    pub retired: Vec<String>,
+}
+
+/// One rotation step. Its state lives in the non-standard `status` and
+/// `rotated_at` members, kept out of the public set.
+#[tauri::command]
+pub async fn rotate_jwks(
+    current_jwks: Option<String>,
+    policy: RotationPolicy,
+) -> Result<RotatedJwks> {
+    info!("rotate jwks: {:?}", policy);
+    let keys = match current_jwks.as_deref().map(str::trim) {
+        Some(jwks) if !jwks.is_empty() => {
+            let jwks: Value =
+                serde_json::from_str(jwks).context("invalid jwks")?;
+            jwks["keys"]
+                .as_array()
+                .cloned()
+                .ok_or(Error::Unsupported("jwks without keys".to_string()))?
+        }
+        _ => vec![],
+    };
+    let now = vault::now();
+    let mut retired = vec![];
+    let mut kept = vec![];
+    for mut key in keys {
+        let rotated_at = key[ROTATED_AT].as_u64().unwrap_or(now);
+        match key[STATUS].as_str() {
+            Some("secondary") if rotated_at + policy.retire_after <= now => {
+                retired
+                    .push(key["kid"].as_str().unwrap_or_default().to_string());
+                continue;
+            }
+            Some("secondary") => {}
+            _ => {
+                key[STATUS] = json!("secondary");
+                key[ROTATED_AT] = json!(now);
+            }
+        }
+        kept.push(key);
+    }
+
+    let random = random_bytes(16)?;
+    let kid = BigInt::from_bytes_be(Sign::Plus, &random).to_str_radix(36);
+    let mut primary = generate_jwk_inner(policy.algorithm).await?;
+    primary["kid"] = json!(kid);
+    primary["alg"] = json!(policy.algorithm);
+    if let Some(usage) = policy.usage {
+        primary["use"] = json!(usage.to_string());
+    }
+    primary[STATUS] = json!("primary");
+    primary[ROTATED_AT] = json!(now);
+    kept.insert(0, primary);
+
+    let public_keys = kept
+        .iter()
+        .filter(|key| key["kty"] != "oct")
+        .map(|key| {
+            let mut key = key.clone();
+            if let Some(members) = key.as_object_mut() {
+                for member in
+                    PRIVATE_MEMBERS.iter().chain(&[STATUS, ROTATED_AT])
+                {
+                    members.remove(*member);
+                }
+            }
+            key
+        })
+        .collect::<Vec<_>>();
+    Ok(RotatedJwks {
+        private_jwks: serde_json::to_string_pretty(&json!({ "keys": kept }))
+            .context("value to string failed")?,
+        public_jwks: serde_json::to_string_pretty(
+            &json!({ "keys": public_keys }),
+        )
+        .context("value to string failed")?,
+        primary: kid,
+        retired,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{rotate_jwks, RotationPolicy};
+    use crate::jwt::JwkeyAlgorithm;
+
+    #[tokio::test]
+    async fn test_rotate_jwks() {
+        let policy = || RotationPolicy {
+            algorithm: JwkeyAlgorithm::ES256,
+            usage: None,
+            retire_after: 0,
+        };
+        let first = rotate_jwks(None, policy()).await.unwrap();
+        let second = rotate_jwks(Some(first.private_jwks), policy())
+            .await
+            .unwrap();
+        let third = rotate_jwks(Some(second.private_jwks), policy())
+            .await
+            .unwrap();
+        assert_eq!(third.retired, vec![first.primary]);
+        let public: Value = serde_json::from_str(&third.public_jwks).unwrap();
+        let keys = public["keys"].as_array().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0]["kid"], third.primary.as_str());
+        assert_eq!(keys[1]["kid"], second.primary.as_str());
+        assert!(keys
+            .iter()
+            .all(|key| key.get("d").is_none() && key.get("status").is_none()));
+    }
+}
@@ -1,6 +1,604 @@
-use crate::errors::Result;
+use anyhow::{anyhow, Context};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use hkdf::hmac::{Hmac, Mac};
+use pkcs8::{EncodePrivateKey, EncodePublicKey};
+use rsa::{
+    signature::{RandomizedSigner, SignatureEncoding, Signer, Verifier},
+    BigUint, RsaPrivateKey, RsaPublicKey,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
 
+use super::{
+    discovery, jcs, jwk::generate_jwk_key, GeneratedJose, JwkeyAlgorithm,
+    JwkeyUsage, SM2_PROVIDER,
+};
+use crate::{
+    errors::{Error, Result},
+    pool, provider, rng, vault,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignJsonDto {
+    pub payload: String,
+    pub jwk: String,
+    pub algorithm: Option<JwkeyAlgorithm>,
+    pub key_id: Option<String>,
+    pub detached: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerateJwsDto {
+    pub payload: String,
+    /// generated for `algorithm` when not given
+    pub jwk: Option<String>,
+    pub algorithm: Option<JwkeyAlgorithm>,
+    pub key_id: Option<String>,
+    /// names the call for `cancel_task`
+    pub task_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyJwtDto {
+    pub token: String,
+    /// a jwk, or a jwks to pick from by `kid`
+    pub jwk: Option<String>,
+    /// fetched and cached instead when no jwk is given
+    pub jwks_uri: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedJwt {
+    pub header: Value,
+    pub payload: Value,
+    pub jwk: Value,
+    pub verified: bool,
+}
+
+/// Key generation and signing run on the crypto pool.
+#[tauri::command]
+pub async fn generate_jws(data: GenerateJwsDto) -> Result<GeneratedJose> {
+    let jwk = data
+        .jwk
+        .as_deref()
+        .map(serde_json::from_str::<Value>)
+        .transpose()
+        .context("invalid jwk")?;
+    let algorithm = match (data.algorithm, &jwk) {
+        (Some(algorithm), _) => algorithm,
+        (None, Some(jwk)) => serde_json::from_value(jwk["alg"].clone())
+            .context("algorithm is required when jwk has no alg")?,
+        (None, None) => {
+            return Err(Error::Unsupported(
+                "jws without jwk nor algorithm".to_string(),
+            ))
+        }
+    };
+    info!(
+        "generate jws, algorithm: {:?}, generated key: {}",
+        algorithm,
+        jwk.is_none()
+    );
+    if let Some(given) = &data.jwk {
+        vault::enforce_policy(
+            given.as_bytes(),
+            JwkeyUsage::Signature,
+            &serde_json::to_value(algorithm)
+                .ok()
+                .and_then(|alg| alg.as_str().map(String::from))
+                .unwrap_or_default(),
+        )?;
+    }
+    let (payload, key_id) = (data.payload, data.key_id);
+    pool::spawn_cancellable(data.task_id, move |cancel| {
+        let (jwk, generated) = match jwk {
+            Some(jwk) => (jwk, false),
+            None => (generate_jwk_key(algorithm)?, true),
+        };
+        cancel.check()?;
+        let mut header = json!({ "alg": algorithm });
+        if let Some(kid) = key_id.or(jwk["kid"].as_str().map(String::from)) {
+            header["kid"] = Value::String(kid);
+        }
+        let signing_input = format!(
+            "{}.{}",
+            Base64UrlUnpadded::encode_string(
+                jcs::canonicalize(&header)?.as_bytes()
+            ),
+            Base64UrlUnpadded::encode_string(payload.as_bytes())
+        );
+        let signature = sign_jws(algorithm, &jwk, signing_input.as_bytes())?;
+        Ok(GeneratedJose {
+            token: format!(
+                "{}.{}",
+                signing_input,
+                Base64UrlUnpadded::encode_string(&signature)
+            ),
+            jwk: generated.then_some(jwk),
+        })
+    })
+    .await
+}
+
+/// Signs the RFC 8785 canonical form of the payload.
+#[tauri::command]
+pub fn sign_json(data: SignJsonDto) -> Result<String> {
+    let payload: Value =
+        serde_json::from_str(&data.payload).context("invalid json payload")?;
+    let jwk: Value = serde_json::from_str(&data.jwk).context("invalid jwk")?;
+    let algorithm = match data.algorithm {
+        Some(algorithm) => algorithm,
+        None => serde_json::from_value(jwk["alg"].clone())
+            .context("algorithm is required when jwk has no alg")?,
+    };
+    info!("sign json, algorithm: {:?}", algorithm);
+    vault::enforce_policy(
+        data.jwk.as_bytes(),
+        JwkeyUsage::Signature,
+        &serde_json::to_value(algorithm)
+            .ok()
+            .and_then(|alg| alg.as_str().map(String::from))
+            .unwrap_or_default(),
+    )?;
+    let mut header = json!({ "alg": algorithm });
+    if let Some(kid) = data.key_id.or(jwk["kid"].as_str().map(String::from)) {
+        header["kid"] = Value::String(kid);
+    }
+    let header = Base64UrlUnpadded::encode_string(
+        jcs::canonicalize(&header)?.as_bytes(),
+    );
+    let payload = Base64UrlUnpadded::encode_string(
+        jcs::canonicalize(&payload)?.as_bytes(),
+    );
+    let signing_input = format!("{}.{}", header, payload);
+    let signature = Base64UrlUnpadded::encode_string(&sign_jws(
+        algorithm,
+        &jwk,
+        signing_input.as_bytes(),
+    )?);
+    Ok(if data.detached.unwrap_or(false) {
+        format!("{}..{}", header, signature)
+    } else {
+        format!("{}.{}", signing_input, signature)
+    })
+}
+
+/// A token without `kid` needs a key set of exactly one key.
 #[tauri::command]
-pub(crate) fn generate_jws() -> Result<String> {
-    Ok("".to_string())
+pub async fn verify_jwt(data: VerifyJwtDto) -> Result<VerifiedJwt> {
+    let segments = data.token.trim().split('.').collect::<Vec<_>>();
+    let [header, payload, signature] = segments[..] else {
+        return Err(Error::Unsupported(format!(
+            "token with {} segments",
+            segments.len()
+        )));
+    };
+    let signing_input = format!("{}.{}", header, payload);
+    let decode = |segment: &str| {
+        Base64UrlUnpadded::decode_vec(segment)
+            .map_err(|_| anyhow!("non base64url segment"))
+    };
+    let header: Value =
+        serde_json::from_slice(&decode(header)?).context("invalid header")?;
+    let payload: Value =
+        serde_json::from_slice(&decode(payload)?).context("invalid payload")?;
+    let signature = decode(signature)?;
+    let algorithm: JwkeyAlgorithm =
+        serde_json::from_value(header["alg"].clone())
+            .with_context(|| format!("unsupported alg {}", header["alg"]))?;
+    let kid = header["kid"].as_str();
+    info!("verify jwt, algorithm: {:?}, kid: {:?}", algorithm, kid);
+
+    let keys = match (&data.jwk, &data.jwks_uri) {
+        (Some(jwk), _) => {
+            let jwk: Value =
+                serde_json::from_str(jwk).context("invalid jwk")?;
+            match jwk["keys"].as_array() {
+                Some(keys) => keys.clone(),
+                None => vec![jwk],
+            }
+        }
+        (None, Some(jwks_uri)) => discovery::jwks_keys(jwks_uri, kid).await?,
+        (None, None) => {
+            return Err(Error::Unsupported("no jwk nor jwks_uri".to_string()))
+        }
+    };
+    let jwk = match kid {
+        Some(kid) => keys.into_iter().find(|key| key["kid"] == kid),
+        None if keys.len() == 1 => keys.into_iter().next(),
+        None => None,
+    }
+    .ok_or_else(|| Error::Unsupported(format!("no key for kid {:?}", kid)))?;
+    if !jwk["alg"].is_null() && jwk["alg"] != header["alg"] {
+        return Err(Error::Unsupported(format!(
+            "key is for {}, token is {}",
+            jwk["alg"], header["alg"]
+        )));
+    }
+    let verified =
+        verify_jws(algorithm, &jwk, signing_input.as_bytes(), &signature)?;
+    Ok(VerifiedJwt {
+        header,
+        payload,
+        jwk,
+        verified,
+    })
+}
+
+pub(crate) fn sign_jws(
+    algorithm: JwkeyAlgorithm,
+    jwk: &Value,
+    signing_input: &[u8],
+) -> Result<Vec<u8>> {
+    Ok(match algorithm {
+        JwkeyAlgorithm::HS256 => hmac::<sha2::Sha256>(jwk, signing_input)?,
+        JwkeyAlgorithm::HS384 => hmac::<sha2::Sha384>(jwk, signing_input)?,
+        JwkeyAlgorithm::HS512 => hmac::<sha2::Sha512>(jwk, signing_input)?,
+        JwkeyAlgorithm::RS256 => {
+            rsa::pkcs1v15::SigningKey::<sha2::Sha256>::new(rsa_key(jwk)?)
+                .sign(signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::RS384 => {
+            rsa::pkcs1v15::SigningKey::<sha2::Sha384>::new(rsa_key(jwk)?)
+                .sign(signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::RS512 => {
+            rsa::pkcs1v15::SigningKey::<sha2::Sha512>::new(rsa_key(jwk)?)
+                .sign(signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::PS256 => {
+            rsa::pss::SigningKey::<sha2::Sha256>::new(rsa_key(jwk)?)
+                .sign_with_rng(&mut rng::rng(), signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::PS384 => {
+            rsa::pss::SigningKey::<sha2::Sha384>::new(rsa_key(jwk)?)
+                .sign_with_rng(&mut rng::rng(), signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::PS512 => {
+            rsa::pss::SigningKey::<sha2::Sha512>::new(rsa_key(jwk)?)
+                .sign_with_rng(&mut rng::rng(), signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::ES256 => {
+            let signature: p256::ecdsa::Signature =
+                p256::ecdsa::SigningKey::from_slice(&jwk_field(jwk, "d")?)
+                    .context("invalid p-256 private key")?
+                    .sign(signing_input);
+            signature.to_vec()
+        }
+        JwkeyAlgorithm::ES384 => {
+            let signature: p384::ecdsa::Signature =
+                p384::ecdsa::SigningKey::from_slice(&jwk_field(jwk, "d")?)
+                    .context("invalid p-384 private key")?
+                    .sign(signing_input);
+            signature.to_vec()
+        }
+        JwkeyAlgorithm::ES256K => {
+            let signature: k256::ecdsa::Signature =
+                k256::ecdsa::SigningKey::from_slice(&jwk_field(jwk, "d")?)
+                    .context("invalid secp256k1 private key")?
+                    .sign(signing_input);
+            signature.to_vec()
+        }
+        JwkeyAlgorithm::EdDSA => {
+            ed25519_curve(jwk)?;
+            let secret: [u8; 32] = jwk_field(jwk, "d")?
+                .try_into()
+                .map_err(|_| anyhow!("invalid ed25519 private key"))?;
+            ed25519_dalek::SigningKey::from_bytes(&secret)
+                .sign(signing_input)
+                .to_vec()
+        }
+        JwkeyAlgorithm::SM2 => {
+            let private_key = sm2::SecretKey::from_slice(&jwk_field(jwk, "d")?)
+                .context("invalid sm2 private key")?
+                .to_pkcs8_der()
+                .context("export sm2 pkcs8 der private key failed")?;
+            provider::signature(SM2_PROVIDER)?
+                .sign(private_key.as_bytes(), signing_input)?
+        }
+        algorithm => {
+            return Err(Error::Unsupported(format!(
+                "jws algorithm {:?}",
+                algorithm
+            )))
+        }
+    })
+}
+
+/// Only the public members of `jwk` are read, except for hmac keys.
+pub(crate) fn verify_jws(
+    algorithm: JwkeyAlgorithm,
+    jwk: &Value,
+    signing_input: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    Ok(match algorithm {
+        JwkeyAlgorithm::HS256 => {
+            hmac_verify::<sha2::Sha256>(jwk, signing_input, signature)?
+        }
+        JwkeyAlgorithm::HS384 => {
+            hmac_verify::<sha2::Sha384>(jwk, signing_input, signature)?
+        }
+        JwkeyAlgorithm::HS512 => {
+            hmac_verify::<sha2::Sha512>(jwk, signing_input, signature)?
+        }
+        JwkeyAlgorithm::RS256 => verify_with::<_, rsa::pkcs1v15::Signature>(
+            rsa::pkcs1v15::VerifyingKey::<sha2::Sha256>::new(rsa_public_key(
+                jwk,
+            )?),
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::RS384 => verify_with::<_, rsa::pkcs1v15::Signature>(
+            rsa::pkcs1v15::VerifyingKey::<sha2::Sha384>::new(rsa_public_key(
+                jwk,
+            )?),
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::RS512 => verify_with::<_, rsa::pkcs1v15::Signature>(
+            rsa::pkcs1v15::VerifyingKey::<sha2::Sha512>::new(rsa_public_key(
+                jwk,
+            )?),
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::PS256 => verify_with::<_, rsa::pss::Signature>(
+            rsa::pss::VerifyingKey::<sha2::Sha256>::new(rsa_public_key(jwk)?),
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::PS384 => verify_with::<_, rsa::pss::Signature>(
+            rsa::pss::VerifyingKey::<sha2::Sha384>::new(rsa_public_key(jwk)?),
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::PS512 => verify_with::<_, rsa::pss::Signature>(
+            rsa::pss::VerifyingKey::<sha2::Sha512>::new(rsa_public_key(jwk)?),
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::ES256 => verify_with::<_, p256::ecdsa::Signature>(
+            p256::ecdsa::VerifyingKey::from_sec1_bytes(&ec_point(jwk)?)
+                .context("invalid p-256 public key")?,
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::ES384 => verify_with::<_, p384::ecdsa::Signature>(
+            p384::ecdsa::VerifyingKey::from_sec1_bytes(&ec_point(jwk)?)
+                .context("invalid p-384 public key")?,
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::ES256K => verify_with::<_, k256::ecdsa::Signature>(
+            k256::ecdsa::VerifyingKey::from_sec1_bytes(&ec_point(jwk)?)
+                .context("invalid secp256k1 public key")?,
+            signing_input,
+            signature,
+        ),
+        JwkeyAlgorithm::EdDSA => {
+            ed25519_curve(jwk)?;
+            let public: [u8; 32] = jwk_field(jwk, "x")?
+                .try_into()
+                .map_err(|_| anyhow!("invalid ed25519 public key"))?;
+            verify_with::<_, ed25519_dalek::Signature>(
+                ed25519_dalek::VerifyingKey::from_bytes(&public)
+                    .context("invalid ed25519 public key")?,
+                signing_input,
+                signature,
+            )
+        }
+        JwkeyAlgorithm::SM2 => {
+            let public_key = sm2::PublicKey::from_sec1_bytes(&ec_point(jwk)?)
+                .context("invalid sm2 public key")?
+                .to_public_key_der()
+                .context("export sm2 spki der public key failed")?;
+            provider::signature(SM2_PROVIDER)?.verify(
+                public_key.as_bytes(),
+                signing_input,
+                signature,
+            )?
+        }
+        algorithm => {
+            return Err(Error::Unsupported(format!(
+                "jws algorithm {:?}",
+                algorithm
+            )))
+        }
+    })
+}
+
+fn verify_with<V, S>(key: V, signing_input: &[u8], signature: &[u8]) -> bool
+where
+    V: Verifier<S>,
+    S: for<'a> TryFrom<&'a [u8]>,
+{
+    S::try_from(signature)
+        .is_ok_and(|signature| key.verify(signing_input, &signature).is_ok())
+}
+
+/// Ed448 waits for crypto::edwards to support it.
+fn ed25519_curve(jwk: &Value) -> Result<()> {
+    match jwk["crv"].as_str() {
+        None | Some("Ed25519") => Ok(()),
+        crv => Err(Error::Unsupported(format!("EdDSA curve {:?}", crv))),
+    }
+}
+
+pub(super) fn jwk_field(jwk: &Value, name: &str) -> Result<Vec<u8>> {
+    let value = jwk[name]
+        .as_str()
+        .ok_or(anyhow!("jwk field `{}` is missing", name))?;
+    Ok(Base64UrlUnpadded::decode_vec(value)
+        .map_err(|_| anyhow!("jwk field `{}` is not base64url", name))?)
+}
+
+fn hmac<D>(jwk: &Value, signing_input: &[u8]) -> Result<Vec<u8>>
+where
+    Hmac<D>: Mac + hkdf::hmac::digest::KeyInit,
+{
+    let mut mac = <Hmac<D> as hkdf::hmac::digest::KeyInit>::new_from_slice(
+        &jwk_field(jwk, "k")?,
+    )
+    .context("invalid hmac key")?;
+    mac.update(signing_input);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn hmac_verify<D>(
+    jwk: &Value,
+    signing_input: &[u8],
+    signature: &[u8],
+) -> Result<bool>
+where
+    Hmac<D>: Mac + hkdf::hmac::digest::KeyInit,
+{
+    let mut mac = <Hmac<D> as hkdf::hmac::digest::KeyInit>::new_from_slice(
+        &jwk_field(jwk, "k")?,
+    )
+    .context("invalid hmac key")?;
+    mac.update(signing_input);
+    Ok(mac.verify_slice(signature).is_ok())
+}
+
+pub(super) fn rsa_public_key(jwk: &Value) -> Result<RsaPublicKey> {
+    let uint = |name| jwk_field(jwk, name).map(|v| BigUint::from_bytes_be(&v));
+    Ok(RsaPublicKey::new(uint("n")?, uint("e")?).context("invalid rsa jwk")?)
+}
+
+fn ec_point(jwk: &Value) -> Result<Vec<u8>> {
+    let mut point = vec![0x04];
+    point.extend_from_slice(&jwk_field(jwk, "x")?);
+    point.extend_from_slice(&jwk_field(jwk, "y")?);
+    Ok(point)
+}
+
+pub(super) fn rsa_key(jwk: &Value) -> Result<RsaPrivateKey> {
+    let uint = |name| jwk_field(jwk, name).map(|v| BigUint::from_bytes_be(&v));
+    let key = RsaPrivateKey::from_components(
+        uint("n")?,
+        uint("e")?,
+        uint("d")?,
+        vec![uint("p")?, uint("q")?],
+    )
+    .context("invalid rsa jwk")?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+
+    use serde_json::json;
+
+    use super::{
+        generate_jws, sign_json, sign_jws, verify_jwt, GenerateJwsDto,
+        SignJsonDto, VerifyJwtDto,
+    };
+    use crate::{errors::Error, jwt::JwkeyAlgorithm};
+
+    #[test]
+    fn test_eddsa_ed448_unsupported() {
+        let jwk = json!({ "kty": "OKP", "crv": "Ed448", "x": "", "d": "" });
+        assert!(matches!(
+            sign_jws(JwkeyAlgorithm::EdDSA, &jwk, b"payload"),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_sign_json_hs256() {
+        let jws = sign_json(SignJsonDto {
+            payload: r#"{"b": 2, "a": [1.0, "x"]}"#.to_string(),
+            jwk: r#"{"kty":"oct","k":"c2VjcmV0"}"#.to_string(),
+            algorithm: Some(JwkeyAlgorithm::HS256),
+            key_id: Some("1".to_string()),
+            detached: None,
+        })
+        .unwrap();
+        let parts = jws.split('.').collect::<Vec<_>>();
+        assert_eq!(
+            Base64UrlUnpadded::decode_vec(parts[0]).unwrap(),
+            br#"{"alg":"HS256","kid":"1"}"#
+        );
+        assert_eq!(
+            Base64UrlUnpadded::decode_vec(parts[1]).unwrap(),
+            br#"{"a":[1,"x"],"b":2}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_jwt() {
+        let token = sign_json(SignJsonDto {
+            payload: r#"{"sub": "1"}"#.to_string(),
+            jwk: r#"{"kty":"oct","k":"c2VjcmV0"}"#.to_string(),
+            algorithm: Some(JwkeyAlgorithm::HS256),
+            key_id: Some("1".to_string()),
+            detached: None,
+        })
+        .unwrap();
+        let verify = |jwk: &str| {
+            verify_jwt(VerifyJwtDto {
+                token: token.clone(),
+                jwk: Some(jwk.to_string()),
+                jwks_uri: None,
+            })
+        };
+        let verified = verify(
+            r#"{"keys": [
+                {"kty":"oct","kid":"0","k":"b3RoZXI"},
+                {"kty":"oct","kid":"1","k":"c2VjcmV0"}
+            ]}"#,
+        )
+        .await
+        .unwrap();
+        assert!(verified.verified);
+        assert_eq!(verified.payload["sub"], "1");
+        assert_eq!(verified.jwk["kid"], "1");
+        let verified = verify(r#"{"kty":"oct","kid":"1","k":"b3RoZXI"}"#)
+            .await
+            .unwrap();
+        assert!(!verified.verified);
+        assert!(verify(r#"{"kty":"oct","kid":"0","k":"c2VjcmV0"}"#)
+            .await
+            .is_err());
+        assert!(verify(
+            r#"{"kty":"oct","kid":"1","alg":"HS512","k":"c2VjcmV0"}"#
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_jws_rs256() {
+        let generated = generate_jws(GenerateJwsDto {
+            payload: r#"{"sub":"1"}"#.to_string(),
+            jwk: None,
+            algorithm: Some(JwkeyAlgorithm::RS256),
+            key_id: None,
+            task_id: None,
+        })
+        .await
+        .unwrap();
+        let verified = verify_jwt(VerifyJwtDto {
+            token: generated.token,
+            jwk: generated.jwk.map(|jwk| jwk.to_string()),
+            jwks_uri: None,
+        })
+        .await
+        .unwrap();
+        assert!(verified.verified);
+        assert_eq!(verified.payload["sub"], "1");
+    }
 }
@@ -0,0 +1,591 @@
+//! Moves secret keys between Tink cleartext keysets, libsodium raw keys, JWK
+//! and PKCS#8 (RFC 8410).
+
+use anyhow::Context;
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng,
+    x509::{encode_tlv, read_all, read_tlv},
+};
+
+const TINK_TYPE_URL: &str = "type.googleapis.com/google.crypto.tink.";
+const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+const X25519_OID: &[u8] = &[0x2b, 0x65, 0x6e];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyBridgeFormat {
+    Tink,
+    Libsodium,
+    Jwk,
+    Pkcs8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyBridgeType {
+    /// libsodium crypto_sign
+    Ed25519,
+    /// libsodium crypto_box and crypto_kx
+    X25519,
+    /// libsodium crypto_aead_aes256gcm
+    Aes256Gcm,
+    /// libsodium crypto_aead_xchacha20poly1305_ietf
+    Xchacha20Poly1305,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBridgeDto {
+    pub input: String,
+    pub from: KeyBridgeFormat,
+    pub to: KeyBridgeFormat,
+    /// what libsodium raw keys and oct jwks hold, they do not say
+    pub key_type: Option<KeyBridgeType>,
+    /// of libsodium raw keys, base64 when absent
+    pub encoding: Option<TextEncoding>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBridgeOutput {
+    pub key_type: KeyBridgeType,
+    pub output: String,
+    pub public: Option<String>,
+    pub notes: Vec<String>,
+}
+
+struct BridgeKey {
+    key_type: KeyBridgeType,
+    /// the ed25519 seed, the x25519 scalar or the symmetric key
+    secret: Vec<u8>,
+}
+
+impl BridgeKey {
+    fn public(&self) -> Option<Vec<u8>> {
+        let secret: [u8; 32] = self.secret.clone().try_into().ok()?;
+        match self.key_type {
+            KeyBridgeType::Ed25519 => Some(
+                ed25519_dalek::SigningKey::from_bytes(&secret)
+                    .verifying_key()
+                    .to_bytes()
+                    .to_vec(),
+            ),
+            KeyBridgeType::X25519 => Some(
+                x25519_dalek::PublicKey::from(
+                    &x25519_dalek::StaticSecret::from(secret),
+                )
+                .as_bytes()
+                .to_vec(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn keybridge(data: KeyBridgeDto) -> Result<KeyBridgeOutput> {
+    info!(
+        "keybridge: {:?} to {:?}, key type: {:?}",
+        data.from, data.to, data.key_type
+    );
+    let encoding = data.encoding.unwrap_or(TextEncoding::Base64);
+    let mut notes = vec![];
+    let key = match data.from {
+        KeyBridgeFormat::Tink => from_tink(&data.input, &mut notes)?,
+        KeyBridgeFormat::Libsodium => {
+            from_libsodium(&encoding.decode(data.input.trim())?, data.key_type)?
+        }
+        KeyBridgeFormat::Jwk => from_jwk(&data.input, data.key_type)?,
+        KeyBridgeFormat::Pkcs8 => from_pkcs8(&data.input)?,
+    };
+    if let Some(key_type) = data.key_type {
+        if key_type != key.key_type {
+            return Err(Error::Unsupported(format!(
+                "the input holds a {:?} key, not {:?}",
+                key.key_type, key_type
+            )));
+        }
+    }
+    if key.secret.len() != 32 {
+        return Err(Error::Unsupported(format!(
+            "{:?} key of {} bytes",
+            key.key_type,
+            key.secret.len()
+        )));
+    }
+    let (output, public) = match data.to {
+        KeyBridgeFormat::Tink => to_tink(&key, &mut notes)?,
+        KeyBridgeFormat::Libsodium => {
+            let public = key.public();
+            let secret = match (&key.key_type, &public) {
+                // crypto_sign secret keys carry their public half
+                (KeyBridgeType::Ed25519, Some(public)) => {
+                    [key.secret.clone(), public.clone()].concat()
+                }
+                _ => key.secret.clone(),
+            };
+            (
+                encoding.encode(&secret)?,
+                public.map(|public| encoding.encode(&public)).transpose()?,
+            )
+        }
+        KeyBridgeFormat::Jwk => to_jwk(&key)?,
+        KeyBridgeFormat::Pkcs8 => to_pkcs8(&key)?,
+    };
+    Ok(KeyBridgeOutput {
+        key_type: key.key_type,
+        output,
+        public,
+        notes,
+    })
+}
+
+fn from_tink(input: &str, notes: &mut Vec<String>) -> Result<BridgeKey> {
+    let keyset: Value =
+        serde_json::from_str(input).context("invalid tink json keyset")?;
+    let keys = keyset["key"].as_array().cloned().unwrap_or_default();
+    let key = match keyset["primaryKeyId"].as_u64() {
+        Some(id) => keys.iter().find(|key| key["keyId"].as_u64() == Some(id)),
+        None => keys.first(),
+    }
+    .ok_or_else(|| {
+        Error::Unsupported("tink keyset without primary key".to_string())
+    })?;
+    if keys.len() > 1 {
+        notes.push(format!(
+            "the keyset holds {} keys, only the primary one is converted",
+            keys.len()
+        ));
+    }
+    if let Some(prefix) = key["outputPrefixType"].as_str() {
+        if prefix != "RAW" {
+            notes.push(format!(
+                "{} output prefix: tink prepends the key id to ciphertexts \
+                 and signatures, other libraries neither write nor expect it",
+                prefix
+            ));
+        }
+    }
+    let type_url = key["keyData"]["typeUrl"].as_str().unwrap_or_default();
+    let value = Base64::decode_vec(
+        key["keyData"]["value"].as_str().unwrap_or_default(),
+    )
+    .context("invalid tink key value")?;
+    let fields = proto_fields(&value)?;
+    let (key_type, field) = match type_url.strip_prefix(TINK_TYPE_URL) {
+        Some("Ed25519PrivateKey") => (KeyBridgeType::Ed25519, 2),
+        Some("AesGcmKey") => (KeyBridgeType::Aes256Gcm, 3),
+        Some("XChaCha20Poly1305Key") => (KeyBridgeType::Xchacha20Poly1305, 3),
+        _ => return Err(Error::Unsupported(format!("tink key {}", type_url))),
+    };
+    let secret = fields
+        .into_iter()
+        .find(|(number, _)| *number == field)
+        .map(|(_, value)| value)
+        .ok_or_else(|| {
+            Error::Unsupported("tink key without key value".to_string())
+        })?;
+    Ok(BridgeKey { key_type, secret })
+}
+
+fn to_tink(
+    key: &BridgeKey,
+    notes: &mut Vec<String>,
+) -> Result<(String, Option<String>)> {
+    let (type_name, field) = match key.key_type {
+        KeyBridgeType::Ed25519 => ("Ed25519PrivateKey", 2),
+        KeyBridgeType::Aes256Gcm => ("AesGcmKey", 3),
+        KeyBridgeType::Xchacha20Poly1305 => ("XChaCha20Poly1305Key", 3),
+        KeyBridgeType::X25519 => {
+            return Err(Error::Unsupported(
+                "tink has no standalone x25519 key".to_string(),
+            ))
+        }
+    };
+    // a zero id reads as unset
+    let id = rng::rng().next_u32().max(1);
+    let keyset = |type_name: &str, material: &str, value: &[u8]| {
+        json!({
+            "primaryKeyId": id,
+            "key": [{
+                "keyData": {
+                    "typeUrl": format!("{}{}", TINK_TYPE_URL, type_name),
+                    "value": Base64::encode_string(value),
+                    "keyMaterialType": material,
+                },
+                "status": "ENABLED",
+                "keyId": id,
+                "outputPrefixType": "RAW",
+            }],
+        })
+    };
+    let mut value = proto_bytes(field, &key.secret);
+    let public = match key.public() {
+        Some(public) => {
+            // Ed25519PublicKey, embedded in the private key as field 3
+            let public = proto_bytes(2, &public);
+            value.extend(proto_bytes(3, &public));
+            let keyset =
+                keyset("Ed25519PublicKey", "ASYMMETRIC_PUBLIC", &public);
+            Some(
+                serde_json::to_string_pretty(&keyset)
+                    .context("serialize tink keyset failed")?,
+            )
+        }
+        None => None,
+    };
+    let material = match public {
+        Some(_) => "ASYMMETRIC_PRIVATE",
+        None => "SYMMETRIC",
+    };
+    notes.push(
+        "cleartext keyset with a RAW output prefix, load it through tink's \
+         insecure cleartext keyset api"
+            .to_string(),
+    );
+    Ok((
+        serde_json::to_string_pretty(&keyset(type_name, material, &value))
+            .context("serialize tink keyset failed")?,
+        public,
+    ))
+}
+
+/// crypto_sign secret keys are the seed and the public key, everything
+/// else is the 32 key bytes.
+fn from_libsodium(
+    input: &[u8],
+    key_type: Option<KeyBridgeType>,
+) -> Result<BridgeKey> {
+    let key_type = key_type.ok_or_else(|| {
+        Error::Unsupported("libsodium keys need a key type".to_string())
+    })?;
+    let secret = match (key_type, input.len()) {
+        (KeyBridgeType::Ed25519, 64) => {
+            let key = BridgeKey {
+                key_type,
+                secret: input[.. 32].to_vec(),
+            };
+            if key.public().as_deref() != Some(&input[32 ..]) {
+                return Err(Error::Unsupported(
+                    "the public half of the secret key does not match its seed"
+                        .to_string(),
+                ));
+            }
+            return Ok(key);
+        }
+        (_, 32) => input.to_vec(),
+        (_, size) => {
+            return Err(Error::Unsupported(format!(
+                "libsodium {:?} key of {} bytes",
+                key_type, size
+            )))
+        }
+    };
+    Ok(BridgeKey { key_type, secret })
+}
+
+fn from_jwk(input: &str, key_type: Option<KeyBridgeType>) -> Result<BridgeKey> {
+    let jwk: Value = serde_json::from_str(input).context("invalid jwk")?;
+    let field = |name: &str| -> Result<Vec<u8>> {
+        Ok(Base64UrlUnpadded::decode_vec(jwk[name].as_str().ok_or_else(
+            || Error::Unsupported(format!("jwk without {}", name)),
+        )?)
+        .with_context(|| format!("invalid jwk {}", name))?)
+    };
+    let key_type = match (jwk["kty"].as_str(), jwk["crv"].as_str()) {
+        (Some("OKP"), Some("Ed25519")) => KeyBridgeType::Ed25519,
+        (Some("OKP"), Some("X25519")) => KeyBridgeType::X25519,
+        (Some("oct"), _) => match (jwk["alg"].as_str(), key_type) {
+            (Some("A256GCM"), _) => KeyBridgeType::Aes256Gcm,
+            (Some("XC20P"), _) => KeyBridgeType::Xchacha20Poly1305,
+            (_, Some(key_type)) => key_type,
+            _ => {
+                return Err(Error::Unsupported(
+                    "oct jwk without alg needs a key type".to_string(),
+                ))
+            }
+        },
+        (kty, crv) => {
+            return Err(Error::Unsupported(format!(
+                "jwk {} {}",
+                kty.unwrap_or_default(),
+                crv.unwrap_or_default()
+            )))
+        }
+    };
+    let secret = match key_type {
+        KeyBridgeType::Ed25519 | KeyBridgeType::X25519 => field("d")?,
+        _ => field("k")?,
+    };
+    let key = BridgeKey { key_type, secret };
+    if jwk["x"].is_string() && key.public() != Some(field("x")?) {
+        return Err(Error::Unsupported(
+            "jwk x does not match its d".to_string(),
+        ));
+    }
+    Ok(key)
+}
+
+fn to_jwk(key: &BridgeKey) -> Result<(String, Option<String>)> {
+    let encode = |bytes: &[u8]| Base64UrlUnpadded::encode_string(bytes);
+    let oct = |alg| json!({"kty": "oct", "alg": alg, "k": encode(&key.secret)});
+    let (jwk, public) = match key.key_type {
+        KeyBridgeType::Ed25519 | KeyBridgeType::X25519 => {
+            let crv = match key.key_type {
+                KeyBridgeType::Ed25519 => "Ed25519",
+                _ => "X25519",
+            };
+            let x = encode(&key.public().unwrap_or_default());
+            let public = json!({"kty": "OKP", "crv": crv, "x": x});
+            let mut jwk = public.clone();
+            jwk["d"] = json!(encode(&key.secret));
+            (jwk, Some(public))
+        }
+        KeyBridgeType::Aes256Gcm => (oct("A256GCM"), None),
+        KeyBridgeType::Xchacha20Poly1305 => (oct("XC20P"), None),
+    };
+    let pretty = |jwk: &Value| {
+        serde_json::to_string_pretty(jwk).context("serialize jwk failed")
+    };
+    Ok((pretty(&jwk)?, public.as_ref().map(pretty).transpose()?))
+}
+
+/// RFC 8410 OneAsymmetricKey, v1 or v2 with the public key.
+fn from_pkcs8(input: &str) -> Result<BridgeKey> {
+    let (label, der) = pem_rfc7468::decode_vec(input.trim().as_bytes())
+        .context("invalid pkcs#8 pem")?;
+    if label != "PRIVATE KEY" {
+        return Err(Error::Unsupported(format!("{} pem", label)));
+    }
+    let invalid = || Error::Unsupported("malformed pkcs#8".to_string());
+    let (info, _) = read_tlv(&der)?;
+    let fields = read_all(info.content)?;
+    let [_, algorithm, private_key, ..] = &fields[..] else {
+        return Err(invalid());
+    };
+    let (oid, _) = read_tlv(algorithm.content)?;
+    let key_type = match oid.content {
+        ED25519_OID => KeyBridgeType::Ed25519,
+        X25519_OID => KeyBridgeType::X25519,
+        _ => {
+            return Err(Error::Unsupported(
+                "pkcs#8 key other than ed25519 or x25519".to_string(),
+            ))
+        }
+    };
+    // CurvePrivateKey, an octet string inside the octet string
+    let (secret, _) = read_tlv(private_key.content)?;
+    if secret.tag != 0x04 {
+        return Err(invalid());
+    }
+    Ok(BridgeKey {
+        key_type,
+        secret: secret.content.to_vec(),
+    })
+}
+
+fn to_pkcs8(key: &BridgeKey) -> Result<(String, Option<String>)> {
+    let oid = match key.key_type {
+        KeyBridgeType::Ed25519 => ED25519_OID,
+        KeyBridgeType::X25519 => X25519_OID,
+        _ => {
+            return Err(Error::Unsupported(
+                "pkcs#8 holds asymmetric keys only".to_string(),
+            ))
+        }
+    };
+    let algorithm = encode_tlv(0x30, &encode_tlv(0x06, oid));
+    let private_key = encode_tlv(
+        0x30,
+        &[
+            encode_tlv(0x02, &[0]),
+            algorithm.clone(),
+            encode_tlv(0x04, &encode_tlv(0x04, &key.secret)),
+        ]
+        .concat(),
+    );
+    let public = key.public().unwrap_or_default();
+    // the bit string starts with its count of unused bits
+    let public_key = encode_tlv(
+        0x30,
+        &[algorithm, encode_tlv(0x03, &[&[0], &public[..]].concat())].concat(),
+    );
+    let pem = |label, der: &[u8]| {
+        pem_rfc7468::encode_string(label, pkcs8::LineEnding::LF, der)
+            .context("encode pem failed")
+    };
+    Ok((
+        pem("PRIVATE KEY", &private_key)?,
+        Some(pem("PUBLIC KEY", &public_key)?),
+    ))
+}
+
+/// The length delimited fields of a protobuf message, varints skipped.
+fn proto_fields(mut input: &[u8]) -> Result<Vec<(u64, Vec<u8>)>> {
+    let mut fields = vec![];
+    while !input.is_empty() {
+        let (tag, rest) = varint(input)?;
+        input = rest;
+        match tag & 7 {
+            0 => input = varint(input)?.1,
+            2 => {
+                let (length, rest) = varint(input)?;
+                let length = usize::try_from(length)
+                    .ok()
+                    .filter(|length| *length <= rest.len())
+                    .ok_or_else(|| {
+                        Error::Unsupported("truncated protobuf".to_string())
+                    })?;
+                fields.push((tag >> 3, rest[.. length].to_vec()));
+                input = &rest[length ..];
+            }
+            wire_type => {
+                return Err(Error::Unsupported(format!(
+                    "protobuf wire type {}",
+                    wire_type
+                )))
+            }
+        }
+    }
+    Ok(fields)
+}
+
+fn varint(input: &[u8]) -> Result<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (index, byte) in input.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, &input[index + 1 ..]));
+        }
+    }
+    Err(Error::Unsupported("malformed protobuf varint".to_string()))
+}
+
+/// The version fields stay at zero, which protobuf leaves out.
+fn proto_bytes(field: u8, value: &[u8]) -> Vec<u8> {
+    let mut output = vec![field << 3 | 2];
+    let mut length = value.len();
+    while length >= 0x80 {
+        output.push(length as u8 | 0x80);
+        length >>= 7;
+    }
+    output.push(length as u8);
+    output.extend_from_slice(value);
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{keybridge, KeyBridgeDto, KeyBridgeFormat, KeyBridgeType};
+    use crate::enums::TextEncoding;
+
+    /// RFC 8032 section 7.1 test 1
+    const SEED: &str =
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBLIC: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const PKCS8: &str = "-----BEGIN PRIVATE \
+                         KEY-----\nMC4CAQAwBQYDK2VwBCIEIJ1hsZ3v/\
+                         VpguoRK9JLsLMREScVpezJpGXA7rAMcrn9g\n-----END \
+                         PRIVATE KEY-----\n";
+
+    fn bridge(
+        input: &str,
+        from: KeyBridgeFormat,
+        to: KeyBridgeFormat,
+        key_type: Option<KeyBridgeType>,
+    ) -> super::KeyBridgeOutput {
+        keybridge(KeyBridgeDto {
+            input: input.to_string(),
+            from,
+            to,
+            key_type,
+            encoding: Some(TextEncoding::Hex),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_keybridge_ed25519() {
+        let libsodium = format!("{}{}", SEED, PUBLIC);
+        let pkcs8 = bridge(
+            &libsodium,
+            KeyBridgeFormat::Libsodium,
+            KeyBridgeFormat::Pkcs8,
+            Some(KeyBridgeType::Ed25519),
+        );
+        assert_eq!(pkcs8.output, PKCS8);
+
+        let tink =
+            bridge(PKCS8, KeyBridgeFormat::Pkcs8, KeyBridgeFormat::Tink, None);
+        let keyset: Value = serde_json::from_str(&tink.output).unwrap();
+        assert_eq!(keyset["key"][0]["outputPrefixType"], "RAW");
+        assert_eq!(
+            keyset["key"][0]["keyData"]["typeUrl"],
+            "type.googleapis.com/google.crypto.tink.Ed25519PrivateKey"
+        );
+
+        let jwk = bridge(
+            &tink.output,
+            KeyBridgeFormat::Tink,
+            KeyBridgeFormat::Jwk,
+            None,
+        );
+        let jwk: Value = serde_json::from_str(&jwk.output).unwrap();
+        assert_eq!(jwk["x"], "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo");
+
+        let back = bridge(
+            &jwk.to_string(),
+            KeyBridgeFormat::Jwk,
+            KeyBridgeFormat::Libsodium,
+            None,
+        );
+        assert_eq!(back.output, libsodium);
+        assert_eq!(back.public.as_deref(), Some(PUBLIC));
+    }
+
+    #[test]
+    fn test_keybridge_symmetric() {
+        let key = "00".repeat(31) + "01";
+        let jwk = bridge(
+            &key,
+            KeyBridgeFormat::Libsodium,
+            KeyBridgeFormat::Jwk,
+            Some(KeyBridgeType::Xchacha20Poly1305),
+        );
+        assert!(jwk.public.is_none());
+        let tink = bridge(
+            &jwk.output,
+            KeyBridgeFormat::Jwk,
+            KeyBridgeFormat::Tink,
+            None,
+        );
+        assert_eq!(tink.key_type, KeyBridgeType::Xchacha20Poly1305);
+        let back = bridge(
+            &tink.output,
+            KeyBridgeFormat::Tink,
+            KeyBridgeFormat::Libsodium,
+            None,
+        );
+        assert_eq!(back.output, key);
+
+        assert!(keybridge(KeyBridgeDto {
+            input: key,
+            from: KeyBridgeFormat::Libsodium,
+            to: KeyBridgeFormat::Pkcs8,
+            key_type: Some(KeyBridgeType::Aes256Gcm),
+            encoding: Some(TextEncoding::Hex),
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! Certificates from the native os stores. Imports land in the pipeline as
+//! `@token`.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    pipeline::pipeline_register,
+    x509::subject_common_name,
+};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod system;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "macos")]
+use macos as store;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use system as store;
+#[cfg(target_os = "windows")]
+use windows as store;
+
+struct NativeCertificate {
+    store: String,
+    label: Option<String>,
+    der: Vec<u8>,
+    private_key: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystoreEntry {
+    /// sha-256 of the certificate der in hex
+    pub id: String,
+    pub store: String,
+    pub label: String,
+    /// whether the store holds the matching private key
+    pub private_key: bool,
+    pub certificate: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystoreImportDto {
+    pub id: String,
+    /// import the private key instead of the certificate
+    #[serde(default)]
+    pub private_key: bool,
+    pub token: Option<String>,
+}
+
+#[tauri::command]
+pub fn keystore_certificates() -> Result<Vec<KeystoreEntry>> {
+    info!("keystore certificates");
+    store::certificates()?
+        .into_iter()
+        .map(|certificate| {
+            let id =
+                TextEncoding::Hex.encode(&Sha256::digest(&certificate.der))?;
+            let label = match certificate.label {
+                Some(label) if !label.is_empty() => label,
+                _ => subject_common_name(&certificate.der)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| id.clone()),
+            };
+            Ok(KeystoreEntry {
+                id,
+                store: certificate.store,
+                label,
+                private_key: certificate.private_key,
+                certificate: certificate_pem(&certificate.der)?,
+            })
+        })
+        .collect()
+}
+
+/// The private key goes as pkcs#8 pem when the store lets it go.
+#[tauri::command]
+pub fn keystore_import(data: KeystoreImportDto) -> Result<String> {
+    info!("keystore import: {:?}", data);
+    let certificate = store::certificates()?
+        .into_iter()
+        .find(|certificate| {
+            TextEncoding::Hex
+                .encode(&Sha256::digest(&certificate.der))
+                .is_ok_and(|id| id.eq_ignore_ascii_case(&data.id))
+        })
+        .ok_or_else(|| {
+            Error::Unsupported(format!("keystore certificate {}", data.id))
+        })?;
+    let value = if data.private_key {
+        if !certificate.private_key {
+            return Err(Error::Unsupported(format!(
+                "keystore certificate {} has no private key",
+                data.id
+            )));
+        }
+        store::private_key(&certificate.der)?
+    } else {
+        certificate_pem(&certificate.der)?
+    };
+    pipeline_register(value, data.token)
+}
+
+pub(crate) fn native_certificates() -> Result<Vec<Vec<u8>>> {
+    Ok(store::certificates()?
+        .into_iter()
+        .map(|certificate| certificate.der)
+        .collect())
+}
+
+fn certificate_pem(der: &[u8]) -> Result<String> {
+    Ok(
+        pem_rfc7468::encode_string("CERTIFICATE", pkcs8::LineEnding::LF, der)
+            .context("encode certificate pem failed")?,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{pipeline, x509::subject_common_name};
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBOTCB4aADAgECAgEBMAoGCCqGSM49BAMCMCcxDTALBgNVBAoMBGtpdHMxFjAU
+BgNVBAMMDWtpdHMga2V5c3RvcmUwHhcNMjQwMTAxMDAwMDAwWhcNMzQwMTAxMDAw
+MDAwWjAnMQ0wCwYDVQQKDARraXRzMRYwFAYDVQQDDA1raXRzIGtleXN0b3JlMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEdKNacPoikYZIxJ50fPqyWN7sZPSNUN9x
+OyGlyjGSkJQx0K4Qf6fJHoDOX2DFqx2Ar/DApUOvq1R6Lvj8Xx8cDzAKBggqhkjO
+PQQDAgNHADBEAiBklfyIG4rqLmMoU6MepK8GzB95MvXildpMt5TRerZqXQIgKhol
+jPAKEmPiBraV4/6vtHAXu8xFTvumGzrOn7WaTNs=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_subject_common_name() {
+        let (_, der) = pem_rfc7468::decode_vec(CERTIFICATE.as_bytes()).unwrap();
+        assert_eq!(
+            subject_common_name(&der).unwrap().as_deref(),
+            Some("kits keystore")
+        );
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn test_keystore_import() {
+        use super::{
+            keystore_certificates, keystore_import, KeystoreImportDto,
+        };
+
+        let _guard = crate::vault::TEST_VAULT
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let bundle = std::env::temp_dir().join("kits-keystore-bundle.pem");
+        std::fs::write(&bundle, format!("# kits\n{}", CERTIFICATE)).unwrap();
+        std::env::set_var("SSL_CERT_FILE", &bundle);
+        let entries = keystore_certificates().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].id,
+            "0f98fc5ab6030b4aa79ada50fd758df12bfb316506b5cc2146d236391e1bad1d"
+        );
+        assert_eq!(entries[0].label, "kits keystore");
+        assert!(!entries[0].private_key);
+
+        let dto = |private_key| KeystoreImportDto {
+            id: entries[0].id.clone(),
+            private_key,
+            token: Some("keystore".to_string()),
+        };
+        assert_eq!(keystore_import(dto(false)).unwrap(), "keystore");
+        assert_eq!(pipeline::resolve("@keystore").unwrap(), CERTIFICATE);
+        assert!(keystore_import(dto(true)).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Context;
+use pkcs1::DecodeRsaPrivateKey;
+use pkcs8::{EncodePrivateKey, LineEnding};
+use security_framework::item::{
+    ItemClass, ItemSearchOptions, Limit, Reference, SearchResult,
+};
+
+use super::NativeCertificate;
+use crate::errors::{Error, Result};
+
+/// errSecItemNotFound, what an empty search returns
+const ITEM_NOT_FOUND: i32 = -25300;
+
+fn search(class: ItemClass) -> Result<Vec<Reference>> {
+    let results = match ItemSearchOptions::new()
+        .class(class)
+        .load_refs(true)
+        .limit(Limit::All)
+        .search()
+    {
+        Ok(results) => results,
+        Err(error) if error.code() == ITEM_NOT_FOUND => vec![],
+        Err(error) => {
+            return Err(anyhow::Error::new(error)
+                .context("search keychain failed")
+                .into())
+        }
+    };
+    Ok(results
+        .into_iter()
+        .filter_map(|result| match result {
+            SearchResult::Ref(reference) => Some(reference),
+            _ => None,
+        })
+        .collect())
+}
+
+pub(super) fn certificates() -> Result<Vec<NativeCertificate>> {
+    let mut identities = Vec::new();
+    for reference in search(ItemClass::identity())? {
+        if let Reference::Identity(identity) = reference {
+            identities.push(
+                identity
+                    .certificate()
+                    .context("read identity certificate failed")?
+                    .to_der(),
+            );
+        }
+    }
+    Ok(search(ItemClass::certificate())?
+        .into_iter()
+        .filter_map(|reference| match reference {
+            Reference::Certificate(certificate) => Some(certificate),
+            _ => None,
+        })
+        .map(|certificate| {
+            let der = certificate.to_der();
+            NativeCertificate {
+                store: "keychain".to_string(),
+                label: Some(certificate.subject_summary()),
+                private_key: identities.contains(&der),
+                der,
+            }
+        })
+        .collect())
+}
+
+/// Items marked as not extractable have no external representation.
+pub(super) fn private_key(certificate: &[u8]) -> Result<String> {
+    for reference in search(ItemClass::identity())? {
+        let Reference::Identity(identity) = reference else {
+            continue;
+        };
+        let der = identity
+            .certificate()
+            .context("read identity certificate failed")?
+            .to_der();
+        if der != certificate {
+            continue;
+        }
+        let representation = identity
+            .private_key()
+            .context("read identity key failed")?
+            .external_representation()
+            .ok_or_else(|| {
+                Error::Unsupported("keychain key is not exportable".to_string())
+            })?;
+        return pkcs8_pem(&representation.to_vec());
+    }
+    Err(Error::Unsupported(
+        "keychain identity not found".to_string(),
+    ))
+}
+
+/// SecKey exports rsa keys as pkcs#1 and ec keys as `04 || x || y || d`.
+fn pkcs8_pem(representation: &[u8]) -> Result<String> {
+    let pem = match representation.len() {
+        97 => p256::SecretKey::from_slice(&representation[65 ..])
+            .context("invalid p-256 key from keychain")?
+            .to_pkcs8_pem(LineEnding::LF),
+        145 => p384::SecretKey::from_slice(&representation[97 ..])
+            .context("invalid p-384 key from keychain")?
+            .to_pkcs8_pem(LineEnding::LF),
+        _ => rsa::RsaPrivateKey::from_pkcs1_der(representation)
+            .context("unsupported key from keychain")?
+            .to_pkcs8_pem(LineEnding::LF),
+    }
+    .context("encode private key failed")?;
+    Ok(pem.to_string())
+}
@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::NativeCertificate;
+use crate::{
+    errors::{Error, Result},
+    x509::certificates_from_pem,
+};
+
+/// Where the common distributions keep their trust bundle, `SSL_CERT_FILE`
+/// wins when set.
+const BUNDLES: [&str; 4] = [
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+
+pub(super) fn certificates() -> Result<Vec<NativeCertificate>> {
+    let path = match std::env::var("SSL_CERT_FILE") {
+        Ok(path) => path,
+        Err(_) => BUNDLES
+            .iter()
+            .find(|path| Path::new(path).exists())
+            .map(|path| path.to_string())
+            .ok_or_else(|| {
+                Error::Unsupported("no system trust bundle found".to_string())
+            })?,
+    };
+    let bundle = std::fs::read_to_string(&path)
+        .context(format!("read trust bundle {} failed", path))?;
+    Ok(certificates_from_pem(&bundle)
+        .context(format!("invalid trust bundle {}", path))?
+        .into_iter()
+        .map(|der| NativeCertificate {
+            store: path.clone(),
+            label: None,
+            der,
+            private_key: false,
+        })
+        .collect())
+}
+
+pub(super) fn private_key(_certificate: &[u8]) -> Result<String> {
+    Err(Error::Unsupported(
+        "the system trust bundle holds no private keys".to_string(),
+    ))
+}
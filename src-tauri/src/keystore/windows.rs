@@ -0,0 +1,34 @@
+use anyhow::Context;
+use schannel::cert_store::CertStore;
+
+use super::NativeCertificate;
+use crate::errors::{Error, Result};
+
+const STORES: [&str; 3] = ["MY", "CA", "ROOT"];
+
+pub(super) fn certificates() -> Result<Vec<NativeCertificate>> {
+    let mut certificates = Vec::new();
+    for name in STORES {
+        let store = CertStore::open_current_user(name)
+            .context(format!("open {} certificate store failed", name))?;
+        for certificate in store.certs() {
+            // silent, so a smartcard backed key never prompts while listing
+            let private_key =
+                certificate.private_key().silent(true).acquire().is_ok();
+            certificates.push(NativeCertificate {
+                store: name.to_string(),
+                label: certificate.friendly_name().ok(),
+                der: certificate.to_der().to_vec(),
+                private_key,
+            });
+        }
+    }
+    Ok(certificates)
+}
+
+pub(super) fn private_key(_certificate: &[u8]) -> Result<String> {
+    Err(Error::Unsupported(
+        "cng keys can not be exported one by one, export a pfx from certmgr"
+            .to_string(),
+    ))
+}
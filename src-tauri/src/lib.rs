@@ -0,0 +1,42 @@
+//! The modules of the app, built on by the binary in `main.rs` and by the
+//! cargo-fuzz targets in `fuzz/`, which drive the parsers through `fuzz`.
+#![feature(let_chains)]
+
+pub mod audit;
+pub mod batch;
+pub mod codec;
+pub mod cose;
+pub mod crypto;
+pub mod dns;
+pub mod enums;
+pub mod errors;
+pub mod fetch;
+pub mod files;
+#[cfg(fuzzing)]
+pub mod fuzz;
+pub mod guard;
+pub mod hsm;
+pub mod i18n;
+pub mod identify;
+pub mod jwt;
+pub mod keybridge;
+pub mod keyfile;
+pub mod keystore;
+pub mod pipeline;
+pub mod piv;
+pub mod pool;
+pub mod profile;
+pub mod protocol;
+pub mod provider;
+pub mod rng;
+pub mod selftest;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod share;
+pub mod signing;
+pub mod ssh;
+pub mod utils;
+pub mod vault;
+pub mod watch;
+pub mod wireguard;
+pub mod x509;
@@ -1,23 +1,20 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-#![feature(let_chains)]
 use anyhow::Context;
-use errors::Result;
+use kits::{errors::Result, *};
 use tauri_plugin_log::{fern::colors::ColoredLevelConfig, LogTarget};
 use tracing_subscriber::fmt::writer::MakeWriterExt;
 
-pub mod codec;
-pub mod crypto;
-pub mod enums;
-pub mod errors;
-pub mod jwt;
-pub mod utils;
-
 fn main() -> Result<()> {
     let file_appender = tracing_appender::rolling::daily("./log", "app.log");
 
-    let (std_writer, _guard) =
-        tracing_appender::non_blocking(std::io::stdout());
+    // stdout carries the results in batch mode
+    let batch = std::env::args().nth(1).as_deref() == Some("batch");
+    let (std_writer, _guard) = if batch {
+        tracing_appender::non_blocking(std::io::stderr())
+    } else {
+        tracing_appender::non_blocking(std::io::stdout())
+    };
     let (file_writer, _guard) = tracing_appender::non_blocking(file_appender);
 
     let subscriber = tracing_subscriber::fmt()
@@ -33,6 +30,38 @@ fn main() -> Result<()> {
     tracing::subscriber::set_global_default(subscriber)
         .context("initial tracing subscriber failed")?;
 
+    #[cfg(feature = "plugins")]
+    if let Ok(dir) = std::env::var("KITS_PLUGIN_DIR") {
+        let loaded = provider::load_plugins(std::path::Path::new(&dir))?;
+        tracing::info!("loaded {} plugins from {}", loaded, dir);
+    }
+
+    #[cfg(feature = "serve")]
+    if std::env::args().nth(1).as_deref() == Some("serve") {
+        let addr = std::env::args()
+            .nth(2)
+            .unwrap_or(serve::DEFAULT_ADDR.to_string())
+            .parse()
+            .context("invalid listen address")?;
+        return tokio::runtime::Runtime::new()
+            .context("initial tokio runtime failed")?
+            .block_on(serve::serve(addr));
+    }
+
+    if batch {
+        return tokio::runtime::Runtime::new()
+            .context("initial tokio runtime failed")?
+            .block_on(batch::run(std::io::stdin().lock(), std::io::stdout()))
+            .map(|_| ());
+    }
+
+    std::thread::spawn(|| {
+        let report = selftest::self_tests();
+        if !report.passed {
+            tracing::error!("cryptographic self tests failed: {:?}", report);
+        }
+    });
+
     tauri::Builder::default()
         .plugin(
             tauri_plugin_log::Builder::default()
@@ -44,7 +73,14 @@ fn main() -> Result<()> {
                 .with_colors(ColoredLevelConfig::default())
                 .build(),
         )
-        .invoke_handler(tauri::generate_handler![
+        .setup(|app| {
+            watch::start(app.handle());
+            vault::session::start(app.handle());
+            Ok(())
+        })
+        .manage(vault::session::Session::default())
+        .register_uri_scheme_protocol(protocol::SCHEME, protocol::handle)
+        .invoke_handler(guard::guarded(tauri::generate_handler![
             // key generator
             crypto::aes::generate_aes,
             crypto::aes::generate_iv,
@@ -52,41 +88,180 @@ fn main() -> Result<()> {
             crypto::rsa::key::derive_rsa,
             crypto::rsa::key::parse_rsa,
             crypto::ecc::key::generate_ecc,
+            crypto::shamir::combine_shares,
             crypto::ecc::key::derive_ecc,
             crypto::ecc::key::parse_ecc,
+            crypto::ecc::exchange::sm2_exchange_key,
+            crypto::ecc::exchange::sm2_key_exchange,
+            crypto::ecc::signature::convert_signature,
+            crypto::ecc::signature::inspect_signature,
+            crypto::ecc::signature::recover_ecdsa_pubkey,
+            crypto::ecc::schnorr::generate_schnorr,
+            crypto::ecc::schnorr::schnorr_sign,
+            crypto::ecc::schnorr::schnorr_verify,
             crypto::ecc::ecies,
             crypto::edwards::key::generate_edwards,
             crypto::edwards::key::derive_edwards,
+            crypto::edwards::frost::frost_keygen,
+            crypto::edwards::frost::frost_sign,
             crypto::edwards::ecies_edwards,
+            crypto::hpke::generate_hpke,
             // encrytion
             crypto::aes::crypto_aes,
             crypto::rsa::crypto_rsa,
             crypto::ecc::ecies,
+            crypto::hpke::hpke,
+            crypto::aes::crypto_aes_batch,
+            crypto::rsa::crypto_rsa_batch,
+            crypto::ecc::ecies_batch,
+            crypto::siv::crypto_aes_siv,
+            crypto::chacha::crypto_chacha20_poly1305,
+            crypto::envelope::generate_data_key,
+            crypto::envelope::decrypt_with_wrapped_key,
+            crypto::blind::rsa_blind,
+            crypto::blind::rsa_blind_sign,
+            crypto::blind::rsa_blind_finalize,
+            crypto::blind::rsa_blind_verify,
+            // digest
+            crypto::digest::digest,
+            crypto::digest::xof,
+            crypto::digest::verify_digest,
             // format
             crypto::rsa::key::transfer_rsa_key,
             crypto::ecc::key::transfer_ecc_key,
             crypto::edwards::key::transfer_edwards_key,
             // kdf
             crypto::kdf::kdf,
+            crypto::calibrate::calibrate_kdf,
+            crypto::prf::prf,
+            crypto::mac::ghash,
+            crypto::mac::gcm_hash_subkey,
+            crypto::mac::cbc_mac,
+            crypto::merkle::merkle_tree,
+            crypto::merkle::merkle_audit_proof,
+            crypto::merkle::merkle_consistency_proof,
+            crypto::merkle::verify_merkle_audit_proof,
+            crypto::merkle::verify_merkle_consistency_proof,
+            crypto::pake::srp_verifier,
+            crypto::pake::srp_exchange,
+            crypto::pake::opaque_registration,
+            crypto::pake::opaque_login,
+            crypto::ratchet::ratchet_init,
+            crypto::ratchet::ratchet_encrypt,
+            crypto::ratchet::ratchet_decrypt,
+            crypto::classical::classical_cipher,
+            crypto::classical::frequency_analysis,
+            crypto::classical::crack_classical,
+            crypto::oracle::padding_oracle_demo,
+            // provider
+            provider::providers,
+            provider::crypto_provider,
+            provider::derive_provider,
+            provider::sign_provider,
+            provider::verify_provider,
             // jwt
             jwt::jws::generate_jws,
+            jwt::jws::sign_json,
+            audit::compare::compare_keys,
+            audit::compare::diff_keys,
+            audit::entropy::analyze_entropy,
+            audit::jwt::audit_jwt,
+            audit::jwt::jwt_dictionary_test,
+            audit::key::audit_key,
+            audit::nonce::detect_nonce_reuse,
+            audit::secret::inspect_secret,
+            ssh::analyze_known_hosts,
+            ssh::analyze_authorized_keys,
+            wireguard::generate_wireguard_keys,
+            wireguard::derive_wireguard_public,
+            wireguard::validate_wireguard_config,
+            signing::http::sign_http_message,
+            signing::http::verify_http_message,
+            signing::message::sign_message,
+            signing::message::verify_message,
+            signing::sigstore::verify_sigstore_bundle,
+            signing::sigv4::sign_sigv4,
+            signing::xmldsig::verify_xml_signature,
+            jwt::jcs::canonicalize_json,
             jwt::jwe::generate_jwe,
+            jwt::jwe::decrypt_jwe,
             jwt::jwk::generate_jwk,
+            jwt::jwk::import_oct_jwk,
+            jwt::fixture::generate_test_fixture,
+            jwt::jwks::rotate_jwks,
+            jwt::jws::verify_jwt,
+            jwt::discovery::oidc_discovery,
+            jwt::discovery::fetch_jwks,
+            cose::key::generate_cose_key,
+            cose::key::jwk_to_cose_key,
+            cose::key::cose_key_to_jwk,
+            cose::sign1::cose_sign1,
+            cose::sign1::cose_verify1,
+            cose::encrypt0::cose_encrypt0,
+            cose::cwt::cwt_encode,
+            cose::cwt::cwt_decode,
+            x509::sct::parse_sct,
+            x509::chain::verify_chain,
+            x509::diff::diff_certs,
+            dns::dnssec_ds,
+            dns::tlsa_record,
+            dns::dkim::generate_dkim_key,
+            dns::dkim::validate_dkim_record,
+            dns::dkim::dkim_sign,
+            dns::dkim::dkim_verify,
             // common
             codec::convert_encoding,
+            codec::cbor_decode,
+            codec::cbor_encode,
+            codec::bytes_op,
+            codec::pem::normalize_pem,
+            identify::identify,
+            keybridge::keybridge,
+            keyfile::save_key_to_file,
+            share::create_share,
+            share::open_share,
             utils::random_id,
-            utils::rsa_key_size,
-            utils::digests,
-            utils::elliptic_curve,
-            utils::edwards,
-            utils::kdfs,
-            utils::ecies_enc_alg,
-            utils::rsa_encryption_padding,
-            utils::jwkey_type,
-            utils::jwkey_algorithm,
-            utils::jwkey_usage,
-            utils::jwkey_operation,
-        ])
+            utils::capabilities,
+            selftest::run_self_tests,
+            pool::cancel_task,
+            rng::seed_rng,
+            profile::security_profile,
+            profile::set_security_profile,
+            guard::input_limits,
+            guard::set_input_limits,
+            i18n::locale,
+            i18n::set_locale,
+            pipeline::pipeline_register,
+            pipeline::pipeline_tokens,
+            pipeline::pipeline_clear,
+            vault::store_key,
+            vault::list_stored_keys,
+            vault::load_stored_key,
+            vault::remove_stored_key,
+            vault::set_key_policy,
+            vault::backup::export_vault,
+            vault::backup::import_vault,
+            vault::notes::add_note,
+            vault::notes::list_notes,
+            vault::notes::decrypt_note,
+            vault::session::unlock,
+            vault::session::lock,
+            vault::session::session_status,
+            watch::add_watch,
+            watch::list_watches,
+            watch::remove_watch,
+            watch::check_watches,
+            hsm::hsm_slots,
+            hsm::hsm_objects,
+            hsm::hsm_generate_key,
+            hsm::hsm_sign,
+            hsm::hsm_decrypt,
+            piv::piv_readers,
+            piv::piv_slots,
+            piv::piv_sign,
+            keystore::keystore_certificates,
+            keystore::keystore_import,
+        ]))
         .run(tauri::generate_context!())
         .context("error while running tauri application")?;
     Ok(())
@@ -0,0 +1,117 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
+};
+
+use anyhow::anyhow;
+use num_bigint::{BigInt, Sign};
+use tracing::info;
+
+use crate::{
+    errors::{Error, Result},
+    utils::random_bytes,
+};
+
+fn values() -> &'static RwLock<BTreeMap<String, String>> {
+    static VALUES: OnceLock<RwLock<BTreeMap<String, String>>> = OnceLock::new();
+    VALUES.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+#[tauri::command]
+pub fn pipeline_register(
+    value: String,
+    token: Option<String>,
+) -> Result<String> {
+    let token = match token {
+        Some(token)
+            if !token.is_empty()
+                && token.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+                }) =>
+        {
+            token
+        }
+        Some(token) => {
+            return Err(Error::Unsupported(format!(
+                "pipeline token `{}`",
+                token
+            )))
+        }
+        None => BigInt::from_bytes_be(Sign::Plus, &random_bytes(8)?)
+            .to_str_radix(36),
+    };
+    info!("pipeline register: {}, size: {}", token, value.len());
+    values()
+        .write()
+        .map_err(|_| anyhow!("pipeline lock poisoned"))?
+        .insert(token.clone(), value);
+    Ok(token)
+}
+
+#[tauri::command]
+pub fn pipeline_tokens() -> Result<Vec<String>> {
+    Ok(values()
+        .read()
+        .map_err(|_| anyhow!("pipeline lock poisoned"))?
+        .keys()
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub fn pipeline_clear(token: Option<String>) -> Result<()> {
+    let mut values = values()
+        .write()
+        .map_err(|_| anyhow!("pipeline lock poisoned"))?;
+    match token {
+        Some(token) => {
+            values.remove(&token);
+        }
+        None => values.clear(),
+    }
+    Ok(())
+}
+
+/// Anything that names no token is returned as is, a leading `@@` stands for
+/// a literal `@`.
+pub(crate) fn resolve(input: &str) -> Result<Cow<'_, str>> {
+    if input.starts_with("@@") {
+        return Ok(Cow::Borrowed(&input[1 ..]));
+    }
+    if let Some(token) = input.strip_prefix('@')
+        && let Some(value) = values()
+            .read()
+            .map_err(|_| anyhow!("pipeline lock poisoned"))?
+            .get(token)
+    {
+        return Ok(Cow::Owned(value.clone()));
+    }
+    Ok(Cow::Borrowed(input))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::PoisonError;
+
+    use super::{pipeline_register, resolve};
+    use crate::vault::TEST_VAULT;
+
+    #[test]
+    fn test_resolve() {
+        let _guard = TEST_VAULT.lock().unwrap_or_else(PoisonError::into_inner);
+        let token =
+            pipeline_register("value".to_string(), Some("resolve".to_string()))
+                .unwrap();
+        assert_eq!(resolve(&format!("@{}", token)).unwrap(), "value");
+        assert_eq!(resolve("@unknown").unwrap(), "@unknown");
+        assert_eq!(resolve("plain").unwrap(), "plain");
+        assert_eq!(resolve(&format!("@@{}", token)).unwrap(), "@resolve");
+        assert_eq!(resolve("@@").unwrap(), "@");
+        assert!(pipeline_register(
+            "value".to_string(),
+            Some("a b".to_string())
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,505 @@
+//! PIV (NIST SP 800-73-4) smartcards over pc/sc. The apdus live here,
+//! `card` only moves bytes.
+
+use anyhow::Context;
+use pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384};
+use tracing::info;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    x509::{encode_tlv, read_all, read_tlv, subject_public_key_info},
+};
+
+#[cfg(feature = "piv")]
+mod card;
+
+/// PIV application identifier, right truncated as the spec allows.
+const PIV_AID: &[u8] = &[0xa0, 0x00, 0x00, 0x03, 0x08, 0x00, 0x00, 0x10, 0x00];
+
+/// SHA-256 `DigestInfo` prefix from RFC 8017 section 9.2.
+const SHA256_DIGEST_INFO: &[u8] = &[
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03,
+    0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
+];
+
+const PRIMARY_SLOTS: [(u8, &str); 4] = [
+    (0x9a, "authentication"),
+    (0x9c, "signature"),
+    (0x9d, "key management"),
+    (0x9e, "card authentication"),
+];
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PivSlot {
+    /// key reference in hex, `9a` .. `9e` or a retired `82` .. `95`
+    pub slot: String,
+    pub name: String,
+    /// certificate pem, absent when the slot is empty
+    pub certificate: Option<String>,
+    /// spki pem taken from the certificate
+    pub public_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PivAlgorithm {
+    /// RSASSA-PKCS1-v1_5 with sha-256, padded here and raw rsa on card
+    #[serde(rename = "RS256")]
+    Rs256,
+    /// ECDSA P-256 with sha-256, raw r || s output
+    #[serde(rename = "ES256")]
+    Es256,
+    /// ECDSA P-384 with sha-384, raw r || s output
+    #[serde(rename = "ES384")]
+    Es384,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PivSignDto {
+    pub reader: String,
+    pub slot: String,
+    pub pin: Option<String>,
+    pub algorithm: PivAlgorithm,
+    pub input: String,
+    pub input_encoding: TextEncoding,
+    pub output_encoding: TextEncoding,
+}
+
+impl std::fmt::Debug for PivSignDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PivSignDto")
+            .field("reader", &self.reader)
+            .field("slot", &self.slot)
+            .field("algorithm", &self.algorithm)
+            .field("input_encoding", &self.input_encoding)
+            .field("output_encoding", &self.output_encoding)
+            .finish()
+    }
+}
+
+#[tauri::command]
+pub fn piv_readers() -> Result<Vec<String>> {
+    info!("piv readers");
+    Ok(card::readers()?
+        .into_iter()
+        .filter(|reader| open(reader).is_ok())
+        .collect())
+}
+
+#[tauri::command]
+pub fn piv_slots(reader: String) -> Result<Vec<PivSlot>> {
+    info!("piv slots: {}", reader);
+    let connection = open(&reader)?;
+    let mut output = Vec::new();
+    for (slot, object) in slots() {
+        let certificate = read_certificate(&connection, &object)?;
+        if certificate.is_none() && !(0x9a ..= 0x9e).contains(&slot) {
+            continue;
+        }
+        let (certificate, public_key) = match certificate {
+            Some(certificate) => (
+                Some(pem(&certificate, "CERTIFICATE")?),
+                Some(pem(
+                    subject_public_key_info(&certificate)?,
+                    "PUBLIC KEY",
+                )?),
+            ),
+            None => (None, None),
+        };
+        output.push(PivSlot {
+            slot: format!("{:02x}", slot),
+            name: slot_name(slot).unwrap_or_default(),
+            certificate,
+            public_key,
+        });
+    }
+    Ok(output)
+}
+
+/// The slot certificate tells the key type and size.
+#[tauri::command]
+pub fn piv_sign(data: PivSignDto) -> Result<String> {
+    info!("piv sign: {:?}", data);
+    let slot = parse_slot(&data.slot)?;
+    let input = data.input_encoding.decode(&data.input)?;
+    let connection = open(&data.reader)?;
+    let object = slots()
+        .find(|(id, _)| *id == slot)
+        .map(|(_, object)| object)
+        .context("piv slot without a data object")?;
+    let certificate =
+        read_certificate(&connection, &object)?.ok_or_else(|| {
+            Error::Unsupported(format!("piv slot {} is empty", data.slot))
+        })?;
+    let (reference, challenge) =
+        challenge(&certificate, data.algorithm, &input)?;
+    if let Some(pin) = &data.pin {
+        command(&connection, &verify_apdu(pin)?)?;
+    }
+    let mut response = Vec::new();
+    for apdu in authenticate_apdus(reference, slot, &challenge) {
+        response = command(&connection, &apdu)?;
+    }
+    let signature =
+        raw_signature(data.algorithm, signature_from_response(&response)?)?;
+    data.output_encoding.encode(&signature)
+}
+
+fn open(reader: &str) -> Result<card::Connection> {
+    let connection = card::connect(reader)?;
+    command(&connection, &select_apdu())?;
+    Ok(connection)
+}
+
+/// Follows `61xx` with GET RESPONSE.
+fn exchange(
+    connection: &card::Connection,
+    apdu: &[u8],
+) -> Result<(Vec<u8>, u16)> {
+    let mut data = Vec::new();
+    let mut apdu = apdu.to_vec();
+    loop {
+        let mut response = connection.transmit(&apdu)?;
+        if response.len() < 2 {
+            return Err(Error::Unsupported(
+                "card response without status".to_string(),
+            ));
+        }
+        let status = response.split_off(response.len() - 2);
+        data.extend(response);
+        if status[0] != 0x61 {
+            return Ok((data, u16::from_be_bytes([status[0], status[1]])));
+        }
+        apdu = vec![0x00, 0xc0, 0x00, 0x00, status[1]];
+    }
+}
+
+fn command(connection: &card::Connection, apdu: &[u8]) -> Result<Vec<u8>> {
+    match exchange(connection, apdu)? {
+        (data, 0x9000) => Ok(data),
+        (_, status) => Err(status_error(status)),
+    }
+}
+
+fn read_certificate(
+    connection: &card::Connection,
+    object: &[u8; 3],
+) -> Result<Option<Vec<u8>>> {
+    match exchange(connection, &get_data_apdu(object))? {
+        (data, 0x9000) => certificate_from_object(&data).map(Some),
+        (_, 0x6a82) => Ok(None),
+        (_, status) => Err(status_error(status)),
+    }
+}
+
+fn pem(der: &[u8], label: &str) -> Result<String> {
+    Ok(
+        pem_rfc7468::encode_string(label, pkcs8::LineEnding::LF, der)
+            .context("encode pem failed")?,
+    )
+}
+
+fn slots() -> impl Iterator<Item = (u8, [u8; 3])> {
+    let primary = [(0x9a, 0x05), (0x9c, 0x0a), (0x9d, 0x0b), (0x9e, 0x01)];
+    let retired = (0x82 ..= 0x95).map(|slot| (slot, slot - 0x82 + 0x0d));
+    primary
+        .into_iter()
+        .chain(retired)
+        .map(|(slot, object)| (slot, [0x5f, 0xc1, object]))
+}
+
+fn slot_name(slot: u8) -> Option<String> {
+    match PRIMARY_SLOTS.iter().find(|(id, _)| *id == slot) {
+        Some((_, name)) => Some(name.to_string()),
+        None => (0x82 ..= 0x95)
+            .contains(&slot)
+            .then(|| format!("retired {}", slot - 0x81)),
+    }
+}
+
+fn parse_slot(slot: &str) -> Result<u8> {
+    let id = u8::from_str_radix(slot.trim_start_matches("0x"), 16)
+        .map_err(|_| Error::Unsupported(format!("piv slot {}", slot)))?;
+    slot_name(id)
+        .map(|_| id)
+        .ok_or_else(|| Error::Unsupported(format!("piv slot {}", slot)))
+}
+
+fn select_apdu() -> Vec<u8> {
+    let mut apdu = vec![0x00, 0xa4, 0x04, 0x00, PIV_AID.len() as u8];
+    apdu.extend_from_slice(PIV_AID);
+    apdu
+}
+
+fn get_data_apdu(object: &[u8; 3]) -> Vec<u8> {
+    let mut apdu = vec![0x00, 0xcb, 0x3f, 0xff, 0x05, 0x5c, 0x03];
+    apdu.extend_from_slice(object);
+    apdu.push(0x00);
+    apdu
+}
+
+fn verify_apdu(pin: &str) -> Result<Vec<u8>> {
+    if !(6 ..= 8).contains(&pin.len()) {
+        return Err(Error::Unsupported(
+            "piv pin must be 6 to 8 characters".to_string(),
+        ));
+    }
+    let mut apdu = vec![0x00, 0x20, 0x00, 0x80, 0x08];
+    apdu.extend_from_slice(pin.as_bytes());
+    apdu.resize(13, 0xff);
+    Ok(apdu)
+}
+
+/// Command chaining splits what does not fit one short apdu.
+fn authenticate_apdus(
+    algorithm: u8,
+    slot: u8,
+    challenge: &[u8],
+) -> Vec<Vec<u8>> {
+    let mut template = encode_tlv(0x82, &[]);
+    template.extend(encode_tlv(0x81, challenge));
+    let data = encode_tlv(0x7c, &template);
+    let chunks = data.chunks(255).collect::<Vec<_>>();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            let class = if index + 1 < chunks.len() { 0x10 } else { 0x00 };
+            let mut apdu =
+                vec![class, 0x87, algorithm, slot, chunk.len() as u8];
+            apdu.extend_from_slice(chunk);
+            apdu.push(0x00);
+            apdu
+        })
+        .collect()
+}
+
+fn status_error(status: u16) -> Error {
+    Error::Unsupported(match status {
+        0x63c0 ..= 0x63cf => {
+            format!("wrong piv pin, {} tries left", status & 0x0f)
+        }
+        0x6983 => "piv pin is blocked".to_string(),
+        0x6982 => "security status not satisfied, verify the pin".to_string(),
+        0x6a82 => "piv object not found".to_string(),
+        0x6a80 | 0x6a86 => "card rejected the piv request".to_string(),
+        status => format!("card returned status {:04x}", status),
+    })
+}
+
+/// The certificate in a `53 { 70 cert, 71 info, fe lrc }` data object.
+fn certificate_from_object(object: &[u8]) -> Result<Vec<u8>> {
+    let (object, _) = read_tlv(object)?;
+    if object.tag != 0x53 {
+        return Err(Error::Unsupported(
+            "malformed piv data object".to_string(),
+        ));
+    }
+    let elements = read_all(object.content)?;
+    let compressed = elements
+        .iter()
+        .find(|element| element.tag == 0x71)
+        .is_some_and(|info| info.content.first() == Some(&0x01));
+    if compressed {
+        return Err(Error::Unsupported(
+            "compressed piv certificate".to_string(),
+        ));
+    }
+    elements
+        .into_iter()
+        .find(|element| element.tag == 0x70)
+        .map(|certificate| certificate.content.to_vec())
+        .ok_or_else(|| {
+            Error::Unsupported("piv object holds no certificate".to_string())
+        })
+}
+
+/// The signature in a `7c { 82 signature }` response template.
+fn signature_from_response(response: &[u8]) -> Result<Vec<u8>> {
+    let (template, _) = read_tlv(response)?;
+    if template.tag != 0x7c {
+        return Err(Error::Unsupported(
+            "malformed general authenticate response".to_string(),
+        ));
+    }
+    read_all(template.content)?
+        .into_iter()
+        .find(|element| element.tag == 0x82)
+        .map(|signature| signature.content.to_vec())
+        .ok_or_else(|| {
+            Error::Unsupported("card returned no signature".to_string())
+        })
+}
+
+fn pkcs1_encode(digest: &[u8], size: usize) -> Result<Vec<u8>> {
+    let length = SHA256_DIGEST_INFO.len() + digest.len();
+    if size < length + 11 {
+        return Err(Error::Unsupported(format!(
+            "rsa modulus of {} bytes",
+            size
+        )));
+    }
+    let mut encoded = vec![0x00, 0x01];
+    encoded.resize(size - length - 1, 0xff);
+    encoded.push(0x00);
+    encoded.extend_from_slice(SHA256_DIGEST_INFO);
+    encoded.extend_from_slice(digest);
+    Ok(encoded)
+}
+
+/// The padded digest for rsa, the bare digest for ecdsa.
+fn challenge(
+    certificate: &[u8],
+    algorithm: PivAlgorithm,
+    input: &[u8],
+) -> Result<(u8, Vec<u8>)> {
+    let spki = subject_public_key_info(certificate)?;
+    let mismatch = || {
+        Error::Unsupported(format!(
+            "{:?} does not match the slot key",
+            algorithm
+        ))
+    };
+    match algorithm {
+        PivAlgorithm::Rs256 => {
+            let key = rsa::RsaPublicKey::from_public_key_der(spki)
+                .map_err(|_| mismatch())?;
+            let reference = match key.size() * 8 {
+                1024 => 0x06,
+                2048 => 0x07,
+                3072 => 0x05,
+                4096 => 0x16,
+                bits => {
+                    return Err(Error::Unsupported(format!(
+                        "piv rsa key of {} bits",
+                        bits
+                    )))
+                }
+            };
+            Ok((reference, pkcs1_encode(&Sha256::digest(input), key.size())?))
+        }
+        PivAlgorithm::Es256 => {
+            p256::PublicKey::from_public_key_der(spki)
+                .map_err(|_| mismatch())?;
+            Ok((0x11, Sha256::digest(input).to_vec()))
+        }
+        PivAlgorithm::Es384 => {
+            p384::PublicKey::from_public_key_der(spki)
+                .map_err(|_| mismatch())?;
+            Ok((0x14, Sha384::digest(input).to_vec()))
+        }
+    }
+}
+
+fn raw_signature(
+    algorithm: PivAlgorithm,
+    signature: Vec<u8>,
+) -> Result<Vec<u8>> {
+    Ok(match algorithm {
+        PivAlgorithm::Rs256 => signature,
+        PivAlgorithm::Es256 => p256::ecdsa::Signature::from_der(&signature)
+            .context("invalid p-256 signature from card")?
+            .to_bytes()
+            .to_vec(),
+        PivAlgorithm::Es384 => p384::ecdsa::Signature::from_der(&signature)
+            .context("invalid p-384 signature from card")?
+            .to_bytes()
+            .to_vec(),
+    })
+}
+
+#[cfg(not(feature = "piv"))]
+mod card {
+    use crate::errors::{Error, Result};
+
+    pub(super) enum Connection {}
+
+    impl Connection {
+        pub(super) fn transmit(&self, _apdu: &[u8]) -> Result<Vec<u8>> {
+            match *self {}
+        }
+    }
+
+    fn disabled<T>() -> Result<T> {
+        Err(Error::Unsupported(
+            "pc/sc, kits was built without the `piv` feature".to_string(),
+        ))
+    }
+
+    pub(super) fn readers() -> Result<Vec<String>> {
+        disabled()
+    }
+
+    pub(super) fn connect(_reader: &str) -> Result<Connection> {
+        disabled()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        authenticate_apdus, certificate_from_object, parse_slot, pkcs1_encode,
+        signature_from_response, slots, verify_apdu,
+    };
+    use crate::enums::TextEncoding;
+
+    #[test]
+    fn test_piv_apdus() {
+        assert_eq!(parse_slot("9c").unwrap(), 0x9c);
+        assert_eq!(parse_slot("95").unwrap(), 0x95);
+        assert!(parse_slot("9b").is_err());
+        assert_eq!(slots().count(), 24);
+        assert_eq!(slots().find(|(slot, _)| *slot == 0x95).unwrap().1, [
+            0x5f, 0xc1, 0x20
+        ]);
+
+        assert_eq!(
+            TextEncoding::Hex
+                .encode(&verify_apdu("123456").unwrap())
+                .unwrap(),
+            "0020008008313233343536ffff"
+        );
+        assert!(verify_apdu("1234").is_err());
+
+        let apdus = authenticate_apdus(0x11, 0x9c, &[0xaa; 32]);
+        assert_eq!(apdus.len(), 1);
+        assert_eq!(
+            TextEncoding::Hex.encode(&apdus[0][.. 11]).unwrap(),
+            "0087119c267c2482008120"
+        );
+        let apdus = authenticate_apdus(0x07, 0x9a, &[0xaa; 256]);
+        assert_eq!(apdus.len(), 2);
+        assert_eq!(apdus[0][0], 0x10);
+        assert_eq!(apdus[1][0], 0x00);
+        assert_eq!(apdus[0][4] as usize + apdus[1][4] as usize, 266);
+    }
+
+    #[test]
+    fn test_piv_objects() {
+        let object = TextEncoding::Hex
+            .decode("530a7003300100710100fe00")
+            .unwrap();
+        assert_eq!(certificate_from_object(&object).unwrap(), [
+            0x30, 0x01, 0x00
+        ]);
+        let compressed = TextEncoding::Hex
+            .decode("530a7003300100710101fe00")
+            .unwrap();
+        assert!(certificate_from_object(&compressed).is_err());
+
+        let response = TextEncoding::Hex.decode("7c048202abcd").unwrap();
+        assert_eq!(signature_from_response(&response).unwrap(), [0xab, 0xcd]);
+
+        let encoded = pkcs1_encode(&[0x11; 32], 256).unwrap();
+        assert_eq!(encoded.len(), 256);
+        assert_eq!(encoded[.. 3], [0x00, 0x01, 0xff]);
+        assert_eq!(encoded[256 - 52], 0x00);
+        assert!(encoded.ends_with(&[0x11; 32]));
+        assert!(pkcs1_encode(&[0x11; 32], 32).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use std::ffi::CString;
+
+use anyhow::Context;
+use pcsc::{Card, Protocols, Scope, ShareMode, MAX_BUFFER_SIZE};
+
+use crate::errors::{Error, Result};
+
+pub(super) struct Connection(Card);
+
+impl Connection {
+    pub(super) fn transmit(&self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let mut buffer = [0u8; MAX_BUFFER_SIZE];
+        Ok(self
+            .0
+            .transmit(apdu, &mut buffer)
+            .context("pc/sc transmit failed")?
+            .to_vec())
+    }
+}
+
+fn establish() -> Result<pcsc::Context> {
+    Ok(pcsc::Context::establish(Scope::User)
+        .context("establish pc/sc context failed")?)
+}
+
+pub(super) fn readers() -> Result<Vec<String>> {
+    match establish()?.list_readers_owned() {
+        Ok(readers) => Ok(readers
+            .into_iter()
+            .map(|reader| reader.to_string_lossy().into_owned())
+            .collect()),
+        Err(pcsc::Error::NoReadersAvailable) => Ok(vec![]),
+        Err(error) => Err(anyhow::Error::new(error)
+            .context("list pc/sc readers failed")
+            .into()),
+    }
+}
+
+pub(super) fn connect(reader: &str) -> Result<Connection> {
+    let name = CString::new(reader)
+        .map_err(|_| Error::Unsupported(format!("pc/sc reader {}", reader)))?;
+    let card = establish()?
+        .connect(&name, ShareMode::Shared, Protocols::ANY)
+        .context(format!("connect to {} failed", reader))?;
+    Ok(Connection(card))
+}
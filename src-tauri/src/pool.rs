@@ -0,0 +1,163 @@
+//! The crypto thread pool the batch commands, jwk generation, jws and jwe
+//! run on. `KITS_POOL_SIZE` caps its threads.
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, OnceLock,
+    },
+};
+
+use anyhow::{anyhow, Context};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::Notify;
+use tracing::info;
+
+use crate::errors::Result;
+
+const POOL_SIZE_ENV: &str = "KITS_POOL_SIZE";
+
+static POOL: OnceLock<ThreadPool> = OnceLock::new();
+/// running cancellable tasks by the id their caller named them with
+static TASKS: Mutex<BTreeMap<String, Arc<Cancel>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Work in progress is not interrupted, the task stops at its next `check`
+/// and the call returns at once.
+#[derive(Default)]
+pub struct Cancel {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl Cancel {
+    pub fn check(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::Acquire) {
+            return Err(anyhow!("task cancelled").into());
+        }
+        Ok(())
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        // a stored permit, the waiter may not be polled yet
+        self.notify.notify_one();
+    }
+}
+
+fn pool_size(configured: Option<&str>, available: usize) -> usize {
+    configured
+        .and_then(|size| size.trim().parse::<usize>().ok())
+        .filter(|size| *size > 0)
+        .map(|size| size.min(available))
+        .unwrap_or(available)
+}
+
+pub fn pool() -> &'static ThreadPool {
+    POOL.get_or_init(|| {
+        let size = pool_size(
+            std::env::var(POOL_SIZE_ENV).ok().as_deref(),
+            std::thread::available_parallelism()
+                .map(NonZeroUsize::get)
+                .unwrap_or(1),
+        );
+        tracing::info!("initial crypto thread pool, size: {}", size);
+        ThreadPoolBuilder::new()
+            .num_threads(size)
+            .thread_name(|index| format!("kits-crypto-{}", index))
+            .build()
+            .expect("build crypto thread pool failed")
+    })
+}
+
+/// Rayon iterators inside `f` are bounded by the same pool.
+pub async fn spawn<F, T>(f: F) -> Result<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    pool().spawn(move || {
+        let _ = tx.send(f());
+    });
+    Ok(rx.await.context("crypto pool task canceled")?)
+}
+
+pub async fn spawn_cancellable<F, T>(task_id: Option<String>, f: F) -> Result<T>
+where
+    F: FnOnce(&Cancel) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let cancel = Arc::new(Cancel::default());
+    if let Some(task_id) = &task_id {
+        tasks()?.insert(task_id.clone(), cancel.clone());
+    }
+    let worker = cancel.clone();
+    let result = tokio::select! {
+        result = spawn(move || f(&worker)) => result.and_then(|result| result),
+        _ = cancel.notify.notified() => Err(anyhow!("task cancelled").into()),
+    };
+    if let Some(task_id) = task_id {
+        let mut tasks = tasks()?;
+        // the id may have been taken over by a later call
+        if tasks
+            .get(&task_id)
+            .is_some_and(|registered| Arc::ptr_eq(registered, &cancel))
+        {
+            tasks.remove(&task_id);
+        }
+    }
+    result
+}
+
+fn tasks() -> Result<MutexGuard<'static, BTreeMap<String, Arc<Cancel>>>> {
+    Ok(TASKS.lock().map_err(|_| anyhow!("tasks lock poisoned"))?)
+}
+
+#[tauri::command]
+pub fn cancel_task(task_id: String) -> Result<bool> {
+    let cancel = tasks()?.remove(&task_id);
+    info!("cancel task: {}, running: {}", task_id, cancel.is_some());
+    if let Some(cancel) = &cancel {
+        cancel.cancel();
+    }
+    Ok(cancel.is_some())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{cancel_task, pool_size, spawn_cancellable};
+
+    #[test]
+    fn test_pool_size() {
+        assert_eq!(pool_size(None, 8), 8);
+        assert_eq!(pool_size(Some("2"), 8), 2);
+        assert_eq!(pool_size(Some(" 4\n"), 8), 4);
+        // capped at the available parallelism
+        assert_eq!(pool_size(Some("64"), 8), 8);
+        for invalid in ["0", "-1", "two", ""] {
+            assert_eq!(pool_size(Some(invalid), 8), 8);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_task() {
+        let task_id = "test_cancel_task".to_string();
+        let task =
+            tokio::spawn(spawn_cancellable(Some(task_id.clone()), |cancel| {
+                std::thread::sleep(Duration::from_millis(500));
+                cancel.check()
+            }));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(cancel_task(task_id.clone()).unwrap());
+        assert!(task.await.unwrap().is_err());
+        assert!(!cancel_task(task_id).unwrap());
+
+        let done = spawn_cancellable(None, |cancel| cancel.check().map(|_| 1));
+        assert_eq!(done.await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,138 @@
+//! App wide security profile: `legacy` keeps everything, `strict` drops
+//! what is broken or misuse prone, `fips` keeps FIPS 140-3 approved
+//! algorithms only.
+
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    enums::{Digest, EccCurveName, EncryptionMode, Kdf, RsaEncryptionPadding},
+    errors::{Error, Result},
+};
+
+static PROFILE: RwLock<Profile> = RwLock::new(Profile::Legacy);
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Legacy,
+    Strict,
+    Fips,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Algorithm {
+    Digest(Digest),
+    RsaKeySize(usize),
+    AesMode(EncryptionMode),
+    RsaPadding(RsaEncryptionPadding),
+    EllipticCurve(EccCurveName),
+    Kdf(Kdf),
+}
+
+impl Profile {
+    /// `None` when the profile allows it.
+    pub(crate) fn rejection(
+        &self,
+        algorithm: Algorithm,
+    ) -> Option<&'static str> {
+        let strict = matches!(self, Profile::Strict | Profile::Fips);
+        let fips = *self == Profile::Fips;
+        match algorithm {
+            Algorithm::Digest(Digest::Sha1) if strict => {
+                Some("sha-1 collisions are practical")
+            }
+            Algorithm::Digest(Digest::Keccak256) if fips => {
+                Some("keccak-256 is not a fips 202 function")
+            }
+            Algorithm::RsaKeySize(bits) if strict && bits < 2048 => {
+                Some("rsa below 2048 bits is factorable")
+            }
+            Algorithm::AesMode(EncryptionMode::Ecb) if strict => {
+                Some("ecb leaks plaintext patterns")
+            }
+            Algorithm::RsaPadding(RsaEncryptionPadding::Pkcs1v15) if strict => {
+                Some("pkcs#1 v1.5 encryption is open to padding oracles")
+            }
+            Algorithm::EllipticCurve(
+                EccCurveName::Secp256k1 | EccCurveName::SM2,
+            ) if fips => Some("not a sp 800-186 curve"),
+            Algorithm::Kdf(Kdf::Scrypt) if fips => {
+                Some("scrypt is not a sp 800-132 kdf")
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn allows(&self, algorithm: Algorithm) -> bool {
+        self.rejection(algorithm).is_none()
+    }
+}
+
+#[tauri::command]
+pub fn security_profile() -> Profile {
+    current()
+}
+
+#[tauri::command]
+pub fn set_security_profile(profile: Profile) -> Result<()> {
+    warn!("security profile changed to {:?}", profile);
+    *PROFILE
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = profile;
+    Ok(())
+}
+
+pub(crate) fn current() -> Profile {
+    *PROFILE
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub(crate) fn check(algorithm: Algorithm) -> Result<()> {
+    let profile = current();
+    match profile.rejection(algorithm) {
+        Some(reason) => Err(Error::Unsupported(format!(
+            "{:?} under the {:?} profile, {}",
+            algorithm, profile, reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Algorithm, Profile};
+    use crate::enums::{Digest, EccCurveName, EncryptionMode};
+
+    #[test]
+    fn test_profiles() {
+        let ecb = Algorithm::AesMode(EncryptionMode::Ecb);
+        assert!(Profile::Legacy.allows(ecb));
+        assert!(!Profile::Strict.allows(ecb));
+        assert!(!Profile::Fips.allows(ecb));
+
+        let k256 = Algorithm::EllipticCurve(EccCurveName::Secp256k1);
+        assert!(Profile::Strict.allows(k256));
+        assert!(!Profile::Fips.allows(k256));
+
+        assert!(!Profile::Strict.allows(Algorithm::RsaKeySize(1024)));
+        assert!(Profile::Strict.allows(Algorithm::RsaKeySize(2048)));
+        assert!(!Profile::Strict.allows(Algorithm::Digest(Digest::Sha1)));
+        assert!(Profile::Fips.allows(Algorithm::Digest(Digest::Sha3_256)));
+    }
+}
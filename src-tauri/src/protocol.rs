@@ -0,0 +1,230 @@
+//! The `kits` uri scheme the webview hands raw bytes through: the request
+//! body is the input, the response body the output, the other arguments go
+//! as base64 json in the `x-kits-params` header.
+
+use anyhow::Context;
+use base64ct::{Base64, Encoding};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tauri::{
+    http::{Request, Response, ResponseBuilder},
+    utils::config::{AppUrl, WindowUrl},
+    AppHandle, Manager, Runtime,
+};
+use tracing::info;
+
+use crate::{
+    crypto::{aes, digest, ecc, rsa},
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+    guard,
+    profile::{self, Algorithm},
+    vault::session::Session,
+};
+
+pub const SCHEME: &str = "kits";
+const PARAMS_HEADER: &str = "x-kits-params";
+/// where the bundled frontend is served from
+const APP_ORIGIN: &str = if cfg!(windows) {
+    "https://tauri.localhost"
+} else {
+    "tauri://localhost"
+};
+
+#[derive(Deserialize)]
+struct DigestParams {
+    digest: Digest,
+}
+
+#[derive(Deserialize)]
+struct EncodingParams {
+    encoding: TextEncoding,
+}
+
+pub fn handle<R: Runtime>(
+    app: &AppHandle<R>,
+    request: &Request,
+) -> std::result::Result<Response, Box<dyn std::error::Error>> {
+    let command = command_of(request.uri());
+    let input = request.body();
+    let output = params(request).and_then(|params| {
+        guard::check_with_input(command, &params, input.len())?;
+        guard::throttle(command)?;
+        app.state::<Session>().enter(command)?;
+        dispatch(command, params, input)
+    });
+    let mut response = ResponseBuilder::new().header("Vary", "Origin");
+    if let Some(origin) = request
+        .headers()
+        .get("origin")
+        .and_then(|origin| origin.to_str().ok())
+        .filter(|origin| allowed_origin(origin, dev_origin(app).as_deref()))
+    {
+        response = response.header("Access-Control-Allow-Origin", origin);
+    }
+    match output {
+        Ok(output) => {
+            response.mimetype("application/octet-stream").body(output)
+        }
+        Err(err) => response
+            .status(match &err {
+                Error::Unsupported(_) => 400,
+                _ => 500,
+            })
+            .mimetype("application/json")
+            .body(serde_json::to_vec(
+                &json!({ "code": err.code(), "error": err }),
+            )?),
+    }
+}
+
+/// The dtos are those of the commands, `input` in place of theirs.
+pub(crate) fn dispatch(
+    command: &str,
+    params: Value,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    info!("{} over {}, size: {}", command, SCHEME, input.len());
+    match command {
+        "crypto_aes" => aes::crypto_aes_inner(&from_params(params)?, input),
+        "crypto_rsa" => rsa::crypto_rsa_inner(&from_params(params)?, input),
+        "ecies" => ecc::ecies_dispatch(&from_params(params)?, input),
+        "digest" => {
+            let DigestParams { digest } = from_params(params)?;
+            profile::check(Algorithm::Digest(digest))?;
+            Ok(digest::digest_inner(digest, input))
+        }
+        "encode" => {
+            let EncodingParams { encoding } = from_params(params)?;
+            encoding.encode(input).map(String::into_bytes)
+        }
+        "decode" => {
+            let EncodingParams { encoding } = from_params(params)?;
+            encoding.decode(
+                std::str::from_utf8(input).context("input is not utf-8")?,
+            )
+        }
+        _ => Err(Error::Unsupported(format!(
+            "{} over the {} scheme",
+            command, SCHEME
+        ))),
+    }
+}
+
+/// The app itself, and the dev server in debug builds.
+fn allowed_origin(origin: &str, dev: Option<&str>) -> bool {
+    origin == APP_ORIGIN || (cfg!(debug_assertions) && dev == Some(origin))
+}
+
+fn dev_origin<R: Runtime>(app: &AppHandle<R>) -> Option<String> {
+    match &app.config().build.dev_path {
+        AppUrl::Url(WindowUrl::External(url)) => {
+            Some(url.origin().ascii_serialization())
+        }
+        _ => None,
+    }
+}
+
+fn command_of(uri: &str) -> &str {
+    let path = uri.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/').next().unwrap_or_default()
+}
+
+fn params(request: &Request) -> Result<Value> {
+    let Some(header) = request.headers().get(PARAMS_HEADER) else {
+        return Ok(json!({}));
+    };
+    let params = header
+        .to_str()
+        .ok()
+        .and_then(|header| Base64::decode_vec(header).ok())
+        .ok_or_else(|| {
+            Error::Unsupported(format!("{} not in base64", PARAMS_HEADER))
+        })?;
+    Ok(serde_json::from_slice(&params).context("malformed params")?)
+}
+
+fn from_params<T: DeserializeOwned>(params: Value) -> Result<T> {
+    Ok(serde_json::from_value(params).context("malformed params")?)
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64, Encoding};
+    use serde_json::json;
+
+    use super::{allowed_origin, command_of, dispatch, APP_ORIGIN};
+    use crate::{crypto::digest::digest_inner, enums::Digest};
+
+    #[test]
+    fn test_command_of() {
+        assert_eq!(command_of("kits://localhost/digest"), "digest");
+        assert_eq!(
+            command_of("https://kits.localhost/crypto_aes?t=1"),
+            "crypto_aes"
+        );
+    }
+
+    #[test]
+    fn test_allowed_origin() {
+        let dev = Some("http://localhost:11420");
+        assert!(allowed_origin(APP_ORIGIN, dev));
+        assert_eq!(
+            allowed_origin("http://localhost:11420", dev),
+            cfg!(debug_assertions)
+        );
+        assert!(!allowed_origin("http://localhost:11420", None));
+        assert!(!allowed_origin("https://example.com", dev));
+        assert!(!allowed_origin("null", dev));
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let input = (0 .. 256 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+        // what the same input costs as an invoke argument
+        let numbers = serde_json::to_vec(&input).unwrap().len();
+        let base64 = serde_json::to_vec(&Base64::encode_string(&input))
+            .unwrap()
+            .len();
+        assert!(numbers > 3 * input.len());
+        assert!(base64 > input.len() * 4 / 3);
+
+        assert_eq!(
+            dispatch("digest", json!({"digest": "sha256"}), &input).unwrap(),
+            digest_inner(Digest::Sha256, &input)
+        );
+
+        let aes = |for_encryption, input: &[u8]| {
+            dispatch(
+                "crypto_aes",
+                json!({
+                    "input": "",
+                    "inputEncoding": "base64",
+                    "key": "00112233445566778899aabbccddeeff",
+                    "keyEncoding": "hex",
+                    "outputEncoding": "base64",
+                    "mode": "GCM",
+                    "padding": "NoPadding",
+                    "iv": "000102030405060708090a0b",
+                    "ivEncoding": "hex",
+                    "forEncryption": for_encryption,
+                }),
+                input,
+            )
+            .unwrap()
+        };
+        let ciphertext = aes(true, &input);
+        // the body is the ciphertext and its tag, nothing more
+        assert_eq!(ciphertext.len(), input.len() + 16);
+        assert_eq!(aes(false, &ciphertext), input);
+
+        let encoded =
+            dispatch("encode", json!({"encoding": "hex"}), b"kits").unwrap();
+        assert_eq!(encoded, b"6b697473");
+        assert_eq!(
+            dispatch("decode", json!({"encoding": "hex"}), &encoded).unwrap(),
+            b"kits"
+        );
+        assert!(dispatch("unknown", json!({}), &input).is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use anyhow::Context;
+use ed25519_dalek::{Signer, Verifier};
+use pkcs8::{DecodePrivateKey, DecodePublicKey};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tracing::info;
+
+use crate::{
+    add_encryption_trait_impl,
+    crypto::{
+        aes::encrypt_or_decrypt_aes, kdf::kdf_inner_digest, EncryptionDto,
+    },
+    enums::{AesEncryptionPadding, Digest, EncryptionMode, Kdf, TextEncoding},
+    errors::{Error, Result},
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct CipherParams {
+    pub iv: Option<Vec<u8>>,
+    pub aad: Option<Vec<u8>>,
+}
+
+pub trait CipherProvider: Send + Sync {
+    fn name(&self) -> String;
+
+    fn encrypt(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+    ) -> Result<Vec<u8>>;
+
+    fn decrypt(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+    ) -> Result<Vec<u8>>;
+}
+
+pub trait KdfProvider: Send + Sync {
+    fn name(&self) -> String;
+
+    fn derive(
+        &self,
+        input: &[u8],
+        salt: Option<Vec<u8>>,
+        info: Option<Vec<u8>>,
+        key_length: usize,
+    ) -> Result<Vec<u8>>;
+}
+
+pub trait SignatureProvider: Send + Sync {
+    fn name(&self) -> String;
+
+    fn sign(&self, key: &[u8], message: &[u8]) -> Result<Vec<u8>>;
+
+    fn verify(
+        &self,
+        key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool>;
+}
+
+#[derive(Default)]
+pub struct Registry {
+    ciphers: BTreeMap<String, Arc<dyn CipherProvider>>,
+    kdfs: BTreeMap<String, Arc<dyn KdfProvider>>,
+    signatures: BTreeMap<String, Arc<dyn SignatureProvider>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProviderCatalog {
+    ciphers: Vec<String>,
+    kdfs: Vec<String>,
+    signatures: Vec<String>,
+}
+
+impl Registry {
+    pub fn register_cipher(&mut self, provider: Arc<dyn CipherProvider>) {
+        info!("register cipher provider: {}", provider.name());
+        self.ciphers.insert(provider.name(), provider);
+    }
+
+    pub fn register_kdf(&mut self, provider: Arc<dyn KdfProvider>) {
+        info!("register kdf provider: {}", provider.name());
+        self.kdfs.insert(provider.name(), provider);
+    }
+
+    pub fn register_signature(&mut self, provider: Arc<dyn SignatureProvider>) {
+        info!("register signature provider: {}", provider.name());
+        self.signatures.insert(provider.name(), provider);
+    }
+
+    pub fn cipher(&self, name: &str) -> Result<Arc<dyn CipherProvider>> {
+        self.ciphers
+            .get(name)
+            .cloned()
+            .ok_or(Error::Unsupported(format!("cipher provider {}", name)))
+    }
+
+    pub fn kdf(&self, name: &str) -> Result<Arc<dyn KdfProvider>> {
+        self.kdfs
+            .get(name)
+            .cloned()
+            .ok_or(Error::Unsupported(format!("kdf provider {}", name)))
+    }
+
+    pub fn signature(&self, name: &str) -> Result<Arc<dyn SignatureProvider>> {
+        self.signatures
+            .get(name)
+            .cloned()
+            .ok_or(Error::Unsupported(format!("signature provider {}", name)))
+    }
+
+    pub fn catalog(&self) -> ProviderCatalog {
+        ProviderCatalog {
+            ciphers: self.ciphers.keys().cloned().collect(),
+            kdfs: self.kdfs.keys().cloned().collect(),
+            signatures: self.signatures.keys().cloned().collect(),
+        }
+    }
+
+    fn builtin() -> Self {
+        let mut registry = Registry::default();
+        for mode in EncryptionMode::iter() {
+            registry.register_cipher(Arc::new(AesProvider(mode)));
+        }
+        for kdf in Kdf::iter() {
+            for digest in Digest::iter() {
+                registry
+                    .register_kdf(Arc::new(BuiltinKdfProvider(kdf, digest)));
+            }
+        }
+        registry.register_signature(Arc::new(Ed25519Provider));
+        #[cfg(feature = "gm")]
+        crate::jwt::gm::register(&mut registry);
+        registry
+    }
+}
+
+pub fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(Registry::builtin()))
+}
+
+fn read_registry() -> std::sync::RwLockReadGuard<'static, Registry> {
+    registry()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub(crate) fn cipher(name: &str) -> Result<Arc<dyn CipherProvider>> {
+    read_registry().cipher(name)
+}
+
+pub(crate) fn signature(name: &str) -> Result<Arc<dyn SignatureProvider>> {
+    read_registry().signature(name)
+}
+
+struct AesProvider(EncryptionMode);
+
+impl AesProvider {
+    fn crypto(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+        for_encryption: bool,
+    ) -> Result<Vec<u8>> {
+        let padding = match self.0 {
+            EncryptionMode::Gcm
+            | EncryptionMode::Ccm
+            | EncryptionMode::Siv
+            | EncryptionMode::Xts => AesEncryptionPadding::NoPadding,
+            _ => AesEncryptionPadding::Pkcs7Padding,
+        };
+        if !matches!(self.0, EncryptionMode::Ecb | EncryptionMode::Siv)
+            && params.iv.is_none()
+        {
+            return Err(Error::Unsupported("iv is required".to_string()));
+        }
+        encrypt_or_decrypt_aes(
+            self.0,
+            input,
+            key,
+            params.iv.clone(),
+            params.aad.clone(),
+            padding,
+            for_encryption,
+        )
+    }
+}
+
+impl CipherProvider for AesProvider {
+    fn decrypt(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+    ) -> Result<Vec<u8>> {
+        self.crypto(key, input, params, false)
+    }
+
+    fn encrypt(
+        &self,
+        key: &[u8],
+        input: &[u8],
+        params: &CipherParams,
+    ) -> Result<Vec<u8>> {
+        self.crypto(key, input, params, true)
+    }
+
+    fn name(&self) -> String {
+        format!("AES-{:?}", self.0).to_uppercase()
+    }
+}
+
+struct BuiltinKdfProvider(Kdf, Digest);
+
+impl KdfProvider for BuiltinKdfProvider {
+    fn derive(
+        &self,
+        input: &[u8],
+        salt: Option<Vec<u8>>,
+        info: Option<Vec<u8>>,
+        key_length: usize,
+    ) -> Result<Vec<u8>> {
+        kdf_inner_digest(self.0, self.1, input, salt, info, key_length)
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}-{:?}", self.0, self.1).to_lowercase()
+    }
+}
+
+struct Ed25519Provider;
+
+impl SignatureProvider for Ed25519Provider {
+    fn name(&self) -> String {
+        "Ed25519".to_string()
+    }
+
+    fn sign(&self, key: &[u8], message: &[u8]) -> Result<Vec<u8>> {
+        let signing_key = ed25519_dalek::SigningKey::from_pkcs8_der(key)
+            .context("invalid ed25519 pkcs8 der private key")?;
+        Ok(signing_key.sign(message).to_vec())
+    }
+
+    fn verify(
+        &self,
+        key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool> {
+        let verifying_key =
+            ed25519_dalek::VerifyingKey::from_public_key_der(key)
+                .context("invalid ed25519 spki der public key")?;
+        let signature = ed25519_dalek::Signature::from_slice(signature)
+            .context("invalid ed25519 signature")?;
+        Ok(verifying_key.verify(message, &signature).is_ok())
+    }
+}
+
+/// Exported by every dynamic plugin, built with the same toolchain as kits
+/// since the registry crosses the boundary as a Rust type.
+#[cfg(feature = "plugins")]
+pub type PluginRegister = unsafe fn(&mut Registry);
+
+#[cfg(feature = "plugins")]
+pub const PLUGIN_REGISTER_SYMBOL: &[u8] = b"kits_plugin_register";
+
+#[cfg(feature = "plugins")]
+pub fn load_plugins(dir: &std::path::Path) -> Result<usize> {
+    let mut loaded = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str())
+            != Some(std::env::consts::DLL_EXTENSION)
+        {
+            continue;
+        }
+        info!("load plugin: {}", path.display());
+        let library = unsafe { libloading::Library::new(&path) }
+            .context(format!("load plugin {} failed", path.display()))?;
+        {
+            let register = unsafe {
+                library.get::<PluginRegister>(PLUGIN_REGISTER_SYMBOL)
+            }
+            .context(format!("plugin {} has no entry", path.display()))?;
+            let mut registry =
+                registry().write().unwrap_or_else(|err| err.into_inner());
+            unsafe { register(&mut registry) };
+        }
+        // providers registered by the plugin point into the library
+        std::mem::forget(library);
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+add_encryption_trait_impl!(ProviderCipherDto {
+    provider: String,
+    iv: Option<String>,
+    iv_encoding: Option<TextEncoding>,
+    aad: Option<String>,
+    aad_encoding: Option<TextEncoding>,
+    for_encryption: bool
+});
+
+impl Debug for ProviderCipherDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderCipherDto")
+            .field("provider", &self.provider)
+            .field("input_encoding", &self.input_encoding)
+            .field("key_encoding", &self.key_encoding)
+            .field("output_encoding", &self.output_encoding)
+            .field("for_encryption", &self.for_encryption)
+            .finish()
+    }
+}
+
+#[tauri::command]
+pub fn providers() -> ProviderCatalog {
+    read_registry().catalog()
+}
+
+#[tauri::command]
+pub async fn crypto_provider(data: ProviderCipherDto) -> Result<String> {
+    info!("provider crypto: {:?}", data);
+    let provider = read_registry().cipher(&data.provider)?;
+    let params = CipherParams {
+        iv: decode_optional(&data.iv, data.iv_encoding)?,
+        aad: decode_optional(&data.aad, data.aad_encoding)?,
+    };
+    let key = data.get_key()?;
+    let input = data.get_input()?;
+    let output = if data.for_encryption {
+        provider.encrypt(&key, &input, &params)?
+    } else {
+        provider.decrypt(&key, &input, &params)?
+    };
+    data.get_output_encoding().encode(&output)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderKdfDto {
+    pub provider: String,
+    pub input: String,
+    pub input_encoding: TextEncoding,
+    pub salt: Option<String>,
+    pub salt_encoding: Option<TextEncoding>,
+    pub info: Option<String>,
+    pub info_encoding: Option<TextEncoding>,
+    pub key_length: usize,
+    pub output_encoding: TextEncoding,
+}
+
+#[tauri::command]
+pub async fn derive_provider(data: ProviderKdfDto) -> Result<String> {
+    info!("provider derive: {}", data.provider);
+    let provider = read_registry().kdf(&data.provider)?;
+    let output = provider.derive(
+        &data.input_encoding.decode(&data.input)?,
+        decode_optional(&data.salt, data.salt_encoding)?,
+        decode_optional(&data.info, data.info_encoding)?,
+        data.key_length,
+    )?;
+    data.output_encoding.encode(&output)
+}
+
+#[tauri::command]
+pub async fn sign_provider(
+    provider: String,
+    key: String,
+    key_encoding: TextEncoding,
+    message: String,
+    message_encoding: TextEncoding,
+    output_encoding: TextEncoding,
+) -> Result<String> {
+    let provider = read_registry().signature(&provider)?;
+    let signature = provider.sign(
+        &key_encoding.decode(&key)?,
+        &message_encoding.decode(&message)?,
+    )?;
+    output_encoding.encode(&signature)
+}
+
+#[tauri::command]
+pub async fn verify_provider(
+    provider: String,
+    key: String,
+    key_encoding: TextEncoding,
+    message: String,
+    message_encoding: TextEncoding,
+    signature: String,
+    signature_encoding: TextEncoding,
+) -> Result<bool> {
+    let provider = read_registry().signature(&provider)?;
+    provider.verify(
+        &key_encoding.decode(&key)?,
+        &message_encoding.decode(&message)?,
+        &signature_encoding.decode(&signature)?,
+    )
+}
+
+fn decode_optional(
+    input: &Option<String>,
+    encoding: Option<TextEncoding>,
+) -> Result<Option<Vec<u8>>> {
+    match (input, encoding) {
+        (Some(input), Some(encoding)) => encoding.decode(input).map(Some),
+        (Some(_), None) => {
+            Err(Error::Unsupported("encoding is required".to_string()))
+        }
+        _ => Ok(None),
+    }
+}
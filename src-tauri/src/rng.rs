@@ -0,0 +1,119 @@
+use std::{cell::RefCell, sync::Mutex};
+
+use rand::{
+    rngs::{StdRng, ThreadRng},
+    SeedableRng,
+};
+use rand_core::{CryptoRng, RngCore};
+use tracing::warn;
+
+use crate::errors::{Error, Result};
+
+/// set by `seed_rng` from the ui, for every thread
+static SEEDED: Mutex<Option<StdRng>> = Mutex::new(None);
+
+thread_local! {
+    /// set by `seeded`, ahead of `SEEDED`
+    static SCOPED: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+/// The thread rng unless a debug build was seeded through `seeded` or
+/// `seed_rng`.
+pub enum KitsRng {
+    Thread(ThreadRng),
+    Seeded(StdRng),
+}
+
+impl RngCore for KitsRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            KitsRng::Thread(rng) => rng.next_u32(),
+            KitsRng::Seeded(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            KitsRng::Thread(rng) => rng.next_u64(),
+            KitsRng::Seeded(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            KitsRng::Thread(rng) => rng.fill_bytes(dest),
+            KitsRng::Seeded(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(
+        &mut self,
+        dest: &mut [u8],
+    ) -> std::result::Result<(), rand_core::Error> {
+        match self {
+            KitsRng::Thread(rng) => rng.try_fill_bytes(dest),
+            KitsRng::Seeded(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+impl CryptoRng for KitsRng {}
+
+pub fn rng() -> KitsRng {
+    if !cfg!(debug_assertions) {
+        return KitsRng::Thread(rand::thread_rng());
+    }
+    if let Some(rng) =
+        SCOPED.with(|scoped| scoped.borrow_mut().as_mut().map(fork))
+    {
+        return KitsRng::Seeded(rng);
+    }
+    match SEEDED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_mut()
+    {
+        Some(seeded) => KitsRng::Seeded(fork(seeded)),
+        None => KitsRng::Thread(rand::thread_rng()),
+    }
+}
+
+fn fork(seeded: &mut StdRng) -> StdRng {
+    let mut seed = <StdRng as SeedableRng>::Seed::default();
+    seeded.fill_bytes(&mut seed);
+    StdRng::from_seed(seed)
+}
+
+/// Seeds `rng` on the current thread of a debug build until dropped, the
+/// seeding before comes back then.
+pub struct Seeded {
+    previous: Option<StdRng>,
+}
+
+pub fn seeded(seed: u64) -> Seeded {
+    let previous =
+        SCOPED.with(|scoped| scoped.replace(Some(StdRng::seed_from_u64(seed))));
+    Seeded { previous }
+}
+
+impl Drop for Seeded {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
+
+#[tauri::command]
+pub fn seed_rng(seed: Option<u64>) -> Result<()> {
+    if !cfg!(debug_assertions) {
+        return Err(Error::Unsupported(
+            "seeded rng outside debug builds".to_string(),
+        ));
+    }
+    warn!("rng seed changed, seeded: {}", seed.is_some());
+    *SEEDED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+        seed.map(StdRng::seed_from_u64);
+    Ok(())
+}
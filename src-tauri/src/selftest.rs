@@ -0,0 +1,192 @@
+use std::time::Instant;
+
+use aes_gcm::{aead::Aead, Aes128Gcm, KeyInit, Nonce};
+use anyhow::Context;
+use p256::ecdsa::signature::Signer;
+use pkcs1::DecodeRsaPrivateKey;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    crypto::{kdf::kdf_inner_digest, rsa::decrypt_rsa_inner},
+    enums::{Digest, Kdf, RsaEncryptionPadding, TextEncoding},
+    errors::{Error, Result},
+    pool,
+};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestResult {
+    name: &'static str,
+    passed: bool,
+    error: Option<String>,
+    elapsed_ms: u128,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub results: Vec<SelfTestResult>,
+}
+
+type SelfTest = fn() -> Result<()>;
+
+const SELF_TESTS: [(&str, SelfTest); 5] = [
+    ("AES-128-GCM known answer", aes_gcm_kat),
+    ("SHA-256 known answer", sha256_kat),
+    ("HKDF-SHA256 known answer", hkdf_kat),
+    ("ECDSA P-256 known answer", ecdsa_p256_kat),
+    ("RSA-OAEP known answer", rsa_oaep_kat),
+];
+
+#[tauri::command]
+pub async fn run_self_tests() -> Result<SelfTestReport> {
+    pool::spawn(self_tests).await
+}
+
+pub fn self_tests() -> SelfTestReport {
+    let results = SELF_TESTS
+        .iter()
+        .map(|&(name, test)| {
+            let start = Instant::now();
+            let outcome = test();
+            let elapsed_ms = start.elapsed().as_millis();
+            match outcome {
+                Ok(_) => {
+                    info!("self test {} passed in {}ms", name, elapsed_ms);
+                    SelfTestResult {
+                        name,
+                        passed: true,
+                        error: None,
+                        elapsed_ms,
+                    }
+                }
+                Err(err) => {
+                    warn!("self test {} failed: {:?}", name, err);
+                    SelfTestResult {
+                        name,
+                        passed: false,
+                        error: Some(err.to_string()),
+                        elapsed_ms,
+                    }
+                }
+            }
+        })
+        .collect::<Vec<SelfTestResult>>();
+    SelfTestReport {
+        passed: results.iter().all(|result| result.passed),
+        results,
+    }
+}
+
+fn expect(name: &str, actual: &[u8], expected: &str) -> Result<()> {
+    if TextEncoding::Hex.encode(actual)? == expected {
+        Ok(())
+    } else {
+        Err(Error::Unsupported(format!("{} mismatch", name)))
+    }
+}
+
+// McGrew & Viega, The Galois/Counter Mode of Operation, test case 2
+fn aes_gcm_kat() -> Result<()> {
+    let cipher = Aes128Gcm::new_from_slice(&[0u8; 16])
+        .context("construct aes_gcm_cipher failed")?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&[0u8; 12]), [0u8; 16].as_ref())
+        .context("aes gcm encrypt failed")?;
+    expect(
+        "aes-gcm ciphertext",
+        &ciphertext,
+        "0388dace60b6a392f328c2b971b2fe78ab6e47d42cec13bdf53a67b21257bddf",
+    )
+}
+
+// FIPS 180-2, appendix B.1
+fn sha256_kat() -> Result<()> {
+    let mut hasher = Digest::Sha256.as_digest();
+    hasher.update(b"abc");
+    expect(
+        "sha-256 digest",
+        &hasher.finalize(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+    )
+}
+
+// RFC 5869, test case 1
+fn hkdf_kat() -> Result<()> {
+    let okm = kdf_inner_digest(
+        Kdf::HKdf,
+        Digest::Sha256,
+        &[0x0b; 22],
+        Some(TextEncoding::Hex.decode("000102030405060708090a0b0c")?),
+        Some(TextEncoding::Hex.decode("f0f1f2f3f4f5f6f7f8f9")?),
+        42,
+    )?;
+    expect(
+        "hkdf okm",
+        &okm,
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf340072\
+         08d5b887185865",
+    )
+}
+
+// RFC 6979, appendix A.2.5, SHA-256 over "sample"
+fn ecdsa_p256_kat() -> Result<()> {
+    let signing_key =
+        p256::ecdsa::SigningKey::from_slice(&TextEncoding::Hex.decode(
+            "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+        )?)
+        .context("invalid ecdsa p-256 key")?;
+    let signature: p256::ecdsa::Signature = signing_key.sign(b"sample");
+    expect(
+        "ecdsa p-256 signature",
+        &signature.to_bytes(),
+        "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716\
+         f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+    )
+}
+
+// "kits rsa-oaep self test" under the test key, OAEP with SHA-256; the
+// fixture holds a pkcs#1 key despite its name
+fn rsa_oaep_kat() -> Result<()> {
+    let private_key = rsa::RsaPrivateKey::from_pkcs1_pem(include_str!(
+        "../tests/rsa/pkcs8_private_key.pem"
+    ))
+    .context("invalid rsa test key")?;
+    let plaintext = decrypt_rsa_inner(
+        &private_key,
+        &TextEncoding::Hex.decode(
+            "89aef7af3d936dcfe73497e604a3ccee06409ded1f98928e4021ed08b29041bf\
+         5f20c10ee4ae3b29e9baaae7c3be93a3cb1f6f41a6760f9bfe2cfc20bf7e3f78\
+         f3d612d2b4f191b41452a719bc8fa671bac45a326cf9c7c8d3bcba5cb5795eaf\
+         14d7b932bb38ec09ad7cdb2d8cba9edd6ebe1152e4f984fc191d57b421ac7238\
+         41a4f5c993bcd79752e28eba895a6001256eb97ddac9c6c851544f8b2491f9c5\
+         a05269d0633644ee6bc32f54affbe10959fed70017e16c9ca296b37278c744c2\
+         d1cc74d7ce294d70fdcfd9b1763cf0fe0b03f12b7faef67286ab657bf81ba6d3\
+         e7a0afcb327b6197c28734ede5172090671d64a48e52cdc79ce6e4e32242fe7e",
+        )?,
+        RsaEncryptionPadding::Oaep,
+        Some(Digest::Sha256),
+        Some(Digest::Sha256),
+    )?;
+    expect(
+        "rsa-oaep plaintext",
+        &plaintext,
+        &TextEncoding::Hex.encode(b"kits rsa-oaep self test")?,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use tracing_test::traced_test;
+
+    use super::self_tests;
+
+    #[test]
+    #[traced_test]
+    fn test_self_tests_pass() {
+        let report = self_tests();
+        assert!(report.passed, "{:?}", report);
+    }
+}
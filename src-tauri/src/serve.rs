@@ -0,0 +1,418 @@
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{header::AUTHORIZATION, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+use tracing::info;
+
+use crate::{
+    codec::{self, PkcsDto},
+    crypto::{
+        aes::{self, AesEncryptoinDto},
+        digest,
+        ecc::{self, EciesDto},
+        edwards::{self, key::EdwardsKeyDto},
+        kdf::{self, KdfDto},
+        rsa::{self, RsaEncryptionDto},
+        OperationResult,
+    },
+    enums::{Digest, EccCurveName, EdwardsCurveName, TextEncoding},
+    errors::{Error, Result},
+    guard,
+    jwt::jwk::{self, JwkGenerate},
+    keyfile, rng,
+    utils::{KeyTuple, Provenance},
+    vault::session::Session,
+};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:11421";
+const TOKEN_ENV: &str = "KITS_API_TOKEN";
+/// where the token goes when `TOKEN_ENV` does not give one, owner-only, in
+/// the app data directory
+const TOKEN_FILE: &str = "kits-api-token";
+/// the bundle identifier tauri names the app data directory after
+const IDENTIFIER: &str = "io.ghcr.heliannuuthus.kits";
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Error::Unsupported(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(json!({ "code": self.code(), "error": self })))
+            .into_response()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertEncodingRequest {
+    input: String,
+    from: TextEncoding,
+    to: TextEncoding,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DigestRequest {
+    digest: Digest,
+    input: String,
+    input_encoding: TextEncoding,
+    output_encoding: TextEncoding,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferRsaKeyRequest {
+    private_key: Option<String>,
+    public_key: Option<String>,
+    from: PkcsDto,
+    to: PkcsDto,
+    provenance: Option<Provenance>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransferKeyRequest<C, F = PkcsDto> {
+    curve_name: C,
+    private_key: Option<String>,
+    public_key: Option<String>,
+    from: F,
+    to: F,
+    provenance: Option<Provenance>,
+}
+
+#[derive(Serialize)]
+struct Output<T: Serialize> {
+    output: T,
+}
+
+type Reply<T> = Result<Json<Output<T>>>;
+
+fn reply<T: Serialize>(output: T) -> Reply<T> {
+    Ok(Json(Output { output }))
+}
+
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    if !addr.ip().is_loopback() {
+        return Err(Error::Unsupported(format!(
+            "non-loopback listen address {}",
+            addr
+        )));
+    }
+
+    let token = match std::env::var(TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => token,
+        _ => {
+            let mut secret = [0u8; 32];
+            rng::rng().fill_bytes(&mut secret);
+            let token = Base64UrlUnpadded::encode_string(&secret);
+            let path = token_path()?;
+            keyfile::write_private(&path, token.as_bytes(), true)?;
+            info!(
+                "{} is not set, the token is in {}",
+                TOKEN_ENV,
+                path.display()
+            );
+            token
+        }
+    };
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .context(format!("bind {} failed", addr))?;
+    info!("kits api listening on http://{}", addr);
+    axum::serve(
+        listener,
+        router(token).into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .context("kits api server failed")?;
+    Ok(())
+}
+
+fn token_path() -> Result<PathBuf> {
+    let dir = tauri::api::path::data_dir()
+        .ok_or_else(|| {
+            Error::Unsupported("a platform without data directory".to_string())
+        })?
+        .join(IDENTIFIER);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("create {} failed", dir.display()))?;
+    Ok(dir.join(TOKEN_FILE))
+}
+
+fn router(token: String) -> Router {
+    Router::new()
+        .route("/api/codec/convert", post(convert_encoding))
+        .route("/api/digest", post(hash))
+        .route("/api/kdf", post(derive))
+        .route("/api/crypto/aes", post(crypto_aes))
+        .route("/api/crypto/rsa", post(crypto_rsa))
+        .route("/api/crypto/ecies", post(ecies))
+        .route("/api/transfer/rsa", post(transfer_rsa_key))
+        .route("/api/transfer/ecc", post(transfer_ecc_key))
+        .route("/api/transfer/edwards", post(transfer_edwards_key))
+        .route("/api/jwt/jwk", post(generate_jwk))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(Session::default()),
+            admit,
+        ))
+        .layer(middleware::from_fn_with_state(Arc::new(token), authorize))
+}
+
+/// The command a route stands for, limits and the session gate go by it.
+fn command_of(path: &str) -> Option<&'static str> {
+    Some(match path {
+        "/api/codec/convert" => "convert_encoding",
+        "/api/digest" => "digest",
+        "/api/kdf" => "kdf",
+        "/api/crypto/aes" => "crypto_aes",
+        "/api/crypto/rsa" => "crypto_rsa",
+        "/api/crypto/ecies" => "ecies",
+        "/api/transfer/rsa" => "transfer_rsa_key",
+        "/api/transfer/ecc" => "transfer_ecc_key",
+        "/api/transfer/edwards" => "transfer_edwards_key",
+        "/api/jwt/jwk" => "generate_jwk",
+        _ => return None,
+    })
+}
+
+/// The same checks `guard::guarded` runs before an invoke.
+async fn admit(
+    State(session): State<Arc<Session>>,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    let Some(command) = command_of(request.uri().path()) else {
+        return Ok(next.run(request).await);
+    };
+    let (parts, body) = request.into_parts();
+    let max_input = guard::max_input(command)?;
+    let body = axum::body::to_bytes(body, max_input).await.map_err(|_| {
+        Error::Unsupported(format!(
+            "input to {} over {} bytes",
+            command, max_input
+        ))
+    })?;
+    let arguments: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    guard::check(command, &arguments)?;
+    guard::throttle(command)?;
+    session.enter(command)?;
+    Ok(next.run(Request::from_parts(parts, Body::from(body))).await)
+}
+
+async fn authorize(
+    State(token): State<Arc<String>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> std::result::Result<Response, StatusCode> {
+    match refusal(&peer, request.headers().get(AUTHORIZATION), &token) {
+        Some(status) => Err(status),
+        None => Ok(next.run(request).await),
+    }
+}
+
+/// The listener only binds loopback, the peer is checked all the same.
+fn refusal(
+    peer: &SocketAddr,
+    authorization: Option<&HeaderValue>,
+    token: &str,
+) -> Option<StatusCode> {
+    if !peer.ip().is_loopback() {
+        return Some(StatusCode::FORBIDDEN);
+    }
+    let authorized = authorization
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|bearer| bool::from(bearer.as_bytes().ct_eq(token.as_bytes())))
+        .unwrap_or(false);
+    (!authorized).then_some(StatusCode::UNAUTHORIZED)
+}
+
+async fn convert_encoding(
+    Json(data): Json<ConvertEncodingRequest>,
+) -> Reply<String> {
+    reply(codec::convert_encoding(data.input, data.from, data.to)?)
+}
+
+async fn hash(
+    Json(data): Json<DigestRequest>,
+) -> Result<Json<OperationResult<String>>> {
+    Ok(Json(
+        digest::digest(
+            data.digest,
+            data.input,
+            data.input_encoding,
+            data.output_encoding,
+        )
+        .await?,
+    ))
+}
+
+async fn derive(
+    Json(data): Json<KdfDto>,
+) -> Result<Json<OperationResult<String>>> {
+    Ok(Json(kdf::kdf(data)?))
+}
+
+async fn crypto_aes(
+    Json(data): Json<AesEncryptoinDto>,
+) -> Result<Json<OperationResult<String>>> {
+    Ok(Json(aes::crypto_aes(data).await?))
+}
+
+async fn crypto_rsa(
+    Json(data): Json<RsaEncryptionDto>,
+) -> Result<Json<OperationResult<String>>> {
+    Ok(Json(rsa::crypto_rsa(data).await?))
+}
+
+async fn ecies(
+    Json(data): Json<EciesDto>,
+) -> Result<Json<OperationResult<String>>> {
+    Ok(Json(ecc::ecies(data).await?))
+}
+
+async fn transfer_rsa_key(
+    Json(data): Json<TransferRsaKeyRequest>,
+) -> Reply<KeyTuple> {
+    reply(
+        rsa::key::transfer_rsa_key(
+            data.private_key,
+            data.public_key,
+            data.from,
+            data.to,
+            data.provenance,
+        )
+        .await?,
+    )
+}
+
+async fn transfer_ecc_key(
+    Json(data): Json<TransferKeyRequest<EccCurveName>>,
+) -> Reply<KeyTuple> {
+    reply(
+        ecc::key::transfer_ecc_key(
+            data.curve_name,
+            data.private_key,
+            data.public_key,
+            data.from,
+            data.to,
+            data.provenance,
+        )
+        .await?,
+    )
+}
+
+async fn transfer_edwards_key(
+    Json(data): Json<TransferKeyRequest<EdwardsCurveName, EdwardsKeyDto>>,
+) -> Reply<KeyTuple> {
+    reply(edwards::key::transfer_edwards_key(
+        data.curve_name,
+        data.private_key,
+        data.public_key,
+        data.from,
+        data.to,
+        data.provenance,
+    )?)
+}
+
+async fn generate_jwk(Json(data): Json<JwkGenerate>) -> Reply<String> {
+    reply(jwk::generate_jwk(data).await?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use axum::http::{HeaderValue, StatusCode};
+    use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+
+    use super::{refusal, router, serve};
+
+    #[test]
+    fn test_refusal() {
+        let loopback: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.0.2.1:50000".parse().unwrap();
+        let bearer = HeaderValue::from_static("Bearer token");
+        assert_eq!(refusal(&loopback, Some(&bearer), "token"), None);
+        assert_eq!(
+            refusal(&loopback, None, "token"),
+            Some(StatusCode::UNAUTHORIZED)
+        );
+        for wrong in ["Bearer wrong", "Bearer tokens", "token"] {
+            assert_eq!(
+                refusal(
+                    &loopback,
+                    Some(&HeaderValue::from_static(wrong)),
+                    "token"
+                ),
+                Some(StatusCode::UNAUTHORIZED)
+            );
+        }
+        assert_eq!(
+            refusal(&remote, Some(&bearer), "token"),
+            Some(StatusCode::FORBIDDEN)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bearer() {
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                router("token".to_string())
+                    .into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+        });
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let convert = |authorization: Option<&str>| {
+            let mut request = client
+                .post(format!("http://{}/api/codec/convert", addr))
+                .header(CONTENT_TYPE, "application/json")
+                .body(r#"{"input":"kits","from":"utf8","to":"hex"}"#);
+            if let Some(authorization) = authorization {
+                request = request.header(AUTHORIZATION, authorization);
+            }
+            request.send()
+        };
+        assert_eq!(convert(None).await.unwrap().status(), 401);
+        assert_eq!(convert(Some("Bearer wrong")).await.unwrap().status(), 401);
+        let response = convert(Some("Bearer token")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), r#"{"output":"6b697473"}"#);
+
+        let response = client
+            .post(format!("http://{}/api/kdf", addr))
+            .header(CONTENT_TYPE, "application/json")
+            .header(AUTHORIZATION, "Bearer token")
+            .body(r#"{"kdf":"hkdf","input":"abc","keyLength":1000000000}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_non_loopback_address() {
+        assert!(serve("0.0.0.0:0".parse().unwrap()).await.is_err());
+    }
+}
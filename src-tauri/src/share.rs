@@ -0,0 +1,396 @@
+//! Share links, `kits+share:` and the base64url of
+//!
+//! magic || kind || created || expires (u64 be) || key material || iv ||
+//! AES-256-CBC ciphertext || HMAC-SHA256 over everything before it
+//!
+//! the key material being the argon2id costs and salt, or an ephemeral
+//! X25519 public key.
+
+use aes::{
+    cipher::{
+        block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit,
+    },
+    Aes256,
+};
+use anyhow::{anyhow, Context};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use hkdf::hmac::{digest::KeyInit, Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::info;
+use x25519_dalek::{EphemeralSecret, PublicKey, SharedSecret, StaticSecret};
+use zeroize::Zeroizing;
+
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng, vault,
+};
+
+pub(crate) const PREFIX: &str = "kits+share:";
+pub(crate) const MAGIC: &[u8; 8] = b"KITSSHR1";
+const PASSPHRASE: u8 = 1;
+const RECIPIENT: u8 = 2;
+const FIXED_SIZE: usize = MAGIC.len() + 1 + 16;
+const SALT_SIZE: usize = 16;
+const IV_SIZE: usize = 16;
+const TAG_SIZE: usize = 32;
+const INFO: &[u8] = b"kits share v1";
+const MAX_EXPIRES_IN: u64 = 30 * 24 * 60 * 60;
+/// argon2 costs read from a link, KiB and passes
+const MAX_ARGON2: (u32, u32) = (1024 * 1024, 10);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateShareDto {
+    pub content: String,
+    pub label: Option<String>,
+    /// seconds the link opens for, at most 30 days
+    pub expires_in: u64,
+    pub passphrase: Option<String>,
+    /// raw X25519 public key
+    pub recipient: Option<String>,
+    pub recipient_encoding: Option<TextEncoding>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenShareDto {
+    pub link: String,
+    pub passphrase: Option<String>,
+    /// raw X25519 private key of the recipient
+    pub private_key: Option<String>,
+    pub private_key_encoding: Option<TextEncoding>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedOutput {
+    pub content: String,
+    pub label: Option<String>,
+    #[serde(skip_deserializing)]
+    pub created: u64,
+    #[serde(skip_deserializing)]
+    pub expires: u64,
+}
+
+#[tauri::command]
+pub fn create_share(data: CreateShareDto) -> Result<String> {
+    seal(data, vault::now())
+}
+
+#[tauri::command]
+pub fn open_share(data: OpenShareDto) -> Result<SharedOutput> {
+    let link = data.link.trim();
+    let link = Base64UrlUnpadded::decode_vec(
+        link.strip_prefix(PREFIX).unwrap_or(link),
+    )
+    .map_err(|err| anyhow!(err).context("base64 decode failed"))?;
+    if link.len() < FIXED_SIZE || !link.starts_with(MAGIC) {
+        return Err(Error::Unsupported("share link format".to_string()));
+    }
+    let kind = link[MAGIC.len()];
+    let material_size = match kind {
+        PASSPHRASE => 12 + SALT_SIZE,
+        RECIPIENT => 32,
+        kind => {
+            return Err(Error::Unsupported(format!("share link kind {}", kind)))
+        }
+    };
+    let header_size = FIXED_SIZE + material_size + IV_SIZE;
+    if link.len() < header_size + TAG_SIZE {
+        return Err(Error::Unsupported("truncated share link".to_string()));
+    }
+    let material = &link[FIXED_SIZE .. FIXED_SIZE + material_size];
+    let keys = if kind == PASSPHRASE {
+        let passphrase = data.passphrase.as_deref().ok_or_else(|| {
+            Error::Unsupported("passphrase is required".to_string())
+        })?;
+        let u32_at = |offset: usize| {
+            u32::from_be_bytes(
+                material[offset .. offset + 4]
+                    .try_into()
+                    .unwrap_or_default(),
+            )
+        };
+        passphrase_keys(
+            passphrase,
+            Params::new(u32_at(0), u32_at(4), u32_at(8), Some(64))
+                .map_err(|err| anyhow!("invalid argon2 parameters: {}", err))?,
+            &material[12 ..],
+        )?
+    } else {
+        let (Some(private_key), Some(encoding)) =
+            (&data.private_key, data.private_key_encoding)
+        else {
+            return Err(Error::Unsupported(
+                "private key and its encoding are required".to_string(),
+            ));
+        };
+        let secret = StaticSecret::from(
+            <[u8; 32]>::try_from(&encoding.decode(private_key)?[..]).map_err(
+                |_| Error::Unsupported("x25519 private key size".to_string()),
+            )?,
+        );
+        let ephemeral = x25519_public(material)?;
+        recipient_keys(
+            secret.diffie_hellman(&ephemeral),
+            &ephemeral,
+            &PublicKey::from(&secret),
+        )?
+    };
+    let (signed, tag) = link.split_at(link.len() - TAG_SIZE);
+    tag_mac(&keys[32 ..])?
+        .chain_update(signed)
+        .verify_slice(tag)
+        .map_err(|_| anyhow!("wrong key or tampered share link"))?;
+
+    let u64_at = |offset: usize| {
+        u64::from_be_bytes(
+            link[offset .. offset + 8].try_into().unwrap_or_default(),
+        )
+    };
+    let (created, expires) = (u64_at(MAGIC.len() + 1), u64_at(MAGIC.len() + 9));
+    if vault::now() > expires {
+        return Err(Error::Unsupported(format!(
+            "share link expired at {}",
+            expires
+        )));
+    }
+    let plaintext = Zeroizing::new(
+        cbc::Decryptor::<Aes256>::new_from_slices(
+            &keys[.. 32],
+            &signed[header_size - IV_SIZE .. header_size],
+        )
+        .context("construct aes_cbc_decryptor failed")?
+        .decrypt_padded_vec_mut::<Pkcs7>(&signed[header_size ..])
+        .map_err(|_| anyhow!("decrypt share link failed"))?,
+    );
+    let mut output: SharedOutput =
+        serde_json::from_slice(&plaintext).context("invalid share link")?;
+    output.created = created;
+    output.expires = expires;
+    info!(
+        "open share link, label: {:?}, kind: {}, expires: {}",
+        output.label, kind, expires
+    );
+    Ok(output)
+}
+
+fn seal(data: CreateShareDto, created: u64) -> Result<String> {
+    if !(1 ..= MAX_EXPIRES_IN).contains(&data.expires_in) {
+        return Err(Error::Unsupported(format!(
+            "{} seconds expiry, between 1 and {}",
+            data.expires_in, MAX_EXPIRES_IN
+        )));
+    }
+    let mut link = MAGIC.to_vec();
+    link.push(if data.passphrase.is_some() {
+        PASSPHRASE
+    } else {
+        RECIPIENT
+    });
+    link.extend(created.to_be_bytes());
+    link.extend((created + data.expires_in).to_be_bytes());
+    let keys = match (&data.passphrase, &data.recipient) {
+        (Some(passphrase), None) => {
+            let params = Params::new(
+                Params::DEFAULT_M_COST,
+                Params::DEFAULT_T_COST,
+                Params::DEFAULT_P_COST,
+                Some(64),
+            )
+            .map_err(|err| anyhow!("invalid argon2 parameters: {}", err))?;
+            let mut salt = [0u8; SALT_SIZE];
+            rng::rng().fill_bytes(&mut salt);
+            link.extend(params.m_cost().to_be_bytes());
+            link.extend(params.t_cost().to_be_bytes());
+            link.extend(params.p_cost().to_be_bytes());
+            link.extend(&salt);
+            passphrase_keys(passphrase, params, &salt)?
+        }
+        (None, Some(recipient)) => {
+            let encoding = data.recipient_encoding.ok_or_else(|| {
+                Error::Unsupported("recipient encoding is required".to_string())
+            })?;
+            let recipient = x25519_public(&encoding.decode(recipient)?)?;
+            let ephemeral = EphemeralSecret::random_from_rng(rng::rng());
+            let public = PublicKey::from(&ephemeral);
+            link.extend(public.as_bytes());
+            recipient_keys(
+                ephemeral.diffie_hellman(&recipient),
+                &public,
+                &recipient,
+            )?
+        }
+        _ => {
+            return Err(Error::Unsupported(
+                "either a passphrase or a recipient".to_string(),
+            ))
+        }
+    };
+    let mut iv = [0u8; IV_SIZE];
+    rng::rng().fill_bytes(&mut iv);
+    link.extend(&iv);
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(&SharedOutput {
+            content: data.content,
+            label: data.label,
+            created: 0,
+            expires: 0,
+        })
+        .context("serialize share link failed")?,
+    );
+    let ciphertext =
+        cbc::Encryptor::<Aes256>::new_from_slices(&keys[.. 32], &iv)
+            .context("construct aes_cbc_encryptor failed")?
+            .encrypt_padded_vec_mut::<Pkcs7>(&plaintext);
+    link.extend(ciphertext);
+    let tag = tag_mac(&keys[32 ..])?.chain_update(&link).finalize();
+    link.extend(tag.into_bytes());
+    info!(
+        "create share link, kind: {}, expires in: {} s, size: {}",
+        link[MAGIC.len()],
+        data.expires_in,
+        link.len()
+    );
+    Ok(format!(
+        "{}{}",
+        PREFIX,
+        Base64UrlUnpadded::encode_string(&link)
+    ))
+}
+
+/// 32 bytes of cipher key then 32 bytes of mac key.
+fn passphrase_keys(
+    passphrase: &str,
+    params: Params,
+    salt: &[u8],
+) -> Result<Zeroizing<[u8; 64]>> {
+    let (max_memory, max_time) = MAX_ARGON2;
+    if params.m_cost() > max_memory || params.t_cost() > max_time {
+        return Err(Error::Unsupported(format!(
+            "argon2 costs of {} KiB and {} passes",
+            params.m_cost(),
+            params.t_cost()
+        )));
+    }
+    let mut keys = Zeroizing::new([0u8; 64]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut keys[..])
+        .map_err(|err| anyhow!("derive share keys failed: {}", err))?;
+    Ok(keys)
+}
+
+/// HKDF-SHA256 of the shared secret salted with both public keys.
+fn recipient_keys(
+    shared: SharedSecret,
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> Result<Zeroizing<[u8; 64]>> {
+    if !shared.was_contributory() {
+        return Err(Error::Unsupported(
+            "low order x25519 public key".to_string(),
+        ));
+    }
+    let salt = [ephemeral.as_bytes(), recipient.as_bytes()].concat();
+    let mut keys = Zeroizing::new([0u8; 64]);
+    hkdf::Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(INFO, &mut keys[..])
+        .map_err(|_| anyhow!("derive share keys failed"))?;
+    Ok(keys)
+}
+
+fn x25519_public(key: &[u8]) -> Result<PublicKey> {
+    <[u8; 32]>::try_from(key).map(PublicKey::from).map_err(|_| {
+        Error::Unsupported(format!("{} bytes x25519 public key", key.len()))
+    })
+}
+
+fn tag_mac(key: &[u8]) -> Result<Hmac<Sha256>> {
+    Ok(<Hmac<Sha256> as KeyInit>::new_from_slice(key)
+        .map_err(|_| anyhow!("invalid share mac key"))?)
+}
+
+#[cfg(test)]
+mod test {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use x25519_dalek::{PublicKey, StaticSecret};
+
+    use super::{
+        create_share, open_share, seal, CreateShareDto, OpenShareDto,
+        SharedOutput, PREFIX,
+    };
+    use crate::{enums::TextEncoding, errors::Result, rng, vault};
+
+    fn dto(
+        passphrase: Option<&str>,
+        recipient: Option<String>,
+    ) -> CreateShareDto {
+        CreateShareDto {
+            content: "secret output".to_string(),
+            label: Some("aes key".to_string()),
+            expires_in: 3600,
+            passphrase: passphrase.map(str::to_string),
+            recipient,
+            recipient_encoding: Some(TextEncoding::Hex),
+        }
+    }
+
+    fn open(
+        link: &str,
+        passphrase: Option<&str>,
+        private_key: Option<String>,
+    ) -> Result<SharedOutput> {
+        open_share(OpenShareDto {
+            link: link.to_string(),
+            passphrase: passphrase.map(str::to_string),
+            private_key,
+            private_key_encoding: Some(TextEncoding::Hex),
+        })
+    }
+
+    #[test]
+    fn test_passphrase_share() {
+        let link = create_share(dto(Some("passphrase"), None)).unwrap();
+        let opened = open(&link, Some("passphrase"), None).unwrap();
+        assert_eq!(opened.content, "secret output");
+        assert_eq!(opened.label.as_deref(), Some("aes key"));
+        assert_eq!(opened.expires, opened.created + 3600);
+        assert!(open(&link, Some("wrong"), None).is_err());
+
+        // a later expiry under the tag
+        let mut raw =
+            Base64UrlUnpadded::decode_vec(&link[PREFIX.len() ..]).unwrap();
+        raw[20] ^= 0x01;
+        let tampered =
+            format!("{}{}", PREFIX, Base64UrlUnpadded::encode_string(&raw));
+        assert!(open(&tampered, Some("passphrase"), None).is_err());
+
+        let expired =
+            seal(dto(Some("passphrase"), None), vault::now() - 7200).unwrap();
+        assert!(open(&expired, Some("passphrase"), None).is_err());
+        assert!(
+            create_share(dto(Some("passphrase"), Some("00".to_string())))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_recipient_share() {
+        let secret = StaticSecret::random_from_rng(rng::rng());
+        let public = TextEncoding::Hex
+            .encode(PublicKey::from(&secret).as_bytes())
+            .unwrap();
+        let link = create_share(dto(None, Some(public))).unwrap();
+        let private = TextEncoding::Hex.encode(secret.as_bytes()).unwrap();
+        let opened = open(&link, None, Some(private)).unwrap();
+        assert_eq!(opened.content, "secret output");
+
+        let other = StaticSecret::random_from_rng(rng::rng());
+        let other = TextEncoding::Hex.encode(other.as_bytes()).unwrap();
+        assert!(open(&link, None, Some(other)).is_err());
+    }
+}
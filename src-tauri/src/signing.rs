@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Result};
+
+pub mod http;
+pub mod message;
+pub mod sigstore;
+pub mod sigv4;
+pub mod xmldsig;
+
+/// Headers keep their order and repetitions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpMessage {
+    pub method: Option<String>,
+    pub uri: Option<String>,
+    pub status: Option<u16>,
+    pub headers: Vec<(String, String)>,
+}
+
+pub(crate) struct Uri<'a> {
+    pub scheme: &'a str,
+    pub authority: &'a str,
+    pub path: &'a str,
+    pub query: Option<&'a str>,
+}
+
+impl HttpMessage {
+    pub(crate) fn header_values(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+            .collect()
+    }
+
+    pub(crate) fn uri(&self) -> Result<Uri> {
+        let uri = self
+            .uri
+            .as_deref()
+            .ok_or_else(|| Error::Unsupported("message without uri".into()))?;
+        let (scheme, rest) = uri
+            .split_once("://")
+            .ok_or_else(|| Error::Unsupported(format!("uri `{}`", uri)))?;
+        let rest = rest.split_once('#').map_or(rest, |(rest, _)| rest);
+        let (authority, path_and_query) = match rest.find(['/', '?']) {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let (path, query) = match path_and_query.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path_and_query, None),
+        };
+        Ok(Uri {
+            scheme,
+            authority,
+            path,
+            query,
+        })
+    }
+}
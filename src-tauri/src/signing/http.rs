@@ -0,0 +1,409 @@
+//! HTTP message signatures (RFC 9421). Keys are jwks, the same ones
+//! `sign_json` takes.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use super::HttpMessage;
+use crate::{
+    errors::{Error, Result},
+    jwt::{jws, JwkeyAlgorithm},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpSignatureAlgorithm {
+    #[serde(rename = "rsa-pss-sha512")]
+    RsaPssSha512,
+    #[serde(rename = "rsa-v1_5-sha256")]
+    RsaV15Sha256,
+    #[serde(rename = "hmac-sha256")]
+    HmacSha256,
+    #[serde(rename = "ecdsa-p256-sha256")]
+    EcdsaP256Sha256,
+    #[serde(rename = "ecdsa-p384-sha384")]
+    EcdsaP384Sha384,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+impl HttpSignatureAlgorithm {
+    fn jws_algorithm(self) -> JwkeyAlgorithm {
+        match self {
+            HttpSignatureAlgorithm::RsaPssSha512 => JwkeyAlgorithm::PS512,
+            HttpSignatureAlgorithm::RsaV15Sha256 => JwkeyAlgorithm::RS256,
+            HttpSignatureAlgorithm::HmacSha256 => JwkeyAlgorithm::HS256,
+            HttpSignatureAlgorithm::EcdsaP256Sha256 => JwkeyAlgorithm::ES256,
+            HttpSignatureAlgorithm::EcdsaP384Sha384 => JwkeyAlgorithm::ES384,
+            HttpSignatureAlgorithm::Ed25519 => JwkeyAlgorithm::EdDSA,
+        }
+    }
+
+    fn name(self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|name| name.as_str().map(String::from))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpSignDto {
+    pub message: HttpMessage,
+    /// component identifiers, `@method` style derived components or
+    /// lowercase header names
+    pub components: Vec<String>,
+    pub label: Option<String>,
+    pub jwk: String,
+    pub algorithm: HttpSignatureAlgorithm,
+    pub key_id: Option<String>,
+    pub created: Option<u64>,
+    pub expires: Option<u64>,
+    pub nonce: Option<String>,
+    pub tag: Option<String>,
+    /// also announce the algorithm in the `alg` parameter
+    pub include_alg: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpVerifyDto {
+    /// carries the `Signature` and `Signature-Input` headers
+    pub message: HttpMessage,
+    pub jwk: String,
+    /// taken from the `alg` parameter when absent
+    pub algorithm: Option<HttpSignatureAlgorithm>,
+    /// the first signature when absent
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpSignature {
+    pub signature_input: String,
+    pub signature: String,
+    pub signature_base: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpVerification {
+    pub label: String,
+    pub signature_base: String,
+    pub verified: bool,
+    pub expired: bool,
+}
+
+#[tauri::command]
+pub fn sign_http_message(data: HttpSignDto) -> Result<HttpSignature> {
+    info!(
+        "sign http message, algorithm: {:?}, components: {:?}",
+        data.algorithm, data.components
+    );
+    let label = data.label.unwrap_or_else(|| "sig1".to_string());
+    let created = match data.created {
+        Some(created) => created,
+        None => now()?,
+    };
+    let mut params = format!(
+        "({});created={}",
+        data.components
+            .iter()
+            .map(|component| format!("\"{}\"", component))
+            .collect::<Vec<_>>()
+            .join(" "),
+        created
+    );
+    if let Some(expires) = data.expires {
+        params.push_str(&format!(";expires={}", expires));
+    }
+    if let Some(nonce) = &data.nonce {
+        params.push_str(&format!(";nonce=\"{}\"", nonce));
+    }
+    if data.include_alg.unwrap_or(false) {
+        params.push_str(&format!(";alg=\"{}\"", data.algorithm.name()));
+    }
+    if let Some(key_id) = &data.key_id {
+        params.push_str(&format!(";keyid=\"{}\"", key_id));
+    }
+    if let Some(tag) = &data.tag {
+        params.push_str(&format!(";tag=\"{}\"", tag));
+    }
+
+    let base = signature_base(&data.message, &data.components, &params)?;
+    let jwk: Value = serde_json::from_str(&data.jwk).context("invalid jwk")?;
+    let signature =
+        jws::sign_jws(data.algorithm.jws_algorithm(), &jwk, base.as_bytes())?;
+    Ok(HttpSignature {
+        signature_input: format!("{}={}", label, params),
+        signature: format!("{}=:{}:", label, Base64::encode_string(&signature)),
+        signature_base: base,
+    })
+}
+
+#[tauri::command]
+pub fn verify_http_message(data: HttpVerifyDto) -> Result<HttpVerification> {
+    let inputs = dictionary(&data.message.header_values("signature-input"));
+    let signatures = dictionary(&data.message.header_values("signature"));
+    let (label, params) = match &data.label {
+        Some(label) => inputs.iter().find(|(name, _)| name == label),
+        None => inputs.first(),
+    }
+    .ok_or_else(|| {
+        Error::Unsupported("message without signature-input".to_string())
+    })?;
+    info!("verify http message, label: {}", label);
+    let signature = signatures
+        .iter()
+        .find(|(name, _)| name == label)
+        .map(|(_, value)| value.trim_matches(':'))
+        .ok_or_else(|| {
+            Error::Unsupported(format!("signature `{}` is missing", label))
+        })?;
+    let signature = Base64::decode_vec(signature)
+        .map_err(|_| Error::Unsupported("signature encoding".to_string()))?;
+
+    let (components, parameters) = inner_list(params)?;
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let algorithm = match (data.algorithm, parameter("alg")) {
+        (Some(algorithm), _) => algorithm,
+        (None, Some(alg)) => serde_json::from_value(Value::String(alg.into()))
+            .map_err(|_| Error::Unsupported(format!("alg `{}`", alg)))?,
+        (None, None) => {
+            return Err(Error::Unsupported(
+                "signature without alg parameter".to_string(),
+            ))
+        }
+    };
+    let expired = match parameter("expires") {
+        Some(expires) => {
+            expires.parse::<u64>().context("invalid expires")? < now()?
+        }
+        None => false,
+    };
+
+    let base = signature_base(&data.message, &components, params)?;
+    let jwk: Value = serde_json::from_str(&data.jwk).context("invalid jwk")?;
+    let verified = jws::verify_jws(
+        algorithm.jws_algorithm(),
+        &jwk,
+        base.as_bytes(),
+        &signature,
+    )?;
+    Ok(HttpVerification {
+        label: label.clone(),
+        signature_base: base,
+        verified,
+        expired,
+    })
+}
+
+fn now() -> Result<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("system time before unix epoch")?
+        .as_secs())
+}
+
+fn signature_base(
+    message: &HttpMessage,
+    components: &[String],
+    params: &str,
+) -> Result<String> {
+    let mut base = String::new();
+    for (index, component) in components.iter().enumerate() {
+        if components[.. index].contains(component) {
+            return Err(Error::Unsupported(format!(
+                "duplicate component `{}`",
+                component
+            )));
+        }
+        base.push_str(&format!(
+            "\"{}\": {}\n",
+            component,
+            component_value(message, component)?
+        ));
+    }
+    base.push_str(&format!("\"@signature-params\": {}", params));
+    Ok(base)
+}
+
+fn component_value(message: &HttpMessage, component: &str) -> Result<String> {
+    let missing =
+        || Error::Unsupported(format!("component `{}` is missing", component));
+    Ok(match component {
+        "@method" => message.method.clone().ok_or_else(missing)?,
+        "@target-uri" => message.uri.clone().ok_or_else(missing)?,
+        "@authority" => message.uri()?.authority.to_ascii_lowercase(),
+        "@scheme" => message.uri()?.scheme.to_ascii_lowercase(),
+        "@path" => match message.uri()?.path {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        },
+        "@query" => format!("?{}", message.uri()?.query.unwrap_or_default()),
+        "@request-target" => {
+            let uri = message.uri()?;
+            match uri.query {
+                Some(query) => format!("{}?{}", uri.path, query),
+                None => uri.path.to_string(),
+            }
+        }
+        "@status" => message.status.ok_or_else(missing)?.to_string(),
+        derived if derived.starts_with('@') => {
+            return Err(Error::Unsupported(format!(
+                "derived component `{}`",
+                derived
+            )))
+        }
+        header => {
+            let values = message.header_values(header);
+            if values.is_empty() {
+                return Err(missing());
+            }
+            values.join(", ")
+        }
+    })
+}
+
+fn split_top_level(input: &str, separator: char) -> Vec<&str> {
+    let (mut items, mut start, mut quoted, mut depth) =
+        (Vec::new(), 0, false, 0);
+    for (index, c) in input.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            c if c == separator && !quoted && depth == 0 => {
+                items.push(input[start .. index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(input[start ..].trim());
+    items
+}
+
+/// Structured field dictionary members as `(key, raw value)`.
+fn dictionary(values: &[&str]) -> Vec<(String, String)> {
+    values
+        .iter()
+        .flat_map(|value| split_top_level(value, ','))
+        .filter_map(|member| member.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Component identifiers and parameters of a signature-input inner list.
+fn inner_list(input: &str) -> Result<(Vec<String>, Vec<(String, String)>)> {
+    let malformed =
+        || Error::Unsupported(format!("signature-input `{}`", input));
+    let (list, parameters) = input
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(')'))
+        .ok_or_else(malformed)?;
+    let components = split_top_level(list, ' ')
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.strip_prefix('"')
+                .and_then(|item| item.strip_suffix('"'))
+                .map(String::from)
+                .ok_or_else(|| {
+                    Error::Unsupported(format!("component `{}`", item))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let parameters = split_top_level(parameters, ';')
+        .into_iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.split_once('=') {
+            Some((key, value)) => {
+                (key.to_string(), value.trim_matches('"').to_string())
+            }
+            None => (item.to_string(), "?1".to_string()),
+        })
+        .collect();
+    Ok((components, parameters))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        sign_http_message, verify_http_message, HttpSignDto,
+        HttpSignatureAlgorithm, HttpVerifyDto,
+    };
+    use crate::signing::HttpMessage;
+
+    const JWK: &str = r#"{"kty":"oct","k":"c2VjcmV0"}"#;
+
+    #[test]
+    fn test_http_signature() {
+        let mut message = HttpMessage {
+            method: Some("POST".to_string()),
+            uri: Some("https://Example.com/foo?param=value".to_string()),
+            status: None,
+            headers: vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Content-Length".to_string(), "18".to_string()),
+            ],
+        };
+        let signed = sign_http_message(HttpSignDto {
+            message: message.clone(),
+            components: ["@method", "@authority", "@path", "content-type"]
+                .map(String::from)
+                .to_vec(),
+            label: None,
+            jwk: JWK.to_string(),
+            algorithm: HttpSignatureAlgorithm::HmacSha256,
+            key_id: Some("test-key".to_string()),
+            created: Some(1618884473),
+            expires: None,
+            nonce: None,
+            tag: None,
+            include_alg: Some(true),
+        })
+        .unwrap();
+        assert_eq!(
+            signed.signature_base,
+            "\"@method\": POST\n\"@authority\": example.com\n\"@path\": \
+             /foo\n\"content-type\": application/json\n\"@signature-params\": \
+             (\"@method\" \"@authority\" \"@path\" \
+             \"content-type\");created=1618884473;alg=\"hmac-sha256\";keyid=\"\
+             test-key\""
+        );
+
+        message.headers.push((
+            "Signature-Input".to_string(),
+            signed.signature_input.clone(),
+        ));
+        message
+            .headers
+            .push(("Signature".to_string(), signed.signature.clone()));
+        let verify = |message: &HttpMessage| {
+            verify_http_message(HttpVerifyDto {
+                message: message.clone(),
+                jwk: JWK.to_string(),
+                algorithm: None,
+                label: Some("sig1".to_string()),
+            })
+            .unwrap()
+        };
+        let verification = verify(&message);
+        assert!(verification.verified);
+        assert_eq!(verification.signature_base, signed.signature_base);
+
+        message.headers[0].1 = "text/plain".to_string();
+        assert!(!verify(&message).verified);
+    }
+}
@@ -0,0 +1,567 @@
+//! Signs and verifies plain messages with a jwk, pem, der or raw hmac key,
+//! reporting the algorithm picked.
+
+use anyhow::Context;
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::{
+    audit::{
+        compare,
+        key::{AuditedKey, SECP256K1, SECP256R1, SECP384R1, SM2},
+    },
+    codec::base64_decode,
+    crypto::ecc::signature::{convert, SignatureFormat},
+    enums::TextEncoding,
+    errors::{Error, Result},
+    jwt::{
+        jws::{sign_jws, verify_jws},
+        JwkeyAlgorithm, JwkeyUsage,
+    },
+    vault,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    Hex,
+    Base64,
+    /// unpadded, as jose writes it
+    Base64url,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignMessageDto {
+    pub message: String,
+    /// utf8 when absent
+    pub message_encoding: Option<TextEncoding>,
+    pub key: String,
+    /// of der and raw keys, base64 when absent
+    pub key_encoding: Option<TextEncoding>,
+    /// picked from the key when absent
+    pub algorithm: Option<JwkeyAlgorithm>,
+    /// base64 when absent
+    pub signature_encoding: Option<SignatureEncoding>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyMessageDto {
+    pub message: String,
+    pub message_encoding: Option<TextEncoding>,
+    pub key: String,
+    pub key_encoding: Option<TextEncoding>,
+    pub algorithm: Option<JwkeyAlgorithm>,
+    pub signature: String,
+    /// told from the signature when absent
+    pub signature_encoding: Option<SignatureEncoding>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageChoices {
+    /// `jwk`, `pem`, `der` or `ssh`
+    pub key_format: &'static str,
+    /// `rsa`, `ec`, `ed25519` or `symmetric`
+    pub key_kind: &'static str,
+    pub algorithm: JwkeyAlgorithm,
+    /// the digest `algorithm` hashes the message with
+    pub digest: &'static str,
+    pub signature_encoding: SignatureEncoding,
+    /// of ecdsa signatures, der ones are verified too
+    pub signature_format: Option<SignatureFormat>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedMessage {
+    pub signature: String,
+    pub chosen: MessageChoices,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedMessage {
+    pub verified: bool,
+    pub chosen: MessageChoices,
+}
+
+struct MessageKey {
+    format: &'static str,
+    kind: &'static str,
+    jwk: Value,
+    algorithm: JwkeyAlgorithm,
+}
+
+/// Ecdsa signatures come out as the fixed width r || s.
+#[tauri::command]
+pub fn sign_message(data: SignMessageDto) -> Result<SignedMessage> {
+    let key = read_key(&data.key, data.key_encoding, data.algorithm)?;
+    let digest = digest_of(key.algorithm)?;
+    info!(
+        "sign message, key: {} {}, algorithm: {:?}",
+        key.format, key.kind, key.algorithm
+    );
+    vault::enforce_policy(
+        key.jwk.to_string().as_bytes(),
+        JwkeyUsage::Signature,
+        &algorithm_name(key.algorithm),
+    )?;
+    let message = data
+        .message_encoding
+        .unwrap_or(TextEncoding::Utf8)
+        .decode(&data.message)?;
+    let signature = sign_jws(key.algorithm, &key.jwk, &message)?;
+    let encoding = data.signature_encoding.unwrap_or(SignatureEncoding::Base64);
+    Ok(SignedMessage {
+        signature: encode(&signature, encoding),
+        chosen: MessageChoices {
+            key_format: key.format,
+            key_kind: key.kind,
+            algorithm: key.algorithm,
+            digest,
+            signature_encoding: encoding,
+            signature_format: ecdsa_size(key.algorithm)
+                .map(|_| SignatureFormat::Raw),
+        },
+    })
+}
+
+/// Ecdsa signatures may be raw or der.
+#[tauri::command]
+pub fn verify_message(data: VerifyMessageDto) -> Result<VerifiedMessage> {
+    let key = read_key(&data.key, data.key_encoding, data.algorithm)?;
+    let digest = digest_of(key.algorithm)?;
+    let signature = data.signature.split_whitespace().collect::<String>();
+    let encoding = data
+        .signature_encoding
+        .unwrap_or_else(|| detect_encoding(&signature));
+    let (signature_format, signature) =
+        ecdsa_raw(key.algorithm, decode(&signature, encoding)?)?;
+    info!(
+        "verify message, key: {} {}, algorithm: {:?}, signature: {:?}",
+        key.format, key.kind, key.algorithm, encoding
+    );
+    let message = data
+        .message_encoding
+        .unwrap_or(TextEncoding::Utf8)
+        .decode(&data.message)?;
+    Ok(VerifiedMessage {
+        verified: verify_jws(key.algorithm, &key.jwk, &message, &signature)?,
+        chosen: MessageChoices {
+            key_format: key.format,
+            key_kind: key.kind,
+            algorithm: key.algorithm,
+            digest,
+            signature_encoding: encoding,
+            signature_format,
+        },
+    })
+}
+
+/// Unless `algorithm` is given, the digest of rsa and hmac keys grows with
+/// the key.
+fn read_key(
+    key: &str,
+    encoding: Option<TextEncoding>,
+    algorithm: Option<JwkeyAlgorithm>,
+) -> Result<MessageKey> {
+    let (format, key) =
+        compare::read(key, encoding.unwrap_or(TextEncoding::Base64))?;
+    let encode = |bytes: &[u8]| Base64UrlUnpadded::encode_string(bytes);
+    let (kind, jwk, default) = match key {
+        AuditedKey::RsaPrivate(key) => {
+            let primes = key.primes();
+            let [p, q] = primes else {
+                return Err(Error::Unsupported(format!(
+                    "rsa key of {} primes",
+                    primes.len()
+                )));
+            };
+            let jwk = json!({
+                "kty": "RSA",
+                "n": encode(&key.n().to_bytes_be()),
+                "e": encode(&key.e().to_bytes_be()),
+                "d": encode(&key.d().to_bytes_be()),
+                "p": encode(&p.to_bytes_be()),
+                "q": encode(&q.to_bytes_be()),
+            });
+            ("rsa", jwk, rsa_algorithm(key.n().bits()))
+        }
+        AuditedKey::RsaPublic(key) => {
+            let jwk = json!({
+                "kty": "RSA",
+                "n": encode(&key.n().to_bytes_be()),
+                "e": encode(&key.e().to_bytes_be()),
+            });
+            ("rsa", jwk, rsa_algorithm(key.n().bits()))
+        }
+        AuditedKey::Ec {
+            curve,
+            secret,
+            point,
+        } => {
+            let (crv, algorithm) = match curve {
+                Some(SECP256R1) => ("P-256", JwkeyAlgorithm::ES256),
+                Some(SECP384R1) => ("P-384", JwkeyAlgorithm::ES384),
+                Some(SECP256K1) => ("secp256k1", JwkeyAlgorithm::ES256K),
+                Some(SM2) => ("SM2", JwkeyAlgorithm::SM2),
+                curve => {
+                    return Err(Error::Unsupported(format!(
+                        "message signatures on curve {:?}",
+                        curve.map(|oid| oid.to_string())
+                    )))
+                }
+            };
+            let point =
+                compare::ec_point(curve, secret.as_deref(), point.as_deref())?;
+            let (x, y) = point[1 ..].split_at((point.len() - 1) / 2);
+            let mut jwk = json!({
+                "kty": "EC",
+                "crv": crv,
+                "x": encode(x),
+                "y": encode(y),
+            });
+            if let Some(secret) = secret {
+                jwk["d"] = json!(encode(&secret));
+            }
+            ("ec", jwk, algorithm)
+        }
+        AuditedKey::Edwards { x25519: true, .. } => {
+            return Err(Error::Unsupported(
+                "x25519 keys agree on secrets, they do not sign".to_string(),
+            ))
+        }
+        AuditedKey::Edwards { secret, point, .. } => {
+            let point = match (point, &secret) {
+                (Some(point), _) => point,
+                (None, Some(secret)) => compare::edwards_point(false, secret)?,
+                (None, None) => {
+                    return Err(Error::Unsupported(
+                        "edwards key without a point".to_string(),
+                    ))
+                }
+            };
+            let mut jwk =
+                json!({"kty": "OKP", "crv": "Ed25519", "x": encode(&point)});
+            if let Some(secret) = secret {
+                jwk["d"] = json!(encode(&secret));
+            }
+            ("ed25519", jwk, JwkeyAlgorithm::EdDSA)
+        }
+        AuditedKey::Symmetric(key) => {
+            let algorithm = match key.len() {
+                64 .. => JwkeyAlgorithm::HS512,
+                48 .. => JwkeyAlgorithm::HS384,
+                _ => JwkeyAlgorithm::HS256,
+            };
+            (
+                "symmetric",
+                json!({"kty": "oct", "k": encode(&key)}),
+                algorithm,
+            )
+        }
+    };
+    let algorithm = match algorithm {
+        Some(algorithm) if !compatible(default, algorithm) => {
+            return Err(Error::Unsupported(format!(
+                "{:?} with a {} key",
+                algorithm, kind
+            )))
+        }
+        Some(algorithm) => algorithm,
+        None => default,
+    };
+    Ok(MessageKey {
+        format,
+        kind,
+        jwk,
+        algorithm,
+    })
+}
+
+/// Pkcs#1 v1.5, which every verifier takes.
+fn rsa_algorithm(bits: usize) -> JwkeyAlgorithm {
+    match bits {
+        4096 .. => JwkeyAlgorithm::RS512,
+        3072 .. => JwkeyAlgorithm::RS384,
+        _ => JwkeyAlgorithm::RS256,
+    }
+}
+
+/// Rsa and hmac keys take any digest, curves have the one of their size.
+fn compatible(default: JwkeyAlgorithm, algorithm: JwkeyAlgorithm) -> bool {
+    match default {
+        JwkeyAlgorithm::RS256
+        | JwkeyAlgorithm::RS384
+        | JwkeyAlgorithm::RS512 => {
+            matches!(
+                algorithm,
+                JwkeyAlgorithm::RS256
+                    | JwkeyAlgorithm::RS384
+                    | JwkeyAlgorithm::RS512
+                    | JwkeyAlgorithm::PS256
+                    | JwkeyAlgorithm::PS384
+                    | JwkeyAlgorithm::PS512
+            )
+        }
+        JwkeyAlgorithm::HS256
+        | JwkeyAlgorithm::HS384
+        | JwkeyAlgorithm::HS512 => {
+            matches!(
+                algorithm,
+                JwkeyAlgorithm::HS256
+                    | JwkeyAlgorithm::HS384
+                    | JwkeyAlgorithm::HS512
+            )
+        }
+        default => algorithm == default,
+    }
+}
+
+fn digest_of(algorithm: JwkeyAlgorithm) -> Result<&'static str> {
+    Ok(match algorithm {
+        JwkeyAlgorithm::HS256
+        | JwkeyAlgorithm::RS256
+        | JwkeyAlgorithm::PS256
+        | JwkeyAlgorithm::ES256
+        | JwkeyAlgorithm::ES256K => "SHA-256",
+        JwkeyAlgorithm::HS384
+        | JwkeyAlgorithm::RS384
+        | JwkeyAlgorithm::PS384
+        | JwkeyAlgorithm::ES384 => "SHA-384",
+        JwkeyAlgorithm::HS512
+        | JwkeyAlgorithm::RS512
+        | JwkeyAlgorithm::PS512
+        | JwkeyAlgorithm::EdDSA => "SHA-512",
+        JwkeyAlgorithm::SM2 => "SM3",
+        algorithm => {
+            return Err(Error::Unsupported(format!(
+                "message signatures with {:?}",
+                algorithm
+            )))
+        }
+    })
+}
+
+fn algorithm_name(algorithm: JwkeyAlgorithm) -> String {
+    serde_json::to_value(algorithm)
+        .ok()
+        .and_then(|alg| alg.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+fn ecdsa_size(algorithm: JwkeyAlgorithm) -> Option<usize> {
+    match algorithm {
+        JwkeyAlgorithm::ES256
+        | JwkeyAlgorithm::ES256K
+        | JwkeyAlgorithm::SM2 => Some(64),
+        JwkeyAlgorithm::ES384 => Some(96),
+        _ => None,
+    }
+}
+
+/// Der signatures, as openssl writes them, brought to r || s.
+fn ecdsa_raw(
+    algorithm: JwkeyAlgorithm,
+    signature: Vec<u8>,
+) -> Result<(Option<SignatureFormat>, Vec<u8>)> {
+    let Some(size) = ecdsa_size(algorithm) else {
+        return Ok((None, signature));
+    };
+    if signature.len() == size || signature.first() != Some(&0x30) {
+        return Ok((Some(SignatureFormat::Raw), signature));
+    }
+    let (der, raw) = (SignatureFormat::Der, SignatureFormat::Raw);
+    let signature = match algorithm {
+        JwkeyAlgorithm::ES256 => {
+            convert::<p256::NistP256>(&signature, der, raw)
+        }
+        JwkeyAlgorithm::ES384 => {
+            convert::<p384::NistP384>(&signature, der, raw)
+        }
+        JwkeyAlgorithm::ES256K => {
+            convert::<k256::Secp256k1>(&signature, der, raw)
+        }
+        _ => convert::<sm2::Sm2>(&signature, der, raw),
+    }?;
+    Ok((Some(der), signature))
+}
+
+/// Hex when every character is a hex digit, which base64 of a signature
+/// all but never is, base64url when its alphabet shows.
+fn detect_encoding(signature: &str) -> SignatureEncoding {
+    if signature.len() % 2 == 0
+        && signature.bytes().all(|byte| byte.is_ascii_hexdigit())
+    {
+        SignatureEncoding::Hex
+    } else if signature.contains(['-', '_']) {
+        SignatureEncoding::Base64url
+    } else {
+        SignatureEncoding::Base64
+    }
+}
+
+fn encode(signature: &[u8], encoding: SignatureEncoding) -> String {
+    match encoding {
+        SignatureEncoding::Hex => base16ct::lower::encode_string(signature),
+        SignatureEncoding::Base64 => Base64::encode_string(signature),
+        SignatureEncoding::Base64url => {
+            Base64UrlUnpadded::encode_string(signature)
+        }
+    }
+}
+
+fn decode(signature: &str, encoding: SignatureEncoding) -> Result<Vec<u8>> {
+    let unpadded = !signature.ends_with('=');
+    match encoding {
+        SignatureEncoding::Hex => Ok(base16ct::mixed::decode_vec(signature)
+            .context("hex decode failed")?),
+        SignatureEncoding::Base64 => base64_decode(signature, unpadded, false),
+        SignatureEncoding::Base64url => {
+            base64_decode(signature, unpadded, true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{
+        sign_message, verify_message, SignMessageDto, SignatureEncoding,
+        VerifyMessageDto,
+    };
+    use crate::{
+        crypto::ecc::signature::{convert, SignatureFormat},
+        enums::TextEncoding,
+        jwt::JwkeyAlgorithm,
+    };
+
+    /// RFC 8032 section 7.1 test 1
+    const ED25519_PKCS8: &str = "-----BEGIN PRIVATE \
+                                 KEY-----\nMC4CAQAwBQYDK2VwBCIEIJ1hsZ3v/\
+                                 VpguoRK9JLsLMREScVpezJpGXA7rAMcrn9g\\
+                                 n-----END PRIVATE KEY-----\n";
+    const ED25519_SIGNATURE: &str = concat!(
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555f",
+        "b8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+    );
+
+    fn sign(
+        message: &str,
+        key: &str,
+        key_encoding: Option<TextEncoding>,
+        signature_encoding: Option<SignatureEncoding>,
+    ) -> super::SignedMessage {
+        sign_message(SignMessageDto {
+            message: message.to_string(),
+            message_encoding: None,
+            key: key.to_string(),
+            key_encoding,
+            algorithm: None,
+            signature_encoding,
+        })
+        .unwrap()
+    }
+
+    fn verify(
+        message: &str,
+        key: &str,
+        key_encoding: Option<TextEncoding>,
+        signature: &str,
+    ) -> super::VerifiedMessage {
+        verify_message(VerifyMessageDto {
+            message: message.to_string(),
+            message_encoding: None,
+            key: key.to_string(),
+            key_encoding,
+            algorithm: None,
+            signature: signature.to_string(),
+            signature_encoding: None,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ed25519_message() {
+        let signed =
+            sign("", ED25519_PKCS8, None, Some(SignatureEncoding::Hex));
+        assert_eq!(signed.signature, ED25519_SIGNATURE);
+        assert_eq!(signed.chosen.key_format, "pem");
+        assert_eq!(signed.chosen.algorithm, JwkeyAlgorithm::EdDSA);
+        assert_eq!(signed.chosen.digest, "SHA-512");
+
+        let verified =
+            verify("", ED25519_PKCS8, None, &ED25519_SIGNATURE.to_uppercase());
+        assert!(verified.verified);
+        assert_eq!(verified.chosen.signature_encoding, SignatureEncoding::Hex);
+        assert!(
+            !verify("tampered", ED25519_PKCS8, None, ED25519_SIGNATURE)
+                .verified
+        );
+    }
+
+    #[test]
+    fn test_hmac_message() {
+        let key = "0b".repeat(64);
+        let signed = sign(
+            "message",
+            &key,
+            Some(TextEncoding::Hex),
+            Some(SignatureEncoding::Base64url),
+        );
+        assert_eq!(signed.chosen.key_kind, "symmetric");
+        assert_eq!(signed.chosen.algorithm, JwkeyAlgorithm::HS512);
+        let verified =
+            verify("message", &key, Some(TextEncoding::Hex), &signed.signature);
+        assert!(verified.verified);
+        assert_eq!(
+            verified.chosen.signature_encoding,
+            SignatureEncoding::Base64url
+        );
+    }
+
+    #[test]
+    fn test_ecdsa_der_message() {
+        // RFC 6979 appendix A.2.5
+        let jwk = json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "YP7UuiVanTHJYet0xjVtaMBJuJI7Yfps5mliLmDyn7Y",
+            "y": "eQP-EAi4vJmkGunpVii8ZPLxsgwtfp9Rd6PClNRGIpk",
+            "d": "ya-p2EW6dRZrXCFXZ7HWk05Qw9s26JsSe4piKxIPZyE",
+        })
+        .to_string();
+        let signed = sign("sample", &jwk, None, Some(SignatureEncoding::Hex));
+        assert_eq!(signed.chosen.key_format, "jwk");
+        assert_eq!(signed.chosen.algorithm, JwkeyAlgorithm::ES256);
+        let der = convert::<p256::NistP256>(
+            &base16ct::lower::decode_vec(&signed.signature).unwrap(),
+            SignatureFormat::Raw,
+            SignatureFormat::Der,
+        )
+        .unwrap();
+        let verified =
+            verify("sample", &jwk, None, &base16ct::lower::encode_string(&der));
+        assert!(verified.verified);
+        assert_eq!(
+            verified.chosen.signature_format,
+            Some(SignatureFormat::Der)
+        );
+
+        assert!(sign_message(SignMessageDto {
+            message: "sample".to_string(),
+            message_encoding: None,
+            key: jwk,
+            key_encoding: None,
+            algorithm: Some(JwkeyAlgorithm::ES384),
+            signature_encoding: None,
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,612 @@
+//! Sigstore bundles (media types 0.1 to 0.3) checked offline against a local
+//! artifact. Identity policies are left to the caller.
+
+use anyhow::Context;
+use base64ct::{Base64, Encoding};
+use ecdsa::signature::Verifier;
+use pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::{
+    crypto::{
+        digest::digest_inner,
+        merkle::{verify_merkle_audit_proof, MerkleAuditProof},
+    },
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+    jwt::jcs::canonicalize,
+    x509::{
+        certificates_from_pem,
+        chain::{verify_chain, ChainDto, ChainVerification},
+        extension, read_all, read_tlv, subject_public_key_info,
+    },
+};
+
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+/// 1.3.6.1.4.1.57264.1.1, the issuer as a raw string
+const FULCIO_ISSUER: &[u8] =
+    &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x01];
+/// 1.3.6.1.4.1.57264.1.8, the issuer as a der utf8 string
+const FULCIO_ISSUER_V2: &[u8] =
+    &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x08];
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SigstoreBundleDto {
+    pub bundle: String,
+    /// path of the artifact file the bundle signs
+    pub artifact: String,
+    /// pem fulcio root and intermediate certificates
+    pub roots: String,
+    /// without it the log signatures are not checked
+    pub rekor_key: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TlogVerification {
+    pub log_index: u64,
+    pub kind: String,
+    /// unix timestamp in seconds
+    pub integrated_time: u64,
+    /// the canonicalized body names the artifact and the signature
+    pub body_valid: bool,
+    /// absent when the entry has no inclusion proof
+    pub inclusion_valid: Option<bool>,
+    /// absent without a checkpoint or a rekor key
+    pub checkpoint_valid: Option<bool>,
+    /// absent without an inclusion promise or a rekor key
+    pub promise_valid: Option<bool>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SigstoreVerification {
+    pub media_type: String,
+    /// hex sha-256 of the artifact
+    pub artifact_digest: String,
+    pub digest_valid: bool,
+    pub signature_valid: bool,
+    /// subject alternative names of the signing certificate
+    pub identities: Vec<String>,
+    pub oidc_issuer: Option<String>,
+    pub chain: ChainVerification,
+    pub tlog: Vec<TlogVerification>,
+    pub verified: bool,
+    pub problems: Vec<String>,
+}
+
+/// The artifact itself or a dsse envelope over an in-toto statement naming it.
+struct Signed {
+    message: Vec<u8>,
+    signature: Vec<u8>,
+    /// dsse payload, its hash is what rekor records
+    payload: Option<Vec<u8>>,
+}
+
+#[tauri::command]
+pub async fn verify_sigstore_bundle(
+    data: SigstoreBundleDto,
+) -> Result<SigstoreVerification> {
+    info!(
+        "verify sigstore bundle: {}, rekor key: {}",
+        data.artifact,
+        data.rekor_key.is_some()
+    );
+    let bundle: Value =
+        serde_json::from_str(&data.bundle).context("invalid bundle json")?;
+    let artifact = std::fs::read(&data.artifact)
+        .with_context(|| format!("read {} failed", data.artifact))?;
+    let rekor_key = data
+        .rekor_key
+        .as_deref()
+        .map(|key| {
+            let (label, der) = pem_rfc7468::decode_vec(key.as_bytes())
+                .context("invalid rekor key pem")?;
+            if label != "PUBLIC KEY" {
+                return Err(Error::Unsupported(format!("{} pem", label)));
+            }
+            Ok(der)
+        })
+        .transpose()?;
+    let mut problems = vec![];
+
+    let certificates = certificates(&bundle)?;
+    let leaf = &certificates[0];
+    let spki = subject_public_key_info(leaf)?;
+    let artifact_digest = digest_inner(Digest::Sha256, &artifact);
+
+    let (signed, digest_valid) = if bundle["messageSignature"].is_object() {
+        let signature = base64(&bundle, "/messageSignature/signature")?;
+        let digest_valid = match bundle
+            .pointer("/messageSignature/messageDigest/algorithm")
+            .and_then(Value::as_str)
+        {
+            Some(algorithm) => {
+                base64(&bundle, "/messageSignature/messageDigest/digest")?
+                    == digest_inner(digest(algorithm)?, &artifact)
+            }
+            None => true,
+        };
+        let signed = Signed {
+            message: artifact.clone(),
+            signature,
+            payload: None,
+        };
+        (signed, digest_valid)
+    } else if bundle["dsseEnvelope"].is_object() {
+        let payload = base64(&bundle, "/dsseEnvelope/payload")?;
+        let payload_type = string(&bundle, "/dsseEnvelope/payloadType")?;
+        let statement: Value = serde_json::from_slice(&payload)
+            .context("invalid in-toto statement")?;
+        let expected = TextEncoding::Hex.encode(&artifact_digest)?;
+        let digest_valid = statement["subject"]
+            .as_array()
+            .map(|subjects| {
+                subjects.iter().any(|subject| {
+                    subject["digest"]["sha256"].as_str()
+                        == Some(expected.as_str())
+                })
+            })
+            .unwrap_or(false);
+        let signed = Signed {
+            message: pae(payload_type, &payload),
+            signature: base64(&bundle, "/dsseEnvelope/signatures/0/sig")?,
+            payload: Some(payload),
+        };
+        (signed, digest_valid)
+    } else {
+        return Err(Error::Unsupported(
+            "bundle without message signature nor dsse envelope".to_string(),
+        ));
+    };
+    if !digest_valid {
+        problems.push("the bundle does not sign this artifact".to_string());
+    }
+    let signature_valid = verify(spki, &signed.message, &signed.signature)?;
+    if !signature_valid {
+        problems.push("the signature does not verify".to_string());
+    }
+
+    let mut tlog = vec![];
+    for entry in bundle
+        .pointer("/verificationMaterial/tlogEntries")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        tlog.push(verify_entry(
+            entry,
+            leaf,
+            &signed,
+            &artifact,
+            rekor_key.as_deref(),
+            &mut problems,
+        )?);
+    }
+    if tlog.is_empty() {
+        problems.push("the bundle has no transparency log entry".to_string());
+    }
+
+    // fulcio certificates live for minutes, they must have been valid when
+    // the log saw the signature
+    let chain = verify_chain(ChainDto {
+        certificates: certificates
+            .iter()
+            .map(|certificate| {
+                pem_rfc7468::encode_string(
+                    "CERTIFICATE",
+                    pkcs8::LineEnding::LF,
+                    certificate,
+                )
+                .context("encode certificate pem failed")
+            })
+            .collect::<std::result::Result<String, _>>()?,
+        roots: Some(data.roots),
+        fetch_missing: false,
+        at: tlog.iter().map(|entry| entry.integrated_time).min(),
+    })
+    .await?;
+    problems.extend(chain.problems.iter().cloned());
+
+    let logged = tlog.iter().all(|entry| {
+        entry.body_valid
+            && [
+                entry.inclusion_valid,
+                entry.checkpoint_valid,
+                entry.promise_valid,
+            ]
+            .iter()
+            .all(|valid| *valid != Some(false))
+            && (entry.inclusion_valid == Some(true)
+                || entry.promise_valid == Some(true))
+    });
+    Ok(SigstoreVerification {
+        media_type: bundle["mediaType"].as_str().unwrap_or_default().into(),
+        artifact_digest: TextEncoding::Hex.encode(&artifact_digest)?,
+        digest_valid,
+        signature_valid,
+        identities: identities(leaf)?,
+        oidc_issuer: oidc_issuer(leaf)?,
+        verified: digest_valid
+            && signature_valid
+            && chain.trusted
+            && !tlog.is_empty()
+            && logged,
+        chain,
+        tlog,
+        problems,
+    })
+}
+
+fn verify_entry(
+    entry: &Value,
+    leaf: &[u8],
+    signed: &Signed,
+    artifact: &[u8],
+    rekor_key: Option<&[u8]>,
+    problems: &mut Vec<String>,
+) -> Result<TlogVerification> {
+    let log_index = number(entry, "/logIndex")?;
+    let body = base64(entry, "/canonicalizedBody")?;
+    let record: Value =
+        serde_json::from_slice(&body).context("invalid rekor entry body")?;
+    let kind = record["kind"].as_str().unwrap_or_default().to_string();
+    let spec = &record["spec"];
+    let body_valid = match kind.as_str() {
+        "hashedrekord" => {
+            let algorithm = spec["data"]["hash"]["algorithm"]
+                .as_str()
+                .unwrap_or("sha256");
+            let hash = TextEncoding::Hex
+                .encode(&digest_inner(digest(algorithm)?, artifact))?;
+            let public_key = base64(spec, "/signature/publicKey/content")?;
+            spec["data"]["hash"]["value"].as_str() == Some(hash.as_str())
+                && base64(spec, "/signature/content")? == signed.signature
+                && certificates_from_pem(&String::from_utf8_lossy(&public_key))?
+                    .first()
+                    .is_some_and(|certificate| certificate == leaf)
+        }
+        "dsse" => {
+            let hash = TextEncoding::Hex.encode(&digest_inner(
+                Digest::Sha256,
+                signed.payload.as_deref().unwrap_or_default(),
+            ))?;
+            let signature = Base64::encode_string(&signed.signature);
+            spec["payloadHash"]["value"].as_str() == Some(hash.as_str())
+                && spec["signatures"].as_array().is_some_and(|signatures| {
+                    signatures.iter().any(|entry| {
+                        entry["signature"].as_str() == Some(signature.as_str())
+                    })
+                })
+        }
+        _ => {
+            problems.push(format!(
+                "log entry {} of kind {} is not cross-checked",
+                log_index, kind
+            ));
+            false
+        }
+    };
+    if !body_valid && matches!(kind.as_str(), "hashedrekord" | "dsse") {
+        problems.push(format!(
+            "log entry {} records another artifact or signature",
+            log_index
+        ));
+    }
+    if let Some(key) = rekor_key {
+        if base64(entry, "/logId/keyId")? != digest_inner(Digest::Sha256, key) {
+            problems.push(format!(
+                "log entry {} comes from another log",
+                log_index
+            ));
+        }
+    }
+
+    let (mut inclusion_valid, mut checkpoint_valid) = (None, None);
+    if let Some(proof) = entry.get("inclusionProof") {
+        let root = base64(proof, "/rootHash")?;
+        let size = number(proof, "/treeSize")?;
+        let hex = |bytes: &[u8]| TextEncoding::Hex.encode(bytes);
+        let mut path = vec![];
+        for hash in proof["hashes"].as_array().into_iter().flatten() {
+            let hash = Base64::decode_vec(hash.as_str().unwrap_or_default())
+                .context("invalid proof hash")?;
+            path.push(hex(&hash)?);
+        }
+        let mut leaf_input = vec![0x00];
+        leaf_input.extend_from_slice(&body);
+        let valid = verify_merkle_audit_proof(MerkleAuditProof {
+            digest: Digest::Sha256,
+            index: number(proof, "/logIndex")? as usize,
+            size: size as usize,
+            leaf: hex(&digest_inner(Digest::Sha256, &leaf_input))?,
+            path,
+            root: hex(&root)?,
+        })?;
+        if !valid {
+            problems.push(format!(
+                "log entry {} is not included in the tree it claims",
+                log_index
+            ));
+        }
+        inclusion_valid = Some(valid);
+        if let Some(envelope) = proof.pointer("/checkpoint/envelope") {
+            let envelope = envelope.as_str().unwrap_or_default();
+            checkpoint_valid =
+                match checkpoint(envelope, size, &root, rekor_key, log_index) {
+                    Ok(valid) => valid,
+                    Err(problem) => {
+                        problems.push(problem);
+                        Some(false)
+                    }
+                };
+        }
+    }
+
+    let integrated_time = number(entry, "/integratedTime")?;
+    let mut promise_valid = None;
+    if let (Some(key), Some(promise)) = (
+        rekor_key,
+        entry.pointer("/inclusionPromise/signedEntryTimestamp"),
+    ) {
+        let promise = Base64::decode_vec(promise.as_str().unwrap_or_default())
+            .context("invalid signed entry timestamp")?;
+        let payload = canonicalize(&json!({
+            "body": entry["canonicalizedBody"],
+            "integratedTime": integrated_time,
+            "logID": TextEncoding::Hex.encode(&base64(entry, "/logId/keyId")?)?,
+            "logIndex": log_index,
+        }))?;
+        let valid = verify(key, payload.as_bytes(), &promise)?;
+        if !valid {
+            problems.push(format!(
+                "signed entry timestamp of log entry {} does not verify",
+                log_index
+            ));
+        }
+        promise_valid = Some(valid);
+    }
+    Ok(TlogVerification {
+        log_index,
+        kind,
+        integrated_time,
+        body_valid,
+        inclusion_valid,
+        checkpoint_valid,
+        promise_valid,
+    })
+}
+
+/// Checks the signed note commits to the proven tree, and its signatures
+/// when `key` is given.
+fn checkpoint(
+    envelope: &str,
+    size: u64,
+    root: &[u8],
+    key: Option<&[u8]>,
+    log_index: u64,
+) -> std::result::Result<Option<bool>, String> {
+    let problem =
+        |what: &str| format!("checkpoint of log entry {} {}", log_index, what);
+    let (text, signatures) = envelope
+        .split_once("\n\n")
+        .ok_or_else(|| problem("is malformed"))?;
+    let lines = text.lines().collect::<Vec<_>>();
+    if lines.get(1) != Some(&size.to_string().as_str())
+        || lines.get(2) != Some(&Base64::encode_string(root).as_str())
+    {
+        return Err(problem("is for another tree"));
+    }
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    // the signed note body keeps its final newline
+    let signed = format!("{}\n", text);
+    let valid = signatures
+        .lines()
+        .filter_map(|line| line.strip_prefix("\u{2014} "))
+        .filter_map(|line| line.rsplit(' ').next())
+        .filter_map(|signature| Base64::decode_vec(signature).ok())
+        // a four byte key hint precedes the signature
+        .filter(|signature| signature.len() > 4)
+        .any(|signature| {
+            verify(key, signed.as_bytes(), &signature[4 ..]).unwrap_or(false)
+        });
+    if !valid {
+        return Err(problem("is not signed by the rekor key"));
+    }
+    Ok(Some(true))
+}
+
+/// The leaf first, from `certificate` (0.3) or `x509CertificateChain`.
+fn certificates(bundle: &Value) -> Result<Vec<Vec<u8>>> {
+    let material = &bundle["verificationMaterial"];
+    let certificates = if material["certificate"].is_object() {
+        vec![base64(material, "/certificate/rawBytes")?]
+    } else if let Some(chain) =
+        material.pointer("/x509CertificateChain/certificates")
+    {
+        chain
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|certificate| base64(certificate, "/rawBytes"))
+            .collect::<Result<_>>()?
+    } else {
+        vec![]
+    };
+    if certificates.is_empty() {
+        return Err(Error::Unsupported(
+            "bundle without fulcio certificate".to_string(),
+        ));
+    }
+    Ok(certificates)
+}
+
+/// Where fulcio puts the signer identity.
+fn identities(certificate: &[u8]) -> Result<Vec<String>> {
+    let Some(value) = extension(certificate, SUBJECT_ALT_NAME)? else {
+        return Ok(vec![]);
+    };
+    let (names, _) = read_tlv(value)?;
+    Ok(read_all(names.content)?
+        .iter()
+        .filter(|name| matches!(name.tag, 0x81 | 0x82 | 0x86))
+        .map(|name| String::from_utf8_lossy(name.content).into_owned())
+        .collect())
+}
+
+fn oidc_issuer(certificate: &[u8]) -> Result<Option<String>> {
+    if let Some(value) = extension(certificate, FULCIO_ISSUER_V2)? {
+        let (issuer, _) = read_tlv(value)?;
+        return Ok(Some(String::from_utf8_lossy(issuer.content).into()));
+    }
+    Ok(extension(certificate, FULCIO_ISSUER)?
+        .map(|issuer| String::from_utf8_lossy(issuer).into()))
+}
+
+/// DSSE pre-authentication encoding.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+fn verify(spki: &[u8], message: &[u8], signature: &[u8]) -> Result<bool> {
+    if let Ok(key) = p256::ecdsa::VerifyingKey::from_public_key_der(spki) {
+        return Ok(p256::ecdsa::Signature::from_der(signature)
+            .is_ok_and(|signature| key.verify(message, &signature).is_ok()));
+    }
+    if let Ok(key) = p384::ecdsa::VerifyingKey::from_public_key_der(spki) {
+        return Ok(p384::ecdsa::Signature::from_der(signature)
+            .is_ok_and(|signature| key.verify(message, &signature).is_ok()));
+    }
+    if let Ok(key) = RsaPublicKey::from_public_key_der(spki) {
+        return Ok(key
+            .verify(
+                Pkcs1v15Sign::new::<sha2::Sha256>(),
+                &digest_inner(Digest::Sha256, message),
+                signature,
+            )
+            .is_ok());
+    }
+    if let Ok(key) = ed25519_dalek::VerifyingKey::from_public_key_der(spki) {
+        return Ok(ed25519_dalek::Signature::from_slice(signature)
+            .is_ok_and(|signature| key.verify(message, &signature).is_ok()));
+    }
+    Err(Error::Unsupported("signing key algorithm".to_string()))
+}
+
+/// `SHA2_256` in bundles, `sha256` in rekor bodies.
+fn digest(algorithm: &str) -> Result<Digest> {
+    match algorithm.to_ascii_lowercase().replace('_', "").as_str() {
+        "sha2256" | "sha256" => Ok(Digest::Sha256),
+        "sha2384" | "sha384" => Ok(Digest::Sha384),
+        "sha2512" | "sha512" => Ok(Digest::Sha512),
+        _ => Err(Error::Unsupported(format!("digest {}", algorithm))),
+    }
+}
+
+fn string<'a>(value: &'a Value, pointer: &str) -> Result<&'a str> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            Error::Unsupported(format!("bundle without {}", pointer))
+        })
+}
+
+fn base64(value: &Value, pointer: &str) -> Result<Vec<u8>> {
+    Ok(Base64::decode_vec(string(value, pointer)?)
+        .with_context(|| format!("invalid base64 {}", pointer))?)
+}
+
+/// int64 fields are strings in the protobuf json mapping.
+fn number(value: &Value, pointer: &str) -> Result<u64> {
+    match value.pointer(pointer) {
+        Some(Value::Number(number)) => number.as_u64(),
+        Some(Value::String(number)) => number.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| Error::Unsupported(format!("bundle without {}", pointer)))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::{verify_sigstore_bundle, SigstoreBundleDto};
+
+    /// see sigstore.py
+    const VECTORS: &str = include_str!("../../tests/vectors/sigstore.json");
+
+    fn dto(name: &str, artifact: &str, rekor_key: bool) -> SigstoreBundleDto {
+        let vectors: Value = serde_json::from_str(VECTORS).unwrap();
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, artifact).unwrap();
+        SigstoreBundleDto {
+            bundle: vectors["bundle"].as_str().unwrap().to_string(),
+            artifact: path.to_string_lossy().into_owned(),
+            roots: vectors["roots"].as_str().unwrap().to_string(),
+            rekor_key: rekor_key
+                .then(|| vectors["rekorKey"].as_str().unwrap().to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_sigstore_bundle() {
+        let verification = verify_sigstore_bundle(dto(
+            "kits-sigstore-artifact",
+            "hello sigstore\n",
+            true,
+        ))
+        .await
+        .unwrap();
+        assert!(verification.verified, "{:?}", verification.problems);
+        assert_eq!(verification.identities, ["dev@kits.example"]);
+        assert_eq!(
+            verification.oidc_issuer.as_deref(),
+            Some("https://accounts.kits.example")
+        );
+        let entry = &verification.tlog[0];
+        assert_eq!(
+            (entry.log_index, entry.integrated_time),
+            (1002, 1717200300)
+        );
+        assert_eq!(entry.checkpoint_valid, Some(true));
+        assert_eq!(entry.promise_valid, Some(true));
+
+        // the log signatures are skipped without the rekor key
+        let verification = verify_sigstore_bundle(dto(
+            "kits-sigstore-artifact-keyless",
+            "hello sigstore\n",
+            false,
+        ))
+        .await
+        .unwrap();
+        assert!(verification.verified, "{:?}", verification.problems);
+        assert_eq!(verification.tlog[0].checkpoint_valid, None);
+
+        let verification = verify_sigstore_bundle(dto(
+            "kits-sigstore-artifact-tampered",
+            "hello sigstore!\n",
+            true,
+        ))
+        .await
+        .unwrap();
+        assert!(!verification.verified);
+        assert!(!verification.digest_valid);
+        assert!(!verification.signature_valid);
+        assert!(!verification.tlog[0].body_valid);
+    }
+}
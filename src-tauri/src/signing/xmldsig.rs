@@ -0,0 +1,624 @@
+//! XML-DSig as used by SAML, C14N 1.0 and Exclusive C14N, same document
+//! references only.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use base64ct::{Base64, Encoding};
+use ecdsa::signature::Verifier;
+use pkcs8::DecodePublicKey;
+use roxmltree::{Document, Node, NodeType};
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    crypto::digest::digest_inner,
+    enums::{Digest, TextEncoding},
+    errors::{Error, Result},
+    x509::subject_public_key_info,
+};
+
+const DSIG: &str = "http://www.w3.org/2000/09/xmldsig#";
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+const ENVELOPED: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const C14N: &str = "http://www.w3.org/TR/2001/REC-xml-c14n-20010315";
+const C14N_COMMENTS: &str =
+    "http://www.w3.org/TR/2001/REC-xml-c14n-20010315#WithComments";
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const EXC_C14N_COMMENTS: &str =
+    "http://www.w3.org/2001/10/xml-exc-c14n#WithComments";
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlSignatureDto {
+    pub xml: String,
+    /// certificate or spki pem to verify with, the `KeyInfo` certificate
+    /// when absent, which only proves integrity and not who signed
+    pub certificate: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlReference {
+    pub uri: String,
+    /// the element the reference points at
+    pub element: String,
+    pub transforms: Vec<String>,
+    pub digest_method: String,
+    pub digest_value: String,
+    pub computed_digest: String,
+    pub digest_valid: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct XmlSignatureInspection {
+    /// the element enclosing the `Signature`
+    pub parent: Option<String>,
+    pub canonicalization_method: String,
+    pub signature_method: String,
+    /// canonical form of `SignedInfo`, the signed bytes
+    pub signed_info: String,
+    pub references: Vec<XmlReference>,
+    pub certificate: Option<String>,
+    /// absent when there is no key to check with
+    pub signature_valid: Option<bool>,
+    pub verified: bool,
+}
+
+/// Canonicalization parameters, `prefixes` is the exclusive
+/// `InclusiveNamespaces PrefixList` with `#default` as the empty prefix.
+struct C14n {
+    exclusive: bool,
+    comments: bool,
+    prefixes: Vec<String>,
+}
+
+impl C14n {
+    fn from_method(method: Node) -> Result<Self> {
+        let algorithm = method.attribute("Algorithm").unwrap_or_default();
+        let (exclusive, comments) = match algorithm {
+            C14N => (false, false),
+            C14N_COMMENTS => (false, true),
+            EXC_C14N => (true, false),
+            EXC_C14N_COMMENTS => (true, true),
+            algorithm => {
+                return Err(Error::Unsupported(format!(
+                    "canonicalization `{}`",
+                    algorithm
+                )))
+            }
+        };
+        let prefixes = method
+            .children()
+            .find(|child| child.has_tag_name((EXC_C14N, "InclusiveNamespaces")))
+            .and_then(|namespaces| namespaces.attribute("PrefixList"))
+            .map(|list| {
+                list.split_whitespace()
+                    .map(|prefix| match prefix {
+                        "#default" => String::new(),
+                        prefix => prefix.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(C14n {
+            exclusive,
+            comments,
+            prefixes,
+        })
+    }
+}
+
+/// Digests are always checked, the signature only when a key is known.
+#[tauri::command]
+pub fn verify_xml_signature(
+    data: XmlSignatureDto,
+) -> Result<Vec<XmlSignatureInspection>> {
+    info!("verify xml signature, size: {}", data.xml.len());
+    let document = Document::parse(&data.xml).context("invalid xml")?;
+    let key = data.certificate.as_deref().map(public_key).transpose()?;
+    let signatures = document
+        .descendants()
+        .filter(|node| node.has_tag_name((DSIG, "Signature")))
+        .map(|signature| inspect(&document, signature, key.as_deref()))
+        .collect::<Result<Vec<_>>>()?;
+    if signatures.is_empty() {
+        return Err(Error::Unsupported(
+            "document without xml signature".to_string(),
+        ));
+    }
+    Ok(signatures)
+}
+
+fn inspect(
+    document: &Document,
+    signature: Node,
+    key: Option<&[u8]>,
+) -> Result<XmlSignatureInspection> {
+    let signed_info = child(signature, "SignedInfo")?;
+    let canonicalization = child(signed_info, "CanonicalizationMethod")?;
+    let signature_method = child(signed_info, "SignatureMethod")?
+        .attribute("Algorithm")
+        .unwrap_or_default()
+        .to_string();
+    let canonical =
+        canonicalize(signed_info, None, &C14n::from_method(canonicalization)?);
+    let references = signed_info
+        .children()
+        .filter(|node| node.has_tag_name((DSIG, "Reference")))
+        .map(|reference| digest_reference(document, signature, reference))
+        .collect::<Result<Vec<_>>>()?;
+    if references.is_empty() {
+        return Err(Error::Unsupported(
+            "signed info without reference".to_string(),
+        ));
+    }
+
+    let certificate = signature
+        .descendants()
+        .find(|node| node.has_tag_name((DSIG, "X509Certificate")))
+        .map(|certificate| base64(&text(certificate)))
+        .transpose()?;
+    let signature_value = base64(&text(child(signature, "SignatureValue")?))?;
+    let signature_valid = match (key, &certificate) {
+        (Some(key), _) => Some(verify(
+            &signature_method,
+            key,
+            canonical.as_bytes(),
+            &signature_value,
+        )?),
+        (None, Some(certificate)) => Some(verify(
+            &signature_method,
+            subject_public_key_info(certificate)?,
+            canonical.as_bytes(),
+            &signature_value,
+        )?),
+        (None, None) => None,
+    };
+    Ok(XmlSignatureInspection {
+        parent: signature
+            .parent_element()
+            .map(|parent| qname(parent).into()),
+        canonicalization_method: canonicalization
+            .attribute("Algorithm")
+            .unwrap_or_default()
+            .to_string(),
+        signature_method,
+        signed_info: canonical,
+        verified: signature_valid == Some(true)
+            && references.iter().all(|reference| reference.digest_valid),
+        references,
+        certificate: certificate
+            .map(|certificate| {
+                pem_rfc7468::encode_string(
+                    "CERTIFICATE",
+                    pkcs8::LineEnding::LF,
+                    &certificate,
+                )
+                .context("encode certificate pem failed")
+            })
+            .transpose()?,
+        signature_valid,
+    })
+}
+
+fn digest_reference(
+    document: &Document,
+    signature: Node,
+    reference: Node,
+) -> Result<XmlReference> {
+    let uri = reference.attribute("URI").unwrap_or_default();
+    let target = match uri.strip_prefix('#') {
+        _ if uri.is_empty() => document.root_element(),
+        Some(id) => {
+            // a second element with the same id is how signature wrapping
+            // attacks slip unsigned content past naive verifiers
+            let mut targets = document.descendants().filter(|node| {
+                ["ID", "Id", "id"]
+                    .iter()
+                    .any(|name| node.attribute(*name) == Some(id))
+            });
+            let target = targets.next().ok_or_else(|| {
+                Error::Unsupported(format!("reference `{}` not found", uri))
+            })?;
+            if targets.next().is_some() {
+                return Err(Error::Unsupported(format!(
+                    "reference `{}` matches more than one element",
+                    uri
+                )));
+            }
+            target
+        }
+        None => {
+            return Err(Error::Unsupported(format!(
+                "external reference `{}`",
+                uri
+            )))
+        }
+    };
+
+    let mut transforms = Vec::new();
+    let mut excluded = None;
+    // a node-set left untransformed is serialized with C14N 1.0
+    let mut c14n = C14n {
+        exclusive: false,
+        comments: false,
+        prefixes: vec![],
+    };
+    if let Some(list) = reference
+        .children()
+        .find(|node| node.has_tag_name((DSIG, "Transforms")))
+    {
+        for transform in list
+            .children()
+            .filter(|node| node.has_tag_name((DSIG, "Transform")))
+        {
+            let algorithm =
+                transform.attribute("Algorithm").unwrap_or_default();
+            if algorithm == ENVELOPED {
+                excluded = Some(signature);
+            } else {
+                c14n = C14n::from_method(transform)?;
+            }
+            transforms.push(algorithm.to_string());
+        }
+    }
+    // same document references drop comments before any transform runs
+    c14n.comments = false;
+
+    let digest_method = child(reference, "DigestMethod")?
+        .attribute("Algorithm")
+        .unwrap_or_default()
+        .to_string();
+    let digest = match digest_method.as_str() {
+        "http://www.w3.org/2000/09/xmldsig#sha1" => Digest::Sha1,
+        "http://www.w3.org/2001/04/xmlenc#sha256" => Digest::Sha256,
+        "http://www.w3.org/2001/04/xmldsig-more#sha384" => Digest::Sha384,
+        "http://www.w3.org/2001/04/xmlenc#sha512" => Digest::Sha512,
+        method => {
+            return Err(Error::Unsupported(format!("digest `{}`", method)))
+        }
+    };
+    let computed =
+        digest_inner(digest, canonicalize(target, excluded, &c14n).as_bytes());
+    let digest_value = text(child(reference, "DigestValue")?);
+    Ok(XmlReference {
+        uri: uri.to_string(),
+        element: qname(target).to_string(),
+        transforms,
+        digest_method,
+        digest_valid: base64(&digest_value)? == computed,
+        digest_value,
+        computed_digest: TextEncoding::Base64.encode(&computed)?,
+    })
+}
+
+fn verify(
+    method: &str,
+    spki: &[u8],
+    data: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    let rsa = |scheme: Pkcs1v15Sign, digest: Digest| -> Result<bool> {
+        let key = RsaPublicKey::from_public_key_der(spki)
+            .context("signing key is not rsa")?;
+        Ok(key
+            .verify(scheme, &digest_inner(digest, data), signature)
+            .is_ok())
+    };
+    Ok(match method {
+        "http://www.w3.org/2000/09/xmldsig#rsa-sha1" => {
+            rsa(Pkcs1v15Sign::new::<sha1::Sha1>(), Digest::Sha1)?
+        }
+        "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256" => {
+            rsa(Pkcs1v15Sign::new::<sha2::Sha256>(), Digest::Sha256)?
+        }
+        "http://www.w3.org/2001/04/xmldsig-more#rsa-sha384" => {
+            rsa(Pkcs1v15Sign::new::<sha2::Sha384>(), Digest::Sha384)?
+        }
+        "http://www.w3.org/2001/04/xmldsig-more#rsa-sha512" => {
+            rsa(Pkcs1v15Sign::new::<sha2::Sha512>(), Digest::Sha512)?
+        }
+        // RFC 4051 ecdsa signatures are the raw r || s
+        "http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha256" => {
+            let key = p256::ecdsa::VerifyingKey::from_public_key_der(spki)
+                .context("signing key is not p-256")?;
+            p256::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify(data, &signature).is_ok())
+        }
+        "http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha384" => {
+            let key = p384::ecdsa::VerifyingKey::from_public_key_der(spki)
+                .context("signing key is not p-384")?;
+            p384::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify(data, &signature).is_ok())
+        }
+        method => {
+            return Err(Error::Unsupported(format!(
+                "signature method `{}`",
+                method
+            )))
+        }
+    })
+}
+
+fn public_key(pem: &str) -> Result<Vec<u8>> {
+    let (label, der) = pem_rfc7468::decode_vec(pem.as_bytes())
+        .context("invalid pem verification key")?;
+    match label {
+        "CERTIFICATE" => Ok(subject_public_key_info(&der)?.to_vec()),
+        "PUBLIC KEY" => Ok(der),
+        label => Err(Error::Unsupported(format!("{} pem", label))),
+    }
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &str,
+) -> Result<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.has_tag_name((DSIG, name)))
+        .ok_or_else(|| {
+            Error::Unsupported(format!("{} without {}", qname(node), name))
+        })
+}
+
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+fn base64(value: &str) -> Result<Vec<u8>> {
+    Base64::decode_vec(&value.split_whitespace().collect::<String>())
+        .map_err(|_| Error::Unsupported("invalid base64 value".to_string()))
+}
+
+/// The element name as written, the tree only keeps the expanded name.
+fn qname<'a>(node: Node<'_, 'a>) -> &'a str {
+    let tag = &node.document().input_text()[node.range().start + 1 ..];
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    &tag[.. end]
+}
+
+fn prefix(qname: &str) -> &str {
+    qname.split_once(':').map_or("", |(prefix, _)| prefix)
+}
+
+/// Serializes the subtree at `apex`, `excluded` left out.
+fn canonicalize(apex: Node, excluded: Option<Node>, c14n: &C14n) -> String {
+    let mut output = String::new();
+    write_element(apex, excluded, c14n, &BTreeMap::new(), true, &mut output);
+    output
+}
+
+fn write_element(
+    element: Node,
+    excluded: Option<Node>,
+    c14n: &C14n,
+    rendered: &BTreeMap<String, String>,
+    apex: bool,
+    output: &mut String,
+) {
+    let name = qname(element);
+    let in_scope = element
+        .namespaces()
+        .filter(|namespace| namespace.name() != Some("xml"))
+        .map(|namespace| {
+            (
+                namespace.name().unwrap_or_default().to_string(),
+                namespace.uri().to_string(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut attributes = element
+        .attributes()
+        .map(|attribute| {
+            let qname = match attribute.namespace() {
+                Some(XML) => format!("xml:{}", attribute.name()),
+                Some(uri) => in_scope
+                    .iter()
+                    .find(|(prefix, value)| !prefix.is_empty() && *value == uri)
+                    .map(|(prefix, _)| {
+                        format!("{}:{}", prefix, attribute.name())
+                    })
+                    .unwrap_or_else(|| attribute.name().to_string()),
+                None => attribute.name().to_string(),
+            };
+            (
+                (
+                    attribute.namespace().unwrap_or_default().to_string(),
+                    attribute.name().to_string(),
+                ),
+                (qname, attribute.value().to_string()),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    // C14N 1.0 carries xml:* attributes of omitted ancestors to the apex
+    if apex && !c14n.exclusive {
+        for ancestor in element.ancestors().skip(1) {
+            for attribute in ancestor.attributes() {
+                if attribute.namespace() == Some(XML) {
+                    attributes
+                        .entry((XML.to_string(), attribute.name().to_string()))
+                        .or_insert_with(|| {
+                            (
+                                format!("xml:{}", attribute.name()),
+                                attribute.value().to_string(),
+                            )
+                        });
+                }
+            }
+        }
+    }
+
+    let candidates = if c14n.exclusive {
+        let mut utilized = vec![prefix(name).to_string()];
+        utilized.extend(
+            attributes
+                .values()
+                .map(|(qname, _)| prefix(qname).to_string())
+                .filter(|prefix| !prefix.is_empty() && prefix != "xml"),
+        );
+        utilized.extend(c14n.prefixes.iter().cloned());
+        utilized
+    } else {
+        let mut all = in_scope.keys().cloned().collect::<Vec<_>>();
+        all.push(String::new());
+        all
+    };
+    let mut scope = rendered.clone();
+    let mut declarations = BTreeMap::new();
+    for prefix in candidates {
+        let uri = match in_scope.get(&prefix) {
+            Some(uri) => uri.clone(),
+            None if prefix.is_empty() => String::new(),
+            None => continue,
+        };
+        let current = scope.get(&prefix).cloned().unwrap_or_default();
+        if current != uri {
+            scope.insert(prefix.clone(), uri.clone());
+            declarations.insert(prefix, uri);
+        }
+    }
+
+    output.push('<');
+    output.push_str(name);
+    for (prefix, uri) in &declarations {
+        match prefix.as_str() {
+            "" => output.push_str(" xmlns=\""),
+            prefix => output.push_str(&format!(" xmlns:{}=\"", prefix)),
+        }
+        escape(uri, true, output);
+        output.push('"');
+    }
+    for (qname, value) in attributes.values() {
+        output.push_str(&format!(" {}=\"", qname));
+        escape(value, true, output);
+        output.push('"');
+    }
+    output.push('>');
+    for child in element.children() {
+        if Some(child) == excluded {
+            continue;
+        }
+        match child.node_type() {
+            NodeType::Element => {
+                write_element(child, excluded, c14n, &scope, false, output)
+            }
+            NodeType::Text => {
+                escape(child.text().unwrap_or_default(), false, output)
+            }
+            NodeType::Comment if c14n.comments => {
+                output.push_str(&format!(
+                    "<!--{}-->",
+                    child.text().unwrap_or_default()
+                ));
+            }
+            NodeType::PI => {
+                if let Some(pi) = child.pi() {
+                    match pi.value {
+                        Some(value) => output
+                            .push_str(&format!("<?{} {}?>", pi.target, value)),
+                        None => output.push_str(&format!("<?{}?>", pi.target)),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    output.push_str(&format!("</{}>", name));
+}
+
+fn escape(value: &str, attribute: bool, output: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' if !attribute => output.push_str("&gt;"),
+            '"' if attribute => output.push_str("&quot;"),
+            '\t' if attribute => output.push_str("&#x9;"),
+            '\n' if attribute => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use roxmltree::Document;
+
+    use super::{canonicalize, verify_xml_signature, C14n, XmlSignatureDto};
+
+    // generated by tests/vectors/saml.py with pyca/cryptography
+    const SAML: &str = include_str!("../../tests/vectors/saml.xml");
+
+    fn dto(xml: String) -> XmlSignatureDto {
+        XmlSignatureDto {
+            xml,
+            certificate: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_xml_signature() {
+        let signatures = verify_xml_signature(dto(SAML.to_string())).unwrap();
+        assert_eq!(signatures.len(), 1);
+        let signature = &signatures[0];
+        assert_eq!(signature.parent.as_deref(), Some("saml:Assertion"));
+        assert!(signature.signed_info.starts_with(
+            "<ds:SignedInfo xmlns:ds=\"http://www.w3.org/2000/09/xmldsig#\">\
+             <ds:CanonicalizationMethod \
+             Algorithm=\"http://www.w3.org/2001/10/xml-exc-c14n#\">\
+             </ds:CanonicalizationMethod>"
+        ));
+        assert_eq!(signature.references[0].element, "saml:Assertion");
+        assert!(signature.references[0].digest_valid);
+        assert_eq!(signature.signature_valid, Some(true));
+        assert!(signature.verified);
+
+        let tampered =
+            verify_xml_signature(dto(SAML.replace(">alice<", ">mallory<")))
+                .unwrap();
+        assert!(!tampered[0].references[0].digest_valid);
+        assert_eq!(tampered[0].signature_valid, Some(true));
+        assert!(!tampered[0].verified);
+
+        // a second element with the signed id, the wrapping attack shape
+        assert!(verify_xml_signature(dto(
+            SAML.replace("<!-- response -->", "<saml:Assertion ID=\"a1\"/>")
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_canonicalize_inclusive() {
+        let document = Document::parse(SAML).unwrap();
+        let assertion = document
+            .descendants()
+            .find(|node| node.attribute("ID") == Some("a1"))
+            .unwrap();
+        let signature = assertion
+            .children()
+            .find(|node| node.tag_name().name() == "Signature");
+        let canonical = canonicalize(assertion, signature, &C14n {
+            exclusive: false,
+            comments: true,
+            prefixes: vec![],
+        });
+        assert!(canonical.starts_with(
+            "<saml:Assertion \
+             xmlns:saml=\"urn:oasis:names:tc:SAML:2.0:assertion\" \
+             xmlns:samlp=\"urn:oasis:names:tc:SAML:2.0:protocol\" ID=\"a1\" \
+             Version=\"2.0\">"
+        ));
+        assert!(canonical.contains("<!-- unsigned -->"));
+        assert!(!canonical.contains("Signature"));
+    }
+}
@@ -0,0 +1,456 @@
+//! OpenSSH public keys (RFC 4253) and the known_hosts and authorized_keys
+//! files of sshd(8).
+
+use base64ct::{Base64, Base64Unpadded, Encoding};
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    audit::{sorted, Finding, Severity},
+    crypto::{digest::digest_inner, prf::prf_inner},
+    enums::{Digest, Prf},
+    errors::{Error, Result},
+};
+
+const CERTIFICATE_SUFFIX: &str = "-cert-v01@openssh.com";
+
+pub(crate) struct SshPublicKey {
+    pub key_type: String,
+    pub bits: Option<usize>,
+    pub certificate: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SshEntry {
+    pub line: usize,
+    /// `@cert-authority` or `@revoked` in known_hosts
+    pub marker: Option<String>,
+    /// the host patterns of known_hosts, hashed ones as written
+    pub hosts: Vec<String>,
+    pub hashed: bool,
+    pub matches: Option<bool>,
+    pub options: Vec<String>,
+    pub key_type: String,
+    pub bits: Option<usize>,
+    pub certificate: bool,
+    /// `SHA256:` and the unpadded base64 digest of the key, as ssh-keygen -l
+    pub fingerprint: String,
+    pub comment: Option<String>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SshFileAnalysis {
+    pub entries: Vec<SshEntry>,
+    pub errors: Vec<String>,
+}
+
+/// A non default port is written `[host]:port` as ssh does.
+#[tauri::command]
+pub fn analyze_known_hosts(
+    content: String,
+    host: Option<String>,
+) -> Result<SshFileAnalysis> {
+    info!("analyze known hosts, host: {:?}", host);
+    analyze(&content, |number, line| {
+        let (marker, line) = match line.strip_prefix('@') {
+            Some(rest) => {
+                let (marker, rest) = split_field(rest);
+                (Some(format!("@{}", marker)), rest)
+            }
+            None => (None, line),
+        };
+        let (hosts, rest) = split_field(line);
+        let mut entry = entry(number, rest)?;
+        entry.hosts = hosts.split(',').map(String::from).collect();
+        entry.hashed = hosts.starts_with("|1|");
+        entry.matches = match &host {
+            Some(host) => Some(host_matches(hosts, host)?),
+            None => None,
+        };
+        match marker.as_deref() {
+            None | Some("@cert-authority") => {}
+            Some("@revoked") => entry.findings.push(Finding::new(
+                Severity::Info,
+                "revoked",
+                "the key is marked as revoked",
+            )),
+            Some(marker) => {
+                return Err(Error::Unsupported(format!("marker {}", marker)))
+            }
+        }
+        entry.marker = marker;
+        if !entry.hashed {
+            entry.findings.push(Finding::new(
+                Severity::Info,
+                "plain-hosts",
+                "host names are readable, HashKnownHosts hides them",
+            ));
+        }
+        Ok(entry)
+    })
+}
+
+#[tauri::command]
+pub fn analyze_authorized_keys(content: String) -> Result<SshFileAnalysis> {
+    info!("analyze authorized keys, size: {}", content.len());
+    analyze(&content, |number, line| {
+        let (first, _) = split_field(line);
+        let (options, rest) = if is_key_type(first) {
+            ("", line)
+        } else {
+            split_field(line)
+        };
+        let mut entry = entry(number, rest)?;
+        entry.options = split_options(options);
+        Ok(entry)
+    })
+}
+
+/// Flags keys seen on an earlier line.
+fn analyze(
+    content: &str,
+    parse: impl Fn(usize, &str) -> Result<SshEntry>,
+) -> Result<SshFileAnalysis> {
+    let mut entries: Vec<SshEntry> = vec![];
+    let mut errors = vec![];
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut entry = match parse(index + 1, line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(format!("line {}: {}", index + 1, e));
+                continue;
+            }
+        };
+        if let Some(previous) = entries.iter().find(|previous| {
+            previous.fingerprint == entry.fingerprint
+                && previous.hosts == entry.hosts
+        }) {
+            entry.findings.push(Finding::new(
+                Severity::Low,
+                "duplicate-key",
+                format!("same key as line {}", previous.line),
+            ));
+        }
+        entry.findings = sorted(entry.findings);
+        entries.push(entry);
+    }
+    Ok(SshFileAnalysis { entries, errors })
+}
+
+fn entry(line: usize, rest: &str) -> Result<SshEntry> {
+    let (key_type, rest) = split_field(rest);
+    let (encoded, comment) = split_field(rest);
+    let blob = Base64::decode_vec(encoded)
+        .map_err(|_| Error::Unsupported("key is not base64".to_string()))?;
+    let key = parse_public_key(&blob)?;
+    let mut findings = vec![];
+    if key.key_type != key_type {
+        findings.push(Finding::new(
+            Severity::Medium,
+            "type-mismatch",
+            format!("listed as {} but the key is {}", key_type, key.key_type),
+        ));
+    }
+    match (key.key_type.trim_end_matches(CERTIFICATE_SUFFIX), key.bits) {
+        ("ssh-dss", _) => findings.push(Finding::new(
+            Severity::High,
+            "dsa-key",
+            "ssh-dss is stuck at 1024 bits and disabled since OpenSSH 7.0",
+        )),
+        ("ssh-rsa", Some(bits)) if bits < 1024 => findings.push(Finding::new(
+            Severity::Critical,
+            "rsa-size",
+            format!("{} bits modulus is factorable today", bits),
+        )),
+        ("ssh-rsa", Some(bits)) if bits < 2048 => findings.push(Finding::new(
+            Severity::High,
+            "rsa-size",
+            format!("{} bits modulus is below the 2048 bits minimum", bits),
+        )),
+        _ => {}
+    }
+    Ok(SshEntry {
+        line,
+        marker: None,
+        hosts: vec![],
+        hashed: false,
+        matches: None,
+        options: vec![],
+        key_type: key.key_type,
+        bits: key.bits,
+        certificate: key.certificate,
+        fingerprint: fingerprint(&blob),
+        comment: Some(comment.to_string()).filter(|c| !c.is_empty()),
+        findings,
+    })
+}
+
+pub(crate) fn parse_public_key(blob: &[u8]) -> Result<SshPublicKey> {
+    let mut input = blob;
+    let key_type = String::from_utf8(take_string(&mut input)?.to_vec())
+        .map_err(|_| Error::Unsupported("invalid ssh key type".to_string()))?;
+    let certificate = key_type.ends_with(CERTIFICATE_SUFFIX);
+    if certificate {
+        // nonce
+        take_string(&mut input)?;
+    }
+    let bits = match key_type.trim_end_matches(CERTIFICATE_SUFFIX) {
+        "ssh-rsa" => {
+            take_string(&mut input)?;
+            Some(mpint_bits(take_string(&mut input)?))
+        }
+        "ssh-dss" => Some(mpint_bits(take_string(&mut input)?)),
+        "ssh-ed25519" | "sk-ssh-ed25519@openssh.com" => {
+            match take_string(&mut input)?.len() {
+                32 => Some(256),
+                _ => {
+                    return Err(Error::Unsupported(
+                        "invalid ed25519 key".to_string(),
+                    ))
+                }
+            }
+        }
+        "ssh-ed448" => Some(448),
+        key_type if is_key_type(key_type) && key_type.contains("nistp") => {
+            match take_string(&mut input)? {
+                b"nistp256" => Some(256),
+                b"nistp384" => Some(384),
+                b"nistp521" => Some(521),
+                _ => None,
+            }
+        }
+        key_type => {
+            return Err(Error::Unsupported(format!(
+                "ssh key type {}",
+                key_type
+            )))
+        }
+    };
+    Ok(SshPublicKey {
+        key_type,
+        bits,
+        certificate,
+    })
+}
+
+pub(crate) fn fingerprint(blob: &[u8]) -> String {
+    format!(
+        "SHA256:{}",
+        Base64Unpadded::encode_string(&digest_inner(Digest::Sha256, blob))
+    )
+}
+
+pub(crate) fn take_string<'a>(input: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let truncated = || Error::Unsupported("truncated ssh key".to_string());
+    let (length, rest) =
+        input.split_first_chunk::<4>().ok_or_else(truncated)?;
+    let length = u32::from_be_bytes(*length) as usize;
+    if rest.len() < length {
+        return Err(truncated());
+    }
+    let (value, rest) = rest.split_at(length);
+    *input = rest;
+    Ok(value)
+}
+
+fn mpint_bits(mpint: &[u8]) -> usize {
+    let skip = mpint.iter().take_while(|byte| **byte == 0).count();
+    match mpint.get(skip) {
+        Some(first) => {
+            (mpint.len() - skip) * 8 - first.leading_zeros() as usize
+        }
+        None => 0,
+    }
+}
+
+pub(crate) fn is_key_type(field: &str) -> bool {
+    ["ssh-", "ecdsa-", "sk-"]
+        .iter()
+        .any(|prefix| field.starts_with(prefix))
+}
+
+/// A field ends at whitespace outside double quotes.
+fn split_field(line: &str) -> (&str, &str) {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                return (&line[.. index], line[index ..].trim_start())
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+fn split_options(options: &str) -> Vec<String> {
+    let mut split = vec![];
+    let (mut start, mut quoted) = (0, false);
+    for (index, c) in options.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                split.push(options[start .. index].to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < options.len() {
+        split.push(options[start ..].to_string());
+    }
+    split
+}
+
+/// A negated pattern that matches wins over everything else, as in ssh.
+fn host_matches(patterns: &str, host: &str) -> Result<bool> {
+    let host = host.to_ascii_lowercase();
+    let mut matched = false;
+    for pattern in patterns.split(',') {
+        if let Some(hashed) = pattern.strip_prefix("|1|") {
+            let (salt, hash) = hashed.split_once('|').ok_or_else(|| {
+                Error::Unsupported("malformed hashed host".to_string())
+            })?;
+            let decode = |value| {
+                Base64::decode_vec(value).map_err(|_| {
+                    Error::Unsupported("malformed hashed host".to_string())
+                })
+            };
+            let mac =
+                prf_inner(Prf::HmacSha1, &decode(salt)?, host.as_bytes())?;
+            matched |= mac == decode(hash)?;
+        } else if let Some(negated) = pattern.strip_prefix('!') {
+            if glob(&negated.to_ascii_lowercase(), &host) {
+                return Ok(false);
+            }
+        } else {
+            matched |= glob(&pattern.to_ascii_lowercase(), &host);
+        }
+    }
+    Ok(matched)
+}
+
+/// `*` and `?` wildcards.
+fn glob(pattern: &str, text: &str) -> bool {
+    match pattern.chars().next() {
+        None => text.is_empty(),
+        Some('*') => (0 ..= text.len())
+            .filter(|index| text.is_char_boundary(*index))
+            .any(|index| glob(&pattern[1 ..], &text[index ..])),
+        Some('?') => {
+            let mut chars = text.chars();
+            chars.next().is_some() && glob(&pattern[1 ..], chars.as_str())
+        }
+        Some(c) => {
+            text.starts_with(c)
+                && glob(&pattern[c.len_utf8() ..], &text[c.len_utf8() ..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{analyze_authorized_keys, analyze_known_hosts, glob};
+
+    const RSA_1024: &str =
+        "AAAAB3NzaC1yc2EAAAADAQABAAAAgQDXpMj2HR84QAY6Gg4CBAUIN3po\
+         uD7lB+ECiQJFmbYNgdmYMx/cIGQnNgOwZ5urPchD3TADc9xjQ9Wi845v\
+         tK1O1x9aR9s/gLfuZobdXfg8vuhviXgoF5pX86qUX03NgMBqxrC6EYc6\
+         C4cyvyHTzfilrd7K87ANejqXEim94iv4NQ==";
+    const ED25519: &str =
+        "AAAAC3NzaC1lZDI1NTE5AAAAIITYE2oQDPhuKdTYX35YaVxuP71QbyUnlsHSHFqXn5gw";
+    const ECDSA: &str =
+        "AAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAAAIbmlzdHAyNTYAAABBBH7B\
+         t6tWcx+qOSOv/leFIXl6c4agEEIYifXJhElAJrxfH6cmh1XXziTOY8EF\
+         XDaVlEZm1ZoJO2OCWPlCxvattRA=";
+    /// github.com hashed under the salt 00 01 .. 13
+    const HASHED: &str =
+        "|1|AAECAwQFBgcICQoLDA0ODxAREhM=|/vGHEQmgsSrggHW81/s0OAW0mAs=";
+
+    #[test]
+    fn test_analyze_known_hosts() {
+        let content = format!(
+            "# comment\n{} ssh-ed25519 {}\nexample.com,192.0.2.1 \
+             ecdsa-sha2-nistp256 {} host\n@revoked * ssh-rsa \
+             {}\nexample.com,192.0.2.1 ecdsa-sha2-nistp256 {}\nexample.com \
+             ssh-ed25519 AAAA\n",
+            HASHED, ED25519, ECDSA, RSA_1024, ECDSA
+        );
+        let analysis =
+            analyze_known_hosts(content, Some("github.com".to_string()))
+                .unwrap();
+        assert_eq!(analysis.errors.len(), 1);
+        assert!(analysis.errors[0].starts_with("line 6:"));
+        let entries = analysis.entries;
+        assert_eq!(entries.len(), 4);
+
+        assert!(entries[0].hashed);
+        assert_eq!(entries[0].matches, Some(true));
+        assert_eq!(
+            entries[0].fingerprint,
+            "SHA256:gV2Onsszjgcns7oUtTFhnVtIr5waIQnySDoQIFkXkwk"
+        );
+        assert!(entries[0].findings.is_empty());
+
+        assert_eq!(entries[1].hosts, ["example.com", "192.0.2.1"]);
+        assert_eq!(entries[1].matches, Some(false));
+        assert_eq!(entries[1].bits, Some(256));
+        assert_eq!(entries[1].comment.as_deref(), Some("host"));
+
+        assert_eq!(entries[2].marker.as_deref(), Some("@revoked"));
+        assert_eq!(entries[2].matches, Some(true));
+        assert_eq!(entries[2].bits, Some(1024));
+        let codes = entries[2]
+            .findings
+            .iter()
+            .map(|finding| finding.code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes, ["rsa-size", "revoked", "plain-hosts"]);
+
+        assert!(entries[3]
+            .findings
+            .iter()
+            .any(|f| f.code == "duplicate-key"));
+    }
+
+    #[test]
+    fn test_analyze_authorized_keys() {
+        let content = format!(
+            "command=\"echo a, b\",no-pty,from=\"10.0.0.0/8\" ssh-ed25519 {} \
+             deploy key\nssh-rsa {}\nssh-ed25519 {} again\n",
+            ED25519, RSA_1024, ED25519
+        );
+        let analysis = analyze_authorized_keys(content).unwrap();
+        assert!(analysis.errors.is_empty());
+        let entries = analysis.entries;
+        assert_eq!(entries[0].options, [
+            "command=\"echo a, b\"",
+            "no-pty",
+            "from=\"10.0.0.0/8\""
+        ]);
+        assert_eq!(entries[0].comment.as_deref(), Some("deploy key"));
+        assert!(entries[1].options.is_empty());
+        assert_eq!(
+            entries[1].fingerprint,
+            "SHA256:GFo1QzjM6Mw+zViQy0JhiCWBVrIBMTHSxod8EAxAuV4"
+        );
+        assert_eq!(entries[1].findings[0].code, "rsa-size");
+        assert_eq!(entries[2].findings[0].code, "duplicate-key");
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("*.example.com", "a.example.com"));
+        assert!(!glob("*.example.com", "example.com"));
+        assert!(glob("192.0.2.?", "192.0.2.1"));
+        assert!(!glob("192.0.2.?", "192.0.2.10"));
+    }
+}
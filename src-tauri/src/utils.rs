@@ -1,47 +1,145 @@
+use std::collections::BTreeMap;
+
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use strum::IntoEnumIterator;
 
 use super::{
     enums::{
-        Digest, EccCurveName, EciesEncryptionAlgorithm, EdwardsCurveName, Kdf,
-        RsaEncryptionPadding,
+        AesEncryptionPadding, Digest, EccCurveName, EciesEncryptionAlgorithm,
+        EdwardsCurveName, EncryptionMode, Kdf, Pkcs, RsaEncryptionPadding, Xof,
     },
     errors::Result,
 };
 use crate::{
+    crypto::digest::digest_inner,
     enums::RsaKeySize,
-    jwt::{JwkeyAlgorithm, JwkeyOperation, JwkeyType, JwkeyUsage},
+    jwt::{JwkeyAlgorithm, JwkeyOperation, JwkeyType, JwkeyUsage, KidStrategy},
+    profile::{self, Algorithm},
+    rng,
+    vault::now,
 };
-#[derive(Serialize, Deserialize)]
-pub struct KeyTuple(pub Option<String>, pub Option<String>);
+
+/// A key pair with the facts the ui shows about it.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyTuple {
+    pub private_key: Option<String>,
+    pub public_key: Option<String>,
+    /// hex sha256 of the public key, its der when it is pem
+    pub fingerprint: Option<String>,
+    pub algorithm: Option<String>,
+    /// curve, key size, pkcs and format the keys come in
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Value>,
+    pub created_at: u64,
+    /// set by the transfers
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+}
+
+/// Where a converted key came from, and every transfer since.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Provenance {
+    /// hex sha256 of the source key, or of its public key
+    pub source_fingerprint: String,
+    /// oldest first
+    pub chain: Vec<Transformation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Transformation {
+    /// the command that applied it
+    pub operation: String,
+    pub from: Value,
+    pub to: Value,
+    pub at: u64,
+}
 
 impl KeyTuple {
     pub fn new(private_key: String, public_key: String) -> Self {
-        KeyTuple(Some(private_key), Some(public_key))
+        KeyTuple {
+            private_key: Some(private_key),
+            public_key: Some(public_key),
+            ..KeyTuple::empty()
+        }
     }
 
     pub fn empty() -> Self {
-        KeyTuple(None, None)
+        KeyTuple {
+            created_at: now(),
+            ..Default::default()
+        }
     }
 
     pub fn private(&mut self, key: Option<String>) -> &mut Self {
-        self.0 = key;
+        self.private_key = key;
         self
     }
 
     pub fn public(&mut self, key: Option<String>) -> &mut Self {
-        self.1 = key;
+        self.public_key = key;
+        self
+    }
+
+    pub fn algorithm(&mut self, algorithm: &str) -> &mut Self {
+        self.algorithm = Some(algorithm.to_string());
+        self
+    }
+
+    pub fn parameter(
+        &mut self,
+        name: &str,
+        value: impl Serialize,
+    ) -> &mut Self {
+        self.parameters.insert(name.to_string(), json!(value));
+        self
+    }
+
+    /// `public_key` as exported, before the text encoding.
+    pub fn fingerprint(&mut self, public_key: &[u8]) -> &mut Self {
+        self.fingerprint = Some(fingerprint(public_key));
+        self
+    }
+
+    /// Extends the chain the key came with, or starts one.
+    pub fn provenance(
+        &mut self,
+        previous: Option<Provenance>,
+        source: &[u8],
+        operation: &str,
+        from: impl Serialize,
+        to: impl Serialize,
+    ) -> &mut Self {
+        let mut provenance = previous.unwrap_or_else(|| Provenance {
+            source_fingerprint: fingerprint(source),
+            chain: Vec::new(),
+        });
+        provenance.chain.push(Transformation {
+            operation: operation.to_string(),
+            from: json!(from),
+            to: json!(to),
+            at: self.created_at,
+        });
+        self.provenance = Some(provenance);
         self
     }
 }
 
+fn fingerprint(key: &[u8]) -> String {
+    let digest = match pem_rfc7468::decode_vec(key) {
+        Ok((_, der)) => digest_inner(Digest::Sha256, &der),
+        Err(_) => digest_inner(Digest::Sha256, key),
+    };
+    base16ct::lower::encode_string(&digest)
+}
+
 #[tauri::command]
 pub fn random_bytes(size: usize) -> Result<Vec<u8>> {
-    Ok(rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(size)
-        .collect())
+    Ok(rng::rng().sample_iter(&Alphanumeric).take(size).collect())
 }
 
 #[tauri::command]
@@ -52,42 +150,163 @@ pub fn random_id() -> Result<String> {
     Ok(base_int.to_str_radix(36))
 }
 
-#[tauri::command]
-pub fn elliptic_curve() -> Vec<EccCurveName> {
-    EccCurveName::iter().collect::<Vec<EccCurveName>>()
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    aes: Vec<AesCapability>,
+    rsa: RsaCapability,
+    elliptic_curves: Vec<EccCurveName>,
+    edwards_curves: Vec<EdwardsCurveName>,
+    ecies: EciesCapability,
+    digests: Vec<Digest>,
+    xofs: Vec<Xof>,
+    kdfs: Vec<KdfCapability>,
+    jwk: Vec<JwkCapability>,
+    jwkey_operations: Vec<JwkeyOperation>,
+    kid_strategies: Vec<KidStrategy>,
 }
 
-#[tauri::command]
-pub fn edwards() -> Vec<EdwardsCurveName> {
-    EdwardsCurveName::iter().collect::<Vec<EdwardsCurveName>>()
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AesCapability {
+    mode: EncryptionMode,
+    key_sizes: Vec<usize>,
+    paddings: Vec<AesEncryptionPadding>,
+    iv_size: Option<usize>,
+    /// the `tagSize` it takes, when it takes one
+    tag_sizes: Vec<usize>,
+    aad: bool,
 }
 
-#[tauri::command]
-pub fn kdfs() -> Vec<Kdf> {
-    Kdf::iter().collect::<Vec<Kdf>>()
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RsaCapability {
+    key_sizes: Vec<RsaKeySize>,
+    pkcs: Vec<Pkcs>,
+    encryption_paddings: Vec<RsaEncryptionPadding>,
+    oaep_digests: Vec<Digest>,
 }
 
-#[tauri::command]
-pub fn digests() -> Vec<Digest> {
-    Digest::iter().collect::<Vec<Digest>>()
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EciesCapability {
+    pkcs: Vec<Pkcs>,
+    encryption_algorithms: Vec<EciesEncryptionAlgorithm>,
 }
 
-#[tauri::command]
-pub fn ecies_enc_alg() -> Vec<EciesEncryptionAlgorithm> {
-    EciesEncryptionAlgorithm::iter().collect::<Vec<EciesEncryptionAlgorithm>>()
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfCapability {
+    kdf: Kdf,
+    salt_required: bool,
+    info_supported: bool,
 }
 
-#[tauri::command]
-pub fn rsa_key_size() -> Vec<RsaKeySize> {
-    RsaKeySize::iter().collect::<Vec<RsaKeySize>>()
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JwkCapability {
+    key_type: JwkeyType,
+    default_algorithm: JwkeyAlgorithm,
+    algorithms: Vec<JwkeyAlgorithm>,
+    usages: Vec<JwkeyUsage>,
 }
 
 #[tauri::command]
-pub fn rsa_encryption_padding() -> Vec<RsaEncryptionPadding> {
-    RsaEncryptionPadding::iter().collect::<Vec<RsaEncryptionPadding>>()
+pub fn capabilities() -> Capabilities {
+    let profile = profile::current();
+    let digests = Digest::iter()
+        .filter(|digest| profile.allows(Algorithm::Digest(*digest)))
+        .collect::<Vec<_>>();
+    Capabilities {
+        aes: EncryptionMode::iter()
+            .filter(|mode| profile.allows(Algorithm::AesMode(*mode)))
+            .map(|mode| AesCapability {
+                mode,
+                key_sizes: match mode {
+                    EncryptionMode::CbcHmac
+                    | EncryptionMode::Siv
+                    | EncryptionMode::Xts => {
+                        vec![256, 512]
+                    }
+                    _ => vec![128, 256],
+                },
+                paddings: match mode {
+                    EncryptionMode::Gcm
+                    | EncryptionMode::Ccm
+                    | EncryptionMode::Siv
+                    | EncryptionMode::Xts => {
+                        vec![AesEncryptionPadding::NoPadding]
+                    }
+                    _ => vec![
+                        AesEncryptionPadding::Pkcs7Padding,
+                        AesEncryptionPadding::NoPadding,
+                    ],
+                },
+                iv_size: match mode {
+                    EncryptionMode::Ecb | EncryptionMode::Siv => None,
+                    EncryptionMode::Cbc
+                    | EncryptionMode::CbcHmac
+                    | EncryptionMode::Xts => Some(16),
+                    EncryptionMode::Gcm | EncryptionMode::Ccm => Some(12),
+                },
+                tag_sizes: match mode {
+                    EncryptionMode::Ccm => (4 ..= 16).step_by(2).collect(),
+                    _ => vec![],
+                },
+                aad: matches!(
+                    mode,
+                    EncryptionMode::CbcHmac
+                        | EncryptionMode::Gcm
+                        | EncryptionMode::Ccm
+                        | EncryptionMode::Siv
+                ),
+            })
+            .collect(),
+        rsa: RsaCapability {
+            key_sizes: RsaKeySize::iter()
+                .filter(|size| {
+                    profile.allows(Algorithm::RsaKeySize(*size as usize))
+                })
+                .collect(),
+            pkcs: vec![Pkcs::Pkcs1, Pkcs::Pkcs8],
+            encryption_paddings: RsaEncryptionPadding::iter()
+                .filter(|padding| {
+                    profile.allows(Algorithm::RsaPadding(*padding))
+                })
+                .collect(),
+            oaep_digests: digests.clone(),
+        },
+        elliptic_curves: EccCurveName::iter()
+            .filter(|curve| profile.allows(Algorithm::EllipticCurve(*curve)))
+            .collect(),
+        edwards_curves: EdwardsCurveName::iter().collect(),
+        ecies: EciesCapability {
+            pkcs: vec![Pkcs::Pkcs8, Pkcs::Sec1],
+            encryption_algorithms: EciesEncryptionAlgorithm::iter().collect(),
+        },
+        digests,
+        xofs: Xof::iter().collect(),
+        kdfs: Kdf::iter()
+            .filter(|kdf| profile.allows(Algorithm::Kdf(*kdf)))
+            .map(|kdf| KdfCapability {
+                kdf,
+                salt_required: matches!(kdf, Kdf::PbKdf2 | Kdf::Scrypt),
+                info_supported: matches!(kdf, Kdf::HKdf | Kdf::Concatenation),
+            })
+            .collect(),
+        jwk: JwkeyType::iter()
+            .map(|key_type| JwkCapability {
+                key_type,
+                default_algorithm: key_type.default_algorithm(),
+                algorithms: jwkey_algorithm(key_type),
+                usages: jwkey_usage(key_type),
+            })
+            .collect(),
+        jwkey_operations: JwkeyOperation::iter().collect(),
+        kid_strategies: KidStrategy::iter().collect(),
+    }
 }
 
-#[tauri::command]
 pub(crate) fn jwkey_algorithm(kty: JwkeyType) -> Vec<JwkeyAlgorithm> {
     match kty {
         JwkeyType::RSA => vec![
@@ -103,6 +322,11 @@ pub(crate) fn jwkey_algorithm(kty: JwkeyType) -> Vec<JwkeyAlgorithm> {
             JwkeyAlgorithm::ES384,
             JwkeyAlgorithm::ES521,
             JwkeyAlgorithm::ES256K,
+            JwkeyAlgorithm::SM2,
+            JwkeyAlgorithm::EcdhEs,
+            JwkeyAlgorithm::EcdhEsA128kw,
+            JwkeyAlgorithm::EcdhEsA192kw,
+            JwkeyAlgorithm::EcdhEsA256kw,
         ],
         JwkeyType::Ed25519 => vec![JwkeyAlgorithm::EdDSA],
         JwkeyType::X25519 => vec![
@@ -128,11 +352,11 @@ pub(crate) fn jwkey_algorithm(kty: JwkeyType) -> Vec<JwkeyAlgorithm> {
             JwkeyAlgorithm::A256GCMKW,
             JwkeyAlgorithm::A256KW,
             JwkeyAlgorithm::A256cbcHs512,
+            JwkeyAlgorithm::Sm4Gcm,
         ],
     }
 }
 
-#[tauri::command]
 pub(crate) fn jwkey_usage(kty: JwkeyType) -> Vec<JwkeyUsage> {
     match kty {
         JwkeyType::RSA => vec![JwkeyUsage::Encryption, JwkeyUsage::Signature],
@@ -144,13 +368,3 @@ pub(crate) fn jwkey_usage(kty: JwkeyType) -> Vec<JwkeyUsage> {
         }
     }
 }
-
-#[tauri::command]
-pub async fn jwkey_type() -> Vec<JwkeyType> {
-    JwkeyType::iter().collect::<Vec<JwkeyType>>()
-}
-
-#[tauri::command]
-pub async fn jwkey_operation() -> Vec<JwkeyOperation> {
-    JwkeyOperation::iter().collect::<Vec<JwkeyOperation>>()
-}
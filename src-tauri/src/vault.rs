@@ -0,0 +1,346 @@
+use std::{
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    audit::{
+        compare,
+        key::{self, AuditedKey},
+    },
+    enums::TextEncoding,
+    errors::{Error, Result},
+    jwt::{jwk::thumbprint, JwkeyUsage},
+    utils::random_bytes,
+};
+
+pub mod backup;
+pub mod notes;
+pub mod session;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEnforcement {
+    #[default]
+    Warn,
+    Refuse,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPolicy {
+    pub usage: Option<JwkeyUsage>,
+    pub algorithms: Option<Vec<String>>,
+    /// unix timestamp in seconds
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub enforcement: PolicyEnforcement,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredKey {
+    pub id: String,
+    pub label: Option<String>,
+    pub key: String,
+    pub key_encoding: TextEncoding,
+    pub fingerprint: String,
+    pub policy: KeyPolicy,
+    pub created_at: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredKeyInfo {
+    pub id: String,
+    pub label: Option<String>,
+    pub fingerprint: String,
+    pub policy: KeyPolicy,
+    pub created_at: u64,
+}
+
+impl From<&StoredKey> for StoredKeyInfo {
+    fn from(value: &StoredKey) -> Self {
+        StoredKeyInfo {
+            id: value.id.clone(),
+            label: value.label.clone(),
+            fingerprint: value.fingerprint.clone(),
+            policy: value.policy.clone(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreKeyDto {
+    pub label: Option<String>,
+    pub key: String,
+    pub key_encoding: TextEncoding,
+    pub policy: Option<KeyPolicy>,
+}
+
+pub(crate) fn vault() -> &'static RwLock<BTreeMap<String, StoredKey>> {
+    static VAULT: OnceLock<RwLock<BTreeMap<String, StoredKey>>> =
+        OnceLock::new();
+    VAULT.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+/// What `enforce_policy` checks while the session has the vault sealed.
+pub(crate) fn locked_policies() -> &'static RwLock<Vec<StoredKeyInfo>> {
+    static LOCKED: OnceLock<RwLock<Vec<StoredKeyInfo>>> = OnceLock::new();
+    LOCKED.get_or_init(|| RwLock::new(vec![]))
+}
+
+/// Held by the tests that lock the session, which empties the vault and the
+/// pipeline values.
+#[cfg(test)]
+pub(crate) static TEST_VAULT: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// RFC 7638 thumbprint, of the public half for key pairs, so pem, der and
+/// jwk forms of a key agree. What does not read as a key counts as oct.
+pub(crate) fn fingerprint(key: &[u8]) -> Result<String> {
+    let oct = |secret: &[u8]| {
+        let k = Base64UrlUnpadded::encode_string(secret);
+        json!({ "kty": "oct", "k": k })
+    };
+    let read = std::str::from_utf8(key)
+        .ok()
+        .and_then(|text| compare::read(text, TextEncoding::Utf8).ok())
+        .map(|(_, read)| read)
+        .unwrap_or_else(|| key::parse_der(key.to_vec()));
+    let jwk = match read {
+        AuditedKey::Symmetric(secret) => oct(&secret),
+        read => compare::public_jwk(read)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| oct(key)),
+    };
+    thumbprint(&jwk)
+}
+
+#[tauri::command]
+pub fn store_key(data: StoreKeyDto) -> Result<StoredKeyInfo> {
+    let key = data.key_encoding.decode(&data.key)?;
+    let stored = StoredKey {
+        id: TextEncoding::Hex.encode(&random_bytes(8)?)?,
+        label: data.label,
+        key: data.key,
+        key_encoding: data.key_encoding,
+        fingerprint: fingerprint(&key)?,
+        policy: data.policy.unwrap_or_default(),
+        created_at: now(),
+    };
+    info!("store key: {}, policy: {:?}", stored.id, stored.policy);
+    let stored_info = StoredKeyInfo::from(&stored);
+    vault()
+        .write()
+        .map_err(|_| anyhow!("vault lock poisoned"))?
+        .insert(stored.id.clone(), stored);
+    Ok(stored_info)
+}
+
+#[tauri::command]
+pub fn list_stored_keys() -> Result<Vec<StoredKeyInfo>> {
+    Ok(vault()
+        .read()
+        .map_err(|_| anyhow!("vault lock poisoned"))?
+        .values()
+        .map(StoredKeyInfo::from)
+        .collect())
+}
+
+#[tauri::command]
+pub fn load_stored_key(id: String) -> Result<StoredKey> {
+    vault()
+        .read()
+        .map_err(|_| anyhow!("vault lock poisoned"))?
+        .get(&id)
+        .cloned()
+        .ok_or(Error::Unsupported(format!("stored key {}", id)))
+}
+
+#[tauri::command]
+pub fn remove_stored_key(id: String) -> Result<()> {
+    vault()
+        .write()
+        .map_err(|_| anyhow!("vault lock poisoned"))?
+        .remove(&id)
+        .map(|_| ())
+        .ok_or(Error::Unsupported(format!("stored key {}", id)))
+}
+
+#[tauri::command]
+pub fn set_key_policy(id: String, policy: KeyPolicy) -> Result<StoredKeyInfo> {
+    let mut vault = vault()
+        .write()
+        .map_err(|_| anyhow!("vault lock poisoned"))?;
+    let stored = vault
+        .get_mut(&id)
+        .ok_or(Error::Unsupported(format!("stored key {}", id)))?;
+    stored.policy = policy;
+    Ok(StoredKeyInfo::from(&*stored))
+}
+
+/// Checks every vault entry holding the same key material.
+pub(crate) fn enforce_policy(
+    key: &[u8],
+    usage: JwkeyUsage,
+    algorithm: &str,
+) -> Result<()> {
+    let fingerprint = fingerprint(key)?;
+    let vault = vault().read().map_err(|_| anyhow!("vault lock poisoned"))?;
+    let locked = locked_policies()
+        .read()
+        .map_err(|_| anyhow!("vault lock poisoned"))?;
+    let policies = vault
+        .values()
+        .filter(|s| s.fingerprint == fingerprint)
+        .map(|s| (&s.id, &s.policy))
+        .chain(
+            locked
+                .iter()
+                .filter(|s| s.fingerprint == fingerprint)
+                .map(|s| (&s.id, &s.policy)),
+        );
+    for (id, policy) in policies {
+        let mut violations = vec![];
+        if let Some(expected) = policy.usage
+            && expected != usage
+        {
+            violations.push(format!("intended for {} only", expected));
+        }
+        if let Some(algorithms) = &policy.algorithms
+            && !algorithms
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(algorithm))
+        {
+            violations
+                .push(format!("{} is not an allowed algorithm", algorithm));
+        }
+        if let Some(expires_at) = policy.expires_at
+            && expires_at <= now()
+        {
+            violations.push(format!("expired at {}", expires_at));
+        }
+        if violations.is_empty() {
+            continue;
+        }
+        let message = format!(
+            "key {} used against its policy: {}",
+            id,
+            violations.join(", ")
+        );
+        match policy.enforcement {
+            PolicyEnforcement::Warn => warn!("{}", message),
+            PolicyEnforcement::Refuse => {
+                return Err(Error::Unsupported(message));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::PoisonError;
+
+    use p256::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+    use serde_json::Value;
+
+    use super::{
+        enforce_policy, fingerprint, store_key, KeyPolicy, PolicyEnforcement,
+        StoreKeyDto, TEST_VAULT,
+    };
+    use crate::{enums::TextEncoding, jwt::JwkeyUsage};
+
+    #[test]
+    fn test_enforce_policy() {
+        let _guard = TEST_VAULT.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = "policy test key 0123456789abcdef";
+        store_key(StoreKeyDto {
+            label: None,
+            key: key.to_string(),
+            key_encoding: TextEncoding::Utf8,
+            policy: Some(KeyPolicy {
+                usage: Some(JwkeyUsage::Encryption),
+                algorithms: Some(vec!["AES-GCM".to_string()]),
+                expires_at: None,
+                enforcement: PolicyEnforcement::Refuse,
+            }),
+        })
+        .unwrap();
+        assert!(enforce_policy(
+            key.as_bytes(),
+            JwkeyUsage::Encryption,
+            "aes-gcm"
+        )
+        .is_ok());
+        assert!(enforce_policy(
+            key.as_bytes(),
+            JwkeyUsage::Encryption,
+            "AES-CBC"
+        )
+        .is_err());
+        assert!(enforce_policy(
+            key.as_bytes(),
+            JwkeyUsage::Signature,
+            "AES-GCM"
+        )
+        .is_err());
+        assert!(
+            enforce_policy(b"unknown", JwkeyUsage::Signature, "HS256").is_ok()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let _guard = TEST_VAULT.lock().unwrap_or_else(PoisonError::into_inner);
+        let secret = p256::SecretKey::from_slice(&[7; 32]).unwrap();
+        let pem = secret.to_pkcs8_pem(LineEnding::LF).unwrap();
+        let spki = secret.public_key().to_public_key_der().unwrap();
+        let jwk: Value = serde_json::from_str(&secret.to_jwk_string()).unwrap();
+        let jwk = serde_json::to_string_pretty(&jwk).unwrap();
+        let expected = fingerprint(pem.as_bytes()).unwrap();
+        assert_eq!(fingerprint(spki.as_bytes()).unwrap(), expected);
+        assert_eq!(fingerprint(jwk.as_bytes()).unwrap(), expected);
+        assert_eq!(
+            fingerprint(br#"{"kty":"oct","k":"c2VjcmV0"}"#).unwrap(),
+            fingerprint(b"secret").unwrap()
+        );
+
+        store_key(StoreKeyDto {
+            label: None,
+            key: pem.to_string(),
+            key_encoding: TextEncoding::Utf8,
+            policy: Some(KeyPolicy {
+                usage: Some(JwkeyUsage::Encryption),
+                algorithms: None,
+                expires_at: None,
+                enforcement: PolicyEnforcement::Refuse,
+            }),
+        })
+        .unwrap();
+        // the jwk of the same key, laid out anew, is refused too
+        assert!(
+            enforce_policy(jwk.as_bytes(), JwkeyUsage::Signature, "ES256")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::{anyhow, Context};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    KeyInit, XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::info;
+use zeroize::Zeroizing;
+
+use super::{fingerprint, vault, StoredKey, StoredKeyInfo};
+use crate::{
+    crypto::siv,
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng,
+};
+
+/// Followed by the version of the archive.
+pub(crate) const MAGIC: &[u8; 7] = b"KITSVLT";
+/// XChaCha20-Poly1305 under the argon2id key itself, read but not written
+const V1: u8 = b'1';
+/// AES-SIV under HKDF sub-keys of the argon2id key
+const V2: u8 = b'2';
+const ARGON2ID: u8 = 1;
+const SALT_SIZE: usize = 16;
+const V1_NONCE_SIZE: usize = 24;
+const V1_HEADER_SIZE: usize = MAGIC.len() + 1 + 12 + SALT_SIZE + V1_NONCE_SIZE;
+const NONCE_SIZE: usize = 16;
+/// argon2 costs read from an archive, KiB and passes
+const MAX_ARGON2: (u32, u32) = (1024 * 1024, 10);
+const MAC_INFO: &[u8] = b"kits vault v2 mac";
+const ENC_INFO: &[u8] = b"kits vault v2 enc";
+
+#[derive(Serialize, Deserialize, Default)]
+struct VaultArchive {
+    keys: Vec<StoredKey>,
+}
+
+/// Archive layout: magic || version || kdf || argon2 version || m_cost ||
+/// t_cost || p_cost (u32 be) || salt size || salt || nonce || AES-SIV
+/// ciphertext, with the header as associated data. The header holds every
+/// key derivation parameter, so archives stay readable when the defaults
+/// change.
+///
+/// Only the stored keys are archived: there are no presets or history to
+/// carry, and the notes stay sealed under their own master passphrase.
+#[tauri::command]
+pub fn export_vault(
+    passphrase: String,
+    encoding: TextEncoding,
+) -> Result<String> {
+    let archive = VaultArchive {
+        keys: vault()
+            .read()
+            .map_err(|_| anyhow!("vault lock poisoned"))?
+            .values()
+            .cloned()
+            .collect(),
+    };
+    info!("export vault, {} keys", archive.keys.len());
+    let plaintext = Zeroizing::new(
+        serde_json::to_vec(&archive).context("serialize vault failed")?,
+    );
+    encoding.encode(&seal_v2(&plaintext, &passphrase, Params::default())?)
+}
+
+/// Entries with the same id are overwritten, the others kept unless
+/// `replace` is set.
+#[tauri::command]
+pub fn import_vault(
+    archive: String,
+    encoding: TextEncoding,
+    passphrase: String,
+    replace: Option<bool>,
+) -> Result<Vec<StoredKeyInfo>> {
+    let archive = encoding.decode(&archive)?;
+    let plaintext = match archive.get(MAGIC.len()) {
+        Some(&V1) if archive.starts_with(MAGIC) => {
+            open_v1(&archive, &passphrase)?
+        }
+        Some(&V2) if archive.starts_with(MAGIC) => {
+            open_v2(&archive, &passphrase)?
+        }
+        _ => {
+            return Err(Error::Unsupported("vault archive format".to_string()))
+        }
+    };
+    let mut restored: VaultArchive =
+        serde_json::from_slice(&plaintext).context("invalid vault archive")?;
+    info!("import vault, {} keys", restored.keys.len());
+    // older archives carry the digest of the key bytes as written
+    for key in &mut restored.keys {
+        key.fingerprint = fingerprint(&key.key_encoding.decode(&key.key)?)?;
+    }
+    let mut vault =
+        vault().write().map_err(|_| anyhow!("vault lock poisoned"))?;
+    if replace.unwrap_or(false) {
+        vault.clear();
+    }
+    let imported = restored.keys.iter().map(StoredKeyInfo::from).collect();
+    vault.extend(restored.keys.into_iter().map(|key| (key.id.clone(), key)));
+    Ok(imported)
+}
+
+fn seal_v2(
+    plaintext: &[u8],
+    passphrase: &str,
+    params: Params,
+) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_SIZE];
+    let mut nonce = [0u8; NONCE_SIZE];
+    let mut rng = rng::rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+    let mut header = MAGIC.to_vec();
+    header.extend([V2, ARGON2ID]);
+    header.extend(u32::from(Version::V0x13).to_be_bytes());
+    header.extend(params.m_cost().to_be_bytes());
+    header.extend(params.t_cost().to_be_bytes());
+    header.extend(params.p_cost().to_be_bytes());
+    header.push(SALT_SIZE as u8);
+    header.extend(&salt);
+    // siv is deterministic, the random nonce in its associated data keeps
+    // equal vaults from sealing alike
+    header.extend(nonce);
+    let key = archive_keys(passphrase, Version::V0x13, params, &salt)?;
+    let ciphertext = siv::siv(&key[..], &[header.clone()], plaintext, true)?;
+    header.extend(ciphertext);
+    Ok(header)
+}
+
+fn open_v2(archive: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let offset = MAGIC.len() + 1;
+    // kdf, argon2 version, costs and salt size
+    let fixed = offset + 1 + 16 + 1;
+    if archive.len() < fixed {
+        return Err(Error::Unsupported("vault archive format".to_string()));
+    }
+    if archive[offset] != ARGON2ID {
+        return Err(Error::Unsupported(format!(
+            "vault archive kdf {}",
+            archive[offset]
+        )));
+    }
+    let version =
+        Version::try_from(u32_at(archive, offset + 1)).map_err(|_| {
+            Error::Unsupported("vault archive argon2 version".to_string())
+        })?;
+    let params = argon2_params(archive, offset + 5)?;
+    let header_size = fixed + archive[fixed - 1] as usize + NONCE_SIZE;
+    if archive.len() < header_size {
+        return Err(Error::Unsupported("vault archive format".to_string()));
+    }
+    let (header, ciphertext) = archive.split_at(header_size);
+    let salt = &header[fixed .. header_size - NONCE_SIZE];
+    let key = archive_keys(passphrase, version, params, salt)?;
+    Ok(Zeroizing::new(
+        siv::siv(&key[..], &[header.to_vec()], ciphertext, false).map_err(
+            |_| anyhow!("wrong passphrase or corrupted vault archive"),
+        )?,
+    ))
+}
+
+/// Each half of the AES-256-SIV key is its own HKDF sub-key.
+fn archive_keys(
+    passphrase: &str,
+    version: Version,
+    params: Params,
+    salt: &[u8],
+) -> Result<Zeroizing<[u8; 64]>> {
+    let mut root = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, version, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut root[..])
+        .map_err(|err| anyhow!("derive vault key failed: {}", err))?;
+    let hkdf = hkdf::Hkdf::<Sha256>::new(None, &root[..]);
+    let mut keys = Zeroizing::new([0u8; 64]);
+    let (mac, enc) = keys.split_at_mut(32);
+    hkdf.expand(MAC_INFO, mac)
+        .and_then(|_| hkdf.expand(ENC_INFO, enc))
+        .map_err(|_| anyhow!("derive vault key failed"))?;
+    Ok(keys)
+}
+
+fn open_v1(archive: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    if archive.len() < V1_HEADER_SIZE {
+        return Err(Error::Unsupported("vault archive format".to_string()));
+    }
+    let (header, ciphertext) = archive.split_at(V1_HEADER_SIZE);
+    let offset = MAGIC.len() + 1;
+    let params = argon2_params(header, offset)?;
+    let salt = &header[offset + 12 .. offset + 12 + SALT_SIZE];
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key[..])
+        .map_err(|err| anyhow!("derive vault key failed: {}", err))?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key[..])
+        .map_err(|_| anyhow!("invalid vault key"))?;
+    Ok(Zeroizing::new(
+        cipher
+            .decrypt(
+                XNonce::from_slice(&header[V1_HEADER_SIZE - V1_NONCE_SIZE ..]),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| {
+                anyhow!("wrong passphrase or corrupted vault archive")
+            })?,
+    ))
+}
+
+/// Bounded so an archive cannot ask for any memory it likes.
+fn argon2_params(header: &[u8], offset: usize) -> Result<Params> {
+    let params = Params::new(
+        u32_at(header, offset),
+        u32_at(header, offset + 4),
+        u32_at(header, offset + 8),
+        Some(32),
+    )
+    .map_err(|err| anyhow!("invalid argon2 parameters: {}", err))?;
+    let (max_memory, max_time) = MAX_ARGON2;
+    if params.m_cost() > max_memory || params.t_cost() > max_time {
+        return Err(Error::Unsupported(format!(
+            "argon2 costs of {} KiB and {} passes",
+            params.m_cost(),
+            params.t_cost()
+        )));
+    }
+    Ok(params)
+}
+
+fn u32_at(header: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(
+        header[offset .. offset + 4].try_into().unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::PoisonError;
+
+    use argon2::{Algorithm, Argon2, Params, Version};
+    use chacha20poly1305::{
+        aead::{Aead, Payload},
+        KeyInit, XChaCha20Poly1305, XNonce,
+    };
+
+    use super::{
+        export_vault, import_vault, open_v1, open_v2, seal_v2, MAGIC, V1,
+    };
+    use crate::{
+        enums::TextEncoding,
+        vault::{load_stored_key, store_key, StoreKeyDto, TEST_VAULT},
+    };
+
+    #[test]
+    fn test_vault_backup() {
+        let _guard = TEST_VAULT.lock().unwrap_or_else(PoisonError::into_inner);
+        let stored = store_key(StoreKeyDto {
+            label: Some("backup".to_string()),
+            key: "00112233".to_string(),
+            key_encoding: TextEncoding::Hex,
+            policy: None,
+        })
+        .unwrap();
+        let archive =
+            export_vault("passphrase".to_string(), TextEncoding::Base64)
+                .unwrap();
+        assert!(import_vault(
+            archive.clone(),
+            TextEncoding::Base64,
+            "wrong".to_string(),
+            None
+        )
+        .is_err());
+        let imported = import_vault(
+            archive,
+            TextEncoding::Base64,
+            "passphrase".to_string(),
+            None,
+        )
+        .unwrap();
+        assert!(imported.iter().any(|key| key.id == stored.id));
+        assert_eq!(load_stored_key(stored.id).unwrap().key, "00112233");
+    }
+
+    // costs other than the defaults are read back from the header
+    #[test]
+    fn test_vault_backup_params() {
+        let params = Params::new(Params::MIN_M_COST, 1, 1, Some(32)).unwrap();
+        let archive = seal_v2(b"vault", "passphrase", params).unwrap();
+        assert_eq!(&open_v2(&archive, "passphrase").unwrap()[..], b"vault");
+        assert!(open_v2(&archive, "wrong").is_err());
+    }
+
+    // archives of the first version still open
+    #[test]
+    fn test_vault_backup_v1() {
+        let params = Params::new(Params::MIN_M_COST, 1, 1, Some(32)).unwrap();
+        let mut archive = MAGIC.to_vec();
+        archive.push(V1);
+        archive.extend(params.m_cost().to_be_bytes());
+        archive.extend(params.t_cost().to_be_bytes());
+        archive.extend(params.p_cost().to_be_bytes());
+        archive.extend([7; 16]);
+        archive.extend([9; 24]);
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(b"passphrase", &[7; 16], &mut key)
+            .unwrap();
+        let ciphertext = XChaCha20Poly1305::new_from_slice(&key)
+            .unwrap()
+            .encrypt(XNonce::from_slice(&[9; 24]), Payload {
+                msg: b"vault",
+                aad: &archive,
+            })
+            .unwrap();
+        archive.extend(ciphertext);
+        assert_eq!(&open_v1(&archive, "passphrase").unwrap()[..], b"vault");
+        assert!(open_v1(&archive, "wrong").is_err());
+    }
+}
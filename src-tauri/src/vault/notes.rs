@@ -0,0 +1,241 @@
+use std::{
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
+};
+
+use anyhow::anyhow;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    KeyInit, XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::Serialize;
+use tracing::info;
+use zeroize::Zeroizing;
+
+use super::now;
+use crate::{
+    enums::TextEncoding,
+    errors::{Error, Result},
+    rng,
+    utils::random_bytes,
+};
+
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+const MAX_NOTE_SIZE: usize = 64 * 1024;
+/// sealed under the master key, a passphrase that cannot open it is wrong
+const CHECK: &[u8] = b"kits notes master key";
+
+/// The salt and costs are fixed by the first note.
+#[derive(Default)]
+struct Notes {
+    master: Option<Master>,
+    entries: BTreeMap<String, Note>,
+}
+
+struct Master {
+    params: Params,
+    salt: Vec<u8>,
+    /// nonce || ciphertext of `CHECK`
+    check: Vec<u8>,
+}
+
+struct Note {
+    title: String,
+    /// nonce || XChaCha20-Poly1305 ciphertext, the id and title as aad
+    sealed: Vec<u8>,
+    created_at: u64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteInfo {
+    pub id: String,
+    /// kept in the clear to list the notes
+    pub title: String,
+    pub size: usize,
+    pub created_at: u64,
+}
+
+impl NoteInfo {
+    fn new(id: &str, note: &Note) -> Self {
+        NoteInfo {
+            id: id.to_string(),
+            title: note.title.clone(),
+            size: note.sealed.len() - NONCE_SIZE - 16,
+            created_at: note.created_at,
+        }
+    }
+}
+
+fn notes() -> &'static RwLock<Notes> {
+    static NOTES: OnceLock<RwLock<Notes>> = OnceLock::new();
+    NOTES.get_or_init(|| RwLock::new(Notes::default()))
+}
+
+#[tauri::command]
+pub fn add_note(
+    title: String,
+    text: String,
+    passphrase: String,
+) -> Result<NoteInfo> {
+    if text.len() > MAX_NOTE_SIZE {
+        return Err(Error::Unsupported(format!(
+            "{} bytes note, at most {}",
+            text.len(),
+            MAX_NOTE_SIZE
+        )));
+    }
+    let mut notes = notes()
+        .write()
+        .map_err(|_| anyhow!("notes lock poisoned"))?;
+    let cipher = match &notes.master {
+        Some(master) => master.cipher(&passphrase)?,
+        None => {
+            let (master, cipher) = Master::new(&passphrase)?;
+            notes.master = Some(master);
+            cipher
+        }
+    };
+    let id = TextEncoding::Hex.encode(&random_bytes(8)?)?;
+    let note = Note {
+        sealed: seal(&cipher, text.as_bytes(), &aad(&id, &title))?,
+        title,
+        created_at: now(),
+    };
+    info!("add note: {}, size: {}", id, text.len());
+    let note_info = NoteInfo::new(&id, &note);
+    notes.entries.insert(id, note);
+    Ok(note_info)
+}
+
+#[tauri::command]
+pub fn list_notes() -> Result<Vec<NoteInfo>> {
+    Ok(notes()
+        .read()
+        .map_err(|_| anyhow!("notes lock poisoned"))?
+        .entries
+        .iter()
+        .map(|(id, note)| NoteInfo::new(id, note))
+        .collect())
+}
+
+#[tauri::command]
+pub fn decrypt_note(id: String, passphrase: String) -> Result<String> {
+    let notes = notes().read().map_err(|_| anyhow!("notes lock poisoned"))?;
+    let (Some(master), Some(note)) = (&notes.master, notes.entries.get(&id))
+    else {
+        return Err(Error::Unsupported(format!("note {}", id)));
+    };
+    let cipher = master.cipher(&passphrase)?;
+    let text = open(&cipher, &note.sealed, &aad(&id, &note.title))
+        .map_err(|_| anyhow!("corrupted note {}", id))?;
+    info!("decrypt note: {}", id);
+    Ok(String::from_utf8(text.to_vec())
+        .map_err(|_| anyhow!("note {} is not utf8", id))?)
+}
+
+impl Master {
+    fn new(passphrase: &str) -> Result<(Self, XChaCha20Poly1305)> {
+        let params = Params::default();
+        let mut salt = vec![0u8; SALT_SIZE];
+        rng::rng().fill_bytes(&mut salt);
+        let cipher = derive(passphrase, &params, &salt)?;
+        let check = seal(&cipher, CHECK, &[])?;
+        Ok((
+            Master {
+                params,
+                salt,
+                check,
+            },
+            cipher,
+        ))
+    }
+
+    fn cipher(&self, passphrase: &str) -> Result<XChaCha20Poly1305> {
+        let cipher = derive(passphrase, &self.params, &self.salt)?;
+        open(&cipher, &self.check, &[])
+            .map_err(|_| anyhow!("wrong notes passphrase"))?;
+        Ok(cipher)
+    }
+}
+
+fn derive(
+    passphrase: &str,
+    params: &Params,
+    salt: &[u8],
+) -> Result<XChaCha20Poly1305> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key[..])
+        .map_err(|err| anyhow!("derive notes master key failed: {}", err))?;
+    Ok(XChaCha20Poly1305::new_from_slice(&key[..])
+        .map_err(|_| anyhow!("invalid notes master key"))?)
+}
+
+fn aad(id: &str, title: &str) -> Vec<u8> {
+    [id.as_bytes(), &[0], title.as_bytes()].concat()
+}
+
+pub(super) fn seal(
+    cipher: &XChaCha20Poly1305,
+    msg: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let mut sealed = vec![0u8; NONCE_SIZE];
+    rng::rng().fill_bytes(&mut sealed);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&sealed), Payload { msg, aad })
+        .map_err(|_| anyhow!("encrypt failed"))?;
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+pub(super) fn open(
+    cipher: &XChaCha20Poly1305,
+    sealed: &[u8],
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    if sealed.len() < NONCE_SIZE {
+        return Err(Error::Unsupported("truncated ciphertext".to_string()));
+    }
+    let (nonce, msg) = sealed.split_at(NONCE_SIZE);
+    Ok(Zeroizing::new(
+        cipher
+            .decrypt(XNonce::from_slice(nonce), Payload { msg, aad })
+            .map_err(|_| anyhow!("decrypt failed"))?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_note, decrypt_note, list_notes};
+
+    #[test]
+    fn test_notes() {
+        let added = add_note(
+            "api token".to_string(),
+            "s3cr3t".to_string(),
+            "passphrase".to_string(),
+        )
+        .unwrap();
+        assert_eq!(added.size, 6);
+        assert!(list_notes()
+            .unwrap()
+            .iter()
+            .any(|note| note.id == added.id && note.title == "api token"));
+        assert_eq!(
+            decrypt_note(added.id.clone(), "passphrase".to_string()).unwrap(),
+            "s3cr3t"
+        );
+        assert!(decrypt_note(added.id, "wrong".to_string()).is_err());
+        assert!(add_note(
+            "other".to_string(),
+            "text".to_string(),
+            "wrong".to_string()
+        )
+        .is_err());
+    }
+}
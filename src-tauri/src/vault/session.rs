@@ -0,0 +1,314 @@
+//! The master passphrase lifecycle. Locking, by hand or after the idle
+//! timeout, seals the stored keys under the master key and drops it.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305};
+use rand::RngCore;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tracing::{info, warn};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{
+    locked_policies,
+    notes::{open, seal},
+    vault, StoredKey, StoredKeyInfo,
+};
+use crate::{
+    errors::{Error, Result},
+    pipeline, rng,
+};
+
+const SALT_SIZE: usize = 16;
+const CHECK: &[u8] = b"kits session master key";
+const VAULT_AAD: &[u8] = b"kits sealed vault";
+const DEFAULT_IDLE_TIMEOUT: u64 = 15 * 60;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Commands refused while locked, all others never touch the master key.
+const GATED: &[&str] = &[
+    "store_key",
+    "list_stored_keys",
+    "load_stored_key",
+    "remove_stored_key",
+    "set_key_policy",
+    "export_vault",
+    "import_vault",
+    "add_note",
+    "list_notes",
+    "decrypt_note",
+];
+
+pub struct Session(Mutex<SessionState>);
+
+struct SessionState {
+    master: Option<Zeroizing<[u8; 32]>>,
+    /// argon2id salt and `CHECK` sealed under the master key
+    verifier: Option<(Vec<u8>, Vec<u8>)>,
+    /// the stored keys while locked
+    sealed_vault: Option<Vec<u8>>,
+    idle_timeout: Duration,
+    last_active: Instant,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatus {
+    pub locked: bool,
+    pub initialized: bool,
+    /// seconds
+    pub idle_timeout: u64,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session(Mutex::new(SessionState {
+            master: None,
+            verifier: None,
+            sealed_vault: None,
+            idle_timeout: Duration::from_secs(DEFAULT_IDLE_TIMEOUT),
+            last_active: Instant::now(),
+        }))
+    }
+}
+
+impl Session {
+    fn state(&self) -> Result<MutexGuard<'_, SessionState>> {
+        Ok(self
+            .0
+            .lock()
+            .map_err(|_| anyhow!("session lock poisoned"))?)
+    }
+
+    fn unlock(
+        &self,
+        passphrase: &str,
+        idle_timeout: Option<u64>,
+    ) -> Result<()> {
+        let mut state = self.state()?;
+        let (salt, check) = match &state.verifier {
+            Some((salt, check)) => (salt.clone(), Some(check.clone())),
+            None => {
+                let mut salt = vec![0u8; SALT_SIZE];
+                rng::rng().fill_bytes(&mut salt);
+                (salt, None)
+            }
+        };
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key[..])
+            .map_err(|err| anyhow!("derive master key failed: {}", err))?;
+        let cipher = cipher(&key)?;
+        match check {
+            Some(check) => {
+                open(&cipher, &check, &[])
+                    .map_err(|_| anyhow!("wrong master passphrase"))?;
+            }
+            None => state.verifier = Some((salt, seal(&cipher, CHECK, &[])?)),
+        }
+        if let Some(sealed) = state.sealed_vault.take() {
+            let restored = open(&cipher, &sealed, VAULT_AAD)?;
+            let restored: BTreeMap<String, StoredKey> =
+                serde_json::from_slice(&restored)
+                    .context("invalid sealed vault")?;
+            vault()
+                .write()
+                .map_err(|_| anyhow!("vault lock poisoned"))?
+                .extend(restored);
+            locked_policies()
+                .write()
+                .map_err(|_| anyhow!("vault lock poisoned"))?
+                .clear();
+        }
+        if let Some(idle_timeout) = idle_timeout {
+            state.idle_timeout = Duration::from_secs(idle_timeout);
+        }
+        state.master = Some(key);
+        state.last_active = Instant::now();
+        info!("session unlocked, idle timeout: {:?}", state.idle_timeout);
+        Ok(())
+    }
+
+    /// The pipeline values go too, they may hold keys.
+    fn lock(&self) -> Result<()> {
+        pipeline::pipeline_clear(None)?;
+        let mut state = self.state()?;
+        let Some(key) = &state.master else {
+            return Ok(());
+        };
+        let mut vault = vault()
+            .write()
+            .map_err(|_| anyhow!("vault lock poisoned"))?;
+        let mut policies = locked_policies()
+            .write()
+            .map_err(|_| anyhow!("vault lock poisoned"))?;
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&*vault).context("serialize vault failed")?,
+        );
+        let sealed = seal(&cipher(key)?, &plaintext, VAULT_AAD)?;
+        // sealed first, a failure leaves the session unlocked and whole
+        state.sealed_vault = Some(sealed);
+        state.master = None;
+        // enforce_policy is not gated, the policies stay out of the seal
+        *policies = vault.values().map(StoredKeyInfo::from).collect();
+        let mut stored = std::mem::take(&mut *vault);
+        for entry in stored.values_mut() {
+            entry.key.zeroize();
+        }
+        info!("session locked, {} keys sealed", stored.len());
+        Ok(())
+    }
+
+    /// Refuses gated commands while locked and counts them as activity.
+    pub(crate) fn enter(&self, command: &str) -> Result<()> {
+        if !GATED.contains(&command) {
+            return Ok(());
+        }
+        let mut state = self.state()?;
+        if state.master.is_none() {
+            return Err(Error::Unsupported(format!(
+                "{} while locked, unlock first",
+                command
+            )));
+        }
+        state.last_active = Instant::now();
+        Ok(())
+    }
+
+    fn lock_if_idle(&self) -> Result<()> {
+        let idle = {
+            let state = self.state()?;
+            state.master.is_some()
+                && state.last_active.elapsed() >= state.idle_timeout
+        };
+        if idle {
+            info!("session idle, locking");
+            self.lock()?;
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> Result<SessionStatus> {
+        let state = self.state()?;
+        Ok(SessionStatus {
+            locked: state.master.is_none(),
+            initialized: state.verifier.is_some(),
+            idle_timeout: state.idle_timeout.as_secs(),
+        })
+    }
+}
+
+fn cipher(key: &[u8; 32]) -> Result<XChaCha20Poly1305> {
+    Ok(XChaCha20Poly1305::new_from_slice(key)
+        .map_err(|_| anyhow!("invalid master key"))?)
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = app.state::<Session>().lock_if_idle() {
+                warn!("session idle lock failed: {}", e);
+            }
+        }
+    });
+}
+
+/// 15 minutes unless given.
+#[tauri::command]
+pub fn unlock(
+    session: State<'_, Session>,
+    passphrase: String,
+    idle_timeout: Option<u64>,
+) -> Result<SessionStatus> {
+    session.unlock(&passphrase, idle_timeout)?;
+    session.status()
+}
+
+#[tauri::command]
+pub fn lock(session: State<'_, Session>) -> Result<SessionStatus> {
+    session.lock()?;
+    session.status()
+}
+
+#[tauri::command]
+pub fn session_status(session: State<'_, Session>) -> Result<SessionStatus> {
+    session.status()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::PoisonError;
+
+    use super::Session;
+    use crate::{
+        enums::TextEncoding,
+        jwt::JwkeyUsage,
+        pipeline::{pipeline_register, pipeline_tokens},
+        vault::{
+            enforce_policy, list_stored_keys, store_key, KeyPolicy,
+            PolicyEnforcement, StoreKeyDto, TEST_VAULT,
+        },
+    };
+
+    #[test]
+    fn test_session_gate() {
+        let session = Session::default();
+        assert!(session.enter("digest").is_ok());
+        assert!(session.enter("load_stored_key").is_err());
+        assert!(session.status().unwrap().locked);
+
+        session.unlock("passphrase", Some(60)).unwrap();
+        assert!(session.enter("load_stored_key").is_ok());
+        assert!(session.unlock("wrong", None).is_err());
+        let status = session.status().unwrap();
+        assert!(!status.locked && status.initialized);
+        assert_eq!(status.idle_timeout, 60);
+    }
+
+    #[test]
+    fn test_locked_policy() {
+        let _guard = TEST_VAULT.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = "locked policy test key";
+        store_key(StoreKeyDto {
+            label: None,
+            key: key.to_string(),
+            key_encoding: TextEncoding::Utf8,
+            policy: Some(KeyPolicy {
+                usage: Some(JwkeyUsage::Signature),
+                algorithms: None,
+                expires_at: None,
+                enforcement: PolicyEnforcement::Refuse,
+            }),
+        })
+        .unwrap();
+        let session = Session::default();
+        session.unlock("passphrase", None).unwrap();
+        session.lock().unwrap();
+        assert!(list_stored_keys().unwrap().is_empty());
+        let enforce = || {
+            enforce_policy(key.as_bytes(), JwkeyUsage::Encryption, "AES-GCM")
+        };
+        assert!(enforce().is_err());
+        session.unlock("passphrase", None).unwrap();
+        assert!(enforce().is_err());
+    }
+
+    #[test]
+    fn test_lock_clears_pipeline() {
+        let _guard = TEST_VAULT.lock().unwrap_or_else(PoisonError::into_inner);
+        let token = pipeline_register("key".to_string(), None).unwrap();
+        let session = Session::default();
+        session.unlock("passphrase", None).unwrap();
+        session.lock().unwrap();
+        assert!(!pipeline_tokens().unwrap().contains(&token));
+    }
+}
@@ -0,0 +1,421 @@
+//! Certificates, jwks urls and tokens checked hourly in the background, with
+//! a desktop notification on expiry, rotation or failure.
+
+use std::{
+    collections::BTreeMap,
+    sync::{OnceLock, RwLock},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use base64ct::{Base64, Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{api::notification::Notification, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    enums::{KeyFormat, TextEncoding},
+    errors::{Error, Result},
+    fetch,
+    utils::random_bytes,
+    vault,
+    x509::{certificate_to_der, subject_common_name, validity},
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+const WARN_BEFORE: u64 = 7 * 24 * 3600;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchKind {
+    Certificate,
+    Jwks,
+    Token,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchStatus {
+    #[default]
+    Pending,
+    Valid,
+    Expiring,
+    Expired,
+    Rotated,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchDto {
+    pub label: Option<String>,
+    pub kind: WatchKind,
+    /// a pem certificate, a jwks url or a compact jwt
+    pub target: String,
+    /// seconds before the expiry the watch turns to expiring
+    pub warn_before: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Watch {
+    pub id: String,
+    pub label: String,
+    pub kind: WatchKind,
+    #[serde(skip)]
+    pub target: String,
+    pub warn_before: u64,
+    pub status: WatchStatus,
+    pub message: Option<String>,
+    /// unix timestamp in seconds, for jwks the earliest x5c expiry
+    pub expires_at: Option<u64>,
+    /// kids, or thumbprints of keys without one, of the last jwks seen
+    pub keys: Vec<String>,
+    pub checked_at: Option<u64>,
+}
+
+struct Observation {
+    expires_at: Option<u64>,
+    keys: Vec<String>,
+}
+
+fn watches() -> &'static RwLock<BTreeMap<String, Watch>> {
+    static WATCHES: OnceLock<RwLock<BTreeMap<String, Watch>>> = OnceLock::new();
+    WATCHES.get_or_init(|| RwLock::new(BTreeMap::new()))
+}
+
+/// Spawns the periodic check, called once from the tauri setup hook.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match check(vault::now()).await {
+                Ok(alerts) => notify(&app, &alerts),
+                Err(e) => warn!("watch check failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Targets that do not parse are refused, an unreachable jwks url is kept
+/// as failed.
+#[tauri::command]
+pub async fn add_watch(data: WatchDto) -> Result<Watch> {
+    info!(
+        "add watch: {:?}, label: {:?}, warn before: {:?}",
+        data.kind, data.label, data.warn_before
+    );
+    let label = match data.label.filter(|label| !label.is_empty()) {
+        Some(label) => label,
+        None => default_label(data.kind, &data.target)?,
+    };
+    let mut watch = Watch {
+        id: TextEncoding::Hex.encode(&random_bytes(8)?)?,
+        label,
+        kind: data.kind,
+        target: data.target,
+        warn_before: data.warn_before.unwrap_or(WARN_BEFORE),
+        status: WatchStatus::Pending,
+        message: None,
+        expires_at: None,
+        keys: vec![],
+        checked_at: None,
+    };
+    let observation = observe(watch.kind, &watch.target).await;
+    update(&mut watch, observation, vault::now());
+    watches()
+        .write()
+        .map_err(|_| anyhow!("watches lock poisoned"))?
+        .insert(watch.id.clone(), watch.clone());
+    Ok(watch)
+}
+
+#[tauri::command]
+pub fn list_watches() -> Result<Vec<Watch>> {
+    Ok(watches()
+        .read()
+        .map_err(|_| anyhow!("watches lock poisoned"))?
+        .values()
+        .cloned()
+        .collect())
+}
+
+#[tauri::command]
+pub fn remove_watch(id: String) -> Result<()> {
+    watches()
+        .write()
+        .map_err(|_| anyhow!("watches lock poisoned"))?
+        .remove(&id)
+        .map(|_| ())
+        .ok_or(Error::Unsupported(format!("watch {}", id)))
+}
+
+#[tauri::command]
+pub async fn check_watches(app: AppHandle) -> Result<Vec<Watch>> {
+    let alerts = check(vault::now()).await?;
+    notify(&app, &alerts);
+    list_watches()
+}
+
+/// The lock is not held while fetching.
+async fn check(now: u64) -> Result<Vec<Watch>> {
+    let mut checked = list_watches()?;
+    let mut alerts = vec![];
+    for watch in &mut checked {
+        let observation = observe(watch.kind, &watch.target).await;
+        if update(watch, observation, now) {
+            alerts.push(watch.clone());
+        }
+    }
+    let mut watches = watches()
+        .write()
+        .map_err(|_| anyhow!("watches lock poisoned"))?;
+    for watch in checked {
+        if let Some(current) = watches.get_mut(&watch.id) {
+            *current = watch;
+        }
+    }
+    Ok(alerts)
+}
+
+fn notify(app: &AppHandle, alerts: &[Watch]) {
+    let identifier = &app.config().tauri.bundle.identifier;
+    for watch in alerts {
+        info!("watch {} turned {:?}", watch.id, watch.status);
+        if let Err(e) = Notification::new(identifier)
+            .title(&watch.label)
+            .body(watch.message.as_deref().unwrap_or_default())
+            .show()
+        {
+            warn!("notify watch {} failed: {}", watch.id, e);
+        }
+    }
+}
+
+/// Tells whether the user should hear about it.
+fn update(
+    watch: &mut Watch,
+    observation: Result<Observation>,
+    now: u64,
+) -> bool {
+    let previous = watch.status;
+    watch.checked_at = Some(now);
+    let observation = match observation {
+        Ok(observation) => observation,
+        Err(e) => {
+            watch.status = WatchStatus::Failed;
+            watch.message = Some(format!("check failed: {}", e));
+            return previous != WatchStatus::Failed;
+        }
+    };
+    let rotated = !watch.keys.is_empty() && observation.keys != watch.keys;
+    let (status, message) = match observation.expires_at {
+        Some(expires_at) if expires_at <= now => (
+            WatchStatus::Expired,
+            format!("expired {} days ago", (now - expires_at) / 86400),
+        ),
+        Some(expires_at) if expires_at <= now + watch.warn_before => (
+            WatchStatus::Expiring,
+            format!("expires in {} days", (expires_at - now) / 86400),
+        ),
+        _ if rotated => (
+            WatchStatus::Rotated,
+            format!(
+                "keys rotated from [{}] to [{}]",
+                watch.keys.join(", "),
+                observation.keys.join(", ")
+            ),
+        ),
+        Some(expires_at) => (
+            WatchStatus::Valid,
+            format!("expires in {} days", (expires_at - now) / 86400),
+        ),
+        None => (WatchStatus::Valid, "does not expire".to_string()),
+    };
+    watch.status = status;
+    watch.message = Some(message);
+    watch.expires_at = observation.expires_at;
+    watch.keys = observation.keys;
+    status == WatchStatus::Rotated
+        || (status != previous
+            && matches!(status, WatchStatus::Expiring | WatchStatus::Expired))
+}
+
+async fn observe(kind: WatchKind, target: &str) -> Result<Observation> {
+    match kind {
+        WatchKind::Certificate => Ok(Observation {
+            expires_at: Some(validity(&certificate(target)?)?.1),
+            keys: vec![],
+        }),
+        WatchKind::Token => Ok(Observation {
+            expires_at: claims(target)?["exp"].as_u64(),
+            keys: vec![],
+        }),
+        WatchKind::Jwks => {
+            let jwks: Value =
+                serde_json::from_slice(&fetch::get(target, true).await?)
+                    .context("invalid jwks")?;
+            let keys = jwks["keys"].as_array().ok_or_else(|| {
+                Error::Unsupported("jwks without keys".to_string())
+            })?;
+            let mut expires_at = None;
+            let mut kids = vec![];
+            for key in keys {
+                kids.push(match key["kid"].as_str() {
+                    Some(kid) => kid.to_string(),
+                    None => vault::fingerprint(key.to_string().as_bytes())?,
+                });
+                let Some(leaf) = key["x5c"][0].as_str() else {
+                    continue;
+                };
+                let der = Base64::decode_vec(leaf).map_err(|_| {
+                    Error::Unsupported("invalid x5c".to_string())
+                })?;
+                let not_after = validity(&der)?.1;
+                expires_at = Some(
+                    expires_at.map_or(not_after, |e: u64| e.min(not_after)),
+                );
+            }
+            kids.sort();
+            Ok(Observation {
+                expires_at,
+                keys: kids,
+            })
+        }
+    }
+}
+
+fn default_label(kind: WatchKind, target: &str) -> Result<String> {
+    Ok(match kind {
+        WatchKind::Certificate => subject_common_name(&certificate(target)?)?
+            .unwrap_or_else(|| "certificate".to_string()),
+        WatchKind::Jwks => target.to_string(),
+        WatchKind::Token => {
+            let claims = claims(target)?;
+            match (claims["iss"].as_str(), claims["sub"].as_str()) {
+                (Some(iss), Some(sub)) => format!("{} {}", iss, sub),
+                (Some(claim), None) | (None, Some(claim)) => claim.to_string(),
+                (None, None) => "token".to_string(),
+            }
+        }
+    })
+}
+
+fn certificate(target: &str) -> Result<Vec<u8>> {
+    certificate_to_der(target.trim(), KeyFormat::Pem, TextEncoding::Base64)
+}
+
+/// The payload of a compact jws, unverified.
+fn claims(token: &str) -> Result<Value> {
+    let segments = token.trim().split('.').collect::<Vec<_>>();
+    let [_, payload, _] = segments[..] else {
+        return Err(Error::Unsupported(format!(
+            "token with {} segments",
+            segments.len()
+        )));
+    };
+    let payload = Base64UrlUnpadded::decode_vec(payload)
+        .map_err(|_| Error::Unsupported("non base64url payload".to_string()))?;
+    Ok(serde_json::from_slice(&payload).context("payload is not json")?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        add_watch, remove_watch, update, Observation, WatchDto, WatchKind,
+        WatchStatus,
+    };
+    use crate::errors::Error;
+
+    /// Valid from 2024-01-01 to 2034-01-01.
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBOTCB4aADAgECAgEBMAoGCCqGSM49BAMCMCcxDTALBgNVBAoMBGtpdHMxFjAU
+BgNVBAMMDWtpdHMga2V5c3RvcmUwHhcNMjQwMTAxMDAwMDAwWhcNMzQwMTAxMDAw
+MDAwWjAnMQ0wCwYDVQQKDARraXRzMRYwFAYDVQQDDA1raXRzIGtleXN0b3JlMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEdKNacPoikYZIxJ50fPqyWN7sZPSNUN9x
+OyGlyjGSkJQx0K4Qf6fJHoDOX2DFqx2Ar/DApUOvq1R6Lvj8Xx8cDzAKBggqhkjO
+PQQDAgNHADBEAiBklfyIG4rqLmMoU6MepK8GzB95MvXildpMt5TRerZqXQIgKhol
+jPAKEmPiBraV4/6vtHAXu8xFTvumGzrOn7WaTNs=
+-----END CERTIFICATE-----
+";
+    const NOT_AFTER: u64 = 2019686400;
+
+    #[tokio::test]
+    async fn test_watch() {
+        let mut watch = add_watch(WatchDto {
+            label: None,
+            kind: WatchKind::Certificate,
+            target: CERTIFICATE.to_string(),
+            warn_before: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(watch.label, "kits keystore");
+        assert_eq!(watch.expires_at, Some(NOT_AFTER));
+        assert_eq!(watch.status, WatchStatus::Valid);
+
+        let observe = || {
+            Ok(Observation {
+                expires_at: Some(NOT_AFTER),
+                keys: vec![],
+            })
+        };
+        assert!(update(&mut watch, observe(), NOT_AFTER - 86400));
+        assert_eq!(watch.status, WatchStatus::Expiring);
+        assert_eq!(watch.message.as_deref(), Some("expires in 1 days"));
+        assert!(!update(&mut watch, observe(), NOT_AFTER - 3600));
+        assert!(update(&mut watch, observe(), NOT_AFTER));
+        assert_eq!(watch.status, WatchStatus::Expired);
+        remove_watch(watch.id).unwrap();
+
+        // eyJhbGciOiJub25lIn0 is {"alg":"none"}, the payload {"iss":"kits",
+        // "exp":1700000000}
+        let watch = add_watch(WatchDto {
+            label: None,
+            kind: WatchKind::Token,
+            target: "eyJhbGciOiJub25lIn0.\
+                     eyJpc3MiOiJraXRzIiwiZXhwIjoxNzAwMDAwMDAwfQ."
+                .to_string(),
+            warn_before: None,
+        })
+        .await
+        .unwrap();
+        assert_eq!(watch.label, "kits");
+        assert_eq!(watch.status, WatchStatus::Expired);
+    }
+
+    #[test]
+    fn test_watch_rotation() {
+        let mut watch = super::Watch {
+            id: "jwks".to_string(),
+            label: "jwks".to_string(),
+            kind: WatchKind::Jwks,
+            target: "https://example.com/jwks".to_string(),
+            warn_before: 0,
+            status: WatchStatus::Pending,
+            message: None,
+            expires_at: None,
+            keys: vec![],
+            checked_at: None,
+        };
+        let observe = |kids: &[&str]| {
+            Ok(Observation {
+                expires_at: None,
+                keys: kids.iter().map(|kid| kid.to_string()).collect(),
+            })
+        };
+        assert!(!update(&mut watch, observe(&["a"]), 0));
+        assert_eq!(watch.status, WatchStatus::Valid);
+        assert!(update(&mut watch, observe(&["a", "b"]), 1));
+        assert_eq!(watch.status, WatchStatus::Rotated);
+        assert!(!update(&mut watch, observe(&["a", "b"]), 2));
+        assert_eq!(watch.status, WatchStatus::Valid);
+        let down = Err(Error::Unsupported("down".to_string()));
+        assert!(update(&mut watch, down, 3));
+        assert_eq!(watch.status, WatchStatus::Failed);
+    }
+}
@@ -0,0 +1,315 @@
+//! WireGuard keys as `wg genkey`, `wg pubkey` and `wg genpsk` print them,
+//! and the key fields of wg-quick configurations.
+
+use std::collections::BTreeMap;
+
+use base64ct::{Base64, Encoding};
+use rand_core::RngCore;
+use serde::Serialize;
+use tracing::info;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{
+    audit::{sorted, Finding, Severity},
+    errors::{Error, Result},
+    rng,
+};
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WireguardKeys {
+    pub private_key: String,
+    pub public_key: String,
+    pub preshared_key: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WireguardSection {
+    pub line: usize,
+    /// `Interface` or `Peer`
+    pub kind: String,
+    /// the public key of the interface private key, or of the peer
+    pub public_key: Option<String>,
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WireguardConfigAnalysis {
+    pub sections: Vec<WireguardSection>,
+    pub errors: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn generate_wireguard_keys(preshared: bool) -> Result<WireguardKeys> {
+    info!("generate wireguard keys, preshared: {}", preshared);
+    let mut rng = rng::rng();
+    let private_key = StaticSecret::random_from_rng(&mut rng);
+    let preshared_key = preshared.then(|| {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        Base64::encode_string(&key)
+    });
+    Ok(WireguardKeys {
+        private_key: Base64::encode_string(private_key.as_bytes()),
+        public_key: Base64::encode_string(
+            PublicKey::from(&private_key).as_bytes(),
+        ),
+        preshared_key,
+    })
+}
+
+#[tauri::command]
+pub fn derive_wireguard_public(private_key: String) -> Result<String> {
+    info!("derive wireguard public key");
+    let private_key = StaticSecret::from(key(&private_key)?);
+    Ok(Base64::encode_string(
+        PublicKey::from(&private_key).as_bytes(),
+    ))
+}
+
+#[tauri::command]
+pub fn validate_wireguard_config(
+    config: String,
+) -> Result<WireguardConfigAnalysis> {
+    info!("validate wireguard config, size: {}", config.len());
+    // each section with its fields
+    let mut sections: Vec<(WireguardSection, BTreeMap<String, String>)> =
+        vec![];
+    let mut errors = vec![];
+    for (index, line) in config.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(kind) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            match kind.trim() {
+                kind @ ("Interface" | "Peer") => sections.push((
+                    WireguardSection {
+                        line: index + 1,
+                        kind: kind.to_string(),
+                        public_key: None,
+                        findings: vec![],
+                    },
+                    BTreeMap::new(),
+                )),
+                kind => errors.push(format!(
+                    "line {}: unknown section [{}]",
+                    index + 1,
+                    kind
+                )),
+            }
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            errors.push(format!("line {}: expected `Key = Value`", index + 1));
+            continue;
+        };
+        let Some((_, fields)) = sections.last_mut() else {
+            errors.push(format!("line {}: field outside a section", index + 1));
+            continue;
+        };
+        // wg matches field names case insensitively
+        fields.insert(name.trim().to_ascii_lowercase(), value.trim().into());
+    }
+
+    let interface_key = sections
+        .iter()
+        .find(|(section, _)| section.kind == "Interface")
+        .and_then(|(_, fields)| fields.get("privatekey").cloned());
+    let interface_public = interface_key.as_deref().and_then(|private_key| {
+        derive_wireguard_public(private_key.into()).ok()
+    });
+    let mut seen_peers = BTreeMap::new();
+    let mut seen_preshared = BTreeMap::new();
+    for (section, fields) in &mut sections {
+        let findings = &mut section.findings;
+        for (field, value) in fields.iter() {
+            if matches!(
+                field.as_str(),
+                "privatekey" | "publickey" | "presharedkey"
+            ) {
+                if let Err(e) = key(value) {
+                    findings.push(Finding::new(
+                        Severity::High,
+                        "invalid-key",
+                        format!("{}: {}", field, e),
+                    ));
+                }
+            }
+        }
+        if section.kind == "Interface" {
+            match fields.get("privatekey") {
+                Some(private_key) => {
+                    section.public_key =
+                        derive_wireguard_public(private_key.clone()).ok();
+                    if key(private_key).is_ok_and(|key| key == [0; 32]) {
+                        findings.push(Finding::new(
+                            Severity::Critical,
+                            "zero-key",
+                            "the private key is all zeros",
+                        ));
+                    }
+                }
+                None => findings.push(Finding::new(
+                    Severity::High,
+                    "missing-key",
+                    "the interface has no PrivateKey",
+                )),
+            }
+            continue;
+        }
+        let Some(public_key) = fields.get("publickey") else {
+            findings.push(Finding::new(
+                Severity::High,
+                "missing-key",
+                "the peer has no PublicKey",
+            ));
+            continue;
+        };
+        section.public_key = Some(public_key.clone());
+        if interface_key.as_ref() == Some(public_key) {
+            findings.push(Finding::new(
+                Severity::Critical,
+                "private-as-public",
+                "the peer PublicKey is the interface PrivateKey",
+            ));
+        }
+        if interface_public.as_ref() == Some(public_key) {
+            findings.push(Finding::new(
+                Severity::Medium,
+                "self-peer",
+                "the peer is the interface itself",
+            ));
+        }
+        // the identity and low order points yield an all zero shared secret
+        if key(public_key).is_ok_and(|key| {
+            let point = PublicKey::from(key);
+            StaticSecret::from([0x48; 32])
+                .diffie_hellman(&point)
+                .as_bytes()
+                == &[0; 32]
+        }) {
+            findings.push(Finding::new(
+                Severity::High,
+                "low-order-key",
+                "the public key is a low order point",
+            ));
+        }
+        if let Some(line) = seen_peers.insert(public_key.clone(), section.line)
+        {
+            findings.push(Finding::new(
+                Severity::Medium,
+                "duplicate-peer",
+                format!("the peer of line {} has the same key", line),
+            ));
+        }
+        match fields.get("presharedkey") {
+            Some(preshared_key) => {
+                if let Some(line) =
+                    seen_preshared.insert(preshared_key.clone(), section.line)
+                {
+                    findings.push(Finding::new(
+                        Severity::Low,
+                        "shared-preshared-key",
+                        format!(
+                            "the peer of line {} uses the same PresharedKey",
+                            line
+                        ),
+                    ));
+                }
+            }
+            None => findings.push(Finding::new(
+                Severity::Info,
+                "no-preshared-key",
+                "a PresharedKey adds a post-quantum layer",
+            )),
+        }
+    }
+    Ok(WireguardConfigAnalysis {
+        sections: sections
+            .into_iter()
+            .map(|(mut section, _)| {
+                section.findings = sorted(section.findings);
+                section
+            })
+            .collect(),
+        errors,
+    })
+}
+
+fn key(input: &str) -> Result<[u8; 32]> {
+    Base64::decode_vec(input.trim())
+        .ok()
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| {
+            Error::Unsupported("not a base64 32 byte key".to_string())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        derive_wireguard_public, generate_wireguard_keys,
+        validate_wireguard_config,
+    };
+
+    // RFC 7748 section 6.1, alice and bob
+    const ALICE: &str = "dwdtCnMYpX08FsFyUbJmRd9ML4frwJkqsXf7pR25LCo=";
+    const ALICE_PUBLIC: &str = "hSDwCYkwp1R0i33ctD73Wg2/Og0mOBr066SpjqqbTmo=";
+    const BOB_PUBLIC: &str = "3p7bfXt9wbTTW2HC7OQ1Nz+DQ8hbeGdNrfx+FG+IK08=";
+
+    #[tokio::test]
+    async fn test_wireguard_keys() {
+        assert_eq!(
+            derive_wireguard_public(ALICE.to_string()).unwrap(),
+            ALICE_PUBLIC
+        );
+        let keys = generate_wireguard_keys(true).await.unwrap();
+        assert_eq!(
+            derive_wireguard_public(keys.private_key).unwrap(),
+            keys.public_key
+        );
+        assert_eq!(keys.preshared_key.unwrap().len(), 44);
+        assert!(derive_wireguard_public("AAAA".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_validate_wireguard_config() {
+        let config = format!(
+            "[Interface]\nPrivateKey = {ALICE}\nListenPort = \
+             51820\n\n[Peer]\nPublicKey = {BOB_PUBLIC}\nAllowedIPs = \
+             10.0.0.2/32\n\n[Peer] # a copy paste mistake\npublickey = \
+             {ALICE}\nPresharedKey = c2hvcnQ=\n\n[Peer]\nPublicKey = \
+             {BOB_PUBLIC}\nPresharedKey = \
+             AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=\nEndpoint\n"
+        );
+        let analysis = validate_wireguard_config(config).unwrap();
+        assert_eq!(analysis.errors, ["line 16: expected `Key = Value`"]);
+        let codes = analysis
+            .sections
+            .iter()
+            .map(|section| {
+                section
+                    .findings
+                    .iter()
+                    .map(|finding| finding.code)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(codes, [
+            vec![],
+            vec!["no-preshared-key"],
+            vec!["private-as-public", "invalid-key"],
+            vec!["duplicate-peer"],
+        ]);
+        assert_eq!(
+            analysis.sections[0].public_key.as_deref(),
+            Some(ALICE_PUBLIC)
+        );
+    }
+}
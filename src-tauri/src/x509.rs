@@ -0,0 +1,226 @@
+use anyhow::Context;
+
+use crate::{
+    enums::{KeyFormat, TextEncoding},
+    errors::{Error, Result},
+};
+
+pub mod chain;
+pub mod diff;
+pub mod sct;
+
+/// One DER element, `raw` keeps the header so it can be copied as is.
+pub(crate) struct Tlv<'a> {
+    pub tag: u8,
+    pub raw: &'a [u8],
+    pub content: &'a [u8],
+}
+
+pub(crate) fn read_tlv(input: &[u8]) -> Result<(Tlv, &[u8])> {
+    let malformed = || Error::Unsupported("malformed der".to_string());
+    let (&tag, rest) = input.split_first().ok_or_else(malformed)?;
+    if tag & 0x1f == 0x1f {
+        return Err(Error::Unsupported("high tag number der".to_string()));
+    }
+    let (&first, rest) = rest.split_first().ok_or_else(malformed)?;
+    let (length, rest) = match first {
+        0 ..= 0x7f => (first as usize, rest),
+        0x81 ..= 0x84 => {
+            let size = (first & 0x7f) as usize;
+            if rest.len() < size {
+                return Err(malformed());
+            }
+            let length = rest[.. size]
+                .iter()
+                .fold(0usize, |acc, byte| acc << 8 | *byte as usize);
+            (length, &rest[size ..])
+        }
+        _ => return Err(malformed()),
+    };
+    if rest.len() < length {
+        return Err(malformed());
+    }
+    let header = input.len() - rest.len();
+    Ok((
+        Tlv {
+            tag,
+            raw: &input[.. header + length],
+            content: &rest[.. length],
+        },
+        &rest[length ..],
+    ))
+}
+
+pub(crate) fn read_all(mut input: &[u8]) -> Result<Vec<Tlv>> {
+    let mut elements = Vec::new();
+    while !input.is_empty() {
+        let (element, rest) = read_tlv(input)?;
+        elements.push(element);
+        input = rest;
+    }
+    Ok(elements)
+}
+
+pub(crate) fn encode_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut output = vec![tag];
+    if content.len() < 0x80 {
+        output.push(content.len() as u8);
+    } else {
+        let length = content.len().to_be_bytes();
+        let skip = length.iter().take_while(|byte| **byte == 0).count();
+        output.push(0x80 | (length.len() - skip) as u8);
+        output.extend_from_slice(&length[skip ..]);
+    }
+    output.extend_from_slice(content);
+    output
+}
+
+pub(crate) fn tbs_elements(certificate: &[u8]) -> Result<Vec<Tlv>> {
+    let (certificate, _) = read_tlv(certificate)?;
+    let (tbs, _) = read_tlv(certificate.content)?;
+    read_all(tbs.content)
+}
+
+pub(crate) fn subject_public_key_info(certificate: &[u8]) -> Result<&[u8]> {
+    let elements = tbs_elements(certificate)?;
+    let offset = usize::from(elements.first().map(|e| e.tag) == Some(0xa0));
+    elements
+        .get(5 + offset)
+        .map(|spki| spki.raw)
+        .ok_or_else(|| {
+            Error::Unsupported("certificate without spki".to_string())
+        })
+}
+
+/// Decoded lossily whatever its string type.
+pub(crate) fn subject_common_name(
+    certificate: &[u8],
+) -> Result<Option<String>> {
+    const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+    let elements = tbs_elements(certificate)?;
+    let offset = usize::from(elements.first().map(|e| e.tag) == Some(0xa0));
+    let subject = elements.get(4 + offset).ok_or_else(|| {
+        Error::Unsupported("certificate without subject".to_string())
+    })?;
+    let mut common_name = None;
+    for rdn in read_all(subject.content)? {
+        for attribute in read_all(rdn.content)? {
+            if let [oid, value] = &read_all(attribute.content)?[..] {
+                if oid.tag == 0x06 && oid.content == COMMON_NAME {
+                    common_name =
+                        Some(String::from_utf8_lossy(value.content).into());
+                }
+            }
+        }
+    }
+    Ok(common_name)
+}
+
+pub(crate) fn extension<'a>(
+    certificate: &'a [u8],
+    oid: &[u8],
+) -> Result<Option<&'a [u8]>> {
+    let elements = tbs_elements(certificate)?;
+    let Some(extensions) = elements.iter().find(|e| e.tag == 0xa3) else {
+        return Ok(None);
+    };
+    let (extensions, _) = read_tlv(extensions.content)?;
+    for extension in read_all(extensions.content)? {
+        let fields = read_all(extension.content)?;
+        if let (Some(id), Some(value)) = (fields.first(), fields.last()) {
+            if id.content == oid {
+                return Ok(Some(value.content));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// notBefore and notAfter in unix seconds.
+pub(crate) fn validity(certificate: &[u8]) -> Result<(u64, u64)> {
+    let elements = tbs_elements(certificate)?;
+    let offset = usize::from(elements.first().map(|e| e.tag) == Some(0xa0));
+    let validity = elements.get(3 + offset).ok_or_else(|| {
+        Error::Unsupported("certificate without validity".to_string())
+    })?;
+    match &read_all(validity.content)?[..] {
+        [not_before, not_after] => Ok((time(not_before)?, time(not_after)?)),
+        _ => Err(Error::Unsupported("malformed validity".to_string())),
+    }
+}
+
+/// UTCTime or GeneralizedTime in the `Z` form RFC 5280 mandates.
+fn time(tlv: &Tlv) -> Result<u64> {
+    let invalid = || Error::Unsupported("invalid certificate time".to_string());
+    let text = std::str::from_utf8(tlv.content).map_err(|_| invalid())?;
+    let text = text.strip_suffix('Z').ok_or_else(invalid)?;
+    let text = match (tlv.tag, text.len()) {
+        (0x17, 12) => {
+            let century = if &text[.. 2] >= "50" { "19" } else { "20" };
+            format!("{}{}", century, text)
+        }
+        (0x18, 14) => text.to_string(),
+        _ => return Err(invalid()),
+    };
+    if !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let field = |range: std::ops::Range<usize>| -> u64 {
+        text[range].parse().unwrap_or_default()
+    };
+    let (year, month, day) = (field(0 .. 4), field(4 .. 6), field(6 .. 8));
+    if year < 1970 || !(1 ..= 12).contains(&month) || !(1 ..= 31).contains(&day)
+    {
+        return Err(invalid());
+    }
+    // days from the civil date, shifted so the year starts in march
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let days = 365 * year + year / 4 - year / 100
+        + year / 400
+        + (153 * month + 2) / 5
+        + day
+        - 1
+        - 719_468;
+    Ok(days * 86400
+        + field(8 .. 10) * 3600
+        + field(10 .. 12) * 60
+        + field(12 .. 14))
+}
+
+pub(crate) fn certificates_from_pem(bundle: &str) -> Result<Vec<Vec<u8>>> {
+    bundle
+        .split_inclusive("-----END CERTIFICATE-----")
+        .filter_map(|block| {
+            block
+                .find("-----BEGIN CERTIFICATE-----")
+                .map(|start| &block[start ..])
+        })
+        .map(|block| {
+            let (_, der) = pem_rfc7468::decode_vec(block.as_bytes())
+                .context("invalid pem certificate")?;
+            Ok(der)
+        })
+        .collect()
+}
+
+pub(crate) fn certificate_to_der(
+    certificate: &str,
+    format: KeyFormat,
+    encoding: TextEncoding,
+) -> Result<Vec<u8>> {
+    match format {
+        KeyFormat::Pem => {
+            let (label, der) = pem_rfc7468::decode_vec(certificate.as_bytes())
+                .context("invalid pem certificate")?;
+            if label != "CERTIFICATE" {
+                return Err(Error::Unsupported(format!("{} pem", label)));
+            }
+            Ok(der)
+        }
+        KeyFormat::Der => encoding.decode(certificate),
+    }
+}
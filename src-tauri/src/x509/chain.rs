@@ -0,0 +1,406 @@
+//! Builds the path from a leaf to a trust anchor. Only signatures and
+//! validity periods are checked, no constraints nor revocation.
+
+use anyhow::Context;
+use ecdsa::hazmat::PrehashVerifier;
+use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Sign, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{
+    certificates_from_pem, extension, read_all, read_tlv, subject_common_name,
+    subject_public_key_info, tbs_elements, validity,
+};
+use crate::{
+    crypto::digest::digest_inner,
+    enums::Digest,
+    errors::{Error, Result},
+    fetch, keystore, vault,
+};
+
+const MAX_DEPTH: usize = 10;
+const AUTHORITY_INFO_ACCESS: &[u8] =
+    &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x01];
+const CA_ISSUERS: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x02];
+const SIGNED_DATA: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainDto {
+    /// pem certificates, the leaf first and the others in any order
+    pub certificates: String,
+    /// pem trust anchors, the native certificate stores when absent
+    pub roots: Option<String>,
+    #[serde(default)]
+    pub fetch_missing: bool,
+    /// unix seconds, now when absent
+    pub at: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainLink {
+    pub subject: Option<String>,
+    /// unix seconds
+    pub not_before: u64,
+    pub not_after: u64,
+    /// the url the certificate was downloaded from
+    pub fetched_from: Option<String>,
+    pub anchor: bool,
+    pub certificate: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainVerification {
+    /// from the leaf up
+    pub chain: Vec<ChainLink>,
+    /// the chain ends at a trust anchor and is valid at `at`
+    pub trusted: bool,
+    pub problems: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn verify_chain(data: ChainDto) -> Result<ChainVerification> {
+    info!(
+        "verify chain, custom roots: {}, fetch missing: {}, at: {:?}",
+        data.roots.is_some(),
+        data.fetch_missing,
+        data.at
+    );
+    let mut pool = certificates_from_pem(&data.certificates)?;
+    if pool.is_empty() {
+        return Err(Error::Unsupported("no certificate".to_string()));
+    }
+    let leaf = pool.remove(0);
+    let roots = match &data.roots {
+        Some(roots) => certificates_from_pem(roots)?,
+        None => keystore::native_certificates()?,
+    };
+
+    // (der, fetched from, anchor)
+    let mut chain = vec![(leaf, None, false)];
+    let mut problems = vec![];
+    let mut anchored = false;
+    while chain.len() <= MAX_DEPTH {
+        let current = chain[chain.len() - 1].0.clone();
+        let name = display_name(&current);
+        if roots.contains(&current) {
+            if let Some(link) = chain.last_mut() {
+                link.2 = true;
+            }
+            anchored = true;
+            break;
+        }
+        if let Some(root) = roots.iter().find(|root| issued_by(&current, root))
+        {
+            chain.push((root.clone(), None, true));
+            anchored = true;
+            break;
+        }
+        if let Some(index) =
+            pool.iter().position(|issuer| issued_by(&current, issuer))
+        {
+            chain.push((pool.remove(index), None, false));
+            continue;
+        }
+        if issued_by(&current, &current) {
+            problems.push(format!("{} is self-signed but not trusted", name));
+            break;
+        }
+        let urls = ca_issuers(&current)?;
+        if !data.fetch_missing || urls.is_empty() {
+            problems.push(if urls.is_empty() {
+                format!("issuer of {} is missing", name)
+            } else {
+                format!(
+                    "issuer of {} is missing, it is published at {}",
+                    name,
+                    urls.join(", ")
+                )
+            });
+            break;
+        }
+        let mut fetched = None;
+        for url in urls {
+            match fetch::get(&url, false).await.and_then(|body| parse(&body)) {
+                Ok(certificates) => {
+                    fetched = certificates
+                        .into_iter()
+                        .find(|issuer| issued_by(&current, issuer))
+                        .map(|issuer| (issuer, url.clone()));
+                    if fetched.is_none() {
+                        problems.push(format!(
+                            "{} does not hold the issuer of {}",
+                            url, name
+                        ));
+                    }
+                }
+                Err(e) => problems.push(format!("fetch {}: {}", url, e)),
+            }
+            if fetched.is_some() {
+                break;
+            }
+        }
+        let Some((issuer, url)) = fetched else {
+            break;
+        };
+        info!("fetched the issuer of {} from {}", name, url);
+        chain.push((issuer, Some(url), false));
+    }
+    if chain.len() > MAX_DEPTH {
+        problems.push(format!("chain longer than {}", MAX_DEPTH));
+    }
+
+    let now = data.at.unwrap_or_else(vault::now);
+    let mut links = vec![];
+    for (der, fetched_from, anchor) in chain {
+        let (not_before, not_after) = validity(&der)?;
+        if now < not_before {
+            problems.push(format!(
+                "{} is not valid before {}",
+                display_name(&der),
+                not_before
+            ));
+        }
+        if now > not_after {
+            problems.push(format!(
+                "{} expired at {}",
+                display_name(&der),
+                not_after
+            ));
+        }
+        links.push(ChainLink {
+            subject: subject_common_name(&der)?,
+            not_before,
+            not_after,
+            fetched_from,
+            anchor,
+            certificate: pem_rfc7468::encode_string(
+                "CERTIFICATE",
+                pkcs8::LineEnding::LF,
+                &der,
+            )
+            .context("encode certificate pem failed")?,
+        });
+    }
+    Ok(ChainVerification {
+        chain: links,
+        trusted: anchored && problems.is_empty(),
+        problems,
+    })
+}
+
+fn issued_by(certificate: &[u8], issuer: &[u8]) -> bool {
+    let names = || -> Result<bool> {
+        let elements = tbs_elements(certificate)?;
+        let offset = usize::from(elements.first().map(|e| e.tag) == Some(0xa0));
+        let issuer_elements = tbs_elements(issuer)?;
+        let issuer_offset =
+            usize::from(issuer_elements.first().map(|e| e.tag) == Some(0xa0));
+        Ok(elements.get(2 + offset).map(|name| name.raw)
+            == issuer_elements.get(4 + issuer_offset).map(|name| name.raw))
+    };
+    names().unwrap_or(false)
+        && subject_public_key_info(issuer)
+            .and_then(|spki| verify_signature(certificate, spki))
+            .unwrap_or(false)
+}
+
+fn verify_signature(certificate: &[u8], spki: &[u8]) -> Result<bool> {
+    const RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01];
+    const ECDSA: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03];
+    const ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+    let (certificate, _) = read_tlv(certificate)?;
+    let elements = read_all(certificate.content)?;
+    let [tbs, algorithm, signature] = &elements[..] else {
+        return Err(Error::Unsupported("malformed certificate".to_string()));
+    };
+    let (oid, _) = read_tlv(algorithm.content)?;
+    // the bit string starts with its count of unused bits
+    let signature = signature.content.get(1 ..).unwrap_or_default();
+    let oid = oid.content;
+    Ok(match oid {
+        _ if oid.starts_with(RSA) => {
+            let key = RsaPublicKey::from_public_key_der(spki)
+                .context("issuer key is not rsa")?;
+            let (scheme, digest) = match oid[RSA.len() ..] {
+                [0x05] => (Pkcs1v15Sign::new::<sha1::Sha1>(), Digest::Sha1),
+                [0x0b] => (Pkcs1v15Sign::new::<sha2::Sha256>(), Digest::Sha256),
+                [0x0c] => (Pkcs1v15Sign::new::<sha2::Sha384>(), Digest::Sha384),
+                [0x0d] => (Pkcs1v15Sign::new::<sha2::Sha512>(), Digest::Sha512),
+                _ => return Err(unsupported(oid)),
+            };
+            key.verify(scheme, &digest_inner(digest, tbs.raw), signature)
+                .is_ok()
+        }
+        _ if oid.starts_with(ECDSA) => {
+            let digest = match oid[ECDSA.len() ..] {
+                [0x02] => Digest::Sha256,
+                [0x03] => Digest::Sha384,
+                [0x04] => Digest::Sha512,
+                _ => return Err(unsupported(oid)),
+            };
+            let hash = digest_inner(digest, tbs.raw);
+            if let Ok(key) =
+                p256::ecdsa::VerifyingKey::from_public_key_der(spki)
+            {
+                p256::ecdsa::Signature::from_der(signature).is_ok_and(
+                    |signature| key.verify_prehash(&hash, &signature).is_ok(),
+                )
+            } else if let Ok(key) =
+                p384::ecdsa::VerifyingKey::from_public_key_der(spki)
+            {
+                p384::ecdsa::Signature::from_der(signature).is_ok_and(
+                    |signature| key.verify_prehash(&hash, &signature).is_ok(),
+                )
+            } else {
+                return Err(Error::Unsupported(
+                    "issuer key is not p-256 nor p-384".to_string(),
+                ));
+            }
+        }
+        ED25519 => {
+            use ed25519_dalek::Verifier;
+            let key = ed25519_dalek::VerifyingKey::from_public_key_der(spki)
+                .context("issuer key is not ed25519")?;
+            ed25519_dalek::Signature::from_slice(signature)
+                .is_ok_and(|signature| key.verify(tbs.raw, &signature).is_ok())
+        }
+        _ => return Err(unsupported(oid)),
+    })
+}
+
+fn unsupported(oid: &[u8]) -> Error {
+    Error::Unsupported(format!(
+        "certificate signature algorithm {}",
+        const_oid::ObjectIdentifier::from_bytes(oid)
+            .map(|oid| oid.to_string())
+            .unwrap_or_default()
+    ))
+}
+
+fn ca_issuers(certificate: &[u8]) -> Result<Vec<String>> {
+    let mut urls = vec![];
+    let Some(value) = extension(certificate, AUTHORITY_INFO_ACCESS)? else {
+        return Ok(urls);
+    };
+    let (descriptions, _) = read_tlv(value)?;
+    for description in read_all(descriptions.content)? {
+        if let [method, location] = &read_all(description.content)?[..] {
+            // uniformResourceIdentifier [6] IMPLICIT IA5String
+            if method.content == CA_ISSUERS && location.tag == 0x86 {
+                urls.push(
+                    String::from_utf8_lossy(location.content).into_owned(),
+                );
+            }
+        }
+    }
+    Ok(urls)
+}
+
+/// caIssuers serve a der certificate, a pkcs#7 certs-only bundle and
+/// sometimes pem.
+fn parse(body: &[u8]) -> Result<Vec<Vec<u8>>> {
+    if body.starts_with(b"-----BEGIN") {
+        return certificates_from_pem(&String::from_utf8_lossy(body));
+    }
+    let (content_info, _) = read_tlv(body)?;
+    match &read_all(content_info.content)?[..] {
+        [oid, signed_data] if oid.tag == 0x06 && oid.content == SIGNED_DATA => {
+            let (signed_data, _) = read_tlv(signed_data.content)?;
+            Ok(read_all(signed_data.content)?
+                .iter()
+                .find(|field| field.tag == 0xa0)
+                .map(|certificates| read_all(certificates.content))
+                .transpose()?
+                .unwrap_or_default()
+                .iter()
+                .map(|certificate| certificate.raw.to_vec())
+                .collect())
+        }
+        _ => Ok(vec![content_info.raw.to_vec()]),
+    }
+}
+
+fn display_name(certificate: &[u8]) -> String {
+    subject_common_name(certificate)
+        .ok()
+        .flatten()
+        .map(|name| format!("`{}`", name))
+        .unwrap_or_else(|| "the certificate".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ca_issuers, verify_chain, ChainDto};
+    use crate::x509::certificates_from_pem;
+
+    /// leaf, intermediate and root, see chain.py
+    const CHAIN: &str = include_str!("../../tests/vectors/chain.pem");
+
+    fn blocks() -> Vec<String> {
+        CHAIN
+            .split_inclusive("-----END CERTIFICATE-----\n")
+            .map(String::from)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_verify_chain() {
+        let blocks = blocks();
+        let verification = verify_chain(ChainDto {
+            certificates: format!("{}{}", blocks[0], blocks[1]),
+            roots: Some(blocks[2].clone()),
+            fetch_missing: false,
+            at: None,
+        })
+        .await
+        .unwrap();
+        assert!(verification.trusted, "{:?}", verification.problems);
+        let subjects = verification
+            .chain
+            .iter()
+            .map(|link| link.subject.as_deref().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(subjects, ["kits leaf", "kits intermediate", "kits root"]);
+        assert!(verification.chain[2].anchor);
+
+        let verification = verify_chain(ChainDto {
+            certificates: blocks[0].clone(),
+            roots: Some(blocks[2].clone()),
+            fetch_missing: false,
+            at: None,
+        })
+        .await
+        .unwrap();
+        assert!(!verification.trusted);
+        assert_eq!(verification.chain.len(), 1);
+        assert_eq!(
+            verification.problems,
+            ["issuer of `kits leaf` is missing, it is published at \
+              http://pki.example/intermediate.der"]
+        );
+
+        // the intermediate is not signed by the leaf
+        let verification = verify_chain(ChainDto {
+            certificates: format!("{}{}", blocks[1], blocks[0]),
+            roots: Some(blocks[0].clone()),
+            fetch_missing: false,
+            at: None,
+        })
+        .await
+        .unwrap();
+        assert!(!verification.trusted);
+    }
+
+    #[test]
+    fn test_ca_issuers() {
+        let certificates = certificates_from_pem(CHAIN).unwrap();
+        assert_eq!(ca_issuers(&certificates[0]).unwrap(), [
+            "http://pki.example/intermediate.der"
+        ]);
+        assert!(ca_issuers(&certificates[2]).unwrap().is_empty());
+    }
+}
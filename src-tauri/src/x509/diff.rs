@@ -0,0 +1,232 @@
+//! Field by field view of two certificates for a side by side diff.
+
+use std::net::IpAddr;
+
+use const_oid::{db::DB, ObjectIdentifier};
+use tracing::info;
+
+use super::{certificate_to_der, read_all, read_tlv, tbs_elements, Tlv};
+use crate::{
+    audit::compare::FieldDiff,
+    crypto::digest::digest_inner,
+    enums::{Digest, KeyFormat, TextEncoding},
+    errors::{Error, Result},
+};
+
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// Attribute types of distinguished names written by their short name.
+const ATTRIBUTES: &[(&[u8], &str)] = &[
+    (&[0x55, 0x04, 0x03], "CN"),
+    (&[0x55, 0x04, 0x06], "C"),
+    (&[0x55, 0x04, 0x07], "L"),
+    (&[0x55, 0x04, 0x08], "ST"),
+    (&[0x55, 0x04, 0x0a], "O"),
+    (&[0x55, 0x04, 0x0b], "OU"),
+    (
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01],
+        "emailAddress",
+    ),
+];
+
+/// Rows follow the fields of `a`, those only `b` has come last.
+#[tauri::command]
+pub fn diff_certs(
+    a: String,
+    b: String,
+    format: KeyFormat,
+    encoding: TextEncoding,
+) -> Result<Vec<FieldDiff>> {
+    let a = fields(&certificate_to_der(&a, format, encoding)?)?;
+    let b = fields(&certificate_to_der(&b, format, encoding)?)?;
+    let get = |fields: &[(String, String)], name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+    };
+    let mut names = a.iter().map(|(name, _)| name).collect::<Vec<_>>();
+    for (name, _) in &b {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let diff = names
+        .into_iter()
+        .map(|name| FieldDiff::new(name, get(&a, name), get(&b, name)))
+        .collect::<Vec<_>>();
+    info!(
+        "diff certificates, fields: {}, differing: {}",
+        diff.len(),
+        diff.iter().filter(|field| !field.equal).count()
+    );
+    Ok(diff)
+}
+
+fn fields(certificate: &[u8]) -> Result<Vec<(String, String)>> {
+    let elements = tbs_elements(certificate)?;
+    let (version, elements) = match elements.split_first() {
+        Some((first, rest)) if first.tag == 0xa0 => {
+            let (version, _) = read_tlv(first.content)?;
+            let version = version.content.last().copied().unwrap_or_default();
+            (version as u16 + 1, rest)
+        }
+        _ => (1, &elements[..]),
+    };
+    let [serial, signature, issuer, _, subject, spki, rest @ ..] = elements
+    else {
+        return Err(Error::Unsupported("malformed certificate".to_string()));
+    };
+    let (not_before, not_after) = super::validity(certificate)?;
+    let spki_algorithm = read_all(spki.content)?
+        .first()
+        .map(algorithm)
+        .transpose()?
+        .unwrap_or_default();
+    let fingerprint = TextEncoding::Hex
+        .encode(&digest_inner(Digest::Sha256, spki.raw))
+        .map(|hex| format!("sha256:{}", hex))?;
+    let mut fields = vec![
+        ("version", version.to_string()),
+        ("serial", TextEncoding::Hex.encode(serial.content)?),
+        ("signature algorithm", algorithm(signature)?),
+        ("issuer", name(issuer)?),
+        ("subject", name(subject)?),
+        ("not before", not_before.to_string()),
+        ("not after", not_after.to_string()),
+        ("public key algorithm", spki_algorithm),
+        ("public key", fingerprint),
+    ]
+    .into_iter()
+    .map(|(field, value)| (field.to_string(), value))
+    .collect::<Vec<_>>();
+
+    let Some(extensions) = rest.iter().find(|element| element.tag == 0xa3)
+    else {
+        return Ok(fields);
+    };
+    let (extensions, _) = read_tlv(extensions.content)?;
+    for extension in read_all(extensions.content)? {
+        let (id, critical, value) = match &read_all(extension.content)?[..] {
+            [id, value] => (id, false, value),
+            [id, critical, value] => {
+                (id, critical.content.first() == Some(&0xff), value)
+            }
+            _ => {
+                return Err(Error::Unsupported(
+                    "malformed extension".to_string(),
+                ))
+            }
+        };
+        let shown = if id.content == SUBJECT_ALT_NAME {
+            general_names(value.content)?
+        } else {
+            TextEncoding::Hex.encode(value.content)?
+        };
+        fields.push((
+            format!("extension {}", oid_name(id.content)?),
+            if critical {
+                format!("critical, {}", shown)
+            } else {
+                shown
+            },
+        ));
+    }
+    Ok(fields)
+}
+
+fn oid_name(content: &[u8]) -> Result<String> {
+    let oid = ObjectIdentifier::from_bytes(content)
+        .map_err(|_| Error::Unsupported("malformed oid".to_string()))?;
+    Ok(DB.by_oid(&oid).map_or(oid.to_string(), str::to_string))
+}
+
+fn algorithm(identifier: &Tlv) -> Result<String> {
+    match read_all(identifier.content)?.first() {
+        Some(oid) if oid.tag == 0x06 => oid_name(oid.content),
+        _ => Err(Error::Unsupported("malformed algorithm".to_string())),
+    }
+}
+
+fn name(name: &Tlv) -> Result<String> {
+    let mut attributes = Vec::new();
+    for rdn in read_all(name.content)? {
+        for attribute in read_all(rdn.content)? {
+            if let [oid, value] = &read_all(attribute.content)?[..] {
+                let short = ATTRIBUTES
+                    .iter()
+                    .find(|(id, _)| *id == oid.content)
+                    .map(|(_, short)| short.to_string());
+                attributes.push(format!(
+                    "{}={}",
+                    short.map_or_else(|| oid_name(oid.content), Ok)?,
+                    String::from_utf8_lossy(value.content)
+                ));
+            }
+        }
+    }
+    Ok(attributes.join(", "))
+}
+
+fn general_names(value: &[u8]) -> Result<String> {
+    let (names, _) = read_tlv(value)?;
+    let names = read_all(names.content)?
+        .iter()
+        .map(|name| {
+            let text = String::from_utf8_lossy(name.content);
+            let ip =
+                <[u8; 4]>::try_from(name.content).map(IpAddr::from).or_else(
+                    |_| <[u8; 16]>::try_from(name.content).map(IpAddr::from),
+                );
+            Ok(match (name.tag, ip) {
+                (0x81, _) => format!("email:{}", text),
+                (0x82, _) => format!("DNS:{}", text),
+                (0x86, _) => format!("URI:{}", text),
+                (0x87, Ok(ip)) => format!("IP:{}", ip),
+                (tag, _) => format!(
+                    "[{}]{}",
+                    tag & 0x1f,
+                    TextEncoding::Hex.encode(name.content)?
+                ),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(names.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::diff_certs;
+    use crate::enums::{KeyFormat, TextEncoding};
+
+    const CHAIN: &str = include_str!("../../tests/vectors/chain.pem");
+
+    #[test]
+    fn test_diff_certs() {
+        let blocks = CHAIN
+            .split_inclusive("-----END CERTIFICATE-----\n")
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let diff = diff_certs(
+            blocks[0].clone(),
+            blocks[1].clone(),
+            KeyFormat::Pem,
+            TextEncoding::Base64,
+        )
+        .unwrap();
+        let field =
+            |name: &str| diff.iter().find(|field| field.field == name).unwrap();
+        assert!(field("version").equal);
+        assert_eq!(field("version").a.as_deref(), Some("3"));
+        assert!(field("not after").equal);
+        assert_eq!(field("subject").a.as_deref(), Some("CN=kits leaf"));
+        assert_eq!(field("subject").b.as_deref(), Some("CN=kits intermediate"));
+        assert!(!field("public key algorithm").equal);
+        // only the leaf points at its issuer
+        assert!(diff
+            .iter()
+            .any(|field| field.field.starts_with("extension ")
+                && field.a.is_some()
+                && field.b.is_none()));
+    }
+}
@@ -0,0 +1,256 @@
+use const_oid::ObjectIdentifier;
+use ecdsa::signature::Verifier;
+use pkcs8::DecodePublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use super::{
+    certificate_to_der, encode_tlv, read_all, read_tlv,
+    subject_public_key_info, tbs_elements,
+};
+use crate::{
+    enums::{KeyFormat, TextEncoding},
+    errors::{Error, Result},
+};
+
+/// RFC 6962 embedded `SignedCertificateTimestampList` extension
+const SCT_LIST: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.4.1.11129.2.4.2");
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CtLog {
+    pub description: String,
+    /// SubjectPublicKeyInfo of the log, its sha-256 is the log id
+    pub key: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedCertificateTimestamp {
+    pub version: u8,
+    pub log_id: String,
+    pub log: Option<String>,
+    /// milliseconds since the unix epoch
+    pub timestamp: u64,
+    pub extensions: String,
+    pub hash_algorithm: String,
+    pub signature_algorithm: String,
+    pub signature: String,
+    /// only known when the issuer is given and the log is in `logs`
+    pub verified: Option<bool>,
+}
+
+struct Sct<'a> {
+    version: u8,
+    log_id: &'a [u8],
+    timestamp: u64,
+    extensions: &'a [u8],
+    hash_algorithm: u8,
+    signature_algorithm: u8,
+    signature: &'a [u8],
+}
+
+/// Signatures are checked over the reconstructed precertificate, which
+/// needs the `issuer` for its key hash.
+#[tauri::command]
+pub fn parse_sct(
+    certificate: String,
+    issuer: Option<String>,
+    format: KeyFormat,
+    logs: Vec<CtLog>,
+    encoding: TextEncoding,
+) -> Result<Vec<SignedCertificateTimestamp>> {
+    let certificate = certificate_to_der(&certificate, format, encoding)?;
+    let issuer_key_hash = issuer
+        .map(|issuer| {
+            let issuer = certificate_to_der(&issuer, format, encoding)?;
+            subject_public_key_info(&issuer)
+                .map(|spki| Sha256::digest(spki).to_vec())
+        })
+        .transpose()?;
+    let logs = logs
+        .iter()
+        .map(|log| {
+            let key = encoding.decode(&log.key)?;
+            Ok((Sha256::digest(&key).to_vec(), &log.description, key))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (list, precertificate) = split_sct_list(&certificate)?;
+    let scts = parse_sct_list(list)?;
+    info!("parse sct, count: {}, logs: {}", scts.len(), logs.len());
+    scts.iter()
+        .map(|sct| {
+            let log = logs.iter().find(|(id, ..)| id == sct.log_id);
+            let verified = match (log, &issuer_key_hash) {
+                (Some((_, _, key)), Some(issuer_key_hash)) => Some(verify(
+                    sct,
+                    key,
+                    &signed_data(sct, issuer_key_hash, &precertificate),
+                )),
+                _ => None,
+            };
+            Ok(SignedCertificateTimestamp {
+                version: sct.version,
+                log_id: encoding.encode(sct.log_id)?,
+                log: log.map(|(_, description, _)| description.to_string()),
+                timestamp: sct.timestamp,
+                extensions: encoding.encode(sct.extensions)?,
+                hash_algorithm: match sct.hash_algorithm {
+                    4 => "sha256".to_string(),
+                    other => format!("unknown({})", other),
+                },
+                signature_algorithm: match sct.signature_algorithm {
+                    1 => "rsa".to_string(),
+                    3 => "ecdsa".to_string(),
+                    other => format!("unknown({})", other),
+                },
+                signature: encoding.encode(sct.signature)?,
+                verified,
+            })
+        })
+        .collect()
+}
+
+/// The tbs certificate comes back without the list, which is what the log
+/// signed.
+fn split_sct_list(certificate: &[u8]) -> Result<(&[u8], Vec<u8>)> {
+    let mut list = None;
+    let mut tbs = Vec::new();
+    for element in tbs_elements(certificate)? {
+        if element.tag != 0xa3 {
+            tbs.extend_from_slice(element.raw);
+            continue;
+        }
+        let (extensions, _) = read_tlv(element.content)?;
+        let mut kept = Vec::new();
+        for extension in read_all(extensions.content)? {
+            let fields = read_all(extension.content)?;
+            match (fields.first(), fields.last()) {
+                (Some(oid), Some(value))
+                    if oid.content == SCT_LIST.as_bytes() =>
+                {
+                    list = Some(read_tlv(value.content)?.0.content);
+                }
+                _ => kept.extend_from_slice(extension.raw),
+            }
+        }
+        if !kept.is_empty() {
+            tbs.extend(encode_tlv(0xa3, &encode_tlv(0x30, &kept)));
+        }
+    }
+    let list = list.ok_or_else(|| {
+        Error::Unsupported("certificate without embedded sct".to_string())
+    })?;
+    Ok((list, encode_tlv(0x30, &tbs)))
+}
+
+fn take<'a>(input: &mut &'a [u8], size: usize) -> Result<&'a [u8]> {
+    if input.len() < size {
+        return Err(Error::Unsupported("truncated sct".to_string()));
+    }
+    let (head, rest) = input.split_at(size);
+    *input = rest;
+    Ok(head)
+}
+
+fn take_vector<'a>(input: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let size = take(input, 2)?;
+    take(input, u16::from_be_bytes([size[0], size[1]]) as usize)
+}
+
+fn parse_sct_list(list: &[u8]) -> Result<Vec<Sct>> {
+    let mut list = list;
+    let mut entries = take_vector(&mut list)?;
+    let mut scts = Vec::new();
+    while !entries.is_empty() {
+        let mut sct = take_vector(&mut entries)?;
+        let version = take(&mut sct, 1)?[0];
+        if version != 0 {
+            return Err(Error::Unsupported(format!("sct version {}", version)));
+        }
+        let log_id = take(&mut sct, 32)?;
+        let timestamp = u64::from_be_bytes(
+            take(&mut sct, 8)?.try_into().expect("eight bytes"),
+        );
+        let extensions = take_vector(&mut sct)?;
+        let algorithms = take(&mut sct, 2)?;
+        let signature = take_vector(&mut sct)?;
+        scts.push(Sct {
+            version,
+            log_id,
+            timestamp,
+            extensions,
+            hash_algorithm: algorithms[0],
+            signature_algorithm: algorithms[1],
+            signature,
+        });
+    }
+    Ok(scts)
+}
+
+fn signed_data(
+    sct: &Sct,
+    issuer_key_hash: &[u8],
+    precertificate: &[u8],
+) -> Vec<u8> {
+    // version, certificate_timestamp, timestamp, precert_entry
+    let mut data = vec![sct.version, 0];
+    data.extend_from_slice(&sct.timestamp.to_be_bytes());
+    data.extend_from_slice(&[0, 1]);
+    data.extend_from_slice(issuer_key_hash);
+    data.extend_from_slice(&(precertificate.len() as u32).to_be_bytes()[1 ..]);
+    data.extend_from_slice(precertificate);
+    data.extend_from_slice(&(sct.extensions.len() as u16).to_be_bytes());
+    data.extend_from_slice(sct.extensions);
+    data
+}
+
+fn verify(sct: &Sct, key: &[u8], data: &[u8]) -> bool {
+    match sct.signature_algorithm {
+        3 => p256::ecdsa::VerifyingKey::from_public_key_der(key)
+            .ok()
+            .zip(p256::ecdsa::Signature::from_der(sct.signature).ok())
+            .is_some_and(|(key, signature)| {
+                key.verify(data, &signature).is_ok()
+            }),
+        1 => rsa::RsaPublicKey::from_public_key_der(key)
+            .ok()
+            .zip(rsa::pkcs1v15::Signature::try_from(sct.signature).ok())
+            .is_some_and(|(key, signature)| {
+                rsa::pkcs1v15::VerifyingKey::<Sha256>::new(key)
+                    .verify(data, &signature)
+                    .is_ok()
+            }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_sct_list;
+
+    #[test]
+    fn test_parse_sct_list() {
+        let mut sct = vec![0u8];
+        sct.extend_from_slice(&[0xab; 32]);
+        sct.extend_from_slice(&1_700_000_000_000u64.to_be_bytes());
+        sct.extend_from_slice(&[0, 0, 4, 3, 0, 2, 0x30, 0x00]);
+        let mut list = ((sct.len() + 2) as u16).to_be_bytes().to_vec();
+        list.extend_from_slice(&(sct.len() as u16).to_be_bytes());
+        list.extend_from_slice(&sct);
+
+        let scts = parse_sct_list(&list).unwrap();
+        assert_eq!(1, scts.len());
+        assert_eq!([0xab; 32], scts[0].log_id);
+        assert_eq!(1_700_000_000_000, scts[0].timestamp);
+        assert_eq!(
+            (4, 3),
+            (scts[0].hash_algorithm, scts[0].signature_algorithm)
+        );
+        assert_eq!([0x30, 0x00], scts[0].signature);
+        assert!(parse_sct_list(&list[.. list.len() - 1]).is_err());
+    }
+}
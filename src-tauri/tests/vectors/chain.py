@@ -0,0 +1,69 @@
+"""Generates tests/vectors/chain.pem with pyca/cryptography (OpenSSL).
+
+A p-256 root, an rsa-2048 intermediate and a p-384 leaf whose authority
+information access points at the intermediate, concatenated leaf first.
+"""
+
+import datetime
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, rsa
+from cryptography.x509.oid import AuthorityInformationAccessOID, NameOID
+
+
+def name(common_name):
+    return x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, common_name)])
+
+
+def certificate(subject, issuer, key, issuer_key, serial, ca, extensions=()):
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name(subject))
+        .issuer_name(name(issuer))
+        .public_key(key.public_key())
+        .serial_number(serial)
+        .not_valid_before(datetime.datetime(2024, 1, 1))
+        .not_valid_after(datetime.datetime(2034, 1, 1))
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=None), True)
+    )
+    for extension in extensions:
+        builder = builder.add_extension(extension, False)
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def main():
+    root_key = ec.generate_private_key(ec.SECP256R1())
+    intermediate_key = rsa.generate_private_key(65537, 2048)
+    leaf_key = ec.generate_private_key(ec.SECP384R1())
+    root = certificate("kits root", "kits root", root_key, root_key, 1, True)
+    intermediate = certificate(
+        "kits intermediate", "kits root", intermediate_key, root_key, 2, True
+    )
+    leaf = certificate(
+        "kits leaf",
+        "kits intermediate",
+        leaf_key,
+        intermediate_key,
+        3,
+        False,
+        [
+            x509.AuthorityInformationAccess(
+                [
+                    x509.AccessDescription(
+                        AuthorityInformationAccessOID.CA_ISSUERS,
+                        x509.UniformResourceIdentifier(
+                            "http://pki.example/intermediate.der"
+                        ),
+                    )
+                ]
+            )
+        ],
+    )
+    with open("chain.pem", "wb") as file:
+        for certificate_ in [leaf, intermediate, root]:
+            file.write(certificate_.public_bytes(serialization.Encoding.PEM))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,116 @@
+// Generates the nist curve vectors of tests/vectors/ecies.json with Web Crypto
+// (node >= 20): `node tests/vectors/ecies.mjs`. The secp256k1 ones come from
+// ecies.py, Web Crypto has no such curve; the vectors of each script are
+// replaced, the others kept.
+//
+// The envelope is: compressed ephemeral public key || AES-256-GCM(ciphertext
+// || tag), the 44 bytes key material (32 bytes key || 12 bytes nonce) derives
+// from the ECDH shared secret with SHA-256 based HKDF or Concat KDF.
+
+import { readFileSync, writeFileSync } from "node:fs";
+
+const { subtle } = globalThis.crypto;
+
+const CURVES = {
+	nistp256: "P-256",
+	nistp384: "P-384",
+	nistp521: "P-521",
+};
+
+const PATH = new URL("ecies.json", import.meta.url);
+
+const b64 = (bytes) => Buffer.from(bytes).toString("base64");
+
+// NIST SP 800-56A concatenation over SHA-256, H(counter || Z || info) blocks
+async function concatKdf(secret, info, length) {
+	const output = [];
+	for (let counter = 1; output.length < length; counter++) {
+		const block = Buffer.alloc(4 + secret.length + info.length);
+		block.writeUInt32BE(counter, 0);
+		block.set(secret, 4);
+		block.set(info, 4 + secret.length);
+		output.push(...new Uint8Array(await subtle.digest("SHA-256", block)));
+	}
+	return new Uint8Array(output.slice(0, length));
+}
+
+async function derive(kdf, secret, salt, info) {
+	if (kdf === "hkdf") {
+		const key = await subtle.importKey("raw", secret, "HKDF", false, [
+			"deriveBits",
+		]);
+		const bits = await subtle.deriveBits(
+			{ name: "HKDF", hash: "SHA-256", salt, info },
+			key,
+			44 * 8,
+		);
+		return new Uint8Array(bits);
+	}
+	return concatKdf(secret, info, 44);
+}
+
+async function compressed(publicKey) {
+	const raw = new Uint8Array(await subtle.exportKey("raw", publicKey));
+	const size = (raw.length - 1) / 2;
+	const y = raw[raw.length - 1];
+	return new Uint8Array([2 + (y & 1), ...raw.slice(1, 1 + size)]);
+}
+
+async function vector(curveName, kdf, plaintext, salt, info) {
+	const algorithm = { name: "ECDH", namedCurve: CURVES[curveName] };
+	const usages = ["deriveBits"];
+	const receiver = await subtle.generateKey(algorithm, true, usages);
+	const ephemeral = await subtle.generateKey(algorithm, true, usages);
+	const secret = new Uint8Array(
+		await subtle.deriveBits(
+			{ name: "ECDH", public: receiver.publicKey },
+			ephemeral.privateKey,
+			null,
+		),
+	);
+	const okm = await derive(
+		kdf,
+		secret,
+		salt ?? new Uint8Array(),
+		info ?? new Uint8Array(),
+	);
+	const key = await subtle.importKey("raw", okm.slice(0, 32), "AES-GCM", false, [
+		"encrypt",
+	]);
+	const ciphertext = new Uint8Array(
+		await subtle.encrypt(
+			{ name: "AES-GCM", iv: okm.slice(32) },
+			key,
+			plaintext,
+		),
+	);
+	return {
+		curveName,
+		kdf,
+		privateKey: b64(await subtle.exportKey("pkcs8", receiver.privateKey)),
+		salt: salt ? b64(salt) : null,
+		info: info ? b64(info) : null,
+		plaintext: b64(plaintext),
+		ciphertext: b64([...(await compressed(ephemeral.publicKey)), ...ciphertext]),
+	};
+}
+
+const vectors = [];
+for (const curveName of Object.keys(CURVES)) {
+	for (const kdf of ["hkdf", "concatenation"]) {
+		vectors.push(await vector(curveName, kdf, new Uint8Array(), null, null));
+		vectors.push(
+			await vector(
+				curveName,
+				kdf,
+				crypto.getRandomValues(new Uint8Array(37)),
+				crypto.getRandomValues(new Uint8Array(16)),
+				new TextEncoder().encode("kits ecies vector"),
+			),
+		);
+	}
+}
+const kept = JSON.parse(readFileSync(PATH, "utf8")).filter(
+	(vector) => !(vector.curveName in CURVES),
+);
+writeFileSync(PATH, `${JSON.stringify([...vectors, ...kept], null, 2)}\n`);
@@ -0,0 +1,91 @@
+"""Generates the secp256k1 vectors of tests/vectors/ecies.json with
+pyca/cryptography (OpenSSL). The nist curve ones come from Web Crypto through
+ecies.mjs; Web Crypto has no secp256k1 and there is no BouncyCastle at hand,
+OpenSSL is the implementation independent of ours that covers it. The vectors
+of each script are replaced, the others kept.
+
+The envelope is: compressed ephemeral public key || AES-256-GCM(ciphertext || tag),
+the 44 bytes key material (32 bytes key || 12 bytes nonce) derives from the ECDH
+shared secret with SHA-256 based HKDF or Concat KDF.
+"""
+
+import base64
+import json
+import os
+
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+from cryptography.hazmat.primitives.kdf.concatkdf import ConcatKDFHash
+from cryptography.hazmat.primitives.kdf.hkdf import HKDF
+
+CURVES = {
+    "secp256k1": ec.SECP256K1(),
+}
+
+
+def derive(kdf, secret, salt, info):
+    if kdf == "hkdf":
+        return HKDF(hashes.SHA256(), 44, salt, info).derive(secret)
+    return ConcatKDFHash(hashes.SHA256(), 44, info).derive(secret)
+
+
+def b64(data):
+    return base64.b64encode(data).decode()
+
+
+def vector(curve_name, kdf, plaintext, salt, info):
+    curve = CURVES[curve_name]
+    receiver = ec.generate_private_key(curve)
+    ephemeral = ec.generate_private_key(curve)
+    secret = ephemeral.exchange(ec.ECDH(), receiver.public_key())
+    okm = derive(kdf, secret, salt, info)
+    ciphertext = AESGCM(okm[:32]).encrypt(okm[32:], plaintext, None)
+    envelope = (
+        ephemeral.public_key().public_bytes(
+            serialization.Encoding.X962,
+            serialization.PublicFormat.CompressedPoint,
+        )
+        + ciphertext
+    )
+    return {
+        "curveName": curve_name,
+        "kdf": kdf,
+        "privateKey": b64(
+            receiver.private_bytes(
+                serialization.Encoding.DER,
+                serialization.PrivateFormat.PKCS8,
+                serialization.NoEncryption(),
+            )
+        ),
+        "salt": b64(salt) if salt else None,
+        "info": b64(info) if info else None,
+        "plaintext": b64(plaintext),
+        "ciphertext": b64(envelope),
+    }
+
+
+def main():
+    vectors = []
+    for curve_name in CURVES:
+        for kdf in ["hkdf", "concatenation"]:
+            vectors.append(vector(curve_name, kdf, b"", None, None))
+            vectors.append(
+                vector(
+                    curve_name,
+                    kdf,
+                    os.urandom(37),
+                    os.urandom(16),
+                    b"kits ecies vector",
+                )
+            )
+    path = os.path.join(os.path.dirname(__file__), "ecies.json")
+    with open(path) as file:
+        kept = [v for v in json.load(file) if v["curveName"] not in CURVES]
+    with open(path, "w") as file:
+        json.dump(kept + vectors, file, indent=2)
+        file.write("\n")
+
+
+if __name__ == "__main__":
+    main()
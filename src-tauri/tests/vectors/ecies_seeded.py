@@ -0,0 +1,113 @@
+"""Computes the golden ciphertext of test_seeded_ecies_golden in
+src/crypto/ecc.rs with pyca/cryptography, the rng replayed in python.
+
+rng::seeded(seed) is rand's StdRng (ChaCha12, 64-bit block counter, zero
+stream) keyed by seed_from_u64, the PCG32 expansion of rand_core. Every
+rng::rng() call forks a child StdRng keyed by the next 32 bytes, ecies draws
+the ephemeral P-256 scalar from the child as 32 big-endian bytes, rejected
+while not below the group order.
+
+The envelope is the version 2 header (KES, 02, P-256, HKDF, SHA-256,
+AES-GCM) || compressed ephemeral public key || AES-256-GCM(ciphertext ||
+tag), the 44 bytes key material (32 bytes key || 12 bytes nonce) derived
+from the ECDH shared secret with HKDF-SHA256 without salt or info.
+"""
+
+import struct
+
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+from cryptography.hazmat.primitives.kdf.hkdf import HKDF
+
+SEED = 0x6B697473
+# RFC 6979 A.2.5
+RECIPIENT = 0xC9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721
+PLAINTEXT = b"plaintext"
+ORDER = 0xFFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551
+MASK = 0xFFFFFFFF
+
+
+def rotl(value, count):
+    return ((value << count) & MASK) | (value >> (32 - count))
+
+
+def quarter_round(state, a, b, c, d):
+    state[a] = (state[a] + state[b]) & MASK
+    state[d] = rotl(state[d] ^ state[a], 16)
+    state[c] = (state[c] + state[d]) & MASK
+    state[b] = rotl(state[b] ^ state[c], 12)
+    state[a] = (state[a] + state[b]) & MASK
+    state[d] = rotl(state[d] ^ state[a], 8)
+    state[c] = (state[c] + state[d]) & MASK
+    state[b] = rotl(state[b] ^ state[c], 7)
+
+
+def chacha12_block(key, counter):
+    initial = (
+        list(struct.unpack("<4I", b"expand 32-byte k"))
+        + list(struct.unpack("<8I", key))
+        + [counter & MASK, counter >> 32, 0, 0]
+    )
+    state = initial[:]
+    for _ in range(6):
+        quarter_round(state, 0, 4, 8, 12)
+        quarter_round(state, 1, 5, 9, 13)
+        quarter_round(state, 2, 6, 10, 14)
+        quarter_round(state, 3, 7, 11, 15)
+        quarter_round(state, 0, 5, 10, 15)
+        quarter_round(state, 1, 6, 11, 12)
+        quarter_round(state, 2, 7, 8, 13)
+        quarter_round(state, 3, 4, 9, 14)
+    return struct.pack(
+        "<16I", *[(word + start) & MASK for word, start in zip(state, initial)]
+    )
+
+
+class StdRng:
+    def __init__(self, key):
+        self.key = key
+        self.counter = 0
+        self.buffer = b""
+
+    @classmethod
+    def seed_from_u64(cls, state):
+        seed = b""
+        for _ in range(8):
+            state = (state * 6364136223846793005 + 11634580027462260723) % 2**64
+            xorshifted = (((state >> 18) ^ state) >> 27) & MASK
+            rot = state >> 59
+            seed += struct.pack("<I", ((xorshifted >> rot) | (xorshifted << (32 - rot))) & MASK)
+        return cls(seed)
+
+    def fill_bytes(self, size):
+        # whole words are consumed
+        words = (size + 3) // 4
+        while len(self.buffer) < words * 4:
+            self.buffer += chacha12_block(self.key, self.counter)
+            self.counter += 1
+        output, self.buffer = self.buffer[:size], self.buffer[words * 4 :]
+        return output
+
+
+def main():
+    child = StdRng(StdRng.seed_from_u64(SEED).fill_bytes(32))
+    while not 0 < (scalar := int.from_bytes(child.fill_bytes(32), "big")) < ORDER:
+        pass
+    ephemeral = ec.derive_private_key(scalar, ec.SECP256R1())
+    recipient = ec.derive_private_key(RECIPIENT, ec.SECP256R1())
+    material = HKDF(hashes.SHA256(), 44, None, b"").derive(
+        ephemeral.exchange(ec.ECDH(), recipient.public_key())
+    )
+    sealed = (
+        b"KES\x02\x01\x01\x02\x01"
+        + ephemeral.public_key().public_bytes(
+            serialization.Encoding.X962, serialization.PublicFormat.CompressedPoint
+        )
+        + AESGCM(material[:32]).encrypt(material[32:], PLAINTEXT, None)
+    )
+    print(sealed.hex())
+
+
+if __name__ == "__main__":
+    main()
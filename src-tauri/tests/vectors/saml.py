@@ -0,0 +1,109 @@
+"""Generates tests/vectors/saml.xml with pyca/cryptography (OpenSSL).
+
+A SAML response whose assertion carries an enveloped ECDSA P-256 signature
+with Exclusive C14N. The canonical forms are spelled out by hand, so the
+vector does not depend on any XML-DSig implementation.
+"""
+
+import base64
+import datetime
+import hashlib
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID
+
+SAML = "urn:oasis:names:tc:SAML:2.0:assertion"
+SAMLP = "urn:oasis:names:tc:SAML:2.0:protocol"
+DSIG = "http://www.w3.org/2000/09/xmldsig#"
+EXC_C14N = "http://www.w3.org/2001/10/xml-exc-c14n#"
+
+
+def signed_info(digest_value, empty):
+    """`empty` closes an element without content, `/>` in the source and
+    `></name>` once canonical."""
+    return (
+        f'<ds:CanonicalizationMethod Algorithm="{EXC_C14N}"'
+        + empty("ds:CanonicalizationMethod")
+        + '<ds:SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha256"'
+        + empty("ds:SignatureMethod")
+        + '<ds:Reference URI="#a1"><ds:Transforms>'
+        + f'<ds:Transform Algorithm="{DSIG}enveloped-signature"'
+        + empty("ds:Transform")
+        + f'<ds:Transform Algorithm="{EXC_C14N}"'
+        + empty("ds:Transform")
+        + "</ds:Transforms>"
+        + '<ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"'
+        + empty("ds:DigestMethod")
+        + f"<ds:DigestValue>{digest_value}</ds:DigestValue></ds:Reference>"
+    )
+
+
+def main():
+    key = ec.generate_private_key(ec.SECP256R1())
+    name = x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, "idp.example")])
+    certificate = (
+        x509.CertificateBuilder()
+        .subject_name(name)
+        .issuer_name(name)
+        .public_key(key.public_key())
+        .serial_number(2)
+        .not_valid_before(datetime.datetime(2024, 1, 1))
+        .not_valid_after(datetime.datetime(2034, 1, 1))
+        .sign(key, hashes.SHA256())
+    )
+    certificate = base64.b64encode(
+        certificate.public_bytes(serialization.Encoding.DER)
+    ).decode()
+
+    # the assertion without the signature and the comments, only the used
+    # saml prefix is declared and the attributes are sorted
+    assertion = (
+        f'<saml:Assertion xmlns:saml="{SAML}" ID="a1" Version="2.0">'
+        "<saml:Issuer>https://idp.example/?a=1&amp;b=2</saml:Issuer>"
+        '\n  <saml:Conditions NotBefore="2024-01-01T00:00:00Z"></saml:Conditions>'
+        "<saml:Subject><saml:NameID>alice</saml:NameID></saml:Subject>"
+        "</saml:Assertion>"
+    )
+    digest_value = base64.b64encode(
+        hashlib.sha256(assertion.encode()).digest()
+    ).decode()
+    canonical = (
+        f'<ds:SignedInfo xmlns:ds="{DSIG}">'
+        + signed_info(digest_value, lambda name: f"></{name}>")
+        + "</ds:SignedInfo>"
+    )
+    r, s = decode_dss_signature(
+        key.sign(canonical.encode(), ec.ECDSA(hashes.SHA256()))
+    )
+    signature_value = base64.b64encode(
+        r.to_bytes(32, "big") + s.to_bytes(32, "big")
+    ).decode()
+
+    document = (
+        '<?xml version="1.0" encoding="UTF-8"?>\n'
+        f'<samlp:Response xmlns:samlp="{SAMLP}" xmlns:saml="{SAML}" ID="r1" Version="2.0">'
+        "<!-- response -->"
+        '<saml:Assertion Version="2.0" ID="a1">'
+        "<saml:Issuer>https://idp.example/?a=1&amp;b=2</saml:Issuer>"
+        f'<ds:Signature xmlns:ds="{DSIG}">'
+        + "<ds:SignedInfo>"
+        + signed_info(digest_value, lambda _: "/>")
+        + "</ds:SignedInfo>"
+        + f"<ds:SignatureValue>{signature_value}</ds:SignatureValue>"
+        + "<ds:KeyInfo><ds:X509Data>"
+        + f"<ds:X509Certificate>{certificate}</ds:X509Certificate>"
+        + "</ds:X509Data></ds:KeyInfo></ds:Signature>"
+        '\n  <saml:Conditions NotBefore="2024-01-01T00:00:00Z"/>'
+        "<!-- unsigned -->"
+        "<saml:Subject><saml:NameID>alice</saml:NameID></saml:Subject>"
+        "</saml:Assertion></samlp:Response>\n"
+    )
+    with open("saml.xml", "w") as file:
+        file.write(document)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,198 @@
+"""Generates tests/vectors/sigstore.json with pyca/cryptography (OpenSSL).
+
+A sigstore bundle for the artifact `hello sigstore\n` signed the way
+`cosign sign-blob` does: a p-256 fulcio root issues a leaf valid for ten
+minutes, the hashedrekord entry sits at index 2 of a five entry log whose
+checkpoint and signed entry timestamp come from a p-256 rekor key.
+"""
+
+import base64
+import datetime
+import hashlib
+import json
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.x509.oid import NameOID, ObjectIdentifier
+
+ARTIFACT = b"hello sigstore\n"
+ORIGIN = "rekor.kits.example - 1"
+INTEGRATED_TIME = 1717200300  # 2024-06-01 00:05:00
+INDEX, SIZE = 2, 5
+
+
+def b64(data):
+    return base64.b64encode(data).decode()
+
+
+def pem(key):
+    return key.public_key().public_bytes(
+        serialization.Encoding.PEM,
+        serialization.PublicFormat.SubjectPublicKeyInfo,
+    )
+
+
+def name(common_name):
+    return x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, common_name)])
+
+
+def leaf_hash(entry):
+    return hashlib.sha256(b"\x00" + entry).digest()
+
+
+def node(left, right):
+    return hashlib.sha256(b"\x01" + left + right).digest()
+
+
+def root(leaves):
+    if len(leaves) == 1:
+        return leaves[0]
+    split = 1
+    while split * 2 < len(leaves):
+        split *= 2
+    return node(root(leaves[:split]), root(leaves[split:]))
+
+
+def path(index, leaves):
+    if len(leaves) == 1:
+        return []
+    split = 1
+    while split * 2 < len(leaves):
+        split *= 2
+    if index < split:
+        return path(index, leaves[:split]) + [root(leaves[split:])]
+    return path(index - split, leaves[split:]) + [root(leaves[:split])]
+
+
+def main():
+    fulcio_key = ec.generate_private_key(ec.SECP256R1())
+    rekor_key = ec.generate_private_key(ec.SECP256R1())
+    signing_key = ec.generate_private_key(ec.SECP256R1())
+
+    fulcio = (
+        x509.CertificateBuilder()
+        .subject_name(name("kits fulcio"))
+        .issuer_name(name("kits fulcio"))
+        .public_key(fulcio_key.public_key())
+        .serial_number(1)
+        .not_valid_before(datetime.datetime(2024, 1, 1))
+        .not_valid_after(datetime.datetime(2034, 1, 1))
+        .add_extension(x509.BasicConstraints(ca=True, path_length=None), True)
+        .sign(fulcio_key, hashes.SHA256())
+    )
+    issued = datetime.datetime(2024, 6, 1)
+    leaf = (
+        x509.CertificateBuilder()
+        .subject_name(x509.Name([]))
+        .issuer_name(name("kits fulcio"))
+        .public_key(signing_key.public_key())
+        .serial_number(2)
+        .not_valid_before(issued)
+        .not_valid_after(issued + datetime.timedelta(minutes=10))
+        .add_extension(
+            x509.SubjectAlternativeName([x509.RFC822Name("dev@kits.example")]),
+            True,
+        )
+        .add_extension(
+            x509.UnrecognizedExtension(
+                ObjectIdentifier("1.3.6.1.4.1.57264.1.8"),
+                b"\x0c\x1bhttps://accounts.kits.example",
+            ),
+            False,
+        )
+        .sign(fulcio_key, hashes.SHA256())
+    )
+    leaf_der = leaf.public_bytes(serialization.Encoding.DER)
+    leaf_pem = leaf.public_bytes(serialization.Encoding.PEM)
+
+    digest = hashlib.sha256(ARTIFACT).digest()
+    signature = signing_key.sign(ARTIFACT, ec.ECDSA(hashes.SHA256()))
+    body = json.dumps(
+        {
+            "apiVersion": "0.0.1",
+            "kind": "hashedrekord",
+            "spec": {
+                "data": {
+                    "hash": {"algorithm": "sha256", "value": digest.hex()}
+                },
+                "signature": {
+                    "content": b64(signature),
+                    "publicKey": {"content": b64(leaf_pem)},
+                },
+            },
+        },
+        separators=(",", ":"),
+    ).encode()
+
+    leaves = [leaf_hash(b"entry %d" % i) for i in range(SIZE)]
+    leaves[INDEX] = leaf_hash(body)
+    tree_root = root(leaves)
+    note = "%s\n%d\n%s\n" % (ORIGIN, SIZE, b64(tree_root))
+    key_id = hashlib.sha256(
+        rekor_key.public_key().public_bytes(
+            serialization.Encoding.DER,
+            serialization.PublicFormat.SubjectPublicKeyInfo,
+        )
+    ).digest()
+    note_signature = rekor_key.sign(note.encode(), ec.ECDSA(hashes.SHA256()))
+    checkpoint = "%s\n— %s %s\n" % (
+        note,
+        ORIGIN.split(" ")[0],
+        b64(key_id[:4] + note_signature),
+    )
+    promise = json.dumps(
+        {
+            "body": b64(body),
+            "integratedTime": INTEGRATED_TIME,
+            "logID": key_id.hex(),
+            "logIndex": 1000 + INDEX,
+        },
+        separators=(",", ":"),
+        sort_keys=True,
+    ).encode()
+
+    bundle = {
+        "mediaType": "application/vnd.dev.sigstore.bundle.v0.3+json",
+        "verificationMaterial": {
+            "certificate": {"rawBytes": b64(leaf_der)},
+            "tlogEntries": [
+                {
+                    "logIndex": str(1000 + INDEX),
+                    "logId": {"keyId": b64(key_id)},
+                    "kindVersion": {"kind": "hashedrekord", "version": "0.0.1"},
+                    "integratedTime": str(INTEGRATED_TIME),
+                    "inclusionPromise": {
+                        "signedEntryTimestamp": b64(
+                            rekor_key.sign(promise, ec.ECDSA(hashes.SHA256()))
+                        )
+                    },
+                    "inclusionProof": {
+                        "logIndex": str(INDEX),
+                        "rootHash": b64(tree_root),
+                        "treeSize": str(SIZE),
+                        "hashes": [b64(h) for h in path(INDEX, leaves)],
+                        "checkpoint": {"envelope": checkpoint},
+                    },
+                    "canonicalizedBody": b64(body),
+                }
+            ],
+        },
+        "messageSignature": {
+            "messageDigest": {"algorithm": "SHA2_256", "digest": b64(digest)},
+            "signature": b64(signature),
+        },
+    }
+    vectors = {
+        "artifact": ARTIFACT.decode(),
+        "bundle": json.dumps(bundle, indent=2),
+        "roots": fulcio.public_bytes(serialization.Encoding.PEM).decode(),
+        "rekorKey": pem(rekor_key).decode(),
+    }
+    with open("sigstore.json", "w") as output:
+        json.dump(vectors, output, indent=2)
+        output.write("\n")
+
+
+if __name__ == "__main__":
+    main()
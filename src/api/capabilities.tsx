@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api";
+
+type JwkCapability = {
+	keyType: string;
+	defaultAlgorithm: string;
+	algorithms: string[];
+	usages: string[];
+};
+
+type Capabilities = {
+	aes: {
+		mode: string;
+		keySizes: number[];
+		paddings: string[];
+		ivSize: number | null;
+		aad: boolean;
+	}[];
+	rsa: {
+		keySizes: string[];
+		pkcs: string[];
+		encryptionPaddings: string[];
+		oaepDigests: string[];
+	};
+	ellipticCurves: string[];
+	edwardsCurves: string[];
+	ecies: {
+		pkcs: string[];
+		encryptionAlgorithms: string[];
+	};
+	digests: string[];
+	kdfs: { kdf: string; saltRequired: boolean; infoSupported: boolean }[];
+	jwk: JwkCapability[];
+	jwkeyOperations: string[];
+	kidStrategies: string[];
+};
+
+let cached: Promise<Capabilities> | undefined;
+
+const fetchCapabilities = (): Promise<Capabilities> => {
+	if (!cached) {
+		cached = invoke<Capabilities>("capabilities").catch((err) => {
+			cached = undefined;
+			throw err;
+		});
+	}
+	return cached;
+};
+
+const fetchJwkCapability = async (
+	kty: string,
+): Promise<JwkCapability | undefined> => {
+	const { jwk } = await fetchCapabilities();
+	return jwk.find((capability) => capability.keyType === kty);
+};
+
+export { fetchCapabilities, fetchJwkCapability };
+export type { Capabilities, JwkCapability };
@@ -1,30 +1,35 @@
 import { invoke } from "@tauri-apps/api";
+import { fetchCapabilities, fetchJwkCapability } from "./capabilities";
 
 const fetchKdfs = async (): Promise<string[]> => {
-	return await invoke<string[]>("kdfs");
+	return (await fetchCapabilities()).kdfs.map(({ kdf }) => kdf);
 };
 
 const fetchDigests = async (): Promise<string[]> => {
-	return await invoke<string[]>("digests");
+	return (await fetchCapabilities()).digests;
 };
 
 const fetchEciesEncAlgs = async (): Promise<string[]> => {
-	return await invoke<string[]>("ecies_enc_alg");
+	return (await fetchCapabilities()).ecies.encryptionAlgorithms;
 };
 
 const fetchJwkeyTypes = async (): Promise<string[]> => {
-	return await invoke<string[]>("jwkey_type");
+	return (await fetchCapabilities()).jwk.map(({ keyType }) => keyType);
 };
 
 const fetchJwkeyAlgs = async (kty: string): Promise<string[]> => {
-	return await invoke<string[]>("jwkey_algorithm", { kty });
+	return (await fetchJwkCapability(kty))?.algorithms ?? [];
 };
 
 const fetchJwkeyUsages = async (kty: string): Promise<string[]> => {
-	return await invoke<string[]>("jwkey_usage", { kty });
+	return (await fetchJwkCapability(kty))?.usages ?? [];
 };
 const fetchJwkeyOps = async (): Promise<string[]> => {
-	return await invoke<string[]>("jwkey_operation");
+	return (await fetchCapabilities()).jwkeyOperations;
+};
+
+const fetchKidStrategies = async (): Promise<string[]> => {
+	return (await fetchCapabilities()).kidStrategies;
 };
 
 const randomId = async (): Promise<string> => {
@@ -39,5 +44,6 @@ export {
 	fetchJwkeyTypes,
 	fetchJwkeyUsages,
 	fetchKdfs,
+	fetchKidStrategies,
 	randomId,
 };
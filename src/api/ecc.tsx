@@ -1,7 +1,7 @@
-import { invoke } from "@tauri-apps/api";
+import { fetchCapabilities } from "./capabilities";
 
 const fetchCurveNames = async (): Promise<string[]> => {
-	return await invoke<string[]>("elliptic_curve");
+	return (await fetchCapabilities()).ellipticCurves;
 };
 
 export { fetchCurveNames };
@@ -1,7 +1,7 @@
-import { invoke } from "@tauri-apps/api";
+import { fetchCapabilities } from "./capabilities";
 
 const fetchEdwardsCuveNames = async (): Promise<string[]> => {
-	return await invoke<string[]>("edwards");
+	return (await fetchCapabilities()).edwardsCurves;
 };
 
 export { fetchEdwardsCuveNames };
@@ -0,0 +1,9 @@
+type OperationResult<T> = {
+	output: T;
+	altEncodings?: Record<string, string>;
+	metadata: Record<string, string>;
+	warnings: string[];
+	timing: number;
+};
+
+export type { OperationResult };
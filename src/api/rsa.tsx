@@ -1,11 +1,11 @@
-import { invoke } from "@tauri-apps/api";
+import { fetchCapabilities } from "./capabilities";
 
 const fetchRsaEncryptionPadding = async (): Promise<string[]> => {
-	return await invoke<string[]>("rsa_encryption_padding");
+	return (await fetchCapabilities()).rsa.encryptionPaddings;
 };
 
 const fetchRsaKeySize = async (): Promise<string[]> => {
-	return await invoke<string[]>("rsa_key_size");
+	return (await fetchCapabilities()).rsa.keySizes;
 };
 
 export { fetchRsaEncryptionPadding, fetchRsaKeySize };
@@ -5,6 +5,7 @@ import {
 	Form,
 	FormRule,
 	Input,
+	InputNumber,
 	Select,
 	Space,
 	notification,
@@ -23,7 +24,8 @@ const { TextArea } = Input;
 const size = "middle";
 
 const ivComputer = (mode: EncryptionMode, encoding: TextEncoding): number => {
-	let length = mode === EncryptionMode.CBC ? 16 : 12;
+	let length =
+		mode === EncryptionMode.GCM || mode === EncryptionMode.CCM ? 12 : 16;
 	switch (encoding) {
 		case TextEncoding.Base64:
 			length = Math.floor((length + 2) / 3) * 4;
@@ -117,7 +119,9 @@ const AesInput = () => {
 					</Form.Item>
 				);
 
+			case EncryptionMode.CBC_HMAC:
 			case EncryptionMode.GCM:
+			case EncryptionMode.CCM:
 				return (
 					<>
 						<Form.Item
@@ -136,6 +140,35 @@ const AesInput = () => {
 						</Form.Item>
 					</>
 				);
+			case EncryptionMode.SIV:
+				return (
+					<Form.Item
+						key="siv_aad"
+						name="aad"
+						label={<FormLabel children="Aad" />}
+					>
+						<Input placeholder="input aad" />
+					</Form.Item>
+				);
+			case EncryptionMode.XTS:
+				return (
+					<>
+						<Form.Item key="xts_iv" label={<FormLabel children="Tweak" />}>
+							<IvInput />
+						</Form.Item>
+						<Form.Item
+							key="xts_sector"
+							name="sector"
+							label={<FormLabel children="Sector" />}
+						>
+							<InputNumber
+								min={0}
+								placeholder="sector number in place of the tweak"
+								style={{ width: "100%" }}
+							/>
+						</Form.Item>
+					</>
+				);
 			default:
 				return <></>;
 		}
@@ -161,6 +194,11 @@ const AesInput = () => {
 						options={[
 							{ value: 128, label: <span>128bit</span> },
 							{ value: 256, label: <span>256bit</span> },
+							...(mode === EncryptionMode.CBC_HMAC ||
+							mode === EncryptionMode.SIV ||
+							mode === EncryptionMode.XTS
+								? [{ value: 512, label: <span>512bit</span> }]
+								: []),
 						]}
 					/>
 					<Button style={{ margin: 0 }} onClick={generateKey}>
@@ -222,7 +260,10 @@ const IvInput = () => {
 	const generateIv = async () => {
 		try {
 			const iv = await invoke<string>("generate_iv", {
-				size: mode === EncryptionMode.CBC ? 16 : 12,
+				size:
+					mode === EncryptionMode.GCM || mode === EncryptionMode.CCM
+						? 12
+						: 16,
 				encoding: form.getFieldValue("ivEncoding"),
 			});
 
@@ -12,6 +12,7 @@ import {
 } from "antd";
 import { createStyles } from "antd-style";
 import { error } from "tauri-plugin-log-api";
+import { OperationResult } from "../../api/result";
 import { AesEncryptionForm } from "../../pages/encryption/aes";
 import Collapse from "../Collapse";
 import { textEncodings } from "../codec/codec";
@@ -30,7 +31,11 @@ const useStyles = createStyles(({ css }) => ({
 export enum EncryptionMode {
 	ECB = "ECB",
 	CBC = "CBC",
+	CBC_HMAC = "CBC-HMAC",
 	GCM = "GCM",
+	CCM = "CCM",
+	SIV = "SIV",
+	XTS = "XTS",
 }
 
 export enum AesPadding {
@@ -101,7 +106,7 @@ export const AesSetting = () => {
 const AesSettingInner = () => {
 	const form = Form.useFormInstance<AesEncryptionForm>();
 	const [msgApi, msgContext] = message.useMessage({
-		maxCount: 1,
+		maxCount: 3,
 		duration: 4,
 	});
 	const forEncryption = Form.useWatch("forEncryption", {
@@ -122,9 +127,12 @@ const AesSettingInner = () => {
 			await form.validateFields({
 				validateOnly: true,
 			});
-			const output = await invoke<string>("crypto_aes", {
-				data: form.getFieldsValue(true),
-			});
+			const { output, warnings } = await invoke<OperationResult<string>>(
+				"crypto_aes",
+				{
+					data: form.getFieldsValue(true),
+				}
+			);
 
 			form.setFieldsValue({ output });
 			if (forEncryption) {
@@ -135,6 +143,7 @@ const AesSettingInner = () => {
 					msgApi.success("output copied");
 				}
 			}
+			warnings.forEach((warning) => msgApi.warning(warning));
 		} catch (err: unknown) {
 			form.setFieldsValue({ output: "" });
 			error(err as string);
@@ -149,7 +158,12 @@ const AesSettingInner = () => {
 			</Form.Item>
 			<Form.Item name="padding" label="padding">
 				<Select
-					disabled={mode === EncryptionMode.GCM}
+					disabled={
+						mode === EncryptionMode.GCM ||
+						mode === EncryptionMode.CCM ||
+						mode === EncryptionMode.SIV ||
+						mode === EncryptionMode.XTS
+					}
 					size={size}
 					options={paddings}
 				/>
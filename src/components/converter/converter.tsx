@@ -13,6 +13,29 @@ export abstract class Converter<T> {
 	): Promise<string[]>;
 }
 
+export interface KeyTuple {
+	privateKey: string | null;
+	publicKey: string | null;
+	fingerprint: string | null;
+	algorithm: string | null;
+	parameters: Record<string, unknown>;
+	createdAt: number;
+	provenance: Provenance | null;
+}
+
+export interface Provenance {
+	sourceFingerprint: string;
+	chain: {
+		operation: string;
+		from: unknown;
+		to: unknown;
+		at: number;
+	}[];
+}
+
+const keyPair = ({ privateKey, publicKey }: KeyTuple) =>
+	[privateKey, publicKey] as string[];
+
 export type ConvertRef = {
 	getTextEncoding: () => TextEncoding;
 	setTextEncoding: (encoding: TextEncoding) => void;
@@ -146,12 +169,14 @@ export class RsaPkcsConverter extends Converter<PkcsEncodingProps> {
 			case from.pkcs === Pkcs.Pkcs1 && to.pkcs === Pkcs.Pkcs8:
 			case from.pkcs === Pkcs.Pkcs8 && to.pkcs === Pkcs.Pkcs8:
 			case from.pkcs === Pkcs.Pkcs1 && to.pkcs === Pkcs.Pkcs1:
-				return await invoke<string[]>("transfer_rsa_key", {
-					privateKey,
-					publicKey,
-					from,
-					to,
-				});
+				return keyPair(
+					await invoke<KeyTuple>("transfer_rsa_key", {
+						privateKey,
+						publicKey,
+						from,
+						to,
+					})
+				);
 			default:
 				throw new Error(
 					`unsupported pkcs: ${from.pkcs} encoding: ${from.encoding} convert pkcs: ${to.pkcs} encoding: ${to.encoding}`
@@ -170,13 +195,15 @@ export class EccPkcsConverter extends Converter<PkcsEncodingProps> {
 	): Promise<string[]> {
 		const curveName =
 			(params?.["curveName"] as EccCurveName) || EccCurveName.Secp256k1;
-		return await invoke<string[]>("transfer_ecc_key", {
-			curveName,
-			privateKey,
-			publicKey,
-			from,
-			to,
-		});
+		return keyPair(
+			await invoke<KeyTuple>("transfer_ecc_key", {
+				curveName,
+				privateKey,
+				publicKey,
+				from,
+				to,
+			})
+		);
 	}
 }
 export class EdwardsPkcsConverter extends Converter<PkcsEncodingProps> {
@@ -190,13 +217,15 @@ export class EdwardsPkcsConverter extends Converter<PkcsEncodingProps> {
 		const curveName =
 			(params?.["curveName"] as EdwardsCurveName) ||
 			EdwardsCurveName.Curve25519;
-		return await invoke<string[]>("transfer_edwards_key", {
-			curveName,
-			privateKey,
-			publicKey,
-			from,
-			to,
-		});
+		return keyPair(
+			await invoke<KeyTuple>("transfer_edwards_key", {
+				curveName,
+				privateKey,
+				publicKey,
+				from,
+				to,
+			})
+		);
 	}
 }
 
@@ -207,12 +236,14 @@ export class RsaEncodingConverter extends Converter<PkcsEncodingProps> {
 		from: PkcsEncodingProps,
 		to: PkcsEncodingProps
 	): Promise<string[]> {
-		return await invoke<string[]>("transfer_rsa_key", {
-			privateKey,
-			publicKey,
-			from,
-			to,
-		});
+		return keyPair(
+			await invoke<KeyTuple>("transfer_rsa_key", {
+				privateKey,
+				publicKey,
+				from,
+				to,
+			})
+		);
 	}
 }
 
@@ -231,13 +262,15 @@ export class EccEncodingConverter extends Converter<PkcsEncodingProps> {
 				params: JSON.stringify(params),
 			},
 		});
-		return await invoke<string[]>("transfer_ecc_key", {
-			curveName,
-			privateKey,
-			publicKey,
-			from,
-			to,
-		});
+		return keyPair(
+			await invoke<KeyTuple>("transfer_ecc_key", {
+				curveName,
+				privateKey,
+				publicKey,
+				from,
+				to,
+			})
+		);
 	}
 }
 
@@ -257,13 +290,15 @@ export class EdwardsEncodingConverter extends Converter<PkcsEncodingProps> {
 				params: JSON.stringify(params),
 			},
 		});
-		return await invoke<string[]>("transfer_edwards_key", {
-			curveName,
-			privateKey,
-			publicKey,
-			from,
-			to,
-		});
+		return keyPair(
+			await invoke<KeyTuple>("transfer_edwards_key", {
+				curveName,
+				privateKey,
+				publicKey,
+				from,
+				to,
+			})
+		);
 	}
 }
 
@@ -3,7 +3,7 @@ import { FormInstance, SelectProps } from "antd";
 import { error } from "tauri-plugin-log-api";
 import { fetchCurveNames } from "../../api/ecc";
 import { TextEncoding } from "../codec/codec";
-import { PkcsFormat, PkcsFormats } from "../converter/converter";
+import { KeyTuple, PkcsFormat, PkcsFormats } from "../converter/converter";
 
 export type EccKeyDeriveForm = {
 	privateKey: string;
@@ -37,7 +37,7 @@ export const deriveEccKey = async (
 		);
 		const pkcs = PkcsFormats[pkcsFormat as PkcsFormat];
 		pkcs.setEncoding(encoding as TextEncoding);
-		const publicKey = await invoke<string>("derive_ecc", {
+		const { publicKey } = await invoke<KeyTuple>("derive_ecc", {
 			curveName,
 			input: privateKey,
 			...pkcs,
@@ -62,7 +62,7 @@ export const generateEccKey = async (form: FormInstance) => {
 		} = form.getFieldValue("elliptic_curve");
 		const pkcs = PkcsFormats[pkcsFormat];
 		pkcs.setEncoding(encoding);
-		const [privateKey, publicKey] = await invoke<string[]>("generate_ecc", {
+		const { privateKey, publicKey } = await invoke<KeyTuple>("generate_ecc", {
 			curveName,
 			...pkcs,
 		});
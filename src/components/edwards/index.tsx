@@ -3,7 +3,7 @@ import { FormInstance, SelectProps } from "antd";
 import { error } from "tauri-plugin-log-api";
 import { fetchEdwardsCuveNames } from "../../api/edwards";
 import { TextEncoding } from "../codec/codec";
-import { PkcsFormat, PkcsFormats } from "../converter/converter";
+import { KeyTuple, PkcsFormat, PkcsFormats } from "../converter/converter";
 
 export type EdwardsDeriveKeyForm = {
 	privateKey: string;
@@ -37,7 +37,7 @@ export const deriveEdwardsKey = async (
 			form.getFieldValue("edwards");
 		const pkcs = PkcsFormats[pkcsFormat as PkcsFormat];
 		pkcs.setEncoding(encoding as TextEncoding);
-		const publicKey = await invoke<string>("derive_edwards", {
+		const { publicKey } = await invoke<KeyTuple>("derive_edwards", {
 			curveName,
 			input: privateKey,
 			...pkcs,
@@ -62,7 +62,7 @@ export const generateEdwardsKey = async (form: FormInstance) => {
 
 		const pkcs = PkcsFormats[pkcsFormat];
 		pkcs.setEncoding(encoding);
-		const [privateKey, publicKey] = await invoke<string[]>("generate_edwards", {
+		const { privateKey, publicKey } = await invoke<KeyTuple>("generate_edwards", {
 			curveName,
 			...pkcs,
 		});
@@ -2,7 +2,7 @@ import { invoke } from "@tauri-apps/api";
 import { FormInstance } from "antd";
 import { error } from "tauri-plugin-log-api";
 import { TextEncoding } from "../codec/codec";
-import { PkcsFormat, PkcsFormats } from "../converter/converter";
+import { KeyTuple, PkcsFormat, PkcsFormats } from "../converter/converter";
 
 export type RsaKeyDeriveForm = {
 	privateKey: string;
@@ -25,7 +25,7 @@ export const deriveRsaKey = async (
 		]);
 		const pkcs = PkcsFormats[pkcsFormat as PkcsFormat];
 		pkcs.setEncoding(encoding as TextEncoding);
-		const publicKey = await invoke<string>("derive_rsa", {
+		const { publicKey } = await invoke<KeyTuple>("derive_rsa", {
 			key: privateKey,
 			...pkcs,
 		});
@@ -48,7 +48,7 @@ export const generateRsaKey = async (form: FormInstance) => {
 		} = form.getFieldValue("rsa");
 		const pkcs = PkcsFormats[pkcsFormat];
 		pkcs.setEncoding(encoding);
-		const [privateKey, publicKey] = await invoke<string[]>("generate_rsa", {
+		const { privateKey, publicKey } = await invoke<KeyTuple>("generate_rsa", {
 			keySize,
 			...pkcs,
 		});
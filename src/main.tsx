@@ -1,3 +1,4 @@
+import { invoke } from "@tauri-apps/api";
 import { ConfigProvider } from "antd";
 import { StrictMode } from "react";
 import { createRoot } from "react-dom/client";
@@ -5,6 +6,9 @@ import { BrowserRouter } from "react-router-dom";
 import { attachConsole } from "tauri-plugin-log-api";
 import App from "./App";
 attachConsole();
+invoke("set_locale", {
+	locale: navigator.language.startsWith("zh") ? "zh" : "en",
+});
 createRoot(document.getElementById("root")!).render(
 	<StrictMode>
 		<BrowserRouter>
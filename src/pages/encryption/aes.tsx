@@ -14,6 +14,7 @@ export type AesEncryptionForm = {
 	padding: AesPadding;
 	mode: EncryptionMode;
 	aad?: string;
+	sector?: number;
 	input: string;
 	output: string;
 	keyEncoding: TextEncoding;
@@ -47,7 +48,12 @@ const AesEncryption = () => {
 		if (Object.keys(value).indexOf("mode") !== -1) {
 			const updated: Record<string, unknown> = { iv: undefined };
 			const mode: string = (value as { mode: string })["mode"];
-			if (mode === EncryptionMode.GCM) {
+			if (
+				mode === EncryptionMode.GCM ||
+				mode === EncryptionMode.CCM ||
+				mode === EncryptionMode.SIV ||
+				mode === EncryptionMode.XTS
+			) {
 				updated["padding"] = AesPadding.NoPadding;
 			}
 			form.setFieldsValue(updated);
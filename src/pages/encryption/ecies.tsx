@@ -16,6 +16,7 @@ import { FormInstance, useWatch } from "antd/es/form/Form";
 import useMessage from "antd/es/message/useMessage";
 import { useEffect, useState } from "react";
 import { error } from "tauri-plugin-log-api";
+import { OperationResult } from "../../api/result";
 import Collapse from "../../components/Collapse";
 import { FormLabel } from "../../components/FormLabel";
 import { TextEncoding } from "../../components/codec/codec";
@@ -87,7 +88,7 @@ const EciesInner = ({ form }: { form: FormInstance }) => {
 				!forEncryption ? "privateKey" : "publicKey"
 			);
 			const keyInfo = await parseEccKey(key);
-			const output = await invoke<string>("ecies", {
+			const { output } = await invoke<OperationResult<string>>("ecies", {
 				data: {
 					...form.getFieldsValue(true),
 					...keyInfo,
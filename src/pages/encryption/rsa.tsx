@@ -6,6 +6,7 @@ import { writeText } from "@tauri-apps/api/clipboard";
 import useMessage from "antd/es/message/useMessage";
 import { useEffect, useState } from "react";
 import { error } from "tauri-plugin-log-api";
+import { OperationResult } from "../../api/result";
 import Collapse from "../../components/Collapse";
 import { FormLabel } from "../../components/FormLabel";
 import { TextEncoding } from "../../components/codec/codec";
@@ -79,7 +80,7 @@ const RsaEncryptionInner = ({ form }: { form: FormInstance }) => {
 
 	const [msg, context] = useMessage({
 		duration: 4,
-		maxCount: 1,
+		maxCount: 3,
 	});
 
 	const forEncryption = Form.useWatch("forEncryption", {
@@ -131,20 +132,24 @@ const RsaEncryptionInner = ({ form }: { form: FormInstance }) => {
 			);
 
 			const keyInfo = await parseRsaKey(key);
-			const output = await invoke<string>("crypto_rsa", {
-				data: {
-					...form.getFieldsValue(true),
-					...keyInfo,
-					key: key,
-					keyEncoding: keyInfo.encoding,
-					keyFormat: keyInfo.format,
-				},
-			});
+			const { output, warnings } = await invoke<OperationResult<string>>(
+				"crypto_rsa",
+				{
+					data: {
+						...form.getFieldsValue(true),
+						...keyInfo,
+						key: key,
+						keyEncoding: keyInfo.encoding,
+						keyFormat: keyInfo.format,
+					},
+				}
+			);
 			form.setFieldsValue({ output });
 			if (output.length > 0 && output.length < 4096) {
 				await writeText(output);
 				msg.success("copied output");
 			}
+			warnings.forEach((warning) => msg.warning(warning));
 		} catch (err) {
 			error(err as string);
 		}
@@ -6,6 +6,7 @@ import {
 	Col,
 	Form,
 	Input,
+	InputNumber,
 	List,
 	Radio,
 	Row,
@@ -24,7 +25,7 @@ import {
 	fetchJwkeyOps,
 	fetchJwkeyTypes,
 	fetchJwkeyUsages,
-	randomId,
+	fetchKidStrategies,
 } from "../../api/constants";
 import { fetchRsaKeySize } from "../../api/rsa";
 
@@ -32,6 +33,9 @@ type JwkeyForm = {
 	keyType: string;
 	algorithm: string | null;
 	keyId: string | null;
+	kidStrategy: string | null;
+	kidPrefix: string | null;
+	keyBytes: number | null;
 	usage: string | null;
 	params: Record<string, unknown> | null;
 	operations: string[] | null;
@@ -44,9 +48,6 @@ const JwkeyType = ({ onChange }: { onChange?: (value: string) => void }) => {
 	const [jwkeyTypes, setJwkeyTypes] = useState<string[]>();
 
 	useEffect(() => {
-		randomId().then((id) => {
-			form.setFieldValue("keyId", id);
-		});
 		fetchJwkeyTypes().then((types) => {
 			setJwkeyTypes(types);
 		});
@@ -92,8 +93,24 @@ const JwkeySetting = () => {
 	const [algs, setAlgs] = useState<SelectProps["options"]>();
 	const [usages, setUsages] = useState<SelectProps["options"]>();
 	const [bits, setBits] = useState<string[]>();
+	const [kidStrategies, setKidStrategies] =
+		useState<SelectProps["options"]>();
 
 	const kty = useWatch("keyType", form) ?? "rsa";
+	const kidStrategy = useWatch("kidStrategy", form);
+
+	useEffect(() => {
+		fetchKidStrategies().then((strategies) => {
+			setKidStrategies(
+				strategies.map((strategy) => {
+					return {
+						value: strategy,
+						label: strategy,
+					};
+				})
+			);
+		});
+	}, []);
 
 	useEffect(() => {
 		fetchRsaKeySize().then((bits) => {
@@ -153,6 +170,13 @@ const JwkeySetting = () => {
 						</Radio.Group>
 					),
 				};
+			case "symmetric":
+				return {
+					label: "Key Bytes:",
+					name: "keyBytes",
+					tips: "the length the algorithm takes unless given",
+					component: <InputNumber min={1} style={{ width: "100%" }} />,
+				};
 			default:
 				return {
 					label: "",
@@ -170,6 +194,24 @@ const JwkeySetting = () => {
 			tips: null,
 			component: <Input />,
 		},
+		{
+			label: "Key ID Strategy:",
+			name: "kidStrategy",
+			tips: "used when no key id is given, thumbprint by default",
+			component: (
+				<Select options={kidStrategies} placeholder="thumbprint" allowClear />
+			),
+		},
+		...(kidStrategy === "prefix"
+			? [
+					{
+						label: "Key ID Prefix:",
+						name: "kidPrefix",
+						tips: null,
+						component: <Input />,
+					},
+				]
+			: []),
 		{
 			label: "Key Algorithm:",
 			name: "algorithm",